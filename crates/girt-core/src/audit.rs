@@ -0,0 +1,127 @@
+//! JSONL audit trail of gate decisions.
+//!
+//! Every terminal decision reached by a layer other than the cache is
+//! appended as one [`AuditRecord`]. Besides being a record of what was
+//! allowed and why, the trail lets a restarted proxy warm its decision
+//! caches (see [`DecisionEngine::warm_from_audit`](crate::engine::DecisionEngine::warm_from_audit))
+//! instead of re-asking the LLM about calls it approved minutes ago.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::decision::{Decision, DecisionLayer, GateKind};
+use crate::spec::RequestSource;
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp_ms: u64,
+    pub gate: GateKind,
+    /// Requested spec name or invoked tool name.
+    pub subject: String,
+    /// [`GateInput::hash`](crate::spec::GateInput::hash) of the evaluated input.
+    pub input_hash: String,
+    /// Who requested the spec, for Creation Gate decisions. Absent in
+    /// Execution records and in lines written before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<RequestSource>,
+    pub layer: DecisionLayer,
+    #[serde(deserialize_with = "current_or_legacy")]
+    pub decision: Decision,
+    /// Whether an Execution Allow may be reused for an identical call (see
+    /// [`GateInput::allow_is_reusable`](crate::spec::GateInput::allow_is_reusable)).
+    /// Absent, and so false, in lines written before it was recorded.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reusable: bool,
+}
+
+/// Read a decision in the current `status`-tagged form, or a terminal one
+/// in the externally tagged form older logs used (`"Allow"`,
+/// `{"Deny":{"reason":..}}`). Older non-terminal decisions are never
+/// replayed, so they are left unreadable.
+fn current_or_legacy<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decision, D::Error> {
+    #[derive(Deserialize)]
+    enum Legacy {
+        Allow,
+        Deny { reason: String },
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Current(Decision),
+        Legacy(Legacy),
+    }
+
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Current(decision) => decision,
+        Stored::Legacy(Legacy::Allow) => Decision::Allow,
+        Stored::Legacy(Legacy::Deny { reason }) => Decision::Deny { reason },
+    })
+}
+
+/// Append-only JSONL log of gate decisions.
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<std::fs::File>,
+}
+
+impl AuditLog {
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write one record. Failures are logged, never surfaced to the caller.
+    pub fn append(&self, record: &AuditRecord) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to serialize audit record");
+                return;
+            }
+        };
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.write_all(&line) {
+            tracing::warn!(path = %self.path.display(), error = %e, "Failed to write audit log");
+        }
+    }
+}
+
+/// What [`DecisionEngine::warm_from_audit`](crate::engine::DecisionEngine::warm_from_audit)
+/// did with each line of the audit log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AuditWarmup {
+    /// Decisions stored into a gate cache.
+    pub loaded: usize,
+    /// Well-formed records that were too old, not terminal, superseded by a
+    /// later record, or an Execution Allow that may not be reused.
+    pub skipped: usize,
+    /// Lines that did not parse as an [`AuditRecord`].
+    pub malformed: usize,
+}
+
+pub(crate) fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
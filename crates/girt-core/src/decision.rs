@@ -1,13 +1,39 @@
+//! Gate decisions and how they were reached.
+//!
+//! These types are the wire format of the gates: the proxy returns a
+//! serialized [`Decision`] (plus its trace) to the agent, and the audit log
+//! stores one per line. The shapes are pinned by the JSON Schemas in
+//! `crates/girt-core/schemas/` and the golden samples checked by
+//! `tests/wire_format.rs`. A [`Decision`] is an object tagged by `status`:
+//!
+//! ```json
+//! { "status": "denied", "reason": "matches deny pattern" }
+//! { "status": "deferred", "target": { "kind": "use_cli", "binary": "jq", ... } }
+//! ```
+
 use serde::{Deserialize, Serialize};
 
+use crate::spec::CapabilitySpec;
+
 /// Tri-state decision outcome from a gate evaluation.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
 pub enum Decision {
     /// Request is approved to proceed.
+    #[serde(rename = "allowed")]
     Allow,
+    /// Request is approved once narrowed: build `spec` in its place
+    /// (Creation Gate only). `changes` says what was changed, one line each.
+    #[serde(rename = "allowed_modified")]
+    AllowModified {
+        spec: CapabilitySpec,
+        changes: Vec<String>,
+    },
     /// Request is denied with a reason.
+    #[serde(rename = "denied")]
     Deny { reason: String },
     /// Request should be redirected to an existing capability (Creation Gate only).
+    #[serde(rename = "deferred")]
     Defer { target: DeferTarget },
     /// Decision requires human input.
     Ask { prompt: String, context: String },
@@ -15,12 +41,27 @@ pub enum Decision {
 
 impl Decision {
     pub fn is_terminal(&self) -> bool {
-        matches!(self, Decision::Allow | Decision::Deny { .. })
+        matches!(
+            self,
+            Decision::Allow | Decision::AllowModified { .. } | Decision::Deny { .. }
+        )
+    }
+
+    /// The decision's `status` tag, as used in spans and the request log.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Decision::Allow => "allowed",
+            Decision::AllowModified { .. } => "allowed_modified",
+            Decision::Deny { .. } => "denied",
+            Decision::Defer { .. } => "deferred",
+            Decision::Ask { .. } => "ask",
+        }
     }
 }
 
-/// What a DEFER decision redirects to.
+/// What a DEFER decision redirects to, tagged by `kind`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum DeferTarget {
     /// An existing tool in a registry.
     RegistryTool {
@@ -28,8 +69,13 @@ pub enum DeferTarget {
         tool_name: String,
         version: String,
     },
-    /// A native CLI utility.
-    CliUtility { name: String, description: String },
+    /// A CLI utility installed on the host.
+    UseCli {
+        binary: String,
+        description: String,
+        /// A typical command line for the agent to adapt.
+        example_invocation: String,
+    },
     /// An existing tool that should be extended.
     ExtendTool {
         tool_name: String,
@@ -37,15 +83,27 @@ pub enum DeferTarget {
     },
 }
 
-/// Which layer of the cascade produced the decision.
+/// Which layer of the cascade produced the decision. Serialized by its
+/// [`Display`](std::fmt::Display) name; the aliases read audit logs written
+/// before that.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DecisionLayer {
+    #[serde(alias = "PolicyRules")]
     PolicyRules,
+    ConstraintBudget,
+    ForceAsk,
+    #[serde(alias = "Cache")]
     Cache,
+    #[serde(alias = "RegistryLookup")]
     RegistryLookup,
+    #[serde(alias = "CliCheck")]
     CliCheck,
+    #[serde(alias = "Similarity")]
     Similarity,
+    #[serde(alias = "LlmEvaluation")]
     LlmEvaluation,
+    #[serde(alias = "Hitl")]
     Hitl,
 }
 
@@ -53,6 +111,8 @@ impl std::fmt::Display for DecisionLayer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DecisionLayer::PolicyRules => write!(f, "policy_rules"),
+            DecisionLayer::ConstraintBudget => write!(f, "constraint_budget"),
+            DecisionLayer::ForceAsk => write!(f, "force_ask"),
             DecisionLayer::Cache => write!(f, "cache"),
             DecisionLayer::RegistryLookup => write!(f, "registry_lookup"),
             DecisionLayer::CliCheck => write!(f, "cli_check"),
@@ -63,16 +123,45 @@ impl std::fmt::Display for DecisionLayer {
     }
 }
 
-/// A decision paired with the layer that produced it.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// A decision paired with the layer that produced it. Serialized as the
+/// decision's own fields alongside `layer`, `rationale`, and
+/// `consulted_layers`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LayeredDecision {
+    #[serde(flatten)]
     pub decision: Decision,
     pub layer: DecisionLayer,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rationale: Option<String>,
+    /// Every layer evaluated for this decision, in cascade order. The
+    /// deciding layer (if any) is last.
+    #[serde(default)]
+    pub consulted_layers: Vec<LayerTrace>,
+}
+
+/// What a single layer did during a cascade run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerOutcome {
+    /// The layer had no opinion; the cascade moved on.
+    PassThrough,
+    /// The layer produced the decision.
+    Decided,
+    /// The layer failed and was skipped.
+    Error,
+}
+
+/// Timing and outcome of one layer evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerTrace {
+    pub layer: DecisionLayer,
+    pub duration_ms: u64,
+    pub outcome: LayerOutcome,
 }
 
 /// The type of gate being evaluated.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GateKind {
     /// "Should this tool be built?"
     Creation,
@@ -1,14 +1,31 @@
-use crate::decision::{Decision, DecisionLayer as DecisionLayerEnum, GateKind, LayeredDecision};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use tracing::Instrument;
+
+use crate::audit::{AuditLog, AuditRecord, AuditWarmup, now_ms};
+use crate::decision::{
+    Decision, DecisionLayer as DecisionLayerEnum, DeferTarget, GateKind, LayerOutcome, LayerTrace,
+    LayeredDecision,
+};
 use crate::error::DecisionError;
-use crate::layers::DecisionLayer;
+use crate::layers::budget::{ConstraintBudget, ConstraintBudgetLayer};
 use crate::layers::cache::CacheLayer;
 use crate::layers::cli_check::CliCheckLayer;
+use crate::layers::force_ask::{AlwaysAsk, ForceAskLayer};
 use crate::layers::hitl::HitlLayer;
 use crate::layers::llm::LlmEvaluationLayer;
-use crate::layers::policy::PolicyRulesLayer;
+use crate::layers::policy::{PolicyPattern, PolicyRulesLayer};
 use crate::layers::registry::RegistryLookupLayer;
-use crate::layers::similarity::SimilarityLayer;
-use crate::spec::GateInput;
+use crate::layers::similarity::{KnownSpec, SimilarityLayer};
+use crate::layers::{DecisionLayer, ExplainedDecision};
+use crate::spec::{CapabilitySpec, GateInput, RequestSource};
+
+/// How long an Execution Gate Allow for a read-only tool is reused when no
+/// other TTL is configured.
+pub const DEFAULT_EXECUTION_ALLOW_TTL: Duration = Duration::from_secs(5 * 60);
 
 /// The Hookwise decision engine -- orchestrates the cascade of layers.
 ///
@@ -17,11 +34,21 @@ use crate::spec::GateInput;
 pub struct DecisionEngine {
     creation_layers: CreationLayers,
     execution_layers: ExecutionLayers,
+    /// Where terminal decisions are recorded, if anywhere.
+    audit: Option<AuditLog>,
+    /// How long an Execution Gate Allow is reused for an identical call to
+    /// a read-only tool (see [`GateInput::allow_is_reusable`]).
+    execution_allow_ttl: Duration,
+    /// Names of the loaded tools, once [`Self::refresh_known_tools`] has
+    /// been called.
+    known_tools: RwLock<Option<HashSet<String>>>,
 }
 
 /// Layers for the Creation Gate ("Should this tool be built?")
 pub struct CreationLayers {
+    pub budget: ConstraintBudgetLayer,
     pub policy: PolicyRulesLayer,
+    pub force_ask: ForceAskLayer,
     pub cache: CacheLayer,
     pub registry: RegistryLookupLayer,
     pub cli_check: CliCheckLayer,
@@ -33,6 +60,7 @@ pub struct CreationLayers {
 /// Layers for the Execution Gate ("Should this invocation proceed?")
 pub struct ExecutionLayers {
     pub policy: PolicyRulesLayer,
+    pub force_ask: ForceAskLayer,
     pub cache: CacheLayer,
     pub llm: LlmEvaluationLayer,
     pub hitl: HitlLayer,
@@ -43,6 +71,9 @@ impl DecisionEngine {
         Self {
             creation_layers,
             execution_layers,
+            audit: None,
+            execution_allow_ttl: DEFAULT_EXECUTION_ALLOW_TTL,
+            known_tools: RwLock::new(None),
         }
     }
 
@@ -56,7 +87,9 @@ impl DecisionEngine {
     ) -> Self {
         Self {
             creation_layers: CreationLayers {
+                budget: ConstraintBudgetLayer::new(ConstraintBudget::default()),
                 policy: PolicyRulesLayer::with_defaults(),
+                force_ask: ForceAskLayer::new(vec![]),
                 cache: CacheLayer::new(),
                 registry: RegistryLookupLayer::new(vec![]),
                 cli_check: CliCheckLayer::with_defaults(),
@@ -66,10 +99,14 @@ impl DecisionEngine {
             },
             execution_layers: ExecutionLayers {
                 policy: PolicyRulesLayer::with_defaults(),
+                force_ask: ForceAskLayer::new(vec![]),
                 cache: CacheLayer::new(),
                 llm: LlmEvaluationLayer::new(execution_evaluator),
                 hitl: HitlLayer::with_default(),
             },
+            audit: None,
+            execution_allow_ttl: DEFAULT_EXECUTION_ALLOW_TTL,
+            known_tools: RwLock::new(None),
         }
     }
 
@@ -77,7 +114,9 @@ impl DecisionEngine {
     pub fn with_defaults() -> Self {
         Self {
             creation_layers: CreationLayers {
+                budget: ConstraintBudgetLayer::new(ConstraintBudget::default()),
                 policy: PolicyRulesLayer::with_defaults(),
+                force_ask: ForceAskLayer::new(vec![]),
                 cache: CacheLayer::new(),
                 registry: RegistryLookupLayer::new(vec![]),
                 cli_check: CliCheckLayer::with_defaults(),
@@ -87,11 +126,144 @@ impl DecisionEngine {
             },
             execution_layers: ExecutionLayers {
                 policy: PolicyRulesLayer::with_defaults(),
+                force_ask: ForceAskLayer::new(vec![]),
                 cache: CacheLayer::new(),
                 llm: LlmEvaluationLayer::with_stub(),
                 hitl: HitlLayer::with_default(),
             },
+            audit: None,
+            execution_allow_ttl: DEFAULT_EXECUTION_ALLOW_TTL,
+            known_tools: RwLock::new(None),
+        }
+    }
+
+    /// Replace the Creation Gate's CLI check layer, e.g. with one that also
+    /// knows the utilities configured in girt.toml.
+    pub fn with_cli_check(mut self, layer: CliCheckLayer) -> Self {
+        self.creation_layers.cli_check = layer;
+        self
+    }
+
+    /// Replace the Creation Gate's constraint budget, e.g. with the
+    /// `[security.budget]` section of girt.toml.
+    pub fn with_constraint_budget(mut self, budget: ConstraintBudget) -> Self {
+        self.creation_layers.budget = ConstraintBudgetLayer::new(budget);
+        self
+    }
+
+    /// Put requests for tools matching `always_ask` to a person at each
+    /// gate, e.g. the `[security.always_ask]` section of girt.toml.
+    pub fn with_always_ask(mut self, always_ask: AlwaysAsk) -> Self {
+        self.creation_layers.force_ask = ForceAskLayer::new(always_ask.creation);
+        self.execution_layers.force_ask = ForceAskLayer::new(always_ask.execution);
+        self
+    }
+
+    /// Reuse Execution Gate Allows for read-only tools for `ttl` instead of
+    /// [`DEFAULT_EXECUTION_ALLOW_TTL`]. Zero re-evaluates every call.
+    pub fn with_execution_allow_ttl(mut self, ttl: Duration) -> Self {
+        self.execution_allow_ttl = ttl;
+        self
+    }
+
+    /// Record every terminal decision not served from the cache in `log`.
+    pub fn with_audit_log(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Repopulate both gate caches from an audit log written by
+    /// [`with_audit_log`](Self::with_audit_log), so recent decisions are not
+    /// re-evaluated after a restart.
+    ///
+    /// Only terminal decisions younger than `max_age` are loaded; later
+    /// records for the same input win. Constraint budget denials are not
+    /// loaded, as they were never cached. An Execution Allow is loaded only
+    /// if it was recorded as reusable, and only for what is left of the
+    /// execution Allow TTL, as [`cache_decision`](Self::cache_decision)
+    /// would have stored it. Malformed lines are skipped and counted. A
+    /// missing file loads nothing.
+    pub async fn warm_from_audit(
+        &self,
+        path: &Path,
+        max_age: Duration,
+    ) -> std::io::Result<AuditWarmup> {
+        let contents = match tokio::fs::read_to_string(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(AuditWarmup::default());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let now = now_ms();
+        let cutoff = now.saturating_sub(max_age.as_millis() as u64);
+        let mut warmup = AuditWarmup::default();
+        let mut latest: HashMap<(GateKind, String), AuditRecord> = HashMap::new();
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            let Ok(record) = serde_json::from_str::<AuditRecord>(line) else {
+                warmup.malformed += 1;
+                continue;
+            };
+            if record.timestamp_ms < cutoff
+                || !record.decision.is_terminal()
+                || record.layer == DecisionLayerEnum::ConstraintBudget
+            {
+                warmup.skipped += 1;
+                continue;
+            }
+            if latest
+                .insert((record.gate, record.input_hash.clone()), record)
+                .is_some()
+            {
+                warmup.skipped += 1;
+            }
         }
+
+        for record in latest.into_values() {
+            match (record.gate, &record.decision) {
+                (GateKind::Execution, Decision::Allow) => {
+                    let age = Duration::from_millis(now.saturating_sub(record.timestamp_ms));
+                    let ttl = self.execution_allow_ttl.saturating_sub(age);
+                    if !record.reusable || ttl.is_zero() {
+                        warmup.skipped += 1;
+                        continue;
+                    }
+                    self.execution_layers
+                        .cache
+                        .store_expiring(&record.subject, record.input_hash, record.decision, ttl)
+                        .await;
+                }
+                (GateKind::Execution, _) => {
+                    self.execution_layers
+                        .cache
+                        .store_for(&record.subject, record.input_hash, record.decision)
+                        .await;
+                }
+                (GateKind::Creation, _) => {
+                    self.creation_layers
+                        .cache
+                        .store_for(&record.subject, record.input_hash, record.decision)
+                        .await;
+                }
+            }
+            warmup.loaded += 1;
+        }
+
+        if warmup.malformed > 0 {
+            tracing::warn!(
+                path = %path.display(),
+                malformed = warmup.malformed,
+                "Skipped malformed audit log lines"
+            );
+        }
+        tracing::info!(
+            path = %path.display(),
+            loaded = warmup.loaded,
+            skipped = warmup.skipped,
+            "Warmed decision caches from audit log"
+        );
+        Ok(warmup)
     }
 
     /// Evaluate a request through the appropriate gate cascade.
@@ -100,12 +272,60 @@ impl DecisionEngine {
         gate: GateKind,
         input: &GateInput,
     ) -> Result<LayeredDecision, DecisionError> {
-        match gate {
-            GateKind::Creation => self.evaluate_creation(input).await,
-            GateKind::Execution => self.evaluate_execution(input).await,
+        let span = tracing::info_span!(
+            "gate",
+            gate = %gate,
+            subject = %input.subject(),
+            decision = tracing::field::Empty,
+            layer = tracing::field::Empty,
+        );
+        let result = async {
+            match gate {
+                GateKind::Creation => self.evaluate_creation(input).await,
+                GateKind::Execution => self.evaluate_execution(input).await,
+            }
+        }
+        .instrument(span.clone())
+        .await;
+        if let Ok(result) = &result {
+            span.record("decision", result.decision.status());
+            span.record("layer", tracing::field::display(&result.layer));
+        }
+        result
+    }
+
+    /// Descriptions of the Creation Gate's current deny rules, including
+    /// any applied by [`Self::reload_policy`].
+    pub fn creation_deny_rules(&self) -> Vec<String> {
+        self.creation_layers.policy.deny_descriptions()
+    }
+
+    /// Whether the Creation Gate's policy denies every spec asking for
+    /// `secret`, so no tool could be built to use it.
+    pub fn secret_denied(&self, secret: &str) -> bool {
+        self.creation_layers.policy.denies_secret(secret)
+    }
+
+    /// Why the Creation Gate's policy rules deny `spec`, checked without
+    /// the later layers: nothing is cached and no LLM or person is asked.
+    /// `None` when no deny rule matches.
+    pub async fn policy_denial(&self, spec: &CapabilitySpec) -> Option<String> {
+        let input = GateInput::creation(spec.clone(), RequestSource::Operator);
+        match self.creation_layers.policy.evaluate(&input).await {
+            Ok(Some(Decision::Deny { reason })) => Some(reason),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!(tool = %spec.name, error = %e, "Policy rules failed");
+                None
+            }
         }
     }
 
+    /// The Creation Gate's constraint budget.
+    pub fn constraint_budget(&self) -> &ConstraintBudget {
+        self.creation_layers.budget.budget()
+    }
+
     /// Access the creation cache for storing decisions after the fact.
     pub fn creation_cache(&self) -> &CacheLayer {
         &self.creation_layers.cache
@@ -116,16 +336,179 @@ impl DecisionEngine {
         &self.execution_layers.cache
     }
 
+    /// Drop cached Execution Gate decisions for a tool, e.g. after it is
+    /// unloaded, so a later tool with the same name is evaluated afresh.
+    /// Returns how many entries were removed.
+    pub async fn invalidate_execution(&self, tool_name: &str) -> usize {
+        let removed = self
+            .execution_layers
+            .cache
+            .invalidate_subject(tool_name)
+            .await;
+        if removed > 0 {
+            tracing::debug!(
+                tool = tool_name,
+                removed,
+                "Invalidated execution cache entries"
+            );
+        }
+        removed
+    }
+
+    /// Replace the tools the Creation Gate knows are loaded, e.g. after one
+    /// is built or removed. The similarity layer compares requests with
+    /// exactly these, and from then on a Defer to extend a tool missing from
+    /// them falls through to the next layer instead of sending the agent to
+    /// a tool it cannot call.
+    pub fn refresh_known_tools(&self, tools: Vec<KnownSpec>) {
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        self.creation_layers.similarity.replace_known_specs(tools);
+        *self.known_tools.write().unwrap_or_else(|e| e.into_inner()) = Some(names);
+    }
+
+    /// Whether `decision` is a Defer to extend a tool that cannot take the
+    /// request: one that is not loaded, or the very tool the request names
+    /// (the proxy compares a same-name request with the loaded tool before
+    /// the gate, so it is a replacement, not a duplicate).
+    fn is_dead_end(&self, input: &GateInput, decision: &Decision) -> bool {
+        let Decision::Defer {
+            target: DeferTarget::ExtendTool { tool_name, .. },
+        } = decision
+        else {
+            return false;
+        };
+        let known_tools = self.known_tools.read().unwrap_or_else(|e| e.into_inner());
+        tool_name == input.subject()
+            || known_tools
+                .as_ref()
+                .is_some_and(|known| !known.contains(tool_name))
+    }
+
+    /// Whether `decision` is a policy allow that a matching `always_ask`
+    /// pattern overrides. Deny patterns still win over `always_ask`; allow
+    /// patterns only spare a request the later layers.
+    fn yields_to_force_ask(
+        &self,
+        gate: GateKind,
+        layer: &DecisionLayerEnum,
+        input: &GateInput,
+        decision: &Decision,
+    ) -> bool {
+        let force_ask = match gate {
+            GateKind::Creation => &self.creation_layers.force_ask,
+            GateKind::Execution => &self.execution_layers.force_ask,
+        };
+        *layer == DecisionLayerEnum::PolicyRules
+            && matches!(decision, Decision::Allow)
+            && force_ask.matching(input.subject()).is_some()
+    }
+
+    /// Swap both gates' policy rules for the defaults plus the given
+    /// patterns. Takes effect on the next evaluation.
+    ///
+    /// Cached decisions are dropped so earlier Allows cannot outlive a newly
+    /// added deny pattern.
+    pub async fn reload_policy(
+        &self,
+        extra_deny: Vec<PolicyPattern>,
+        extra_allow: Vec<PolicyPattern>,
+    ) {
+        self.creation_layers
+            .policy
+            .reset_with_extra(extra_deny.clone(), extra_allow.clone());
+        self.execution_layers
+            .policy
+            .reset_with_extra(extra_deny, extra_allow);
+        self.creation_layers.cache.clear().await;
+        self.execution_layers.cache.clear().await;
+    }
+
+    /// Check that `spec`, a narrowed version of the spec in `input`, can be
+    /// built in its place: it keeps the name, description, inputs and
+    /// outputs, grants nothing the request did not ask for, passes
+    /// validation, fits the constraint budget for the request's source, and
+    /// matches no policy deny rule. The error says what is wrong with it.
+    pub async fn check_modified_spec(
+        &self,
+        input: &GateInput,
+        spec: &CapabilitySpec,
+    ) -> Result<(), String> {
+        let GateInput::Creation {
+            spec: requested,
+            source,
+        } = input
+        else {
+            return Err("only replaces capability requests".into());
+        };
+        if spec.name != requested.name {
+            return Err(format!("renames the tool to '{}'", spec.name));
+        }
+        // Only the grants may change: a different tool was never gated.
+        if spec.description != requested.description {
+            return Err("changes the description".into());
+        }
+        if spec.inputs != requested.inputs {
+            return Err("changes the inputs".into());
+        }
+        if spec.outputs != requested.outputs {
+            return Err("changes the outputs".into());
+        }
+        let added = spec.constraints.additions_over(&requested.constraints);
+        if !added.is_empty() {
+            return Err(format!("adds to the request: {}", added.join(", ")));
+        }
+        spec.validate().map_err(|e| e.to_string())?;
+        if let Some(reason) = self.constraint_budget().exceeded_by(spec, *source) {
+            return Err(reason);
+        }
+        match self.policy_denial(spec).await {
+            Some(reason) => Err(reason),
+            None => Ok(()),
+        }
+    }
+
+    /// `decision`, unless it is an AllowModified whose spec cannot be built
+    /// in place of the requested one; that is put to a person instead.
+    async fn checked_modification(&self, input: &GateInput, decision: Decision) -> Decision {
+        let Decision::AllowModified { spec, changes } = &decision else {
+            return decision;
+        };
+        let Err(problem) = self.check_modified_spec(input, spec).await else {
+            return decision;
+        };
+        tracing::warn!(
+            subject = %input.subject(),
+            %problem,
+            "Modified spec failed re-validation, asking a person instead"
+        );
+        Decision::Ask {
+            prompt: format!(
+                "The gate would allow this request with changes ({}), but the changed spec \
+                 does not pass: {problem}. Approve to build it as requested.",
+                changes.join("; ")
+            ),
+            context: problem,
+        }
+    }
+
     async fn evaluate_creation(&self, input: &GateInput) -> Result<LayeredDecision, DecisionError> {
         let layers: Vec<(&dyn DecisionLayer, DecisionLayerEnum)> = vec![
+            (
+                &self.creation_layers.budget,
+                DecisionLayerEnum::ConstraintBudget,
+            ),
             (&self.creation_layers.policy, DecisionLayerEnum::PolicyRules),
+            (&self.creation_layers.force_ask, DecisionLayerEnum::ForceAsk),
             (&self.creation_layers.cache, DecisionLayerEnum::Cache),
             (
                 &self.creation_layers.registry,
                 DecisionLayerEnum::RegistryLookup,
             ),
             (&self.creation_layers.cli_check, DecisionLayerEnum::CliCheck),
-            (&self.creation_layers.similarity, DecisionLayerEnum::Similarity),
+            (
+                &self.creation_layers.similarity,
+                DecisionLayerEnum::Similarity,
+            ),
             (&self.creation_layers.llm, DecisionLayerEnum::LlmEvaluation),
             (&self.creation_layers.hitl, DecisionLayerEnum::Hitl),
         ];
@@ -142,6 +525,10 @@ impl DecisionEngine {
                 &self.execution_layers.policy,
                 DecisionLayerEnum::PolicyRules,
             ),
+            (
+                &self.execution_layers.force_ask,
+                DecisionLayerEnum::ForceAsk,
+            ),
             (&self.execution_layers.cache, DecisionLayerEnum::Cache),
             (&self.execution_layers.llm, DecisionLayerEnum::LlmEvaluation),
             (&self.execution_layers.hitl, DecisionLayerEnum::Hitl),
@@ -156,6 +543,8 @@ impl DecisionEngine {
         input: &GateInput,
         gate: GateKind,
     ) -> Result<LayeredDecision, DecisionError> {
+        let mut consulted_layers = Vec::with_capacity(layers.len());
+
         for (layer, layer_enum) in layers {
             tracing::debug!(
                 gate = %gate,
@@ -163,29 +552,80 @@ impl DecisionEngine {
                 "Evaluating layer"
             );
 
-            match layer.evaluate(input).await {
-                Ok(Some(decision)) => {
+            let started = Instant::now();
+            let outcome = layer.evaluate_explained(input).await;
+            let duration_ms = started.elapsed().as_millis() as u64;
+            let mut trace = |outcome| {
+                consulted_layers.push(LayerTrace {
+                    layer: layer_enum.clone(),
+                    duration_ms,
+                    outcome,
+                })
+            };
+
+            match outcome {
+                Ok(Some(ExplainedDecision { decision, .. }))
+                    if self.is_dead_end(input, &decision) =>
+                {
+                    tracing::info!(
+                        gate = %gate,
+                        layer = layer.name(),
+                        decision = ?decision,
+                        "Ignoring a Defer to a tool that cannot take the request"
+                    );
+                    trace(LayerOutcome::PassThrough);
+                }
+                Ok(Some(ExplainedDecision { decision, .. }))
+                    if self.yields_to_force_ask(gate, layer_enum, input, &decision) =>
+                {
+                    tracing::info!(
+                        gate = %gate,
+                        layer = layer.name(),
+                        "Policy allow left to always_ask"
+                    );
+                    trace(LayerOutcome::PassThrough);
+                }
+                Ok(Some(ExplainedDecision {
+                    decision,
+                    rationale,
+                })) => {
+                    let decision = self.checked_modification(input, decision).await;
                     tracing::info!(
                         gate = %gate,
                         layer = layer.name(),
                         decision = ?decision,
+                        duration_ms,
                         "Layer produced decision"
                     );
+                    trace(LayerOutcome::Decided);
 
                     let result = LayeredDecision {
                         decision: decision.clone(),
                         layer: layer_enum.clone(),
-                        rationale: None,
+                        rationale,
+                        consulted_layers,
                     };
 
                     // Cache terminal decisions for future lookups
-                    if decision.is_terminal() {
+                    if decision.is_terminal() && *layer_enum != DecisionLayerEnum::Cache {
                         let hash = input.hash();
-                        let cache = match gate {
-                            GateKind::Creation => &self.creation_layers.cache,
-                            GateKind::Execution => &self.execution_layers.cache,
-                        };
-                        cache.store(hash, decision).await;
+                        if let Some(audit) = &self.audit {
+                            audit.append(&AuditRecord {
+                                timestamp_ms: now_ms(),
+                                gate,
+                                subject: input.subject().to_string(),
+                                input_hash: hash.clone(),
+                                source: input.source(),
+                                layer: layer_enum.clone(),
+                                decision: decision.clone(),
+                                reusable: input.allow_is_reusable(),
+                            });
+                        }
+                        // A budget denial depends on the request source,
+                        // which the cache key leaves out.
+                        if *layer_enum != DecisionLayerEnum::ConstraintBudget {
+                            self.cache_decision(gate, input, hash, decision).await;
+                        }
                     }
 
                     return Ok(result);
@@ -194,8 +634,10 @@ impl DecisionEngine {
                     tracing::debug!(
                         gate = %gate,
                         layer = layer.name(),
+                        duration_ms,
                         "Layer passed through"
                     );
+                    trace(LayerOutcome::PassThrough);
                 }
                 Err(e) => {
                     // Layer error: log and continue to next layer (fail-open within cascade)
@@ -205,6 +647,7 @@ impl DecisionEngine {
                         error = %e,
                         "Layer error, skipping"
                     );
+                    trace(LayerOutcome::Error);
                 }
             }
         }
@@ -222,29 +665,80 @@ impl DecisionEngine {
             },
             layer: DecisionLayerEnum::Hitl,
             rationale: Some("Fallback deny: no layer produced a decision".into()),
+            consulted_layers,
         })
     }
+
+    /// Store a terminal decision in its gate's cache.
+    ///
+    /// An Execution Gate Allow is only worth reusing for the same call to a
+    /// read-only tool, and only briefly; for any other tool every call is
+    /// evaluated afresh. Denials are always kept.
+    async fn cache_decision(
+        &self,
+        gate: GateKind,
+        input: &GateInput,
+        hash: String,
+        decision: Decision,
+    ) {
+        match (gate, &decision) {
+            (GateKind::Execution, Decision::Allow) => {
+                if input.allow_is_reusable() && !self.execution_allow_ttl.is_zero() {
+                    self.execution_layers
+                        .cache
+                        .store_expiring(input.subject(), hash, decision, self.execution_allow_ttl)
+                        .await;
+                }
+            }
+            (GateKind::Execution, _) => {
+                self.execution_layers
+                    .cache
+                    .store_for(input.subject(), hash, decision)
+                    .await;
+            }
+            (GateKind::Creation, _) => {
+                self.creation_layers
+                    .cache
+                    .store_for(input.subject(), hash, decision)
+                    .await;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec, ExecutionRequest};
+    use crate::layers::cli_check::default_utilities;
+    #[cfg(unix)]
+    use crate::layers::cli_check::stub_bin_dir;
+    use crate::layers::llm::{LlmDecision, LlmDecisionKind, LlmEvaluator, StubLlmEvaluator};
+    use crate::layers::policy::ConstraintPatterns;
+    use crate::spec::{
+        CapabilityConstraints, CapabilitySpec, ExecutionRequest, NetworkGrant, RequestSource,
+        ToolProfile,
+    };
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     fn make_creation_input(name: &str, desc: &str) -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: name.into(),
-            description: desc.into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: name.into(),
+                description: desc.into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
     fn make_execution_input(name: &str) -> GateInput {
         GateInput::Execution(ExecutionRequest {
             tool_name: name.into(),
             arguments: serde_json::Value::Null,
+            tool: None,
         })
     }
 
@@ -270,9 +764,13 @@ mod tests {
         assert_eq!(result.layer, DecisionLayerEnum::PolicyRules);
     }
 
+    #[cfg(unix)]
     #[tokio::test]
     async fn creation_gate_defers_to_cli() {
-        let engine = DecisionEngine::with_defaults();
+        let bin = stub_bin_dir(&["jq"]);
+        let engine = DecisionEngine::with_defaults().with_cli_check(
+            CliCheckLayer::with_search_path(default_utilities(), bin.path()),
+        );
         let input = make_creation_input("json_query", "Query JSON documents");
 
         let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
@@ -281,6 +779,36 @@ mod tests {
         assert_eq!(result.layer, DecisionLayerEnum::CliCheck);
     }
 
+    #[tokio::test]
+    async fn policy_denial_consults_only_the_policy_rules() {
+        let engine = DecisionEngine::with_defaults();
+        let internal = PolicyPattern {
+            description: "Internal hosts".into(),
+            name_pattern: None,
+            description_pattern: None,
+            constraint_patterns: Some(ConstraintPatterns {
+                network_deny: Some(vec![r"\.internal\.corp$".into()]),
+                storage_deny: None,
+                secrets_deny: None,
+            }),
+        };
+        engine.reload_policy(vec![internal], vec![]).await;
+
+        let GateInput::Creation { mut spec, .. } =
+            make_creation_input("billing_lookup", "Look up an invoice")
+        else {
+            unreachable!()
+        };
+        assert_eq!(engine.policy_denial(&spec).await, None);
+
+        spec.constraints.network = vec!["billing.internal.corp".into()];
+        assert_eq!(
+            engine.policy_denial(&spec).await.as_deref(),
+            Some("Policy rule: Internal hosts")
+        );
+        assert_eq!(engine.creation_cache().len().await, 0);
+    }
+
     #[tokio::test]
     async fn creation_gate_caches_terminal_decisions() {
         let engine = DecisionEngine::with_defaults();
@@ -294,14 +822,49 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn creation_gate_unknown_tool_reaches_llm() {
+    async fn budget_denials_are_not_cached_across_sources() {
         let engine = DecisionEngine::with_defaults();
+        let spec = CapabilitySpec {
+            name: "math_with_key".into(),
+            description: "Add two numbers".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: CapabilityConstraints {
+                secrets: vec!["API_KEY".into()],
+                ..Default::default()
+            },
+        };
+
+        let agent = GateInput::creation(spec.clone(), RequestSource::Agent);
+        let denied = engine.evaluate(GateKind::Creation, &agent).await.unwrap();
+        assert!(matches!(denied.decision, Decision::Deny { .. }));
+        assert_eq!(denied.layer, DecisionLayerEnum::ConstraintBudget);
+        assert_eq!(engine.creation_cache().len().await, 0);
+
+        let operator = GateInput::creation(spec, RequestSource::Operator);
+        let allowed = engine
+            .evaluate(GateKind::Creation, &operator)
+            .await
+            .unwrap();
+        assert_eq!(allowed.decision, Decision::Allow);
+        assert_eq!(allowed.layer, DecisionLayerEnum::PolicyRules);
+    }
+
+    #[tokio::test]
+    async fn creation_gate_unknown_tool_reaches_llm() {
+        // Whether or not gh is installed here, nothing is on this PATH.
+        let engine = DecisionEngine::with_defaults()
+            .with_cli_check(CliCheckLayer::with_search_path(default_utilities(), ""));
         let input = make_creation_input("github_issues", "Fetch GitHub issues with filtering");
 
         let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
 
         // Stub LLM returns Ask, which is a terminal decision from LLM layer
         assert_eq!(result.layer, DecisionLayerEnum::LlmEvaluation);
+        assert_eq!(
+            result.rationale.as_deref(),
+            Some("LLM evaluation not yet configured, deferring to human")
+        );
     }
 
     #[tokio::test]
@@ -325,4 +888,800 @@ mod tests {
         // Stub LLM returns Ask
         assert!(matches!(result.decision, Decision::Ask { .. }));
     }
+
+    fn always_ask(pattern: &str) -> AlwaysAsk {
+        AlwaysAsk {
+            creation: vec![pattern.into()],
+            execution: vec![pattern.into()],
+        }
+    }
+
+    #[tokio::test]
+    async fn always_ask_overrides_cached_and_llm_allows() {
+        let engine = DecisionEngine::with_defaults().with_always_ask(always_ask("deploy_*"));
+        let input = make_execution_input("deploy_prod");
+        engine
+            .execution_cache()
+            .store_for("deploy_prod", input.hash(), Decision::Allow)
+            .await;
+
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        let Decision::Ask { prompt, .. } = &result.decision else {
+            panic!("expected Ask, got {:?}", result.decision);
+        };
+        assert!(prompt.contains("always-ask rule 'deploy_*'"), "{prompt}");
+        assert_eq!(result.layer, DecisionLayerEnum::ForceAsk);
+
+        // Not cached: the next call is asked about again.
+        let again = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert_eq!(again.layer, DecisionLayerEnum::ForceAsk);
+
+        let creation = make_creation_input("deploy_site", "Deploy the site");
+        let result = engine
+            .evaluate(GateKind::Creation, &creation)
+            .await
+            .unwrap();
+        assert!(matches!(result.decision, Decision::Ask { .. }));
+        assert_eq!(result.layer, DecisionLayerEnum::ForceAsk);
+    }
+
+    #[tokio::test]
+    async fn policy_denials_win_over_always_ask() {
+        let engine = DecisionEngine::with_defaults().with_always_ask(always_ask("shell_*"));
+        let input = make_execution_input("shell_exec");
+
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+
+        assert!(matches!(result.decision, Decision::Deny { .. }));
+        assert_eq!(result.layer, DecisionLayerEnum::PolicyRules);
+    }
+
+    #[tokio::test]
+    async fn always_ask_overrides_policy_allows() {
+        let engine = DecisionEngine::with_defaults().with_always_ask(always_ask("deploy_*"));
+        let allow = |pattern: &str| PolicyPattern {
+            description: "Deploys".into(),
+            name_pattern: Some(pattern.into()),
+            description_pattern: None,
+            constraint_patterns: None,
+        };
+        engine.reload_policy(vec![], vec![allow("^deploy_")]).await;
+
+        let input = make_execution_input("deploy_prod");
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert!(matches!(result.decision, Decision::Ask { .. }));
+        assert_eq!(result.layer, DecisionLayerEnum::ForceAsk);
+        let policy = &result.consulted_layers[0];
+        assert_eq!(policy.layer, DecisionLayerEnum::PolicyRules);
+        assert_eq!(policy.outcome, LayerOutcome::PassThrough);
+
+        // Without an always_ask match the allow pattern still decides.
+        engine.reload_policy(vec![], vec![allow("deploy")]).await;
+        let input = make_execution_input("redeploy_prod");
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert_eq!(result.decision, Decision::Allow);
+        assert_eq!(result.layer, DecisionLayerEnum::PolicyRules);
+    }
+
+    #[tokio::test]
+    async fn always_ask_leaves_other_tools_alone() {
+        let engine = DecisionEngine::with_defaults().with_always_ask(always_ask("deploy_*"));
+        let input = make_execution_input("redeploy_prod");
+
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+
+        assert_eq!(result.layer, DecisionLayerEnum::LlmEvaluation);
+        let force_ask = &result.consulted_layers[1];
+        assert_eq!(force_ask.layer, DecisionLayerEnum::ForceAsk);
+        assert_eq!(force_ask.outcome, LayerOutcome::PassThrough);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn trace_lists_consulted_layers_in_cascade_order() {
+        let bin = stub_bin_dir(&["jq"]);
+        let engine = DecisionEngine::with_defaults().with_cli_check(
+            CliCheckLayer::with_search_path(default_utilities(), bin.path()),
+        );
+        let input = make_creation_input("json_query", "Query JSON documents");
+
+        let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
+
+        let layers: Vec<_> = result
+            .consulted_layers
+            .iter()
+            .map(|t| t.layer.clone())
+            .collect();
+        assert_eq!(
+            layers,
+            vec![
+                DecisionLayerEnum::ConstraintBudget,
+                DecisionLayerEnum::PolicyRules,
+                DecisionLayerEnum::ForceAsk,
+                DecisionLayerEnum::Cache,
+                DecisionLayerEnum::RegistryLookup,
+                DecisionLayerEnum::CliCheck,
+            ]
+        );
+        assert!(
+            result.consulted_layers[..5]
+                .iter()
+                .all(|t| t.outcome == LayerOutcome::PassThrough)
+        );
+    }
+
+    #[tokio::test]
+    async fn trace_ends_with_deciding_layer() {
+        let engine = DecisionEngine::with_defaults();
+        let input = make_execution_input("shell_exec");
+
+        let first = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert_eq!(first.consulted_layers.len(), 1);
+        let last = first.consulted_layers.last().unwrap();
+        assert_eq!(last.layer, first.layer);
+        assert_eq!(last.outcome, LayerOutcome::Decided);
+
+        let input = make_execution_input("some_approved_tool");
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        let last = result.consulted_layers.last().unwrap();
+        assert_eq!(last.layer, result.layer);
+        assert_eq!(last.outcome, LayerOutcome::Decided);
+        assert_eq!(
+            result
+                .consulted_layers
+                .iter()
+                .filter(|t| t.outcome == LayerOutcome::Decided)
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn reloaded_deny_pattern_applies_to_next_evaluation() {
+        let engine = DecisionEngine::with_defaults();
+        let input = make_execution_input("crypto_miner");
+        engine
+            .execution_cache()
+            .store_for("crypto_miner", input.hash(), Decision::Allow)
+            .await;
+
+        engine
+            .reload_policy(
+                vec![PolicyPattern {
+                    description: "No crypto miners".into(),
+                    name_pattern: Some(r"(?i)miner".into()),
+                    description_pattern: None,
+                    constraint_patterns: None,
+                }],
+                vec![],
+            )
+            .await;
+
+        let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert!(matches!(result.decision, Decision::Deny { .. }));
+        assert_eq!(result.layer, DecisionLayerEnum::PolicyRules);
+    }
+
+    #[tokio::test]
+    async fn invalidate_execution_clears_cached_decisions_for_tool() {
+        let engine = DecisionEngine::with_defaults();
+        let input = make_execution_input("removed_tool");
+        engine
+            .execution_cache()
+            .store_for("removed_tool", input.hash(), Decision::Allow)
+            .await;
+
+        let cached = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert_eq!(cached.layer, DecisionLayerEnum::Cache);
+
+        assert_eq!(engine.invalidate_execution("removed_tool").await, 1);
+        let fresh = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert_ne!(fresh.layer, DecisionLayerEnum::Cache);
+    }
+
+    /// Allows everything, counting how often it was asked.
+    struct CountingAllow(Arc<AtomicUsize>);
+
+    impl LlmEvaluator for CountingAllow {
+        fn evaluate<'a>(
+            &'a self,
+            _input: &'a GateInput,
+        ) -> std::pin::Pin<
+            Box<dyn std::future::Future<Output = Result<LlmDecision, DecisionError>> + Send + 'a>,
+        > {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                Ok(LlmDecision {
+                    decision: LlmDecisionKind::Allow,
+                    rationale: "fine".into(),
+                    conditions: Vec::new(),
+                    modified_spec: None,
+                    changes: Vec::new(),
+                })
+            })
+        }
+    }
+
+    fn allowing_engine() -> (DecisionEngine, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let engine = DecisionEngine::with_real_llm(
+            Box::new(StubLlmEvaluator),
+            Box::new(CountingAllow(Arc::clone(&calls))),
+        );
+        (engine, calls)
+    }
+
+    /// Allows creation requests once the function has narrowed their spec,
+    /// reporting the changes it returns.
+    struct Narrowing(fn(&mut CapabilitySpec) -> Vec<String>);
+
+    impl LlmEvaluator for Narrowing {
+        fn evaluate<'a>(
+            &'a self,
+            input: &'a GateInput,
+        ) -> std::pin::Pin<
+            Box<dyn std::future::Future<Output = Result<LlmDecision, DecisionError>> + Send + 'a>,
+        > {
+            Box::pin(async move {
+                let GateInput::Creation { spec, .. } = input else {
+                    return Err(DecisionError::LlmError("not a creation request".into()));
+                };
+                let mut spec = spec.clone();
+                let changes = (self.0)(&mut spec);
+                Ok(LlmDecision {
+                    decision: LlmDecisionKind::AllowModified,
+                    rationale: "fine once narrowed".into(),
+                    conditions: Vec::new(),
+                    modified_spec: Some(spec),
+                    changes,
+                })
+            })
+        }
+    }
+
+    fn narrowing_engine(narrow: fn(&mut CapabilitySpec) -> Vec<String>) -> DecisionEngine {
+        DecisionEngine::with_real_llm(Box::new(Narrowing(narrow)), Box::new(StubLlmEvaluator))
+    }
+
+    /// An operator's request for a tool with a secret it may not need.
+    fn request_with_a_secret() -> GateInput {
+        GateInput::creation(
+            CapabilitySpec {
+                name: "weather_lookup".into(),
+                description: "Current weather for a city".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints {
+                    network: vec!["api.open-meteo.com".into()],
+                    secrets: vec!["WEATHER_API_KEY".into()],
+                    ..Default::default()
+                },
+            },
+            RequestSource::Operator,
+        )
+    }
+
+    #[tokio::test]
+    async fn llm_can_allow_a_narrowed_spec() {
+        let engine = narrowing_engine(|spec| {
+            spec.constraints.secrets.clear();
+            vec!["dropped the WEATHER_API_KEY secret".into()]
+        });
+
+        let result = engine
+            .evaluate(GateKind::Creation, &request_with_a_secret())
+            .await
+            .unwrap();
+        assert_eq!(result.layer, DecisionLayerEnum::LlmEvaluation);
+        assert!(result.decision.is_terminal());
+        let Decision::AllowModified { spec, changes } = result.decision else {
+            panic!("expected AllowModified, got {:?}", result.decision);
+        };
+        assert!(spec.constraints.secrets.is_empty());
+        assert_eq!(spec.constraints.network, ["api.open-meteo.com"]);
+        assert_eq!(changes, ["dropped the WEATHER_API_KEY secret"]);
+    }
+
+    #[tokio::test]
+    async fn a_narrowed_spec_that_fails_revalidation_is_put_to_a_person() {
+        let over_budget = narrowing_engine(|spec| {
+            spec.constraints.secrets.clear();
+            spec.constraints.network = (0..9)
+                .map(|i| format!("host{i}.example.com").into())
+                .collect();
+            vec!["dropped the secret".into()]
+        });
+        let result = over_budget
+            .evaluate(GateKind::Creation, &request_with_a_secret())
+            .await
+            .unwrap();
+        let Decision::Ask { prompt, context } = result.decision else {
+            panic!("expected Ask, got {:?}", result.decision);
+        };
+        assert!(
+            prompt.contains("with changes (dropped the secret)"),
+            "{prompt}"
+        );
+        assert!(context.contains("network"), "{context}");
+
+        let renamed = narrowing_engine(|spec| {
+            spec.name = "forecast_lookup".into();
+            vec!["renamed it".into()]
+        });
+        let result = renamed
+            .evaluate(GateKind::Creation, &request_with_a_secret())
+            .await
+            .unwrap();
+        assert_eq!(result.decision.status(), "ask");
+    }
+
+    #[tokio::test]
+    async fn a_narrowed_spec_may_not_add_grants_or_match_a_deny_rule() {
+        let widenings: [fn(&mut CapabilitySpec) -> Vec<String>; 3] = [
+            |spec| {
+                spec.constraints.network.push("evil.example.com".into());
+                vec!["added a host".into()]
+            },
+            |spec| {
+                spec.constraints.secrets = vec!["GITHUB_TOKEN".into()];
+                vec!["swapped the secret".into()]
+            },
+            |spec| {
+                spec.constraints.storage.push("/home".into());
+                vec!["added storage".into()]
+            },
+        ];
+        for widen in widenings {
+            let result = narrowing_engine(widen)
+                .evaluate(GateKind::Creation, &request_with_a_secret())
+                .await
+                .unwrap();
+            let Decision::Ask { context, .. } = result.decision else {
+                panic!("expected Ask, got {:?}", result.decision);
+            };
+            assert!(context.starts_with("adds to the request: "), "{context}");
+        }
+
+        // Scoping the host to DELETE requests narrows the grant, but makes
+        // it match a deny rule the requested grant did not.
+        let denied = narrowing_engine(|spec| {
+            spec.constraints.secrets.clear();
+            spec.constraints.network = vec![NetworkGrant::scoped(
+                "api.open-meteo.com",
+                vec!["DELETE".into()],
+                vec![],
+            )];
+            vec!["dropped the secret".into()]
+        });
+        let deny = PolicyPattern {
+            description: "Deletes".into(),
+            name_pattern: None,
+            description_pattern: None,
+            constraint_patterns: Some(ConstraintPatterns {
+                network_deny: Some(vec![r"\(DELETE\)".into()]),
+                storage_deny: None,
+                secrets_deny: None,
+            }),
+        };
+        denied.reload_policy(vec![deny], vec![]).await;
+        let result = denied
+            .evaluate(GateKind::Creation, &request_with_a_secret())
+            .await
+            .unwrap();
+        let Decision::Ask { context, .. } = result.decision else {
+            panic!("expected Ask, got {:?}", result.decision);
+        };
+        assert_eq!(context, "Policy rule: Deletes");
+    }
+
+    #[tokio::test]
+    async fn a_narrowed_spec_may_only_change_the_grants() {
+        let changes: [fn(&mut CapabilitySpec) -> Vec<String>; 3] = [
+            |spec| {
+                spec.description = "Uploads ~/.ssh to a paste site".into();
+                vec!["reworded the description".into()]
+            },
+            |spec| {
+                spec.inputs = serde_json::json!({"path": "string"});
+                vec!["added an input".into()]
+            },
+            |spec| {
+                spec.outputs = serde_json::json!({"contents": "string"});
+                vec!["added an output".into()]
+            },
+        ];
+        for (change, expected) in changes.into_iter().zip([
+            "changes the description",
+            "changes the inputs",
+            "changes the outputs",
+        ]) {
+            let result = narrowing_engine(change)
+                .evaluate(GateKind::Creation, &request_with_a_secret())
+                .await
+                .unwrap();
+            let Decision::Ask { context, .. } = result.decision else {
+                panic!("expected Ask, got {:?}", result.decision);
+            };
+            assert_eq!(context, expected);
+        }
+    }
+
+    fn profiled_call(profile: ToolProfile, arguments: serde_json::Value) -> GateInput {
+        GateInput::Execution(ExecutionRequest {
+            tool_name: "weather_lookup".into(),
+            arguments,
+            tool: Some(profile),
+        })
+    }
+
+    fn read_only() -> ToolProfile {
+        ToolProfile {
+            idempotent: true,
+            description: "Look up the current weather for a city".into(),
+            secrets: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_call_to_read_only_tool_reuses_the_allow() {
+        let (engine, calls) = allowing_engine();
+        let first = profiled_call(
+            read_only(),
+            serde_json::json!({"city": "Oslo", "units": "c"}),
+        );
+        let reordered = profiled_call(
+            read_only(),
+            serde_json::json!({"units": "c", "city": "Oslo"}),
+        );
+
+        let evaluated = engine.evaluate(GateKind::Execution, &first).await.unwrap();
+        assert_eq!(evaluated.layer, DecisionLayerEnum::LlmEvaluation);
+        let reused = engine
+            .evaluate(GateKind::Execution, &reordered)
+            .await
+            .unwrap();
+        assert_eq!(reused.decision, Decision::Allow);
+        assert_eq!(reused.layer, DecisionLayerEnum::Cache);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let other = profiled_call(read_only(), serde_json::json!({"city": "Bergen"}));
+        engine.evaluate(GateKind::Execution, &other).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn allows_for_tools_with_secrets_or_side_effects_are_never_reused() {
+        let (engine, calls) = allowing_engine();
+        let profiles = [
+            ToolProfile {
+                secrets: vec!["WEATHER_API_KEY".into()],
+                ..read_only()
+            },
+            ToolProfile {
+                idempotent: false,
+                ..read_only()
+            },
+            ToolProfile {
+                description: "Send a weather report to a channel".into(),
+                ..read_only()
+            },
+        ];
+
+        for profile in profiles {
+            let input = profiled_call(profile, serde_json::json!({"city": "Oslo"}));
+            for _ in 0..2 {
+                let result = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+                assert_eq!(result.layer, DecisionLayerEnum::LlmEvaluation);
+            }
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert!(engine.execution_cache().is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn zero_allow_ttl_disables_reuse() {
+        let (engine, calls) = allowing_engine();
+        let engine = engine.with_execution_allow_ttl(Duration::ZERO);
+        let input = profiled_call(read_only(), serde_json::json!({"city": "Oslo"}));
+
+        for _ in 0..2 {
+            engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    fn audit_line(age: Duration, gate: GateKind, input: &GateInput, decision: Decision) -> String {
+        serde_json::to_string(&AuditRecord {
+            timestamp_ms: now_ms() - age.as_millis() as u64,
+            gate,
+            subject: input.subject().to_string(),
+            input_hash: input.hash(),
+            source: input.source(),
+            layer: DecisionLayerEnum::LlmEvaluation,
+            decision,
+            reusable: input.allow_is_reusable(),
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn warm_from_audit_replays_recent_terminal_decisions() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        let recent = profiled_call(read_only(), serde_json::json!({"city": "Oslo"}));
+        let stale = make_execution_input("old_tool");
+        let asked = make_execution_input("asked_tool");
+        let lines = [
+            audit_line(
+                Duration::from_secs(60),
+                GateKind::Execution,
+                &recent,
+                Decision::Allow,
+            ),
+            audit_line(
+                Duration::from_secs(7200),
+                GateKind::Execution,
+                &stale,
+                Decision::Allow,
+            ),
+            audit_line(
+                Duration::from_secs(60),
+                GateKind::Execution,
+                &asked,
+                Decision::Ask {
+                    prompt: "p".into(),
+                    context: "c".into(),
+                },
+            ),
+            "{not json".to_string(),
+            r#"{"timestamp_ms": 1}"#.to_string(),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let engine = DecisionEngine::with_defaults();
+        let warmup = engine
+            .warm_from_audit(&path, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(
+            warmup,
+            AuditWarmup {
+                loaded: 1,
+                skipped: 2,
+                malformed: 2,
+            }
+        );
+
+        // Served from the cache instead of reaching the (stub) LLM layer.
+        let replayed = engine.evaluate(GateKind::Execution, &recent).await.unwrap();
+        assert_eq!(replayed.decision, Decision::Allow);
+        assert_eq!(replayed.layer, DecisionLayerEnum::Cache);
+
+        let fresh = engine.evaluate(GateKind::Execution, &stale).await.unwrap();
+        assert_ne!(fresh.layer, DecisionLayerEnum::Cache);
+    }
+
+    #[tokio::test]
+    async fn audit_log_round_trips_into_a_fresh_engine() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        let input = make_execution_input("shell_exec");
+
+        let engine = DecisionEngine::with_defaults().with_audit_log(AuditLog::open(&path).unwrap());
+        engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        let restarted = DecisionEngine::with_defaults();
+        let warmup = restarted
+            .warm_from_audit(&path, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(warmup.loaded, 1);
+        assert!(restarted.execution_cache().len().await > 0);
+    }
+
+    #[tokio::test]
+    async fn audit_records_name_the_creation_source() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        let engine = DecisionEngine::with_defaults().with_audit_log(AuditLog::open(&path).unwrap());
+        let GateInput::Creation { spec, .. } = make_creation_input("shell_exec", "Run commands")
+        else {
+            unreachable!()
+        };
+        let creation = GateInput::creation(spec, RequestSource::Cli);
+        engine
+            .evaluate(GateKind::Creation, &creation)
+            .await
+            .unwrap();
+        engine
+            .evaluate(GateKind::Execution, &make_execution_input("shell_exec"))
+            .await
+            .unwrap();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["source"], "cli");
+        assert!(lines[1].get("source").is_none());
+    }
+
+    #[tokio::test]
+    async fn warm_from_audit_reads_lines_written_before_status_tags() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        let allowed = make_execution_input("weather_lookup");
+        let denied = make_execution_input("unlisted_tool");
+        let line = |input: &GateInput, decision: &str| {
+            format!(
+                r#"{{"timestamp_ms":{},"gate":"execution","subject":"{}","input_hash":"{}","layer":"LlmEvaluation","decision":{decision}}}"#,
+                now_ms(),
+                input.subject(),
+                input.hash()
+            )
+        };
+        let lines = [
+            line(&allowed, r#""Allow""#),
+            line(&denied, r#"{"Deny":{"reason":"no"}}"#),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let engine = DecisionEngine::with_defaults();
+        let warmup = engine
+            .warm_from_audit(&path, Duration::from_secs(60))
+            .await
+            .unwrap();
+        // An Allow recorded before reusability was is never replayed.
+        assert_eq!(warmup.loaded, 1);
+        assert_eq!(warmup.skipped, 1);
+        let replayed = engine.evaluate(GateKind::Execution, &denied).await.unwrap();
+        assert_eq!(
+            replayed.decision,
+            Decision::Deny {
+                reason: "no".into()
+            }
+        );
+        assert_eq!(replayed.layer, DecisionLayerEnum::Cache);
+    }
+
+    #[tokio::test]
+    async fn restart_replays_only_reusable_allows_and_only_for_their_ttl() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        let read_only_call = profiled_call(read_only(), serde_json::json!({"city": "Oslo"}));
+        let secret_call = profiled_call(
+            ToolProfile {
+                secrets: vec!["WEATHER_API_KEY".into()],
+                ..read_only()
+            },
+            serde_json::json!({"city": "Bergen"}),
+        );
+
+        let (engine, _) = allowing_engine();
+        let engine = engine.with_audit_log(AuditLog::open(&path).unwrap());
+        for input in [&read_only_call, &secret_call] {
+            let result = engine.evaluate(GateKind::Execution, input).await.unwrap();
+            assert_eq!(result.decision, Decision::Allow);
+        }
+
+        let (restarted, calls) = allowing_engine();
+        let warmup = restarted
+            .warm_from_audit(&path, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(warmup.loaded, 1);
+        assert_eq!(warmup.skipped, 1);
+        let reused = restarted
+            .evaluate(GateKind::Execution, &read_only_call)
+            .await
+            .unwrap();
+        assert_eq!(reused.layer, DecisionLayerEnum::Cache);
+        let evaluated = restarted
+            .evaluate(GateKind::Execution, &secret_call)
+            .await
+            .unwrap();
+        assert_eq!(evaluated.layer, DecisionLayerEnum::LlmEvaluation);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // An Allow older than the execution Allow TTL has already expired.
+        let (restarted, _) = allowing_engine();
+        let restarted = restarted.with_execution_allow_ttl(Duration::from_secs(30));
+        let line = audit_line(
+            Duration::from_secs(60),
+            GateKind::Execution,
+            &read_only_call,
+            Decision::Allow,
+        );
+        std::fs::write(&path, line).unwrap();
+        let warmup = restarted
+            .warm_from_audit(&path, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(warmup.loaded, 0);
+        assert!(restarted.execution_cache().is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn warm_from_missing_audit_log_loads_nothing() {
+        let engine = DecisionEngine::with_defaults();
+        let warmup = engine
+            .warm_from_audit(
+                Path::new("/nonexistent/audit.jsonl"),
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+        assert_eq!(warmup, AuditWarmup::default());
+    }
+
+    fn weather_tool() -> KnownSpec {
+        KnownSpec::new("weather_forecast", "Get weather forecast data for a city")
+    }
+
+    fn similarity_outcome(result: &LayeredDecision) -> Option<LayerOutcome> {
+        result
+            .consulted_layers
+            .iter()
+            .find(|trace| trace.layer == DecisionLayerEnum::Similarity)
+            .map(|trace| trace.outcome)
+    }
+
+    #[tokio::test]
+    async fn removed_tool_is_no_longer_deferred_to() {
+        let engine = DecisionEngine::with_defaults();
+        let input = make_creation_input(
+            "city_weather_forecast",
+            "Get the weather forecast for a city",
+        );
+
+        engine.refresh_known_tools(vec![weather_tool()]);
+        let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
+        assert_eq!(
+            result.decision,
+            Decision::Defer {
+                target: DeferTarget::ExtendTool {
+                    tool_name: "weather_forecast".into(),
+                    suggested_features: vec![],
+                },
+            }
+        );
+        assert_eq!(result.layer, DecisionLayerEnum::Similarity);
+
+        // Refreshing replaces the known tools rather than adding to them.
+        engine.refresh_known_tools(vec![]);
+        let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
+        assert!(!matches!(result.decision, Decision::Defer { .. }));
+        assert_eq!(similarity_outcome(&result), Some(LayerOutcome::PassThrough));
+    }
+
+    #[tokio::test]
+    async fn defers_to_unloaded_or_same_name_tools_fall_through() {
+        let engine = DecisionEngine::with_defaults();
+        engine.refresh_known_tools(vec![]);
+        // A similarity layer that missed the removal.
+        engine
+            .creation_layers
+            .similarity
+            .replace_known_specs(vec![weather_tool()]);
+        let input = make_creation_input(
+            "city_weather_forecast",
+            "Get the weather forecast for a city",
+        );
+        let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
+        assert!(!matches!(result.decision, Decision::Defer { .. }));
+        assert_eq!(similarity_outcome(&result), Some(LayerOutcome::PassThrough));
+
+        // Loaded, but the request is for the tool itself.
+        engine.refresh_known_tools(vec![weather_tool()]);
+        let input = make_creation_input(
+            "weather_forecast",
+            "Get weather forecasts with hourly detail",
+        );
+        let result = engine.evaluate(GateKind::Creation, &input).await.unwrap();
+        assert!(!matches!(result.decision, Decision::Defer { .. }));
+        assert_eq!(similarity_outcome(&result), Some(LayerOutcome::PassThrough));
+    }
 }
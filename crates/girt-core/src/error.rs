@@ -1,3 +1,4 @@
+use serde::Serialize;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -23,3 +24,68 @@ pub enum DecisionError {
     #[error("serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 }
+
+/// A single field of a `CapabilitySpec` that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Dotted path to the field, e.g. `constraints.network[1]`.
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Every problem found in a `CapabilitySpec`, in field order.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid capability spec: {}", join_field_errors(.errors))]
+pub struct SpecValidationError {
+    pub errors: Vec<FieldError>,
+}
+
+fn join_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// A payload that is over one of the configured [`SizeLimits`](crate::limits::SizeLimits).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Error)]
+#[error("{setting} exceeded: {measured} {unit} (limit {limit})")]
+pub struct SizeLimitError {
+    /// The setting that was exceeded, e.g. `max_spec_bytes` under
+    /// `[security]`.
+    pub setting: &'static str,
+    pub measured: usize,
+    pub limit: usize,
+    /// `bytes` or `chars`.
+    pub unit: &'static str,
+}
+
+/// No directory could be found for girt's files (see [`crate::paths`]).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("cannot resolve a home directory for girt's files; set GIRT_HOME (or HOME)")]
+pub struct PathError;
+
+/// Fault injection settings that cannot be used (see [`crate::faults`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FaultInjectionError {
+    #[error(
+        "fault injection is configured but i_understand_this_breaks_tools is not set; refusing to break tool calls"
+    )]
+    NotAcknowledged,
+
+    #[error("fault injection rate must be between 0 and 1, got {0}")]
+    Rate(f64),
+
+    #[error("fault injection needs at least one fault kind")]
+    NoKinds,
+
+    #[error("{var} is not valid: {value:?}")]
+    Env { var: &'static str, value: String },
+}
@@ -0,0 +1,344 @@
+//! Fault injection, for testing how agents and the proxy cope with tools
+//! that misbehave.
+//!
+//! With `[runtime.fault_injection]` set (or `GIRT_FAULT_RATE`), the runtime
+//! replaces a random fraction of tool call results with a [`FaultKind`]:
+//! a timeout, a tool error, or well-formed JSON of the wrong shape. It is
+//! off by default, and stays off unless `i_understand_this_breaks_tools` is
+//! set in girt.toml. A fixed `seed` makes the faults of a run reproducible.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::error::FaultInjectionError;
+
+/// What an injected fault turns a tool call into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultKind {
+    /// The call runs into its time limit: it takes the tool's whole
+    /// timeout, then fails as a timed-out call.
+    Timeout,
+    /// A tool error that is not worth retrying.
+    Error,
+    /// Valid JSON in which every value has the wrong type. A call that
+    /// failed anyway fails with an error saying so.
+    Garbage,
+}
+
+impl FaultKind {
+    pub const ALL: [FaultKind; 3] = [Self::Timeout, Self::Error, Self::Garbage];
+}
+
+impl FromStr for FaultKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "timeout" => Ok(Self::Timeout),
+            "error" => Ok(Self::Error),
+            "garbage" => Ok(Self::Garbage),
+            _ => Err(()),
+        }
+    }
+}
+
+/// `[runtime.fault_injection]` in girt.toml.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FaultInjectionConfig {
+    /// Fraction of tool calls to break, 0 to 1. 0 disables injection.
+    #[serde(default)]
+    pub rate: f64,
+    /// Faults to pick from, uniformly.
+    #[serde(default = "all_kinds")]
+    pub kinds: Vec<FaultKind>,
+    /// Seed for which calls fail and how. Random per run when unset.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Must be set for any fault to be injected.
+    #[serde(default)]
+    pub i_understand_this_breaks_tools: bool,
+}
+
+fn all_kinds() -> Vec<FaultKind> {
+    FaultKind::ALL.to_vec()
+}
+
+impl Default for FaultInjectionConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            kinds: all_kinds(),
+            seed: None,
+            i_understand_this_breaks_tools: false,
+        }
+    }
+}
+
+impl FaultInjectionConfig {
+    /// These settings with `GIRT_FAULT_RATE`, `GIRT_FAULT_KINDS` (comma
+    /// separated) and `GIRT_FAULT_SEED` applied over them, read through
+    /// `var`. The guard flag can only be set in girt.toml.
+    pub fn with_env(
+        mut self,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, FaultInjectionError> {
+        let invalid = |var: &'static str, value: String| FaultInjectionError::Env { var, value };
+        if let Some(rate) = var("GIRT_FAULT_RATE") {
+            self.rate = rate
+                .trim()
+                .parse()
+                .map_err(|_| invalid("GIRT_FAULT_RATE", rate))?;
+        }
+        if let Some(kinds) = var("GIRT_FAULT_KINDS") {
+            self.kinds = kinds
+                .split(',')
+                .map(FaultKind::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|()| invalid("GIRT_FAULT_KINDS", kinds))?;
+        }
+        if let Some(seed) = var("GIRT_FAULT_SEED") {
+            self.seed = Some(
+                seed.trim()
+                    .parse()
+                    .map_err(|_| invalid("GIRT_FAULT_SEED", seed))?,
+            );
+        }
+        Ok(self)
+    }
+
+    /// The injector these settings describe, or `None` with a rate of 0.
+    /// Refuses to build one unless `i_understand_this_breaks_tools` is set.
+    pub fn injector(&self) -> Result<Option<FaultInjector>, FaultInjectionError> {
+        if !(0.0..=1.0).contains(&self.rate) {
+            return Err(FaultInjectionError::Rate(self.rate));
+        }
+        if self.rate == 0.0 {
+            return Ok(None);
+        }
+        if !self.i_understand_this_breaks_tools {
+            return Err(FaultInjectionError::NotAcknowledged);
+        }
+        if self.kinds.is_empty() {
+            return Err(FaultInjectionError::NoKinds);
+        }
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Ok(Some(FaultInjector::new(
+            self.rate,
+            self.kinds.clone(),
+            seed,
+        )))
+    }
+}
+
+/// Decides which tool calls to break. Draws come from one seeded sequence,
+/// so a run with the same seed and the same calls in the same order breaks
+/// the same calls the same way.
+#[derive(Debug)]
+pub struct FaultInjector {
+    rate: f64,
+    kinds: Vec<FaultKind>,
+    seed: u64,
+    state: Mutex<u64>,
+}
+
+impl FaultInjector {
+    pub fn new(rate: f64, kinds: Vec<FaultKind>, seed: u64) -> Self {
+        Self {
+            rate,
+            kinds,
+            seed,
+            state: Mutex::new(seed),
+        }
+    }
+
+    /// The fault to inject into the next call, if any.
+    pub fn next_fault(&self) -> Option<FaultKind> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let draw = (splitmix64(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+        if draw >= self.rate || self.kinds.is_empty() {
+            return None;
+        }
+        let pick = splitmix64(&mut state) % self.kinds.len() as u64;
+        Some(self.kinds[pick as usize])
+    }
+
+    /// The settings in use, for `girt_info`.
+    pub fn describe(&self) -> serde_json::Value {
+        serde_json::json!({
+            "rate": self.rate,
+            "kinds": self.kinds,
+            "seed": self.seed,
+        })
+    }
+}
+
+impl fmt::Display for FaultInjector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<_> = self
+            .kinds
+            .iter()
+            .map(|k| format!("{k:?}").to_lowercase())
+            .collect();
+        write!(
+            f,
+            "{} on {}% of tool calls (seed {})",
+            kinds.join(", "),
+            self.rate * 100.0,
+            self.seed
+        )
+    }
+}
+
+/// `value` with every leaf replaced by one of another type: strings become
+/// numbers, numbers strings, booleans null, null `false`. Arrays and objects
+/// keep their shape, so the result still parses as the expected container.
+pub fn garble(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Null => Value::Bool(false),
+        Value::Bool(_) => Value::Null,
+        Value::Number(n) => Value::String(n.to_string()),
+        Value::String(s) => Value::from(s.len()),
+        Value::Array(items) => Value::Array(items.iter().map(garble).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), garble(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(rate: f64) -> FaultInjectionConfig {
+        FaultInjectionConfig {
+            rate,
+            seed: Some(7),
+            i_understand_this_breaks_tools: true,
+            ..FaultInjectionConfig::default()
+        }
+    }
+
+    #[test]
+    fn faults_follow_the_rate_and_spread_over_kinds() {
+        let injector = config(0.2).injector().unwrap().unwrap();
+        let mut counts: HashMap<FaultKind, usize> = HashMap::new();
+        for _ in 0..10_000 {
+            if let Some(kind) = injector.next_fault() {
+                *counts.entry(kind).or_default() += 1;
+            }
+        }
+        let total: usize = counts.values().sum();
+        assert!((1800..=2200).contains(&total), "{total} faults");
+        for kind in FaultKind::ALL {
+            let n = counts.get(&kind).copied().unwrap_or_default();
+            assert!((500..=850).contains(&n), "{kind:?}: {n}");
+        }
+
+        let never = config(0.0).injector().unwrap();
+        assert!(never.is_none());
+        let always = config(1.0).injector().unwrap().unwrap();
+        assert!((0..100).all(|_| always.next_fault().is_some()));
+    }
+
+    #[test]
+    fn same_seed_injects_the_same_faults() {
+        let draws = |seed| {
+            let injector = FaultInjector::new(0.5, FaultKind::ALL.to_vec(), seed);
+            (0..50).map(|_| injector.next_fault()).collect::<Vec<_>>()
+        };
+        assert_eq!(draws(42), draws(42));
+        assert_ne!(draws(42), draws(43));
+    }
+
+    #[test]
+    fn injection_needs_the_guard_flag() {
+        let mut unguarded = config(0.2);
+        unguarded.i_understand_this_breaks_tools = false;
+        assert_eq!(
+            unguarded.injector().unwrap_err(),
+            FaultInjectionError::NotAcknowledged
+        );
+        // Off is off, flag or not.
+        unguarded.rate = 0.0;
+        assert!(unguarded.injector().unwrap().is_none());
+
+        assert_eq!(
+            config(1.5).injector().unwrap_err(),
+            FaultInjectionError::Rate(1.5)
+        );
+        let mut no_kinds = config(0.2);
+        no_kinds.kinds.clear();
+        assert_eq!(
+            no_kinds.injector().unwrap_err(),
+            FaultInjectionError::NoKinds
+        );
+    }
+
+    #[test]
+    fn env_overrides_rate_kinds_and_seed_but_not_the_guard() {
+        let env = HashMap::from([
+            ("GIRT_FAULT_RATE", "0.25"),
+            ("GIRT_FAULT_KINDS", "timeout, garbage"),
+            ("GIRT_FAULT_SEED", "9"),
+        ]);
+        let lookup = |key: &str| env.get(key).map(|v| v.to_string());
+        let config = FaultInjectionConfig::default().with_env(lookup).unwrap();
+        assert_eq!(config.rate, 0.25);
+        assert_eq!(config.kinds, [FaultKind::Timeout, FaultKind::Garbage]);
+        assert_eq!(config.seed, Some(9));
+        assert!(config.injector().is_err());
+
+        let bad = FaultInjectionConfig::default()
+            .with_env(|key| (key == "GIRT_FAULT_KINDS").then(|| "timeout,slow".to_string()));
+        assert!(matches!(
+            bad,
+            Err(FaultInjectionError::Env {
+                var: "GIRT_FAULT_KINDS",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn garbage_keeps_the_shape_but_not_the_types() {
+        let output = serde_json::json!({
+            "title": "hello",
+            "count": 3,
+            "ok": true,
+            "tags": ["a"],
+            "next": null
+        });
+        assert_eq!(
+            garble(&output),
+            serde_json::json!({
+                "title": 5,
+                "count": "3",
+                "ok": null,
+                "tags": [1],
+                "next": false
+            })
+        );
+    }
+}
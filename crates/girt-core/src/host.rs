@@ -0,0 +1,92 @@
+//! What the runtime links for tools to import.
+//!
+//! girt-runtime describes the interfaces it provides as
+//! [`HostCapabilities`]; the pipeline renders that description into the
+//! Engineer's prompt and the worlds it scaffolds, so generated code is
+//! offered exactly the imports a loaded component can resolve.
+
+use serde::{Deserialize, Serialize};
+
+/// Interfaces the runtime links into every component's imports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCapabilities {
+    pub imports: Vec<HostImport>,
+}
+
+/// One importable interface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostImport {
+    /// Name a world imports it by, e.g. `girt:host/utils@0.1.0`.
+    pub name: String,
+    /// One line on what the interface is for.
+    pub doc: String,
+    pub functions: Vec<HostFunction>,
+}
+
+/// A function of a [`HostImport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostFunction {
+    /// WIT name, e.g. `now-ms`.
+    pub name: String,
+    /// WIT signature, e.g. `func() -> u64`.
+    pub signature: String,
+    /// One line on what a call does.
+    pub doc: String,
+    /// Whether the tool's policy limits what a call may do (hosts for
+    /// outgoing HTTP, say).
+    pub policy_gated: bool,
+}
+
+impl HostCapabilities {
+    /// The import named `name`, if linked.
+    pub fn get(&self, name: &str) -> Option<&HostImport> {
+        self.imports.iter().find(|import| import.name == name)
+    }
+
+    /// These capabilities without the import named `name`.
+    pub fn without(mut self, name: &str) -> Self {
+        self.imports.retain(|import| import.name != name);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty()
+    }
+}
+
+impl HostImport {
+    pub fn new(name: impl Into<String>, doc: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            doc: doc.into(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Add a function anyone may call.
+    pub fn function(mut self, name: &str, signature: &str, doc: &str) -> Self {
+        self.functions.push(HostFunction {
+            name: name.into(),
+            signature: signature.into(),
+            doc: doc.into(),
+            policy_gated: false,
+        });
+        self
+    }
+
+    /// Add a function the tool's policy limits.
+    pub fn gated_function(mut self, name: &str, signature: &str, doc: &str) -> Self {
+        self.functions.push(HostFunction {
+            name: name.into(),
+            signature: signature.into(),
+            doc: doc.into(),
+            policy_gated: true,
+        });
+        self
+    }
+
+    /// The `import` line a world adds to use the interface.
+    pub fn import_line(&self) -> String {
+        format!("import {};", self.name)
+    }
+}
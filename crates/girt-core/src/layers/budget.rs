@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::decision::Decision;
+use crate::error::DecisionError;
+use crate::layers::DecisionLayer;
+use crate::spec::{CapabilitySpec, GateInput, RequestSource};
+
+/// Constraint budget layer — a hard ceiling on how much one capability spec
+/// may ask for, however it is worded.
+///
+/// Runs first in the Creation Gate, so no allow pattern or cached Allow can
+/// let an oversized spec through. Its denials depend on the request source
+/// as well as the spec, so the engine never caches them.
+///
+/// This layer only applies to Creation Gate (not Execution Gate).
+pub struct ConstraintBudgetLayer {
+    budget: ConstraintBudget,
+}
+
+/// Most constraints one spec may request, configured in girt.toml as
+/// `[security.budget]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintBudget {
+    #[serde(default = "default_max_network_hosts")]
+    pub max_network_hosts: usize,
+    #[serde(default = "default_max_storage_paths")]
+    pub max_storage_paths: usize,
+    #[serde(default = "default_max_secrets")]
+    pub max_secrets: usize,
+    /// Deny any spec with secrets unless an operator asked for it.
+    #[serde(default = "default_secrets_require_operator")]
+    pub secrets_require_operator: bool,
+    /// Limits for requests from one source, replacing those above. Keyed by
+    /// source name, e.g. `[security.budget.overrides.operator]`.
+    #[serde(default)]
+    pub overrides: HashMap<RequestSource, BudgetOverride>,
+}
+
+/// The limits that apply to one request source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BudgetLimits {
+    pub max_network_hosts: usize,
+    pub max_storage_paths: usize,
+    pub max_secrets: usize,
+}
+
+/// Per-source limits. Unset limits keep the budget's own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetOverride {
+    pub max_network_hosts: Option<usize>,
+    pub max_storage_paths: Option<usize>,
+    pub max_secrets: Option<usize>,
+}
+
+impl Default for ConstraintBudget {
+    fn default() -> Self {
+        Self {
+            max_network_hosts: default_max_network_hosts(),
+            max_storage_paths: default_max_storage_paths(),
+            max_secrets: default_max_secrets(),
+            secrets_require_operator: default_secrets_require_operator(),
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+fn default_max_network_hosts() -> usize {
+    8
+}
+
+fn default_max_storage_paths() -> usize {
+    4
+}
+
+fn default_max_secrets() -> usize {
+    4
+}
+
+fn default_secrets_require_operator() -> bool {
+    true
+}
+
+impl ConstraintBudget {
+    /// The limits for requests from `source`: its override where set, and
+    /// the budget's own otherwise.
+    pub fn limits_for(&self, source: RequestSource) -> BudgetLimits {
+        let limits = self.overrides.get(&source).copied().unwrap_or_default();
+        BudgetLimits {
+            max_network_hosts: limits.max_network_hosts.unwrap_or(self.max_network_hosts),
+            max_storage_paths: limits.max_storage_paths.unwrap_or(self.max_storage_paths),
+            max_secrets: limits.max_secrets.unwrap_or(self.max_secrets),
+        }
+    }
+
+    /// Why `spec` requested by `source` is over budget, if it is.
+    pub fn exceeded_by(&self, spec: &CapabilitySpec, source: RequestSource) -> Option<String> {
+        let constraints = &spec.constraints;
+        if self.secrets_require_operator
+            && source != RequestSource::Operator
+            && !constraints.secrets.is_empty()
+        {
+            return Some(format!(
+                "secrets budget exceeded: {} secret(s) requested, but only operator requests may use secrets",
+                constraints.secrets.len()
+            ));
+        }
+
+        let limits = self.limits_for(source);
+        let dimensions = [
+            (
+                "network",
+                "network host(s)",
+                constraints.network.len(),
+                limits.max_network_hosts,
+            ),
+            (
+                "storage",
+                "storage path(s)",
+                constraints.storage.len(),
+                limits.max_storage_paths,
+            ),
+            (
+                "secrets",
+                "secret(s)",
+                constraints.secrets.len(),
+                limits.max_secrets,
+            ),
+        ];
+        dimensions
+            .into_iter()
+            .find(|(_, _, requested, max)| requested > max)
+            .map(|(budget, what, requested, max)| {
+                format!(
+                    "{budget} budget exceeded: {requested} {what} requested, at most {max} allowed for {} requests",
+                    source.as_str()
+                )
+            })
+    }
+}
+
+impl ConstraintBudgetLayer {
+    pub fn new(budget: ConstraintBudget) -> Self {
+        Self { budget }
+    }
+
+    pub fn budget(&self) -> &ConstraintBudget {
+        &self.budget
+    }
+}
+
+impl DecisionLayer for ConstraintBudgetLayer {
+    fn name(&self) -> &str {
+        "constraint_budget"
+    }
+
+    fn evaluate<'a>(
+        &'a self,
+        input: &'a GateInput,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>,
+    > {
+        Box::pin(async move {
+            let GateInput::Creation { spec, source } = input else {
+                return Ok(None);
+            };
+
+            Ok(self.budget.exceeded_by(spec, *source).map(|reason| {
+                tracing::info!(tool = %spec.name, ?source, %reason, "Constraint budget: DENY");
+                Decision::Deny { reason }
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{CapabilityConstraints, ExecutionRequest};
+
+    fn count(prefix: &str, n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("{prefix}{i}")).collect()
+    }
+
+    fn input(network: usize, storage: usize, secrets: usize, source: RequestSource) -> GateInput {
+        GateInput::creation(
+            CapabilitySpec {
+                name: "sync_everything".into(),
+                description: "Sync data between services".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints {
+                    network: count("api.example", network)
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                    storage: count("/data/", storage),
+                    secrets: count("TOKEN_", secrets),
+                },
+            },
+            source,
+        )
+    }
+
+    fn budget(network: usize, storage: usize, secrets: usize) -> ConstraintBudget {
+        ConstraintBudget {
+            max_network_hosts: network,
+            max_storage_paths: storage,
+            max_secrets: secrets,
+            ..Default::default()
+        }
+    }
+
+    async fn denial(layer: &ConstraintBudgetLayer, input: &GateInput) -> Option<String> {
+        match layer.evaluate(input).await.unwrap() {
+            Some(Decision::Deny { reason }) => Some(reason),
+            None => None,
+            Some(other) => panic!("unexpected decision {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn specs_within_budget_pass_through() {
+        let layer = ConstraintBudgetLayer::new(budget(2, 2, 2));
+        assert_eq!(
+            denial(&layer, &input(2, 2, 0, RequestSource::Agent)).await,
+            None
+        );
+        assert_eq!(
+            denial(&layer, &input(2, 2, 2, RequestSource::Operator)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn too_many_network_hosts_are_denied() {
+        let layer = ConstraintBudgetLayer::new(budget(2, 2, 2));
+        let reason = denial(&layer, &input(3, 0, 0, RequestSource::Agent))
+            .await
+            .unwrap();
+        assert_eq!(
+            reason,
+            "network budget exceeded: 3 network host(s) requested, at most 2 allowed for agent requests"
+        );
+    }
+
+    #[tokio::test]
+    async fn too_many_storage_paths_are_denied() {
+        let layer = ConstraintBudgetLayer::new(budget(2, 2, 2));
+        let reason = denial(&layer, &input(0, 3, 0, RequestSource::Cli))
+            .await
+            .unwrap();
+        assert!(reason.starts_with("storage budget exceeded: 3"), "{reason}");
+    }
+
+    #[tokio::test]
+    async fn too_many_secrets_are_denied_even_for_operators() {
+        let layer = ConstraintBudgetLayer::new(budget(2, 2, 2));
+        let reason = denial(&layer, &input(0, 0, 3, RequestSource::Operator))
+            .await
+            .unwrap();
+        assert!(reason.starts_with("secrets budget exceeded: 3"), "{reason}");
+    }
+
+    #[tokio::test]
+    async fn only_operators_may_request_secrets() {
+        let layer = ConstraintBudgetLayer::new(budget(2, 2, 2));
+        for source in [
+            RequestSource::Agent,
+            RequestSource::Cli,
+            RequestSource::Hook,
+        ] {
+            let reason = denial(&layer, &input(0, 0, 1, source)).await.unwrap();
+            assert!(reason.contains("only operator requests"), "{reason}");
+        }
+
+        let layer = ConstraintBudgetLayer::new(ConstraintBudget {
+            secrets_require_operator: false,
+            ..budget(2, 2, 2)
+        });
+        assert_eq!(
+            denial(&layer, &input(0, 0, 1, RequestSource::Agent)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn overrides_apply_to_their_source_only() {
+        let mut budget = budget(1, 1, 1);
+        budget.overrides.insert(
+            RequestSource::Operator,
+            BudgetOverride {
+                max_network_hosts: Some(5),
+                ..Default::default()
+            },
+        );
+        let layer = ConstraintBudgetLayer::new(budget);
+        assert_eq!(
+            layer.budget().limits_for(RequestSource::Operator),
+            BudgetLimits {
+                max_network_hosts: 5,
+                max_storage_paths: 1,
+                max_secrets: 1,
+            }
+        );
+
+        assert_eq!(
+            denial(&layer, &input(5, 1, 0, RequestSource::Operator)).await,
+            None
+        );
+        // Limits the override leaves unset are the budget's own.
+        assert!(
+            denial(&layer, &input(5, 2, 0, RequestSource::Operator))
+                .await
+                .is_some()
+        );
+        assert!(
+            denial(&layer, &input(5, 0, 0, RequestSource::Agent))
+                .await
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn execution_requests_pass_through() {
+        let layer = ConstraintBudgetLayer::new(budget(0, 0, 0));
+        let input = GateInput::Execution(ExecutionRequest {
+            tool_name: "sync_everything".into(),
+            arguments: serde_json::Value::Null,
+            tool: None,
+        });
+        assert_eq!(denial(&layer, &input).await, None);
+    }
+}
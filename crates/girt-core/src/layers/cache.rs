@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 use crate::decision::Decision;
@@ -12,7 +13,21 @@ use crate::spec::GateInput;
 /// A previously-allowed spec skips to the build pipeline.
 /// DEFER decisions are cached with a pointer to the deferred-to tool.
 pub struct CacheLayer {
-    entries: RwLock<HashMap<String, Decision>>,
+    entries: RwLock<HashMap<String, CacheEntry>>,
+}
+
+struct CacheEntry {
+    decision: Decision,
+    /// Tool the decision is about (see [`GateInput::subject`]); empty when unknown.
+    subject: String,
+    /// After this the entry is ignored; `None` never expires.
+    expires_at: Option<Instant>,
+}
+
+impl CacheEntry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.is_none_or(|at| now < at)
+    }
 }
 
 impl CacheLayer {
@@ -24,8 +39,44 @@ impl CacheLayer {
 
     /// Store a decision in the cache.
     pub async fn store(&self, hash: String, decision: Decision) {
+        self.store_for("", hash, decision).await;
+    }
+
+    /// Store a decision tagged with the tool it concerns, so it can later be
+    /// dropped with [`invalidate_subject`](Self::invalidate_subject).
+    pub async fn store_for(&self, subject: &str, hash: String, decision: Decision) {
+        self.insert(subject, hash, decision, None).await;
+    }
+
+    /// Like [`store_for`](Self::store_for), but the decision is only served
+    /// for `ttl`.
+    pub async fn store_expiring(
+        &self,
+        subject: &str,
+        hash: String,
+        decision: Decision,
+        ttl: Duration,
+    ) {
+        self.insert(subject, hash, decision, Some(Instant::now() + ttl))
+            .await;
+    }
+
+    async fn insert(
+        &self,
+        subject: &str,
+        hash: String,
+        decision: Decision,
+        expires_at: Option<Instant>,
+    ) {
         let mut entries = self.entries.write().await;
-        entries.insert(hash, decision);
+        entries.insert(
+            hash,
+            CacheEntry {
+                decision,
+                subject: subject.to_string(),
+                expires_at,
+            },
+        );
     }
 
     /// Remove a decision from the cache.
@@ -34,6 +85,19 @@ impl CacheLayer {
         entries.remove(hash);
     }
 
+    /// Remove every decision about `subject`. Returns how many were removed.
+    pub async fn invalidate_subject(&self, subject: &str) -> usize {
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.subject != subject);
+        before - entries.len()
+    }
+
+    /// Remove every cached decision.
+    pub async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+
     /// Number of cached entries.
     pub async fn len(&self) -> usize {
         let entries = self.entries.read().await;
@@ -68,13 +132,15 @@ impl DecisionLayer for CacheLayer {
             let hash = input.hash();
             let entries = self.entries.read().await;
 
-            if let Some(cached) = entries.get(&hash) {
+            if let Some(cached) = entries.get(&hash)
+                && cached.is_live(Instant::now())
+            {
                 tracing::info!(
                     hash = %hash,
-                    decision = ?cached,
+                    decision = ?cached.decision,
                     "Cache hit"
                 );
-                return Ok(Some(cached.clone()));
+                return Ok(Some(cached.decision.clone()));
             }
 
             Ok(None)
@@ -85,7 +151,7 @@ impl DecisionLayer for CacheLayer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec};
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, RequestSource};
 
     fn make_spec(name: &str) -> (GateInput, String) {
         let spec = CapabilitySpec {
@@ -96,7 +162,7 @@ mod tests {
             constraints: CapabilityConstraints::default(),
         };
         let hash = spec.spec_hash();
-        (GateInput::Creation(spec), hash)
+        (GateInput::creation(spec, RequestSource::Agent), hash)
     }
 
     #[tokio::test]
@@ -140,4 +206,46 @@ mod tests {
         let result = cache.evaluate(&input).await.unwrap();
         assert!(result.is_none());
     }
+
+    #[tokio::test]
+    async fn expiring_entries_are_ignored_after_their_ttl() {
+        let cache = CacheLayer::new();
+        let (fresh, fresh_hash) = make_spec("fresh_tool");
+        let (expired, expired_hash) = make_spec("expired_tool");
+
+        cache
+            .store_expiring(
+                "fresh_tool",
+                fresh_hash,
+                Decision::Allow,
+                Duration::from_secs(60),
+            )
+            .await;
+        cache
+            .store_expiring(
+                "expired_tool",
+                expired_hash,
+                Decision::Allow,
+                Duration::ZERO,
+            )
+            .await;
+
+        assert!(cache.evaluate(&fresh).await.unwrap().is_some());
+        assert!(cache.evaluate(&expired).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn invalidate_subject_drops_only_matching_entries() {
+        let cache = CacheLayer::new();
+        let (input_a, hash_a) = make_spec("tool_a");
+        let (input_b, hash_b) = make_spec("tool_b");
+
+        cache.store_for("tool_a", hash_a, Decision::Allow).await;
+        cache.store_for("tool_b", hash_b, Decision::Allow).await;
+
+        assert_eq!(cache.invalidate_subject("tool_a").await, 1);
+        assert!(cache.evaluate(&input_a).await.unwrap().is_none());
+        assert!(cache.evaluate(&input_b).await.unwrap().is_some());
+        assert_eq!(cache.invalidate_subject("tool_a").await, 0);
+    }
 }
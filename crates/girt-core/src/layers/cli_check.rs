@@ -1,3 +1,8 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
 use crate::decision::{Decision, DeferTarget};
 use crate::error::DecisionError;
 use crate::layers::DecisionLayer;
@@ -6,31 +11,68 @@ use crate::spec::GateInput;
 /// CLI/native check layer — checks if a well-known CLI utility already handles
 /// the requested capability better than a WASM tool.
 ///
+/// Only utilities whose binary is on `PATH` are offered. The lookup happens
+/// once, when the layer is built, so installing a tool takes a restart.
+///
 /// This layer only applies to Creation Gate (not Execution Gate).
 pub struct CliCheckLayer {
-    known_utilities: Vec<CliUtility>,
+    /// Installed utilities in match order, with where each binary was found.
+    installed: Vec<(CliUtility, PathBuf)>,
 }
 
-#[derive(Debug, Clone)]
+/// A CLI utility the Creation Gate can defer to, configured in girt.toml as
+/// `[[cli_check.utilities]]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CliUtility {
-    pub name: String,
+    /// Executable name looked up on `PATH`.
+    pub binary: String,
+    #[serde(default)]
     pub description: String,
-    /// Keywords that trigger a match against this utility.
+    /// Keywords that trigger a match against this utility. A keyword must
+    /// appear as a whole word in the spec's name or description.
     pub keywords: Vec<String>,
+    /// A typical command line, shown to the agent.
+    #[serde(default)]
+    pub example_invocation: String,
 }
 
 impl CliCheckLayer {
+    /// Keep the `utilities` installed on this process's `PATH`.
     pub fn new(utilities: Vec<CliUtility>) -> Self {
-        Self {
-            known_utilities: utilities,
-        }
+        Self::with_search_path(utilities, std::env::var_os("PATH").unwrap_or_default())
+    }
+
+    /// Keep the `utilities` found in `search_path`, a `PATH`-style list of
+    /// directories.
+    pub fn with_search_path(utilities: Vec<CliUtility>, search_path: impl AsRef<OsStr>) -> Self {
+        let dirs: Vec<PathBuf> = std::env::split_paths(search_path.as_ref()).collect();
+        let installed = utilities
+            .into_iter()
+            .filter_map(|utility| match find_binary(&utility.binary, &dirs) {
+                Some(path) => Some((utility, path)),
+                None => {
+                    tracing::debug!(binary = %utility.binary, "CLI utility not installed");
+                    None
+                }
+            })
+            .collect();
+        Self { installed }
     }
 
     /// Create a layer with a default list of well-known CLI utilities.
     pub fn with_defaults() -> Self {
-        Self {
-            known_utilities: default_utilities(),
-        }
+        Self::new(default_utilities())
+    }
+
+    /// The defaults plus `extra`, which are matched first. An extra utility
+    /// replaces a default with the same binary.
+    pub fn with_extra_utilities(extra: Vec<CliUtility>) -> Self {
+        Self::new(merge_utilities(extra))
+    }
+
+    /// Utilities whose binary was found, in match order.
+    pub fn installed(&self) -> impl Iterator<Item = &CliUtility> {
+        self.installed.iter().map(|(utility, _)| utility)
     }
 }
 
@@ -47,28 +89,30 @@ impl DecisionLayer for CliCheckLayer {
     > {
         Box::pin(async move {
             let spec = match input {
-                GateInput::Creation(spec) => spec,
+                GateInput::Creation { spec, .. } => spec,
                 GateInput::Execution(_) => return Ok(None),
             };
 
             let name_lower = spec.name.to_lowercase();
             let desc_lower = spec.description.to_lowercase();
 
-            for utility in &self.known_utilities {
+            for (utility, path) in &self.installed {
                 let matched = utility.keywords.iter().any(|kw| {
                     let kw_lower = kw.to_lowercase();
-                    name_lower.contains(&kw_lower) || desc_lower.contains(&kw_lower)
+                    contains_word(&name_lower, &kw_lower) || contains_word(&desc_lower, &kw_lower)
                 });
 
                 if matched {
                     tracing::info!(
-                        utility = %utility.name,
+                        binary = %utility.binary,
+                        path = %path.display(),
                         "CLI utility match found: DEFER"
                     );
                     return Ok(Some(Decision::Defer {
-                        target: DeferTarget::CliUtility {
-                            name: utility.name.clone(),
+                        target: DeferTarget::UseCli {
+                            binary: utility.binary.clone(),
                             description: utility.description.clone(),
+                            example_invocation: utility.example_invocation.clone(),
                         },
                     }));
                 }
@@ -79,99 +123,247 @@ impl DecisionLayer for CliCheckLayer {
     }
 }
 
-fn default_utilities() -> Vec<CliUtility> {
+/// Whether `keyword` occurs in `text` bounded by non-alphanumerics, so `sed`
+/// matches `sed_replace` and "run sed" but not "parsed".
+fn contains_word(text: &str, keyword: &str) -> bool {
+    if keyword.is_empty() {
+        return false;
+    }
+    text.match_indices(keyword).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + keyword.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// First executable named `binary` in `dirs`.
+fn find_binary(binary: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(binary))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+/// `extra` followed by the defaults it does not override.
+fn merge_utilities(extra: Vec<CliUtility>) -> Vec<CliUtility> {
+    let defaults: Vec<CliUtility> = default_utilities()
+        .into_iter()
+        .filter(|d| !extra.iter().any(|e| e.binary == d.binary))
+        .collect();
+    extra.into_iter().chain(defaults).collect()
+}
+
+/// The utilities every layer built by [`CliCheckLayer::with_defaults`] knows.
+pub fn default_utilities() -> Vec<CliUtility> {
     vec![
         CliUtility {
-            name: "jq".into(),
+            binary: "jq".into(),
             description: "Command-line JSON processor".into(),
             keywords: vec!["jq".into(), "json_query".into(), "json_filter".into()],
+            example_invocation: "jq '.items[] | .name' data.json".into(),
         },
         CliUtility {
-            name: "curl".into(),
+            binary: "curl".into(),
             description: "Transfer data with URLs".into(),
-            keywords: vec!["curl".into()],
+            keywords: vec!["curl".into(), "http_fetch".into(), "http_get".into()],
+            example_invocation: "curl -fsSL https://example.com/api".into(),
         },
         CliUtility {
-            name: "ripgrep".into(),
-            description: "Recursively search directories for a regex pattern".into(),
+            binary: "gh".into(),
+            description: "GitHub CLI: issues, pull requests, releases, and API calls".into(),
+            keywords: vec!["gh".into(), "github".into()],
+            example_invocation: "gh issue list --repo owner/name --state open".into(),
+        },
+        CliUtility {
+            binary: "rg".into(),
+            description: "ripgrep: recursively search directories for a regex pattern".into(),
             keywords: vec!["ripgrep".into(), "rg".into()],
+            example_invocation: "rg --json 'pattern' path/".into(),
         },
         CliUtility {
-            name: "sed".into(),
+            binary: "sed".into(),
             description: "Stream editor for filtering and transforming text".into(),
             keywords: vec!["sed".into(), "stream_edit".into()],
+            example_invocation: "sed 's/old/new/g' input.txt".into(),
         },
         CliUtility {
-            name: "awk".into(),
+            binary: "awk".into(),
             description: "Pattern scanning and processing language".into(),
             keywords: vec!["awk".into()],
+            example_invocation: "awk -F, '{ print $2 }' data.csv".into(),
         },
         CliUtility {
-            name: "git".into(),
+            binary: "git".into(),
             description: "Distributed version control system".into(),
             keywords: vec!["git_clone".into(), "git_commit".into(), "git_push".into()],
+            example_invocation: "git clone https://github.com/owner/name".into(),
         },
     ]
 }
 
+/// A directory of stub executables named `binaries`, for use as `PATH`.
+#[cfg(all(test, unix))]
+pub(crate) fn stub_bin_dir(binaries: &[&str]) -> tempfile::TempDir {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    for binary in binaries {
+        let path = dir.path().join(binary);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    dir
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec};
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, RequestSource};
 
     fn make_spec(name: &str, desc: &str) -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: name.into(),
-            description: desc.into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: name.into(),
+                description: desc.into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
+    /// A layer over the default utilities that sees nothing installed.
+    fn nothing_installed() -> CliCheckLayer {
+        CliCheckLayer::with_search_path(default_utilities(), "")
+    }
+
+    #[cfg(unix)]
     #[tokio::test]
-    async fn defers_to_jq_for_json_query() {
-        let layer = CliCheckLayer::with_defaults();
+    async fn defers_to_installed_jq_for_json_query() {
+        let bin = stub_bin_dir(&["jq"]);
+        let layer = CliCheckLayer::with_search_path(default_utilities(), bin.path());
         let input = make_spec("json_query", "Query JSON documents");
 
         let result = layer.evaluate(&input).await.unwrap();
         match result {
             Some(Decision::Defer {
-                target: DeferTarget::CliUtility { name, .. },
-            }) => assert_eq!(name, "jq"),
+                target:
+                    DeferTarget::UseCli {
+                        binary,
+                        example_invocation,
+                        ..
+                    },
+            }) => {
+                assert_eq!(binary, "jq");
+                assert!(example_invocation.starts_with("jq "));
+            }
             other => panic!("Expected Defer to jq, got {other:?}"),
         }
     }
 
     #[tokio::test]
-    async fn defers_to_ripgrep() {
-        let layer = CliCheckLayer::with_defaults();
-        let input = make_spec("ripgrep_search", "Search files with ripgrep");
+    async fn missing_binary_never_defers() {
+        let input = make_spec("json_query", "Query JSON documents");
+        assert!(
+            nothing_installed()
+                .evaluate(&input)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(nothing_installed().installed().count(), 0);
+    }
 
-        let result = layer.evaluate(&input).await.unwrap();
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn only_installed_candidates_are_offered() {
+        // gh is installed, curl is not; a non-executable file does not count.
+        let bin = stub_bin_dir(&["gh"]);
+        std::fs::write(bin.path().join("curl"), "not executable").unwrap();
+        let layer = CliCheckLayer::with_search_path(default_utilities(), bin.path());
+
+        let installed: Vec<&str> = layer.installed().map(|u| u.binary.as_str()).collect();
+        assert_eq!(installed, vec!["gh"]);
+
+        let github = make_spec("github_issues", "Fetch GitHub issues");
         assert!(matches!(
-            result,
+            layer.evaluate(&github).await.unwrap(),
             Some(Decision::Defer {
-                target: DeferTarget::CliUtility { .. }
-            })
+                target: DeferTarget::UseCli { binary, .. }
+            }) if binary == "gh"
         ));
+        let fetch = make_spec("http_fetch", "Fetch a URL");
+        assert!(layer.evaluate(&fetch).await.unwrap().is_none());
     }
 
+    #[cfg(unix)]
     #[tokio::test]
-    async fn passes_through_unknown_tool() {
-        let layer = CliCheckLayer::with_defaults();
-        let input = make_spec("github_issues", "Fetch GitHub issues");
+    async fn keywords_match_whole_words_only() {
+        let bin = stub_bin_dir(&["sed", "rg"]);
+        let layer = CliCheckLayer::with_search_path(default_utilities(), bin.path());
 
-        let result = layer.evaluate(&input).await.unwrap();
-        assert!(result.is_none());
+        let parse = make_spec("csv_merge", "Return the parsed rows, merged by key");
+        assert!(layer.evaluate(&parse).await.unwrap().is_none());
+
+        let replace = make_spec("sed_replace", "Replace text");
+        assert!(layer.evaluate(&replace).await.unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extra_utilities_come_first_and_override_defaults() {
+        let extra = vec![
+            CliUtility {
+                binary: "yq".into(),
+                description: "YAML processor".into(),
+                keywords: vec!["yaml".into()],
+                example_invocation: "yq '.key' file.yaml".into(),
+            },
+            CliUtility {
+                binary: "jq".into(),
+                description: "Local jq build".into(),
+                keywords: vec!["json".into()],
+                example_invocation: "jq . file.json".into(),
+            },
+        ];
+        let bin = stub_bin_dir(&["yq", "jq", "curl"]);
+        let layer = CliCheckLayer::with_search_path(merge_utilities(extra), bin.path());
+
+        let installed: Vec<&str> = layer.installed().map(|u| u.binary.as_str()).collect();
+        assert_eq!(installed, vec!["yq", "jq", "curl"]);
+        let jq = layer.installed().find(|u| u.binary == "jq").unwrap();
+        assert_eq!(jq.description, "Local jq build");
+    }
+
+    #[test]
+    fn utilities_deserialize_with_optional_fields() {
+        let utility: CliUtility =
+            serde_json::from_value(serde_json::json!({ "binary": "yq", "keywords": ["yaml"] }))
+                .unwrap();
+        assert_eq!(utility.binary, "yq");
+        assert!(utility.description.is_empty());
+        assert!(utility.example_invocation.is_empty());
     }
 
     #[tokio::test]
     async fn skips_execution_requests() {
-        let layer = CliCheckLayer::with_defaults();
+        let layer = nothing_installed();
         let input = GateInput::Execution(crate::spec::ExecutionRequest {
             tool_name: "jq".into(),
             arguments: serde_json::Value::Null,
+            tool: None,
         });
 
         let result = layer.evaluate(&input).await.unwrap();
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+use crate::decision::Decision;
+use crate::error::DecisionError;
+use crate::layers::DecisionLayer;
+use crate::spec::GateInput;
+
+/// Force-ask layer -- puts requests for named tools to a person, whatever
+/// the later layers would decide.
+///
+/// Runs right after the policy rules, so a deny pattern still denies, but
+/// neither an allow pattern, a cached decision, nor the LLM evaluator can
+/// answer for a matching tool: the engine passes over a policy allow for
+/// one. Its Ask is not terminal, so it is never cached.
+pub struct ForceAskLayer {
+    patterns: Vec<String>,
+}
+
+/// Tool name patterns that always need a person, configured in girt.toml as
+/// `[security.always_ask]`. A pattern is a tool name in which `*` matches
+/// any run of characters, e.g. `deploy_*`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlwaysAsk {
+    /// Capability requests for these names go to a person before building.
+    #[serde(default)]
+    pub creation: Vec<String>,
+    /// Every call to these tools goes to a person.
+    #[serde(default)]
+    pub execution: Vec<String>,
+}
+
+impl ForceAskLayer {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    /// The first pattern matching `name`.
+    pub fn matching(&self, name: &str) -> Option<&str> {
+        self.patterns
+            .iter()
+            .map(String::as_str)
+            .find(|pattern| matches_pattern(pattern, name))
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any (possibly
+/// empty) run of characters and everything else is literal.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*`: the whole name must match.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+impl DecisionLayer for ForceAskLayer {
+    fn name(&self) -> &str {
+        "force_ask"
+    }
+
+    fn evaluate<'a>(
+        &'a self,
+        input: &'a GateInput,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>,
+    > {
+        Box::pin(async move {
+            let name = input.subject();
+            let Some(pattern) = self.matching(name) else {
+                return Ok(None);
+            };
+            tracing::info!(tool = %name, %pattern, "Always-ask rule matched: ASK");
+            let prompt = match input {
+                GateInput::Creation { .. } => format!(
+                    "Capability request '{name}' matches the always-ask rule '{pattern}', so a \
+                     person decides whether it is built."
+                ),
+                GateInput::Execution(_) => format!(
+                    "Tool '{name}' matches the always-ask rule '{pattern}', so a person decides \
+                     whether each call runs."
+                ),
+            };
+            Ok(Some(Decision::Ask {
+                prompt,
+                context: format!("security.always_ask: {pattern}"),
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, ExecutionRequest, RequestSource};
+
+    fn execution(name: &str) -> GateInput {
+        GateInput::Execution(ExecutionRequest {
+            tool_name: name.into(),
+            arguments: serde_json::Value::Null,
+            tool: None,
+        })
+    }
+
+    #[test]
+    fn patterns_match_whole_names_with_wildcards() {
+        assert!(matches_pattern("send_payment", "send_payment"));
+        assert!(!matches_pattern("send_payment", "send_payments"));
+        assert!(matches_pattern("deploy_*", "deploy_"));
+        assert!(matches_pattern("deploy_*", "deploy_prod"));
+        assert!(!matches_pattern("deploy_*", "redeploy_prod"));
+        assert!(matches_pattern("*_prod", "deploy_prod"));
+        assert!(matches_pattern("*delete*", "bulk_delete_rows"));
+        assert!(matches_pattern("a*b*a", "aba"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("*", "anything"));
+    }
+
+    #[tokio::test]
+    async fn matching_call_is_asked_about() {
+        let layer = ForceAskLayer::new(vec!["send_payment".into(), "deploy_*".into()]);
+
+        let decision = layer.evaluate(&execution("deploy_prod")).await.unwrap();
+        let Some(Decision::Ask { prompt, context }) = decision else {
+            panic!("expected Ask, got {decision:?}");
+        };
+        assert!(prompt.contains("'deploy_prod'"), "{prompt}");
+        assert!(prompt.contains("'deploy_*'"), "{prompt}");
+        assert_eq!(context, "security.always_ask: deploy_*");
+    }
+
+    #[tokio::test]
+    async fn other_requests_pass_through() {
+        let layer = ForceAskLayer::new(vec!["deploy_*".into()]);
+        assert!(layer.evaluate(&execution("echo")).await.unwrap().is_none());
+
+        let creation = GateInput::creation(
+            CapabilitySpec {
+                name: "weather_lookup".into(),
+                description: "Look up the weather".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        );
+        assert!(layer.evaluate(&creation).await.unwrap().is_none());
+        assert!(
+            ForceAskLayer::new(vec![])
+                .evaluate(&execution("deploy_prod"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -76,7 +76,7 @@ impl DecisionLayer for HitlLayer {
     ) -> Pin<Box<dyn Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>> {
         Box::pin(async move {
             let context = match input {
-                GateInput::Creation(spec) => {
+                GateInput::Creation { spec, .. } => {
                     format!("Capability request '{}': {}", spec.name, spec.description)
                 }
                 GateInput::Execution(req) => {
@@ -110,16 +110,19 @@ impl DecisionLayer for HitlLayer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec};
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, RequestSource};
 
     fn make_input() -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: "ambiguous_tool".into(),
-            description: "Might be dangerous, might not".into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: "ambiguous_tool".into(),
+                description: "Might be dangerous, might not".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
     #[tokio::test]
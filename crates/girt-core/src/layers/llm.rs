@@ -5,8 +5,12 @@ use serde::{Deserialize, Serialize};
 
 use crate::decision::Decision;
 use crate::error::DecisionError;
-use crate::layers::DecisionLayer;
-use crate::spec::GateInput;
+use crate::layers::{DecisionLayer, ExplainedDecision};
+use crate::spec::{CapabilitySpec, GateInput};
+
+/// Default lowest confidence at which an LLM Allow stands; less confident
+/// Allows are put to a human instead.
+pub const DEFAULT_MIN_ALLOW_CONFIDENCE: f64 = 0.7;
 
 /// LLM evaluation layer -- uses an LLM to evaluate ambiguous requests.
 ///
@@ -29,12 +33,26 @@ pub trait LlmEvaluator: Send + Sync {
 pub struct LlmDecision {
     pub decision: LlmDecisionKind,
     pub rationale: String,
+    /// What a human approving an Ask should make sure of.
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    /// For [`LlmDecisionKind::AllowModified`]: the spec to build instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_spec: Option<CapabilitySpec>,
+    /// For [`LlmDecisionKind::AllowModified`]: what was changed, one line
+    /// each.
+    #[serde(default)]
+    pub changes: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LlmDecisionKind {
     Allow,
+    /// Allow once the spec is narrowed to
+    /// [`modified_spec`](LlmDecision::modified_spec). Creation Gate only.
+    #[serde(rename = "allow_modified")]
+    AllowModified,
     Deny,
     Ask,
 }
@@ -52,6 +70,9 @@ impl LlmEvaluator for StubLlmEvaluator {
             Ok(LlmDecision {
                 decision: LlmDecisionKind::Ask,
                 rationale: "LLM evaluation not yet configured, deferring to human".into(),
+                conditions: Vec::new(),
+                modified_spec: None,
+                changes: Vec::new(),
             })
         })
     }
@@ -78,6 +99,19 @@ impl DecisionLayer for LlmEvaluationLayer {
         &'a self,
         input: &'a GateInput,
     ) -> Pin<Box<dyn Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(self
+                .evaluate_explained(input)
+                .await?
+                .map(|explained| explained.decision))
+        })
+    }
+
+    fn evaluate_explained<'a>(
+        &'a self,
+        input: &'a GateInput,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<ExplainedDecision>, DecisionError>> + Send + 'a>>
+    {
         Box::pin(async move {
             match self.evaluator.evaluate(input).await {
                 Ok(llm_result) => {
@@ -87,18 +121,38 @@ impl DecisionLayer for LlmEvaluationLayer {
                         "LLM evaluation complete"
                     );
 
+                    let rationale = llm_result.rationale.clone();
                     let decision = match llm_result.decision {
                         LlmDecisionKind::Allow => Decision::Allow,
+                        LlmDecisionKind::AllowModified => {
+                            match (input, llm_result.modified_spec) {
+                                (GateInput::Creation { .. }, Some(spec)) => {
+                                    Decision::AllowModified {
+                                        spec,
+                                        changes: llm_result.changes,
+                                    }
+                                }
+                                // Nothing to build, or a call that cannot be
+                                // rewritten: a person decides.
+                                _ => Decision::Ask {
+                                    prompt: ask_prompt(&llm_result.conditions),
+                                    context: llm_result.rationale,
+                                },
+                            }
+                        }
                         LlmDecisionKind::Deny => Decision::Deny {
                             reason: llm_result.rationale,
                         },
                         LlmDecisionKind::Ask => Decision::Ask {
-                            prompt: "LLM evaluation requires human input".into(),
+                            prompt: ask_prompt(&llm_result.conditions),
                             context: llm_result.rationale,
                         },
                     };
 
-                    Ok(Some(decision))
+                    Ok(Some(ExplainedDecision {
+                        decision,
+                        rationale: Some(rationale),
+                    }))
                 }
                 Err(e) => {
                     // LLM failure: pass through to next layer (HITL) rather than blocking
@@ -110,19 +164,32 @@ impl DecisionLayer for LlmEvaluationLayer {
     }
 }
 
+fn ask_prompt(conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        return "LLM evaluation requires human input".into();
+    }
+    format!(
+        "LLM evaluation requires human input. Approve only if: {}",
+        conditions.join("; ")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec};
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, RequestSource};
 
     fn make_input() -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: "test".into(),
-            description: "test".into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: "test".into(),
+                description: "test".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
     #[tokio::test]
@@ -144,6 +211,9 @@ mod tests {
                 Ok(LlmDecision {
                     decision: LlmDecisionKind::Allow,
                     rationale: "looks safe".into(),
+                    conditions: Vec::new(),
+                    modified_spec: None,
+                    changes: Vec::new(),
                 })
             })
         }
@@ -158,6 +228,47 @@ mod tests {
         assert!(matches!(result, Some(Decision::Allow)));
     }
 
+    struct AskEvaluator;
+    impl LlmEvaluator for AskEvaluator {
+        fn evaluate<'a>(
+            &'a self,
+            _input: &'a GateInput,
+        ) -> Pin<Box<dyn Future<Output = Result<LlmDecision, DecisionError>> + Send + 'a>> {
+            Box::pin(async move {
+                Ok(LlmDecision {
+                    decision: LlmDecisionKind::Ask,
+                    rationale: "writes to disk".into(),
+                    conditions: vec!["the path is under /tmp".into(), "it never deletes".into()],
+                    modified_spec: None,
+                    changes: Vec::new(),
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn ask_prompt_lists_conditions_and_rationale_is_kept() {
+        let layer = LlmEvaluationLayer::new(Box::new(AskEvaluator));
+        let explained = layer
+            .evaluate_explained(&make_input())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(explained.rationale.as_deref(), Some("writes to disk"));
+        match explained.decision {
+            Decision::Ask { prompt, context } => {
+                assert_eq!(
+                    prompt,
+                    "LLM evaluation requires human input. \
+                     Approve only if: the path is under /tmp; it never deletes"
+                );
+                assert_eq!(context, "writes to disk");
+            }
+            other => panic!("expected Ask, got {other:?}"),
+        }
+    }
+
     struct FailingEvaluator;
     impl LlmEvaluator for FailingEvaluator {
         fn evaluate<'a>(
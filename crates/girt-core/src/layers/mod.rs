@@ -1,5 +1,7 @@
+pub mod budget;
 pub mod cache;
 pub mod cli_check;
+pub mod force_ask;
 pub mod hitl;
 pub mod llm;
 pub mod policy;
@@ -26,4 +28,30 @@ pub trait DecisionLayer: Send + Sync {
         &'a self,
         input: &'a GateInput,
     ) -> Pin<Box<dyn Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>>;
+
+    /// Like [`evaluate`](Self::evaluate), with the layer's reasoning for the
+    /// decision. Layers with nothing to add beyond the decision itself keep
+    /// this default.
+    fn evaluate_explained<'a>(
+        &'a self,
+        input: &'a GateInput,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<ExplainedDecision>, DecisionError>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            Ok(self
+                .evaluate(input)
+                .await?
+                .map(|decision| ExplainedDecision {
+                    decision,
+                    rationale: None,
+                }))
+        })
+    }
+}
+
+/// A layer's decision together with why it was reached.
+#[derive(Debug, Clone)]
+pub struct ExplainedDecision {
+    pub decision: Decision,
+    pub rationale: Option<String>,
 }
@@ -1,16 +1,26 @@
+use std::sync::RwLock;
+
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use crate::decision::Decision;
 use crate::error::DecisionError;
 use crate::layers::DecisionLayer;
+use crate::reserved;
 use crate::spec::{CapabilitySpec, ExecutionRequest, GateInput};
 
 /// Pattern-matching policy rules for known-good and known-bad requests.
 ///
 /// Rules are evaluated in order. The first matching rule produces the decision.
 /// If no rule matches, the layer passes through to the next cascade layer.
+///
+/// Patterns can be swapped at runtime (e.g. on config reload); evaluations
+/// already in flight finish against the rules they started with.
 pub struct PolicyRulesLayer {
+    rules: RwLock<PolicyRules>,
+}
+
+struct PolicyRules {
     deny_patterns: Vec<PolicyPattern>,
     allow_patterns: Vec<PolicyPattern>,
 }
@@ -23,6 +33,10 @@ pub struct PolicyPattern {
     pub constraint_patterns: Option<ConstraintPatterns>,
 }
 
+/// Regexes matched against a spec's constraint entries. `network_deny`
+/// patterns are matched against both the bare host and the grant as
+/// displayed, e.g. `api.github.com (GET) under /repos/`, so a pattern can
+/// deny a host outright or only some methods or paths on it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConstraintPatterns {
     pub network_deny: Option<Vec<String>>,
@@ -33,17 +47,66 @@ pub struct ConstraintPatterns {
 impl PolicyRulesLayer {
     pub fn new(deny_patterns: Vec<PolicyPattern>, allow_patterns: Vec<PolicyPattern>) -> Self {
         Self {
-            deny_patterns,
-            allow_patterns,
+            rules: RwLock::new(PolicyRules {
+                deny_patterns,
+                allow_patterns,
+            }),
         }
     }
 
     /// Create a layer with sensible default deny/allow patterns.
     pub fn with_defaults() -> Self {
-        Self {
-            deny_patterns: default_deny_patterns(),
-            allow_patterns: default_allow_patterns(),
-        }
+        Self::new(default_deny_patterns(), default_allow_patterns())
+    }
+
+    /// Replace all patterns.
+    pub fn replace_patterns(
+        &self,
+        deny_patterns: Vec<PolicyPattern>,
+        allow_patterns: Vec<PolicyPattern>,
+    ) {
+        let mut rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+        *rules = PolicyRules {
+            deny_patterns,
+            allow_patterns,
+        };
+    }
+
+    /// Replace all patterns with the defaults followed by `extra_deny` and
+    /// `extra_allow` (e.g. the `[policy]` section of girt.toml).
+    pub fn reset_with_extra(
+        &self,
+        extra_deny: Vec<PolicyPattern>,
+        extra_allow: Vec<PolicyPattern>,
+    ) {
+        let mut deny = default_deny_patterns();
+        deny.extend(extra_deny);
+        let mut allow = default_allow_patterns();
+        allow.extend(extra_allow);
+        self.replace_patterns(deny, allow);
+    }
+
+    /// Descriptions of the current deny patterns, in evaluation order.
+    pub fn deny_descriptions(&self) -> Vec<String> {
+        let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
+        rules
+            .deny_patterns
+            .iter()
+            .map(|p| p.description.clone())
+            .collect()
+    }
+
+    /// Whether a deny pattern's `secrets_deny` matches `secret`, so no
+    /// spec asking for it can pass.
+    pub fn denies_secret(&self, secret: &str) -> bool {
+        let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
+        rules.deny_patterns.iter().any(|pattern| {
+            pattern
+                .constraint_patterns
+                .as_ref()
+                .and_then(|c| c.secrets_deny.as_ref())
+                .is_some_and(|deny| matches_any(deny, std::iter::once(secret)))
+        })
     }
 
     fn matches_spec(pattern: &PolicyPattern, spec: &CapabilitySpec) -> bool {
@@ -78,7 +141,7 @@ impl PolicyRulesLayer {
                         .constraints
                         .network
                         .iter()
-                        .any(|host| re.is_match(host))
+                        .any(|grant| re.is_match(grant.host()) || re.is_match(&grant.to_string()))
                 {
                     return true;
                 }
@@ -99,6 +162,15 @@ impl PolicyRulesLayer {
             }
         }
 
+        if let Some(secrets_deny) = &patterns.secrets_deny
+            && matches_any(
+                secrets_deny,
+                spec.constraints.secrets.iter().map(String::as_str),
+            )
+        {
+            return true;
+        }
+
         false
     }
 
@@ -125,10 +197,12 @@ impl DecisionLayer for PolicyRulesLayer {
         Box<dyn std::future::Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>,
     > {
         Box::pin(async move {
+            let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
+
             // Check deny patterns first (deny takes priority)
-            for pattern in &self.deny_patterns {
+            for pattern in &rules.deny_patterns {
                 let matched = match input {
-                    GateInput::Creation(spec) => Self::matches_spec(pattern, spec),
+                    GateInput::Creation { spec, .. } => Self::matches_spec(pattern, spec),
                     GateInput::Execution(req) => Self::matches_execution(pattern, req),
                 };
 
@@ -144,9 +218,9 @@ impl DecisionLayer for PolicyRulesLayer {
             }
 
             // Check allow patterns
-            for pattern in &self.allow_patterns {
+            for pattern in &rules.allow_patterns {
                 let matched = match input {
-                    GateInput::Creation(spec) => Self::matches_spec(pattern, spec),
+                    GateInput::Creation { spec, .. } => Self::matches_spec(pattern, spec),
                     GateInput::Execution(req) => Self::matches_execution(pattern, req),
                 };
 
@@ -165,9 +239,23 @@ impl DecisionLayer for PolicyRulesLayer {
     }
 }
 
+/// Whether any of `patterns` matches any of `values`. Invalid patterns
+/// never match.
+fn matches_any<'a>(patterns: &[String], values: impl Iterator<Item = &'a str> + Clone) -> bool {
+    patterns.iter().any(|pattern| {
+        Regex::new(pattern).is_ok_and(|re| values.clone().any(|value| re.is_match(value)))
+    })
+}
+
 /// Known-dangerous patterns that should be auto-denied.
 fn default_deny_patterns() -> Vec<PolicyPattern> {
     vec![
+        PolicyPattern {
+            description: "Name reserved for a GIRT built-in".into(),
+            name_pattern: Some(reserved::name_pattern()),
+            description_pattern: None,
+            constraint_patterns: None,
+        },
         PolicyPattern {
             description: "Shell execution access".into(),
             name_pattern: Some(r"(?i)(shell_exec|run_command|system_call|exec_cmd)".into()),
@@ -250,30 +338,36 @@ fn default_allow_patterns() -> Vec<PolicyPattern> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::CapabilityConstraints;
+    use crate::spec::{CapabilityConstraints, NetworkGrant, RequestSource};
 
     fn make_spec(name: &str, desc: &str) -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: name.into(),
-            description: desc.into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: name.into(),
+                description: desc.into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
     fn make_spec_with_constraints(name: &str, network: Vec<&str>, storage: Vec<&str>) -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: name.into(),
-            description: "test".into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints {
-                network: network.into_iter().map(String::from).collect(),
-                storage: storage.into_iter().map(String::from).collect(),
-                secrets: vec![],
+        GateInput::creation(
+            CapabilitySpec {
+                name: name.into(),
+                description: "test".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints {
+                    network: network.into_iter().map(NetworkGrant::from).collect(),
+                    storage: storage.into_iter().map(String::from).collect(),
+                    secrets: vec![],
+                },
             },
-        })
+            RequestSource::Agent,
+        )
     }
 
     #[tokio::test]
@@ -360,4 +454,149 @@ mod tests {
         let result = layer.evaluate(&input).await.unwrap();
         assert!(matches!(result, Some(Decision::Deny { .. })));
     }
+
+    #[tokio::test]
+    async fn reserved_names_are_denied_by_default() {
+        let layer = PolicyRulesLayer::with_defaults();
+        for name in ["girt_info", "request_capability", "girt_build_status"] {
+            let result = layer
+                .evaluate(&make_spec(name, "Report status"))
+                .await
+                .unwrap();
+            assert!(
+                matches!(&result, Some(Decision::Deny { reason }) if reason.contains("reserved")),
+                "{name}: {result:?}"
+            );
+        }
+        let result = layer
+            .evaluate(&make_spec("status_report", "Report status"))
+            .await
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn reset_with_extra_keeps_defaults_and_adds_patterns() {
+        let layer = PolicyRulesLayer::with_defaults();
+        let input = make_spec("crypto_miner", "Mine coins in the background");
+        assert!(layer.evaluate(&input).await.unwrap().is_none());
+
+        layer.reset_with_extra(
+            vec![PolicyPattern {
+                description: "No crypto miners".into(),
+                name_pattern: Some(r"(?i)miner".into()),
+                description_pattern: None,
+                constraint_patterns: None,
+            }],
+            vec![],
+        );
+
+        assert!(matches!(
+            layer.evaluate(&input).await.unwrap(),
+            Some(Decision::Deny { .. })
+        ));
+        let shell = make_spec("shell_exec", "Run shell commands");
+        assert!(matches!(
+            layer.evaluate(&shell).await.unwrap(),
+            Some(Decision::Deny { .. })
+        ));
+        let descriptions = layer.deny_descriptions();
+        assert_eq!(descriptions.len(), default_deny_patterns().len() + 1);
+        assert_eq!(descriptions.last().unwrap(), "No crypto miners");
+    }
+
+    #[tokio::test]
+    async fn secrets_deny_blocks_specs_that_ask_for_the_secret() {
+        let layer = PolicyRulesLayer::new(
+            vec![PolicyPattern {
+                description: "No cloud root credentials".into(),
+                name_pattern: None,
+                description_pattern: None,
+                constraint_patterns: Some(ConstraintPatterns {
+                    network_deny: None,
+                    storage_deny: None,
+                    secrets_deny: Some(vec![r"(?i)^aws".into()]),
+                }),
+            }],
+            vec![],
+        );
+        assert!(layer.denies_secret("aws_root"));
+        assert!(!layer.denies_secret("github"));
+
+        let input = GateInput::creation(
+            CapabilitySpec {
+                name: "s3_upload".into(),
+                description: "Upload a file".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints {
+                    secrets: vec!["AWS_SECRET_ACCESS_KEY".into()],
+                    ..CapabilityConstraints::default()
+                },
+            },
+            RequestSource::Agent,
+        );
+        assert!(matches!(
+            layer.evaluate(&input).await.unwrap(),
+            Some(Decision::Deny { .. })
+        ));
+        let unrelated = make_spec("gh_issues", "List GitHub issues");
+        assert!(layer.evaluate(&unrelated).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn network_deny_sees_the_scope_of_a_grant() {
+        let layer = PolicyRulesLayer::new(
+            vec![PolicyPattern {
+                description: "No deletes against GitHub".into(),
+                name_pattern: None,
+                description_pattern: None,
+                constraint_patterns: Some(ConstraintPatterns {
+                    network_deny: Some(vec![r"^api\.github\.com \(.*DELETE".into()]),
+                    storage_deny: None,
+                    secrets_deny: None,
+                }),
+            }],
+            vec![],
+        );
+        let with_grant = |grant: NetworkGrant| {
+            GateInput::creation(
+                CapabilitySpec {
+                    name: "gh_issues".into(),
+                    description: "Manage GitHub issues".into(),
+                    inputs: serde_json::Value::Null,
+                    outputs: serde_json::Value::Null,
+                    constraints: CapabilityConstraints {
+                        network: vec![grant],
+                        ..CapabilityConstraints::default()
+                    },
+                },
+                RequestSource::Agent,
+            )
+        };
+
+        let deletes = with_grant(NetworkGrant::scoped(
+            "api.github.com",
+            vec!["GET".into(), "DELETE".into()],
+            vec![],
+        ));
+        assert!(matches!(
+            layer.evaluate(&deletes).await.unwrap(),
+            Some(Decision::Deny { .. })
+        ));
+        let reads = with_grant(NetworkGrant::scoped(
+            "api.github.com",
+            vec!["GET".into()],
+            vec![],
+        ));
+        assert!(layer.evaluate(&reads).await.unwrap().is_none());
+
+        // The default wildcard rule still sees the host of a scoped grant.
+        let defaults = PolicyRulesLayer::new(default_deny_patterns(), vec![]);
+        let wildcard = with_grant(NetworkGrant::scoped("*", vec!["GET".into()], vec![]));
+        assert!(matches!(
+            defaults.evaluate(&wildcard).await.unwrap(),
+            Some(Decision::Deny { .. })
+        ));
+    }
 }
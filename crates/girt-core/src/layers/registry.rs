@@ -69,7 +69,7 @@ impl DecisionLayer for RegistryLookupLayer {
         Box::pin(async move {
             // Registry lookup only applies to Creation Gate
             let spec = match input {
-                GateInput::Creation(spec) => spec,
+                GateInput::Creation { spec, .. } => spec,
                 GateInput::Execution(_) => return Ok(None),
             };
 
@@ -112,22 +112,26 @@ impl DecisionLayer for RegistryLookupLayer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec, ExecutionRequest};
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, ExecutionRequest, RequestSource};
 
     fn make_creation_input(name: &str) -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: name.into(),
-            description: "test".into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: name.into(),
+                description: "test".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
     fn make_execution_input(name: &str) -> GateInput {
         GateInput::Execution(ExecutionRequest {
             tool_name: name.into(),
             arguments: serde_json::Value::Null,
+            tool: None,
         })
     }
 
@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::RwLock;
 
 use crate::decision::{Decision, DeferTarget};
 use crate::error::DecisionError;
@@ -39,16 +40,24 @@ impl KnownSpec {
 /// Future enhancement: replace keyword matching with embedding-based
 /// similarity using a vector store.
 pub struct SimilarityLayer {
-    known_specs: Vec<KnownSpec>,
+    known_specs: RwLock<Vec<KnownSpec>>,
 }
 
 impl SimilarityLayer {
     pub fn new(known_specs: Vec<KnownSpec>) -> Self {
-        Self { known_specs }
+        Self {
+            known_specs: RwLock::new(known_specs),
+        }
+    }
+
+    /// Replace all known specs.
+    pub fn replace_known_specs(&self, known_specs: Vec<KnownSpec>) {
+        *self.known_specs.write().unwrap_or_else(|e| e.into_inner()) = known_specs;
     }
 
-    /// Find the best matching known spec above the similarity threshold.
-    fn find_match(&self, name: &str, description: &str) -> Option<(&KnownSpec, f64)> {
+    /// Find the best matching known spec above the similarity threshold,
+    /// returning its name and score.
+    fn find_match(&self, name: &str, description: &str) -> Option<(String, f64)> {
         let input_keywords = extract_keywords(description);
         let input_name_keywords = extract_keywords(name);
         let combined: HashSet<String> = input_keywords
@@ -56,12 +65,13 @@ impl SimilarityLayer {
             .cloned()
             .collect();
 
+        let known_specs = self.known_specs.read().unwrap_or_else(|e| e.into_inner());
         let mut best_match: Option<(&KnownSpec, f64)> = None;
 
-        for spec in &self.known_specs {
+        for spec in known_specs.iter() {
             // Check exact name match first
             if spec.name == name {
-                return Some((spec, 1.0));
+                return Some((spec.name.clone(), 1.0));
             }
 
             let score = jaccard_similarity(&combined, &spec.keywords);
@@ -73,7 +83,7 @@ impl SimilarityLayer {
             }
         }
 
-        best_match
+        best_match.map(|(spec, score)| (spec.name.clone(), score))
     }
 }
 
@@ -88,21 +98,21 @@ impl DecisionLayer for SimilarityLayer {
     ) -> Pin<Box<dyn Future<Output = Result<Option<Decision>, DecisionError>> + Send + 'a>> {
         Box::pin(async move {
             let spec = match input {
-                GateInput::Creation(spec) => spec,
+                GateInput::Creation { spec, .. } => spec,
                 GateInput::Execution(_) => return Ok(None), // Skip for execution
             };
 
             if let Some((matched, score)) = self.find_match(&spec.name, &spec.description) {
                 tracing::info!(
                     input_name = %spec.name,
-                    matched_name = %matched.name,
+                    matched_name = %matched,
                     score = score,
                     "Similarity match found"
                 );
 
                 Ok(Some(Decision::Defer {
                     target: DeferTarget::ExtendTool {
-                        tool_name: matched.name.clone(),
+                        tool_name: matched,
                         suggested_features: vec![],
                     },
                 }))
@@ -148,7 +158,7 @@ fn jaccard_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::spec::{CapabilityConstraints, CapabilitySpec};
+    use crate::spec::{CapabilityConstraints, CapabilitySpec, RequestSource};
 
     fn make_known_specs() -> Vec<KnownSpec> {
         vec![
@@ -168,13 +178,16 @@ mod tests {
     }
 
     fn make_creation_input(name: &str, description: &str) -> GateInput {
-        GateInput::Creation(CapabilitySpec {
-            name: name.into(),
-            description: description.into(),
-            inputs: serde_json::Value::Null,
-            outputs: serde_json::Value::Null,
-            constraints: CapabilityConstraints::default(),
-        })
+        GateInput::creation(
+            CapabilitySpec {
+                name: name.into(),
+                description: description.into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Agent,
+        )
     }
 
     #[tokio::test]
@@ -200,6 +213,18 @@ mod tests {
         assert!(matches!(result.unwrap(), Decision::Defer { .. }));
     }
 
+    #[tokio::test]
+    async fn replacing_known_specs_forgets_the_old_ones() {
+        let layer = SimilarityLayer::new(make_known_specs());
+        let input = make_creation_input("github_api", "fetch issues from GitHub");
+
+        layer.replace_known_specs(vec![KnownSpec::new(
+            "weather_forecast",
+            "Get weather forecast data for a location",
+        )]);
+        assert!(layer.evaluate(&input).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn unrelated_spec_passes_through() {
         let layer = SimilarityLayer::new(make_known_specs());
@@ -218,6 +243,7 @@ mod tests {
         let input = GateInput::Execution(crate::spec::ExecutionRequest {
             tool_name: "github_api".into(),
             arguments: serde_json::Value::Null,
+            tool: None,
         });
 
         let result = layer.evaluate(&input).await.unwrap();
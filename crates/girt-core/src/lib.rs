@@ -1,5 +1,12 @@
+pub mod audit;
 pub mod decision;
 pub mod engine;
 pub mod error;
+pub mod faults;
+pub mod host;
 pub mod layers;
+pub mod limits;
+pub mod paths;
+pub mod reserved;
 pub mod spec;
+pub mod usage;
@@ -0,0 +1,253 @@
+//! Size limits on agent-supplied payloads and tool responses.
+//!
+//! Specs are serialized into every pipeline prompt and tool arguments are
+//! copied across the WASM boundary, so both are capped before any gate,
+//! LLM, or runtime work is done on them. Tool responses end up in the
+//! agent's context, so they are capped on the way back
+//! ([`OversizeResponse`]).
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SizeLimitError;
+use crate::spec::MAX_DESCRIPTION_LEN;
+
+/// Default cap on a serialized capability spec: 64 KiB.
+pub const DEFAULT_MAX_SPEC_BYTES: usize = 64 * 1024;
+
+/// Default cap on a serialized tool-call arguments object: 1 MiB.
+pub const DEFAULT_MAX_ARGUMENT_BYTES: usize = 1024 * 1024;
+
+/// Payload caps, configured under `[security]` in girt.toml.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    pub max_spec_bytes: usize,
+    /// Values above [`MAX_DESCRIPTION_LEN`] have no effect: spec
+    /// validation rejects longer descriptions regardless.
+    pub max_description_chars: usize,
+    pub max_argument_bytes: usize,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            max_spec_bytes: DEFAULT_MAX_SPEC_BYTES,
+            max_description_chars: MAX_DESCRIPTION_LEN,
+            max_argument_bytes: DEFAULT_MAX_ARGUMENT_BYTES,
+        }
+    }
+}
+
+impl SizeLimits {
+    /// Check a capability spec in its JSON form, as received from an agent
+    /// or about to be enqueued. The spec need not be well-formed yet.
+    pub fn check_spec(&self, spec: &serde_json::Value) -> Result<(), SizeLimitError> {
+        check(
+            "max_spec_bytes",
+            serialized_len(spec),
+            self.max_spec_bytes,
+            "bytes",
+        )?;
+        if let Some(description) = spec.get("description").and_then(|d| d.as_str()) {
+            check(
+                "max_description_chars",
+                description.chars().count(),
+                self.max_description_chars,
+                "chars",
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Check a tool call's arguments before they reach the runtime.
+    pub fn check_arguments(&self, arguments: &serde_json::Value) -> Result<(), SizeLimitError> {
+        check(
+            "max_argument_bytes",
+            serialized_len(arguments),
+            self.max_argument_bytes,
+            "bytes",
+        )
+    }
+}
+
+/// What happens to a tool response larger than its `max_response_bytes`
+/// (`[runtime] oversize_response`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizeResponse {
+    /// Fail the call.
+    Error,
+    /// Cut the response down to the limit and say so at the end.
+    #[default]
+    Truncate,
+}
+
+/// A response cut down by [`OversizeResponse::Truncate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Truncation {
+    /// Size of the response the tool returned.
+    pub original_bytes: usize,
+    pub limit_bytes: usize,
+}
+
+impl OversizeResponse {
+    /// Hold a tool's response to `limit` bytes.
+    ///
+    /// A truncated response ends with a marker and stays within `limit`
+    /// unless the limit is shorter than the marker itself.
+    pub fn apply(
+        self,
+        response: &mut String,
+        limit: usize,
+    ) -> Result<Option<Truncation>, SizeLimitError> {
+        let original_bytes = response.len();
+        let checked = check("max_response_bytes", original_bytes, limit, "bytes");
+        match (checked, self) {
+            (Ok(()), _) => return Ok(None),
+            (Err(e), Self::Error) => return Err(e),
+            (Err(_), Self::Truncate) => {}
+        }
+        let marker = format!("…[truncated: {original_bytes} bytes, limit {limit}]");
+        let mut end = limit.saturating_sub(marker.len());
+        while !response.is_char_boundary(end) {
+            end -= 1;
+        }
+        response.truncate(end);
+        response.push_str(&marker);
+        Ok(Some(Truncation {
+            original_bytes,
+            limit_bytes: limit,
+        }))
+    }
+}
+
+fn serialized_len(value: &serde_json::Value) -> usize {
+    serde_json::to_string(value).map_or(0, |s| s.len())
+}
+
+fn check(
+    setting: &'static str,
+    measured: usize,
+    limit: usize,
+    unit: &'static str,
+) -> Result<(), SizeLimitError> {
+    if measured > limit {
+        return Err(SizeLimitError {
+            setting,
+            measured,
+            limit,
+            unit,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limits() -> SizeLimits {
+        SizeLimits {
+            max_spec_bytes: 100,
+            max_description_chars: 10,
+            max_argument_bytes: 20,
+        }
+    }
+
+    #[test]
+    fn spec_at_the_byte_limit_passes_and_one_over_fails() {
+        // {"name":"<pad>"} is 11 bytes of framing.
+        let at_limit = json!({ "name": "x".repeat(100 - 11) });
+        assert_eq!(serialized_len(&at_limit), 100);
+        assert!(limits().check_spec(&at_limit).is_ok());
+
+        let over = json!({ "name": "x".repeat(100 - 10) });
+        assert_eq!(
+            limits().check_spec(&over).unwrap_err(),
+            SizeLimitError {
+                setting: "max_spec_bytes",
+                measured: 101,
+                limit: 100,
+                unit: "bytes",
+            }
+        );
+    }
+
+    #[test]
+    fn description_is_measured_in_chars_not_bytes() {
+        // Ten multi-byte chars: at the limit despite being 20+ bytes.
+        assert!(
+            limits()
+                .check_spec(&json!({ "description": "é".repeat(10) }))
+                .is_ok()
+        );
+
+        let err = limits()
+            .check_spec(&json!({ "description": "é".repeat(11) }))
+            .unwrap_err();
+        assert_eq!(err.setting, "max_description_chars");
+        assert_eq!(err.measured, 11);
+        assert_eq!(err.unit, "chars");
+    }
+
+    #[test]
+    fn oversize_responses_fail_or_are_truncated() {
+        let mut fits = "x".repeat(100);
+        assert_eq!(OversizeResponse::Error.apply(&mut fits, 100), Ok(None));
+        assert_eq!(OversizeResponse::Truncate.apply(&mut fits, 100), Ok(None));
+        assert_eq!(fits.len(), 100);
+
+        let mut over = "x".repeat(101);
+        let err = OversizeResponse::Error.apply(&mut over, 100).unwrap_err();
+        assert_eq!(err.setting, "max_response_bytes");
+        assert_eq!(err.measured, 101);
+        assert_eq!(over.len(), 101);
+
+        let truncation = OversizeResponse::Truncate.apply(&mut over, 100).unwrap();
+        assert_eq!(
+            truncation,
+            Some(Truncation {
+                original_bytes: 101,
+                limit_bytes: 100,
+            })
+        );
+        assert!(over.len() <= 100);
+        assert!(over.ends_with("…[truncated: 101 bytes, limit 100]"));
+    }
+
+    #[test]
+    fn truncation_keeps_whole_chars() {
+        let mut response = "é".repeat(60);
+        let truncation = OversizeResponse::Truncate.apply(&mut response, 100);
+        assert!(truncation.unwrap().is_some());
+        assert!(response.len() <= 100);
+        assert!(response.starts_with('é'));
+    }
+
+    #[test]
+    fn arguments_boundary_and_error_payload() {
+        // {"a":"<pad>"} is 8 bytes of framing.
+        assert!(
+            limits()
+                .check_arguments(&json!({ "a": "x".repeat(12) }))
+                .is_ok()
+        );
+
+        let err = limits()
+            .check_arguments(&json!({ "a": "x".repeat(13) }))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "max_argument_bytes exceeded: 21 bytes (limit 20)"
+        );
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({
+                "setting": "max_argument_bytes",
+                "measured": 21,
+                "limit": 20,
+                "unit": "bytes"
+            })
+        );
+    }
+}
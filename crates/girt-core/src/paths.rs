@@ -0,0 +1,237 @@
+//! Where girt keeps its files.
+//!
+//! Everything girt writes (queue, built tools, components, caches, logs)
+//! goes under the data directory; girt.toml and OAuth tokens live in the
+//! config directory:
+//!
+//! | Set | Data | Config |
+//! |---|---|---|
+//! | `GIRT_HOME` | `$GIRT_HOME` | `$GIRT_HOME/config` |
+//! | `XDG_DATA_HOME` / `XDG_CONFIG_HOME` | `$XDG_DATA_HOME/girt` | `$XDG_CONFIG_HOME/girt` |
+//! | neither | `~/.girt` | `~/.config/girt` |
+//!
+//! With none of these set and no home directory (`HOME` unset, as under
+//! systemd `DynamicUser`), resolution fails with [`PathError`] instead of
+//! writing into the working directory.
+//!
+//! Earlier versions always used `~/.girt` and `~/.config/girt`. While the
+//! configured directory does not exist yet and the old one does, the old
+//! one stays in use. [`migrate_legacy_data`] moves the old data directory
+//! over; config is read in place.
+
+use std::path::{Path, PathBuf};
+
+pub use crate::error::PathError;
+
+/// The environment girt's directories are resolved from.
+#[derive(Debug, Clone, Default)]
+pub struct PathEnv {
+    pub girt_home: Option<PathBuf>,
+    pub xdg_data_home: Option<PathBuf>,
+    pub xdg_config_home: Option<PathBuf>,
+    pub home: Option<PathBuf>,
+}
+
+impl PathEnv {
+    /// Read `GIRT_HOME`, `XDG_DATA_HOME`, `XDG_CONFIG_HOME`, and the user's
+    /// home directory. Empty variables and relative XDG paths are ignored.
+    pub fn from_process() -> Self {
+        Self {
+            girt_home: env_path("GIRT_HOME"),
+            xdg_data_home: env_path("XDG_DATA_HOME").filter(|p| p.is_absolute()),
+            xdg_config_home: env_path("XDG_CONFIG_HOME").filter(|p| p.is_absolute()),
+            home: dirs::home_dir().filter(|p| !p.as_os_str().is_empty()),
+        }
+    }
+
+    pub fn home_dir(&self) -> Result<PathBuf, PathError> {
+        self.home.clone().ok_or(PathError)
+    }
+
+    pub fn data_dir(&self) -> Result<PathBuf, PathError> {
+        let legacy = self.home_dir().map(|home| home.join(".girt"));
+        match self.configured_data_dir() {
+            Some(dir) => Ok(unless_legacy_in_use(dir, legacy.ok())),
+            None => legacy,
+        }
+    }
+
+    pub fn config_dir(&self) -> Result<PathBuf, PathError> {
+        let legacy = self
+            .home_dir()
+            .map(|home| home.join(".config").join("girt"));
+        let configured = match (&self.girt_home, &self.xdg_config_home) {
+            (Some(girt_home), _) => girt_home.join("config"),
+            (None, Some(xdg)) => xdg.join("girt"),
+            (None, None) => return legacy,
+        };
+        Ok(unless_legacy_in_use(configured, legacy.ok()))
+    }
+
+    /// `path` with a leading `~` (`~`, `~/…`) replaced by the home
+    /// directory. `~user` forms are left alone.
+    pub fn expand_tilde(&self, path: &str) -> Result<PathBuf, PathError> {
+        let Some(rest) = path.strip_prefix('~') else {
+            return Ok(PathBuf::from(path));
+        };
+        if rest.is_empty() {
+            return self.home_dir();
+        }
+        match rest.strip_prefix(['/', std::path::MAIN_SEPARATOR]) {
+            Some(rest) => Ok(self.home_dir()?.join(rest)),
+            None => Ok(PathBuf::from(path)),
+        }
+    }
+
+    /// Move `~/.girt` to the configured data directory when that is
+    /// elsewhere and does not exist yet. Returns the `(from, to)` of a move.
+    pub fn migrate_legacy_data(&self) -> std::io::Result<Option<(PathBuf, PathBuf)>> {
+        let (Ok(home), Some(target)) = (self.home_dir(), self.configured_data_dir()) else {
+            return Ok(None);
+        };
+        let legacy = home.join(".girt");
+        if target == legacy || target.exists() || !legacy.is_dir() {
+            return Ok(None);
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&legacy, &target)?;
+        Ok(Some((legacy, target)))
+    }
+
+    /// The data directory `GIRT_HOME` or `XDG_DATA_HOME` asks for.
+    fn configured_data_dir(&self) -> Option<PathBuf> {
+        match (&self.girt_home, &self.xdg_data_home) {
+            (Some(girt_home), _) => Some(girt_home.clone()),
+            (None, Some(xdg)) => Some(xdg.join("girt")),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Data directory: see the [module docs](self).
+pub fn data_dir() -> Result<PathBuf, PathError> {
+    PathEnv::from_process().data_dir()
+}
+
+/// Config directory, holding girt.toml and `auth/`: see the
+/// [module docs](self).
+pub fn config_dir() -> Result<PathBuf, PathError> {
+    PathEnv::from_process().config_dir()
+}
+
+/// The user's home directory.
+pub fn home_dir() -> Result<PathBuf, PathError> {
+    PathEnv::from_process().home_dir()
+}
+
+/// Expand a leading `~` in a path from girt.toml.
+pub fn expand_tilde(path: &str) -> Result<PathBuf, PathError> {
+    PathEnv::from_process().expand_tilde(path)
+}
+
+/// Move `~/.girt` to the data directory `GIRT_HOME` or `XDG_DATA_HOME`
+/// names, if that does not exist yet. A failed move (e.g. across file
+/// systems) leaves `~/.girt` in use.
+pub fn migrate_legacy_data() -> std::io::Result<Option<(PathBuf, PathBuf)>> {
+    PathEnv::from_process().migrate_legacy_data()
+}
+
+fn env_path(key: &str) -> Option<PathBuf> {
+    std::env::var_os(key)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `dir`, or `legacy` while only the latter exists.
+fn unless_legacy_in_use(dir: PathBuf, legacy: Option<PathBuf>) -> PathBuf {
+    match legacy {
+        Some(legacy) if !exists(&dir) && exists(&legacy) => legacy,
+        _ => dir,
+    }
+}
+
+fn exists(path: &Path) -> bool {
+    path.try_exists().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(home: Option<&Path>) -> PathEnv {
+        PathEnv {
+            home: home.map(Path::to_path_buf),
+            ..PathEnv::default()
+        }
+    }
+
+    #[test]
+    fn girt_home_beats_xdg_beats_home() {
+        let home = Path::new("/home/u");
+        let mut env = env(Some(home));
+        assert_eq!(env.data_dir().unwrap(), home.join(".girt"));
+        assert_eq!(env.config_dir().unwrap(), home.join(".config/girt"));
+
+        env.xdg_data_home = Some("/xdg/data".into());
+        env.xdg_config_home = Some("/xdg/config".into());
+        assert_eq!(env.data_dir().unwrap(), Path::new("/xdg/data/girt"));
+        assert_eq!(env.config_dir().unwrap(), Path::new("/xdg/config/girt"));
+
+        env.girt_home = Some("/srv/girt".into());
+        assert_eq!(env.data_dir().unwrap(), Path::new("/srv/girt"));
+        assert_eq!(env.config_dir().unwrap(), Path::new("/srv/girt/config"));
+    }
+
+    #[test]
+    fn unset_home_is_an_error() {
+        let mut env = env(None);
+        assert_eq!(env.data_dir(), Err(PathError));
+        assert_eq!(env.config_dir(), Err(PathError));
+        assert_eq!(env.expand_tilde("~/standards.md"), Err(PathError));
+        assert_eq!(
+            env.expand_tilde("/etc/standards.md").unwrap(),
+            Path::new("/etc/standards.md")
+        );
+
+        env.girt_home = Some("/srv/girt".into());
+        assert_eq!(env.data_dir().unwrap(), Path::new("/srv/girt"));
+        assert_eq!(env.config_dir().unwrap(), Path::new("/srv/girt/config"));
+    }
+
+    #[test]
+    fn tilde_expands_to_home() {
+        let env = env(Some(Path::new("/home/u")));
+        assert_eq!(env.expand_tilde("~").unwrap(), Path::new("/home/u"));
+        assert_eq!(
+            env.expand_tilde("~/a/b.md").unwrap(),
+            Path::new("/home/u/a/b.md")
+        );
+        assert_eq!(env.expand_tilde("~other/x").unwrap(), Path::new("~other/x"));
+        assert_eq!(env.expand_tilde("rel/x").unwrap(), Path::new("rel/x"));
+    }
+
+    #[test]
+    fn legacy_data_is_used_until_migrated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = tmp.path().join("home");
+        let legacy = home.join(".girt");
+        std::fs::create_dir_all(legacy.join("tools")).unwrap();
+        std::fs::create_dir_all(home.join(".config/girt")).unwrap();
+        let mut env = env(Some(&home));
+        env.girt_home = Some(tmp.path().join("srv/girt"));
+
+        assert_eq!(env.data_dir().unwrap(), legacy);
+        assert_eq!(env.config_dir().unwrap(), home.join(".config/girt"));
+
+        let moved = env.migrate_legacy_data().unwrap();
+        assert_eq!(moved, Some((legacy.clone(), tmp.path().join("srv/girt"))));
+        assert!(!legacy.exists());
+        assert!(tmp.path().join("srv/girt/tools").is_dir());
+        assert_eq!(env.data_dir().unwrap(), tmp.path().join("srv/girt"));
+
+        // Nothing left to move.
+        assert_eq!(env.migrate_legacy_data().unwrap(), None);
+    }
+}
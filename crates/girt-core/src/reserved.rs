@@ -0,0 +1,81 @@
+//! Tool names a built tool may not take.
+//!
+//! The proxy implements some tools itself (`request_capability`,
+//! `girt_info`, ...). A built tool with one of those names would be
+//! unreachable at best and, if dispatch ever changed, would take over the
+//! built-in. So the names are refused three times over: by
+//! [`CapabilitySpec::validate`](crate::spec::CapabilitySpec::validate), by
+//! a default policy deny rule, and when the runtime loads a component.
+//! Names starting with [`RESERVED_PREFIX`] are kept for future built-ins.
+//!
+//! GIRT's own bundled components (`girt_echo`, in girt-runtime's `bundled`
+//! module) take reserved names on purpose. The runtime loads them by a
+//! separate path that only accepts the exact embedded wasm, and the proxy
+//! runs them without the Execution Gate, as it does its built-ins.
+
+/// Tools the proxy implements itself.
+pub const BUILTIN_TOOL_NAMES: &[&str] = &[
+    "request_capability",
+    "extend_capability",
+    "approve_capability",
+    "girt_info",
+    "girt_tool_info",
+    "girt_batch",
+    "girt_list_services",
+];
+
+/// Prefix reserved for GIRT's own tools.
+pub const RESERVED_PREFIX: &str = "girt_";
+
+/// Why `name` cannot be used for a built tool, or `None` if it can.
+pub fn conflict(name: &str) -> Option<String> {
+    if BUILTIN_TOOL_NAMES.contains(&name) {
+        Some(format!("clashes with the GIRT built-in `{name}`"))
+    } else if name.starts_with(RESERVED_PREFIX) {
+        Some(format!(
+            "starts with `{RESERVED_PREFIX}`, which is reserved for GIRT built-ins"
+        ))
+    } else {
+        None
+    }
+}
+
+/// Regex matching every reserved name, for the policy deny rule.
+pub fn name_pattern() -> String {
+    let names: Vec<String> = BUILTIN_TOOL_NAMES
+        .iter()
+        .map(|name| regex::escape(name))
+        .collect();
+    format!(
+        "^({}|{}.*)$",
+        names.join("|"),
+        regex::escape(RESERVED_PREFIX)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_ins_and_the_prefix_conflict() {
+        assert_eq!(
+            conflict("request_capability").as_deref(),
+            Some("clashes with the GIRT built-in `request_capability`")
+        );
+        assert!(conflict("girt_build_status").unwrap().contains("reserved"));
+        assert_eq!(conflict("github_issues"), None);
+        assert_eq!(conflict("request_capability_v2"), None);
+    }
+
+    #[test]
+    fn name_pattern_matches_exactly_the_reserved_names() {
+        let re = regex::Regex::new(&name_pattern()).unwrap();
+        for name in BUILTIN_TOOL_NAMES.iter().chain(&["girt_build_status"]) {
+            assert!(re.is_match(name), "{name}");
+        }
+        for name in ["my_girt_info", "request_capability_v2", "github_issues"] {
+            assert!(!re.is_match(name), "{name}");
+        }
+    }
+}
@@ -1,9 +1,39 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
 use sha2::{Digest, Sha256};
 
+use crate::error::{FieldError, SpecValidationError};
+use crate::reserved;
+
+/// Maximum length of a capability name. Names end up in queue filenames,
+/// OCI tags, git directories, and WIT package identifiers.
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Maximum length of a capability description, in characters.
+pub const MAX_DESCRIPTION_LEN: usize = 2_000;
+
 /// A capability spec describing what tool is being requested.
+///
+/// Deserializing a spec validates it, failing with every
+/// [`validate`](Self::validate) error and its field path. Read a spec that
+/// is repaired afterwards with [`RawCapabilitySpec`] or
+/// [`deserialize_unchecked`](Self::deserialize_unchecked); write one in code
+/// with [`builder`](Self::builder).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawCapabilitySpec")]
 pub struct CapabilitySpec {
+    pub name: String,
+    pub description: String,
+    pub inputs: serde_json::Value,
+    pub outputs: serde_json::Value,
+    pub constraints: CapabilityConstraints,
+}
+
+/// A [`CapabilitySpec`] as written, before validation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RawCapabilitySpec {
     pub name: String,
     pub description: String,
     #[serde(default)]
@@ -14,17 +44,245 @@ pub struct CapabilitySpec {
     pub constraints: CapabilityConstraints,
 }
 
+impl RawCapabilitySpec {
+    /// The spec as written, whether or not it is valid.
+    pub fn into_unchecked(self) -> CapabilitySpec {
+        CapabilitySpec {
+            name: self.name,
+            description: self.description,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            constraints: self.constraints,
+        }
+    }
+}
+
+impl TryFrom<RawCapabilitySpec> for CapabilitySpec {
+    type Error = SpecValidationError;
+
+    fn try_from(raw: RawCapabilitySpec) -> Result<Self, Self::Error> {
+        let spec = raw.into_unchecked();
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
 /// Security constraints for a capability.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct CapabilityConstraints {
     #[serde(default)]
-    pub network: Vec<String>,
+    pub network: Vec<NetworkGrant>,
     #[serde(default)]
     pub storage: Vec<String>,
     #[serde(default)]
     pub secrets: Vec<String>,
 }
 
+impl CapabilityConstraints {
+    /// What these constraints grant beyond `granted`: network access no
+    /// grant of `granted` [covers](NetworkGrant::covers), and storage
+    /// paths and secrets it does not list. Empty when they grant no more.
+    pub fn additions_over(&self, granted: &CapabilityConstraints) -> Vec<String> {
+        let network = self
+            .network
+            .iter()
+            .filter(|grant| !granted.network.iter().any(|g| g.covers(grant)))
+            .map(|grant| format!("network {grant}"));
+        let storage = self
+            .storage
+            .iter()
+            .filter(|path| !granted.storage.contains(path))
+            .map(|path| format!("storage {path}"));
+        let secrets = self
+            .secrets
+            .iter()
+            .filter(|secret| !granted.secrets.contains(secret))
+            .map(|secret| format!("secret {secret}"));
+        network.chain(storage).chain(secrets).collect()
+    }
+}
+
+/// HTTP methods a [`NetworkGrant`] may name.
+pub const HTTP_METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+/// A host a tool may reach, optionally limited to some HTTP methods and
+/// path prefixes.
+///
+/// A bare string is the original form and allows any method on any path:
+///
+/// ```json
+/// ["api.open-meteo.com", {"host": "api.github.com", "methods": ["GET"], "path_prefixes": ["/repos/"]}]
+/// ```
+///
+/// In the scoped form, empty `methods` or `path_prefixes` allow any method
+/// or path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NetworkGrant {
+    Host(String),
+    Scoped(ScopedHost),
+}
+
+/// The structured form of a [`NetworkGrant`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScopedHost {
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_prefixes: Vec<String>,
+}
+
+impl NetworkGrant {
+    /// A grant for `host` limited to `methods` and `path_prefixes`; the
+    /// bare form when both are empty.
+    pub fn scoped(
+        host: impl Into<String>,
+        methods: Vec<String>,
+        path_prefixes: Vec<String>,
+    ) -> Self {
+        let host = host.into();
+        if methods.is_empty() && path_prefixes.is_empty() {
+            Self::Host(host)
+        } else {
+            Self::Scoped(ScopedHost {
+                host,
+                methods,
+                path_prefixes,
+            })
+        }
+    }
+
+    /// The host pattern (`host`, `host:port`, or `*.domain`).
+    pub fn host(&self) -> &str {
+        match self {
+            Self::Host(host) => host,
+            Self::Scoped(scoped) => &scoped.host,
+        }
+    }
+
+    /// Methods allowed; empty allows any.
+    pub fn methods(&self) -> &[String] {
+        match self {
+            Self::Host(_) => &[],
+            Self::Scoped(scoped) => &scoped.methods,
+        }
+    }
+
+    /// Path prefixes allowed; empty allows any path.
+    pub fn path_prefixes(&self) -> &[String] {
+        match self {
+            Self::Host(_) => &[],
+            Self::Scoped(scoped) => &scoped.path_prefixes,
+        }
+    }
+
+    /// Whether the grant allows less than the whole host.
+    pub fn is_scoped(&self) -> bool {
+        !self.methods().is_empty() || !self.path_prefixes().is_empty()
+    }
+
+    /// Whether a `method` request for `path` is within the grant. The host
+    /// is matched separately.
+    ///
+    /// Under path prefixes, a path with `.` or `..` segments is refused,
+    /// so `/repos/../admin` cannot pass for `/repos/`.
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        let method_ok = self.methods().is_empty()
+            || self
+                .methods()
+                .iter()
+                .any(|m| m.eq_ignore_ascii_case(method));
+        let path_ok = self.path_prefixes().is_empty()
+            || (!has_dot_segments(path)
+                && self
+                    .path_prefixes()
+                    .iter()
+                    .any(|prefix| path.starts_with(prefix.as_str())));
+        method_ok && path_ok
+    }
+
+    /// Whether `self` allows everything `other` does: the same host, and
+    /// `other`'s methods and path prefixes within `self`'s.
+    pub fn covers(&self, other: &NetworkGrant) -> bool {
+        let methods_ok = self.methods().is_empty()
+            || (!other.methods().is_empty()
+                && other.methods().iter().all(|method| {
+                    self.methods()
+                        .iter()
+                        .any(|m| m.eq_ignore_ascii_case(method))
+                }));
+        let paths_ok = self.path_prefixes().is_empty()
+            || (!other.path_prefixes().is_empty()
+                && other.path_prefixes().iter().all(|path| {
+                    self.path_prefixes()
+                        .iter()
+                        .any(|prefix| path.starts_with(prefix.as_str()))
+                }));
+        self.host().eq_ignore_ascii_case(other.host()) && methods_ok && paths_ok
+    }
+
+    /// Trimmed, with methods uppercased and repeats dropped.
+    pub fn normalized(&self) -> Self {
+        let mut methods: Vec<String> = Vec::new();
+        for method in self.methods() {
+            let method = method.trim().to_ascii_uppercase();
+            if !method.is_empty() && !methods.contains(&method) {
+                methods.push(method);
+            }
+        }
+        let mut path_prefixes: Vec<String> = Vec::new();
+        for prefix in self.path_prefixes() {
+            let prefix = prefix.trim().to_string();
+            if !prefix.is_empty() && !path_prefixes.contains(&prefix) {
+                path_prefixes.push(prefix);
+            }
+        }
+        Self::scoped(self.host().trim(), methods, path_prefixes)
+    }
+}
+
+/// `api.github.com`, or `api.github.com (GET, HEAD) under /repos/, /issues/`
+/// for a scoped grant. Policy `network_deny` patterns match this form.
+impl fmt::Display for NetworkGrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.host())?;
+        if !self.methods().is_empty() {
+            write!(f, " ({})", self.methods().join(", "))?;
+        }
+        if !self.path_prefixes().is_empty() {
+            write!(f, " under {}", self.path_prefixes().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for NetworkGrant {
+    fn from(host: &str) -> Self {
+        Self::Host(host.into())
+    }
+}
+
+impl From<String> for NetworkGrant {
+    fn from(host: String) -> Self {
+        Self::Host(host)
+    }
+}
+
+/// An unscoped grant equals its host string.
+impl PartialEq<&str> for NetworkGrant {
+    fn eq(&self, other: &&str) -> bool {
+        !self.is_scoped() && self.host() == *other
+    }
+}
+
+fn has_dot_segments(path: &str) -> bool {
+    path.split(['/', '?', '#']).any(|segment| {
+        let segment = segment.to_ascii_lowercase().replace("%2e", ".");
+        segment == "." || segment == ".."
+    })
+}
+
 impl CapabilitySpec {
     /// Compute a stable SHA-256 hash of this spec for cache keying.
     ///
@@ -37,6 +295,481 @@ impl CapabilitySpec {
         let result = hasher.finalize();
         hex::encode(result)
     }
+
+    /// Check that the spec is well-formed enough to gate and build.
+    ///
+    /// Collects every violation rather than stopping at the first, so callers
+    /// can report them field by field.
+    pub fn validate(&self) -> Result<(), SpecValidationError> {
+        let mut errors = Vec::new();
+        let mut fail = |field: String, message: &str| {
+            errors.push(FieldError {
+                field,
+                message: message.into(),
+            })
+        };
+
+        if self.name.is_empty() || self.name.len() > MAX_NAME_LEN {
+            fail(
+                "name".into(),
+                &format!("must be 1-{MAX_NAME_LEN} characters"),
+            );
+        } else if !is_snake_case(&self.name) {
+            fail(
+                "name".into(),
+                "must be snake_case (lowercase letters, digits, underscores; starting with a letter)",
+            );
+        } else if let Some(conflict) = reserved::conflict(&self.name) {
+            fail("name".into(), &conflict);
+        }
+
+        if self.description.trim().is_empty() {
+            fail("description".into(), "must not be empty");
+        } else if self.description.chars().count() > MAX_DESCRIPTION_LEN {
+            fail(
+                "description".into(),
+                &format!("must be at most {MAX_DESCRIPTION_LEN} characters"),
+            );
+        }
+
+        for (field, value) in [("inputs", &self.inputs), ("outputs", &self.outputs)] {
+            if !(value.is_null() || value.is_object()) {
+                fail(field.into(), "must be null or a JSON object");
+            }
+        }
+
+        for (i, grant) in self.constraints.network.iter().enumerate() {
+            if !is_plausible_host(grant.host()) {
+                fail(
+                    format!("constraints.network[{i}]"),
+                    "must be a hostname, optionally with a port (no scheme or path)",
+                );
+            }
+            for (j, method) in grant.methods().iter().enumerate() {
+                if !HTTP_METHODS.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+                    fail(
+                        format!("constraints.network[{i}].methods[{j}]"),
+                        &format!("must be one of {}", HTTP_METHODS.join(", ")),
+                    );
+                }
+            }
+            for (j, prefix) in grant.path_prefixes().iter().enumerate() {
+                if !prefix.starts_with('/')
+                    || prefix.chars().any(|c| c.is_whitespace() || c.is_control())
+                    || has_dot_segments(prefix)
+                {
+                    fail(
+                        format!("constraints.network[{i}].path_prefixes[{j}]"),
+                        "must be a path starting with '/', without whitespace or '.' segments",
+                    );
+                }
+            }
+        }
+        for (i, path) in self.constraints.storage.iter().enumerate() {
+            if path.trim().is_empty() || path.trim() != path || path.chars().any(char::is_control) {
+                fail(
+                    format!("constraints.storage[{i}]"),
+                    "must be a non-empty path without surrounding whitespace or control characters",
+                );
+            }
+        }
+        for (i, secret) in self.constraints.secrets.iter().enumerate() {
+            let valid = !secret.is_empty()
+                && secret
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+            if !valid {
+                fail(
+                    format!("constraints.secrets[{i}]"),
+                    "must be a non-empty name of letters, digits, '_', '-' or '.'",
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SpecValidationError { errors })
+        }
+    }
+
+    /// What changes going from `self` to `other`: input and output fields,
+    /// and network, storage, and secret constraint entries. Name and
+    /// description are not compared.
+    pub fn diff(&self, other: &CapabilitySpec) -> SpecDiff {
+        let (before, after) = (&self.constraints, &other.constraints);
+        let mut constraints = diff_entries("network", &before.network, &after.network);
+        for (key, before, after) in [
+            ("storage", &before.storage, &after.storage),
+            ("secrets", &before.secrets, &after.secrets),
+        ] {
+            constraints.extend(diff_entries(key, before, after));
+        }
+        SpecDiff {
+            inputs: diff_fields(&self.inputs, &other.inputs),
+            outputs: diff_fields(&self.outputs, &other.outputs),
+            constraints,
+        }
+    }
+}
+
+impl CapabilitySpec {
+    /// Start a spec in code. Fields are declared as JSON Schema, and
+    /// [`build`](CapabilitySpecBuilder::build) validates the result.
+    pub fn builder() -> CapabilitySpecBuilder {
+        CapabilitySpecBuilder::default()
+    }
+
+    /// Deserialize a spec without validating it, for input that is repaired
+    /// afterwards. For `#[serde(deserialize_with = "...")]`.
+    pub fn deserialize_unchecked<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        RawCapabilitySpec::deserialize(deserializer).map(RawCapabilitySpec::into_unchecked)
+    }
+}
+
+/// The JSON Schema `type` of a field declared with [`CapabilitySpecBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Object,
+    Array,
+}
+
+impl InputType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Object => "object",
+            Self::Array => "array",
+        }
+    }
+}
+
+/// The schema of one field declared with [`CapabilitySpecBuilder`]. An
+/// [`InputType`] converts to a field with just a `type`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field(Map<String, Value>);
+
+impl Field {
+    pub fn new(ty: InputType) -> Self {
+        Self(Map::from_iter([("type".into(), ty.as_str().into())]))
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.0
+            .insert("description".into(), description.into().into());
+        self
+    }
+
+    /// Limit the field to `values` (JSON Schema `enum`).
+    pub fn with_enum<V: Into<Value>>(mut self, values: impl IntoIterator<Item = V>) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.0.insert("enum".into(), Value::Array(values));
+        self
+    }
+
+    pub fn with_default(mut self, value: impl Into<Value>) -> Self {
+        self.0.insert("default".into(), value.into());
+        self
+    }
+
+    pub fn into_schema(self) -> Value {
+        Value::Object(self.0)
+    }
+}
+
+impl From<InputType> for Field {
+    fn from(ty: InputType) -> Self {
+        Self::new(ty)
+    }
+}
+
+/// Builds a [`CapabilitySpec`]; see [`CapabilitySpec::builder`].
+///
+/// ```
+/// use girt_core::spec::{CapabilitySpec, InputType};
+///
+/// let spec = CapabilitySpec::builder()
+///     .name("github_issues")
+///     .description("List the open issues of a GitHub repository")
+///     .input("repo", InputType::String, true)
+///     .output("issues", InputType::Array)
+///     .allow_host("api.github.com")
+///     .secret("GITHUB_TOKEN")
+///     .build()
+///     .unwrap();
+/// assert_eq!(spec.inputs["required"], serde_json::json!(["repo"]));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct CapabilitySpecBuilder {
+    name: String,
+    description: String,
+    inputs: Value,
+    outputs: Value,
+    constraints: CapabilityConstraints,
+}
+
+impl CapabilitySpecBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Declare an input field, replacing any of the same name.
+    pub fn input(
+        mut self,
+        name: impl Into<String>,
+        field: impl Into<Field>,
+        required: bool,
+    ) -> Self {
+        let name = name.into();
+        let schema = object_schema(&mut self.inputs);
+        properties(schema).insert(name.clone(), field.into().into_schema());
+        let mut listed = match schema.remove("required") {
+            Some(Value::Array(listed)) => listed,
+            _ => Vec::new(),
+        };
+        listed.retain(|entry| entry.as_str() != Some(name.as_str()));
+        if required {
+            listed.push(name.into());
+        }
+        if !listed.is_empty() {
+            schema.insert("required".into(), Value::Array(listed));
+        }
+        self
+    }
+
+    /// Declare an output field, replacing any of the same name.
+    pub fn output(mut self, name: impl Into<String>, field: impl Into<Field>) -> Self {
+        let schema = object_schema(&mut self.outputs);
+        properties(schema).insert(name.into(), field.into().into_schema());
+        self
+    }
+
+    /// Use `schema` as the inputs. Fields declared afterwards are added to
+    /// its `properties`.
+    pub fn inputs_schema(mut self, schema: Value) -> Self {
+        self.inputs = schema;
+        self
+    }
+
+    /// Use `schema` as the outputs, like [`inputs_schema`](Self::inputs_schema).
+    pub fn outputs_schema(mut self, schema: Value) -> Self {
+        self.outputs = schema;
+        self
+    }
+
+    /// Allow a host, or a [`NetworkGrant`] scoped to methods and paths.
+    pub fn allow_host(mut self, grant: impl Into<NetworkGrant>) -> Self {
+        self.constraints.network.push(grant.into());
+        self
+    }
+
+    pub fn storage(mut self, path: impl Into<String>) -> Self {
+        self.constraints.storage.push(path.into());
+        self
+    }
+
+    pub fn secret(mut self, name: impl Into<String>) -> Self {
+        self.constraints.secrets.push(name.into());
+        self
+    }
+
+    /// The spec, if it passes [`CapabilitySpec::validate`].
+    pub fn build(self) -> Result<CapabilitySpec, SpecValidationError> {
+        CapabilitySpec::try_from(RawCapabilitySpec {
+            name: self.name,
+            description: self.description,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            constraints: self.constraints,
+        })
+    }
+}
+
+/// `value` as a JSON Schema object, made into `{"type": "object"}` if it is
+/// not an object yet.
+fn object_schema(value: &mut Value) -> &mut Map<String, Value> {
+    if !value.is_object() {
+        *value = serde_json::json!({"type": "object"});
+    }
+    match value {
+        Value::Object(schema) => schema,
+        _ => unreachable!("just made an object"),
+    }
+}
+
+fn properties(schema: &mut Map<String, Value>) -> &mut Map<String, Value> {
+    let properties = schema
+        .entry("properties")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !properties.is_object() {
+        *properties = Value::Object(Map::new());
+    }
+    match properties {
+        Value::Object(properties) => properties,
+        _ => unreachable!("just made an object"),
+    }
+}
+
+fn is_snake_case(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Hostname, IP, or wildcard pattern with an optional port. Wildcards are
+/// accepted here; whether they are allowed is a policy decision.
+fn is_plausible_host(host: &str) -> bool {
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) => (name, Some(port)),
+        None => (host, None),
+    };
+    let port_ok =
+        port.is_none_or(|p| !p.is_empty() && p.len() <= 5 && p.chars().all(|c| c.is_ascii_digit()));
+    let name_ok = !name.is_empty()
+        && name.len() <= 253
+        && !name.starts_with(['.', '-'])
+        && !name.ends_with(['.', '-'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '*'));
+    port_ok && name_ok
+}
+
+/// How one spec differs from another, from [`CapabilitySpec::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecDiff {
+    /// Input fields, keyed by field name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<SpecChange>,
+    /// Output fields, keyed by field name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<SpecChange>,
+    /// Constraint entries, keyed by `network`, `storage`, or `secrets`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<SpecChange>,
+}
+
+impl SpecDiff {
+    /// The two specs declare the same fields and constraints.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.outputs.is_empty() && self.constraints.is_empty()
+    }
+
+    /// Every change is a removal: the newer spec asks for nothing the older
+    /// one lacks. True for an empty diff.
+    pub fn only_removes(&self) -> bool {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .chain(&self.constraints)
+            .all(|change| matches!(change, SpecChange::Removed { .. }))
+    }
+}
+
+/// One entry of a [`SpecDiff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SpecChange {
+    Added {
+        key: String,
+        value: serde_json::Value,
+    },
+    Removed {
+        key: String,
+        value: serde_json::Value,
+    },
+    Changed {
+        key: String,
+        before: serde_json::Value,
+        after: serde_json::Value,
+    },
+}
+
+/// The fields of an `inputs` or `outputs` value: the `properties` of a JSON
+/// Schema, or the object itself for the `{"field": "type"}` shorthand.
+fn schema_fields(value: &serde_json::Value) -> Option<&serde_json::Map<String, serde_json::Value>> {
+    value
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .or_else(|| value.as_object())
+}
+
+fn diff_fields(before: &serde_json::Value, after: &serde_json::Value) -> Vec<SpecChange> {
+    let empty = serde_json::Map::new();
+    let (Some(old), Some(new)) = (
+        schema_fields(before).or(before.is_null().then_some(&empty)),
+        schema_fields(after).or(after.is_null().then_some(&empty)),
+    ) else {
+        // Not field maps; report the value as a whole.
+        if before == after {
+            return Vec::new();
+        }
+        return vec![SpecChange::Changed {
+            key: String::new(),
+            before: before.clone(),
+            after: after.clone(),
+        }];
+    };
+
+    let mut changes = Vec::new();
+    for (key, value) in old {
+        match new.get(key) {
+            None => changes.push(SpecChange::Removed {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            Some(changed) if changed != value => changes.push(SpecChange::Changed {
+                key: key.clone(),
+                before: value.clone(),
+                after: changed.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in new {
+        if !old.contains_key(key) {
+            changes.push(SpecChange::Added {
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn diff_entries<T: PartialEq + Serialize>(key: &str, before: &[T], after: &[T]) -> Vec<SpecChange> {
+    let value = |e: &T| serde_json::to_value(e).unwrap_or_default();
+    let removed = before
+        .iter()
+        .filter(|e| !after.contains(e))
+        .map(|e| SpecChange::Removed {
+            key: key.into(),
+            value: value(e),
+        });
+    let added = after
+        .iter()
+        .filter(|e| !before.contains(e))
+        .map(|e| SpecChange::Added {
+            key: key.into(),
+            value: value(e),
+        });
+    removed.chain(added).collect()
 }
 
 /// An execution request describing a tool invocation being evaluated.
@@ -45,12 +778,21 @@ pub struct ExecutionRequest {
     pub tool_name: String,
     #[serde(default)]
     pub arguments: serde_json::Value,
+    /// What the proxy knows about the invoked tool. Not part of the hash.
+    #[serde(skip)]
+    pub tool: Option<ToolProfile>,
 }
 
 impl ExecutionRequest {
-    /// Compute a hash for cache keying.
+    /// Compute a hash for cache keying. Argument object keys are sorted
+    /// first, so the same arguments in a different order hash the same.
     pub fn request_hash(&self) -> String {
-        let canonical = serde_json::to_string(self).unwrap_or_default();
+        let normalized = Self {
+            tool_name: self.tool_name.clone(),
+            arguments: sorted_keys(&self.arguments),
+            tool: None,
+        };
+        let canonical = serde_json::to_string(&normalized).unwrap_or_default();
         let mut hasher = Sha256::new();
         hasher.update(canonical.as_bytes());
         let result = hasher.finalize();
@@ -58,20 +800,153 @@ impl ExecutionRequest {
     }
 }
 
-/// Union type for what a gate evaluates.
-#[derive(Debug, Clone)]
+fn sorted_keys(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, v)| (key.clone(), sorted_keys(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(sorted_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Word stems in a tool description that suggest a call changes
+/// something. Matched against the start of each word, so "creat" covers
+/// "creates" and "creating".
+const WRITE_VERBS: &[&str] = &[
+    "writ", "overwrit", "delet", "remov", "creat", "updat", "modif", "insert", "append", "send",
+    "sent", "post", "put", "upload", "submit", "publish", "deploy", "install", "execut", "kill",
+    "drop", "move", "renam", "commit", "push", "merg", "transfer",
+];
+
+/// Runtime metadata about an invoked tool, used to decide whether an
+/// Execution Gate Allow may be reused for an identical call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolProfile {
+    /// The tool declares that calls have no side effects.
+    pub idempotent: bool,
+    pub description: String,
+    /// Secrets or environment variables the tool can read.
+    pub secrets: Vec<String>,
+}
+
+impl ToolProfile {
+    /// Read-only by declaration, with no secrets, and nothing in the
+    /// description that reads like a write.
+    pub fn allow_is_reusable(&self) -> bool {
+        self.idempotent && self.secrets.is_empty() && !mentions_write(&self.description)
+    }
+}
+
+fn mentions_write(description: &str) -> bool {
+    description
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(str::to_ascii_lowercase)
+        .any(|word| WRITE_VERBS.iter().any(|verb| word.starts_with(verb)))
+}
+
+/// Who asked for a capability.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestSource {
+    /// An agent calling `request_capability` over MCP.
+    #[default]
+    Agent,
+    /// A person, e.g. writing a request into the queue directly.
+    Operator,
+    /// `girt enqueue`.
+    Cli,
+    /// A hook or tool sync.
+    Hook,
+}
+
+impl RequestSource {
+    /// The name used in girt.toml and serialized requests.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RequestSource::Agent => "agent",
+            RequestSource::Operator => "operator",
+            RequestSource::Cli => "cli",
+            RequestSource::Hook => "hook",
+        }
+    }
+}
+
+impl std::fmt::Display for RequestSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Union type for what a gate evaluates. Serialized as the spec or request
+/// with a `gate` field naming its [`GateKind`](crate::decision::GateKind).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "gate", rename_all = "snake_case")]
 pub enum GateInput {
-    Creation(CapabilitySpec),
+    Creation {
+        #[serde(flatten)]
+        spec: CapabilitySpec,
+        /// Absent in inputs serialized before it was recorded, which read
+        /// as [`RequestSource::Agent`].
+        #[serde(default)]
+        source: RequestSource,
+    },
     Execution(ExecutionRequest),
 }
 
 impl GateInput {
+    /// A Creation Gate input for `spec`, requested by `source`.
+    pub fn creation(spec: CapabilitySpec, source: RequestSource) -> Self {
+        GateInput::Creation { spec, source }
+    }
+
+    /// Cache key for this input. A Creation input hashes its spec alone,
+    /// whatever the source.
     pub fn hash(&self) -> String {
         match self {
-            GateInput::Creation(spec) => spec.spec_hash(),
+            GateInput::Creation { spec, .. } => spec.spec_hash(),
             GateInput::Execution(req) => req.request_hash(),
         }
     }
+
+    /// Whether an Allow for this input may be reused for an identical later
+    /// request. Only Execution requests for tools whose
+    /// [`ToolProfile::allow_is_reusable`] qualify.
+    pub fn allow_is_reusable(&self) -> bool {
+        match self {
+            GateInput::Creation { .. } => false,
+            GateInput::Execution(req) => req
+                .tool
+                .as_ref()
+                .is_some_and(ToolProfile::allow_is_reusable),
+        }
+    }
+
+    /// Who requested a Creation input; `None` for an Execution input.
+    pub fn source(&self) -> Option<RequestSource> {
+        match self {
+            GateInput::Creation { source, .. } => Some(*source),
+            GateInput::Execution(_) => None,
+        }
+    }
+
+    /// The tool this input is about: the requested spec name (Creation) or
+    /// the invoked tool name (Execution).
+    pub fn subject(&self) -> &str {
+        match self {
+            GateInput::Creation { spec, .. } => &spec.name,
+            GateInput::Execution(req) => &req.tool_name,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -113,4 +988,563 @@ mod tests {
 
         assert_ne!(spec1.spec_hash(), spec2.spec_hash());
     }
+
+    #[test]
+    fn request_hash_ignores_argument_key_order() {
+        let request = |arguments| ExecutionRequest {
+            tool_name: "weather_lookup".into(),
+            arguments,
+            tool: None,
+        };
+        let a = request(serde_json::json!({"city": "Oslo", "units": {"temp": "c", "wind": "m/s"}}));
+        let b = request(serde_json::json!({"units": {"wind": "m/s", "temp": "c"}, "city": "Oslo"}));
+        let c =
+            request(serde_json::json!({"city": "Bergen", "units": {"temp": "c", "wind": "m/s"}}));
+
+        assert_eq!(a.request_hash(), b.request_hash());
+        assert_ne!(a.request_hash(), c.request_hash());
+        let profiled = ExecutionRequest {
+            tool: Some(ToolProfile::default()),
+            ..a.clone()
+        };
+        assert_eq!(profiled.request_hash(), a.request_hash());
+    }
+
+    #[test]
+    fn only_read_only_tools_without_secrets_have_reusable_allows() {
+        let read_only = ToolProfile {
+            idempotent: true,
+            description: "Look up the current weather for a city".into(),
+            secrets: vec![],
+        };
+        assert!(read_only.allow_is_reusable());
+
+        for profile in [
+            ToolProfile {
+                idempotent: false,
+                ..read_only.clone()
+            },
+            ToolProfile {
+                secrets: vec!["WEATHER_API_KEY".into()],
+                ..read_only.clone()
+            },
+            ToolProfile {
+                description: "Creates a GitHub issue".into(),
+                ..read_only.clone()
+            },
+            ToolProfile {
+                description: "Fetch a URL, then POST the body to a webhook".into(),
+                ..read_only.clone()
+            },
+        ] {
+            assert!(!profile.allow_is_reusable(), "{profile:?}");
+        }
+    }
+
+    #[test]
+    fn diff_reports_an_added_network_host() {
+        let before = valid_spec();
+        let mut after = valid_spec();
+        after.constraints.network.push("uploads.github.com".into());
+
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.constraints,
+            vec![SpecChange::Added {
+                key: "network".into(),
+                value: "uploads.github.com".into(),
+            }]
+        );
+        assert!(diff.inputs.is_empty() && diff.outputs.is_empty());
+        assert_eq!(
+            serde_json::to_value(&diff).unwrap(),
+            serde_json::json!({"constraints": [
+                {"change": "added", "key": "network", "value": "uploads.github.com"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn diff_reports_a_removed_input_field() {
+        let mut before = valid_spec();
+        before.inputs = serde_json::json!({
+            "type": "object",
+            "properties": {"repo": {"type": "string"}, "state": {"type": "string"}},
+        });
+        let mut after = before.clone();
+        after.inputs["properties"]
+            .as_object_mut()
+            .unwrap()
+            .remove("state");
+
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.inputs,
+            vec![SpecChange::Removed {
+                key: "state".into(),
+                value: serde_json::json!({"type": "string"}),
+            }]
+        );
+        assert!(diff.constraints.is_empty());
+        assert!(diff.only_removes());
+        assert!(!after.diff(&before).only_removes());
+    }
+
+    #[test]
+    fn identical_specs_have_an_empty_diff() {
+        let spec = valid_spec();
+        let diff = spec.diff(&spec.clone());
+        assert!(diff.is_empty());
+        assert!(diff.only_removes());
+        assert_eq!(serde_json::to_value(&diff).unwrap(), serde_json::json!({}));
+    }
+
+    fn valid_spec() -> CapabilitySpec {
+        CapabilitySpec::builder()
+            .name("github_issues")
+            .description("Fetch GitHub issues")
+            .inputs_schema(serde_json::json!({"repo": "string"}))
+            .allow_host("api.github.com")
+            .allow_host("localhost:8080")
+            .storage("/tmp/cache")
+            .secret("GITHUB_TOKEN")
+            .build()
+            .unwrap()
+    }
+
+    fn failed_fields(spec: &CapabilitySpec) -> Vec<String> {
+        spec.validate()
+            .unwrap_err()
+            .errors
+            .into_iter()
+            .map(|e| e.field)
+            .collect()
+    }
+
+    #[test]
+    fn valid_spec_passes() {
+        assert!(valid_spec().validate().is_ok());
+    }
+
+    #[test]
+    fn name_must_be_snake_case_and_bounded() {
+        for bad in [
+            "",
+            "Bad Name",
+            "tool-name",
+            "1tool",
+            "_tool",
+            "tool🚀",
+            "CamelCase",
+        ] {
+            let spec = CapabilitySpec {
+                name: bad.into(),
+                ..valid_spec()
+            };
+            assert_eq!(failed_fields(&spec), vec!["name"], "{bad:?}");
+        }
+        let spec = CapabilitySpec {
+            name: "a".repeat(MAX_NAME_LEN + 1),
+            ..valid_spec()
+        };
+        assert_eq!(failed_fields(&spec), vec!["name"]);
+        let spec = CapabilitySpec {
+            name: "a".repeat(MAX_NAME_LEN),
+            ..valid_spec()
+        };
+        assert!(spec.validate().is_ok());
+    }
+
+    #[test]
+    fn name_must_not_be_reserved() {
+        let spec = CapabilitySpec {
+            name: "request_capability".into(),
+            ..valid_spec()
+        };
+        assert_eq!(
+            spec.validate().unwrap_err().to_string(),
+            "invalid capability spec: name: clashes with the GIRT built-in `request_capability`"
+        );
+        let spec = CapabilitySpec {
+            name: "girt_build_status".into(),
+            ..valid_spec()
+        };
+        assert_eq!(failed_fields(&spec), vec!["name"]);
+    }
+
+    #[test]
+    fn description_must_be_present_and_bounded() {
+        for bad in [
+            String::new(),
+            "   ".into(),
+            "x".repeat(MAX_DESCRIPTION_LEN + 1),
+        ] {
+            let spec = CapabilitySpec {
+                description: bad,
+                ..valid_spec()
+            };
+            assert_eq!(failed_fields(&spec), vec!["description"]);
+        }
+    }
+
+    #[test]
+    fn inputs_and_outputs_must_be_objects_or_null() {
+        let spec = CapabilitySpec {
+            inputs: serde_json::json!("a string"),
+            outputs: serde_json::json!([1, 2]),
+            ..valid_spec()
+        };
+        assert_eq!(failed_fields(&spec), vec!["inputs", "outputs"]);
+    }
+
+    #[test]
+    fn constraint_entries_must_be_plausible() {
+        let spec = CapabilitySpec {
+            constraints: CapabilityConstraints {
+                network: vec![
+                    "https://api.github.com".into(),
+                    "".into(),
+                    "*.example.com".into(),
+                    "host:99999999".into(),
+                ],
+                storage: vec![" /tmp".into(), "".into()],
+                secrets: vec!["MY TOKEN".into()],
+            },
+            ..valid_spec()
+        };
+        assert_eq!(
+            failed_fields(&spec),
+            vec![
+                "constraints.network[0]",
+                "constraints.network[1]",
+                "constraints.network[3]",
+                "constraints.storage[0]",
+                "constraints.storage[1]",
+                "constraints.secrets[0]",
+            ]
+        );
+    }
+
+    #[test]
+    fn network_grants_parse_from_strings_and_objects() {
+        let constraints: CapabilityConstraints = serde_json::from_value(serde_json::json!({
+            "network": [
+                "api.open-meteo.com",
+                {"host": "api.github.com", "methods": ["GET"], "path_prefixes": ["/repos/"]},
+                {"host": "example.com"},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(constraints.network[0], "api.open-meteo.com");
+        assert_eq!(
+            constraints.network[1],
+            NetworkGrant::scoped("api.github.com", vec!["GET".into()], vec!["/repos/".into()])
+        );
+        assert!(!constraints.network[2].is_scoped());
+        assert_eq!(
+            constraints.network[1].to_string(),
+            "api.github.com (GET) under /repos/"
+        );
+
+        // A spec with only bare hosts hashes as it did before scoping.
+        let json = serde_json::to_value(&valid_spec().constraints).unwrap();
+        assert_eq!(
+            json["network"],
+            serde_json::json!(["api.github.com", "localhost:8080"])
+        );
+    }
+
+    #[test]
+    fn scoped_grants_allow_and_cover_only_their_scope() {
+        let grant = NetworkGrant::scoped(
+            "api.github.com",
+            vec!["GET".into(), "HEAD".into()],
+            vec!["/repos/".into()],
+        );
+        assert!(grant.allows("get", "/repos/a/b/issues"));
+        assert!(!grant.allows("POST", "/repos/a/b/issues"));
+        assert!(!grant.allows("GET", "/user"));
+        assert!(!grant.allows("GET", "/repos/../user"));
+        assert!(!grant.allows("GET", "/repos/%2E%2E/user"));
+        assert!(NetworkGrant::from("api.github.com").allows("DELETE", "/anything"));
+
+        let narrower = NetworkGrant::scoped(
+            "API.github.com",
+            vec!["GET".into()],
+            vec!["/repos/a/".into()],
+        );
+        assert!(grant.covers(&narrower));
+        assert!(!narrower.covers(&grant));
+        assert!(!grant.covers(&"api.github.com".into()));
+        assert!(NetworkGrant::from("api.github.com").covers(&grant));
+        assert!(!grant.covers(&NetworkGrant::scoped(
+            "uploads.github.com",
+            vec!["GET".into()],
+            vec!["/repos/".into()],
+        )));
+    }
+
+    #[test]
+    fn additions_over_lists_what_constraints_add() {
+        let requested = CapabilityConstraints {
+            network: vec![NetworkGrant::scoped(
+                "api.github.com",
+                vec!["GET".into()],
+                vec!["/repos/".into()],
+            )],
+            storage: vec!["/data".into()],
+            secrets: vec!["GITHUB_TOKEN".into()],
+        };
+        let narrowed = CapabilityConstraints {
+            network: vec![NetworkGrant::scoped(
+                "api.github.com",
+                vec!["GET".into()],
+                vec!["/repos/a/".into()],
+            )],
+            ..Default::default()
+        };
+        assert!(narrowed.additions_over(&requested).is_empty());
+
+        let widened = CapabilityConstraints {
+            network: vec!["api.github.com".into()],
+            storage: vec!["/data".into(), "/home".into()],
+            secrets: vec!["GITHUB_TOKEN".into(), "AWS_SECRET".into()],
+        };
+        assert_eq!(
+            widened.additions_over(&requested),
+            [
+                "network api.github.com",
+                "storage /home",
+                "secret AWS_SECRET"
+            ]
+        );
+    }
+
+    #[test]
+    fn grant_methods_and_path_prefixes_must_be_plausible() {
+        let mut spec = valid_spec();
+        spec.constraints.network = vec![NetworkGrant::scoped(
+            "api.github.com",
+            vec!["get".into(), "FETCH".into()],
+            vec!["/repos/".into(), "repos".into(), "/a/../b".into()],
+        )];
+        assert_eq!(
+            failed_fields(&spec),
+            vec![
+                "constraints.network[0].methods[1]",
+                "constraints.network[0].path_prefixes[1]",
+                "constraints.network[0].path_prefixes[2]",
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_error_message_is_stable() {
+        let spec = CapabilitySpec {
+            name: "Bad Name".into(),
+            description: String::new(),
+            ..valid_spec()
+        };
+        assert_eq!(
+            spec.validate().unwrap_err().to_string(),
+            "invalid capability spec: \
+             name: must be snake_case (lowercase letters, digits, underscores; starting with a letter); \
+             description: must not be empty"
+        );
+    }
+
+    #[test]
+    fn builder_matches_the_literal_it_replaces() {
+        let built = CapabilitySpec::builder()
+            .name("greet")
+            .description("Greet someone by name")
+            .input("name", InputType::String, true)
+            .output("greeting", InputType::String)
+            .build()
+            .unwrap();
+        let literal = CapabilitySpec {
+            name: "greet".into(),
+            description: "Greet someone by name".into(),
+            inputs: serde_json::json!({
+                "type": "object",
+                "properties": {"name": {"type": "string"}},
+                "required": ["name"],
+            }),
+            outputs: serde_json::json!({
+                "type": "object",
+                "properties": {"greeting": {"type": "string"}},
+            }),
+            constraints: CapabilityConstraints::default(),
+        };
+        assert_eq!(built, literal);
+
+        let literal = CapabilitySpec {
+            name: "github_issues".into(),
+            description: "Fetch GitHub issues".into(),
+            inputs: serde_json::json!({"repo": "string"}),
+            outputs: serde_json::Value::Null,
+            constraints: CapabilityConstraints {
+                network: vec!["api.github.com".into(), "localhost:8080".into()],
+                storage: vec!["/tmp/cache".into()],
+                secrets: vec!["GITHUB_TOKEN".into()],
+            },
+        };
+        assert_eq!(valid_spec(), literal);
+        assert_eq!(valid_spec().spec_hash(), literal.spec_hash());
+    }
+
+    #[test]
+    fn builder_fields_carry_enums_defaults_and_descriptions() {
+        let spec = CapabilitySpec::builder()
+            .name("github_api")
+            .description("Query GitHub")
+            .input(
+                "resource",
+                Field::new(InputType::String).with_enum(["issues", "pulls"]),
+                true,
+            )
+            .input("owner", InputType::String, true)
+            .input(
+                "page",
+                Field::new(InputType::Integer)
+                    .with_default(1)
+                    .with_description("Page to fetch"),
+                false,
+            )
+            // Redeclaring a field replaces it, requiredness included.
+            .input("owner", InputType::String, false)
+            .allow_host(NetworkGrant::scoped(
+                "api.github.com",
+                vec!["GET".into()],
+                vec!["/repos/".into()],
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(
+            spec.inputs,
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "resource": {"type": "string", "enum": ["issues", "pulls"]},
+                    "owner": {"type": "string"},
+                    "page": {"type": "integer", "default": 1, "description": "Page to fetch"},
+                },
+                "required": ["resource"],
+            })
+        );
+        assert!(spec.outputs.is_null());
+        assert!(spec.constraints.network[0].is_scoped());
+    }
+
+    #[test]
+    fn builder_adds_fields_to_a_given_schema() {
+        let spec = CapabilitySpec::builder()
+            .name("search")
+            .description("Search documents")
+            .inputs_schema(serde_json::json!({
+                "type": "object",
+                "properties": {"query": {"type": "string"}},
+                "required": ["query"],
+                "additionalProperties": false,
+            }))
+            .input("limit", InputType::Integer, true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            spec.inputs["required"],
+            serde_json::json!(["query", "limit"])
+        );
+        assert_eq!(spec.inputs["additionalProperties"], false);
+        assert_eq!(spec.inputs["properties"]["limit"]["type"], "integer");
+    }
+
+    #[test]
+    fn builder_refuses_an_invalid_spec() {
+        let err = CapabilitySpec::builder()
+            .name("Fetch Issues")
+            .description("Fetch GitHub issues")
+            .allow_host("https://api.github.com")
+            .secret("GITHUB TOKEN")
+            .build()
+            .unwrap_err();
+        let fields: Vec<&str> = err.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["name", "constraints.network[0]", "constraints.secrets[0]"]
+        );
+    }
+
+    #[test]
+    fn deserializing_validates_and_names_the_fields() {
+        let err = serde_json::from_str::<CapabilitySpec>(
+            r#"{"name": "Bad Name", "description": "x", "constraints": {"network": ["api.github.com", "http://x/y"]}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid capability spec: \
+             name: must be snake_case (lowercase letters, digits, underscores; starting with a letter); \
+             constraints.network[1]: must be a hostname, optionally with a port (no scheme or path)"
+        );
+
+        let err = serde_json::from_value::<CapabilitySpec>(serde_json::json!({
+            "name": "fetch",
+            "description": "  ",
+            "inputs": ["url"],
+        }))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid capability spec: description: must not be empty; \
+             inputs: must be null or a JSON object"
+        );
+
+        let err = serde_json::from_value::<CapabilitySpec>(serde_json::json!({"name": "fetch"}))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "missing field `description`");
+    }
+
+    #[test]
+    fn every_embedded_spec_is_validated() {
+        let gate: Result<GateInput, _> = serde_json::from_value(serde_json::json!({
+            "gate": "creation",
+            "name": "girt_status",
+            "description": "Report status",
+        }));
+        assert!(
+            gate.unwrap_err()
+                .to_string()
+                .contains("name: starts with `girt_`")
+        );
+
+        let valid = serde_json::to_value(valid_spec()).unwrap();
+        assert_eq!(
+            serde_json::from_value::<CapabilitySpec>(valid).unwrap(),
+            valid_spec()
+        );
+    }
+
+    #[test]
+    fn unchecked_deserialization_keeps_an_invalid_spec() {
+        #[derive(Deserialize)]
+        struct Draft {
+            #[serde(deserialize_with = "CapabilitySpec::deserialize_unchecked")]
+            spec: CapabilitySpec,
+        }
+        let draft: Draft = serde_json::from_value(serde_json::json!({
+            "spec": {"name": " fetch ", "description": "Fetch a URL"},
+        }))
+        .unwrap();
+        assert_eq!(draft.spec.name, " fetch ");
+        assert!(draft.spec.inputs.is_null());
+
+        let raw: RawCapabilitySpec = serde_json::from_value(serde_json::json!({
+            "name": " fetch ",
+            "description": "Fetch a URL",
+        }))
+        .unwrap();
+        let err = CapabilitySpec::try_from(raw.clone()).unwrap_err();
+        assert_eq!(err.errors[0].field, "name");
+        assert_eq!(raw.into_unchecked(), draft.spec);
+    }
 }
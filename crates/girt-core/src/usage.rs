@@ -0,0 +1,176 @@
+//! What tool calls cost to run, for sizing `ResourceTier`s to real tools.
+//!
+//! The runtime measures every call: wall time, how far the largest linear
+//! memory grew, and, with `[runtime] fuel_metering` on, the fuel burned.
+//! The proxy keeps the measurements with each tool's recorded calls, and
+//! `girt tools stats --resources` reduces them to a [`UsageSummary`]. A
+//! [`HighUsageTracker`] flags tools that keep running close to the memory
+//! limit.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Share of the memory limit above which a call counts as high usage.
+pub const HIGH_USAGE_FRACTION: f64 = 0.8;
+
+/// High-usage calls in a row before a tool is flagged, and again after
+/// each further run of this many.
+pub const HIGH_USAGE_STREAK: u32 = 3;
+
+/// Resources one tool call used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub wall_time_ms: u64,
+    /// Size the largest of the component's memories reached.
+    pub peak_memory_bytes: u64,
+    /// Size any one memory was allowed to reach.
+    pub memory_limit_bytes: u64,
+    /// `None` unless fuel metering is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel_consumed: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// Peak memory as a share of the limit; 0 without a limit.
+    pub fn memory_fraction(&self) -> f64 {
+        if self.memory_limit_bytes == 0 {
+            return 0.0;
+        }
+        self.peak_memory_bytes as f64 / self.memory_limit_bytes as f64
+    }
+}
+
+/// 95th percentile (nearest rank) of `values`; `None` when there are none.
+pub fn p95(values: impl IntoIterator<Item = u64>) -> Option<u64> {
+    let mut values: Vec<u64> = values.into_iter().collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let rank = (values.len() * 95).div_ceil(100);
+    Some(values[rank.saturating_sub(1)])
+}
+
+/// p95 resource usage over a tool's measured calls.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageSummary {
+    /// Calls measured.
+    pub calls: usize,
+    pub p95_wall_time_ms: u64,
+    pub p95_peak_memory_bytes: u64,
+    pub max_peak_memory_bytes: u64,
+    /// The limit at the most recent call.
+    pub memory_limit_bytes: u64,
+    /// Over the calls that were metered; `None` if none were.
+    pub p95_fuel_consumed: Option<u64>,
+}
+
+impl UsageSummary {
+    /// Summarize `usage`, oldest first; `None` when it is empty.
+    pub fn new<'a>(usage: impl IntoIterator<Item = &'a ResourceUsage>) -> Option<Self> {
+        let usage: Vec<&ResourceUsage> = usage.into_iter().collect();
+        let latest = usage.last()?;
+        Some(Self {
+            calls: usage.len(),
+            p95_wall_time_ms: p95(usage.iter().map(|u| u.wall_time_ms))?,
+            p95_peak_memory_bytes: p95(usage.iter().map(|u| u.peak_memory_bytes))?,
+            max_peak_memory_bytes: usage.iter().map(|u| u.peak_memory_bytes).max()?,
+            memory_limit_bytes: latest.memory_limit_bytes,
+            p95_fuel_consumed: p95(usage.iter().filter_map(|u| u.fuel_consumed)),
+        })
+    }
+
+    /// p95 peak memory as a share of the limit.
+    pub fn p95_memory_fraction(&self) -> f64 {
+        ResourceUsage {
+            peak_memory_bytes: self.p95_peak_memory_bytes,
+            memory_limit_bytes: self.memory_limit_bytes,
+            ..ResourceUsage::default()
+        }
+        .memory_fraction()
+    }
+}
+
+/// Counts each tool's high-usage calls in a row.
+#[derive(Debug, Default)]
+pub struct HighUsageTracker {
+    streaks: Mutex<HashMap<String, u32>>,
+}
+
+impl HighUsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note a call of `tool`. Returns the length of its high-usage streak
+    /// when that reaches a multiple of [`HIGH_USAGE_STREAK`], i.e. when the
+    /// tool is worth a warning.
+    pub fn observe(&self, tool: &str, usage: &ResourceUsage) -> Option<u32> {
+        let mut streaks = self.streaks.lock().unwrap_or_else(|e| e.into_inner());
+        if usage.memory_fraction() <= HIGH_USAGE_FRACTION {
+            streaks.remove(tool);
+            return None;
+        }
+        let streak = streaks.entry(tool.to_string()).or_default();
+        *streak += 1;
+        streak.is_multiple_of(HIGH_USAGE_STREAK).then_some(*streak)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn usage(peak_mib: u64, fuel: Option<u64>) -> ResourceUsage {
+        ResourceUsage {
+            wall_time_ms: peak_mib,
+            peak_memory_bytes: peak_mib * MIB,
+            memory_limit_bytes: 10 * MIB,
+            fuel_consumed: fuel,
+        }
+    }
+
+    #[test]
+    fn p95_is_the_nearest_rank() {
+        assert_eq!(p95([]), None);
+        assert_eq!(p95([7]), Some(7));
+        assert_eq!(p95(1..=100), Some(95));
+        assert_eq!(p95((1..=20).rev()), Some(19));
+    }
+
+    #[test]
+    fn summary_takes_p95_and_skips_unmetered_fuel() {
+        let calls: Vec<_> = (1..=20)
+            .map(|i| usage(i % 10, (i > 10).then_some(i * 100)))
+            .collect();
+        let summary = UsageSummary::new(&calls).unwrap();
+        assert_eq!(summary.calls, 20);
+        assert_eq!(summary.p95_peak_memory_bytes, 9 * MIB);
+        assert_eq!(summary.max_peak_memory_bytes, 9 * MIB);
+        assert_eq!(summary.memory_limit_bytes, 10 * MIB);
+        assert_eq!(summary.p95_fuel_consumed, Some(2000));
+        assert!((summary.p95_memory_fraction() - 0.9).abs() < 1e-9);
+
+        let unmetered = UsageSummary::new(&[usage(1, None)]).unwrap();
+        assert_eq!(unmetered.p95_fuel_consumed, None);
+        assert!(UsageSummary::new(&[]).is_none());
+    }
+
+    #[test]
+    fn tools_are_flagged_after_a_streak_of_high_usage() {
+        let tracker = HighUsageTracker::new();
+        let high = usage(9, None);
+        assert_eq!(tracker.observe("t", &high), None);
+        assert_eq!(tracker.observe("t", &high), None);
+        // A call at or under 80% breaks the streak.
+        assert_eq!(tracker.observe("t", &usage(8, None)), None);
+        assert_eq!(tracker.observe("other", &high), None);
+
+        let streaks: Vec<_> = (0..6).map(|_| tracker.observe("t", &high)).collect();
+        assert_eq!(streaks, [None, None, Some(3), None, None, Some(6)]);
+    }
+}
@@ -0,0 +1,350 @@
+//! The gates' wire format: round trips, golden samples, and the schema.
+//!
+//! `golden/gate.json` holds one serialized sample of each shape of
+//! [`Decision`], [`LayeredDecision`], and [`GateInput`]. A change to how any
+//! of them serializes fails `golden_samples_are_unchanged`. If the change is
+//! intended, update `schemas/gate.schema.json` to match and regenerate the
+//! samples with:
+//!
+//! ```text
+//! GIRT_UPDATE_GOLDEN=1 cargo test -p girt-core --test wire_format
+//! ```
+
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use girt_core::decision::{
+    Decision, DecisionLayer, DeferTarget, LayerOutcome, LayerTrace, LayeredDecision,
+};
+use girt_core::spec::{
+    CapabilityConstraints, CapabilitySpec, ExecutionRequest, GateInput, RequestSource,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+/// A serialized value and the schema `$defs` entry it must satisfy.
+struct Sample {
+    name: &'static str,
+    def: &'static str,
+    json: Value,
+}
+
+/// Serialize `value`, checking that it reads back unchanged.
+fn sample<T>(name: &'static str, def: &'static str, value: T) -> Sample
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let text = serde_json::to_string(&value).unwrap();
+    let back: T = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, value, "{name} does not round-trip");
+    Sample {
+        name,
+        def,
+        json: serde_json::from_str(&text).unwrap(),
+    }
+}
+
+fn samples() -> Vec<Sample> {
+    let denied = Decision::Deny {
+        reason: "matches deny pattern: shell_exec".into(),
+    };
+    vec![
+        sample("decision_allowed", "Decision", Decision::Allow),
+        sample("decision_denied", "Decision", denied.clone()),
+        sample(
+            "decision_allowed_modified",
+            "Decision",
+            Decision::AllowModified {
+                spec: CapabilitySpec {
+                    name: "weather_lookup".into(),
+                    description: "Current weather for a city".into(),
+                    inputs: json!({"type": "object"}),
+                    outputs: json!({"type": "object"}),
+                    constraints: CapabilityConstraints {
+                        network: vec!["api.open-meteo.com".into()],
+                        ..Default::default()
+                    },
+                },
+                changes: vec!["dropped the WEATHER_API_KEY secret".into()],
+            },
+        ),
+        sample(
+            "decision_deferred_registry_tool",
+            "Decision",
+            Decision::Defer {
+                target: DeferTarget::RegistryTool {
+                    registry: "ghcr.io/epiphytic/girt-tools".into(),
+                    tool_name: "weather_lookup".into(),
+                    version: "1.2.0".into(),
+                },
+            },
+        ),
+        sample(
+            "decision_deferred_use_cli",
+            "Decision",
+            Decision::Defer {
+                target: DeferTarget::UseCli {
+                    binary: "jq".into(),
+                    description: "JSON processor".into(),
+                    example_invocation: "jq '.items[]' data.json".into(),
+                },
+            },
+        ),
+        sample(
+            "decision_deferred_extend_tool",
+            "Decision",
+            Decision::Defer {
+                target: DeferTarget::ExtendTool {
+                    tool_name: "github_issues".into(),
+                    suggested_features: vec!["list labels".into()],
+                },
+            },
+        ),
+        sample(
+            "decision_ask",
+            "Decision",
+            Decision::Ask {
+                prompt: "Allow building a tool that reads ~/.ssh?".into(),
+                context: "requested storage: ~/.ssh".into(),
+            },
+        ),
+        sample(
+            "layered_decision",
+            "LayeredDecision",
+            LayeredDecision {
+                decision: denied,
+                layer: DecisionLayer::PolicyRules,
+                rationale: Some("deny list".into()),
+                consulted_layers: vec![
+                    LayerTrace {
+                        layer: DecisionLayer::Cache,
+                        duration_ms: 0,
+                        outcome: LayerOutcome::PassThrough,
+                    },
+                    LayerTrace {
+                        layer: DecisionLayer::PolicyRules,
+                        duration_ms: 2,
+                        outcome: LayerOutcome::Decided,
+                    },
+                ],
+            },
+        ),
+        sample(
+            "layered_decision_without_rationale",
+            "LayeredDecision",
+            LayeredDecision {
+                decision: Decision::Allow,
+                layer: DecisionLayer::LlmEvaluation,
+                rationale: None,
+                consulted_layers: vec![],
+            },
+        ),
+        sample(
+            "gate_input_creation",
+            "GateInput",
+            GateInput::creation(
+                CapabilitySpec {
+                    name: "weather_lookup".into(),
+                    description: "Current weather for a city".into(),
+                    inputs: json!({ "city": "string" }),
+                    outputs: json!({ "temperature_c": "number" }),
+                    constraints: CapabilityConstraints {
+                        network: vec!["api.open-meteo.com".into()],
+                        ..Default::default()
+                    },
+                },
+                RequestSource::Agent,
+            ),
+        ),
+        sample(
+            "gate_input_execution",
+            "GateInput",
+            GateInput::Execution(ExecutionRequest {
+                tool_name: "weather_lookup".into(),
+                arguments: json!({ "city": "Oslo" }),
+                tool: None,
+            }),
+        ),
+    ]
+}
+
+fn crate_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+fn read_json(relative: &str) -> Value {
+    let path = crate_path(relative);
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+    serde_json::from_str(&text).unwrap()
+}
+
+/// Check `value` against `schema`, for the keywords `gate.schema.json`
+/// uses. Any other keyword fails, so it cannot be silently ignored.
+fn validate(root: &Value, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    const KNOWN: &[&str] = &[
+        "$schema",
+        "$defs",
+        "$ref",
+        "title",
+        "description",
+        "allOf",
+        "oneOf",
+        "const",
+        "enum",
+        "type",
+        "minimum",
+        "required",
+        "properties",
+        "items",
+    ];
+    for keyword in schema.as_object().unwrap().keys() {
+        assert!(
+            KNOWN.contains(&keyword.as_str()),
+            "unsupported keyword {keyword}"
+        );
+    }
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let def = reference.strip_prefix("#/$defs/").expect("local $ref");
+        return validate(root, &root["$defs"][def], value, path);
+    }
+    for sub in schema["allOf"].as_array().into_iter().flatten() {
+        validate(root, sub, value, path)?;
+    }
+    if let Some(options) = schema["oneOf"].as_array() {
+        let matched = options
+            .iter()
+            .filter(|option| validate(root, option, value, path).is_ok())
+            .count();
+        if matched != 1 {
+            return Err(format!("{path}: matches {matched} oneOf options"));
+        }
+    }
+    if let Some(expected) = schema.get("const")
+        && value != expected
+    {
+        return Err(format!("{path}: expected {expected}, got {value}"));
+    }
+    if let Some(options) = schema["enum"].as_array()
+        && !options.contains(value)
+    {
+        return Err(format!("{path}: {value} is not one of {options:?}"));
+    }
+    if let Some(ty) = schema["type"].as_str() {
+        let matches = match ty {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            other => panic!("unsupported type {other}"),
+        };
+        if !matches {
+            return Err(format!("{path}: expected {ty}, got {value}"));
+        }
+    }
+    if let Some(minimum) = schema["minimum"].as_i64()
+        && value.as_i64().is_some_and(|n| n < minimum)
+    {
+        return Err(format!("{path}: {value} is below {minimum}"));
+    }
+    for key in schema["required"].as_array().into_iter().flatten() {
+        let key = key.as_str().unwrap();
+        if value.get(key).is_none() {
+            return Err(format!("{path}: missing {key}"));
+        }
+    }
+    if let Some(properties) = schema["properties"].as_object()
+        && let Some(object) = value.as_object()
+    {
+        for (key, sub) in properties {
+            if let Some(field) = object.get(key) {
+                validate(root, sub, field, &format!("{path}.{key}"))?;
+            }
+        }
+    }
+    if let Some(items) = schema.get("items")
+        && let Some(array) = value.as_array()
+    {
+        for (i, item) in array.iter().enumerate() {
+            validate(root, items, item, &format!("{path}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn samples_round_trip() {
+    // `sample` asserts the round trip for each one.
+    assert_eq!(samples().len(), 11);
+}
+
+#[test]
+fn golden_samples_are_unchanged() {
+    let actual: serde_json::Map<String, Value> = samples()
+        .into_iter()
+        .map(|s| (s.name.to_string(), s.json))
+        .collect();
+    let actual = Value::Object(actual);
+
+    if std::env::var_os("GIRT_UPDATE_GOLDEN").is_some() {
+        let text = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+        std::fs::write(crate_path("tests/golden/gate.json"), text).unwrap();
+        return;
+    }
+    let golden = read_json("tests/golden/gate.json");
+    for (name, expected) in golden.as_object().unwrap() {
+        assert_eq!(
+            actual.get(name),
+            Some(expected),
+            "wire format of {name} changed; see the module docs to update it"
+        );
+    }
+    assert_eq!(actual, golden);
+}
+
+#[test]
+fn golden_samples_match_the_schema() {
+    let schema = read_json("schemas/gate.schema.json");
+    for sample in samples() {
+        let def = &schema["$defs"][sample.def];
+        assert!(def.is_object(), "no $defs entry for {}", sample.def);
+        if let Err(e) = validate(&schema, def, &sample.json, sample.name) {
+            panic!("{e}");
+        }
+    }
+}
+
+#[test]
+fn schema_rejects_other_shapes() {
+    let schema = read_json("schemas/gate.schema.json");
+    let check = |def: &str, value: Value| validate(&schema, &schema["$defs"][def], &value, def);
+
+    // The externally tagged form decisions had before `status`.
+    assert!(check("Decision", json!({ "Deny": { "reason": "no" } })).is_err());
+    assert!(check("Decision", json!({ "status": "denied" })).is_err());
+    assert!(check("LayeredDecision", json!({ "status": "allowed" })).is_err());
+    assert!(check("GateInput", json!({ "gate": "creation", "tool_name": "x" })).is_err());
+    let unknown_source =
+        json!({ "gate": "creation", "name": "x", "description": "x", "source": "robot" });
+    assert!(check("GateInput", unknown_source).is_err());
+}
+
+#[test]
+fn legacy_layer_names_still_parse() {
+    let layer: DecisionLayer = serde_json::from_str(r#""LlmEvaluation""#).unwrap();
+    assert_eq!(layer, DecisionLayer::LlmEvaluation);
+    assert_eq!(serde_json::to_value(&layer).unwrap(), "llm_evaluation");
+}
+
+#[test]
+fn creation_inputs_without_a_source_are_from_an_agent() {
+    let input: GateInput =
+        serde_json::from_value(json!({ "gate": "creation", "name": "x", "description": "y" }))
+            .unwrap();
+    let GateInput::Creation { source, .. } = input else {
+        panic!("not a creation input: {input:?}");
+    };
+    assert_eq!(source, RequestSource::Agent);
+}
@@ -0,0 +1,247 @@
+//! Worked examples for the Engineer, one per common shape of tool.
+//!
+//! The Rust prompt shows a single do-nothing component. Tools that call
+//! WASI HTTP or wait on a clock fail their first builds far more often, so
+//! the Orchestrator [`select`]s at most one of these for the refined spec
+//! and hands it to [`EngineerAgent::with_example`](super::engineer::EngineerAgent::with_example):
+//!
+//! | Example     | Selected when                                       |
+//! |-------------|-----------------------------------------------------|
+//! | `polling`   | the spec's name or description says poll or wait    |
+//! | `http_get`  | the spec has network constraints                    |
+//! | `transform` | no network constraints, and it says convert, parse… |
+//!
+//! The sources live in `agent/examples/` and target the 0.1.0 world. The
+//! ignored `examples_compile` test builds each one with cargo-component.
+
+use crate::types::RefinedSpec;
+
+/// A complete tool the Engineer can follow.
+#[derive(Debug)]
+pub struct Example {
+    pub name: &'static str,
+    /// What the example shows, for the prompt.
+    pub description: &'static str,
+    /// `src/lib.rs`.
+    pub source_code: &'static str,
+    /// The world the source is built against, including any WASI imports.
+    pub wit_definition: &'static str,
+    /// Whole words in the spec's name or description that select it.
+    keywords: &'static [&'static str],
+    network: Network,
+}
+
+/// How a spec's network constraints bear on choosing an example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Network {
+    /// Any spec with network constraints selects it.
+    Selects,
+    /// Never shown for a spec with network constraints.
+    Excludes,
+    /// Only the keywords count.
+    Ignored,
+}
+
+const PLAIN_WIT: &str = r#"package girt:tool;
+
+world girt-tool {
+    export run: func(input: string) -> result<string, string>;
+}
+"#;
+
+const HTTP_WIT: &str = r#"package girt:tool;
+
+world girt-tool {
+    import wasi:http/outgoing-handler@0.2.0;
+
+    export run: func(input: string) -> result<string, string>;
+}
+"#;
+
+const POLLING_WIT: &str = r#"package girt:tool;
+
+world girt-tool {
+    import wasi:http/outgoing-handler@0.2.0;
+    import wasi:clocks/monotonic-clock@0.2.0;
+
+    export run: func(input: string) -> result<string, string>;
+}
+"#;
+
+/// Every example, in selection order: the first match wins.
+pub static EXAMPLES: &[Example] = &[
+    Example {
+        name: "polling",
+        description: "Polls a JSON endpoint until a field has the wanted value, \
+                      sleeping on the WASI monotonic clock between attempts and \
+                      giving up before the tool's timeout.",
+        source_code: include_str!("examples/polling.rs"),
+        wit_definition: POLLING_WIT,
+        keywords: &["poll", "polls", "polling", "wait", "waits", "waiting"],
+        network: Network::Ignored,
+    },
+    Example {
+        name: "http_get",
+        description: "Fetches a JSON document with a WASI HTTP GET, refusing hosts \
+                      outside its allowlist and responses over a size cap.",
+        source_code: include_str!("examples/http_get.rs"),
+        wit_definition: HTTP_WIT,
+        keywords: &[],
+        network: Network::Selects,
+    },
+    Example {
+        name: "transform",
+        description: "A pure function of its input: validates it, computes, and \
+                      returns JSON, with no WASI imports.",
+        source_code: include_str!("examples/transform.rs"),
+        wit_definition: PLAIN_WIT,
+        keywords: &[
+            "convert",
+            "converts",
+            "transform",
+            "transforms",
+            "parse",
+            "parses",
+            "format",
+            "formats",
+            "count",
+            "counts",
+            "statistics",
+        ],
+        network: Network::Excludes,
+    },
+];
+
+/// The example to show the Engineer for `spec`, if any fits.
+pub fn select(spec: &RefinedSpec) -> Option<&'static Example> {
+    let has_network = !spec.spec.constraints.network.is_empty();
+    let words: Vec<String> = format!("{} {}", spec.spec.name, spec.spec.description)
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let mentions = |example: &Example| {
+        example
+            .keywords
+            .iter()
+            .any(|k| words.iter().any(|w| w == k))
+    };
+
+    EXAMPLES.iter().find(|example| match example.network {
+        Network::Selects => has_network || mentions(example),
+        Network::Excludes => !has_network && mentions(example),
+        Network::Ignored => mentions(example),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::{CompileInput, WasmCompiler};
+    use crate::types::SpecAction;
+    use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+
+    fn spec(name: &str, description: &str, network: &[&str]) -> RefinedSpec {
+        RefinedSpec {
+            action: SpecAction::Build,
+            spec: CapabilitySpec {
+                name: name.into(),
+                description: description.into(),
+                inputs: serde_json::json!({}),
+                outputs: serde_json::json!({}),
+                constraints: CapabilityConstraints {
+                    network: network.iter().map(|&h| h.into()).collect(),
+                    ..Default::default()
+                },
+            },
+            design_notes: String::new(),
+            extend_target: None,
+            extend_features: None,
+            idempotent: false,
+        }
+    }
+
+    fn selected(spec: &RefinedSpec) -> Option<&'static str> {
+        select(spec).map(|example| example.name)
+    }
+
+    #[test]
+    fn network_constraints_select_http() {
+        let fetch = spec("get_issue", "Fetch a GitHub issue", &["api.github.com"]);
+        assert_eq!(selected(&fetch), Some("http_get"));
+
+        // A transform that also fetches still gets the HTTP example.
+        let fetch_and_parse = spec("feed_titles", "Parse an RSS feed", &["example.com"]);
+        assert_eq!(selected(&fetch_and_parse), Some("http_get"));
+    }
+
+    #[test]
+    fn poll_and_wait_select_polling_over_http() {
+        let poll = spec(
+            "poll_ci_status",
+            "Check a CI run until it finishes",
+            &["api.github.com"],
+        );
+        assert_eq!(selected(&poll), Some("polling"));
+
+        let wait = spec("deploy_ready", "Wait for a deployment to be ready", &[]);
+        assert_eq!(selected(&wait), Some("polling"));
+    }
+
+    #[test]
+    fn keywords_match_whole_words_only() {
+        // "awaiting" and "reformatted" contain keywords but are not them.
+        let neither = spec("queue_depth", "Report awaiting, reformatted jobs", &[]);
+        assert_eq!(selected(&neither), None);
+
+        let transform = spec("temp_convert", "Convert temperature units", &[]);
+        assert_eq!(selected(&transform), Some("transform"));
+    }
+
+    #[test]
+    fn unmatched_specs_get_no_example() {
+        assert_eq!(selected(&spec("dice", "Roll some dice", &[])), None);
+    }
+
+    #[test]
+    fn example_wit_matches_its_imports() {
+        for example in EXAMPLES {
+            let uses_http = example.source_code.contains("outgoing_handler");
+            let uses_clock = example.source_code.contains("monotonic_clock");
+            assert_eq!(
+                example
+                    .wit_definition
+                    .contains("wasi:http/outgoing-handler"),
+                uses_http,
+                "{}",
+                example.name
+            );
+            assert_eq!(
+                example
+                    .wit_definition
+                    .contains("wasi:clocks/monotonic-clock"),
+                uses_clock,
+                "{}",
+                example.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires cargo-component installed
+    async fn examples_compile() {
+        let compiler = WasmCompiler::new().without_cache();
+        for example in EXAMPLES {
+            let input = CompileInput {
+                source_code: example.source_code.into(),
+                wit_definition: example.wit_definition.into(),
+                tool_name: format!("example_{}", example.name),
+                tool_version: "0.1.0".into(),
+                files: vec![],
+            };
+            if let Err(e) = compiler.compile(&input).await {
+                panic!("example {} does not compile: {e}", example.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+#[allow(warnings)]
+mod bindings;
+
+use bindings::Guest;
+use bindings::wasi::clocks::monotonic_clock;
+use bindings::wasi::http::outgoing_handler;
+use bindings::wasi::http::types::{Fields, IncomingBody, Method, OutgoingRequest, Scheme};
+use bindings::wasi::io::streams::StreamError;
+
+/// Hosts this tool may call. Keep in step with the hosts in policy_yaml;
+/// the runtime refuses any other host anyway.
+const ALLOWED_HOSTS: &[&str] = &["api.github.com"];
+
+/// Responses larger than this are rejected rather than buffered.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Upper bound on `timeout_secs`; keep it below the policy's timeout_seconds
+/// so the tool reports a timeout instead of being killed.
+const MAX_TIMEOUT_SECS: u64 = 12;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+struct Component;
+
+impl Guest for Component {
+    /// `{"host": "api.github.com", "path": "/repos/o/n/actions/runs/1",
+    ///   "field": "status", "until": "completed",
+    ///   "interval_secs": 2, "timeout_secs": 10}`
+    /// -> GET the path every `interval_secs` until the JSON body's `field`
+    /// equals `until`, then return that body. Fails once `timeout_secs` pass.
+    fn run(input: String) -> Result<String, String> {
+        let input: serde_json::Value =
+            serde_json::from_str(&input).map_err(|e| format!("Invalid input: {e}"))?;
+        let host = required_str(&input, "host")?;
+        let path = required_str(&input, "path")?;
+        let field = required_str(&input, "field")?;
+        let until = input.get("until").ok_or("Missing required field 'until'")?;
+        if !path.starts_with('/') {
+            return Err("'path' must start with '/'".into());
+        }
+        let interval_secs = input["interval_secs"].as_u64().unwrap_or(2).clamp(1, 10);
+        let timeout_secs = input["timeout_secs"]
+            .as_u64()
+            .unwrap_or(10)
+            .min(MAX_TIMEOUT_SECS);
+
+        let deadline = monotonic_clock::now() + timeout_secs * NANOS_PER_SEC;
+        let mut attempts = 0u32;
+        loop {
+            attempts += 1;
+            let (status, body) = http_get(host, path)?;
+            if !(200..300).contains(&status) {
+                return Err(format!("GET https://{host}{path} returned HTTP {status}"));
+            }
+            let body: serde_json::Value =
+                serde_json::from_slice(&body).map_err(|e| format!("Response is not JSON: {e}"))?;
+            if body.get(field) == Some(until) {
+                let result = serde_json::json!({ "attempts": attempts, "body": body });
+                return serde_json::to_string(&result)
+                    .map_err(|e| format!("Serialization error: {e}"));
+            }
+
+            let now = monotonic_clock::now();
+            if now >= deadline {
+                return Err(format!(
+                    "'{field}' was {} after {attempts} attempt(s) in {timeout_secs}s, not {until}",
+                    body.get(field).unwrap_or(&serde_json::Value::Null)
+                ));
+            }
+            // Sleep for the interval, but never past the deadline.
+            let wait = (interval_secs * NANOS_PER_SEC).min(deadline - now);
+            monotonic_clock::subscribe_duration(wait).block();
+        }
+    }
+}
+
+fn required_str<'a>(input: &'a serde_json::Value, key: &str) -> Result<&'a str, String> {
+    input[key]
+        .as_str()
+        .ok_or_else(|| format!("Missing required string field '{key}'"))
+}
+
+/// GET `https://{host}{path}` over WASI HTTP, returning status and body.
+fn http_get(host: &str, path: &str) -> Result<(u16, Vec<u8>), String> {
+    if !ALLOWED_HOSTS.contains(&host) {
+        return Err(format!("Host '{host}' is not allowed"));
+    }
+
+    let headers = Fields::new();
+    headers
+        .append("accept", b"application/json")
+        .map_err(|e| format!("Invalid header: {e:?}"))?;
+    headers
+        .append("user-agent", b"girt-tool")
+        .map_err(|e| format!("Invalid header: {e:?}"))?;
+    let request = OutgoingRequest::new(headers);
+    request
+        .set_method(&Method::Get)
+        .map_err(|()| "Invalid method")?;
+    request
+        .set_scheme(Some(&Scheme::Https))
+        .map_err(|()| "Invalid scheme")?;
+    request
+        .set_authority(Some(host))
+        .map_err(|()| format!("Invalid host '{host}'"))?;
+    request
+        .set_path_with_query(Some(path))
+        .map_err(|()| format!("Invalid path '{path}'"))?;
+
+    let pending =
+        outgoing_handler::handle(request, None).map_err(|e| format!("Request failed: {e:?}"))?;
+    pending.subscribe().block();
+    let response = pending
+        .get()
+        .ok_or("Response not ready")?
+        .map_err(|()| "Response already taken")?
+        .map_err(|e| format!("Request failed: {e:?}"))?;
+
+    let status = response.status();
+    let body = response.consume().map_err(|()| "Body already consumed")?;
+    let stream = body.stream().map_err(|()| "Body stream already taken")?;
+    let mut bytes = Vec::new();
+    loop {
+        match stream.blocking_read(64 * 1024) {
+            Ok(chunk) => {
+                bytes.extend_from_slice(&chunk);
+                if bytes.len() > MAX_BODY_BYTES {
+                    return Err(format!("Response is larger than {MAX_BODY_BYTES} bytes"));
+                }
+            }
+            Err(StreamError::Closed) => break,
+            Err(StreamError::LastOperationFailed(e)) => {
+                return Err(format!("Reading response failed: {}", e.to_debug_string()));
+            }
+        }
+    }
+    // The stream must be dropped before the body is finished.
+    drop(stream);
+    IncomingBody::finish(body);
+    Ok((status, bytes))
+}
+
+bindings::export!(Component with_types_in bindings);
@@ -0,0 +1,58 @@
+#[allow(warnings)]
+mod bindings;
+
+use std::collections::BTreeMap;
+
+use bindings::Guest;
+
+/// Inputs larger than this are rejected rather than processed.
+const MAX_TEXT_BYTES: usize = 1024 * 1024;
+
+struct Component;
+
+impl Guest for Component {
+    /// `{"text": "...", "top": 5}` -> line, word, and character counts and
+    /// the `top` most frequent words.
+    fn run(input: String) -> Result<String, String> {
+        let input: serde_json::Value =
+            serde_json::from_str(&input).map_err(|e| format!("Invalid input: {e}"))?;
+        let text = input["text"]
+            .as_str()
+            .ok_or("Missing required string field 'text'")?;
+        if text.len() > MAX_TEXT_BYTES {
+            return Err(format!("'text' is larger than {MAX_TEXT_BYTES} bytes"));
+        }
+        let top = match &input["top"] {
+            serde_json::Value::Null => 5,
+            value => value
+                .as_u64()
+                .filter(|n| *n <= 100)
+                .ok_or("'top' must be an integer from 0 to 100")? as usize,
+        };
+
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            *counts.entry(word.to_lowercase()).or_default() += 1;
+        }
+        let mut frequent: Vec<(String, u64)> = counts.into_iter().collect();
+        // Most frequent first; ties in alphabetical order.
+        frequent.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        frequent.truncate(top);
+
+        let result = serde_json::json!({
+            "lines": text.lines().count(),
+            "words": text.split_whitespace().count(),
+            "characters": text.chars().count(),
+            "top_words": frequent
+                .into_iter()
+                .map(|(word, count)| serde_json::json!({ "word": word, "count": count }))
+                .collect::<Vec<_>>(),
+        });
+        serde_json::to_string(&result).map_err(|e| format!("Serialization error: {e}"))
+    }
+}
+
+bindings::export!(Component with_types_in bindings);
@@ -1,4 +1,12 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use girt_core::paths::{self, PathError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::OwnedMutexGuard;
 
 use crate::error::PipelineError;
 use crate::types::BuildArtifact;
@@ -12,19 +20,43 @@ use crate::types::BuildArtifact;
 ///     manifest.json   -- BuildArtifact metadata
 ///     source.rs       -- generated source code
 ///     policy.yaml     -- Wassette policy
+///     tool.wasm       -- compiled component (when published with WASM)
 /// ```
+///
+/// Writes to one tool are serialized by a per-name lock (see
+/// [`lock`](Self::lock)). A tool is stored by writing a staging directory
+/// next to it and renaming that into place, so readers see the old tool or
+/// the new one, never a mix of both.
 pub struct ToolCache {
     base_dir: PathBuf,
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Exclusive hold on one cached tool, from [`ToolCache::lock`]. Released
+/// on drop.
+pub struct ToolLock {
+    name: String,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl ToolLock {
+    /// The tool this lock holds.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl ToolCache {
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        Self {
+            base_dir,
+            locks: Mutex::new(HashMap::new()),
+        }
     }
 
-    /// Default cache location: ~/.girt/tools/
-    pub fn default_path() -> PathBuf {
-        dirs_path().join("tools")
+    /// Default cache location: `tools/` in the data directory (~/.girt/tools/).
+    pub fn default_path() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("tools"))
     }
 
     /// Initialize the cache directory.
@@ -33,40 +65,178 @@ impl ToolCache {
         Ok(())
     }
 
-    /// Store a build artifact in the cache.
-    pub async fn store(&self, artifact: &BuildArtifact) -> Result<PathBuf, PipelineError> {
-        let tool_dir = self.base_dir.join(&artifact.spec.name);
-        tokio::fs::create_dir_all(&tool_dir).await?;
-
-        // Write manifest (full artifact metadata)
-        let manifest_path = tool_dir.join("manifest.json");
-        let manifest_json = serde_json::to_string_pretty(artifact)?;
-        tokio::fs::write(&manifest_path, manifest_json).await?;
+    /// Wait for exclusive hold on the tool `name`. Every write to a cached
+    /// tool takes it; hold it across a read and the write that depends on
+    /// it, as [`Publisher`](crate::publish::Publisher) does.
+    pub async fn lock(&self, name: &str) -> ToolLock {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(locks.entry(name.to_string()).or_default())
+        };
+        ToolLock {
+            name: name.to_string(),
+            _guard: lock.lock_owned().await,
+        }
+    }
 
-        // Write source code
-        let source_path = tool_dir.join("source.rs");
-        tokio::fs::write(&source_path, &artifact.build_output.source_code).await?;
+    /// Store a build artifact in the cache, replacing any tool of its name.
+    pub async fn store(&self, artifact: &BuildArtifact) -> Result<PathBuf, PipelineError> {
+        let lock = self.lock(&artifact.spec.name).await;
+        let (tool_dir, _) = self.store_locked(&lock, artifact, None).await?;
+        Ok(tool_dir)
+    }
 
-        // Write policy.yaml
-        let policy_path = tool_dir.join("policy.yaml");
-        tokio::fs::write(&policy_path, &artifact.build_output.policy_yaml).await?;
+    /// [`store`](Self::store) under a lock already held, also copying
+    /// `wasm_src` in as `tool.wasm` with its digest recorded in
+    /// `manifest.json` under a `wasm` key.
+    ///
+    /// An artifact published before the cached tool was is refused, so a
+    /// build that finished first but reached the cache last cannot roll the
+    /// tool back.
+    pub async fn store_locked(
+        &self,
+        lock: &ToolLock,
+        artifact: &BuildArtifact,
+        wasm_src: Option<&Path>,
+    ) -> Result<(PathBuf, Option<WasmDigest>), PipelineError> {
+        let name = &artifact.spec.name;
+        if lock.name() != name {
+            return Err(PipelineError::PublishFailed(format!(
+                "storing '{name}' under the lock for '{}'",
+                lock.name()
+            )));
+        }
+        let tool_dir = self.base_dir.join(name);
+        if let (Some(cached), Some(incoming)) =
+            (published_at(&tool_dir).await, artifact.published_at)
+            && cached > incoming
+        {
+            tracing::warn!(
+                tool = %name,
+                cached = %cached,
+                incoming = %incoming,
+                "Refusing to replace a cached tool with an older build"
+            );
+            return Err(PipelineError::PublishFailed(format!(
+                "a newer build of '{name}' (published {cached}) is already cached"
+            )));
+        }
 
-        // Write WIT definition
-        if !artifact.build_output.wit_definition.is_empty() {
-            let wit_path = tool_dir.join("world.wit");
-            tokio::fs::write(&wit_path, &artifact.build_output.wit_definition).await?;
+        tokio::fs::create_dir_all(&self.base_dir).await?;
+        let staging = self
+            .base_dir
+            .join(format!(".{name}.staging-{}", uuid::Uuid::new_v4()));
+        let written = write_tool(&staging, artifact, wasm_src).await;
+        let digest = match written {
+            Ok(digest) => digest,
+            Err(e) => {
+                let _ = tokio::fs::remove_dir_all(&staging).await;
+                return Err(e);
+            }
+        };
+        if let Err(e) = replace_dir(&staging, &tool_dir).await {
+            let _ = tokio::fs::remove_dir_all(&staging).await;
+            return Err(e.into());
         }
 
         tracing::info!(
-            tool = %artifact.spec.name,
+            tool = %name,
             path = %tool_dir.display(),
             "Tool cached"
         );
 
-        Ok(tool_dir)
+        Ok((tool_dir, digest))
+    }
+
+    /// Copy a compiled component into a cached tool's directory as `tool.wasm`
+    /// and record its digest in `manifest.json` under a `wasm` key.
+    ///
+    /// The tool must already have been stored with [`store`](Self::store).
+    pub async fn store_wasm(
+        &self,
+        name: &str,
+        wasm_src: &Path,
+    ) -> Result<WasmDigest, PipelineError> {
+        let _lock = self.lock(name).await;
+        let tool_dir = self.base_dir.join(name);
+        let bytes = tokio::fs::read(wasm_src).await?;
+        let digest = WasmDigest::of(&bytes);
+        write_atomic(&tool_dir.join("tool.wasm"), &bytes).await?;
+
+        let manifest_path = tool_dir.join("manifest.json");
+        let content = tokio::fs::read_to_string(&manifest_path).await?;
+        let mut manifest: serde_json::Value = serde_json::from_str(&content)?;
+        if let Some(obj) = manifest.as_object_mut() {
+            obj.insert("wasm".into(), serde_json::to_value(&digest)?);
+        }
+        let manifest = serde_json::to_string_pretty(&manifest)?;
+        write_atomic(&manifest_path, manifest.as_bytes()).await?;
+
+        Ok(digest)
+    }
+
+    /// Read the WASM digest recorded in a cached tool's manifest, if any.
+    pub async fn wasm_digest(&self, name: &str) -> Result<Option<WasmDigest>, PipelineError> {
+        let manifest_path = self.base_dir.join(name).join("manifest.json");
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+        let content = tokio::fs::read_to_string(&manifest_path).await?;
+        let manifest: serde_json::Value = serde_json::from_str(&content)?;
+        match manifest.get("wasm") {
+            Some(v) => Ok(Some(serde_json::from_value(v.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a deprecation in a cached tool's manifest as top-level
+    /// `deprecated`, `replaced_by`, and `deprecated_at` keys, mirroring the
+    /// runtime's component metadata. Returns `false` if the tool is not cached.
+    pub async fn mark_deprecated(
+        &self,
+        name: &str,
+        replaced_by: Option<&str>,
+        deprecated_at: u64,
+    ) -> Result<bool, PipelineError> {
+        let _lock = self.lock(name).await;
+        let manifest_path = self.base_dir.join(name).join("manifest.json");
+        if !manifest_path.exists() {
+            return Ok(false);
+        }
+        let content = tokio::fs::read_to_string(&manifest_path).await?;
+        let mut manifest: serde_json::Value = serde_json::from_str(&content)?;
+        if let Some(obj) = manifest.as_object_mut() {
+            obj.insert("deprecated".into(), true.into());
+            obj.insert("replaced_by".into(), replaced_by.into());
+            obj.insert("deprecated_at".into(), deprecated_at.into());
+        }
+        let manifest = serde_json::to_string_pretty(&manifest)?;
+        write_atomic(&manifest_path, manifest.as_bytes()).await?;
+        Ok(true)
+    }
+
+    /// Record where a cached tool was pushed or mirrored. See
+    /// [`record_locations`].
+    pub async fn record_locations(
+        &self,
+        name: &str,
+        locations: &ToolLocations,
+    ) -> Result<bool, PipelineError> {
+        let _lock = self.lock(name).await;
+        record_locations(&self.base_dir.join(name), locations).await
+    }
+
+    /// Where a cached tool was pushed or mirrored, as far as its manifest
+    /// records. See [`locations`].
+    pub async fn locations(&self, name: &str) -> Result<ToolLocations, PipelineError> {
+        locations(&self.base_dir.join(name)).await
     }
 
     /// Look up a cached tool by name.
+    ///
+    /// The stored spec is not validated: specs are checked when they are
+    /// requested, and one stored before a rule was added must still load.
+    /// A stored spec that no longer passes is logged as a warning.
     pub async fn get(&self, name: &str) -> Result<Option<BuildArtifact>, PipelineError> {
         let manifest_path = self.base_dir.join(name).join("manifest.json");
 
@@ -76,6 +246,13 @@ impl ToolCache {
 
         let content = tokio::fs::read_to_string(&manifest_path).await?;
         let artifact: BuildArtifact = serde_json::from_str(&content)?;
+        if let Err(e) = artifact.spec.validate() {
+            tracing::warn!(
+                tool = %name,
+                "Stored spec does not pass current validation; a rebuild or \
+                 extension must fix it: {e}"
+            );
+        }
         Ok(Some(artifact))
     }
 
@@ -90,8 +267,10 @@ impl ToolCache {
         let mut entries = tokio::fs::read_dir(&self.base_dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
+            // Dot directories are stores in progress.
             if path.is_dir()
                 && let Some(name) = path.file_name()
+                && !name.to_string_lossy().starts_with('.')
             {
                 let manifest = path.join("manifest.json");
                 if manifest.exists() {
@@ -105,6 +284,7 @@ impl ToolCache {
 
     /// Remove a cached tool.
     pub async fn remove(&self, name: &str) -> Result<(), PipelineError> {
+        let _lock = self.lock(name).await;
         let tool_dir = self.base_dir.join(name);
         if tool_dir.exists() {
             tokio::fs::remove_dir_all(&tool_dir).await?;
@@ -119,16 +299,157 @@ impl ToolCache {
     }
 }
 
-fn dirs_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".girt")
+/// Where a published tool lives outside the local cache. Recorded in its
+/// `manifest.json` as top-level keys of the same names, and copied into the
+/// runtime's component metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolLocations {
+    /// OCI reference (`registry/name:tag`) the component was pushed to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oci_reference: Option<String>,
+    /// Where tool sync mirrored the tool's source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_repo_path: Option<String>,
+}
+
+/// Record the locations that are set in `locations` in the manifest of the
+/// cached tool in `tool_dir`, leaving the others as they are. Returns
+/// `false` if there is no manifest. Publishing the tool again writes a
+/// fresh manifest, so stale locations do not outlive the build they
+/// describe.
+pub async fn record_locations(
+    tool_dir: &Path,
+    locations: &ToolLocations,
+) -> Result<bool, PipelineError> {
+    let manifest_path = tool_dir.join("manifest.json");
+    if !manifest_path.exists() {
+        return Ok(false);
+    }
+    let content = tokio::fs::read_to_string(&manifest_path).await?;
+    let mut manifest: serde_json::Value = serde_json::from_str(&content)?;
+    if let (Some(obj), serde_json::Value::Object(set)) =
+        (manifest.as_object_mut(), serde_json::to_value(locations)?)
+    {
+        obj.extend(set);
+    }
+    let manifest = serde_json::to_string_pretty(&manifest)?;
+    write_atomic(&manifest_path, manifest.as_bytes()).await?;
+    Ok(true)
+}
+
+/// The locations recorded in the manifest of the cached tool in `tool_dir`;
+/// none when it has no manifest.
+pub async fn locations(tool_dir: &Path) -> Result<ToolLocations, PipelineError> {
+    let manifest_path = tool_dir.join("manifest.json");
+    if !manifest_path.exists() {
+        return Ok(ToolLocations::default());
+    }
+    let content = tokio::fs::read_to_string(&manifest_path).await?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Integrity data for a cached `tool.wasm`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmDigest {
+    /// SHA-256 hex of the WASM bytes.
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+impl WasmDigest {
+    fn of(bytes: &[u8]) -> Self {
+        Self {
+            sha256: hex::encode(Sha256::digest(bytes)),
+            size_bytes: bytes.len() as u64,
+        }
+    }
+}
+
+/// Write every file of `artifact` (and `wasm_src` as `tool.wasm`) into the
+/// new directory `dir`.
+async fn write_tool(
+    dir: &Path,
+    artifact: &BuildArtifact,
+    wasm_src: Option<&Path>,
+) -> Result<Option<WasmDigest>, PipelineError> {
+    tokio::fs::create_dir(dir).await?;
+
+    let mut manifest = serde_json::to_value(artifact)?;
+    let digest = match wasm_src {
+        Some(wasm_src) => {
+            let bytes = tokio::fs::read(wasm_src).await?;
+            tokio::fs::write(dir.join("tool.wasm"), &bytes).await?;
+            let digest = WasmDigest::of(&bytes);
+            if let Some(obj) = manifest.as_object_mut() {
+                obj.insert("wasm".into(), serde_json::to_value(&digest)?);
+            }
+            Some(digest)
+        }
+        None => None,
+    };
+    tokio::fs::write(
+        dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .await?;
+    tokio::fs::write(dir.join("source.rs"), &artifact.build_output.source_code).await?;
+    tokio::fs::write(dir.join("policy.yaml"), &artifact.build_output.policy_yaml).await?;
+    if !artifact.build_output.wit_definition.is_empty() {
+        tokio::fs::write(dir.join("world.wit"), &artifact.build_output.wit_definition).await?;
+    }
+    Ok(digest)
+}
+
+/// Move the directory `staged` to `dest`, replacing whatever is there.
+///
+/// A directory cannot be renamed over a non-empty one, so an existing
+/// `dest` is first renamed aside and removed afterwards; in between, `dest`
+/// briefly does not exist.
+async fn replace_dir(staged: &Path, dest: &Path) -> std::io::Result<()> {
+    if !tokio::fs::try_exists(dest).await? {
+        return tokio::fs::rename(staged, dest).await;
+    }
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let retired = dest.with_file_name(format!(".{name}.retired-{}", uuid::Uuid::new_v4()));
+    tokio::fs::rename(dest, &retired).await?;
+    if let Err(e) = tokio::fs::rename(staged, dest).await {
+        // Put the old tool back rather than leave none.
+        let _ = tokio::fs::rename(&retired, dest).await;
+        return Err(e);
+    }
+    if let Err(e) = tokio::fs::remove_dir_all(&retired).await {
+        tracing::warn!(path = %retired.display(), error = %e, "Failed to remove a replaced tool directory");
+    }
+    Ok(())
+}
+
+/// Replace the file at `path` with `contents` by writing a sibling and
+/// renaming it over `path`.
+async fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let staged = path.with_file_name(format!(".{name}.{}", uuid::Uuid::new_v4()));
+    tokio::fs::write(&staged, contents).await?;
+    tokio::fs::rename(&staged, path).await.inspect_err(|_| {
+        let _ = std::fs::remove_file(&staged);
+    })
+}
+
+/// When the tool cached in `tool_dir` was published, if its manifest says.
+async fn published_at(tool_dir: &Path) -> Option<DateTime<Utc>> {
+    let content = tokio::fs::read_to_string(tool_dir.join("manifest.json"))
+        .await
+        .ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+    serde_json::from_value(manifest.get("published_at")?.clone()).ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{BuildOutput, QaResult, RefinedSpec, SecurityResult, SpecAction};
+    use crate::types::{
+        AgentProvenance, BuildOutput, PipelineSnapshot, Provenance, QaResult, RefinedSpec,
+        RequestSource, SecurityResult, SpecAction, StageTimings,
+    };
     use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
     use tempfile::TempDir;
 
@@ -149,12 +470,15 @@ mod tests {
                 design_notes: "test".into(),
                 extend_target: None,
                 extend_features: None,
+                idempotent: false,
             },
             build_output: BuildOutput {
                 source_code: "fn main() {}".into(),
                 wit_definition: "package test:tool;".into(),
                 policy_yaml: "version: \"1.0\"".into(),
                 language: "rust".into(),
+                files: vec![],
+                change_summary: None,
             },
             qa_result: QaResult {
                 passed: true,
@@ -162,14 +486,23 @@ mod tests {
                 tests_passed: 5,
                 tests_failed: 0,
                 bug_tickets: vec![],
+                data_quality: vec![],
             },
             security_result: SecurityResult {
                 passed: true,
                 exploits_attempted: 6,
                 exploits_succeeded: 0,
                 bug_tickets: vec![],
+                data_quality: vec![],
             },
             build_iterations: 1,
+            ticket_history: vec![],
+            provenance: None,
+            requested_name: None,
+            revision: 0,
+            published_at: None,
+            iteration_outputs: vec![],
+            test_vectors: vec![],
         }
     }
 
@@ -189,6 +522,73 @@ mod tests {
         assert_eq!(retrieved.build_iterations, 1);
     }
 
+    #[tokio::test]
+    async fn provenance_round_trips_and_older_manifests_still_load() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().to_path_buf());
+        cache.init().await.unwrap();
+
+        let mut artifact = make_artifact("traced");
+        artifact.provenance = Some(Provenance {
+            girt_version: "0.1.0".into(),
+            request_id: Some("req-1".into()),
+            request_source: Some(RequestSource::Cli),
+            agents: [(
+                "engineer".to_string(),
+                AgentProvenance {
+                    provider: "anthropic".into(),
+                    model: "some-model".into(),
+                    prompt_sha256: "ab".repeat(32),
+                },
+            )]
+            .into(),
+            pipeline: PipelineSnapshot { max_iterations: 3 },
+            timings: StageTimings {
+                engineer_ms: 1200,
+                total_ms: 1500,
+                ..Default::default()
+            },
+        });
+        cache.store(&artifact).await.unwrap();
+        let retrieved = cache.get("traced").await.unwrap().unwrap();
+        assert_eq!(retrieved.provenance, artifact.provenance);
+
+        // A manifest written before provenance was recorded.
+        let legacy = make_artifact("legacy");
+        cache.store(&legacy).await.unwrap();
+        let manifest = tokio::fs::read_to_string(tmp.path().join("legacy/manifest.json"))
+            .await
+            .unwrap();
+        assert!(!manifest.contains("provenance"));
+        let retrieved = cache.get("legacy").await.unwrap().unwrap();
+        assert_eq!(retrieved.provenance, None);
+    }
+
+    #[tokio::test]
+    async fn stored_specs_load_without_current_validation() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().to_path_buf());
+        cache.init().await.unwrap();
+
+        // A manifest from before network grants had to be bare hosts.
+        cache.store(&make_artifact("old_fetch")).await.unwrap();
+        let path = tmp.path().join("old_fetch/manifest.json");
+        let mut manifest: serde_json::Value =
+            serde_json::from_str(&tokio::fs::read_to_string(&path).await.unwrap()).unwrap();
+        let network = serde_json::json!(["https://api.example.com/v1"]);
+        manifest["spec"]["constraints"]["network"] = network.clone();
+        manifest["refined_spec"]["spec"]["constraints"]["network"] = network;
+        tokio::fs::write(&path, manifest.to_string()).await.unwrap();
+
+        let artifact = cache.get("old_fetch").await.unwrap().unwrap();
+        assert_eq!(
+            artifact.spec.constraints.network,
+            ["https://api.example.com/v1"]
+        );
+        let err = artifact.spec.validate().unwrap_err();
+        assert_eq!(err.errors[0].field, "constraints.network[0]");
+    }
+
     #[tokio::test]
     async fn list_cached_tools() {
         let tmp = TempDir::new().unwrap();
@@ -239,4 +639,118 @@ mod tests {
         assert!(tool_dir.join("policy.yaml").exists());
         assert!(tool_dir.join("world.wit").exists());
     }
+
+    #[tokio::test]
+    async fn store_wasm_records_digest_in_manifest() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        cache.init().await.unwrap();
+
+        cache.store(&make_artifact("hashed")).await.unwrap();
+        let wasm_src = tmp.path().join("hashed.wasm");
+        std::fs::write(&wasm_src, b"fake wasm bytes").unwrap();
+
+        let digest = cache.store_wasm("hashed", &wasm_src).await.unwrap();
+        assert_eq!(digest.size_bytes, 15);
+        assert_eq!(digest.sha256, hex::encode(Sha256::digest(b"fake wasm bytes")));
+
+        assert_eq!(cache.wasm_digest("hashed").await.unwrap(), Some(digest));
+        // The manifest is still a readable artifact
+        assert!(cache.get("hashed").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn mark_deprecated_annotates_manifest() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        cache.init().await.unwrap();
+        cache.store(&make_artifact("old_tool")).await.unwrap();
+
+        assert!(
+            cache
+                .mark_deprecated("old_tool", Some("new_tool"), 1_234)
+                .await
+                .unwrap()
+        );
+        assert!(!cache.mark_deprecated("missing", None, 1_234).await.unwrap());
+
+        let manifest: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(tmp.path().join("tools/old_tool/manifest.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest["deprecated"], true);
+        assert_eq!(manifest["replaced_by"], "new_tool");
+        assert_eq!(manifest["deprecated_at"], 1_234);
+        assert!(cache.get("old_tool").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn locations_are_recorded_in_the_manifest() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        cache.init().await.unwrap();
+        let artifact = make_artifact("pushed_tool");
+        cache.store(&artifact).await.unwrap();
+        assert_eq!(
+            cache.locations("pushed_tool").await.unwrap(),
+            ToolLocations::default()
+        );
+
+        let oci = ToolLocations {
+            oci_reference: Some("ghcr.io/acme/pushed_tool:v1".into()),
+            source_repo_path: None,
+        };
+        assert!(cache.record_locations("pushed_tool", &oci).await.unwrap());
+        let synced = ToolLocations {
+            oci_reference: None,
+            source_repo_path: Some("/srv/tools (main:tools/pushed_tool)".into()),
+        };
+        assert!(
+            cache
+                .record_locations("pushed_tool", &synced)
+                .await
+                .unwrap()
+        );
+        assert!(!cache.record_locations("missing", &oci).await.unwrap());
+
+        // Each records its own location without clearing the other.
+        let recorded = cache.locations("pushed_tool").await.unwrap();
+        assert_eq!(recorded.oci_reference, oci.oci_reference);
+        assert_eq!(recorded.source_repo_path, synced.source_repo_path);
+        assert!(cache.get("pushed_tool").await.unwrap().is_some());
+
+        // A new build starts without them.
+        cache.store(&artifact).await.unwrap();
+        assert_eq!(
+            cache.locations("pushed_tool").await.unwrap(),
+            ToolLocations::default()
+        );
+    }
+    #[tokio::test]
+    async fn older_builds_do_not_replace_newer_ones() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().to_path_buf());
+        cache.init().await.unwrap();
+        let built_at = |secs| chrono::DateTime::from_timestamp(secs, 0);
+
+        let mut newer = make_artifact("raced");
+        newer.published_at = built_at(2_000);
+        newer.build_output.source_code = "// newer".into();
+        cache.store(&newer).await.unwrap();
+
+        let mut older = make_artifact("raced");
+        older.published_at = built_at(1_000);
+        older.build_output.source_code = "// older".into();
+        let err = cache.store(&older).await.unwrap_err();
+        assert_eq!(err.code(), "publish_failed");
+        let cached = cache.get("raced").await.unwrap().unwrap();
+        assert_eq!(cached.build_output.source_code, "// newer");
+
+        // Nothing is left behind but the tool itself.
+        let entries: Vec<_> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["raced"]);
+    }
 }
@@ -1,8 +1,80 @@
-use std::path::{Path, PathBuf};
+//! Compiles generated tool source into WASM components with `cargo-component`.
+//!
+//! # Threat model
+//!
+//! The Engineer writes the tool's source, but girt writes its `Cargo.toml`:
+//! a fixed set of dependencies and no build script of the tool's own. What
+//! runs on the host during a build is therefore the compiler and the build
+//! scripts and proc macros of those dependencies (and, through them, of
+//! whatever crates.io serves for them). A compromised dependency or index
+//! would run that code with the user's privileges.
+//!
+//! So each build runs in two steps:
+//!
+//! 1. `cargo-component fetch` resolves and downloads the dependencies. No
+//!    dependency code runs, so this step alone may use the network.
+//! 2. `cargo-component build --release --locked` compiles them with
+//!    `CARGO_NET_OFFLINE=true`, against exactly the fetched lockfile.
+//!
+//! Both start from an empty environment plus [`PASSTHROUGH_ENV`], so API
+//! keys and tokens in girt's own environment never reach a build script.
+//! With `[build] sandbox = "auto"` (the default) both steps are also
+//! wrapped in `bwrap` on Linux or `sandbox-exec` on macOS when available.
+//! In it, the home directory and girt's config and data dirs are hidden, so
+//! girt's credentials and the user's SSH keys and tokens cannot be read and
+//! compiled into the component. Only `CARGO_HOME`, `RUSTUP_HOME`, the
+//! `cargo-component` binary and the `PATH` entries under those dirs stay
+//! readable. Writes are allowed only to the project and target dirs and
+//! cargo's registry and git checkouts. The rest of `CARGO_HOME` (`bin/`,
+//! `config.toml`) is read-only, so a build script cannot plant anything that
+//! later runs outside the sandbox. Only the fetch step has the network.
+//! This limits what a malicious build script can reach; it is not a
+//! substitute for vetting the dependency set. Files outside the hidden dirs,
+//! e.g. under `/etc`, stay readable.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use girt_core::host::HostCapabilities;
+use girt_core::paths::{self, PathError};
+use serde::Deserialize;
+use tracing::Instrument;
 
 use crate::error::PipelineError;
+use crate::scratch::Scratch;
+use crate::types::{SourceFile, WitVersion};
+
+/// Variables copied from girt's environment into `cargo-component`'s.
+/// Everything else is dropped. `CARGO_HOME` and `RUSTUP_HOME` are set to
+/// their defaults under `HOME` if girt has them unset and a home is known.
+pub const PASSTHROUGH_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+];
+
+/// Whether builds run in an OS sandbox (`[build] sandbox`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildSandbox {
+    /// Use `bwrap` or `sandbox-exec` if one is installed and works.
+    #[default]
+    Auto,
+    /// Run `cargo-component` directly.
+    Off,
+}
+
+/// An OS sandbox found on this host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SandboxTool {
+    Bwrap,
+    SandboxExec,
+}
 
-/// Default WIT definition for girt tools.
+/// Default WIT definition for girt tools (`girt:tool@0.1.0`).
 const DEFAULT_WIT: &str = r#"package girt:tool;
 
 world girt-tool {
@@ -10,11 +82,69 @@ world girt-tool {
 }
 "#;
 
+/// Default WIT definition for `girt:tool@0.2.0`, with structured errors.
+const DEFAULT_WIT_0_2: &str = r#"package girt:tool;
+
+world girt-tool {
+    record tool-error {
+        kind: string,
+        message: string,
+        retryable: bool,
+    }
+
+    export run: func(input: string) -> result<string, tool-error>;
+}
+"#;
+
+/// The `girt:host/utils` interface girt-runtime provides to tools that
+/// import it; the same as girt-runtime's `wit/host/utils.wit`.
+pub const HOST_UTILS_WIT: &str = r#"// Host helpers a GIRT tool may import.
+//
+// Tools import the interface with `import girt:host/utils@0.1.0;` in their
+// world. It is optional: components that do not import it load as before.
+//
+// - `now-ms`: wall-clock time in Unix milliseconds. Deterministic calls see
+//   their fixed time.
+// - `deadline-ms`: milliseconds left before the call is cut off, or the
+//   maximum u64 when it has no deadline. Deterministic calls see their whole
+//   budget on every read.
+// - `uuid-v4`: a random version 4 UUID, hyphenated and lowercase.
+// - `log`: a log line on the host, tagged with the component. `level` is
+//   "trace", "debug", "info", "warn", or "error" (anything else logs at
+//   info). A call may log at most 100 lines; later ones are dropped.
+
+package girt:host@0.1.0;
+
+interface utils {
+    now-ms: func() -> u64;
+    deadline-ms: func() -> u64;
+    uuid-v4: func() -> string;
+    log: func(level: string, message: string);
+}
+"#;
+
+/// Where a build finds [`HOST_UTILS_WIT`], relative to the project.
+const HOST_UTILS_DEP_DIR: &str = "wit/deps/girt-host";
+
+/// The standard girt-tool world at `version`, as scaffolded for builds.
+///
+/// The package is unversioned: cargo-component v0.21 does not support
+/// versioned package names. The runtime tells the versions apart by the
+/// `run` error type instead.
+pub fn default_wit(version: WitVersion) -> &'static str {
+    match version {
+        WitVersion::V0_1 => DEFAULT_WIT,
+        WitVersion::V0_2 => DEFAULT_WIT_0_2,
+    }
+}
+
 pub struct CompileInput {
     pub source_code: String,
     pub wit_definition: String,
     pub tool_name: String,
     pub tool_version: String,
+    /// Extra files under `src/` or `tests/`; `source_code` is `src/lib.rs`.
+    pub files: Vec<SourceFile>,
 }
 
 pub struct CompileOutput {
@@ -22,17 +152,112 @@ pub struct CompileOutput {
     pub build_dir: PathBuf,
 }
 
+/// Compiles generated tool source into WASM components with `cargo-component`.
+///
+/// By default builds run in a persistent workspace (`~/.girt/build-cache/`)
+/// whose cargo target dir is shared across tools, so wit-bindgen, serde,
+/// and friends are compiled once rather than on every build:
+///
+/// ```text
+/// build-cache/
+///   .lock           held for the duration of a build
+///   src/<tool>/     scaffolded project, recreated on every build
+///   target/         shared CARGO_TARGET_DIR
+/// ```
+///
+/// [`WasmCompiler::without_cache`] builds each tool in a [`Scratch`]
+/// directory (`~/.girt/tmp/girt-build-*`) with a cold target dir instead.
+///
+/// Concurrent compiles share one compiler: the CPU-bound `build` step runs
+/// one at a time per compiler, while scaffolding and `fetch` may overlap.
 pub struct WasmCompiler {
     cargo_component_bin: String,
+    /// Persistent build workspace; `None` builds in a fresh scratch dir.
+    cache_dir: Option<PathBuf>,
+    scratch: Scratch,
+    /// Duration of the previous build, for the logged delta.
+    last_build: Mutex<Option<Duration>>,
+    /// Held while `cargo-component build` runs.
+    build_step: tokio::sync::Mutex<()>,
+    /// World scaffolded when the Engineer supplies no WIT.
+    wit_version: WitVersion,
+    sandbox: BuildSandbox,
+    /// Imports listed in a comment at the end of each scaffolded world.
+    host_capabilities: HostCapabilities,
 }
 
 impl WasmCompiler {
     pub fn new() -> Self {
         Self {
             cargo_component_bin: "cargo-component".into(),
+            cache_dir: match Self::default_cache_dir() {
+                Ok(dir) => Some(dir),
+                Err(e) => {
+                    tracing::warn!("Building without the shared build cache: {e}");
+                    None
+                }
+            },
+            scratch: Scratch::default(),
+            last_build: Mutex::new(None),
+            build_step: tokio::sync::Mutex::new(()),
+            wit_version: WitVersion::default(),
+            sandbox: BuildSandbox::default(),
+            host_capabilities: HostCapabilities::default(),
         }
     }
 
+    /// Default build workspace: `build-cache/` in the data directory
+    /// (`~/.girt/build-cache`).
+    pub fn default_cache_dir() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("build-cache"))
+    }
+
+    /// Use `dir` as the persistent build workspace.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Build every tool from scratch in a scratch dir (no shared target dir).
+    pub fn without_cache(mut self) -> Self {
+        self.cache_dir = None;
+        self
+    }
+
+    /// Create uncached builds' dirs under `scratch` rather than
+    /// `~/.girt/tmp`.
+    pub fn with_scratch(mut self, scratch: Scratch) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    /// Use a different `cargo-component` executable (a pinned install, or a
+    /// stand-in that emits a prebuilt component in tests).
+    pub fn with_binary(mut self, bin: impl Into<String>) -> Self {
+        self.cargo_component_bin = bin.into();
+        self
+    }
+
+    /// Scaffold `version` of the girt-tool world when a build has no WIT
+    /// of its own (`[build] wit_version`).
+    pub fn with_wit_version(mut self, version: WitVersion) -> Self {
+        self.wit_version = version;
+        self
+    }
+
+    /// Run builds in an OS sandbox when available (`[build] sandbox`).
+    pub fn with_sandbox(mut self, sandbox: BuildSandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// List the imports in `capabilities` in a comment at the end of each
+    /// scaffolded `wit/world.wit`, for whoever reads the build directory.
+    pub fn with_host_capabilities(mut self, capabilities: HostCapabilities) -> Self {
+        self.host_capabilities = capabilities;
+        self
+    }
+
     pub fn scaffold_project(
         &self,
         input: &CompileInput,
@@ -46,7 +271,21 @@ impl WasmCompiler {
         // for valid component labels.
         let package_name = input.tool_name.replace('_', "-");
 
-        let cargo_toml = format!(
+        // Use the provided WIT or fall back to the standard girt-tool world.
+        let wit = if input.wit_definition.trim().is_empty()
+            || !input.wit_definition.contains("package")
+        {
+            default_wit(self.wit_version).to_string()
+        } else {
+            // Strip version suffix from WIT package line if present.
+            // cargo-component v0.21 does not support versioned package names.
+            input
+                .wit_definition
+                .replace("package girt:tool@0.1.0;", "package girt:tool;")
+                .replace("package girt:tool@0.2.0;", "package girt:tool;")
+        };
+
+        let mut cargo_toml = format!(
             r#"[package]
 name = "{name}"
 version = "{version}"
@@ -72,85 +311,83 @@ path = "wit"
             name = package_name,
             version = input.tool_version,
         );
+        let mut target_dependencies: Vec<String> = wasi_dependencies(&wit)
+            .into_iter()
+            .map(|(package, version)| format!("\"{package}\" = \"{version}\"\n"))
+            .collect();
+        if wit.contains("import girt:host/") {
+            let dep_dir = project_dir.join(HOST_UTILS_DEP_DIR);
+            std::fs::create_dir_all(&dep_dir)?;
+            std::fs::write(dep_dir.join("utils.wit"), HOST_UTILS_WIT)?;
+            target_dependencies.push(format!(
+                "\"girt:host\" = {{ path = \"{HOST_UTILS_DEP_DIR}\" }}\n"
+            ));
+        }
+        if !target_dependencies.is_empty() {
+            cargo_toml.push_str("\n[package.metadata.component.target.dependencies]\n");
+            cargo_toml.extend(target_dependencies);
+        }
         std::fs::write(project_dir.join("Cargo.toml"), cargo_toml)?;
 
         std::fs::write(project_dir.join("src/lib.rs"), &input.source_code)?;
+        for file in &input.files {
+            let path = project_dir.join(checked_source_path(&file.path)?);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, &file.content)?;
+        }
 
-        // Use the provided WIT or fall back to the standard girt-tool world.
-        let wit = if input.wit_definition.trim().is_empty()
-            || !input.wit_definition.contains("package")
-        {
-            DEFAULT_WIT.to_string()
-        } else {
-            // Strip version suffix from WIT package line if present.
-            // cargo-component v0.21 does not support versioned package names.
-            input
-                .wit_definition
-                .replace("package girt:tool@0.1.0;", "package girt:tool;")
-        };
+        let wit = wit + &host_imports_comment(&self.host_capabilities);
         std::fs::write(project_dir.join("wit/world.wit"), wit)?;
 
         Ok(project_dir)
     }
 
     pub async fn compile(&self, input: &CompileInput) -> Result<CompileOutput, PipelineError> {
-        let tmp = tempfile::tempdir()?;
-        let project_dir = self.scaffold_project(input, tmp.path())?;
-
-        let output = tokio::process::Command::new(&self.cargo_component_bin)
-            .arg("build")
-            .arg("--release")
-            .current_dir(&project_dir)
-            .output()
-            .await
-            .map_err(|e| {
-                PipelineError::CompilationError(format!(
-                    "Failed to run cargo-component: {e}. Is it installed? (cargo install cargo-component)"
-                ))
-            })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return Err(PipelineError::CompilationError(format!(
-                "cargo-component build failed:\nstdout: {stdout}\nstderr: {stderr}"
-            )));
-        }
-
-        let wasm_dir = project_dir
-            .join("target")
-            .join("wasm32-wasip1")
-            .join("release");
-
-        let wasm_filename = format!("{}.wasm", input.tool_name.replace('-', "_"));
-        let wasm_path = wasm_dir.join(&wasm_filename);
-
-        if !wasm_path.exists() {
-            let mut found = None;
-            if wasm_dir.exists() {
-                for entry in std::fs::read_dir(&wasm_dir)? {
-                    let entry = entry?;
-                    if entry.path().extension().is_some_and(|e| e == "wasm") {
-                        found = Some(entry.path());
-                        break;
-                    }
-                }
-            }
-            match found {
-                Some(path) => {
-                    return Ok(CompileOutput {
-                        wasm_path: path,
-                        build_dir: project_dir,
-                    })
-                }
-                None => {
-                    return Err(PipelineError::CompilationError(format!(
-                        "No .wasm file found in {}",
-                        wasm_dir.display()
-                    )))
-                }
+        let span = tracing::info_span!(
+            "compile",
+            tool = %input.tool_name,
+            cached = self.cache_dir.is_some(),
+        );
+        let started = Instant::now();
+        let output = async {
+            match &self.cache_dir {
+                Some(cache_dir) => self.compile_cached(input, cache_dir).await,
+                None => self.compile_uncached(input).await,
             }
         }
+        .instrument(span)
+        .await?;
+
+        let elapsed = started.elapsed();
+        let previous = self
+            .last_build
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(elapsed);
+        tracing::info!(
+            tool = %input.tool_name,
+            cached = self.cache_dir.is_some(),
+            duration_ms = elapsed.as_millis() as u64,
+            previous_ms = previous.map(|d| d.as_millis() as u64),
+            delta_ms = previous.map(|d| elapsed.as_millis() as i64 - d.as_millis() as i64),
+            "WASM compile finished"
+        );
+        Ok(output)
+    }
+
+    /// Build in a fresh scratch dir with its own target dir. The dir is
+    /// kept for `build_dir`; [`Scratch::sweep`] removes it later.
+    async fn compile_uncached(&self, input: &CompileInput) -> Result<CompileOutput, PipelineError> {
+        let tmp = self
+            .scratch
+            .create(&format!("girt-build-{}-", input.tool_name))?;
+        let project_dir = self.scaffold_project(input, tmp.path())?;
+
+        let target_dir = project_dir.join("target");
+        self.run_build(&project_dir, None).await?;
+        let wasm_path = find_wasm(&target_dir, input, true)?;
 
         let _ = tmp.keep();
 
@@ -159,6 +396,523 @@ path = "wit"
             build_dir: project_dir,
         })
     }
+
+    /// Build in the shared workspace, holding its lock throughout.
+    async fn compile_cached(
+        &self,
+        input: &CompileInput,
+        cache_dir: &Path,
+    ) -> Result<CompileOutput, PipelineError> {
+        let _lock = lock_build_cache(cache_dir).await?;
+
+        // Start from a clean source dir so files from an earlier build of
+        // this tool can't leak into this one.
+        let src_dir = cache_dir.join("src");
+        let stale = src_dir.join(&input.tool_name);
+        if stale.exists() {
+            tokio::fs::remove_dir_all(&stale).await?;
+        }
+        let project_dir = self.scaffold_project(input, &src_dir)?;
+
+        let target_dir = cache_dir.join("target");
+        self.run_build(&project_dir, Some(&target_dir)).await?;
+        // Other tools' outputs share this directory, so no fallback search.
+        let built = find_wasm(&target_dir, input, false)?;
+
+        // The shared target dir is overwritten by the next build of this
+        // tool; hand the caller a copy that outlives the lock.
+        let wasm_path = project_dir.join(wasm_filename(input));
+        tokio::fs::copy(&built, &wasm_path).await?;
+
+        Ok(CompileOutput {
+            wasm_path,
+            build_dir: project_dir,
+        })
+    }
+
+    /// Fetch dependencies, then build offline from the locked set.
+    async fn run_build(
+        &self,
+        project_dir: &Path,
+        target_dir: Option<&Path>,
+    ) -> Result<(), PipelineError> {
+        let tool = self.sandbox_tool();
+        if tool.is_some()
+            && let Some(target_dir) = target_dir
+        {
+            tokio::fs::create_dir_all(target_dir).await?;
+        }
+
+        let env = build_env(std::env::vars(), target_dir, false);
+        if tool.is_some()
+            && let Some((_, cargo_home)) = env.iter().find(|(key, _)| key == "CARGO_HOME")
+        {
+            // A first fetch creates these; in the sandbox only existing ones
+            // are writable.
+            for dir in ["registry", "git"] {
+                tokio::fs::create_dir_all(Path::new(cargo_home).join(dir)).await?;
+            }
+        }
+        let mut fetch = match tool {
+            Some(tool) => {
+                let policy = SandboxPolicy::for_build(
+                    &env,
+                    &self.cargo_component_bin,
+                    project_dir,
+                    target_dir,
+                )
+                .with_network();
+                sandboxed(tool, &self.cargo_component_bin, &["fetch"], &policy)
+            }
+            None => {
+                let mut command = tokio::process::Command::new(&self.cargo_component_bin);
+                command.arg("fetch");
+                command
+            }
+        };
+        fetch.current_dir(project_dir).env_clear().envs(env);
+        run_step(fetch, "fetch").await?;
+
+        let env = build_env(std::env::vars(), target_dir, true);
+        let args = ["build", "--release", "--locked"];
+        let mut build = match tool {
+            Some(tool) => {
+                let policy = SandboxPolicy::for_build(
+                    &env,
+                    &self.cargo_component_bin,
+                    project_dir,
+                    target_dir,
+                );
+                sandboxed(tool, &self.cargo_component_bin, &args, &policy)
+            }
+            None => {
+                let mut command = tokio::process::Command::new(&self.cargo_component_bin);
+                command.args(args);
+                command
+            }
+        };
+        build.current_dir(project_dir).env_clear().envs(env);
+        let _turn = self.build_step.lock().await;
+        run_step(build, "build").await
+    }
+
+    /// The sandbox to wrap builds in, if enabled and available.
+    fn sandbox_tool(&self) -> Option<SandboxTool> {
+        static DETECTED: OnceLock<Option<SandboxTool>> = OnceLock::new();
+        match self.sandbox {
+            BuildSandbox::Off => None,
+            BuildSandbox::Auto => *DETECTED.get_or_init(detect_sandbox),
+        }
+    }
+}
+
+/// How much of a failed step's output is kept in [`PipelineError::CompileFailed`].
+const STDERR_TAIL_BYTES: usize = 4000;
+
+/// Run one `cargo-component` step to completion.
+async fn run_step(mut command: tokio::process::Command, step: &str) -> Result<(), PipelineError> {
+    let stage = format!("cargo-component {step}");
+    let output = command
+        .output()
+        .await
+        .map_err(|e| PipelineError::CompileFailed {
+            stage: stage.clone(),
+            stderr_tail: format!(
+                "Failed to run cargo-component: {e}. Is it installed? (cargo install cargo-component)"
+            ),
+        })?;
+
+    if output.status.code().is_none() {
+        return Err(PipelineError::Cancelled(format!(
+            "{stage} was killed by a signal"
+        )));
+    }
+    if !output.status.success() {
+        // Compiler errors go to stderr; fall back to stdout for tools that
+        // report there.
+        let stream = if output.stderr.trim_ascii().is_empty() {
+            &output.stdout
+        } else {
+            &output.stderr
+        };
+        return Err(PipelineError::CompileFailed {
+            stage,
+            stderr_tail: tail(&String::from_utf8_lossy(stream), STDERR_TAIL_BYTES).into(),
+        });
+    }
+    Ok(())
+}
+
+/// The last `max` bytes of `text`, trimmed, cut at a char boundary.
+fn tail(text: &str, max: usize) -> &str {
+    let text = text.trim();
+    let mut start = text.len().saturating_sub(max);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+/// The environment for a `cargo-component` step: [`PASSTHROUGH_ENV`] from
+/// `parent`, the target dir, and `CARGO_NET_OFFLINE` when `offline`.
+fn build_env(
+    parent: impl Iterator<Item = (String, String)>,
+    target_dir: Option<&Path>,
+    offline: bool,
+) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = parent
+        .filter(|(key, _)| PASSTHROUGH_ENV.contains(&key.as_str()))
+        .collect();
+    let home = env
+        .iter()
+        .find(|(key, _)| key == "HOME")
+        .map(|(_, value)| PathBuf::from(value))
+        .or_else(|| paths::home_dir().ok());
+    for (key, default) in [("CARGO_HOME", ".cargo"), ("RUSTUP_HOME", ".rustup")] {
+        if let Some(home) = &home
+            && !env.iter().any(|(k, _)| k == key)
+        {
+            env.push((key.into(), home.join(default).display().to_string()));
+        }
+    }
+    if let Some(target_dir) = target_dir {
+        env.push(("CARGO_TARGET_DIR".into(), target_dir.display().to_string()));
+    }
+    if offline {
+        env.push(("CARGO_NET_OFFLINE".into(), "true".into()));
+    }
+    env
+}
+
+/// The sandbox on this host that can run a command, if any.
+fn detect_sandbox() -> Option<SandboxTool> {
+    let tool = if cfg!(target_os = "linux") {
+        // Unprivileged containers often lack the namespaces bwrap needs, so
+        // only a trial run shows whether it works.
+        let works = std::process::Command::new("bwrap")
+            .args(["--ro-bind", "/", "/", "--unshare-all", "--", "true"])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        works.then_some(SandboxTool::Bwrap)
+    } else if cfg!(target_os = "macos") {
+        Path::new("/usr/bin/sandbox-exec")
+            .exists()
+            .then_some(SandboxTool::SandboxExec)
+    } else {
+        None
+    };
+    match tool {
+        Some(tool) => tracing::info!(?tool, "Sandboxing tool builds"),
+        None => tracing::warn!(
+            "No working build sandbox found (bwrap or sandbox-exec); \
+             builds run unsandboxed with a stripped environment"
+        ),
+    }
+    tool
+}
+
+/// Entries of `CARGO_HOME` a sandboxed build may write to.
+///
+/// Dependency sources and the lock and usage files cargo keeps beside them.
+/// The rest, `bin/` and `config.toml` included, stays read-only so a build
+/// script cannot leave behind anything that later runs outside the sandbox.
+const CARGO_HOME_WRITABLE: &[&str] = &[
+    "registry",
+    "git",
+    ".package-cache",
+    ".package-cache-mutate",
+    ".global-cache",
+];
+
+/// The existing [`CARGO_HOME_WRITABLE`] entries of `cargo_home`.
+fn cargo_home_writable(cargo_home: &Path) -> Vec<PathBuf> {
+    CARGO_HOME_WRITABLE
+        .iter()
+        .map(|entry| cargo_home.join(entry))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// What a sandboxed `cargo-component` step may see and change.
+#[derive(Debug, Default)]
+struct SandboxPolicy {
+    /// Replaced by empty dirs: the home dir and girt's config and data dirs.
+    hidden: Vec<PathBuf>,
+    /// Mounted read-only, also inside `hidden` dirs and `/tmp`.
+    read_only: Vec<PathBuf>,
+    /// Writable, also inside `read_only` ones.
+    writable: Vec<PathBuf>,
+    /// Keep the network, for the fetch step.
+    network: bool,
+}
+
+impl SandboxPolicy {
+    /// The policy for building `project_dir` into `target_dir` with `bin`,
+    /// given the step's `env`. Only existing paths are listed.
+    fn for_build(
+        env: &[(String, String)],
+        bin: &str,
+        project_dir: &Path,
+        target_dir: Option<&Path>,
+    ) -> Self {
+        let var = |key: &str| {
+            env.iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| PathBuf::from(value))
+        };
+        let home = var("HOME").or_else(|| paths::home_dir().ok());
+        let hidden: Vec<PathBuf> = [home, paths::config_dir().ok(), paths::data_dir().ok()]
+            .into_iter()
+            .flatten()
+            .filter(|dir| dir.is_dir() && dir.parent().is_some())
+            .collect();
+
+        let cargo_home = var("CARGO_HOME").filter(|dir| dir.exists());
+        let mut read_only: Vec<PathBuf> = cargo_home
+            .iter()
+            .cloned()
+            .chain(var("RUSTUP_HOME").filter(|dir| dir.exists()))
+            .chain(resolve_bin(bin, var("PATH").as_deref()))
+            .collect();
+        // Toolchains installed under the home dir, e.g. in ~/.local/bin.
+        let path = var("PATH").unwrap_or_default();
+        read_only.extend(
+            std::env::split_paths(&path)
+                .filter(|dir| dir.is_dir())
+                .filter(|dir| hidden.iter().any(|h| dir.starts_with(h) && dir != h)),
+        );
+
+        let mut writable = vec![project_dir.to_path_buf()];
+        writable.extend(target_dir.map(Path::to_path_buf));
+        if let Some(cargo_home) = &cargo_home {
+            writable.extend(cargo_home_writable(cargo_home));
+        }
+        // cargo-component keeps fetched WIT packages in its cache dir.
+        let wit_cache = dirs::cache_dir().map(|dir| dir.join("cargo-component"));
+        writable.extend(wit_cache.filter(|dir| dir.exists()));
+
+        Self {
+            hidden,
+            read_only,
+            writable,
+            network: false,
+        }
+    }
+
+    fn with_network(mut self) -> Self {
+        self.network = true;
+        self
+    }
+}
+
+/// Where `bin` is run from: itself when it is a path, else the first match
+/// in `path`.
+fn resolve_bin(bin: &str, path: Option<&Path>) -> Option<PathBuf> {
+    let bin = Path::new(bin);
+    if bin.components().count() > 1 {
+        return bin.exists().then(|| bin.to_path_buf());
+    }
+    std::env::split_paths(path?)
+        .map(|dir| dir.join(bin))
+        .find(|candidate| candidate.is_file())
+}
+
+/// `bin args` run by `tool` under `policy`: `hidden` dirs replaced by empty
+/// ones, writes allowed only under `writable`, and no network unless
+/// `network` is set. `read_only` paths are mounted read-only even where the
+/// sandbox would otherwise hide or replace them (e.g. under `/tmp`);
+/// `writable` paths inside them stay writable.
+fn sandboxed(
+    tool: SandboxTool,
+    bin: &str,
+    args: &[&str],
+    policy: &SandboxPolicy,
+) -> tokio::process::Command {
+    match tool {
+        SandboxTool::Bwrap => {
+            let mut command = tokio::process::Command::new("bwrap");
+            command.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]);
+            command.args(["--tmpfs", "/tmp"]);
+            for dir in &policy.hidden {
+                command.arg("--tmpfs").arg(dir);
+            }
+            for dir in &policy.read_only {
+                command.arg("--ro-bind").arg(dir).arg(dir);
+            }
+            for dir in &policy.writable {
+                command.arg("--bind").arg(dir).arg(dir);
+            }
+            command.arg("--unshare-all");
+            if policy.network {
+                command.arg("--share-net");
+            }
+            command.args(["--die-with-parent", "--"]);
+            command.arg(bin).args(args);
+            command
+        }
+        SandboxTool::SandboxExec => {
+            // Later rules win over earlier ones.
+            let mut profile = String::from("(version 1)\n(allow default)\n");
+            if !policy.network {
+                profile.push_str("(deny network*)\n");
+            }
+            if !policy.hidden.is_empty() {
+                // Contents only: metadata stays readable for path lookups.
+                profile.push_str(&format!(
+                    "(deny file-read-data{})\n(allow file-read-data{}{})\n",
+                    subpaths(&policy.hidden),
+                    subpaths(&policy.read_only),
+                    subpaths(&policy.writable)
+                ));
+            }
+            profile.push_str(&format!(
+                "(deny file-write*)\n(allow file-write* (subpath \"/dev\") \
+                 (subpath \"/private/tmp\") (subpath \"/private/var/folders\"){})",
+                subpaths(&policy.writable)
+            ));
+            let mut command = tokio::process::Command::new("/usr/bin/sandbox-exec");
+            command.arg("-p").arg(profile).arg(bin).args(args);
+            command
+        }
+    }
+}
+
+/// ` (subpath "...")` for each of `paths`, for a `sandbox-exec` profile.
+fn subpaths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| {
+            // The sandbox matches resolved paths (/var is /private/var).
+            let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            let quoted = path.display().to_string().replace('\\', "\\\\");
+            let quoted = quoted.replace('"', "\\\"");
+            format!(" (subpath \"{quoted}\")")
+        })
+        .collect()
+}
+
+/// Validate a generated file path: relative, under `src/` or `tests/`, with
+/// no `..` or other non-plain components, and not `src/lib.rs` (which is
+/// always `source_code`).
+fn checked_source_path(path: &str) -> Result<PathBuf, PipelineError> {
+    let invalid = |reason: &str| {
+        PipelineError::LlmMalformedOutput(format!("Invalid source file path '{path}': {reason}"))
+    };
+    let relative = Path::new(path);
+    let mut components = relative.components();
+    match components.next() {
+        Some(Component::Normal(top)) if top == "src" || top == "tests" => {}
+        _ => return Err(invalid("must be under src/ or tests/")),
+    }
+    let mut depth = 0;
+    for component in components {
+        match component {
+            Component::Normal(_) => depth += 1,
+            _ => return Err(invalid("must not contain '..', '.', or a root")),
+        }
+    }
+    if depth == 0 {
+        return Err(invalid("must name a file"));
+    }
+    if relative == Path::new("src/lib.rs") {
+        return Err(invalid("src/lib.rs is reserved for source_code"));
+    }
+    Ok(relative.to_path_buf())
+}
+
+fn wasm_filename(input: &CompileInput) -> String {
+    format!("{}.wasm", input.tool_name.replace('-', "_"))
+}
+
+/// Locate the built component under `target_dir`. With `fallback`, any
+/// `.wasm` in the release dir is accepted when the expected name is missing.
+fn find_wasm(
+    target_dir: &Path,
+    input: &CompileInput,
+    fallback: bool,
+) -> Result<PathBuf, PipelineError> {
+    let wasm_dir = target_dir.join("wasm32-wasip1").join("release");
+    let wasm_path = wasm_dir.join(wasm_filename(input));
+    if wasm_path.exists() {
+        return Ok(wasm_path);
+    }
+
+    if fallback && wasm_dir.exists() {
+        for entry in std::fs::read_dir(&wasm_dir)? {
+            let entry = entry?;
+            if entry.path().extension().is_some_and(|e| e == "wasm") {
+                return Ok(entry.path());
+            }
+        }
+    }
+    Err(PipelineError::CompileFailed {
+        stage: "cargo-component build".into(),
+        stderr_tail: format!("No .wasm file found in {}", wasm_dir.display()),
+    })
+}
+
+/// Take the exclusive build-cache lock, waiting for any build in progress.
+/// Released when the returned file is dropped.
+async fn lock_build_cache(cache_dir: &Path) -> Result<std::fs::File, PipelineError> {
+    tokio::fs::create_dir_all(cache_dir).await?;
+    let path = cache_dir.join(".lock");
+    let file = tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        file.lock()?;
+        Ok::<_, std::io::Error>(file)
+    })
+    .await
+    .map_err(|e| PipelineError::CompileFailed {
+        stage: "build cache lock".into(),
+        stderr_tail: e.to_string(),
+    })??;
+    Ok(file)
+}
+
+/// A WIT comment listing the imports in `capabilities`, or nothing when
+/// there are none.
+fn host_imports_comment(capabilities: &HostCapabilities) -> String {
+    if capabilities.is_empty() {
+        return String::new();
+    }
+    let mut comment =
+        String::from("\n// Host imports girt-runtime links; add a line to the world to use one:\n");
+    for import in &capabilities.imports {
+        comment.push_str(&format!(
+            "//   {:<40} {}\n",
+            import.import_line(),
+            import.doc
+        ));
+    }
+    comment
+}
+
+/// WASI packages a world imports, with the version each is imported at
+/// (`import wasi:http/outgoing-handler@0.2.0;` → `("wasi:http", "0.2.0")`).
+/// cargo-component resolves these as target dependencies.
+fn wasi_dependencies(wit: &str) -> Vec<(String, String)> {
+    let mut packages: Vec<(String, String)> = Vec::new();
+    for line in wit.lines() {
+        let Some(path) = line.trim().strip_prefix("import wasi:") else {
+            continue;
+        };
+        let Some((name, rest)) = path.split_once('/') else {
+            continue;
+        };
+        let version = rest
+            .split_once('@')
+            .map(|(_, v)| v.trim_end_matches(';').trim())
+            .unwrap_or("0.2.0");
+        let package = format!("wasi:{name}");
+        if !packages.iter().any(|(p, _)| *p == package) {
+            packages.push((package, version.to_string()));
+        }
+    }
+    packages
 }
 
 impl Default for WasmCompiler {
@@ -170,6 +924,7 @@ impl Default for WasmCompiler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use girt_core::host::HostImport;
     use tempfile::TempDir;
 
     #[test]
@@ -182,6 +937,7 @@ mod tests {
             wit_definition: "package test:tool;".into(),
             tool_name: "test_tool".into(),
             tool_version: "0.1.0".into(),
+            files: vec![],
         };
 
         let build_dir = compiler.scaffold_project(&input, tmp.path()).unwrap();
@@ -191,6 +947,622 @@ mod tests {
         assert!(build_dir.join("wit/world.wit").exists());
     }
 
+    #[test]
+    fn scaffolds_the_configured_wit_version() {
+        let tmp = TempDir::new().unwrap();
+        let mut input = CompileInput {
+            source_code: "// placeholder".into(),
+            wit_definition: String::new(),
+            tool_name: "structured".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        };
+        let compiler = WasmCompiler::new().with_wit_version(WitVersion::V0_2);
+
+        let dir = compiler.scaffold_project(&input, tmp.path()).unwrap();
+        let wit = std::fs::read_to_string(dir.join("wit/world.wit")).unwrap();
+        assert!(wit.contains("result<string, tool-error>"));
+
+        input.wit_definition = DEFAULT_WIT_0_2.replace("girt:tool;", "girt:tool@0.2.0;");
+        let dir = compiler.scaffold_project(&input, tmp.path()).unwrap();
+        let wit = std::fs::read_to_string(dir.join("wit/world.wit")).unwrap();
+        assert!(wit.starts_with("package girt:tool;\n"));
+    }
+
+    #[test]
+    fn scaffolds_wasi_imports_as_target_dependencies() {
+        let tmp = TempDir::new().unwrap();
+        let input = CompileInput {
+            source_code: "// placeholder".into(),
+            wit_definition: r#"package girt:tool;
+
+world girt-tool {
+    import wasi:http/outgoing-handler@0.2.0;
+    import wasi:http/types@0.2.0;
+    import wasi:clocks/monotonic-clock@0.2.0;
+
+    export run: func(input: string) -> result<string, string>;
+}
+"#
+            .into(),
+            tool_name: "poller".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        };
+
+        let dir = WasmCompiler::new()
+            .scaffold_project(&input, tmp.path())
+            .unwrap();
+        let cargo_toml = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.ends_with(
+            "[package.metadata.component.target.dependencies]\n\
+             \"wasi:http\" = \"0.2.0\"\n\
+             \"wasi:clocks\" = \"0.2.0\"\n"
+        ));
+
+        assert!(wasi_dependencies(DEFAULT_WIT).is_empty());
+    }
+
+    #[test]
+    fn scaffolds_the_host_utils_interface_when_imported() {
+        let tmp = TempDir::new().unwrap();
+        let mut input = CompileInput {
+            source_code: "// placeholder".into(),
+            wit_definition: r#"package girt:tool;
+
+world girt-tool {
+    import girt:host/utils@0.1.0;
+
+    export run: func(input: string) -> result<string, string>;
+}
+"#
+            .into(),
+            tool_name: "stamper".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        };
+
+        let dir = WasmCompiler::new()
+            .scaffold_project(&input, tmp.path())
+            .unwrap();
+        let cargo_toml = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.ends_with(
+            "[package.metadata.component.target.dependencies]\n\
+             \"girt:host\" = { path = \"wit/deps/girt-host\" }\n"
+        ));
+        let utils = std::fs::read_to_string(dir.join("wit/deps/girt-host/utils.wit")).unwrap();
+        assert_eq!(utils, HOST_UTILS_WIT);
+
+        input.wit_definition = String::new();
+        input.tool_name = "plain".into();
+        let dir = WasmCompiler::new()
+            .scaffold_project(&input, tmp.path())
+            .unwrap();
+        assert!(!dir.join("wit/deps").exists());
+    }
+
+    #[test]
+    fn scaffolded_worlds_list_the_host_imports() {
+        let tmp = TempDir::new().unwrap();
+        let input = CompileInput {
+            source_code: "// placeholder".into(),
+            wit_definition: String::new(),
+            tool_name: "plain".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        };
+        let capabilities = HostCapabilities {
+            imports: vec![
+                HostImport::new("girt:host/utils@0.1.0", "Clock, UUID, and logging helpers."),
+                HostImport::new("wasi:http/outgoing-handler@0.2.0", "Outgoing HTTP."),
+            ],
+        };
+
+        let dir = WasmCompiler::new()
+            .with_host_capabilities(capabilities.clone())
+            .scaffold_project(&input, tmp.path())
+            .unwrap();
+        let wit = std::fs::read_to_string(dir.join("wit/world.wit")).unwrap();
+        assert!(wit.starts_with(DEFAULT_WIT));
+        assert!(wit.ends_with(
+            "// Host imports girt-runtime links; add a line to the world to use one:\n\
+             //   import girt:host/utils@0.1.0;            Clock, UUID, and logging helpers.\n\
+             //   import wasi:http/outgoing-handler@0.2.0; Outgoing HTTP.\n"
+        ));
+
+        let dir = WasmCompiler::new()
+            .with_host_capabilities(capabilities.without("girt:host/utils@0.1.0"))
+            .scaffold_project(&input, tmp.path())
+            .unwrap();
+        let wit = std::fs::read_to_string(dir.join("wit/world.wit")).unwrap();
+        assert!(wit.contains("wasi:http/outgoing-handler") && !wit.contains("girt:host"));
+    }
+
+    #[test]
+    fn scaffolds_extra_source_and_test_files() {
+        let tmp = TempDir::new().unwrap();
+        let input = CompileInput {
+            source_code: "mod helpers;".into(),
+            wit_definition: String::new(),
+            tool_name: "multi_file".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![
+                SourceFile {
+                    path: "src/helpers.rs".into(),
+                    content: "pub fn helper() {}".into(),
+                },
+                SourceFile {
+                    path: "src/nested/deep.rs".into(),
+                    content: "// nested".into(),
+                },
+                SourceFile {
+                    path: "tests/run.rs".into(),
+                    content: "#[test] fn ok() {}".into(),
+                },
+            ],
+        };
+
+        let dir = WasmCompiler::new()
+            .scaffold_project(&input, tmp.path())
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+            "mod helpers;"
+        );
+        assert!(dir.join("src/helpers.rs").exists());
+        assert!(dir.join("src/nested/deep.rs").exists());
+        assert!(dir.join("tests/run.rs").exists());
+    }
+
+    #[test]
+    fn rejects_paths_outside_src_and_tests() {
+        for path in [
+            "../escape.rs",
+            "src/../../escape.rs",
+            "/etc/passwd",
+            "build.rs",
+            "Cargo.toml",
+            "src",
+            "./src/x.rs",
+            "src/lib.rs",
+        ] {
+            let err = checked_source_path(path).unwrap_err();
+            assert!(
+                matches!(&err, PipelineError::LlmMalformedOutput(msg) if msg.contains(path)),
+                "{path}: {err}"
+            );
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let input = CompileInput {
+            source_code: String::new(),
+            wit_definition: String::new(),
+            tool_name: "evil".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![SourceFile {
+                path: "tests/../../outside.rs".into(),
+                content: "oops".into(),
+            }],
+        };
+        assert!(
+            WasmCompiler::new()
+                .scaffold_project(&input, tmp.path())
+                .is_err()
+        );
+        assert!(!tmp.path().join("outside.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn missing_binary_is_a_compile_failure() {
+        let compiler = WasmCompiler::new()
+            .without_cache()
+            .with_binary("/nonexistent/cargo-component");
+        let input = CompileInput {
+            source_code: "// placeholder".into(),
+            wit_definition: String::new(),
+            tool_name: "test_tool".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        };
+
+        let err = compiler.compile(&input).await.err().unwrap();
+        assert!(matches!(
+            &err,
+            PipelineError::CompileFailed { stage, stderr_tail }
+                if stage.starts_with("cargo-component ") && stderr_tail.contains("Failed to run")
+        ));
+        assert_eq!(err.code(), "compile_failed");
+    }
+
+    /// A `cargo-component` stand-in. It writes `<tool>.wasm` containing
+    /// "cold" or "warm" depending on whether an earlier build already left
+    /// shared dependencies in the target dir, and fails if another build is
+    /// using the same target dir at the same time.
+    #[cfg(unix)]
+    fn fake_cargo_component(dir: &Path) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("fake-cargo-component");
+        std::fs::write(
+            &script,
+            r#"#!/bin/sh
+set -e
+[ "$1" = fetch ] && exit 0
+target="${CARGO_TARGET_DIR:-target}"
+mkdir -p "$target"
+mkdir "$target.busy"
+if [ -e "$target/deps.built" ]; then state=warm; else state=cold; fi
+touch "$target/deps.built"
+sleep 0.2
+mkdir -p "$target/wasm32-wasip1/release"
+printf '%s' "$state" > "$target/wasm32-wasip1/release/$(basename "$PWD").wasm"
+rmdir "$target.busy"
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script.display().to_string()
+    }
+
+    #[cfg(unix)]
+    fn input(tool_name: &str) -> CompileInput {
+        CompileInput {
+            source_code: "// placeholder".into(),
+            wit_definition: String::new(),
+            tool_name: tool_name.into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn second_build_reuses_shared_target_dir() {
+        let tmp = TempDir::new().unwrap();
+        let cache = tmp.path().join("build-cache");
+        let compiler = WasmCompiler::new()
+            .with_cache_dir(&cache)
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(fake_cargo_component(tmp.path()));
+
+        let first = compiler.compile(&input("tool_a")).await.unwrap();
+        let second = compiler.compile(&input("tool_b")).await.unwrap();
+
+        assert!(cache.join("target/deps.built").exists());
+        assert_eq!(std::fs::read_to_string(&first.wasm_path).unwrap(), "cold");
+        assert_eq!(std::fs::read_to_string(&second.wasm_path).unwrap(), "warm");
+        assert_eq!(second.build_dir, cache.join("src/tool_b"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn concurrent_builds_serialize_on_lock() {
+        let tmp = TempDir::new().unwrap();
+        let cache = tmp.path().join("build-cache");
+        let bin = fake_cargo_component(tmp.path());
+        let compiler = || {
+            WasmCompiler::new()
+                .with_cache_dir(&cache)
+                .with_sandbox(BuildSandbox::Off)
+                .with_binary(&bin)
+        };
+        let (a, b) = (compiler(), compiler());
+
+        // Overlapping builds would trip the stand-in's busy marker.
+        let (input_a, input_b) = (input("tool_a"), input("tool_b"));
+        let (first, second) = tokio::join!(a.compile(&input_a), b.compile(&input_b));
+        assert!(first.is_ok() && second.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn uncached_builds_on_one_compiler_take_turns() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new().unwrap();
+        // Fails if another build step is running, whatever its target dir.
+        let busy = tmp.path().join("build.busy");
+        let script = tmp.path().join("fake-cargo-component");
+        std::fs::write(
+            &script,
+            format!(
+                r#"#!/bin/sh
+set -e
+[ "$1" = fetch ] && exit 0
+mkdir '{busy}'
+sleep 0.2
+mkdir -p target/wasm32-wasip1/release
+printf cold > "target/wasm32-wasip1/release/$(basename "$PWD").wasm"
+rmdir '{busy}'
+"#,
+                busy = busy.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let scratch = tmp.path().join("scratch");
+        let compiler = WasmCompiler::new()
+            .without_cache()
+            .with_scratch(Scratch::new(&scratch))
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(script.display().to_string());
+
+        let (input_a, input_b) = (input("tool_a"), input("tool_b"));
+        let (first, second) = tokio::join!(compiler.compile(&input_a), compiler.compile(&input_b));
+        let (first, second) = (first.unwrap(), second.unwrap());
+        // Each build gets its own dir under the scratch root.
+        assert!(first.build_dir.starts_with(&scratch));
+        assert!(second.build_dir.starts_with(&scratch));
+        assert_ne!(first.build_dir.parent(), second.build_dir.parent());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stale_source_files_are_removed_between_builds() {
+        let tmp = TempDir::new().unwrap();
+        let cache = tmp.path().join("build-cache");
+        let stale = cache.join("src/tool_a/src/old_module.rs");
+        std::fs::create_dir_all(stale.parent().unwrap()).unwrap();
+        std::fs::write(&stale, "// left over").unwrap();
+
+        let compiler = WasmCompiler::new()
+            .with_cache_dir(&cache)
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(fake_cargo_component(tmp.path()));
+        compiler.compile(&input("tool_a")).await.unwrap();
+
+        assert!(!stale.exists());
+        assert!(cache.join("src/tool_a/src/lib.rs").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn without_cache_builds_cold_every_time() {
+        let tmp = TempDir::new().unwrap();
+        let compiler = WasmCompiler::new()
+            .without_cache()
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(fake_cargo_component(tmp.path()));
+
+        for tool in ["tool_a", "tool_b"] {
+            let output = compiler.compile(&input(tool)).await.unwrap();
+            assert_eq!(std::fs::read_to_string(&output.wasm_path).unwrap(), "cold");
+        }
+    }
+
+    #[test]
+    fn build_env_keeps_only_the_allowlist() {
+        let parent = [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/dev"),
+            ("ANTHROPIC_API_KEY", "sk-ant-secret"),
+            ("GITHUB_TOKEN", "ghp_secret"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let env = build_env(parent.clone().into_iter(), Some(Path::new("/t")), true);
+        let expected = [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/dev"),
+            ("CARGO_HOME", "/home/dev/.cargo"),
+            ("RUSTUP_HOME", "/home/dev/.rustup"),
+            ("CARGO_TARGET_DIR", "/t"),
+            ("CARGO_NET_OFFLINE", "true"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        assert_eq!(env, expected);
+
+        let fetch_env = build_env(parent.into_iter(), None, false);
+        assert!(fetch_env.iter().all(|(k, _)| k != "CARGO_NET_OFFLINE"));
+    }
+
+    /// A `cargo-component` stand-in that records each call's arguments and
+    /// environment in `dir/calls`, and builds a component.
+    #[cfg(unix)]
+    fn recording_cargo_component(dir: &Path) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("recording-cargo-component");
+        std::fs::write(
+            &script,
+            format!(
+                r#"#!/bin/sh
+set -e
+echo "args $*" >> "{calls}"
+env | sed 's/^/env /' >> "{calls}"
+[ "$1" = fetch ] && exit 0
+mkdir -p target/wasm32-wasip1/release
+printf built > "target/wasm32-wasip1/release/$(basename "$PWD").wasm"
+"#,
+                calls = dir.join("calls").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script.display().to_string()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn builds_offline_and_locked_with_a_stripped_environment() {
+        let tmp = TempDir::new().unwrap();
+        let compiler = WasmCompiler::new()
+            .without_cache()
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(recording_cargo_component(tmp.path()));
+        compiler.compile(&input("tool_a")).await.unwrap();
+
+        let calls = std::fs::read_to_string(tmp.path().join("calls")).unwrap();
+        let steps: Vec<&str> = calls.split("args ").skip(1).collect();
+        let [fetch, build] = steps.as_slice() else {
+            panic!("expected fetch then build, got {calls}");
+        };
+        assert!(fetch.starts_with("fetch\n"), "{fetch}");
+        assert!(build.starts_with("build --release --locked\n"), "{build}");
+        assert!(build.contains("env CARGO_NET_OFFLINE=true\n"));
+        assert!(!fetch.contains("CARGO_NET_OFFLINE"));
+
+        // Only the allowlist, and what the shell adds itself.
+        let allowed = [
+            "CARGO_TARGET_DIR",
+            "CARGO_NET_OFFLINE",
+            "PWD",
+            "OLDPWD",
+            "SHLVL",
+            "_",
+        ];
+        for line in calls.lines().filter_map(|l| l.strip_prefix("env ")) {
+            let key = line.split('=').next().unwrap_or_default();
+            assert!(
+                PASSTHROUGH_ENV.contains(&key) || allowed.contains(&key),
+                "{key} leaked into the build"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sandboxed_build_cannot_write_outside_its_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        if detect_sandbox().is_none() {
+            eprintln!("skipping: no working bwrap or sandbox-exec");
+            return;
+        }
+        // Not under /tmp, which the sandbox replaces with its own.
+        let tmp = TempDir::new_in(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let outside = tmp.path().join("escaped");
+        let bin = tmp.path().join("sandboxed-cargo-component");
+        std::fs::write(
+            &bin,
+            format!(
+                r#"#!/bin/sh
+[ "$1" = fetch ] && exit 0
+touch "{outside}" 2>/dev/null
+release="$CARGO_TARGET_DIR/wasm32-wasip1/release"
+mkdir -p "$release" && printf built > "$release/$(basename "$PWD").wasm"
+"#,
+                outside = outside.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let compiler = WasmCompiler::new()
+            .with_cache_dir(tmp.path().join("build-cache"))
+            .with_binary(bin.display().to_string());
+        let output = compiler.compile(&input("tool_a")).await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(output.wasm_path).unwrap(), "built");
+        assert!(!outside.exists(), "the build wrote outside its sandbox");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sandboxed_build_can_write_only_cargo_sources() {
+        let Some(tool) = detect_sandbox() else {
+            eprintln!("skipping: no working bwrap or sandbox-exec");
+            return;
+        };
+        // Not under /tmp, which the sandbox replaces with its own.
+        let tmp = TempDir::new_in(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let cargo_home = tmp.path().join("cargo-home");
+        for dir in ["registry", "git", "bin"] {
+            std::fs::create_dir_all(cargo_home.join(dir)).unwrap();
+        }
+        std::fs::write(cargo_home.join("config.toml"), "").unwrap();
+
+        let script = "touch registry/fetched git/checkout bin/planted; \
+                      echo '[build]' >> config.toml; true";
+        let policy = SandboxPolicy {
+            read_only: vec![cargo_home.clone()],
+            writable: cargo_home_writable(&cargo_home),
+            ..SandboxPolicy::default()
+        };
+        let mut command = sandboxed(tool, "sh", &["-c", script], &policy);
+        let status = command.current_dir(&cargo_home).status().await.unwrap();
+        assert!(status.success());
+
+        assert!(cargo_home.join("registry/fetched").exists());
+        assert!(cargo_home.join("git/checkout").exists());
+        assert!(!cargo_home.join("bin/planted").exists());
+        assert_eq!(
+            std::fs::read_to_string(cargo_home.join("config.toml")).unwrap(),
+            ""
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sandboxed_build_cannot_read_hidden_dirs() {
+        let Some(tool) = detect_sandbox() else {
+            eprintln!("skipping: no working bwrap or sandbox-exec");
+            return;
+        };
+        // Not under /tmp, which the sandbox replaces with its own.
+        let tmp = TempDir::new_in(env!("CARGO_MANIFEST_DIR")).unwrap();
+        let home = tmp.path().join("home");
+        let cargo_home = home.join(".cargo");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        std::fs::create_dir_all(&cargo_home).unwrap();
+        std::fs::write(home.join(".ssh/id_ed25519"), "secret").unwrap();
+        std::fs::write(cargo_home.join("config.toml"), "").unwrap();
+
+        let policy = SandboxPolicy {
+            hidden: vec![home.clone()],
+            read_only: vec![cargo_home.clone()],
+            ..SandboxPolicy::default()
+        };
+        let script = format!(
+            "test -r '{config}' && ! cat '{key}'",
+            config = cargo_home.join("config.toml").display(),
+            key = home.join(".ssh/id_ed25519").display()
+        );
+        let status = sandboxed(tool, "sh", &["-c", &script], &policy)
+            .current_dir(tmp.path())
+            .status()
+            .await
+            .unwrap();
+        assert!(
+            status.success(),
+            "the key was readable or cargo home was not"
+        );
+    }
+
+    #[test]
+    fn bwrap_hides_dirs_and_keeps_the_network_only_when_asked() {
+        let policy = SandboxPolicy {
+            hidden: vec!["/home/dev".into()],
+            read_only: vec!["/home/dev/.cargo".into()],
+            writable: vec!["/home/dev/.cargo/registry".into()],
+            network: false,
+        };
+        let args = |policy: &SandboxPolicy| -> Vec<String> {
+            sandboxed(SandboxTool::Bwrap, "cargo-component", &["fetch"], policy)
+                .as_std()
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+        let offline = args(&policy);
+        let position = |args: &[String], pair: [&str; 2]| {
+            args.windows(2)
+                .position(|w| w[0] == pair[0] && w[1] == pair[1])
+                .unwrap_or_else(|| panic!("{pair:?} missing from {args:?}"))
+        };
+        // Mounts apply in order: hide the home dir, then re-expose in it.
+        let hidden = position(&offline, ["--tmpfs", "/home/dev"]);
+        let read_only = position(&offline, ["--ro-bind", "/home/dev/.cargo"]);
+        let writable = position(&offline, ["--bind", "/home/dev/.cargo/registry"]);
+        assert!(hidden < read_only && read_only < writable, "{offline:?}");
+        assert!(offline.contains(&"--unshare-all".to_string()));
+        assert!(!offline.contains(&"--share-net".to_string()));
+
+        let online = args(&policy.with_network());
+        assert!(online.contains(&"--share-net".to_string()));
+    }
+
     #[tokio::test]
     #[ignore] // Requires cargo-component installed
     async fn compiles_minimal_wasm_component() {
@@ -224,6 +1596,7 @@ world girt-tool {
             .into(),
             tool_name: "echo_tool".into(),
             tool_version: "0.1.0".into(),
+            files: vec![],
         };
 
         let output = compiler.compile(&input).await.unwrap();
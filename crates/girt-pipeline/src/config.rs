@@ -1,10 +1,32 @@
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
-use serde::Deserialize;
+use girt_core::engine::DEFAULT_EXECUTION_ALLOW_TTL;
+use girt_core::faults::FaultInjectionConfig;
+use girt_core::layers::budget::ConstraintBudget;
+use girt_core::layers::cli_check::CliUtility;
+use girt_core::layers::force_ask::AlwaysAsk;
+use girt_core::layers::llm::DEFAULT_MIN_ALLOW_CONFIDENCE;
+use girt_core::layers::policy::PolicyPattern;
+use girt_core::limits::{OversizeResponse, SizeLimits};
+use girt_core::paths;
+use serde::{Deserialize, Serialize};
 
+use crate::compiler::BuildSandbox;
 use crate::error::PipelineError;
-use crate::llm::{AnthropicLlmClient, LlmClient, OpenAiCompatibleClient, StubLlmClient};
+use crate::llm::{
+    ANTHROPIC_BASE_URL, AnthropicLlmClient, LlmClient, OpenAiCompatibleClient, Sampling,
+    StubLlmClient,
+};
+use crate::llm_cache::{self, CachingLlmClient};
+use crate::llm_fallback::FallbackLlmClient;
+use crate::publish::NameCollision;
+use crate::queue;
+use crate::static_checks::StaticChecks;
+use crate::tool_sync;
+use crate::types::{BlockingPolicy, CodingStandards, ResourceTier, TargetLanguage, WitVersion};
 
 #[derive(Debug, Deserialize)]
 pub struct GirtConfig {
@@ -15,38 +37,765 @@ pub struct GirtConfig {
     pub build: BuildConfig,
     #[serde(default)]
     pub pipeline: PipelineConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub cli_check: CliCheckConfig,
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+}
+
+/// Extra policy rules, evaluated after the built-in defaults.
+///
+/// ```toml
+/// [[policy.deny]]
+/// description = "No crypto miners"
+/// name_pattern = "(?i)miner"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub deny: Vec<PolicyPattern>,
+    #[serde(default)]
+    pub allow: Vec<PolicyPattern>,
+}
+
+/// CLI utilities the Creation Gate may defer to, on top of the built-in
+/// list. Each is offered only if its binary is on `PATH` at startup.
+///
+/// ```toml
+/// [[cli_check.utilities]]
+/// binary = "yq"
+/// description = "YAML processor"
+/// keywords = ["yaml_query"]
+/// example_invocation = "yq '.spec' deploy.yaml"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CliCheckConfig {
+    /// Matched before the built-ins; an entry replaces the built-in with
+    /// the same binary.
+    #[serde(default)]
+    pub utilities: Vec<CliUtility>,
+}
+
+/// Decision engine reporting and payload limits.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityConfig {
+    /// Attach the per-layer cascade trace to every gate decision returned to
+    /// the agent. Also enabled implicitly when logging at debug level.
+    #[serde(default)]
+    pub include_decision_trace: bool,
+    /// Largest accepted capability spec, serialized.
+    #[serde(default = "default_max_spec_bytes")]
+    pub max_spec_bytes: usize,
+    /// Longest accepted capability description.
+    #[serde(default = "default_max_description_chars")]
+    pub max_description_chars: usize,
+    /// Largest accepted tool-call arguments object, serialized.
+    #[serde(default = "default_max_argument_bytes")]
+    pub max_argument_bytes: usize,
+    /// Append every gate decision to `~/.girt/audit.jsonl`.
+    #[serde(default)]
+    pub audit_log: bool,
+    /// On startup, reload decisions younger than this (`"30m"`, `"1h"`) from
+    /// the audit log into the gate caches.
+    #[serde(default, deserialize_with = "deserialize_opt_duration")]
+    pub warm_cache_from_audit: Option<Duration>,
+    /// LLM gate Allows with a confidence below this (0 to 1) are put to a
+    /// human as Ask instead.
+    #[serde(default = "default_min_allow_confidence")]
+    pub min_allow_confidence: f64,
+    /// How long an Execution Gate Allow is reused for an identical call to
+    /// a read-only tool without secrets. `"0s"` evaluates every call.
+    #[serde(
+        default = "default_execution_allow_ttl",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub execution_allow_ttl: Duration,
+    /// Most network hosts, storage paths, and secrets one requested spec
+    /// may have, per request source.
+    #[serde(default)]
+    pub budget: ConstraintBudget,
+    /// Tool name patterns whose requests and calls always go to a human,
+    /// whatever the LLM evaluator would decide.
+    #[serde(default)]
+    pub always_ask: AlwaysAsk,
+    /// On startup, put every restored tool through the policy rules again
+    /// and quarantine the ones they now deny.
+    #[serde(default = "default_true")]
+    pub recheck_on_restore: bool,
+    /// Let the agent answer Creation Gate asks with `approve_capability`.
+    /// Off, only an operator can, with `girt approve` or `girt reject`.
+    /// Tool calls and always_ask requests are operator-only either way.
+    #[serde(default)]
+    pub agent_approvals: bool,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            include_decision_trace: false,
+            max_spec_bytes: default_max_spec_bytes(),
+            max_description_chars: default_max_description_chars(),
+            max_argument_bytes: default_max_argument_bytes(),
+            audit_log: false,
+            warm_cache_from_audit: None,
+            min_allow_confidence: default_min_allow_confidence(),
+            execution_allow_ttl: default_execution_allow_ttl(),
+            budget: ConstraintBudget::default(),
+            always_ask: AlwaysAsk::default(),
+            recheck_on_restore: true,
+            agent_approvals: false,
+        }
+    }
+}
+
+impl SecurityConfig {
+    pub fn size_limits(&self) -> SizeLimits {
+        SizeLimits {
+            max_spec_bytes: self.max_spec_bytes,
+            max_description_chars: self.max_description_chars,
+            max_argument_bytes: self.max_argument_bytes,
+        }
+    }
+}
+
+fn default_min_allow_confidence() -> f64 {
+    DEFAULT_MIN_ALLOW_CONFIDENCE
+}
+
+fn default_execution_allow_ttl() -> Duration {
+    DEFAULT_EXECUTION_ALLOW_TTL
+}
+
+fn default_max_spec_bytes() -> usize {
+    SizeLimits::default().max_spec_bytes
+}
+
+fn default_max_description_chars() -> usize {
+    SizeLimits::default().max_description_chars
+}
+
+fn default_max_argument_bytes() -> usize {
+    SizeLimits::default().max_argument_bytes
+}
+
+fn deserialize_opt_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Some(text) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    parse_duration(&text).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    parse_duration(&text).map_err(serde::de::Error::custom)
+}
+
+/// Parse a duration like `"90s"`, `"30m"`, `"1h"`, or `"2d"`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{text}': expected e.g. \"30m\" or \"1h\""))?;
+    let secs = match unit {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 60 * 60,
+        "d" => value * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid duration '{text}': unit must be s, m, h, or d"
+            ));
+        }
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+/// Per-tool runtime settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolsConfig {
+    /// Environment variables injected into a tool's WASI context, keyed by
+    /// tool name (`[tools.env.<tool_name>]`). Only keys the component's
+    /// policy allows under `permissions.environment` are passed through.
+    #[serde(default)]
+    pub env: HashMap<String, HashMap<String, String>>,
+    /// How long a deprecated tool keeps answering calls (with a warning)
+    /// before calls are refused and pointed at its replacement.
+    #[serde(
+        default = "default_deprecation_grace",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub deprecation_grace: Duration,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            env: HashMap::new(),
+            deprecation_grace: default_deprecation_grace(),
+        }
+    }
+}
+
+fn default_deprecation_grace() -> Duration {
+    Duration::from_secs(7 * 24 * 60 * 60)
+}
+
+/// WASM runtime settings. All of them are read at startup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuntimeConfig {
+    /// How components reach the network.
+    #[serde(default)]
+    pub egress_mode: EgressMode,
+    /// Preallocate instance and memory slots instead of allocating them on
+    /// every call.
+    #[serde(default)]
+    pub pooling_allocator: bool,
+    /// Largest size any one component memory may grow to, in MiB.
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: u32,
+    /// Component instances alive at once; bounds the pooling allocator.
+    #[serde(default = "default_max_instances")]
+    pub max_instances: u32,
+    /// How often a running component yields to other tasks, in ms. `0`
+    /// disables epoch ticks.
+    #[serde(default)]
+    pub epoch_tick_ms: u64,
+    /// Emit native unwind info, for host backtraces through WASM frames.
+    #[serde(default = "default_true")]
+    pub native_unwind_info: bool,
+    /// Count the fuel each tool call burns, for its resource usage.
+    #[serde(default)]
+    pub fuel_metering: bool,
+    /// Break a fraction of tool calls on purpose, for resilience tests.
+    /// `GIRT_FAULT_*` variables override it at startup.
+    #[serde(default)]
+    pub fault_injection: FaultInjectionConfig,
+    /// Largest response any tool may return, in bytes, whatever its
+    /// policy's `max_response_bytes` allows.
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: u64,
+    /// Whether a response over its limit fails the call or is truncated.
+    #[serde(default)]
+    pub oversize_response: OversizeResponse,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            egress_mode: EgressMode::default(),
+            pooling_allocator: false,
+            max_memory_mb: default_max_memory_mb(),
+            max_instances: default_max_instances(),
+            epoch_tick_ms: 0,
+            native_unwind_info: true,
+            fuel_metering: false,
+            fault_injection: FaultInjectionConfig::default(),
+            max_response_bytes: default_max_response_bytes(),
+            oversize_response: OversizeResponse::default(),
+        }
+    }
+}
+
+/// The largest resource tier's `max_response_bytes`.
+fn default_max_response_bytes() -> u64 {
+    ResourceTier::Extended.to_resources().max_response_bytes
+}
+
+/// Room for the largest resource tier's `memory_mb`.
+fn default_max_memory_mb() -> u32 {
+    ResourceTier::Extended.to_resources().memory_mb
+}
+
+fn default_max_instances() -> u32 {
+    100
+}
+
+impl RuntimeConfig {
+    /// Reject settings under which tools would trap on their first call.
+    pub fn validate(&self) -> Result<(), PipelineError> {
+        let tier = ResourceTier::default();
+        let needed = tier.to_resources().memory_mb;
+        if self.max_memory_mb < needed {
+            return Err(PipelineError::ConfigError(format!(
+                "runtime.max_memory_mb = {} is below the {tier:?} tier's memory_mb of {needed}; \
+                 tools built for it could not allocate their memory",
+                self.max_memory_mb
+            )));
+        }
+        if self.pooling_allocator && self.max_instances == 0 {
+            return Err(PipelineError::ConfigError(
+                "runtime.max_instances must be at least 1 with the pooling allocator".into(),
+            ));
+        }
+        self.fault_injection
+            .injector()
+            .map_err(|e| PipelineError::ConfigError(format!("runtime.fault_injection: {e}")))?;
+        Ok(())
+    }
+
+    /// Whether `self` and `other` build different Wasmtime engines.
+    fn engine_differs(&self, other: &RuntimeConfig) -> bool {
+        self.pooling_allocator != other.pooling_allocator
+            || self.max_memory_mb != other.max_memory_mb
+            || self.max_instances != other.max_instances
+            || self.epoch_tick_ms != other.epoch_tick_ms
+            || self.native_unwind_info != other.native_unwind_info
+            || self.fuel_metering != other.fuel_metering
+    }
+}
+
+/// How a component's outgoing HTTP leaves the runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EgressMode {
+    /// Requests are sent as the component made them.
+    #[default]
+    Direct,
+    /// Requests pass through the host's egress proxy, which enforces the
+    /// tool's network allowlist and `max_response_bytes` and logs each one.
+    Proxy,
+}
+
+/// Trace export, read at startup. Spans are only exported by binaries built
+/// with the `otel` feature.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP collector to send spans to, e.g.
+    /// `http://localhost:4318/v1/traces`. Unset disables export.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// `service.name` of the exported spans.
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: default_service_name(),
+        }
+    }
+}
+
+fn default_service_name() -> String {
+    "girt".into()
+}
+
+/// MCP server behaviour.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    /// Attach tool outputs, gate decisions, and build summaries as MCP
+    /// `structuredContent` in addition to the JSON text rendering.
+    #[serde(default = "default_true")]
+    pub structured_results: bool,
+    /// Wrap each tool call's structured result as `{ok, data | error,
+    /// meta}`, with its duration, truncation, and tool version in `meta`.
+    /// The text content stays the raw output. Needs `structured_results`.
+    #[serde(default)]
+    pub result_envelope: bool,
+    /// Append one JSON line per handled MCP request to
+    /// `~/.girt/requests.jsonl` (argument sizes only, never values).
+    #[serde(default)]
+    pub request_log: bool,
+    /// Check call arguments against the tool's `input_schema` before
+    /// invoking it. Disable if generated schemas are too sloppy to trust.
+    #[serde(default = "default_true")]
+    pub validate_arguments: bool,
+    /// How long a capability request awaiting human approval stays
+    /// resolvable, in seconds.
+    #[serde(default = "default_approval_ttl_secs")]
+    pub approval_ttl_secs: u64,
+    /// Extra attempts for a tool marked idempotent when it fails with a
+    /// transient error (5xx, timeout, connection reset). `0` disables retry.
+    #[serde(default = "default_tool_retry_attempts")]
+    pub tool_retry_attempts: u32,
+    /// Tools returned per `tools/list` page. `0` returns every tool at once.
+    #[serde(default = "default_tools_page_size")]
+    pub tools_page_size: usize,
+    /// Keep the last `invocation_history` calls of each tool, arguments
+    /// included (with known secrets redacted), in `~/.girt/history/`.
+    #[serde(default)]
+    pub record_invocations: bool,
+    /// Calls kept per tool when `record_invocations` is on.
+    #[serde(default = "default_invocation_history")]
+    pub invocation_history: usize,
+    /// Most calls one `girt_batch` request may carry.
+    #[serde(default = "default_batch_max_calls")]
+    pub batch_max_calls: usize,
+    /// Calls of a `parallel` batch run at once.
+    #[serde(default = "default_batch_parallelism")]
+    pub batch_parallelism: usize,
+    /// Add a `resource_usage` field (wall time, peak memory, fuel) to each
+    /// tool call's structured result.
+    #[serde(default)]
+    pub include_resource_usage: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            structured_results: true,
+            result_envelope: false,
+            request_log: false,
+            validate_arguments: true,
+            approval_ttl_secs: default_approval_ttl_secs(),
+            tool_retry_attempts: default_tool_retry_attempts(),
+            tools_page_size: default_tools_page_size(),
+            record_invocations: false,
+            invocation_history: default_invocation_history(),
+            batch_max_calls: default_batch_max_calls(),
+            batch_parallelism: default_batch_parallelism(),
+            include_resource_usage: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_invocation_history() -> usize {
+    20
+}
+
+fn default_approval_ttl_secs() -> u64 {
+    15 * 60
+}
+
+fn default_tool_retry_attempts() -> u32 {
+    2
+}
+
+fn default_tools_page_size() -> usize {
+    50
+}
+
+fn default_batch_max_calls() -> usize {
+    16
+}
+
+fn default_batch_parallelism() -> usize {
+    4
 }
 
 /// Pipeline-level configuration.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct PipelineConfig {
     /// Path to a coding standards file (e.g. ~/.claude/CLAUDE.md).
     /// When set, the contents are injected into the Engineer's system prompt
-    /// so generated code follows your project's conventions.
+    /// so generated code follows your project's conventions. Languages
+    /// listed in `coding_standards` use their own file instead.
     pub coding_standards_path: Option<String>,
+    /// `[pipeline.coding_standards]`: a standards file per target language,
+    /// e.g. `rust = "~/standards/rust.md"`.
+    #[serde(default)]
+    pub coding_standards: HashMap<TargetLanguage, String>,
+    /// Longest standards text, in characters, put in a prompt. Longer files
+    /// are cut at a paragraph break.
+    #[serde(default = "default_coding_standards_max_chars")]
+    pub coding_standards_max_chars: usize,
+    /// Compile tools in the shared `~/.girt/build-cache/` workspace so
+    /// dependencies are built once. Disable to build every tool from scratch.
+    #[serde(default = "default_true")]
+    pub build_cache: bool,
+    /// Upper bounds on the `pipeline` overrides a request may carry.
+    #[serde(default)]
+    pub caps: PipelineCaps,
+    /// Builds of a queued request, including the first, before it is moved
+    /// to `failed/`.
+    #[serde(default = "default_max_request_attempts")]
+    pub max_request_attempts: u32,
+    /// Pipelines run at the same time, by the proxy and by `girt worker`.
+    /// Their compile steps still take turns.
+    #[serde(default = "default_concurrent_builds")]
+    pub concurrent_builds: usize,
+    /// Per-agent sampling settings, over each agent's defaults.
+    #[serde(default)]
+    pub sampling: SamplingConfig,
+    /// Checks run on every Engineer output before QA and the Red Team.
+    #[serde(default)]
+    pub static_checks: StaticChecks,
+    /// The least severe QA and Red Team tickets that keep a build from
+    /// passing, per ticket type.
+    #[serde(default)]
+    pub blocking: BlockingPolicy,
+    /// Keep every fix-loop iteration's `src/lib.rs` as `iter-N.rs` under
+    /// `~/.girt/failures/`, for builds that pass as well as failed ones.
+    #[serde(default)]
+    pub keep_iteration_sources: bool,
+    /// How often `girt worker` re-runs the test vectors of published tools
+    /// to catch upstream drift (see `girt tools validate`). Off when unset.
+    #[serde(default, deserialize_with = "deserialize_opt_duration")]
+    pub revalidate_interval: Option<Duration>,
+    /// Queue a rebuild of each tool whose validation finds drift, with the
+    /// failing test vectors as context for the Architect.
+    #[serde(default)]
+    pub revalidate_rebuild: bool,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            coding_standards_path: None,
+            coding_standards: HashMap::new(),
+            coding_standards_max_chars: default_coding_standards_max_chars(),
+            build_cache: true,
+            caps: PipelineCaps::default(),
+            max_request_attempts: default_max_request_attempts(),
+            concurrent_builds: default_concurrent_builds(),
+            sampling: SamplingConfig::default(),
+            static_checks: StaticChecks::default(),
+            blocking: BlockingPolicy::default(),
+            keep_iteration_sources: false,
+            revalidate_interval: None,
+            revalidate_rebuild: false,
+        }
+    }
+}
+
+fn default_coding_standards_max_chars() -> usize {
+    8_000
+}
+
+fn default_max_request_attempts() -> u32 {
+    queue::DEFAULT_MAX_ATTEMPTS
+}
+
+fn default_concurrent_builds() -> usize {
+    2
+}
+
+/// Read the standards file at `raw` (`~` expanded), cut to `max_chars`.
+fn load_standards_file(raw: &str, max_chars: usize) -> Option<String> {
+    let expanded = match paths::expand_tilde(raw) {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!(path = raw, "Cannot read coding standards: {e}");
+            return None;
+        }
+    };
+    match std::fs::read_to_string(&expanded) {
+        Ok(content) => {
+            tracing::info!(path = %expanded.display(), "Loaded coding standards");
+            let (content, truncated) = fit_standards(&content, max_chars);
+            if truncated {
+                tracing::warn!(
+                    path = %expanded.display(),
+                    max_chars,
+                    "Coding standards are longer than pipeline.coding_standards_max_chars; \
+                     the rest is left out of prompts"
+                );
+            }
+            Some(content)
+        }
+        Err(e) => {
+            tracing::warn!(
+                path = %expanded.display(),
+                error = %e,
+                "Could not load coding standards — continuing without them"
+            );
+            None
+        }
+    }
+}
+
+const STANDARDS_TRUNCATED: &str = "[truncated]";
+
+/// `standards`, or as many of its leading paragraphs as fit in `max_chars`
+/// with a `[truncated]` marker. True when anything was cut.
+fn fit_standards(standards: &str, max_chars: usize) -> (String, bool) {
+    if standards.chars().count() <= max_chars {
+        return (standards.to_string(), false);
+    }
+    let budget = max_chars.saturating_sub(STANDARDS_TRUNCATED.chars().count() + 2);
+    let kept: String = standards.chars().take(budget).collect();
+    // Cut at the last paragraph break, or a line break if there is none.
+    let end = kept
+        .rfind("\n\n")
+        .or_else(|| kept.rfind('\n'))
+        .unwrap_or(kept.len());
+    let kept = kept[..end].trim_end();
+    (format!("{kept}\n\n{STANDARDS_TRUNCATED}"), true)
+}
+
+/// `[pipeline.sampling]`: temperature and top_p per agent, e.g.
+///
+/// ```toml
+/// [pipeline.sampling.red_team]
+/// temperature = 1.0
+/// ```
+///
+/// Values left unset keep the agent's default: temperature 0.4 for the
+/// Architect, 0.2 for the Engineer, 0.3 for QA, 0.8 for the Red Team.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct SamplingConfig {
+    #[serde(default)]
+    pub architect: Sampling,
+    #[serde(default)]
+    pub engineer: Sampling,
+    #[serde(default)]
+    pub qa: Sampling,
+    #[serde(default)]
+    pub red_team: Sampling,
+}
+
+impl SamplingConfig {
+    /// Reject values no provider accepts.
+    pub fn validate(&self) -> Result<(), PipelineError> {
+        let agents = [
+            ("architect", self.architect),
+            ("engineer", self.engineer),
+            ("qa", self.qa),
+            ("red_team", self.red_team),
+        ];
+        for (agent, sampling) in agents {
+            if let Some(t) = sampling.temperature
+                && !(0.0..=2.0).contains(&t)
+            {
+                return Err(PipelineError::ConfigError(format!(
+                    "pipeline.sampling.{agent}.temperature = {t} is outside 0 to 2"
+                )));
+            }
+            if let Some(p) = sampling.top_p
+                && !(0.0..=1.0).contains(&p)
+            {
+                return Err(PipelineError::ConfigError(format!(
+                    "pipeline.sampling.{agent}.top_p = {p} is outside 0 to 1"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `[pipeline.caps]`: the most a request's
+/// [`PipelineOverrides`](crate::types::PipelineOverrides) can ask for, so an
+/// agent cannot grant itself an unlimited budget.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PipelineCaps {
+    #[serde(default = "default_max_iterations_cap")]
+    pub max_iterations: u32,
+    #[serde(default = "default_token_budget_cap")]
+    pub token_budget: u32,
+    /// Target languages a request may choose.
+    #[serde(default = "default_languages_cap")]
+    pub languages: Vec<TargetLanguage>,
+    #[serde(default)]
+    pub max_tier: ResourceTier,
+}
+
+impl Default for PipelineCaps {
+    fn default() -> Self {
+        Self {
+            max_iterations: default_max_iterations_cap(),
+            token_budget: default_token_budget_cap(),
+            languages: default_languages_cap(),
+            max_tier: ResourceTier::default(),
+        }
+    }
+}
+
+fn default_max_iterations_cap() -> u32 {
+    5
+}
+
+fn default_token_budget_cap() -> u32 {
+    200_000
+}
+
+/// Only Rust: the compiler builds with `cargo-component`.
+fn default_languages_cap() -> Vec<TargetLanguage> {
+    vec![TargetLanguage::Rust]
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LlmConfig {
     pub provider: LlmProvider,
-    #[serde(default = "default_base_url")]
-    pub base_url: String,
+    /// The provider's root URL; see [`LlmEndpoint::base_url`] for the
+    /// default.
+    #[serde(default)]
+    pub base_url: Option<String>,
     #[serde(default = "default_model")]
     pub model: String,
     pub api_key: Option<String>,
+    /// Headers sent with every request, e.g. a gateway's own credentials.
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
+    /// Proxy all provider requests go through. Unset, the `HTTPS_PROXY`
+    /// family of environment variables applies.
+    #[serde(default)]
+    pub http_proxy: Option<String>,
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
+    /// Reuse responses to identical Architect calls from
+    /// `~/.girt/llm-cache/` (see [`CachingLlmClient`]).
+    #[serde(default)]
+    pub cache: bool,
+    /// Size the LLM cache may grow to before the oldest entries go.
+    #[serde(default = "default_llm_cache_max_bytes")]
+    pub cache_max_bytes: u64,
+    /// How long a cached response is reused (`"7d"`, `"12h"`).
+    #[serde(
+        default = "default_llm_cache_ttl",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub cache_ttl: Duration,
+    /// Providers to fail over to, in order, when the one before cannot be
+    /// reached (see [`FallbackLlmClient`]).
+    #[serde(default)]
+    pub fallback: Vec<LlmEndpoint>,
 }
 
-fn default_base_url() -> String {
-    "http://localhost:8000/v1".into()
+/// A `[[llm.fallback]]` provider: the `[llm]` connection keys.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LlmEndpoint {
+    pub provider: LlmProvider,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default = "default_model")]
+    pub model: String,
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub http_proxy: Option<String>,
 }
+
+/// Where an `openai-compatible` provider is looked for without a `base_url`.
+const DEFAULT_OPENAI_BASE_URL: &str = "http://localhost:8000/v1";
 fn default_model() -> String {
     "zai-org/GLM-4.7-Flash".into()
 }
 fn default_max_tokens() -> u32 {
     4096
 }
+fn default_llm_cache_max_bytes() -> u64 {
+    llm_cache::DEFAULT_MAX_BYTES
+}
+fn default_llm_cache_ttl() -> Duration {
+    llm_cache::DEFAULT_TTL
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub enum LlmProvider {
@@ -58,16 +807,50 @@ pub enum LlmProvider {
     Stub,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct RegistryConfig {
     #[serde(default = "default_registry_url")]
     pub url: String,
     pub token: Option<String>,
+    /// Git repository that published tools are mirrored into (see `ToolSync`).
+    /// Unset disables syncing.
+    pub source_repo: Option<String>,
+    /// Branch to push synced tools to. Defaults to `main`.
+    pub source_branch: Option<String>,
+    /// Largest file a sync will commit; a larger one aborts the sync. The
+    /// compiled `tool.wasm` is exempt.
+    #[serde(default = "default_sync_max_file_bytes")]
+    pub sync_max_file_bytes: u64,
+    /// Abort a sync when a file holds something credential-shaped
+    /// (`sk-ant-`, `ghp_`, `AKIA`, PEM private keys).
+    #[serde(default = "default_true")]
+    pub sync_secret_scan: bool,
+    /// What to do when a build would replace a tool of the same name with
+    /// a different spec: `error`, `suffix`, or `replace` (the default).
+    #[serde(default)]
+    pub on_name_collision: NameCollision,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            url: default_registry_url(),
+            token: None,
+            source_repo: None,
+            source_branch: None,
+            sync_max_file_bytes: default_sync_max_file_bytes(),
+            sync_secret_scan: true,
+            on_name_collision: NameCollision::default(),
+        }
+    }
 }
 
 fn default_registry_url() -> String {
     "ghcr.io/epiphytic/girt-tools".into()
 }
+fn default_sync_max_file_bytes() -> u64 {
+    tool_sync::DEFAULT_MAX_FILE_BYTES
+}
 
 #[derive(Debug, Default, Deserialize)]
 pub struct BuildConfig {
@@ -75,6 +858,14 @@ pub struct BuildConfig {
     pub default_language: String,
     #[serde(default = "default_tier")]
     pub default_tier: String,
+    /// `girt:tool` world new tools are built against: "0.1.0" (string
+    /// errors) or "0.2.0" (structured `tool-error` records).
+    #[serde(default)]
+    pub wit_version: WitVersion,
+    /// Wrap `cargo-component build` in bwrap or sandbox-exec when one is
+    /// available ("auto"), or never ("off").
+    #[serde(default)]
+    pub sandbox: BuildSandbox,
 }
 
 fn default_language() -> String {
@@ -85,99 +876,665 @@ fn default_tier() -> String {
 }
 
 impl GirtConfig {
-    /// Load and return the coding standards content, if a path is configured.
+    /// Load the configured coding standards: the file for each language in
+    /// `pipeline.coding_standards`, and `coding_standards_path` for the rest.
     ///
-    /// Expands `~` to the home directory. Returns `None` silently if the path
-    /// isn't set or the file doesn't exist (non-fatal — standards are optional).
-    pub fn load_coding_standards(&self) -> Option<String> {
-        let raw = self.pipeline.coding_standards_path.as_deref()?;
-        let expanded = if raw.starts_with('~') {
-            dirs::home_dir()?.join(&raw[2..])
-        } else {
-            std::path::PathBuf::from(raw)
-        };
-        match std::fs::read_to_string(&expanded) {
-            Ok(content) => {
-                tracing::info!(path = %expanded.display(), "Loaded coding standards");
-                Some(content)
-            }
-            Err(e) => {
-                tracing::warn!(
-                    path = %expanded.display(),
-                    error = %e,
-                    "Could not load coding standards — continuing without them"
-                );
-                None
+    /// Expands `~` to the home directory and cuts each file to
+    /// `coding_standards_max_chars`. A file that can't be read is skipped
+    /// with a warning (non-fatal — standards are optional).
+    pub fn load_coding_standards(&self) -> CodingStandards {
+        let max_chars = self.pipeline.coding_standards_max_chars;
+        let load = |raw: &str| load_standards_file(raw, max_chars);
+        let mut standards = CodingStandards::default().with_fallback(
+            self.pipeline
+                .coding_standards_path
+                .as_deref()
+                .and_then(load),
+        );
+        for (language, raw) in &self.pipeline.coding_standards {
+            if let Some(content) = load(raw) {
+                standards = standards.with_language(language.clone(), content);
             }
         }
+        standards
     }
 
-    pub fn from_file(path: &Path) -> Result<Self, PipelineError> {
-        let content = std::fs::read_to_string(path).map_err(PipelineError::IoError)?;
-        toml::from_str(&content).map_err(|e| {
-            PipelineError::LlmError(format!("Failed to parse config: {e}"))
-        })
+    /// Load `base`, with `profile`'s overlay merged over it when given.
+    pub fn load(base: &Path, profile: Option<&Profile>) -> Result<Self, PipelineError> {
+        ConfigLayers::read(base, profile)?.config()
+    }
+
+    fn parse(content: &str) -> Result<Self, PipelineError> {
+        let config: Self = toml::from_str(content).map_err(|e| {
+            PipelineError::ConfigError(format!("Failed to parse config: {e}"))
+        })?;
+        config.validate()
     }
 
+    fn validate(self) -> Result<Self, PipelineError> {
+        self.runtime.validate()?;
+        self.pipeline.sampling.validate()?;
+        Ok(self)
+    }
+
+    /// Settings that differ from `newer` but only take effect on restart.
+    ///
+    /// Policy rules and `[tools.env]` are hot-reloadable and never listed.
+    pub fn restart_required_changes(&self, newer: &GirtConfig) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        let mut check = |key, differs: bool| {
+            if differs {
+                changed.push(key);
+            }
+        };
+        check("llm.provider", self.llm.provider != newer.llm.provider);
+        check("llm.base_url", self.llm.base_url != newer.llm.base_url);
+        check("llm.model", self.llm.model != newer.llm.model);
+        check("llm.api_key", self.llm.api_key != newer.llm.api_key);
+        check("llm.max_tokens", self.llm.max_tokens != newer.llm.max_tokens);
+        check(
+            "llm.extra_headers",
+            self.llm.extra_headers != newer.llm.extra_headers,
+        );
+        check(
+            "llm.http_proxy",
+            self.llm.http_proxy != newer.llm.http_proxy,
+        );
+        check("llm.fallback", self.llm.fallback != newer.llm.fallback);
+        check(
+            "llm.cache",
+            self.llm.cache != newer.llm.cache
+                || self.llm.cache_max_bytes != newer.llm.cache_max_bytes
+                || self.llm.cache_ttl != newer.llm.cache_ttl,
+        );
+        check("registry.url", self.registry.url != newer.registry.url);
+        check(
+            "registry.source_repo",
+            self.registry.source_repo != newer.registry.source_repo,
+        );
+        check(
+            "registry.sync",
+            self.registry.sync_max_file_bytes != newer.registry.sync_max_file_bytes
+                || self.registry.sync_secret_scan != newer.registry.sync_secret_scan,
+        );
+        check(
+            "registry.on_name_collision",
+            self.registry.on_name_collision != newer.registry.on_name_collision,
+        );
+        check(
+            "pipeline.coding_standards",
+            self.pipeline.coding_standards_path != newer.pipeline.coding_standards_path
+                || self.pipeline.coding_standards != newer.pipeline.coding_standards
+                || self.pipeline.coding_standards_max_chars
+                    != newer.pipeline.coding_standards_max_chars,
+        );
+        check(
+            "pipeline.build_cache",
+            self.pipeline.build_cache != newer.pipeline.build_cache,
+        );
+        check("pipeline.caps", self.pipeline.caps != newer.pipeline.caps);
+        check(
+            "pipeline.max_request_attempts",
+            self.pipeline.max_request_attempts != newer.pipeline.max_request_attempts,
+        );
+        check(
+            "pipeline.concurrent_builds",
+            self.pipeline.concurrent_builds != newer.pipeline.concurrent_builds,
+        );
+        check(
+            "pipeline.sampling",
+            self.pipeline.sampling != newer.pipeline.sampling,
+        );
+        check(
+            "pipeline.static_checks",
+            self.pipeline.static_checks != newer.pipeline.static_checks,
+        );
+        check(
+            "pipeline.blocking",
+            self.pipeline.blocking != newer.pipeline.blocking,
+        );
+        check(
+            "pipeline.keep_iteration_sources",
+            self.pipeline.keep_iteration_sources != newer.pipeline.keep_iteration_sources,
+        );
+        check(
+            "pipeline.revalidate_interval",
+            self.pipeline.revalidate_interval != newer.pipeline.revalidate_interval,
+        );
+        check(
+            "pipeline.revalidate_rebuild",
+            self.pipeline.revalidate_rebuild != newer.pipeline.revalidate_rebuild,
+        );
+        check(
+            "build.wit_version",
+            self.build.wit_version != newer.build.wit_version,
+        );
+        check("build.sandbox", self.build.sandbox != newer.build.sandbox);
+        check(
+            "runtime.egress_mode",
+            self.runtime.egress_mode != newer.runtime.egress_mode,
+        );
+        check(
+            "runtime.engine",
+            self.runtime.engine_differs(&newer.runtime),
+        );
+        check(
+            "runtime.fault_injection",
+            self.runtime.fault_injection != newer.runtime.fault_injection,
+        );
+        check(
+            "runtime.max_response_bytes",
+            self.runtime.max_response_bytes != newer.runtime.max_response_bytes,
+        );
+        check(
+            "runtime.oversize_response",
+            self.runtime.oversize_response != newer.runtime.oversize_response,
+        );
+        check(
+            "server.request_log",
+            self.server.request_log != newer.server.request_log,
+        );
+        check(
+            "server.validate_arguments",
+            self.server.validate_arguments != newer.server.validate_arguments,
+        );
+        check(
+            "server.structured_results",
+            self.server.structured_results != newer.server.structured_results,
+        );
+        check(
+            "server.result_envelope",
+            self.server.result_envelope != newer.server.result_envelope,
+        );
+        check(
+            "server.approval_ttl_secs",
+            self.server.approval_ttl_secs != newer.server.approval_ttl_secs,
+        );
+        check(
+            "server.tool_retry_attempts",
+            self.server.tool_retry_attempts != newer.server.tool_retry_attempts,
+        );
+        check(
+            "server.tools_page_size",
+            self.server.tools_page_size != newer.server.tools_page_size,
+        );
+        check(
+            "server.record_invocations",
+            self.server.record_invocations != newer.server.record_invocations,
+        );
+        check(
+            "server.invocation_history",
+            self.server.invocation_history != newer.server.invocation_history,
+        );
+        check(
+            "server.batch_max_calls",
+            self.server.batch_max_calls != newer.server.batch_max_calls,
+        );
+        check(
+            "server.batch_parallelism",
+            self.server.batch_parallelism != newer.server.batch_parallelism,
+        );
+        check(
+            "server.include_resource_usage",
+            self.server.include_resource_usage != newer.server.include_resource_usage,
+        );
+        check(
+            "security.include_decision_trace",
+            self.security.include_decision_trace != newer.security.include_decision_trace,
+        );
+        check(
+            "security.max_spec_bytes",
+            self.security.max_spec_bytes != newer.security.max_spec_bytes,
+        );
+        check(
+            "security.max_description_chars",
+            self.security.max_description_chars != newer.security.max_description_chars,
+        );
+        check(
+            "security.audit_log",
+            self.security.audit_log != newer.security.audit_log,
+        );
+        check(
+            "security.warm_cache_from_audit",
+            self.security.warm_cache_from_audit != newer.security.warm_cache_from_audit,
+        );
+        check(
+            "security.max_argument_bytes",
+            self.security.max_argument_bytes != newer.security.max_argument_bytes,
+        );
+        check(
+            "security.min_allow_confidence",
+            self.security.min_allow_confidence != newer.security.min_allow_confidence,
+        );
+        check(
+            "security.execution_allow_ttl",
+            self.security.execution_allow_ttl != newer.security.execution_allow_ttl,
+        );
+        check(
+            "security.budget",
+            self.security.budget != newer.security.budget,
+        );
+        check(
+            "security.always_ask",
+            self.security.always_ask != newer.security.always_ask,
+        );
+        check(
+            "security.recheck_on_restore",
+            self.security.recheck_on_restore != newer.security.recheck_on_restore,
+        );
+        check(
+            "security.agent_approvals",
+            self.security.agent_approvals != newer.security.agent_approvals,
+        );
+        check(
+            "cli_check.utilities",
+            self.cli_check.utilities != newer.cli_check.utilities,
+        );
+        check(
+            "tools.deprecation_grace",
+            self.tools.deprecation_grace != newer.tools.deprecation_grace,
+        );
+        check("telemetry", self.telemetry != newer.telemetry);
+        changed
+    }
+
+    /// The configured provider's client, failing over to any
+    /// `[[llm.fallback]]` providers, behind the LLM cache when `[llm] cache`
+    /// is on.
     pub fn build_llm_client(&self) -> Result<Arc<dyn LlmClient>, PipelineError> {
-        match self.llm.provider {
+        let primary = LlmEndpoint {
+            provider: self.llm.provider.clone(),
+            base_url: self.llm.base_url.clone(),
+            model: self.llm.model.clone(),
+            api_key: self.llm.api_key.clone(),
+            extra_headers: self.llm.extra_headers.clone(),
+            http_proxy: self.llm.http_proxy.clone(),
+        };
+        let mut client = primary.build_client()?;
+        if !self.llm.fallback.is_empty() {
+            let fallbacks = self
+                .llm
+                .fallback
+                .iter()
+                .map(LlmEndpoint::build_client)
+                .collect::<Result<_, _>>()?;
+            client = Arc::new(FallbackLlmClient::new(client, fallbacks));
+        }
+        if !self.llm.cache {
+            return Ok(client);
+        }
+        let dir = CachingLlmClient::default_dir()
+            .map_err(|e| PipelineError::ConfigError(format!("llm.cache: {e}")))?;
+        Ok(Arc::new(
+            CachingLlmClient::new(client, dir)
+                .with_max_bytes(self.llm.cache_max_bytes)
+                .with_ttl(self.llm.cache_ttl),
+        ))
+    }
+}
+
+impl LlmEndpoint {
+    /// The configured `base_url`, or the provider's default:
+    /// `https://api.anthropic.com` for `anthropic`, a local server on port
+    /// 8000 for `openai-compatible`.
+    pub fn base_url(&self) -> &str {
+        match (&self.base_url, &self.provider) {
+            (Some(url), _) => url,
+            (None, LlmProvider::Anthropic) => ANTHROPIC_BASE_URL,
+            (None, _) => DEFAULT_OPENAI_BASE_URL,
+        }
+    }
+
+    fn build_client(&self) -> Result<Arc<dyn LlmClient>, PipelineError> {
+        match self.provider {
             LlmProvider::Anthropic => {
                 // from_env_or checks: ANTHROPIC_API_KEY → openclaw auth-profiles → api_key in toml
-                let client = AnthropicLlmClient::from_env_or(
-                    self.llm.model.clone(),
-                    self.llm.api_key.clone(),
+                let mut client = AnthropicLlmClient::from_env_or(
+                    self.model.clone(),
+                    self.api_key.clone(),
+                    self.base_url.clone(),
+                    &self.extra_headers,
                 )?;
+                if let Some(proxy) = &self.http_proxy {
+                    client = client.with_http_proxy(proxy)?;
+                }
                 Ok(Arc::new(client))
             }
             LlmProvider::OpenAiCompatible => {
                 let api_key = std::env::var("GIRT_LLM_API_KEY")
                     .ok()
-                    .or_else(|| self.llm.api_key.clone());
-                Ok(Arc::new(OpenAiCompatibleClient::new(
-                    self.llm.base_url.clone(),
-                    self.llm.model.clone(),
+                    .or_else(|| self.api_key.clone());
+                let mut client = OpenAiCompatibleClient::new(
+                    self.base_url().to_string(),
+                    self.model.clone(),
                     api_key,
-                )))
+                )
+                .with_extra_headers(&self.extra_headers)?;
+                if let Some(proxy) = &self.http_proxy {
+                    client = client.with_http_proxy(proxy)?;
+                }
+                Ok(Arc::new(client))
             }
             LlmProvider::Stub => Ok(Arc::new(StubLlmClient::constant("stub response"))),
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A named overlay, `girt.<name>.toml`, merged over the base girt.toml
+/// (`--profile <name>` or `GIRT_PROFILE`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    name: String,
+    optional: bool,
+}
 
-    #[test]
-    fn parses_minimal_config() {
-        let toml_str = r#"
-[llm]
-provider = "openai-compatible"
-base_url = "http://localhost:8000/v1"
-model = "zai-org/GLM-4.7-Flash"
-"#;
-        let config: GirtConfig = toml::from_str(toml_str).unwrap();
-        assert_eq!(config.llm.provider, LlmProvider::OpenAiCompatible);
-        assert_eq!(config.llm.base_url, "http://localhost:8000/v1");
-        assert_eq!(config.llm.model, "zai-org/GLM-4.7-Flash");
-        assert_eq!(config.llm.max_tokens, 4096);
+impl Profile {
+    /// Names are limited to letters, digits, `-` and `_` so the overlay
+    /// always sits next to the base file.
+    pub fn new(name: impl Into<String>) -> Result<Self, PipelineError> {
+        let name = name.into();
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if name.is_empty() || !valid {
+            return Err(PipelineError::ConfigError(format!(
+                "Invalid profile name '{name}': use letters, digits, '-' and '_'"
+            )));
+        }
+        Ok(Self {
+            name,
+            optional: false,
+        })
     }
 
-    #[test]
-    fn parses_stub_provider() {
-        let toml_str = r#"
-[llm]
-provider = "stub"
-"#;
-        let config: GirtConfig = toml::from_str(toml_str).unwrap();
-        assert_eq!(config.llm.provider, LlmProvider::Stub);
+    /// Fall back to the base config alone when the overlay does not exist,
+    /// instead of failing.
+    pub fn with_optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
     }
 
-    #[test]
-    fn parses_anthropic_provider() {
-        let toml_str = r#"
-[llm]
-provider = "anthropic"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The overlay for `base`: `girt.toml` becomes `girt.<name>.toml`.
+    pub fn overlay_path(&self, base: &Path) -> PathBuf {
+        let stem = base
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        base.with_file_name(format!("{stem}.{}.toml", self.name))
+    }
+}
+
+/// The files a config is built from, base first.
+///
+/// Later files are merged over earlier ones: tables merge key by key, any
+/// other value (including arrays such as `[[policy.deny]]`) replaces.
+#[derive(Debug)]
+pub struct ConfigLayers {
+    layers: Vec<ConfigLayer>,
+}
+
+#[derive(Debug)]
+struct ConfigLayer {
+    path: PathBuf,
+    contents: String,
+    table: toml::Table,
+}
+
+/// One setting of the merged config and the file that set it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveValue {
+    /// Dotted path, e.g. `llm.model`.
+    pub key: String,
+    /// The value as TOML.
+    pub value: String,
+    pub source: PathBuf,
+}
+
+impl ConfigLayers {
+    /// Read `base` and, when given, `profile`'s overlay.
+    ///
+    /// A missing overlay is an error unless the profile is optional.
+    pub fn read(base: &Path, profile: Option<&Profile>) -> Result<Self, PipelineError> {
+        Self::parse(read_layer_files(base, profile)?)
+    }
+
+    fn parse(files: Vec<(PathBuf, String)>) -> Result<Self, PipelineError> {
+        let layers = files
+            .into_iter()
+            .map(|(path, contents)| {
+                let table = contents.parse::<toml::Table>().map_err(|e| {
+                    PipelineError::ConfigError(format!("Failed to parse {}: {e}", path.display()))
+                })?;
+                Ok(ConfigLayer {
+                    path,
+                    contents,
+                    table,
+                })
+            })
+            .collect::<Result<_, PipelineError>>()?;
+        Ok(Self { layers })
+    }
+
+    /// The files that were read, base first.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.layers.iter().map(|layer| layer.path.as_path())
+    }
+
+    /// All layers merged into one table.
+    pub fn merged(&self) -> toml::Table {
+        let mut merged = toml::Table::new();
+        for layer in &self.layers {
+            merge_tables(&mut merged, &layer.table);
+        }
+        merged
+    }
+
+    /// The merged, validated config.
+    pub fn config(&self) -> Result<GirtConfig, PipelineError> {
+        if let [base] = self.layers.as_slice() {
+            // Parsing the text keeps line numbers in error messages.
+            return GirtConfig::parse(&base.contents);
+        }
+        let config: GirtConfig = toml::Value::Table(self.merged()).try_into().map_err(|e| {
+            PipelineError::ConfigError(format!("Failed to parse merged config: {e}"))
+        })?;
+        config.validate()
+    }
+
+    /// Every setting in the merged config, in key order, with the last file
+    /// that set it. Defaults that no file sets are not listed.
+    pub fn effective(&self) -> Vec<EffectiveValue> {
+        let mut leaves = Vec::new();
+        flatten(&mut Vec::new(), &self.merged(), &mut leaves);
+        leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let source = self
+                    .layers
+                    .iter()
+                    .rev()
+                    .find(|layer| lookup(&layer.table, &key).is_some())
+                    .map(|layer| layer.path.clone())
+                    .unwrap_or_default();
+                EffectiveValue {
+                    key: dotted_key(&key),
+                    value: value.to_string(),
+                    source,
+                }
+            })
+            .collect()
+    }
+}
+
+fn read_layer_files(
+    base: &Path,
+    profile: Option<&Profile>,
+) -> Result<Vec<(PathBuf, String)>, PipelineError> {
+    let mut files = vec![(base.to_path_buf(), std::fs::read_to_string(base)?)];
+    if let Some(profile) = profile {
+        let overlay = profile.overlay_path(base);
+        match std::fs::read_to_string(&overlay) {
+            Ok(contents) => files.push((overlay, contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && profile.optional => {
+                tracing::debug!(
+                    profile = %profile.name,
+                    overlay = %overlay.display(),
+                    "No overlay for optional profile; using the base config"
+                );
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(PipelineError::ConfigError(format!(
+                    "Profile '{}' has no overlay: {} does not exist",
+                    profile.name,
+                    overlay.display()
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(files)
+}
+
+fn merge_tables(base: &mut toml::Table, overlay: &toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay)
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+type KeyPath = Vec<String>;
+
+fn flatten(path: &mut KeyPath, table: &toml::Table, out: &mut Vec<(KeyPath, toml::Value)>) {
+    for (key, value) in table {
+        path.push(key.clone());
+        match value {
+            toml::Value::Table(table) => flatten(path, table, out),
+            value => out.push((path.clone(), value.clone())),
+        }
+        path.pop();
+    }
+}
+
+fn lookup<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    match (table.get(first)?, rest) {
+        (value, []) => Some(value),
+        (toml::Value::Table(table), rest) => lookup(table, rest),
+        _ => None,
+    }
+}
+
+/// `["tools", "env", "my.tool"]` becomes `tools.env."my.tool"`.
+fn dotted_key(path: &[String]) -> String {
+    let bare = |key: &str| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    path.iter()
+        .map(|key| {
+            if bare(key) {
+                key.clone()
+            } else {
+                toml::Value::String(key.clone()).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Polls girt.toml for edits so hot-reloadable settings can be applied
+/// without restarting the proxy (`girt serve --watch-config`).
+///
+/// Change detection compares file contents rather than mtimes, so rapid
+/// successive saves are never missed. With a profile, edits to either the
+/// base file or the overlay are picked up.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    profile: Option<Profile>,
+    last_contents: Vec<(PathBuf, String)>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: impl Into<PathBuf>, profile: Option<Profile>) -> Result<Self, PipelineError> {
+        let path = path.into();
+        let last_contents = read_layer_files(&path, profile.as_ref())?;
+        Ok(Self {
+            path,
+            profile,
+            last_contents,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check the file once.
+    ///
+    /// Returns `Ok(None)` when unchanged and `Ok(Some(config))` when it
+    /// changed and parsed. A changed file that fails to parse returns an
+    /// error once; the caller should keep its current config.
+    pub fn poll(&mut self) -> Result<Option<GirtConfig>, PipelineError> {
+        let contents = read_layer_files(&self.path, self.profile.as_ref())?;
+        if contents == self.last_contents {
+            return Ok(None);
+        }
+        self.last_contents = contents.clone();
+        ConfigLayers::parse(contents)?.config().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+    use girt_core::spec::RequestSource;
+
+    #[test]
+    fn parses_minimal_config() {
+        let toml_str = r#"
+[llm]
+provider = "openai-compatible"
+base_url = "http://localhost:8000/v1"
+model = "zai-org/GLM-4.7-Flash"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.llm.provider, LlmProvider::OpenAiCompatible);
+        assert_eq!(
+            config.llm.base_url.as_deref(),
+            Some("http://localhost:8000/v1")
+        );
+        assert_eq!(config.llm.model, "zai-org/GLM-4.7-Flash");
+        assert_eq!(config.llm.max_tokens, 4096);
+    }
+
+    #[test]
+    fn parses_stub_provider() {
+        let toml_str = r#"
+[llm]
+provider = "stub"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.llm.provider, LlmProvider::Stub);
+    }
+
+    #[test]
+    fn parses_anthropic_provider() {
+        let toml_str = r#"
+[llm]
+provider = "anthropic"
 model = "claude-sonnet-4-5"
 api_key = "sk-ant-test"
 "#;
@@ -195,6 +1552,84 @@ provider = "stub"
         assert!(config.build_llm_client().is_ok());
     }
 
+    #[test]
+    fn llm_fallback_providers_parse_and_build_a_chain() {
+        let toml_str = r#"[llm]
+provider = "anthropic"
+model = "claude-sonnet-4-5"
+api_key = "sk-ant-test-key"
+
+[[llm.fallback]]
+provider = "openai-compatible"
+base_url = "http://localhost:11434/v1"
+model = "llama3"
+
+[[llm.fallback]]
+provider = "stub"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.llm.fallback.len(), 2);
+        assert_eq!(config.llm.fallback[0].model, "llama3");
+        assert_eq!(config.llm.fallback[1].provider, LlmProvider::Stub);
+        assert_eq!(config.llm.fallback[1].base_url, None);
+        assert_eq!(config.llm.fallback[1].base_url(), DEFAULT_OPENAI_BASE_URL);
+
+        let client = config.build_llm_client().unwrap();
+        assert_eq!(client.provider(), "anthropic");
+        assert_eq!(client.model(), "claude-sonnet-4-5");
+
+        let mut reordered: GirtConfig = toml::from_str(toml_str).unwrap();
+        reordered.llm.fallback.reverse();
+        assert_eq!(
+            config.restart_required_changes(&reordered),
+            ["llm.fallback"]
+        );
+    }
+
+    #[test]
+    fn anthropic_gateway_settings_parse_and_build() {
+        let toml_str = r#"[llm]
+provider = "anthropic"
+model = "claude-sonnet-4-5"
+api_key = "sk-ant-test-key"
+base_url = "https://llm-gateway.internal/anthropic/"
+http_proxy = "http://proxy.internal:3128"
+
+[llm.extra_headers]
+X-Gateway-Token = "gw-secret"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.llm.extra_headers["X-Gateway-Token"],
+            "gw-secret".to_string()
+        );
+        assert!(config.build_llm_client().is_ok());
+
+        let mut direct: GirtConfig = toml::from_str(toml_str).unwrap();
+        direct.llm.http_proxy = None;
+        direct.llm.extra_headers.clear();
+        assert_eq!(
+            config.restart_required_changes(&direct),
+            ["llm.extra_headers", "llm.http_proxy"]
+        );
+
+        let unset = LlmEndpoint {
+            provider: LlmProvider::Anthropic,
+            base_url: None,
+            model: "m".into(),
+            api_key: None,
+            extra_headers: BTreeMap::new(),
+            http_proxy: None,
+        };
+        assert_eq!(unset.base_url(), "https://api.anthropic.com");
+
+        direct.llm.http_proxy = Some("not a url".into());
+        assert_eq!(
+            direct.build_llm_client().err().unwrap().code(),
+            "config_error"
+        );
+    }
+
     #[test]
     fn build_llm_client_anthropic_with_inline_key_succeeds() {
         // An api_key in girt.toml is the last-resort fallback.
@@ -244,7 +1679,790 @@ default_tier = "standard"
         let config: GirtConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.llm.api_key, Some("sk-test".into()));
         assert_eq!(config.llm.max_tokens, 8192);
+        assert!(!config.llm.cache);
         assert_eq!(config.registry.url, "ghcr.io/epiphytic/girt-tools");
+        assert!(config.registry.source_repo.is_none());
+        assert!(config.server.structured_results);
+        assert_eq!(config.server.approval_ttl_secs, 900);
+        assert_eq!(config.server.tool_retry_attempts, 2);
+        assert_eq!(config.server.tools_page_size, 50);
+        assert!(!config.server.record_invocations);
+        assert_eq!(config.server.invocation_history, 20);
+        assert_eq!(config.server.batch_max_calls, 16);
+        assert_eq!(config.server.batch_parallelism, 4);
+        assert!(!config.server.include_resource_usage);
+        assert!(config.pipeline.build_cache);
+        assert_eq!(config.pipeline.max_request_attempts, 2);
+        assert_eq!(config.pipeline.concurrent_builds, 2);
+        assert!(!config.pipeline.keep_iteration_sources);
+        assert_eq!(config.pipeline.revalidate_interval, None);
+        assert!(!config.pipeline.revalidate_rebuild);
         assert_eq!(config.build.default_language, "rust");
+        assert_eq!(config.build.wit_version, WitVersion::V0_1);
+        assert_eq!(config.build.sandbox, BuildSandbox::Auto);
+    }
+
+    #[test]
+    fn llm_cache_settings_parse() {
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\ncache = true\ncache_max_bytes = 1024\ncache_ttl = \"12h\"\n",
+        )
+        .unwrap();
+        assert!(config.llm.cache);
+        assert_eq!(config.llm.cache_max_bytes, 1024);
+        assert_eq!(config.llm.cache_ttl, Duration::from_secs(12 * 60 * 60));
+        assert!(config.build_llm_client().is_ok());
+    }
+
+    #[test]
+    fn wit_version_is_configurable() {
+        let config: GirtConfig =
+            toml::from_str("[llm]\nprovider = \"stub\"\n\n[build]\nwit_version = \"0.2.0\"\n")
+                .unwrap();
+        assert_eq!(config.build.wit_version, WitVersion::V0_2);
+        let unknown = "[llm]\nprovider = \"stub\"\n\n[build]\nwit_version = \"0.3.0\"\n";
+        assert!(toml::from_str::<GirtConfig>(unknown).is_err());
+    }
+
+    #[test]
+    fn build_sandbox_can_be_turned_off() {
+        let config: GirtConfig =
+            toml::from_str("[llm]\nprovider = \"stub\"\n\n[build]\nsandbox = \"off\"\n").unwrap();
+        assert_eq!(config.build.sandbox, BuildSandbox::Off);
+    }
+
+    #[test]
+    fn egress_mode_defaults_to_direct() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.runtime.egress_mode, EgressMode::Direct);
+
+        let proxied: GirtConfig =
+            toml::from_str("[llm]\nprovider = \"stub\"\n\n[runtime]\negress_mode = \"proxy\"\n")
+                .unwrap();
+        assert_eq!(proxied.runtime.egress_mode, EgressMode::Proxy);
+        assert_eq!(
+            config.restart_required_changes(&proxied),
+            vec!["runtime.egress_mode"]
+        );
+    }
+
+    #[test]
+    fn runtime_engine_settings_are_parsed() {
+        let config = GirtConfig::parse("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert!(!config.runtime.pooling_allocator);
+        assert_eq!(config.runtime.max_memory_mb, 512);
+        assert_eq!(config.runtime.epoch_tick_ms, 0);
+        assert!(config.runtime.native_unwind_info);
+        assert!(!config.runtime.fuel_metering);
+
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[runtime]
+pooling_allocator = true
+max_memory_mb = 256
+max_instances = 8
+epoch_tick_ms = 10
+native_unwind_info = false
+fuel_metering = true
+"#;
+        let tuned = GirtConfig::parse(toml_str).unwrap();
+        assert!(tuned.runtime.pooling_allocator);
+        assert_eq!(tuned.runtime.max_memory_mb, 256);
+        assert_eq!(tuned.runtime.max_instances, 8);
+        assert_eq!(tuned.runtime.epoch_tick_ms, 10);
+        assert!(!tuned.runtime.native_unwind_info);
+        assert!(tuned.runtime.fuel_metering);
+        assert_eq!(
+            config.restart_required_changes(&tuned),
+            vec!["runtime.engine"]
+        );
+    }
+
+    #[test]
+    fn response_limits_default_to_truncating_at_the_largest_tier() {
+        let config = GirtConfig::parse("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.runtime.max_response_bytes, 20_971_520);
+        assert_eq!(config.runtime.oversize_response, OversizeResponse::Truncate);
+
+        let strict = GirtConfig::parse(
+            "[llm]\nprovider = \"stub\"\n\n[runtime]\nmax_response_bytes = 65536\n\
+             oversize_response = \"error\"\n",
+        )
+        .unwrap();
+        assert_eq!(strict.runtime.max_response_bytes, 65536);
+        assert_eq!(strict.runtime.oversize_response, OversizeResponse::Error);
+        assert_eq!(
+            config.restart_required_changes(&strict),
+            vec!["runtime.max_response_bytes", "runtime.oversize_response"]
+        );
+    }
+
+    #[test]
+    fn runtime_limits_below_the_default_tier_are_rejected() {
+        let err =
+            GirtConfig::parse("[llm]\nprovider = \"stub\"\n\n[runtime]\nmax_memory_mb = 64\n")
+                .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("max_memory_mb = 64 is below the Standard tier's memory_mb of 128"),
+            "{err}"
+        );
+
+        let err = GirtConfig::parse(
+            "[llm]\nprovider = \"stub\"\n\n[runtime]\npooling_allocator = true\nmax_instances = 0\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("max_instances"), "{err}");
+    }
+
+    #[test]
+    fn fault_injection_is_off_unless_acknowledged() {
+        let config = GirtConfig::parse("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.runtime.fault_injection.rate, 0.0);
+        assert!(config.runtime.fault_injection.injector().unwrap().is_none());
+
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[runtime.fault_injection]
+rate = 0.2
+kinds = ["timeout", "garbage"]
+seed = 42
+"#;
+        let err = GirtConfig::parse(toml_str).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("i_understand_this_breaks_tools is not set"),
+            "{err}"
+        );
+
+        let acknowledged = format!("{toml_str}i_understand_this_breaks_tools = true\n");
+        let faulty = GirtConfig::parse(&acknowledged).unwrap();
+        let injector = faulty.runtime.fault_injection.injector().unwrap().unwrap();
+        assert_eq!(
+            injector.describe()["kinds"],
+            serde_json::json!(["timeout", "garbage"])
+        );
+        assert_eq!(
+            config.restart_required_changes(&faulty),
+            vec!["runtime.fault_injection"]
+        );
+    }
+
+    #[test]
+    fn structured_results_can_be_disabled() {
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[server]
+structured_results = false
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert!(!config.server.structured_results);
+        assert!(!config.server.request_log);
+        assert!(config.server.validate_arguments);
+    }
+
+    #[test]
+    fn result_envelope_defaults_off_and_needs_a_restart() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert!(!config.server.result_envelope);
+
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[server]
+result_envelope = true
+"#;
+        let enveloped: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert!(enveloped.server.result_envelope);
+        assert_eq!(
+            config.restart_required_changes(&enveloped),
+            vec!["server.result_envelope"]
+        );
+    }
+
+    #[test]
+    fn decision_trace_defaults_off() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert!(!config.security.include_decision_trace);
+
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[security]
+include_decision_trace = true
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.security.include_decision_trace);
+    }
+
+    #[test]
+    fn min_allow_confidence_defaults_and_parses() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(
+            config.security.min_allow_confidence,
+            DEFAULT_MIN_ALLOW_CONFIDENCE
+        );
+
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[security]\nmin_allow_confidence = 0.9\n",
+        )
+        .unwrap();
+        assert_eq!(config.security.min_allow_confidence, 0.9);
+    }
+
+    #[test]
+    fn revalidate_settings_parse() {
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[pipeline]\nrevalidate_interval = \"7d\"\nrevalidate_rebuild = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.pipeline.revalidate_interval,
+            Some(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert!(config.pipeline.revalidate_rebuild);
+    }
+
+    #[test]
+    fn pipeline_caps_default_and_parse() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.pipeline.caps, PipelineCaps::default());
+        assert_eq!(config.pipeline.caps.languages, [TargetLanguage::Rust]);
+
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[pipeline.caps]
+max_iterations = 8
+languages = ["rust", "go"]
+max_tier = "extended"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        let caps = &config.pipeline.caps;
+        assert_eq!(caps.max_iterations, 8);
+        assert_eq!(caps.token_budget, 200_000);
+        assert_eq!(caps.languages, [TargetLanguage::Rust, TargetLanguage::Go]);
+        assert_eq!(caps.max_tier, ResourceTier::Extended);
+    }
+
+    #[test]
+    fn sampling_parses_per_agent_and_rejects_out_of_range_values() {
+        let config = GirtConfig::parse(
+            r#"
+[llm]
+provider = "stub"
+
+[pipeline.sampling.red_team]
+temperature = 1.0
+top_p = 0.95
+"#,
+        )
+        .unwrap();
+        let sampling = config.pipeline.sampling;
+        assert_eq!(sampling.red_team.temperature, Some(1.0));
+        assert_eq!(sampling.red_team.top_p, Some(0.95));
+        assert_eq!(sampling.engineer, Sampling::default());
+
+        let err = GirtConfig::parse(
+            "[llm]\nprovider = \"stub\"\n\n[pipeline.sampling.qa]\ntop_p = 1.5\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("pipeline.sampling.qa.top_p"));
+    }
+
+    #[test]
+    fn static_checks_default_on_and_can_be_turned_off() {
+        let config = GirtConfig::parse("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.pipeline.static_checks, StaticChecks::default());
+
+        let config = GirtConfig::parse(
+            r#"
+[llm]
+provider = "stub"
+
+[pipeline.static_checks]
+no_threads = false
+"#,
+        )
+        .unwrap();
+        let checks = config.pipeline.static_checks;
+        assert!(!checks.no_threads);
+        assert!(checks.export_macro && checks.no_secret_printing);
+    }
+
+    #[test]
+    fn blocking_thresholds_are_per_ticket_type() {
+        let config = GirtConfig::parse("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.pipeline.blocking.functional_defect, Severity::High);
+        assert_eq!(
+            config.pipeline.blocking.security_vulnerability,
+            Severity::High
+        );
+
+        let config = GirtConfig::parse(
+            r#"
+[llm]
+provider = "stub"
+
+[pipeline.blocking]
+security_vulnerability = "medium"
+"#,
+        )
+        .unwrap();
+        let blocking = config.pipeline.blocking;
+        assert_eq!(blocking.functional_defect, Severity::High);
+        assert_eq!(blocking.security_vulnerability, Severity::Medium);
+
+        let err = GirtConfig::parse(
+            "[llm]\nprovider = \"stub\"\n\n[pipeline.blocking]\nfunctional_defect = \"urgent\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variant `urgent`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn coding_standards_are_chosen_by_language() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let general = tmp.path().join("CLAUDE.md");
+        let go = tmp.path().join("go.md");
+        std::fs::write(&general, "Keep functions short.").unwrap();
+        std::fs::write(&go, "Return errors, never panic.").unwrap();
+        let toml_str = format!(
+            "[llm]\nprovider = \"stub\"\n\n[pipeline]\ncoding_standards_path = {:?}\n\n\
+             [pipeline.coding_standards]\ngo = {:?}\nassembly_script = \"/nonexistent.md\"\n",
+            general.display().to_string(),
+            go.display().to_string(),
+        );
+        let config: GirtConfig = toml::from_str(&toml_str).unwrap();
+
+        let standards = config.load_coding_standards();
+        assert_eq!(
+            standards.for_language(&TargetLanguage::Go),
+            Some("Return errors, never panic.")
+        );
+        // Languages without a readable file of their own use the fallback.
+        for language in [TargetLanguage::Rust, TargetLanguage::AssemblyScript] {
+            assert_eq!(
+                standards.for_language(&language),
+                Some("Keep functions short.")
+            );
+        }
+
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.pipeline.coding_standards_max_chars, 8_000);
+        assert_eq!(
+            config
+                .load_coding_standards()
+                .for_language(&TargetLanguage::Rust),
+            None
+        );
+    }
+
+    #[test]
+    fn long_coding_standards_are_cut_at_a_paragraph() {
+        let standards = "# Style\n\nUse snake_case.\n\nDocument every public item.";
+        assert_eq!(
+            fit_standards(standards, 100),
+            (standards.to_string(), false)
+        );
+
+        let (cut, truncated) = fit_standards(standards, 40);
+        assert!(truncated);
+        assert_eq!(cut, "# Style\n\nUse snake_case.\n\n[truncated]");
+        assert!(cut.chars().count() <= 40);
+    }
+
+    #[test]
+    fn parses_per_tool_env() {
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[tools.env.gitlab_issues]
+GITLAB_BASE_URL = "https://gitlab.example.com"
+GITLAB_PROJECT = "infra/ops"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        let env = &config.tools.env["gitlab_issues"];
+        assert_eq!(env["GITLAB_BASE_URL"], "https://gitlab.example.com");
+        assert_eq!(env["GITLAB_PROJECT"], "infra/ops");
+        assert_eq!(config.tools.env.len(), 1);
+    }
+
+    #[test]
+    fn parses_registry_source_repo() {
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[registry]
+source_repo = "git@github.com:epiphytic/girt-tools-src.git"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.registry.source_repo.as_deref(),
+            Some("git@github.com:epiphytic/girt-tools-src.git")
+        );
+        assert!(config.registry.source_branch.is_none());
+        assert_eq!(config.registry.sync_max_file_bytes, 512 * 1024);
+        assert!(config.registry.sync_secret_scan);
+        assert_eq!(config.registry.on_name_collision, NameCollision::Replace);
+
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[registry]\non_name_collision = \"suffix\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.registry.on_name_collision, NameCollision::Suffix);
+    }
+
+    #[test]
+    fn parses_policy_patterns() {
+        let toml_str = r#"
+[llm]
+provider = "stub"
+
+[[policy.deny]]
+description = "No crypto miners"
+name_pattern = "(?i)miner"
+
+[[policy.allow]]
+description = "Date helpers"
+name_pattern = "^date_"
+"#;
+        let config: GirtConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.policy.deny.len(), 1);
+        assert_eq!(config.policy.deny[0].name_pattern.as_deref(), Some("(?i)miner"));
+        assert_eq!(config.policy.allow[0].description, "Date helpers");
+    }
+
+    #[test]
+    fn watcher_reports_changes_once() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("girt.toml");
+        std::fs::write(&path, "[llm]\nprovider = \"stub\"\n").unwrap();
+        let mut watcher = ConfigWatcher::new(&path, None).unwrap();
+        assert!(watcher.poll().unwrap().is_none());
+
+        std::fs::write(
+            &path,
+            "[llm]\nprovider = \"stub\"\n\n[[policy.deny]]\ndescription = \"x\"\nname_pattern = \"x\"\n",
+        )
+        .unwrap();
+        let reloaded = watcher.poll().unwrap().expect("change detected");
+        assert_eq!(reloaded.policy.deny.len(), 1);
+        assert!(watcher.poll().unwrap().is_none());
+    }
+
+    #[test]
+    fn watcher_surfaces_parse_errors_once() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("girt.toml");
+        std::fs::write(&path, "[llm]\nprovider = \"stub\"\n").unwrap();
+        let mut watcher = ConfigWatcher::new(&path, None).unwrap();
+
+        std::fs::write(&path, "[llm\nprovider = ").unwrap();
+        assert!(watcher.poll().is_err());
+        assert!(watcher.poll().unwrap().is_none());
+    }
+
+    fn write_profile(dir: &Path) -> PathBuf {
+        let base = dir.join("girt.toml");
+        std::fs::write(
+            &base,
+            "[llm]\nprovider = \"stub\"\nmodel = \"base-model\"\nmax_tokens = 1000\n\n\
+             [[policy.deny]]\ndescription = \"a\"\nname_pattern = \"a\"\n\n\
+             [[policy.deny]]\ndescription = \"b\"\nname_pattern = \"b\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("girt.staging.toml"),
+            "[llm]\nmodel = \"staging-model\"\n\n\
+             [[policy.deny]]\ndescription = \"c\"\nname_pattern = \"c\"\n",
+        )
+        .unwrap();
+        base
+    }
+
+    #[test]
+    fn profile_overlay_merges_tables_and_replaces_values() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = write_profile(tmp.path());
+        let staging = Profile::new("staging").unwrap();
+
+        let config = GirtConfig::load(&base, Some(&staging)).unwrap();
+        assert_eq!(config.llm.provider, LlmProvider::Stub);
+        assert_eq!(config.llm.model, "staging-model");
+        assert_eq!(config.llm.max_tokens, 1000);
+        assert_eq!(config.policy.deny.len(), 1);
+        assert_eq!(config.policy.deny[0].description, "c");
+
+        let config = GirtConfig::load(&base, None).unwrap();
+        assert_eq!(config.llm.model, "base-model");
+    }
+
+    #[test]
+    fn missing_overlay_fails_unless_optional() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = write_profile(tmp.path());
+        let prod = Profile::new("prod").unwrap();
+
+        let err = GirtConfig::load(&base, Some(&prod)).unwrap_err();
+        assert!(err.to_string().contains("girt.prod.toml"), "{err}");
+
+        let config = GirtConfig::load(&base, Some(&prod.with_optional(true))).unwrap();
+        assert_eq!(config.llm.model, "base-model");
+
+        assert!(Profile::new("../prod").is_err());
+        assert!(Profile::new("").is_err());
+    }
+
+    #[test]
+    fn effective_values_name_their_source_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = write_profile(tmp.path());
+        let overlay = tmp.path().join("girt.staging.toml");
+        let layers = ConfigLayers::read(&base, Some(&Profile::new("staging").unwrap())).unwrap();
+        assert_eq!(
+            layers.files().collect::<Vec<_>>(),
+            vec![base.as_path(), overlay.as_path()]
+        );
+
+        let effective = layers.effective();
+        let value = |key: &str| effective.iter().find(|v| v.key == key).unwrap();
+        assert_eq!(value("llm.model").value, "\"staging-model\"");
+        assert_eq!(value("llm.model").source, overlay);
+        assert_eq!(value("llm.provider").source, base);
+        assert_eq!(value("llm.max_tokens").value, "1000");
+        assert_eq!(value("policy.deny").source, overlay);
+        assert_eq!(effective.len(), 4);
+    }
+
+    #[test]
+    fn watcher_picks_up_overlay_edits() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = write_profile(tmp.path());
+        let staging = Profile::new("staging").unwrap();
+        let mut watcher = ConfigWatcher::new(&base, Some(staging)).unwrap();
+        assert!(watcher.poll().unwrap().is_none());
+
+        std::fs::write(
+            tmp.path().join("girt.staging.toml"),
+            "[llm]\nmodel = \"edited\"\n",
+        )
+        .unwrap();
+        let reloaded = watcher.poll().unwrap().expect("change detected");
+        assert_eq!(reloaded.llm.model, "edited");
+        assert_eq!(reloaded.policy.deny.len(), 2);
+    }
+
+    #[test]
+    fn warm_cache_from_audit_parses_durations() {
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[security]\naudit_log = true\nwarm_cache_from_audit = \"1h\"\n",
+        )
+        .unwrap();
+        assert!(config.security.audit_log);
+        assert_eq!(
+            config.security.warm_cache_from_audit,
+            Some(Duration::from_secs(3600))
+        );
+
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        assert!(parse_duration("1 hour").is_err());
+        assert!(parse_duration("h").is_err());
+
+        let bad = toml::from_str::<GirtConfig>(
+            "[llm]\nprovider = \"stub\"\n\n[security]\nwarm_cache_from_audit = \"soon\"\n",
+        );
+        assert!(bad.is_err());
+    }
+
+    #[test]
+    fn execution_allow_ttl_defaults_and_parses() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(
+            config.security.execution_allow_ttl,
+            DEFAULT_EXECUTION_ALLOW_TTL
+        );
+
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[security]\nexecution_allow_ttl = \"0s\"\n",
+        )
+        .unwrap();
+        assert!(config.security.execution_allow_ttl.is_zero());
+    }
+
+    #[test]
+    fn constraint_budget_parses_with_source_overrides() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.security.budget, ConstraintBudget::default());
+
+        let config: GirtConfig = toml::from_str(
+            r#"
+[llm]
+provider = "stub"
+
+[security.budget]
+max_network_hosts = 2
+secrets_require_operator = false
+
+[security.budget.overrides.operator]
+max_network_hosts = 10
+max_secrets = 8
+"#,
+        )
+        .unwrap();
+        let budget = &config.security.budget;
+        assert_eq!(budget.max_network_hosts, 2);
+        assert_eq!(
+            budget.max_storage_paths,
+            ConstraintBudget::default().max_storage_paths
+        );
+        assert!(!budget.secrets_require_operator);
+        let operator = budget.overrides[&RequestSource::Operator];
+        assert_eq!(operator.max_network_hosts, Some(10));
+        assert_eq!(operator.max_storage_paths, None);
+        assert_eq!(operator.max_secrets, Some(8));
+    }
+
+    #[test]
+    fn always_ask_parses_per_gate() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(config.security.always_ask, AlwaysAsk::default());
+
+        let always_ask: GirtConfig = toml::from_str(
+            r#"
+[llm]
+provider = "stub"
+
+[security.always_ask]
+execution = ["deploy_*", "send_payment"]
+"#,
+        )
+        .unwrap();
+        assert!(always_ask.security.always_ask.creation.is_empty());
+        assert_eq!(
+            always_ask.security.always_ask.execution,
+            vec!["deploy_*", "send_payment"]
+        );
+        assert_eq!(
+            config.restart_required_changes(&always_ask),
+            vec!["security.always_ask"]
+        );
+    }
+
+    #[test]
+    fn recheck_on_restore_defaults_on() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert!(config.security.recheck_on_restore);
+        assert!(SecurityConfig::default().recheck_on_restore);
+
+        let off: GirtConfig = toml::from_str(
+            r#"
+[llm]
+provider = "stub"
+
+[security]
+recheck_on_restore = false
+"#,
+        )
+        .unwrap();
+        assert!(!off.security.recheck_on_restore);
+        assert_eq!(
+            config.restart_required_changes(&off),
+            vec!["security.recheck_on_restore"]
+        );
+    }
+
+    #[test]
+    fn agent_approvals_are_opt_in() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert!(!config.security.agent_approvals);
+
+        let on: GirtConfig = toml::from_str(
+            r#"
+[llm]
+provider = "stub"
+
+[security]
+agent_approvals = true
+"#,
+        )
+        .unwrap();
+        assert!(on.security.agent_approvals);
+        assert_eq!(
+            config.restart_required_changes(&on),
+            vec!["security.agent_approvals"]
+        );
+    }
+
+    #[test]
+    fn cli_check_utilities_parse_from_toml() {
+        let config: GirtConfig = toml::from_str(
+            r#"
+[llm]
+provider = "stub"
+
+[[cli_check.utilities]]
+binary = "yq"
+keywords = ["yaml_query"]
+example_invocation = "yq '.spec' deploy.yaml"
+"#,
+        )
+        .unwrap();
+        let utilities = &config.cli_check.utilities;
+        assert_eq!(utilities.len(), 1);
+        assert_eq!(utilities[0].binary, "yq");
+        assert_eq!(utilities[0].keywords, vec!["yaml_query"]);
+        assert!(utilities[0].description.is_empty());
+    }
+
+    #[test]
+    fn deprecation_grace_defaults_to_a_week() {
+        let config: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        assert_eq!(
+            config.tools.deprecation_grace,
+            Duration::from_secs(7 * 24 * 60 * 60)
+        );
+
+        let config: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[tools]\ndeprecation_grace = \"2d\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.tools.deprecation_grace,
+            Duration::from_secs(2 * 24 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn restart_required_changes_ignores_reloadable_settings() {
+        let base: GirtConfig = toml::from_str("[llm]\nprovider = \"stub\"\n").unwrap();
+        let reloadable: GirtConfig = toml::from_str(
+            "[llm]\nprovider = \"stub\"\n\n[tools.env.t]\nKEY = \"v\"\n",
+        )
+        .unwrap();
+        assert!(base.restart_required_changes(&reloadable).is_empty());
+
+        let provider: GirtConfig =
+            toml::from_str("[llm]\nprovider = \"anthropic\"\nmodel = \"m\"\n").unwrap();
+        assert_eq!(
+            base.restart_required_changes(&provider),
+            vec!["llm.provider", "llm.model"]
+        );
     }
 }
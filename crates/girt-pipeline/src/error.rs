@@ -1,27 +1,74 @@
 use thiserror::Error;
 
+/// Why a pipeline operation failed.
+///
+/// Each variant has a stable [`code`](Self::code), reported to agents as
+/// `error_code` and kept with failed queue requests, so a caller can tell
+/// "retry later" (`llm_transport`) from "fix the request" (`invalid_spec`)
+/// without parsing the message.
 #[derive(Debug, Error)]
 pub enum PipelineError {
+    /// girt.toml could not be parsed or names an unusable setting.
+    #[error("config error: {0}")]
+    ConfigError(String),
+
     #[error("queue error: {0}")]
     QueueError(String),
 
-    #[error("LLM call failed: {0}")]
-    LlmError(String),
+    #[error(transparent)]
+    InvalidSpec(#[from] girt_core::error::SpecValidationError),
+
+    #[error(transparent)]
+    TooLarge(#[from] girt_core::error::SizeLimitError),
+
+    /// A supplied policy grants more than its spec's constraints allow.
+    #[error("policy violates the spec: {0}")]
+    PolicyViolation(String),
+
+    /// The LLM provider could not be reached or answered with an error.
+    #[error("LLM request failed: {0}")]
+    LlmTransport(String),
 
-    #[error("compilation failed: {0}")]
-    CompilationError(String),
+    /// The LLM answered, but not with what was asked for.
+    #[error("LLM returned malformed output: {0}")]
+    LlmMalformedOutput(String),
 
-    #[error("QA test failed: {0}")]
-    QaError(String),
+    /// The Architect's refined spec parsed but cannot be built from.
+    #[error("architect output invalid: {0}")]
+    ArchitectOutputInvalid(String),
 
-    #[error("security audit failed: {0}")]
-    SecurityError(String),
+    /// A build step failed. `stderr_tail` is the end of its error output.
+    #[error("{stage} failed: {stderr_tail}")]
+    CompileFailed { stage: String, stderr_tail: String },
 
     #[error("circuit breaker triggered after {attempts} attempts: {summary}")]
     CircuitBreaker { attempts: u32, summary: String },
 
-    #[error("publish error: {0}")]
-    PublishError(String),
+    #[error("token budget of {budget} exhausted")]
+    BudgetExceeded { budget: u32 },
+
+    /// The build was stopped before it finished.
+    #[error("cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("publish failed: {0}")]
+    PublishFailed(String),
+
+    /// A different tool is already published under the requested name.
+    #[error("a different tool named '{name}' is already published: {existing}")]
+    NameCollision { name: String, existing: String },
+
+    /// A git command run by `ToolSync` failed.
+    #[error("git sync failed: {0}")]
+    GitSyncFailed(String),
+
+    /// A tool's files failed the checks run before a `ToolSync` commit.
+    #[error("tool sync rejected: {0}")]
+    SyncRejected(String),
+
+    /// An OpenAPI document could not be turned into a capability spec.
+    #[error("OpenAPI import failed: {0}")]
+    SpecImport(String),
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
@@ -29,3 +76,75 @@ pub enum PipelineError {
     #[error("serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 }
+
+impl PipelineError {
+    /// A stable snake_case name for this kind of failure.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ConfigError(_) => "config_error",
+            Self::QueueError(_) => "queue_error",
+            Self::InvalidSpec(_) => "invalid_spec",
+            Self::TooLarge(_) => "too_large",
+            Self::PolicyViolation(_) => "policy_violation",
+            Self::LlmTransport(_) => "llm_transport",
+            Self::LlmMalformedOutput(_) => "llm_malformed_output",
+            Self::ArchitectOutputInvalid(_) => "architect_output_invalid",
+            Self::CompileFailed { .. } => "compile_failed",
+            Self::CircuitBreaker { .. } => "circuit_breaker",
+            Self::BudgetExceeded { .. } => "budget_exceeded",
+            Self::Cancelled(_) => "cancelled",
+            Self::PublishFailed(_) => "publish_failed",
+            Self::NameCollision { .. } => "name_collision",
+            Self::GitSyncFailed(_) => "git_sync_failed",
+            Self::SyncRejected(_) => "sync_rejected",
+            Self::SpecImport(_) => "spec_import",
+            Self::IoError(_) => "io_error",
+            Self::SerializationError(_) => "serialization_error",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_snake_case_and_distinct() {
+        let errors = [
+            PipelineError::ConfigError(String::new()),
+            PipelineError::QueueError(String::new()),
+            PipelineError::PolicyViolation(String::new()),
+            PipelineError::LlmTransport(String::new()),
+            PipelineError::LlmMalformedOutput(String::new()),
+            PipelineError::ArchitectOutputInvalid(String::new()),
+            PipelineError::CompileFailed {
+                stage: "cargo-component build".into(),
+                stderr_tail: String::new(),
+            },
+            PipelineError::CircuitBreaker {
+                attempts: 3,
+                summary: String::new(),
+            },
+            PipelineError::BudgetExceeded { budget: 100 },
+            PipelineError::Cancelled(String::new()),
+            PipelineError::PublishFailed(String::new()),
+            PipelineError::NameCollision {
+                name: String::new(),
+                existing: String::new(),
+            },
+            PipelineError::GitSyncFailed(String::new()),
+            PipelineError::SyncRejected(String::new()),
+            PipelineError::SpecImport(String::new()),
+            PipelineError::IoError(std::io::Error::other("x")),
+        ];
+        let mut codes: Vec<&str> = errors.iter().map(PipelineError::code).collect();
+        assert!(
+            codes
+                .iter()
+                .all(|c| c.chars().all(|ch| ch.is_ascii_lowercase() || ch == '_'))
+        );
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+}
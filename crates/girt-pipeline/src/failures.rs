@@ -0,0 +1,712 @@
+//! Failure bundles: what a failed build had produced, kept for debugging.
+//!
+//! When a build fails, [`FailureStore::write`] saves its [`FailureReport`]
+//! as a directory named after the request:
+//!
+//! ```text
+//! ~/.girt/failures/{request_id}/
+//!   failure.json        -- the error, timings, and which iterations are kept
+//!   spec.json           -- the refined spec
+//!   tickets.json        -- every bug ticket filed
+//!   iteration-N.json    -- the Engineer's output in iteration N
+//!   iter-N.rs           -- its `src/lib.rs`, with `keep_iteration_sources`
+//!   compile-stderr.txt  -- the compiler's error output, for compile failures
+//! ```
+//!
+//! With [`FailureStore::with_iteration_sources`] (`[pipeline]
+//! keep_iteration_sources`), builds that pass leave a directory too, holding
+//! only their `iter-N.rs` files, so a fix loop that got there the long way
+//! can be diffed (`girt failures diff`).
+//!
+//! A bundle over the size cap drops its earliest iterations first. Only the
+//! most recent bundles are kept (see [`DEFAULT_KEEP`]), and as many source
+//! directories of passed builds.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use girt_core::paths::{self, PathError};
+use serde::{Deserialize, Serialize};
+
+use crate::error::PipelineError;
+use crate::types::{BuildArtifact, BuildOutput, RefinedSpec, StageTimings, TicketRecord};
+
+/// Bundles kept before the oldest are removed.
+pub const DEFAULT_KEEP: usize = 20;
+
+/// Largest bundle written, in bytes.
+pub const DEFAULT_MAX_BUNDLE_BYTES: usize = 2 * 1024 * 1024;
+
+const SUMMARY_FILE: &str = "failure.json";
+const SPEC_FILE: &str = "spec.json";
+const TICKETS_FILE: &str = "tickets.json";
+const STDERR_FILE: &str = "compile-stderr.txt";
+
+/// What a failed pipeline run had produced when it stopped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureReport {
+    pub request_id: Option<String>,
+    pub refined_spec: RefinedSpec,
+    /// The Engineer's output in each iteration, the first build first.
+    pub iterations: Vec<BuildOutput>,
+    pub tickets: Vec<TicketRecord>,
+    /// End of the compiler's error output, when compiling failed.
+    pub compile_stderr: Option<String>,
+    pub timings: StageTimings,
+}
+
+impl FailureReport {
+    pub fn new(request_id: Option<String>, refined_spec: RefinedSpec) -> Self {
+        Self {
+            request_id,
+            refined_spec,
+            iterations: Vec::new(),
+            tickets: Vec::new(),
+            compile_stderr: None,
+            timings: StageTimings::default(),
+        }
+    }
+
+    /// A report for `artifact`, which passed the agents but failed to
+    /// compile with `error`. Only its final iteration is known.
+    pub fn compile_failure(artifact: &BuildArtifact, error: &PipelineError) -> Self {
+        let provenance = artifact.provenance.as_ref();
+        Self {
+            request_id: provenance.and_then(|p| p.request_id.clone()),
+            refined_spec: artifact.refined_spec.clone(),
+            iterations: vec![artifact.build_output.clone()],
+            tickets: artifact.ticket_history.clone(),
+            compile_stderr: match error {
+                PipelineError::CompileFailed { stderr_tail, .. } => Some(stderr_tail.clone()),
+                _ => None,
+            },
+            timings: provenance.map(|p| p.timings.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// `failure.json`: one line of `girt failures list`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureSummary {
+    pub id: String,
+    pub tool_name: String,
+    pub error: String,
+    pub error_code: String,
+    pub failed_at: DateTime<Utc>,
+    pub timings: StageTimings,
+    /// Iterations whose output is in the bundle, numbered from 1.
+    pub iterations: Vec<u32>,
+    /// Iterations left out to keep the bundle under the size cap.
+    #[serde(default)]
+    pub omitted_iterations: Vec<u32>,
+}
+
+/// A bundle as read back by [`FailureStore::show`]. The report holds only
+/// the iterations listed in `summary.iterations`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureBundle {
+    pub summary: FailureSummary,
+    pub report: FailureReport,
+}
+
+/// Directory of failure bundles.
+pub struct FailureStore {
+    dir: PathBuf,
+    keep: usize,
+    max_bytes: usize,
+    /// Write `iter-N.rs` files, for passed builds as well as failed ones.
+    iteration_sources: bool,
+}
+
+impl FailureStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            keep: DEFAULT_KEEP,
+            max_bytes: DEFAULT_MAX_BUNDLE_BYTES,
+            iteration_sources: false,
+        }
+    }
+
+    /// Default location: `failures/` in the data directory (~/.girt/failures/).
+    pub fn default_path() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("failures"))
+    }
+
+    /// Keep the `keep` most recent bundles.
+    pub fn with_keep(mut self, keep: usize) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Write at most `max_bytes` per bundle.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Keep each iteration's `src/lib.rs` as `iter-N.rs`: in failure
+    /// bundles, and through [`Self::record_sources`] for builds that pass.
+    pub fn with_iteration_sources(mut self, keep: bool) -> Self {
+        self.iteration_sources = keep;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save the bundle for a build that failed with `error`, replacing any
+    /// earlier one for the same request, then remove bundles past the
+    /// retention limit. Returns the bundle's directory.
+    pub async fn write(
+        &self,
+        error: &PipelineError,
+        report: &FailureReport,
+    ) -> Result<PathBuf, PipelineError> {
+        let id = report
+            .request_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let bundle = self.bundle_dir(&id)?;
+
+        let spec = serde_json::to_vec_pretty(&report.refined_spec)?;
+        let tickets = serde_json::to_vec_pretty(&report.tickets)?;
+        let stderr = report.compile_stderr.as_deref().unwrap_or_default();
+        let mut used = spec.len() + tickets.len() + stderr.len();
+
+        // The latest iterations are the most telling; drop from the start.
+        let mut kept = Vec::new();
+        let mut omitted = Vec::new();
+        for (i, output) in report.iterations.iter().enumerate().rev() {
+            let number = i as u32 + 1;
+            let json = serde_json::to_vec_pretty(output)?;
+            if used + json.len() <= self.max_bytes {
+                used += json.len();
+                kept.push((number, json));
+            } else {
+                omitted.push(number);
+            }
+        }
+        kept.reverse();
+        omitted.reverse();
+        if !omitted.is_empty() {
+            tracing::warn!(id = %id, ?omitted, "Failure bundle over its size cap; iterations left out");
+        }
+
+        let summary = FailureSummary {
+            id: id.clone(),
+            tool_name: report.refined_spec.spec.name.clone(),
+            error: error.to_string(),
+            error_code: error.code().to_string(),
+            failed_at: Utc::now(),
+            timings: report.timings.clone(),
+            iterations: kept.iter().map(|(n, _)| *n).collect(),
+            omitted_iterations: omitted,
+        };
+
+        // Written beside the final directory and renamed into place, so a
+        // bundle is never seen half-written.
+        let staging = self.dir.join(format!(".{id}.partial"));
+        remove_dir_if_present(&staging).await?;
+        tokio::fs::create_dir_all(&staging).await?;
+        tokio::fs::write(
+            staging.join(SUMMARY_FILE),
+            serde_json::to_vec_pretty(&summary)?,
+        )
+        .await?;
+        tokio::fs::write(staging.join(SPEC_FILE), spec).await?;
+        tokio::fs::write(staging.join(TICKETS_FILE), tickets).await?;
+        for (number, json) in kept {
+            tokio::fs::write(staging.join(iteration_file(number)), json).await?;
+            if self.iteration_sources {
+                let source = &report.iterations[number as usize - 1].source_code;
+                tokio::fs::write(staging.join(source_file(number)), source).await?;
+            }
+        }
+        if report.compile_stderr.is_some() {
+            tokio::fs::write(staging.join(STDERR_FILE), stderr).await?;
+        }
+        remove_dir_if_present(&bundle).await?;
+        tokio::fs::rename(&staging, &bundle).await?;
+
+        self.prune().await?;
+        Ok(bundle)
+    }
+
+    /// Save the source of each iteration of a build that passed, as
+    /// `iter-N.rs` in the directory for its request (replacing any earlier
+    /// bundle, as [`Self::write`] does), then remove source directories
+    /// past the retention limit. Over the size cap, the earliest
+    /// iterations are left out.
+    pub async fn write_sources(&self, report: &FailureReport) -> Result<PathBuf, PipelineError> {
+        let id = report
+            .request_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let dir = self.bundle_dir(&id)?;
+
+        let mut used = 0;
+        let mut kept = Vec::new();
+        for (i, output) in report.iterations.iter().enumerate().rev() {
+            used += output.source_code.len();
+            if used > self.max_bytes {
+                break;
+            }
+            kept.push((i as u32 + 1, &output.source_code));
+        }
+
+        let staging = self.dir.join(format!(".{id}.partial"));
+        remove_dir_if_present(&staging).await?;
+        tokio::fs::create_dir_all(&staging).await?;
+        for (number, source) in kept {
+            tokio::fs::write(staging.join(source_file(number)), source).await?;
+        }
+        remove_dir_if_present(&dir).await?;
+        tokio::fs::rename(&staging, &dir).await?;
+
+        self.prune_sources().await?;
+        Ok(dir)
+    }
+
+    /// [`Self::write_sources`] when the store keeps iteration sources,
+    /// logging rather than returning an error.
+    pub async fn record_sources(&self, report: &FailureReport) {
+        if !self.iteration_sources {
+            return;
+        }
+        match self.write_sources(report).await {
+            Ok(dir) => tracing::debug!(dir = %dir.display(), "Iteration sources kept"),
+            Err(e) => tracing::warn!(error = %e, "Failed to keep iteration sources"),
+        }
+    }
+
+    /// The `src/lib.rs` of iteration `number` of request `id`, from its
+    /// `iter-N.rs` or, in a failure bundle kept without them, its
+    /// `iteration-N.json`.
+    pub async fn iteration_source(&self, id: &str, number: u32) -> Result<String, PipelineError> {
+        let dir = self.bundle_dir(id)?;
+        match tokio::fs::read_to_string(dir.join(source_file(number))).await {
+            Ok(source) => return Ok(source),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        match read_json::<BuildOutput>(&dir.join(iteration_file(number))).await {
+            Ok(output) => Ok(output.source_code),
+            Err(PipelineError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(PipelineError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("iteration {number} of {id} is not kept"),
+                )))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// [`Self::write`], logging rather than returning an error: a bundle
+    /// that cannot be written never fails the build it describes.
+    pub async fn record(&self, error: &PipelineError, report: &FailureReport) {
+        match self.write(error, report).await {
+            Ok(bundle) => tracing::info!(bundle = %bundle.display(), "Failure bundle written"),
+            Err(e) => tracing::warn!(error = %e, "Failed to write failure bundle"),
+        }
+    }
+
+    /// Summaries of the kept bundles, most recent first.
+    pub async fn list(&self) -> Result<Vec<FailureSummary>, PipelineError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut summaries = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // The sources of a build that passed.
+            if !tokio::fs::try_exists(entry.path().join(SUMMARY_FILE)).await? {
+                continue;
+            }
+            match read_json::<FailureSummary>(&entry.path().join(SUMMARY_FILE)).await {
+                Ok(summary) => summaries.push(summary),
+                Err(e) => {
+                    tracing::warn!(path = %entry.path().display(), error = %e, "Skipping unreadable failure bundle")
+                }
+            }
+        }
+        summaries.sort_by(|a, b| b.failed_at.cmp(&a.failed_at).then(a.id.cmp(&b.id)));
+        Ok(summaries)
+    }
+
+    /// Read back the bundle for request `id`.
+    pub async fn show(&self, id: &str) -> Result<FailureBundle, PipelineError> {
+        let bundle = self.bundle_dir(id)?;
+        let summary: FailureSummary = read_json(&bundle.join(SUMMARY_FILE)).await?;
+        let mut iterations = Vec::new();
+        for number in &summary.iterations {
+            iterations.push(read_json(&bundle.join(iteration_file(*number))).await?);
+        }
+        let compile_stderr = match tokio::fs::read_to_string(bundle.join(STDERR_FILE)).await {
+            Ok(stderr) => Some(stderr),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let report = FailureReport {
+            request_id: Some(summary.id.clone()),
+            refined_spec: read_json(&bundle.join(SPEC_FILE)).await?,
+            iterations,
+            tickets: read_json(&bundle.join(TICKETS_FILE)).await?,
+            compile_stderr,
+            timings: summary.timings.clone(),
+        };
+        Ok(FailureBundle { summary, report })
+    }
+
+    /// Remove all but the `keep` most recent bundles.
+    async fn prune(&self) -> Result<(), PipelineError> {
+        for old in self.list().await?.iter().skip(self.keep) {
+            tracing::debug!(id = %old.id, "Removing old failure bundle");
+            remove_dir_if_present(&self.dir.join(&old.id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove all but the `keep` most recently written source directories
+    /// of passed builds.
+    async fn prune_sources(&self) -> Result<(), PipelineError> {
+        let mut dirs = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.')
+                || !entry.file_type().await?.is_dir()
+                || tokio::fs::try_exists(path.join(SUMMARY_FILE)).await?
+            {
+                continue;
+            }
+            dirs.push((entry.metadata().await?.modified()?, path));
+        }
+        dirs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        for (_, old) in dirs.iter().skip(self.keep) {
+            tracing::debug!(dir = %old.display(), "Removing old iteration sources");
+            remove_dir_if_present(old).await?;
+        }
+        Ok(())
+    }
+
+    /// The directory for request `id`, which must be a plain file name.
+    fn bundle_dir(&self, id: &str) -> Result<PathBuf, PipelineError> {
+        let plain = !id.is_empty()
+            && !id.starts_with('.')
+            && !id.contains(['/', '\\'])
+            && Path::new(id).file_name().is_some_and(|name| name == id);
+        if !plain {
+            return Err(PipelineError::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid failure bundle id: {id:?}"),
+            )));
+        }
+        Ok(self.dir.join(id))
+    }
+}
+
+fn iteration_file(number: u32) -> String {
+    format!("iteration-{number}.json")
+}
+
+fn source_file(number: u32) -> String {
+    format!("iter-{number}.rs")
+}
+
+/// Lines around each change shown by [`line_diff`].
+const DIFF_CONTEXT: usize = 3;
+
+/// A unified diff of `old` against `new`, line by line, with
+/// [`DIFF_CONTEXT`] lines of context; empty when they are the same.
+pub fn line_diff(old: &str, old_name: &str, new: &str, new_name: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // Only the part between a common prefix and suffix needs the table.
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // lcs[i][j]: longest common subsequence of a_mid[i..] and b_mid[j..].
+    let mut lcs = vec![vec![0u32; b_mid.len() + 1]; a_mid.len() + 1];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // (' ' | '-' | '+', line in old, line in new), 0-based.
+    let mut ops: Vec<(char, usize, usize)> = (0..prefix).map(|n| (' ', n, n)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a_mid.len() || j < b_mid.len() {
+        if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+            ops.push((' ', prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if i < a_mid.len() && (j == b_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', prefix + i, prefix + j));
+            i += 1;
+        } else {
+            ops.push(('+', prefix + i, prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((0..suffix).map(|n| (' ', a.len() - suffix + n, b.len() - suffix + n)));
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&n| ops[n].0 != ' ').collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("--- {old_name}\n+++ {new_name}\n");
+    let mut first = 0;
+    while first < changes.len() {
+        // Changes closer than twice the context share a hunk.
+        let mut last = first;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * DIFF_CONTEXT {
+            last += 1;
+        }
+        let start = changes[first].saturating_sub(DIFF_CONTEXT);
+        let end = (changes[last] + DIFF_CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_count = hunk.iter().filter(|op| op.0 != '-').count();
+        // An empty range names the line before it.
+        let line = |n: usize, count: usize| if count == 0 { n } else { n + 1 };
+        out.push_str(&format!(
+            "@@ -{},{old_count} +{},{new_count} @@\n",
+            line(hunk[0].1, old_count),
+            line(hunk[0].2, new_count),
+        ));
+        for &(op, i, j) in hunk {
+            let text = if op == '+' { b[j] } else { a[i] };
+            out.push(op);
+            out.push_str(text);
+            out.push('\n');
+        }
+        first = last + 1;
+    }
+    out
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, PipelineError> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+async fn remove_dir_if_present(path: &Path) -> Result<(), PipelineError> {
+    match tokio::fs::remove_dir_all(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SpecAction;
+    use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+    use tempfile::TempDir;
+
+    fn report(id: &str, iterations: usize) -> FailureReport {
+        let spec = RefinedSpec {
+            action: SpecAction::Build,
+            spec: CapabilitySpec {
+                name: "test_tool".into(),
+                description: "A test tool".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            design_notes: String::new(),
+            extend_target: None,
+            extend_features: None,
+            idempotent: false,
+        };
+        let mut report = FailureReport::new(Some(id.into()), spec);
+        report.iterations = (1..=iterations)
+            .map(|n| BuildOutput {
+                source_code: format!("// v{n}\n{}", "x".repeat(1000)),
+                wit_definition: String::new(),
+                policy_yaml: String::new(),
+                language: "rust".into(),
+                files: vec![],
+                change_summary: None,
+            })
+            .collect();
+        report
+    }
+
+    fn breaker() -> PipelineError {
+        PipelineError::CircuitBreaker {
+            attempts: 3,
+            summary: "#1: [FunctionalDefect] expected: a, actual: b".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_most_recent_bundles_are_kept() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf()).with_keep(2);
+        for id in ["first", "second", "third"] {
+            store.write(&breaker(), &report(id, 1)).await.unwrap();
+        }
+
+        let ids: Vec<String> = store
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, ["third", "second"]);
+        assert!(!tmp.path().join("first").exists());
+    }
+
+    #[tokio::test]
+    async fn oversized_bundles_drop_the_earliest_iterations() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf()).with_max_bytes(3000);
+        store.write(&breaker(), &report("req", 3)).await.unwrap();
+
+        let bundle = store.show("req").await.unwrap();
+        assert_eq!(bundle.summary.iterations, [2, 3]);
+        assert_eq!(bundle.summary.omitted_iterations, [1]);
+        assert!(bundle.report.iterations[0].source_code.starts_with("// v2"));
+        assert!(!tmp.path().join("req/iteration-1.json").exists());
+    }
+
+    #[tokio::test]
+    async fn compile_failures_keep_the_compiler_output() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf());
+        let mut report = report("req", 1);
+        let error = PipelineError::CompileFailed {
+            stage: "cargo-component build".into(),
+            stderr_tail: "error[E0425]: cannot find value `x`".into(),
+        };
+        report.compile_stderr = Some("error[E0425]: cannot find value `x`".into());
+        store.write(&error, &report).await.unwrap();
+
+        let bundle = store.show("req").await.unwrap();
+        assert_eq!(bundle.summary.error_code, "compile_failed");
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("req/compile-stderr.txt")).unwrap(),
+            "error[E0425]: cannot find value `x`"
+        );
+        assert_eq!(bundle.report.compile_stderr, report.compile_stderr);
+    }
+
+    #[tokio::test]
+    async fn a_retried_request_replaces_its_bundle() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf());
+        store.write(&breaker(), &report("req", 3)).await.unwrap();
+        store.write(&breaker(), &report("req", 1)).await.unwrap();
+
+        assert_eq!(store.list().await.unwrap().len(), 1);
+        assert!(!tmp.path().join("req/iteration-3.json").exists());
+    }
+
+    #[tokio::test]
+    async fn iteration_sources_are_kept_for_passed_and_failed_builds() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf())
+            .with_keep(1)
+            .with_iteration_sources(true);
+        store.write(&breaker(), &report("failed", 2)).await.unwrap();
+        assert!(tmp.path().join("failed/iter-2.rs").exists());
+
+        store.record_sources(&report("passed", 2)).await;
+        let mut files: Vec<_> = std::fs::read_dir(tmp.path().join("passed"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["iter-1.rs", "iter-2.rs"]);
+        let source = store.iteration_source("passed", 2).await.unwrap();
+        assert!(source.starts_with("// v2"));
+        // Passed builds are not failures, and are kept apart from them.
+        let ids: Vec<String> = store
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, ["failed"]);
+
+        store.record_sources(&report("passed_later", 1)).await;
+        assert!(!tmp.path().join("passed").exists());
+        assert!(tmp.path().join("failed").exists());
+        assert!(
+            store
+                .iteration_source("passed_later", 2)
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("iteration 2 of passed_later is not kept")
+        );
+    }
+
+    #[tokio::test]
+    async fn failure_bundles_without_sources_still_diff() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf());
+        store.write(&breaker(), &report("req", 2)).await.unwrap();
+        store.record_sources(&report("passed", 2)).await;
+        assert!(!tmp.path().join("req/iter-1.rs").exists());
+        assert!(!tmp.path().join("passed").exists());
+
+        let old = store.iteration_source("req", 1).await.unwrap();
+        let new = store.iteration_source("req", 2).await.unwrap();
+        assert_eq!(
+            line_diff(&old, "iter-1.rs", &new, "iter-2.rs"),
+            "--- iter-1.rs\n+++ iter-2.rs\n@@ -1,2 +1,2 @@\n-// v1\n+// v2\n ".to_string()
+                + &"x".repeat(1000)
+                + "\n"
+        );
+    }
+
+    #[test]
+    fn line_diffs_show_changes_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        assert_eq!(
+            line_diff(old, "old", new, "new"),
+            "--- old\n+++ new\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+        );
+        assert_eq!(line_diff(old, "old", old, "new"), "");
+        assert_eq!(
+            line_diff("", "old", "a\n", "new"),
+            "--- old\n+++ new\n@@ -0,0 +1,1 @@\n+a\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn ids_outside_the_store_are_refused() {
+        let tmp = TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().join("failures"));
+        for id in ["", "..", "../queue", "a/b", ".hidden"] {
+            assert!(store.show(id).await.is_err(), "{id:?}");
+        }
+        assert!(store.list().await.unwrap().is_empty());
+    }
+}
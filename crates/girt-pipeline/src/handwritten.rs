@@ -0,0 +1,221 @@
+//! Tools written by hand rather than by the agents.
+//!
+//! `girt tools add` passes an operator's `lib.rs`, spec, and optional
+//! policy to [`build`], which checks and compiles them into an artifact
+//! that publishes and loads like any other. No LLM is called: QA and the
+//! Red Team do not run, and the artifact's provenance lists no agents.
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use girt_core::spec::CapabilitySpec;
+
+use crate::compiler::{CompileInput, CompileOutput, WasmCompiler};
+use crate::error::PipelineError;
+use crate::types::{
+    BuildArtifact, BuildOutput, PipelineSnapshot, PolicyYaml, Provenance, QaResult, RefinedSpec,
+    RequestSource, SecurityResult, SpecAction, StageTimings,
+};
+use crate::validation::TestVector;
+
+/// A tool's sources as supplied by an operator.
+#[derive(Debug, Clone)]
+pub struct HandwrittenTool {
+    pub spec: CapabilitySpec,
+    /// `src/lib.rs`, built against the default girt-tool world.
+    pub source_code: String,
+    /// `policy.yaml`. Generated from the spec's constraints when absent.
+    pub policy_yaml: Option<String>,
+    /// Recorded in the manifest for `girt tools validate` to re-run.
+    pub test_vectors: Vec<TestVector>,
+}
+
+/// A compiled handwritten tool, ready for
+/// [`Publisher::publish_with_wasm`](crate::publish::Publisher::publish_with_wasm).
+pub struct HandwrittenBuild {
+    pub artifact: BuildArtifact,
+    pub compiled: CompileOutput,
+}
+
+/// Validate `tool`'s spec, check its policy against the spec's constraints,
+/// and compile it. Fails before compiling if either check does.
+pub async fn build(
+    tool: HandwrittenTool,
+    compiler: &WasmCompiler,
+) -> Result<HandwrittenBuild, PipelineError> {
+    let HandwrittenTool {
+        spec,
+        source_code,
+        policy_yaml,
+        test_vectors,
+    } = tool;
+    spec.validate()?;
+    let policy_yaml = match policy_yaml {
+        Some(policy) => {
+            let violations = PolicyYaml::violations(&policy, &spec);
+            if !violations.is_empty() {
+                return Err(PipelineError::PolicyViolation(violations.join("; ")));
+            }
+            policy
+        }
+        None => {
+            let tier = PolicyYaml::infer_tier(&spec);
+            serde_json::to_string_pretty(&PolicyYaml::from_spec_with_tier(&spec, &tier))?
+        }
+    };
+
+    let started = Instant::now();
+    let compiled = compiler
+        .compile(&CompileInput {
+            source_code: source_code.clone(),
+            wit_definition: String::new(),
+            tool_name: spec.name.clone(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        })
+        .await?;
+    tracing::info!(tool = %spec.name, wasm = %compiled.wasm_path.display(), "Compiled handwritten tool");
+
+    let artifact = BuildArtifact {
+        spec: spec.clone(),
+        refined_spec: RefinedSpec {
+            action: SpecAction::Build,
+            spec,
+            design_notes: "Written by hand".into(),
+            extend_target: None,
+            extend_features: None,
+            idempotent: false,
+        },
+        build_output: BuildOutput {
+            source_code,
+            wit_definition: String::new(),
+            policy_yaml,
+            language: "rust".into(),
+            files: vec![],
+            change_summary: None,
+        },
+        qa_result: QaResult {
+            passed: true,
+            tests_run: 0,
+            tests_passed: 0,
+            tests_failed: 0,
+            bug_tickets: vec![],
+            data_quality: vec![],
+        },
+        security_result: SecurityResult {
+            passed: true,
+            exploits_attempted: 0,
+            exploits_succeeded: 0,
+            bug_tickets: vec![],
+            data_quality: vec![],
+        },
+        build_iterations: 1,
+        ticket_history: vec![],
+        provenance: Some(Provenance {
+            girt_version: env!("CARGO_PKG_VERSION").to_string(),
+            request_id: None,
+            request_source: Some(RequestSource::Operator),
+            agents: BTreeMap::new(),
+            pipeline: PipelineSnapshot { max_iterations: 1 },
+            timings: StageTimings {
+                total_ms: started.elapsed().as_millis() as u64,
+                ..StageTimings::default()
+            },
+        }),
+        requested_name: None,
+        revision: 0,
+        published_at: None,
+        iteration_outputs: vec![],
+        test_vectors,
+    };
+    Ok(HandwrittenBuild { artifact, compiled })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::BuildSandbox;
+    use tempfile::TempDir;
+
+    const SOURCE: &str = include_str!("../tests/fixtures/greet/lib.rs");
+    const SPEC: &str = include_str!("../tests/fixtures/greet/spec.json");
+
+    fn greet(policy_yaml: Option<&str>) -> HandwrittenTool {
+        HandwrittenTool {
+            spec: serde_json::from_str(SPEC).unwrap(),
+            source_code: SOURCE.into(),
+            policy_yaml: policy_yaml.map(Into::into),
+            test_vectors: vec![],
+        }
+    }
+
+    /// A compiler whose `cargo-component` cannot be run.
+    fn unusable_compiler() -> WasmCompiler {
+        WasmCompiler::new()
+            .without_cache()
+            .with_binary("/nonexistent/cargo-component")
+    }
+
+    #[tokio::test]
+    async fn policy_wider_than_the_spec_is_refused_before_compiling() {
+        let policy = "permissions:\n  network:\n    allow:\n      - host: example.com\n";
+        let Err(err) = build(greet(Some(policy)), &unusable_compiler()).await else {
+            panic!("built despite the policy");
+        };
+        assert_eq!(err.code(), "policy_violation", "{err}");
+        assert!(err.to_string().contains("example.com"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn invalid_spec_is_refused_before_compiling() {
+        let mut tool = greet(None);
+        tool.spec.name = "Not A Name".into();
+        let Err(err) = build(tool, &unusable_compiler()).await else {
+            panic!("built despite the spec");
+        };
+        assert_eq!(err.code(), "invalid_spec", "{err}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn builds_an_artifact_without_agents() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new().unwrap();
+        let script = tmp.path().join("cargo-component");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nout=\"${CARGO_TARGET_DIR:-target}/wasm32-wasip1/release\"\n\
+             mkdir -p \"$out\" && printf wasm > \"$out/$(basename \"$PWD\").wasm\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let compiler = WasmCompiler::new()
+            .with_cache_dir(tmp.path().join("build-cache"))
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(script.display().to_string());
+
+        let built = build(greet(None), &compiler).await.unwrap();
+
+        assert_eq!(std::fs::read(&built.compiled.wasm_path).unwrap(), b"wasm");
+        let artifact = built.artifact;
+        assert_eq!(artifact.spec.name, "greet");
+        assert_eq!(artifact.build_output.source_code, SOURCE);
+        // The generated policy grants nothing the spec does not ask for.
+        assert!(
+            PolicyYaml::violations(&artifact.build_output.policy_yaml, &artifact.spec).is_empty()
+        );
+        let provenance = artifact.provenance.unwrap();
+        assert!(provenance.agents.is_empty());
+        assert_eq!(provenance.request_source, Some(RequestSource::Operator));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires cargo-component installed
+    async fn fixture_compiles() {
+        let compiler = WasmCompiler::new().without_cache();
+        if let Err(e) = build(greet(None), &compiler).await {
+            panic!("greet fixture does not compile: {e}");
+        }
+    }
+}
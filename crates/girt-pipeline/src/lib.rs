@@ -3,10 +3,21 @@ pub mod cache;
 pub mod compiler;
 pub mod config;
 pub mod error;
+pub mod failures;
+pub mod handwritten;
 pub mod llm;
+pub mod llm_cache;
+pub mod llm_fallback;
 pub mod metrics;
 pub mod orchestrator;
 pub mod publish;
 pub mod queue;
+pub mod rebuild;
+pub mod scratch;
+pub mod spec_import;
+pub mod static_checks;
 pub mod stdlib;
+pub mod tool_sync;
 pub mod types;
+pub mod validation;
+pub mod worker;
@@ -1,10 +1,19 @@
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::pin::Pin;
 
+use girt_core::paths;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
 
 use crate::error::PipelineError;
 
+/// How long [`LlmClient::ping`] waits for a provider to answer.
+pub const PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Where [`AnthropicLlmClient`] sends requests unless given another base URL.
+pub const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+
 /// A message in an LLM conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmMessage {
@@ -18,12 +27,69 @@ pub struct LlmRequest {
     pub system_prompt: String,
     pub messages: Vec<LlmMessage>,
     pub max_tokens: u32,
+    /// Always ask the provider, even with `[llm] cache` on. Set for calls
+    /// whose answer should not be replayed, like code generation.
+    pub no_cache: bool,
+    /// Sampling temperature; the provider's default when unset.
+    pub temperature: Option<f32>,
+    /// Nucleus sampling cutoff; the provider's default when unset.
+    pub top_p: Option<f32>,
+}
+
+/// Sampling settings an agent sends with each of its requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct Sampling {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl Sampling {
+    pub const fn temperature(temperature: f32) -> Self {
+        Self {
+            temperature: Some(temperature),
+            top_p: None,
+        }
+    }
+
+    /// `self`, with unset values taken from `fallback`.
+    pub fn or(self, fallback: Sampling) -> Self {
+        Self {
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+        }
+    }
+}
+
+/// Add `request`'s sampling settings, where set, to a provider request body.
+fn insert_sampling(body: &mut serde_json::Value, request: &LlmRequest) {
+    for (key, value) in [
+        ("temperature", request.temperature),
+        ("top_p", request.top_p),
+    ] {
+        if let Some(value) = value {
+            body[key] = json_f32(value);
+        }
+    }
+}
+
+/// `value` as a JSON number with its shortest decimal form: widening 0.2f32
+/// to f64 directly would send 0.20000000298023224.
+fn json_f32(value: f32) -> serde_json::Value {
+    let widened = value.to_string().parse().unwrap_or(f64::from(value));
+    serde_json::Value::from(widened)
 }
 
 /// Response from an LLM.
 #[derive(Debug, Clone)]
 pub struct LlmResponse {
     pub content: String,
+    /// Tokens generated, as reported by the provider.
+    pub output_tokens: Option<u32>,
+    /// Answered from the LLM cache rather than the provider.
+    pub cached: bool,
+    /// Provider that answered, when the client chose among several (see
+    /// [`FallbackLlmClient`](crate::llm_fallback::FallbackLlmClient)).
+    pub provider: Option<String>,
 }
 
 /// Facade trait for LLM providers.
@@ -35,6 +101,65 @@ pub trait LlmClient: Send + Sync {
         &'a self,
         request: &'a LlmRequest,
     ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>>;
+
+    /// Provider name recorded in build provenance.
+    fn provider(&self) -> &str {
+        "unknown"
+    }
+
+    /// Model identifier recorded in build provenance.
+    fn model(&self) -> &str {
+        "unknown"
+    }
+
+    /// Check that the provider answers, without generating anything (for
+    /// `girt health`). Clients with no such call always succeed.
+    fn ping(&self) -> Pin<Box<dyn Future<Output = Result<(), PipelineError>> + Send + '_>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// `path` under `base_url`, with or without a trailing slash on `base_url`.
+fn endpoint(base_url: &str, path: &str) -> String {
+    format!("{}/{path}", base_url.trim_end_matches('/'))
+}
+
+/// Configured extra headers, refusing names or values HTTP does not allow.
+fn header_map(headers: &BTreeMap<String, String>) -> Result<HeaderMap, PipelineError> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let invalid = || PipelineError::ConfigError(format!("llm.extra_headers: bad {name}"));
+        let mut value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+        // Gateway credentials stay out of debug logs.
+        value.set_sensitive(true);
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+/// Send `builder`'s requests through `proxy`, rather than the proxy named
+/// by `HTTPS_PROXY`-style environment variables, which reqwest otherwise
+/// uses.
+fn via_proxy(
+    builder: reqwest::ClientBuilder,
+    proxy: &str,
+) -> Result<reqwest::ClientBuilder, PipelineError> {
+    let proxy = reqwest::Proxy::all(proxy)
+        .map_err(|e| PipelineError::ConfigError(format!("llm.http_proxy: {e}")))?;
+    Ok(builder.proxy(proxy))
+}
+
+/// Fail a ping that got an error status back.
+async fn ping_status(resp: reqwest::Response) -> Result<(), PipelineError> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = resp.text().await.unwrap_or_default();
+    Err(PipelineError::LlmTransport(format!(
+        "LLM API returned {status}: {body}"
+    )))
 }
 
 pub struct OpenAiCompatibleClient {
@@ -42,6 +167,7 @@ pub struct OpenAiCompatibleClient {
     base_url: String,
     model: String,
     api_key: Option<String>,
+    extra_headers: HeaderMap,
 }
 
 impl OpenAiCompatibleClient {
@@ -51,11 +177,56 @@ impl OpenAiCompatibleClient {
             base_url,
             model,
             api_key,
+            extra_headers: HeaderMap::new(),
         }
     }
+
+    /// Send `headers` with every request, e.g. a gateway's credentials.
+    pub fn with_extra_headers(
+        mut self,
+        headers: &BTreeMap<String, String>,
+    ) -> Result<Self, PipelineError> {
+        self.extra_headers = header_map(headers)?;
+        Ok(self)
+    }
+
+    /// Connect through the HTTP(S) proxy at `proxy`.
+    pub fn with_http_proxy(mut self, proxy: &str) -> Result<Self, PipelineError> {
+        self.http = via_proxy(reqwest::Client::builder(), proxy)?
+            .build()
+            .map_err(|e| PipelineError::ConfigError(format!("llm.http_proxy: {e}")))?;
+        Ok(self)
+    }
 }
 
 impl LlmClient for OpenAiCompatibleClient {
+    fn provider(&self) -> &str {
+        "openai-compatible"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Lists the provider's models.
+    fn ping(&self) -> Pin<Box<dyn Future<Output = Result<(), PipelineError>> + Send + '_>> {
+        Box::pin(async move {
+            let mut req = self
+                .http
+                .get(endpoint(&self.base_url, "models"))
+                .headers(self.extra_headers.clone())
+                .timeout(PING_TIMEOUT);
+            if let Some(key) = &self.api_key {
+                req = req.bearer_auth(key);
+            }
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| PipelineError::LlmTransport(e.to_string()))?;
+            ping_status(resp).await
+        })
+    }
+
     fn chat<'a>(
         &'a self,
         request: &'a LlmRequest,
@@ -73,46 +244,61 @@ impl LlmClient for OpenAiCompatibleClient {
                 }));
             }
 
-            let body = serde_json::json!({
+            let mut body = serde_json::json!({
                 "model": self.model,
                 "messages": messages,
                 "max_tokens": request.max_tokens,
             });
+            insert_sampling(&mut body, request);
 
-            let url = format!("{}/chat/completions", self.base_url);
-            let mut req = self.http.post(&url).json(&body);
+            let url = endpoint(&self.base_url, "chat/completions");
+            let mut req = self
+                .http
+                .post(&url)
+                .headers(self.extra_headers.clone())
+                .json(&body);
 
             if let Some(key) = &self.api_key {
                 req = req.bearer_auth(key);
             }
 
-            let resp = req.send().await.map_err(|e| {
-                PipelineError::LlmError(format!("HTTP request failed: {e}"))
-            })?;
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| PipelineError::LlmTransport(e.to_string()))?;
 
             if !resp.status().is_success() {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
-                return Err(PipelineError::LlmError(format!(
+                return Err(PipelineError::LlmTransport(format!(
                     "LLM API returned {status}: {body}"
                 )));
             }
 
-            let json: serde_json::Value = resp.json().await.map_err(|e| {
-                PipelineError::LlmError(format!("Failed to parse response: {e}"))
-            })?;
+            let json: serde_json::Value = resp
+                .json()
+                .await
+                .map_err(|e| PipelineError::LlmMalformedOutput(format!("Failed to parse response: {e}")))?;
 
             let content = json["choices"][0]["message"]["content"]
                 .as_str()
                 .ok_or_else(|| {
-                    PipelineError::LlmError(format!(
+                    PipelineError::LlmMalformedOutput(format!(
                         "No content in response: {}",
                         serde_json::to_string_pretty(&json).unwrap_or_default()
                     ))
                 })?
                 .to_string();
+            let output_tokens = json["usage"]["completion_tokens"]
+                .as_u64()
+                .map(|n| n as u32);
 
-            Ok(LlmResponse { content })
+            Ok(LlmResponse {
+                content,
+                output_tokens,
+                cached: false,
+                provider: None,
+            })
         })
     }
 }
@@ -126,7 +312,7 @@ fn openclaw_anthropic_token() -> Option<String> {
     let state_dir = std::env::var("OPENCLAW_STATE_DIR")
         .ok()
         .map(std::path::PathBuf::from)
-        .or_else(|| dirs::home_dir().map(|h| h.join(".openclaw")))?;
+        .or_else(|| paths::home_dir().ok().map(|h| h.join(".openclaw")))?;
 
     let profiles_path = state_dir
         .join("agents")
@@ -174,23 +360,87 @@ fn openclaw_anthropic_token() -> Option<String> {
 /// Calls `POST /v1/messages` with the Claude model specified in config.
 /// Reads the API key from the `ANTHROPIC_API_KEY` env var or the value
 /// passed at construction time.
+///
+/// Gateways that expose the Messages API (corporate proxies, LiteLLM,
+/// regional endpoints) are reached by giving their root as the base URL;
+/// `/v1/messages` is appended to it.
 pub struct AnthropicLlmClient {
     http: reqwest::Client,
+    base_url: String,
     model: String,
     api_key: String,
+    extra_headers: HeaderMap,
+}
+
+/// The HTTP client for Anthropic, through `proxy` when set.
+fn anthropic_http(proxy: Option<&str>) -> Result<reqwest::Client, PipelineError> {
+    let mut builder = reqwest::Client::builder()
+        // Anthropic closes idle connections; avoid reusing stale ones
+        .pool_idle_timeout(std::time::Duration::from_secs(25))
+        .pool_max_idle_per_host(2)
+        // Pipeline calls can be large (Engineer generates full source) — generous timeout
+        .timeout(std::time::Duration::from_secs(180));
+    if let Some(proxy) = proxy {
+        builder = via_proxy(builder, proxy)?;
+    }
+    builder
+        .build()
+        .map_err(|e| PipelineError::ConfigError(format!("llm.http_proxy: {e}")))
 }
 
 impl AnthropicLlmClient {
     pub fn new(model: String, api_key: String) -> Self {
-        let http = reqwest::Client::builder()
-            // Anthropic closes idle connections; avoid reusing stale ones
-            .pool_idle_timeout(std::time::Duration::from_secs(25))
-            .pool_max_idle_per_host(2)
-            // Pipeline calls can be large (Engineer generates full source) — generous timeout
-            .timeout(std::time::Duration::from_secs(180))
-            .build()
-            .expect("reqwest Client build should not fail");
-        Self { http, model, api_key }
+        Self {
+            http: anthropic_http(None).expect("reqwest Client build should not fail"),
+            base_url: ANTHROPIC_BASE_URL.into(),
+            model,
+            api_key,
+            extra_headers: HeaderMap::new(),
+        }
+    }
+
+    /// Send requests to `base_url` instead of [`ANTHROPIC_BASE_URL`].
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Send `headers` with every request, e.g. a gateway's credentials.
+    /// They replace same-named headers the client sets itself.
+    pub fn with_extra_headers(
+        mut self,
+        headers: &BTreeMap<String, String>,
+    ) -> Result<Self, PipelineError> {
+        self.extra_headers = header_map(headers)?;
+        Ok(self)
+    }
+
+    /// Connect through the HTTP(S) proxy at `proxy`.
+    pub fn with_http_proxy(mut self, proxy: &str) -> Result<Self, PipelineError> {
+        self.http = anthropic_http(Some(proxy))?;
+        Ok(self)
+    }
+
+    /// Add the API version and credentials to `req`.
+    fn authorized(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        // OAuth tokens (sk-ant-oat...) require:
+        //   Authorization: Bearer <token>
+        //   anthropic-beta: claude-code-20250219,oauth-2025-04-20
+        //
+        // Standard API keys (sk-ant-api...) use:
+        //   x-api-key: <key>
+        //
+        // Source: OpenClaw dist/pi-embedded-*.js — PI_AI_OAUTH_ANTHROPIC_BETAS
+        let is_oauth = self.api_key.starts_with("sk-ant-oat");
+
+        let req = req.header("anthropic-version", "2023-06-01");
+        let req = if is_oauth {
+            req.header("Authorization", format!("Bearer {}", self.api_key))
+                .header("anthropic-beta", "claude-code-20250219,oauth-2025-04-20")
+        } else {
+            req.header("x-api-key", &self.api_key)
+        };
+        req.headers(self.extra_headers.clone())
     }
 
     /// Resolve the Anthropic token using the following priority:
@@ -201,23 +451,56 @@ impl AnthropicLlmClient {
     ///
     /// This allows GIRT to reuse the same credentials OpenClaw is already
     /// configured with (setup-token or API key), with no separate configuration.
-    pub fn from_env_or(model: String, api_key_fallback: Option<String>) -> Result<Self, PipelineError> {
+    ///
+    /// Requests go to `base_url` ([`ANTHROPIC_BASE_URL`] when unset) with
+    /// `extra_headers` added.
+    pub fn from_env_or(
+        model: String,
+        api_key_fallback: Option<String>,
+        base_url: Option<String>,
+        extra_headers: &BTreeMap<String, String>,
+    ) -> Result<Self, PipelineError> {
         let api_key = std::env::var("ANTHROPIC_API_KEY")
             .ok()
             .or_else(openclaw_anthropic_token)
             .or(api_key_fallback)
             .ok_or_else(|| {
-                PipelineError::LlmError(
+                PipelineError::ConfigError(
                     "Anthropic credentials not found. \
                      Options: set ANTHROPIC_API_KEY, run `openclaw models auth setup-token --provider anthropic`, \
                      or set api_key in girt.toml".into(),
                 )
             })?;
-        Ok(Self::new(model, api_key))
+        let client = Self::new(model, api_key).with_extra_headers(extra_headers)?;
+        Ok(match base_url {
+            Some(base_url) => client.with_base_url(base_url),
+            None => client,
+        })
     }
 }
 
 impl LlmClient for AnthropicLlmClient {
+    fn provider(&self) -> &str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Lists the account's models.
+    fn ping(&self) -> Pin<Box<dyn Future<Output = Result<(), PipelineError>> + Send + '_>> {
+        Box::pin(async move {
+            let resp = self
+                .authorized(self.http.get(endpoint(&self.base_url, "v1/models")))
+                .timeout(PING_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| PipelineError::LlmTransport(e.to_string()))?;
+            ping_status(resp).await
+        })
+    }
+
     fn chat<'a>(
         &'a self,
         request: &'a LlmRequest,
@@ -229,46 +512,26 @@ impl LlmClient for AnthropicLlmClient {
                 .map(|m| serde_json::json!({"role": m.role, "content": m.content}))
                 .collect();
 
-            let body = serde_json::json!({
+            let mut body = serde_json::json!({
                 "model": self.model,
                 "max_tokens": request.max_tokens,
                 "system": request.system_prompt,
                 "messages": messages,
             });
+            insert_sampling(&mut body, request);
 
-            // OAuth tokens (sk-ant-oat...) require:
-            //   Authorization: Bearer <token>
-            //   anthropic-beta: claude-code-20250219,oauth-2025-04-20
-            //
-            // Standard API keys (sk-ant-api...) use:
-            //   x-api-key: <key>
-            //
-            // Source: OpenClaw dist/pi-embedded-*.js — PI_AI_OAUTH_ANTHROPIC_BETAS
-            let is_oauth = self.api_key.starts_with("sk-ant-oat");
-
-            let mut req = self
-                .http
-                .post("https://api.anthropic.com/v1/messages")
-                .header("anthropic-version", "2023-06-01")
-                .header("content-type", "application/json");
-
-            req = if is_oauth {
-                req.header("Authorization", format!("Bearer {}", self.api_key))
-                   .header("anthropic-beta", "claude-code-20250219,oauth-2025-04-20")
-            } else {
-                req.header("x-api-key", &self.api_key)
-            };
-
-            let resp = req
+            let resp = self
+                .authorized(self.http.post(endpoint(&self.base_url, "v1/messages")))
+                .header("content-type", "application/json")
                 .json(&body)
                 .send()
                 .await
-                .map_err(|e| PipelineError::LlmError(format!("HTTP request failed: {e}")))?;
+                .map_err(|e| PipelineError::LlmTransport(e.to_string()))?;
 
             if !resp.status().is_success() {
                 let status = resp.status();
                 let body = resp.text().await.unwrap_or_default();
-                return Err(PipelineError::LlmError(format!(
+                return Err(PipelineError::LlmTransport(format!(
                     "Anthropic API returned {status}: {body}"
                 )));
             }
@@ -276,60 +539,191 @@ impl LlmClient for AnthropicLlmClient {
             let json: serde_json::Value = resp
                 .json()
                 .await
-                .map_err(|e| PipelineError::LlmError(format!("Failed to parse response: {e}")))?;
+                .map_err(|e| PipelineError::LlmMalformedOutput(format!("Failed to parse response: {e}")))?;
 
             let content = json["content"]
                 .as_array()
                 .and_then(|arr| arr.first())
                 .and_then(|block| block["text"].as_str())
                 .ok_or_else(|| {
-                    PipelineError::LlmError(format!(
+                    PipelineError::LlmMalformedOutput(format!(
                         "Unexpected Anthropic response shape: {}",
                         serde_json::to_string_pretty(&json).unwrap_or_default()
                     ))
                 })?
                 .to_string();
+            let output_tokens = json["usage"]["output_tokens"].as_u64().map(|n| n as u32);
 
-            Ok(LlmResponse { content })
+            Ok(LlmResponse {
+                content,
+                output_tokens,
+                cached: false,
+                provider: None,
+            })
         })
     }
 }
 
+/// Picks the response a keyed [`StubLlmClient`] gives to a request.
+pub enum Matcher {
+    /// The system prompt contains this text.
+    SystemPrompt(String),
+    /// The last message has this role.
+    LastRole(String),
+    /// Any predicate over the request.
+    Custom(Box<dyn Fn(&LlmRequest) -> bool + Send + Sync>),
+}
+
+impl Matcher {
+    pub fn system_prompt(text: impl Into<String>) -> Self {
+        Self::SystemPrompt(text.into())
+    }
+
+    pub fn last_role(role: impl Into<String>) -> Self {
+        Self::LastRole(role.into())
+    }
+
+    pub fn custom(predicate: impl Fn(&LlmRequest) -> bool + Send + Sync + 'static) -> Self {
+        Self::Custom(Box::new(predicate))
+    }
+
+    fn matches(&self, request: &LlmRequest) -> bool {
+        match self {
+            Self::SystemPrompt(text) => request.system_prompt.contains(text.as_str()),
+            Self::LastRole(role) => request.messages.last().is_some_and(|m| &m.role == role),
+            Self::Custom(predicate) => predicate(request),
+        }
+    }
+}
+
+impl std::fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SystemPrompt(text) => f.debug_tuple("SystemPrompt").field(text).finish(),
+            Self::LastRole(role) => f.debug_tuple("LastRole").field(role).finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+enum StubResponses {
+    /// Returned in call order, cycling.
+    Sequential(Vec<String>),
+    /// The response of the first matching [`Matcher`].
+    Keyed(Vec<(Matcher, String)>),
+}
+
 /// Stub LLM client that returns deterministic responses for testing.
+///
+/// The sequential mode ([`new`](Self::new)) answers in call order, so any
+/// change to the number or order of pipeline calls shifts every later
+/// response. The keyed mode ([`with_matchers`](Self::with_matchers)) answers
+/// by what is asked instead. Sampling settings are ignored, but kept with
+/// each request in [`requests`](Self::requests).
 pub struct StubLlmClient {
-    responses: Vec<String>,
+    responses: StubResponses,
     call_count: std::sync::atomic::AtomicUsize,
+    requests: std::sync::Mutex<Vec<LlmRequest>>,
 }
 
 impl StubLlmClient {
     pub fn new(responses: Vec<String>) -> Self {
+        Self::from_responses(StubResponses::Sequential(responses))
+    }
+
+    /// Create a stub that always returns the given response.
+    pub fn constant(response: &str) -> Self {
+        Self::new(vec![response.to_string()])
+    }
+
+    /// Create a stub that answers each request with the response of the
+    /// first matcher it satisfies. A request no matcher accepts panics,
+    /// naming the request.
+    pub fn with_matchers(matchers: Vec<(Matcher, String)>) -> Self {
+        Self::from_responses(StubResponses::Keyed(matchers))
+    }
+
+    fn from_responses(responses: StubResponses) -> Self {
         Self {
             responses,
             call_count: std::sync::atomic::AtomicUsize::new(0),
+            requests: std::sync::Mutex::new(Vec::new()),
         }
     }
 
-    /// Create a stub that always returns the given response.
-    pub fn constant(response: &str) -> Self {
-        Self::new(vec![response.to_string()])
+    /// Every request received so far, in call order.
+    pub fn requests(&self) -> Vec<LlmRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn respond(&self, request: &LlmRequest) -> String {
+        match &self.responses {
+            StubResponses::Sequential(responses) => {
+                let idx = self
+                    .call_count
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                if responses.is_empty() {
+                    "stub response".to_string()
+                } else {
+                    responses[idx % responses.len()].clone()
+                }
+            }
+            StubResponses::Keyed(matchers) => {
+                if let Some((_, response)) = matchers.iter().find(|(m, _)| m.matches(request)) {
+                    return response.clone();
+                }
+                let matchers: Vec<&Matcher> = matchers.iter().map(|(m, _)| m).collect();
+                let last = request
+                    .messages
+                    .last()
+                    .map(|m| format!("{}: {}", m.role, preview(&m.content)))
+                    .unwrap_or_else(|| "(none)".into());
+                panic!(
+                    "StubLlmClient: no matcher accepts this request\n  \
+                     system prompt: {}\n  last message: {last}\n  matchers: {matchers:?}",
+                    preview(&request.system_prompt)
+                );
+            }
+        }
+    }
+}
+
+/// First line of `text`, cut to 120 chars, for panic messages.
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(120) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
     }
 }
 
 impl LlmClient for StubLlmClient {
+    fn provider(&self) -> &str {
+        "stub"
+    }
+
+    fn model(&self) -> &str {
+        "stub"
+    }
+
     fn chat<'a>(
         &'a self,
-        _request: &'a LlmRequest,
+        request: &'a LlmRequest,
     ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
         Box::pin(async move {
-            let idx = self
-                .call_count
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            let response = if self.responses.is_empty() {
-                "stub response".to_string()
-            } else {
-                self.responses[idx % self.responses.len()].clone()
-            };
-            Ok(LlmResponse { content: response })
+            self.requests
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(request.clone());
+            Ok(LlmResponse {
+                content: self.respond(request),
+                output_tokens: None,
+                cached: false,
+                provider: None,
+            })
         })
     }
 }
@@ -345,6 +739,9 @@ mod tests {
             system_prompt: "test".into(),
             messages: vec![],
             max_tokens: 100,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
         };
 
         let response = client.chat(&request).await.unwrap();
@@ -365,6 +762,9 @@ mod tests {
                 content: "Hello".into(),
             }],
             max_tokens: 100,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
         };
         let result = client.chat(&request).await;
         assert!(result.is_err());
@@ -377,6 +777,9 @@ mod tests {
             system_prompt: "test".into(),
             messages: vec![],
             max_tokens: 100,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
         };
 
         let r1 = client.chat(&request).await.unwrap();
@@ -389,6 +792,266 @@ mod tests {
         assert_eq!(r3.content, "first"); // cycles back
     }
 
+    fn request(system_prompt: &str, role: &str) -> LlmRequest {
+        LlmRequest {
+            system_prompt: system_prompt.into(),
+            messages: vec![LlmMessage {
+                role: role.into(),
+                content: "hi".into(),
+            }],
+            max_tokens: 100,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn keyed_stub_answers_with_first_matching_response() {
+        let client = StubLlmClient::with_matchers(vec![
+            (Matcher::system_prompt("QA"), "qa".into()),
+            (Matcher::custom(|r| r.max_tokens > 1000), "big".into()),
+            (Matcher::last_role("user"), "user".into()),
+        ]);
+
+        let qa = request("You are a QA Automation Engineer.", "user");
+        assert_eq!(client.chat(&qa).await.unwrap().content, "qa");
+        let other = request("You are someone else.", "user");
+        assert_eq!(client.chat(&other).await.unwrap().content, "user");
+        let big = LlmRequest {
+            max_tokens: 4000,
+            ..request("Anything", "assistant")
+        };
+        assert_eq!(client.chat(&big).await.unwrap().content, "big");
+
+        let prompts: Vec<_> = client
+            .requests()
+            .into_iter()
+            .map(|r| r.system_prompt)
+            .collect();
+        assert_eq!(
+            prompts,
+            [
+                "You are a QA Automation Engineer.",
+                "You are someone else.",
+                "Anything"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "no matcher accepts this request")]
+    async fn keyed_stub_panics_on_unmatched_request() {
+        let client =
+            StubLlmClient::with_matchers(vec![(Matcher::system_prompt("QA"), "qa".into())]);
+        let _ = client.chat(&request("You are an Architect.", "user")).await;
+    }
+
+    /// Answer one HTTP request on a local port with `response`. Returns the
+    /// base URL and the JSON body the client sent.
+    async fn mock_server(
+        response: serde_json::Value,
+    ) -> (String, tokio::task::JoinHandle<serde_json::Value>) {
+        let (url, exchange) = mock_exchange(response).await;
+        (url, tokio::spawn(async { exchange.await.unwrap().1 }))
+    }
+
+    /// Like [`mock_server`], also returning the request line and headers
+    /// the client sent, lowercased.
+    async fn mock_exchange(
+        response: serde_json::Value,
+    ) -> (String, tokio::task::JoinHandle<(String, serde_json::Value)>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let (head, body) = loop {
+                let mut chunk = [0u8; 4096];
+                let n = stream.read(&mut chunk).await.unwrap();
+                assert!(n > 0, "connection closed before the body arrived");
+                received.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&received);
+                let Some(end) = text.find("\r\n\r\n") else {
+                    continue;
+                };
+                let length: usize = text[..end]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse().unwrap())
+                    })
+                    .unwrap_or(0);
+                if received.len() >= end + 4 + length {
+                    let head = text[..end].to_ascii_lowercase();
+                    break (head, received[end + 4..end + 4 + length].to_vec());
+                }
+            };
+            let response = response.to_string();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{response}",
+                response.len()
+            );
+            stream.write_all(reply.as_bytes()).await.unwrap();
+            (head, serde_json::from_slice(&body).unwrap())
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn sampling_settings_are_sent_when_set() {
+        let sampled = LlmRequest {
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+            ..request("You are helpful.", "user")
+        };
+
+        let (url, body) = mock_server(serde_json::json!({
+            "choices": [{"message": {"content": "ok"}}],
+        }))
+        .await;
+        let client = OpenAiCompatibleClient::new(format!("{url}/v1"), "m".into(), None);
+        client.chat(&sampled).await.unwrap();
+        let body = body.await.unwrap();
+        assert_eq!(body["temperature"], serde_json::json!(0.2));
+        assert_eq!(body["top_p"], serde_json::json!(0.9));
+
+        let (url, body) = mock_server(serde_json::json!({
+            "content": [{"type": "text", "text": "ok"}],
+        }))
+        .await;
+        let client =
+            AnthropicLlmClient::new("m".into(), "sk-ant-api-test".into()).with_base_url(url);
+        client.chat(&sampled).await.unwrap();
+        let body = body.await.unwrap();
+        assert_eq!(body["temperature"], serde_json::json!(0.2));
+        assert_eq!(body["top_p"], serde_json::json!(0.9));
+
+        // Unset values are left to the provider.
+        let (url, body) = mock_server(serde_json::json!({
+            "content": [{"type": "text", "text": "ok"}],
+        }))
+        .await;
+        let client =
+            AnthropicLlmClient::new("m".into(), "sk-ant-api-test".into()).with_base_url(url);
+        client
+            .chat(&request("You are helpful.", "user"))
+            .await
+            .unwrap();
+        let body = body.await.unwrap();
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("top_p").is_none());
+    }
+
+    fn anthropic_reply() -> serde_json::Value {
+        serde_json::json!({ "content": [{"type": "text", "text": "ok"}] })
+    }
+
+    #[tokio::test]
+    async fn anthropic_base_urls_join_with_or_without_a_trailing_slash() {
+        for suffix in ["", "/", "/gateway", "/gateway/"] {
+            let (url, exchange) = mock_exchange(anthropic_reply()).await;
+            let client = AnthropicLlmClient::new("m".into(), "sk-ant-api-test".into())
+                .with_base_url(format!("{url}{suffix}"));
+            client.chat(&request("System", "user")).await.unwrap();
+            let (head, _) = exchange.await.unwrap();
+            let path = suffix.trim_end_matches('/');
+            assert!(
+                head.starts_with(&format!("post {path}/v1/messages http/1.1")),
+                "{head}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn extra_headers_are_sent_and_replace_the_clients_own() {
+        let headers = BTreeMap::from([
+            ("X-Gateway-Token".to_string(), "gw-secret".to_string()),
+            ("anthropic-version".to_string(), "2024-01-01".to_string()),
+        ]);
+        let (url, exchange) = mock_exchange(anthropic_reply()).await;
+        let client = AnthropicLlmClient::from_env_or(
+            "m".into(),
+            Some("sk-ant-api-test".into()),
+            Some(url),
+            &headers,
+        )
+        .unwrap();
+        client.chat(&request("System", "user")).await.unwrap();
+        let (head, _) = exchange.await.unwrap();
+        assert!(head.contains("\r\nx-gateway-token: gw-secret"), "{head}");
+        assert!(head.contains("\r\nanthropic-version: 2024-01-01"), "{head}");
+        assert!(!head.contains("2023-06-01"), "{head}");
+
+        let (url, exchange) = mock_exchange(serde_json::json!({
+            "choices": [{"message": {"content": "ok"}}],
+        }))
+        .await;
+        let client = OpenAiCompatibleClient::new(format!("{url}/v1/"), "m".into(), None)
+            .with_extra_headers(&headers)
+            .unwrap();
+        client.chat(&request("System", "user")).await.unwrap();
+        let (head, _) = exchange.await.unwrap();
+        assert!(head.starts_with("post /v1/chat/completions "), "{head}");
+        assert!(head.contains("\r\nx-gateway-token: gw-secret"), "{head}");
+
+        let bad = BTreeMap::from([("bad header".to_string(), "x".to_string())]);
+        let err = AnthropicLlmClient::new("m".into(), "k".into())
+            .with_extra_headers(&bad)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "config_error");
+    }
+
+    #[tokio::test]
+    async fn requests_go_through_the_configured_proxy() {
+        let (proxy, exchange) = mock_exchange(anthropic_reply()).await;
+        let client = AnthropicLlmClient::new("m".into(), "sk-ant-api-test".into())
+            .with_base_url("http://anthropic.example.invalid")
+            .with_http_proxy(&proxy)
+            .unwrap();
+        client.chat(&request("System", "user")).await.unwrap();
+        let (head, _) = exchange.await.unwrap();
+        // A forward proxy is sent the absolute URL.
+        assert!(
+            head.starts_with("post http://anthropic.example.invalid/v1/messages "),
+            "{head}"
+        );
+    }
+
+    #[tokio::test]
+    async fn ping_fails_when_the_provider_is_unreachable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let client = OpenAiCompatibleClient::new(url.clone(), "m".into(), None);
+        assert_eq!(client.ping().await.unwrap_err().code(), "llm_transport");
+        let client =
+            AnthropicLlmClient::new("m".into(), "sk-ant-api-test".into()).with_base_url(url);
+        assert_eq!(client.ping().await.unwrap_err().code(), "llm_transport");
+        assert!(StubLlmClient::constant("ok").ping().await.is_ok());
+    }
+
+    #[test]
+    fn sampling_falls_back_per_value() {
+        let configured = Sampling {
+            temperature: None,
+            top_p: Some(0.5),
+        };
+        assert_eq!(
+            configured.or(Sampling::temperature(0.8)),
+            Sampling {
+                temperature: Some(0.8),
+                top_p: Some(0.5),
+            }
+        );
+    }
+
     #[tokio::test]
     #[ignore] // Requires vLLM running on localhost:8000
     async fn openai_client_calls_real_vllm() {
@@ -404,6 +1067,9 @@ mod tests {
                 content: "PING".into(),
             }],
             max_tokens: 10,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
         };
         let response = client.chat(&request).await.unwrap();
         assert!(!response.content.is_empty());
@@ -0,0 +1,270 @@
+//! Content-addressed cache of LLM responses (`[llm] cache = true`).
+//!
+//! Re-running the pipeline for a spec whose build failed after the
+//! Architect would otherwise pay again for an identical Architect call.
+//! [`CachingLlmClient`] answers a request it has seen before from disk,
+//! keyed by the SHA-256 of its system prompt, messages, model, and token
+//! limit:
+//!
+//! ```text
+//! ~/.girt/llm-cache/
+//!   <sha256>.json    { "content": "..." }
+//! ```
+//!
+//! Requests with [`LlmRequest::no_cache`] set always go to the provider, so
+//! a bad generation is never replayed. Entries expire after a TTL, and the
+//! oldest are evicted once the directory outgrows its size cap.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use girt_core::paths::{self, PathError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::PipelineError;
+use crate::llm::{LlmClient, LlmRequest, LlmResponse};
+
+/// Total size of cached responses before the oldest are evicted.
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// How long a cached response is reused.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// What is stored per request.
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    content: String,
+}
+
+/// Wraps an [`LlmClient`], answering repeated requests from disk.
+pub struct CachingLlmClient {
+    inner: Arc<dyn LlmClient>,
+    dir: PathBuf,
+    max_bytes: u64,
+    ttl: Duration,
+}
+
+impl CachingLlmClient {
+    /// Default location: `llm-cache/` in the data directory (`~/.girt/llm-cache`).
+    pub fn default_dir() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("llm-cache"))
+    }
+
+    pub fn new(inner: Arc<dyn LlmClient>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// Evict the oldest responses once the cache holds more than `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Reuse a response for at most `ttl`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn entry_path(&self, request: &LlmRequest) -> PathBuf {
+        let key = serde_json::json!({
+            "system_prompt": request.system_prompt,
+            "messages": request.messages,
+            "model": self.inner.model(),
+            "max_tokens": request.max_tokens,
+            "temperature": request.temperature,
+            "top_p": request.top_p,
+        });
+        let digest = Sha256::digest(key.to_string().as_bytes());
+        self.dir.join(format!("{}.json", hex::encode(digest)))
+    }
+
+    /// The cached response for `request`, unless missing or expired.
+    async fn lookup(&self, request: &LlmRequest) -> Option<CachedResponse> {
+        let path = self.entry_path(request);
+        let modified = tokio::fs::metadata(&path).await.ok()?.modified().ok()?;
+        if modified.elapsed().unwrap_or_default() > self.ttl {
+            return None;
+        }
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    async fn store(&self, request: &LlmRequest, content: &str) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.entry_path(request);
+        let tmp = path.with_extension("json.tmp");
+        let entry = CachedResponse {
+            content: content.to_string(),
+        };
+        tokio::fs::write(&tmp, serde_json::to_vec(&entry)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        self.evict().await
+    }
+
+    /// Remove expired entries, then the oldest until under the size cap.
+    async fn evict(&self) -> std::io::Result<()> {
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+        let mut dir = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let metadata = entry.metadata().await?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+        entries.sort();
+
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (modified, len, path) in entries {
+            let expired = modified.elapsed().unwrap_or_default() > self.ttl;
+            if !expired && total <= self.max_bytes {
+                break;
+            }
+            tokio::fs::remove_file(&path).await?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+impl LlmClient for CachingLlmClient {
+    fn chat<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+        Box::pin(async move {
+            if request.no_cache {
+                return self.inner.chat(request).await;
+            }
+            if let Some(hit) = self.lookup(request).await {
+                tracing::debug!("LLM cache hit");
+                return Ok(LlmResponse {
+                    content: hit.content,
+                    output_tokens: Some(0),
+                    cached: true,
+                    provider: None,
+                });
+            }
+            let response = self.inner.chat(request).await?;
+            if let Err(e) = self.store(request, &response.content).await {
+                tracing::warn!(dir = %self.dir.display(), error = %e, "Failed to cache LLM response");
+            }
+            Ok(response)
+        })
+    }
+
+    fn provider(&self) -> &str {
+        self.inner.provider()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn ping(&self) -> Pin<Box<dyn Future<Output = Result<(), PipelineError>> + Send + '_>> {
+        self.inner.ping()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmMessage, StubLlmClient};
+    use tempfile::TempDir;
+
+    fn request(prompt: &str) -> LlmRequest {
+        LlmRequest {
+            system_prompt: "You are the Architect.".into(),
+            messages: vec![LlmMessage {
+                role: "user".into(),
+                content: prompt.into(),
+            }],
+            max_tokens: 100,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
+        }
+    }
+
+    fn cached_client(tmp: &TempDir) -> (Arc<StubLlmClient>, CachingLlmClient) {
+        let stub = Arc::new(StubLlmClient::new(vec!["first".into(), "second".into()]));
+        let client = CachingLlmClient::new(stub.clone(), tmp.path().join("llm-cache"));
+        (stub, client)
+    }
+
+    #[tokio::test]
+    async fn identical_requests_hit_the_cache() {
+        let tmp = TempDir::new().unwrap();
+        let (stub, client) = cached_client(&tmp);
+
+        let miss = client.chat(&request("spec")).await.unwrap();
+        let hit = client.chat(&request("spec")).await.unwrap();
+        assert_eq!(miss.content, "first");
+        assert!(!miss.cached);
+        assert_eq!(hit.content, "first");
+        assert!(hit.cached);
+        assert_eq!(hit.output_tokens, Some(0));
+
+        let other = client.chat(&request("another spec")).await.unwrap();
+        assert_eq!(other.content, "second");
+        assert_eq!(stub.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn no_cache_requests_bypass_it() {
+        let tmp = TempDir::new().unwrap();
+        let (stub, client) = cached_client(&tmp);
+        let mut engineer = request("spec");
+        engineer.no_cache = true;
+
+        client.chat(&engineer).await.unwrap();
+        let again = client.chat(&engineer).await.unwrap();
+        assert_eq!(again.content, "second");
+        assert!(!again.cached);
+        assert_eq!(stub.requests().len(), 2);
+        assert!(!tmp.path().join("llm-cache").exists());
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_not_reused() {
+        let tmp = TempDir::new().unwrap();
+        let (stub, client) = cached_client(&tmp);
+        let client = client.with_ttl(Duration::ZERO);
+
+        client.chat(&request("spec")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let again = client.chat(&request("spec")).await.unwrap();
+        assert!(!again.cached);
+        assert_eq!(stub.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn oldest_entries_are_evicted_over_the_cap() {
+        let tmp = TempDir::new().unwrap();
+        let stub = Arc::new(StubLlmClient::constant(&"x".repeat(100)));
+        let dir = tmp.path().join("llm-cache");
+        // Room for two entries, not three.
+        let client = CachingLlmClient::new(stub.clone(), &dir).with_max_bytes(250);
+
+        for prompt in ["a", "b", "c"] {
+            client.chat(&request(prompt)).await.unwrap();
+            // Distinct mtimes, so eviction order is well defined.
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        assert!(!client.chat(&request("a")).await.unwrap().cached);
+        assert!(client.chat(&request("c")).await.unwrap().cached);
+    }
+}
@@ -0,0 +1,220 @@
+//! Ordered LLM provider chain (`[[llm.fallback]]`).
+//!
+//! When the primary provider is down mid-build, the pipeline would
+//! otherwise fail even with another provider (say, a local Ollama) able to
+//! finish the job. [`FallbackLlmClient`] sends each call to the primary
+//! and, when that fails to reach its provider ([`PipelineError::LlmTransport`]),
+//! to the next provider in the chain, and so on until one answers. A
+//! provider that answers with something unusable (malformed output) is not
+//! skipped: another model would not make the request any better formed.
+//!
+//! Each response names the provider that served it, so builds can record
+//! where their tokens went.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::error::PipelineError;
+use crate::llm::{LlmClient, LlmRequest, LlmResponse};
+
+/// Wraps a primary [`LlmClient`] and the clients to fail over to.
+pub struct FallbackLlmClient {
+    /// The primary first, then the fallbacks in order.
+    chain: Vec<Arc<dyn LlmClient>>,
+}
+
+impl FallbackLlmClient {
+    pub fn new(primary: Arc<dyn LlmClient>, fallbacks: Vec<Arc<dyn LlmClient>>) -> Self {
+        let mut chain = vec![primary];
+        chain.extend(fallbacks);
+        Self { chain }
+    }
+}
+
+impl LlmClient for FallbackLlmClient {
+    fn chat<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut last_error = None;
+            for (position, client) in self.chain.iter().enumerate() {
+                let provider = client.provider();
+                match client.chat(request).await {
+                    Ok(mut response) => {
+                        if position == 0 {
+                            tracing::debug!(provider, model = client.model(), "LLM call served");
+                        } else {
+                            tracing::warn!(
+                                provider,
+                                model = client.model(),
+                                primary = self.provider(),
+                                "LLM call served by fallback provider"
+                            );
+                        }
+                        response
+                            .provider
+                            .get_or_insert_with(|| provider.to_string());
+                        return Ok(response);
+                    }
+                    Err(PipelineError::LlmTransport(message)) => {
+                        tracing::warn!(
+                            provider,
+                            model = client.model(),
+                            error = %message,
+                            remaining = self.chain.len() - position - 1,
+                            "LLM provider unavailable; failing over"
+                        );
+                        last_error = Some(PipelineError::LlmTransport(message));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(last_error.unwrap_or_else(|| {
+                PipelineError::LlmTransport("no LLM provider configured".into())
+            }))
+        })
+    }
+
+    fn provider(&self) -> &str {
+        self.chain[0].provider()
+    }
+
+    fn model(&self) -> &str {
+        self.chain[0].model()
+    }
+
+    /// Succeeds when any provider in the chain answers.
+    fn ping(&self) -> Pin<Box<dyn Future<Output = Result<(), PipelineError>> + Send + '_>> {
+        Box::pin(async move {
+            let mut last_error = None;
+            for client in &self.chain {
+                match client.ping().await {
+                    Ok(()) => return Ok(()),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            Err(last_error.unwrap_or_else(|| {
+                PipelineError::LlmTransport("no LLM provider configured".into())
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmMessage, StubLlmClient};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A provider that always fails with `error`, counting its calls.
+    struct FailingLlm {
+        name: &'static str,
+        error: fn() -> PipelineError,
+        calls: AtomicUsize,
+    }
+
+    impl FailingLlm {
+        fn new(name: &'static str, error: fn() -> PipelineError) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                error,
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    impl LlmClient for FailingLlm {
+        fn chat<'a>(
+            &'a self,
+            _request: &'a LlmRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async move { Err((self.error)()) })
+        }
+
+        fn provider(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn outage() -> PipelineError {
+        PipelineError::LlmTransport("HTTP 529: overloaded".into())
+    }
+
+    fn request() -> LlmRequest {
+        LlmRequest {
+            system_prompt: "You are the Architect.".into(),
+            messages: vec![LlmMessage {
+                role: "user".into(),
+                content: "Refine this spec".into(),
+            }],
+            max_tokens: 100,
+            no_cache: false,
+            temperature: None,
+            top_p: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn transport_errors_fail_over_in_order() {
+        let primary = FailingLlm::new("anthropic", outage);
+        let second = FailingLlm::new("openai-compatible", outage);
+        let third = Arc::new(StubLlmClient::constant("refined"));
+        let client = FallbackLlmClient::new(primary.clone(), vec![second.clone(), third.clone()]);
+
+        let response = client.chat(&request()).await.unwrap();
+        assert_eq!(response.content, "refined");
+        assert_eq!(response.provider.as_deref(), Some("stub"));
+        assert_eq!(primary.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(second.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(third.requests().len(), 1);
+        // Recorded under the primary's name in provenance.
+        assert_eq!(client.provider(), "anthropic");
+
+        // Every call starts from the primary again.
+        client.chat(&request()).await.unwrap();
+        assert_eq!(primary.calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn primary_answers_are_annotated_too() {
+        let fallback = FailingLlm::new("openai-compatible", outage);
+        let client = FallbackLlmClient::new(
+            Arc::new(StubLlmClient::constant("ok")),
+            vec![fallback.clone()],
+        );
+        let response = client.chat(&request()).await.unwrap();
+        assert_eq!(response.provider.as_deref(), Some("stub"));
+        assert_eq!(fallback.calls.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn malformed_output_does_not_fail_over() {
+        let primary = FailingLlm::new("anthropic", || {
+            PipelineError::LlmMalformedOutput("No content in response".into())
+        });
+        let fallback = Arc::new(StubLlmClient::constant("ok"));
+        let client = FallbackLlmClient::new(primary, vec![fallback.clone()]);
+
+        let err = client.chat(&request()).await.unwrap_err();
+        assert_eq!(err.code(), "llm_malformed_output");
+        assert!(fallback.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_last_transport_error_is_returned_when_all_fail() {
+        let client = FallbackLlmClient::new(
+            FailingLlm::new("anthropic", outage),
+            vec![FailingLlm::new("openai-compatible", || {
+                PipelineError::LlmTransport("connection refused".into())
+            })],
+        );
+        let err = client.chat(&request()).await.unwrap_err();
+        assert!(
+            matches!(&err, PipelineError::LlmTransport(m) if m == "connection refused"),
+            "{err}"
+        );
+    }
+}
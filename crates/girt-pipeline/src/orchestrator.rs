@@ -1,12 +1,33 @@
-use crate::agent::architect::ArchitectAgent;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
+
+use girt_core::host::HostCapabilities;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::Instrument;
+
+use crate::agent::architect::{ARCHITECT_SYSTEM_PROMPT, ArchitectAgent};
 use crate::agent::engineer::EngineerAgent;
-use crate::agent::qa::QaAgent;
-use crate::agent::red_team::RedTeamAgent;
+use crate::agent::examples;
+use crate::agent::qa::{QA_SYSTEM_PROMPT, QaAgent};
+use crate::agent::red_team::{RED_TEAM_SYSTEM_PROMPT, RedTeamAgent};
+use crate::config::SamplingConfig;
 use crate::error::PipelineError;
-use crate::llm::LlmClient;
-use crate::types::{BugTicket, BuildArtifact, CapabilityRequest, RefinedSpec, SpecAction};
+use crate::failures::{FailureReport, FailureStore};
+use crate::llm::{LlmClient, LlmRequest, LlmResponse};
+use crate::static_checks::StaticChecks;
+use crate::types::{
+    AgentProvenance, BlockingPolicy, BugTicket, BuildArtifact, BuildOutput, CapabilityRequest,
+    CodingStandards, FixHistory, IterationSnapshot, PipelineOverrides, PipelineSnapshot,
+    PolicyYaml, Provenance, RefinedSpec, ResourceTier, SpecAction, StageTimings, TargetLanguage,
+    TicketHistory, WitVersion,
+};
 
-/// Maximum number of build-fix iterations before circuit breaker triggers.
+/// Default number of build-fix iterations before circuit breaker triggers.
 const MAX_ITERATIONS: u32 = 3;
 
 /// Result of a single pipeline run.
@@ -19,8 +40,37 @@ pub enum PipelineOutcome {
         target: String,
         features: Vec<String>,
     },
-    /// Pipeline failed after exhausting retries.
-    Failed(PipelineError),
+    /// Pipeline failed after exhausting retries, with what it had produced
+    /// up to then.
+    Failed(PipelineError, Box<FailureReport>),
+}
+
+/// What a build of a request would start from, without building it: the
+/// Architect's refined spec and plan, and the resource tier and policy the
+/// tool would get (see [`Orchestrator::dry_run`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunReport {
+    pub refined_spec: RefinedSpec,
+    /// The Architect's design notes; `None` when it gave none.
+    pub plan: Option<String>,
+    /// The requested tier, or the one inferred from the refined constraints.
+    pub resource_tier: ResourceTier,
+    pub policy: PolicyYaml,
+    /// Tokens the Architect generated. Providers that report no usage are
+    /// estimated at 4 bytes a token.
+    pub tokens_used: u32,
+    pub timings: StageTimings,
+}
+
+/// How a build got its spec and first Engineer output, for its provenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    /// The Architect refined the request.
+    Architect,
+    /// An already-refined spec was built as given.
+    Spec,
+    /// A published build was extended.
+    Extension,
 }
 
 /// Orchestrates the Architect -> Engineer -> QA + Red Team pipeline.
@@ -33,34 +83,164 @@ pub enum PipelineOutcome {
 /// 5. Return the final artifact or failure
 pub struct Orchestrator<'a> {
     llm: &'a dyn LlmClient,
-    /// Optional coding standards to inject into the Engineer's system prompt.
-    coding_standards: Option<String>,
+    /// Coding standards to inject into the Engineer's system prompt, by
+    /// target language.
+    coding_standards: CodingStandards,
+    /// Why earlier requests with the same name were denied, for the Architect.
+    previous_denials: Vec<String>,
+    max_iterations: u32,
+    target_language: TargetLanguage,
+    resource_tier: Option<ResourceTier>,
+    /// Tokens the whole run may generate; unlimited when unset.
+    token_budget: Option<u32>,
+    /// `girt:tool` world the Engineer builds against.
+    wit_version: WitVersion,
+    /// Sampling settings per agent, over the agents' defaults.
+    sampling: SamplingConfig,
+    /// Checks each Engineer output must pass before QA sees it.
+    static_checks: StaticChecks,
+    /// Which tickets keep a build from passing.
+    blocking: BlockingPolicy,
+    /// Where the iteration sources of builds that pass are kept.
+    failures: Option<&'a FailureStore>,
+    /// Imports the runtime links, offered to the Engineer.
+    host_capabilities: HostCapabilities,
 }
 
 impl<'a> Orchestrator<'a> {
     pub fn new(llm: &'a dyn LlmClient) -> Self {
         Self {
             llm,
-            coding_standards: None,
+            coding_standards: CodingStandards::default(),
+            previous_denials: Vec::new(),
+            max_iterations: MAX_ITERATIONS,
+            target_language: TargetLanguage::default(),
+            resource_tier: None,
+            token_budget: None,
+            wit_version: WitVersion::default(),
+            sampling: SamplingConfig::default(),
+            static_checks: StaticChecks::default(),
+            blocking: BlockingPolicy::default(),
+            failures: None,
+            host_capabilities: HostCapabilities::default(),
         }
     }
 
-    /// Attach coding standards to be passed to the Engineer agent.
-    pub fn with_standards(mut self, standards: Option<String>) -> Self {
+    /// Attach coding standards to be passed to the Engineer agent. It is
+    /// shown those for the target language.
+    pub fn with_standards(mut self, standards: CodingStandards) -> Self {
         self.coding_standards = standards;
         self
     }
 
+    /// Pass the reasons earlier versions of the request were denied to the
+    /// Architect.
+    pub fn with_previous_denials(mut self, reasons: Vec<String>) -> Self {
+        self.previous_denials = reasons;
+        self
+    }
+
+    /// Have the Engineer build against `version` of the girt-tool world.
+    pub fn with_wit_version(mut self, version: WitVersion) -> Self {
+        self.wit_version = version;
+        self
+    }
+
+    /// Apply `[pipeline.sampling]` to the agents.
+    pub fn with_sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Run `checks` on each Engineer output (`[pipeline.static_checks]`).
+    pub fn with_static_checks(mut self, checks: StaticChecks) -> Self {
+        self.static_checks = checks;
+        self
+    }
+
+    /// Let builds pass with tickets below `policy`'s thresholds
+    /// (`[pipeline.blocking]`).
+    pub fn with_blocking_policy(mut self, policy: BlockingPolicy) -> Self {
+        self.blocking = policy;
+        self
+    }
+
+    /// Keep the source of each iteration of builds that pass in `store`,
+    /// if it keeps them (see [`FailureStore::with_iteration_sources`]).
+    /// Failed builds are left to the caller, which writes their bundle.
+    pub fn with_failure_store(mut self, store: &'a FailureStore) -> Self {
+        self.failures = Some(store);
+        self
+    }
+
+    /// Offer the Engineer the imports in `capabilities`, as described by
+    /// `girt_runtime::host_capabilities`.
+    pub fn with_host_capabilities(mut self, capabilities: HostCapabilities) -> Self {
+        self.host_capabilities = capabilities;
+        self
+    }
+
+    /// Apply a request's pipeline overrides. Callers cap them first (see
+    /// [`PipelineOverrides::capped`]).
+    pub fn with_overrides(mut self, overrides: &PipelineOverrides) -> Self {
+        if let Some(n) = overrides.max_iterations {
+            self.max_iterations = n.max(1);
+        }
+        if let Some(language) = &overrides.target_language {
+            self.target_language = language.clone();
+        }
+        if overrides.resource_tier.is_some() {
+            self.resource_tier = overrides.resource_tier;
+        }
+        if overrides.token_budget.is_some() {
+            self.token_budget = overrides.token_budget;
+        }
+        self
+    }
+
+    /// The Engineer for `spec`, shown the example that best fits it.
+    fn engineer<'l>(&self, llm: &'l dyn LlmClient, spec: &RefinedSpec) -> EngineerAgent<'l> {
+        EngineerAgent::with_target(llm, self.target_language.clone())
+            .with_standards(
+                self.coding_standards
+                    .for_language(&self.target_language)
+                    .map(str::to_string),
+            )
+            .with_resource_tier(self.resource_tier)
+            .with_wit_version(self.wit_version)
+            .with_example(examples::select(spec))
+            .with_host_capabilities(self.host_capabilities.clone())
+            .with_sampling(self.sampling.engineer)
+    }
+
     /// Run the full pipeline for a capability request.
     pub async fn run(&self, request: &CapabilityRequest) -> PipelineOutcome {
+        let span = tracing::info_span!(
+            "pipeline",
+            request_id = %request.id,
+            tool = %request.spec.name,
+        );
+        self.run_request(request).instrument(span).await
+    }
+
+    async fn run_request(&self, request: &CapabilityRequest) -> PipelineOutcome {
+        let started = Instant::now();
+        let mut timings = StageTimings::default();
+        let llm = BudgetedLlm::new(self.llm, self.token_budget);
+
         // Phase 1: Architect refines the spec
-        let refined = match self.architect_phase(&request.spec).await {
+        let refined = match self
+            .architect_phase(&llm, request)
+            .instrument(tracing::info_span!("architect"))
+            .await
+        {
             Ok(refined) => refined,
             Err(e) => {
                 tracing::warn!(error = %e, "Architect failed, using passthrough spec");
                 ArchitectAgent::passthrough(&request.spec)
             }
         };
+        timings.architect_ms = elapsed_ms(started);
 
         // Check if architect recommends extending instead of building
         if refined.action == SpecAction::RecommendExtend {
@@ -71,46 +251,235 @@ impl<'a> Orchestrator<'a> {
         }
 
         // Phase 2-4: Build loop with QA and Red Team validation
-        match self.build_loop(&refined).await {
-            Ok(artifact) => PipelineOutcome::Built(artifact),
-            Err(e) => PipelineOutcome::Failed(e),
+        let mut report = FailureReport::new(Some(request.id.clone()), refined.clone());
+        let result = self
+            .build_loop(&llm, &refined, None, &mut timings, &mut report)
+            .await;
+        timings.total_ms = elapsed_ms(started);
+        timings.cached = llm.any_cached();
+        timings.providers = llm.providers();
+        match result {
+            Ok(mut artifact) => {
+                artifact.provenance =
+                    Some(self.provenance(Some(request), Origin::Architect, &refined, timings));
+                self.keep_sources(&report).await;
+                PipelineOutcome::Built(artifact)
+            }
+            Err(e) => {
+                report.timings = timings;
+                PipelineOutcome::Failed(e, Box::new(report))
+            }
+        }
+    }
+
+    /// Preview a build of `request`: run only the Architect and report the
+    /// spec, plan, resource tier and policy the build would start from. No
+    /// code is generated, so the Engineer, QA and Red Team are never called.
+    pub async fn dry_run(&self, request: &CapabilityRequest) -> DryRunReport {
+        let span = tracing::info_span!(
+            "pipeline",
+            request_id = %request.id,
+            tool = %request.spec.name,
+            dry_run = true,
+        );
+        self.dry_run_request(request).instrument(span).await
+    }
+
+    async fn dry_run_request(&self, request: &CapabilityRequest) -> DryRunReport {
+        let started = Instant::now();
+        let llm = BudgetedLlm::new(self.llm, self.token_budget);
+        let refined = match self
+            .architect_phase(&llm, request)
+            .instrument(tracing::info_span!("architect"))
+            .await
+        {
+            Ok(refined) => refined,
+            Err(e) => {
+                tracing::warn!(error = %e, "Architect failed, using passthrough spec");
+                ArchitectAgent::passthrough(&request.spec)
+            }
+        };
+        let elapsed = elapsed_ms(started);
+        let resource_tier = self
+            .resource_tier
+            .unwrap_or_else(|| PolicyYaml::infer_tier(&refined.spec));
+        let plan = Some(refined.design_notes.trim())
+            .filter(|notes| !notes.is_empty())
+            .map(str::to_string);
+        DryRunReport {
+            policy: PolicyYaml::from_spec_with_tier(&refined.spec, &resource_tier),
+            plan,
+            resource_tier,
+            tokens_used: llm.tokens_used(),
+            timings: StageTimings {
+                architect_ms: elapsed,
+                total_ms: elapsed,
+                cached: llm.any_cached(),
+                providers: llm.providers(),
+                ..StageTimings::default()
+            },
+            refined_spec: refined,
         }
     }
 
     async fn architect_phase(
         &self,
-        spec: &girt_core::spec::CapabilitySpec,
+        llm: &dyn LlmClient,
+        request: &CapabilityRequest,
     ) -> Result<RefinedSpec, PipelineError> {
-        let architect = ArchitectAgent::new(self.llm);
-        let refined = architect.refine(spec).await?;
+        let spec = &request.spec;
+        let architect = ArchitectAgent::new(llm)
+            .with_previous_denials(self.previous_denials.clone())
+            .with_context(request.context.clone())
+            .with_sampling(self.sampling.architect);
+        let refined = architect.refine(spec).await?.validate_and_normalize(spec)?;
         tracing::info!(name = %refined.spec.name, action = ?refined.action, "Spec refined");
         Ok(refined)
     }
 
-    async fn build_loop(&self, spec: &RefinedSpec) -> Result<Box<BuildArtifact>, PipelineError> {
-        let engineer = EngineerAgent::new(self.llm)
-            .with_standards(self.coding_standards.clone());
-        let qa = QaAgent::new(self.llm);
-        let red_team = RedTeamAgent::new(self.llm);
+    /// Record what this orchestrator is configured with, alongside the
+    /// request and timings of a finished build of `spec`.
+    fn provenance(
+        &self,
+        request: Option<&CapabilityRequest>,
+        origin: Origin,
+        spec: &RefinedSpec,
+        timings: StageTimings,
+    ) -> Provenance {
+        let engineer = self.engineer(self.llm, spec);
+        let mut prompts = vec![
+            ("engineer", engineer.system_prompt()),
+            ("engineer_fix", engineer.fix_prompt()),
+            ("qa", QA_SYSTEM_PROMPT.to_string()),
+            ("red_team", RED_TEAM_SYSTEM_PROMPT.to_string()),
+        ];
+        match origin {
+            Origin::Architect => prompts.push(("architect", ARCHITECT_SYSTEM_PROMPT.to_string())),
+            Origin::Extension => prompts.push(("engineer_extend", engineer.extend_prompt())),
+            Origin::Spec => {}
+        }
+        let agents: BTreeMap<String, AgentProvenance> = prompts
+            .into_iter()
+            .map(|(agent, prompt)| {
+                let entry = AgentProvenance {
+                    provider: self.llm.provider().to_string(),
+                    model: self.llm.model().to_string(),
+                    prompt_sha256: hex::encode(Sha256::digest(prompt.as_bytes())),
+                };
+                (agent.to_string(), entry)
+            })
+            .collect();
+
+        Provenance {
+            girt_version: env!("CARGO_PKG_VERSION").to_string(),
+            request_id: request.map(|r| r.id.clone()),
+            request_source: request.map(|r| r.source),
+            agents,
+            pipeline: PipelineSnapshot {
+                max_iterations: self.max_iterations,
+            },
+            timings,
+        }
+    }
+
+    /// Build `spec`, from scratch or, given `extending`, by adding features
+    /// to a published build's output.
+    async fn build_loop(
+        &self,
+        llm: &dyn LlmClient,
+        spec: &RefinedSpec,
+        extending: Option<(&BuildOutput, &[String])>,
+        timings: &mut StageTimings,
+        report: &mut FailureReport,
+    ) -> Result<Box<BuildArtifact>, PipelineError> {
+        if let Some(example) = examples::select(spec) {
+            tracing::debug!(example = example.name, "Showing the Engineer an example");
+        }
+        let engineer = self.engineer(llm, spec);
+        let qa = QaAgent::new(llm).with_sampling(self.sampling.qa);
+        let red_team = RedTeamAgent::new(llm).with_sampling(self.sampling.red_team);
 
-        let mut build_output = engineer.build(spec).await?;
+        let stage = Instant::now();
+        let mut build_output = match extending {
+            None => {
+                engineer
+                    .build(spec)
+                    .instrument(tracing::info_span!("engineer"))
+                    .await?
+            }
+            Some((previous, features)) => {
+                engineer
+                    .extend(spec, previous, features)
+                    .instrument(tracing::info_span!("engineer_extend"))
+                    .await?
+            }
+        };
+        timings.engineer_ms += elapsed_ms(stage);
+        report.iterations.push(build_output.clone());
+        let mut snapshots = Vec::new();
+        // Fingerprints of the tickets the current output was a fix for.
+        let mut addressed = Vec::new();
         let mut iteration = 1u32;
+        let mut history = TicketHistory::new();
+        let mut fixes = FixHistory::new();
 
         loop {
             tracing::info!(iteration, "Build iteration starting");
+            snapshots.push(IterationSnapshot::new(
+                iteration,
+                &build_output,
+                std::mem::take(&mut addressed),
+            ));
 
-            // Run QA and Red Team
-            let qa_result = qa.test(spec, &build_output).await?;
-            let security_result = red_team.audit(spec, &build_output).await?;
+            // Output that fails a static check goes straight back to the
+            // Engineer; QA and Red Team only see output that passes them.
+            let mut tickets: Vec<BugTicket> = self.static_checks.check(&build_output);
+            let reviews = if tickets.is_empty() {
+                let stage = Instant::now();
+                let qa_result = qa
+                    .test(spec, &build_output)
+                    .instrument(tracing::info_span!("qa", iteration))
+                    .await?;
+                timings.qa_ms += elapsed_ms(stage);
+                let stage = Instant::now();
+                let security_result = red_team
+                    .audit(spec, &build_output)
+                    .instrument(tracing::info_span!("red_team", iteration))
+                    .await?;
+                timings.red_team_ms += elapsed_ms(stage);
 
-            // Collect bug tickets from both
-            let mut tickets: Vec<BugTicket> = Vec::new();
-            tickets.extend(qa_result.bug_tickets.iter().cloned());
-            tickets.extend(security_result.bug_tickets.iter().cloned());
+                // Collect bug tickets from both
+                tickets.extend(qa_result.bug_tickets.iter().cloned());
+                tickets.extend(security_result.bug_tickets.iter().cloned());
+                Some((qa_result, security_result))
+            } else {
+                tracing::info!(
+                    iteration,
+                    failed = tickets.len(),
+                    "Static checks failed; skipping QA and Red Team"
+                );
+                None
+            };
+            let (new_tickets, recurring_tickets) = history.record(iteration, &tickets);
+            report.tickets = history.records().to_vec();
+            if !tickets.is_empty() {
+                tracing::info!(iteration, new_tickets, recurring_tickets, "Bug tickets filed");
+            }
 
-            // If both passed, we're done
-            if qa_result.passed && security_result.passed {
-                tracing::info!(iterations = iteration, "Pipeline passed all checks");
+            // If both passed, or left only non-blocking tickets, we're done
+            if let Some((qa_result, security_result)) = reviews
+                && qa_result.passes_under(&self.blocking)
+                && security_result.passes_under(&self.blocking)
+            {
+                if tickets.is_empty() {
+                    tracing::info!(iterations = iteration, "Pipeline passed all checks");
+                } else {
+                    tracing::info!(
+                        iterations = iteration,
+                        non_blocking = tickets.len(),
+                        "Pipeline passed with non-blocking bug tickets"
+                    );
+                }
                 return Ok(Box::new(BuildArtifact {
                     spec: spec.spec.clone(),
                     refined_spec: spec.clone(),
@@ -118,15 +487,26 @@ impl<'a> Orchestrator<'a> {
                     qa_result,
                     security_result,
                     build_iterations: iteration,
+                    ticket_history: history.into_records(),
+                    provenance: None,
+                    requested_name: None,
+                    revision: 0,
+                    published_at: None,
+                    iteration_outputs: snapshots,
+                    test_vectors: vec![],
                 }));
             }
 
             // Circuit breaker
-            if iteration >= MAX_ITERATIONS {
-                let summary = format_ticket_summary(&tickets);
+            if iteration >= self.max_iterations {
+                let summary = format_ticket_summary(&history, &self.blocking);
                 tracing::error!(
                     iteration,
                     tickets = tickets.len(),
+                    blocking = tickets
+                        .iter()
+                        .filter(|t| t.is_blocking_under(&self.blocking))
+                        .count(),
                     "Circuit breaker: max iterations reached"
                 );
                 return Err(PipelineError::CircuitBreaker {
@@ -135,14 +515,30 @@ impl<'a> Orchestrator<'a> {
                 });
             }
 
-            // Fix: pick the first ticket and send it back to engineer
-            if let Some(ticket) = tickets.first() {
+            // Fix: send the first blocking ticket back to the Engineer, or
+            // any ticket if none blocks but the build still did not pass
+            let ticket = tickets
+                .iter()
+                .find(|t| t.is_blocking_under(&self.blocking))
+                .or(tickets.first());
+            if let Some(ticket) = ticket {
+                let occurrences = history.occurrences(ticket);
                 tracing::info!(
                     iteration,
                     ticket_type = ?ticket.ticket_type,
+                    occurrences,
                     "Sending fix directive to engineer"
                 );
-                build_output = engineer.fix(spec, &build_output, ticket).await?;
+                let stage = Instant::now();
+                let fixed = engineer
+                    .fix(spec, &build_output, ticket, occurrences, &fixes)
+                    .instrument(tracing::info_span!("engineer_fix", iteration))
+                    .await?;
+                timings.engineer_ms += elapsed_ms(stage);
+                fixes.record(iteration, ticket, &build_output, &fixed);
+                report.iterations.push(fixed.clone());
+                addressed = vec![ticket.fingerprint()];
+                build_output = fixed;
             }
 
             iteration += 1;
@@ -152,6 +548,11 @@ impl<'a> Orchestrator<'a> {
     /// Run the pipeline with an already-refined spec (skips Architect phase).
     /// Useful when the decision engine has already produced a spec.
     pub async fn run_from_spec(&self, spec: &RefinedSpec) -> PipelineOutcome {
+        let span = tracing::info_span!("pipeline", tool = %spec.spec.name);
+        self.build_from_spec(spec).instrument(span).await
+    }
+
+    async fn build_from_spec(&self, spec: &RefinedSpec) -> PipelineOutcome {
         if spec.action == SpecAction::RecommendExtend {
             return PipelineOutcome::RecommendExtend {
                 target: spec.extend_target.clone().unwrap_or_default(),
@@ -159,24 +560,227 @@ impl<'a> Orchestrator<'a> {
             };
         }
 
-        match self.build_loop(spec).await {
-            Ok(artifact) => PipelineOutcome::Built(artifact),
-            Err(e) => PipelineOutcome::Failed(e),
+        let started = Instant::now();
+        let mut timings = StageTimings::default();
+        let llm = BudgetedLlm::new(self.llm, self.token_budget);
+        let mut report = FailureReport::new(None, spec.clone());
+        let result = self
+            .build_loop(&llm, spec, None, &mut timings, &mut report)
+            .await;
+        timings.total_ms = elapsed_ms(started);
+        timings.cached = llm.any_cached();
+        timings.providers = llm.providers();
+        match result {
+            Ok(mut artifact) => {
+                artifact.provenance = Some(self.provenance(None, Origin::Spec, spec, timings));
+                self.keep_sources(&report).await;
+                PipelineOutcome::Built(artifact)
+            }
+            Err(e) => {
+                report.timings = timings;
+                PipelineOutcome::Failed(e, Box::new(report))
+            }
+        }
+    }
+
+    /// Extend `previous`, a published build, into `request.spec` by adding
+    /// `features` to its code. The Architect is skipped: the extended spec
+    /// was already checked by the Creation Gate, and the Engineer starts
+    /// from the published source instead of from scratch.
+    pub async fn run_extension(
+        &self,
+        request: &CapabilityRequest,
+        previous: &BuildArtifact,
+        features: &[String],
+    ) -> PipelineOutcome {
+        let span = tracing::info_span!(
+            "pipeline",
+            request_id = %request.id,
+            tool = %request.spec.name,
+            extension = true,
+        );
+        self.extend_request(request, previous, features)
+            .instrument(span)
+            .await
+    }
+
+    async fn extend_request(
+        &self,
+        request: &CapabilityRequest,
+        previous: &BuildArtifact,
+        features: &[String],
+    ) -> PipelineOutcome {
+        let started = Instant::now();
+        let mut timings = StageTimings::default();
+        let llm = BudgetedLlm::new(self.llm, self.token_budget);
+        let refined = RefinedSpec {
+            action: SpecAction::Build,
+            spec: request.spec.clone(),
+            extend_target: None,
+            extend_features: None,
+            ..previous.refined_spec.clone()
+        };
+        let mut report = FailureReport::new(Some(request.id.clone()), refined.clone());
+        let extending = Some((&previous.build_output, features));
+        let result = self
+            .build_loop(&llm, &refined, extending, &mut timings, &mut report)
+            .await;
+        timings.total_ms = elapsed_ms(started);
+        timings.cached = llm.any_cached();
+        timings.providers = llm.providers();
+        match result {
+            Ok(mut artifact) => {
+                artifact.provenance =
+                    Some(self.provenance(Some(request), Origin::Extension, &refined, timings));
+                self.keep_sources(&report).await;
+                PipelineOutcome::Built(artifact)
+            }
+            Err(e) => {
+                report.timings = timings;
+                PipelineOutcome::Failed(e, Box::new(report))
+            }
+        }
+    }
+
+    async fn keep_sources(&self, report: &FailureReport) {
+        if let Some(store) = self.failures {
+            store.record_sources(report).await;
         }
     }
 }
 
-fn format_ticket_summary(tickets: &[BugTicket]) -> String {
-    tickets
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+/// Counts the tokens one pipeline run generates and refuses calls once its
+/// budget is spent. Each call's `max_tokens` is lowered to what remains.
+/// Also notes whether any answer came from the LLM cache, and which
+/// providers answered.
+struct BudgetedLlm<'a> {
+    inner: &'a dyn LlmClient,
+    budget: Option<u32>,
+    used: AtomicU32,
+    cached: AtomicBool,
+    providers: Mutex<Vec<String>>,
+}
+
+impl<'a> BudgetedLlm<'a> {
+    fn new(inner: &'a dyn LlmClient, budget: Option<u32>) -> Self {
+        Self {
+            inner,
+            budget,
+            used: AtomicU32::new(0),
+            cached: AtomicBool::new(false),
+            providers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Tokens generated so far.
+    fn tokens_used(&self) -> u32 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Whether any call so far was answered from the LLM cache.
+    fn any_cached(&self) -> bool {
+        self.cached.load(Ordering::Relaxed)
+    }
+
+    /// Providers that answered so far, in the order first used.
+    fn providers(&self) -> Vec<String> {
+        self.providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl LlmClient for BudgetedLlm<'_> {
+    fn chat<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+        let span = tracing::info_span!(
+            "llm.chat",
+            provider = self.inner.provider(),
+            model = self.inner.model(),
+            max_tokens = request.max_tokens,
+            output_tokens = tracing::field::Empty,
+            cached = tracing::field::Empty,
+        );
+        let call = async move {
+            let response = match self.budget {
+                None => self.inner.chat(request).await?,
+                Some(budget) => {
+                    let remaining = budget.saturating_sub(self.used.load(Ordering::Relaxed));
+                    if remaining == 0 {
+                        return Err(PipelineError::BudgetExceeded { budget });
+                    }
+                    let mut request = request.clone();
+                    request.max_tokens = request.max_tokens.min(remaining);
+                    self.inner.chat(&request).await?
+                }
+            };
+            // Providers that report no usage are estimated at 4 bytes a token.
+            let tokens = response
+                .output_tokens
+                .unwrap_or_else(|| response.content.len().div_ceil(4) as u32);
+            self.used.fetch_add(tokens, Ordering::Relaxed);
+            if response.cached {
+                self.cached.store(true, Ordering::Relaxed);
+            }
+            if let Some(provider) = &response.provider {
+                let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+                if !providers.contains(provider) {
+                    providers.push(provider.clone());
+                }
+            }
+            let span = tracing::Span::current();
+            if let Some(tokens) = response.output_tokens {
+                span.record("output_tokens", tokens);
+            }
+            span.record("cached", response.cached);
+            Ok(response)
+        };
+        Box::pin(call.instrument(span))
+    }
+
+    fn provider(&self) -> &str {
+        self.inner.provider()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+}
+
+/// One entry per distinct ticket; tickets filed in several iterations are
+/// marked `×N`, and those below `policy`'s thresholds `(non-blocking)`.
+fn format_ticket_summary(history: &TicketHistory, policy: &BlockingPolicy) -> String {
+    history
+        .records()
         .iter()
         .enumerate()
-        .map(|(i, t)| {
+        .map(|(i, record)| {
+            let t = &record.ticket;
+            let repeats = if record.occurrences > 1 {
+                format!(" ×{}", record.occurrences)
+            } else {
+                String::new()
+            };
+            let blocking = if t.is_blocking_under(policy) {
+                ""
+            } else {
+                " (non-blocking)"
+            };
             format!(
-                "#{}: [{:?}] expected: {}, actual: {}",
+                "#{}: [{:?}] expected: {}, actual: {}{}{}",
                 i + 1,
                 t.ticket_type,
                 t.expected,
-                t.actual
+                t.actual,
+                repeats,
+                blocking
             )
         })
         .collect::<Vec<_>>()
@@ -186,8 +790,11 @@ fn format_ticket_summary(tickets: &[BugTicket]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::llm::StubLlmClient;
-    use crate::types::{RequestSource, SpecAction};
+    use crate::llm::{Matcher, Sampling, StubLlmClient};
+    use crate::llm_cache::CachingLlmClient;
+    use crate::llm_fallback::FallbackLlmClient;
+    use crate::static_checks::clean_source;
+    use crate::types::{RequestSource, Severity, SpecAction};
     use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
 
     fn make_request() -> CapabilityRequest {
@@ -216,15 +823,12 @@ mod tests {
             design_notes: "test".into(),
             extend_target: None,
             extend_features: None,
+            idempotent: false,
         }
     }
 
-    /// Builds a StubLlmClient that returns:
-    /// 1. Architect response (refine)
-    /// 2. Engineer response (build)
-    /// 3. QA response (passing)
-    /// 4. Red Team response (passing)
-    fn make_happy_path_client() -> StubLlmClient {
+    /// Architect, Engineer, passing QA, and passing Red Team responses.
+    fn happy_path_responses() -> [String; 4] {
         let architect_resp = serde_json::json!({
             "action": "build",
             "spec": {
@@ -238,8 +842,8 @@ mod tests {
         });
 
         let engineer_resp = serde_json::json!({
-            "source_code": "fn main() {}",
-            "wit_definition": "package test:tool;",
+            "source_code": clean_source("stub"),
+            "wit_definition": "",
             "policy_yaml": "version: \"1.0\"",
             "language": "rust"
         });
@@ -259,12 +863,21 @@ mod tests {
             "bug_tickets": []
         });
 
-        StubLlmClient::new(vec![
+        [
             architect_resp.to_string(),
             engineer_resp.to_string(),
             qa_resp.to_string(),
             security_resp.to_string(),
-        ])
+        ]
+    }
+
+    /// Builds a StubLlmClient that returns:
+    /// 1. Architect response (refine)
+    /// 2. Engineer response (build)
+    /// 3. QA response (passing)
+    /// 4. Red Team response (passing)
+    fn make_happy_path_client() -> StubLlmClient {
+        StubLlmClient::new(happy_path_responses().to_vec())
     }
 
     #[tokio::test]
@@ -279,11 +892,236 @@ mod tests {
                 assert_eq!(artifact.build_iterations, 1);
                 assert!(artifact.qa_result.passed);
                 assert!(artifact.security_result.passed);
+
+                let provenance = artifact.provenance.expect("provenance recorded");
+                assert_eq!(provenance.girt_version, env!("CARGO_PKG_VERSION"));
+                assert_eq!(provenance.request_id.as_deref(), Some(request.id.as_str()));
+                assert_eq!(provenance.request_source, Some(RequestSource::Operator));
+                assert_eq!(provenance.pipeline.max_iterations, MAX_ITERATIONS);
+                assert_eq!(
+                    provenance.agents.keys().collect::<Vec<_>>(),
+                    ["architect", "engineer", "engineer_fix", "qa", "red_team"]
+                );
+                let engineer = &provenance.agents["engineer"];
+                assert_eq!(
+                    (engineer.provider.as_str(), engineer.model.as_str()),
+                    ("stub", "stub")
+                );
+                assert_eq!(engineer.prompt_sha256.len(), 64);
+                let t = provenance.timings;
+                assert!(t.total_ms >= t.architect_ms + t.engineer_ms + t.qa_ms + t.red_team_ms);
             }
             other => panic!("Expected Built, got {:?}", other),
         }
     }
 
+    #[tokio::test]
+    async fn rerun_reuses_the_cached_architect_answer() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let [architect, engineer, qa, security] = happy_path_responses();
+        let stub = std::sync::Arc::new(StubLlmClient::with_matchers(vec![
+            (Matcher::system_prompt("Chief Software Architect"), architect),
+            (Matcher::system_prompt("Senior Backend Engineer"), engineer),
+            (Matcher::system_prompt("QA Automation Engineer"), qa),
+            (Matcher::system_prompt("Offensive Security Researcher"), security),
+        ]));
+        let client = CachingLlmClient::new(stub.clone(), tmp.path());
+        let orchestrator = Orchestrator::new(&client);
+        let request = make_request();
+        let timings = |outcome| match outcome {
+            PipelineOutcome::Built(artifact) => artifact.provenance.unwrap().timings,
+            other => panic!("Expected Built, got {other:?}"),
+        };
+
+        let first = timings(orchestrator.run(&request).await);
+        let second = timings(orchestrator.run(&request).await);
+        assert!(!first.cached);
+        assert!(second.cached);
+
+        // Only the Architect call was answered from the cache.
+        let requests = stub.requests();
+        assert_eq!(requests.len(), 7);
+        let architect_calls = requests
+            .iter()
+            .filter(|r| r.system_prompt.contains("Chief Software Architect"))
+            .count();
+        assert_eq!(architect_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn dry_run_stops_after_the_architect() {
+        let architect = serde_json::json!({
+            "action": "build",
+            "spec": {
+                "name": "test_tool",
+                "description": "A test tool",
+                "inputs": {"value": "string"},
+                "outputs": {"result": "string"},
+                "constraints": {"network": ["api.example.com"], "storage": [], "secrets": []}
+            },
+            "design_notes": "1. Fetch the record\n2. Return its result field"
+        });
+        let [_, engineer, qa, security] = happy_path_responses();
+        let client = StubLlmClient::with_matchers(vec![
+            (
+                Matcher::system_prompt("Chief Software Architect"),
+                architect.to_string(),
+            ),
+            (Matcher::system_prompt("Senior Backend Engineer"), engineer),
+            (Matcher::system_prompt("QA Automation Engineer"), qa),
+            (
+                Matcher::system_prompt("Offensive Security Researcher"),
+                security,
+            ),
+        ]);
+
+        let report = Orchestrator::new(&client).dry_run(&make_request()).await;
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(
+            requests[0]
+                .system_prompt
+                .contains("Chief Software Architect")
+        );
+        assert_eq!(
+            report.plan.as_deref(),
+            Some("1. Fetch the record\n2. Return its result field")
+        );
+        assert_eq!(report.resource_tier, ResourceTier::Standard);
+        let hosts: Vec<&str> = report
+            .policy
+            .permissions
+            .network
+            .allow
+            .iter()
+            .map(|h| h.host.as_str())
+            .collect();
+        assert_eq!(hosts, ["api.example.com"]);
+        assert!(report.tokens_used > 0);
+        assert_eq!(report.timings.engineer_ms, 0);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["resource_tier"], "standard");
+        assert_eq!(json["refined_spec"]["spec"]["name"], "test_tool");
+    }
+
+    #[tokio::test]
+    async fn dry_run_uses_the_requested_tier() {
+        let client = make_happy_path_client();
+        let orchestrator = Orchestrator::new(&client).with_overrides(&PipelineOverrides {
+            resource_tier: Some(ResourceTier::Extended),
+            ..Default::default()
+        });
+        let report = orchestrator.dry_run(&make_request()).await;
+        assert_eq!(report.resource_tier, ResourceTier::Extended);
+        assert_eq!(report.plan.as_deref(), Some("Simple tool"));
+    }
+
+    /// A provider that cannot be reached.
+    struct UnreachableLlm;
+
+    impl LlmClient for UnreachableLlm {
+        fn chat<'a>(
+            &'a self,
+            _request: &'a LlmRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+            Box::pin(async { Err(PipelineError::LlmTransport("connection refused".into())) })
+        }
+
+        fn provider(&self) -> &str {
+            "anthropic"
+        }
+    }
+
+    #[tokio::test]
+    async fn timings_name_the_providers_that_answered() {
+        let [architect, engineer, qa, security] = happy_path_responses();
+        let fallback =
+            std::sync::Arc::new(StubLlmClient::new(vec![architect, engineer, qa, security]));
+        let client = FallbackLlmClient::new(std::sync::Arc::new(UnreachableLlm), vec![fallback]);
+        let orchestrator = Orchestrator::new(&client);
+
+        match orchestrator.run(&make_request()).await {
+            PipelineOutcome::Built(artifact) => {
+                let provenance = artifact.provenance.unwrap();
+                assert_eq!(provenance.timings.providers, ["stub"]);
+                assert_eq!(provenance.agents["engineer"].provider, "anthropic");
+            }
+            other => panic!("Expected Built, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn agents_sample_with_their_defaults_unless_configured() {
+        let client = make_happy_path_client();
+        let sampling = SamplingConfig {
+            red_team: Sampling {
+                temperature: Some(1.0),
+                top_p: Some(0.9),
+            },
+            qa: Sampling {
+                temperature: None,
+                top_p: Some(0.5),
+            },
+            ..SamplingConfig::default()
+        };
+        let outcome = Orchestrator::new(&client)
+            .with_sampling(sampling)
+            .run(&make_request())
+            .await;
+        assert!(matches!(outcome, PipelineOutcome::Built(_)));
+
+        let sent: Vec<_> = client
+            .requests()
+            .iter()
+            .map(|r| (r.temperature, r.top_p))
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                (Some(0.4), None),      // Architect
+                (Some(0.2), None),      // Engineer
+                (Some(0.3), Some(0.5)), // QA
+                (Some(1.0), Some(0.9)), // Red Team
+            ]
+        );
+    }
+
+    #[test]
+    fn prompt_hash_changes_with_coding_standards() {
+        let client = StubLlmClient::constant("");
+        let request = make_request();
+        let spec = ArchitectAgent::passthrough(&request.spec);
+        let plain = Orchestrator::new(&client).provenance(
+            Some(&request),
+            Origin::Architect,
+            &spec,
+            StageTimings::default(),
+        );
+        let with_standards = Orchestrator::new(&client)
+            .with_standards(CodingStandards::default().with_fallback(Some("Never panic.".into())))
+            .provenance(
+                Some(&request),
+                Origin::Architect,
+                &spec,
+                StageTimings::default(),
+            );
+
+        for agent in ["engineer", "engineer_fix"] {
+            assert_ne!(
+                plain.agents[agent].prompt_sha256, with_standards.agents[agent].prompt_sha256,
+                "{agent}"
+            );
+        }
+        for agent in ["architect", "qa", "red_team"] {
+            assert_eq!(
+                plain.agents[agent].prompt_sha256, with_standards.agents[agent].prompt_sha256,
+                "{agent}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn recommend_extend_skips_build() {
         let architect_resp = serde_json::json!({
@@ -314,12 +1152,87 @@ mod tests {
         }
     }
 
+    /// Build `make_request()` with `architect` as the Architect's answer and
+    /// the happy path for the rest.
+    async fn build_with_architect(architect: serde_json::Value) -> Box<BuildArtifact> {
+        let [_, engineer, qa, security] = happy_path_responses();
+        let client = StubLlmClient::new(vec![architect.to_string(), engineer, qa, security]);
+        match Orchestrator::new(&client).run(&make_request()).await {
+            PipelineOutcome::Built(artifact) => artifact,
+            other => panic!("Expected Built, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn architect_constraint_strings_become_lists() {
+        let artifact = build_with_architect(serde_json::json!({
+            "action": "build",
+            "spec": {
+                "name": "test_tool",
+                "description": "A test tool ",
+                "inputs": {"value": "string"},
+                "outputs": {"result": "string"},
+                "constraints": {"network": "api.example.com", "storage": null}
+            },
+            "design_notes": "Simple tool"
+        }))
+        .await;
+
+        let spec = &artifact.refined_spec.spec;
+        assert_eq!(artifact.refined_spec.design_notes, "Simple tool");
+        assert_eq!(spec.description, "A test tool");
+        assert_eq!(spec.constraints.network, ["api.example.com"]);
+        assert!(spec.constraints.storage.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_architect_spec_falls_back_to_the_request() {
+        let artifact = build_with_architect(serde_json::json!({
+            "action": "build",
+            "spec": {
+                "name": "test_tool",
+                "description": "A test tool",
+                "inputs": "value: string",
+                "outputs": {},
+                "constraints": {"network": ["https://api.example.com/v1"]}
+            },
+            "design_notes": "Simple tool"
+        }))
+        .await;
+
+        let request = make_request();
+        assert_eq!(artifact.refined_spec.spec, request.spec);
+        assert_eq!(
+            artifact.refined_spec.design_notes,
+            "Unrefined spec (Architect unavailable)"
+        );
+    }
+
+    #[tokio::test]
+    async fn recommend_extend_without_target_builds_the_request() {
+        let artifact = build_with_architect(serde_json::json!({
+            "action": "recommend_extend",
+            "spec": {
+                "name": "test_tool",
+                "description": "A test tool",
+                "inputs": {},
+                "outputs": {}
+            },
+            "design_notes": "Extend something",
+            "extend_features": "new_feature"
+        }))
+        .await;
+
+        assert_eq!(artifact.refined_spec.action, SpecAction::Build);
+        assert_eq!(artifact.refined_spec.spec, make_request().spec);
+    }
+
     #[tokio::test]
     async fn fix_loop_succeeds_on_second_iteration() {
         // Engineer initial build, QA fails, Engineer fix, QA passes, Red Team passes
         let engineer_resp = serde_json::json!({
-            "source_code": "fn main() { /* v1 */ }",
-            "wit_definition": "package test:tool;",
+            "source_code": clean_source("v1"),
+            "wit_definition": "",
             "policy_yaml": "version: \"1.0\"",
             "language": "rust"
         });
@@ -347,8 +1260,8 @@ mod tests {
         });
 
         let engineer_fix = serde_json::json!({
-            "source_code": "fn main() { /* v2 fixed */ }",
-            "wit_definition": "package test:tool;",
+            "source_code": clean_source("v2 fixed"),
+            "wit_definition": "",
             "policy_yaml": "version: \"1.0\"",
             "language": "rust"
         });
@@ -378,7 +1291,9 @@ mod tests {
             security_pass2.to_string(),
         ]);
 
-        let orchestrator = Orchestrator::new(&client);
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = FailureStore::new(tmp.path().to_path_buf()).with_iteration_sources(true);
+        let orchestrator = Orchestrator::new(&client).with_failure_store(&store);
         let spec = make_refined_spec();
 
         let outcome = orchestrator.run_from_spec(&spec).await;
@@ -387,16 +1302,258 @@ mod tests {
                 assert_eq!(artifact.build_iterations, 2);
                 assert!(artifact.qa_result.passed);
                 assert!(artifact.build_output.source_code.contains("v2 fixed"));
+
+                let [first, second] = &artifact.iteration_outputs[..] else {
+                    panic!("{:?}", artifact.iteration_outputs);
+                };
+                assert_eq!((first.iteration, second.iteration), (1, 2));
+                assert_ne!(first.source_sha256, second.source_sha256);
+                assert!(first.tickets_addressed.is_empty());
+                assert_eq!(second.tickets_addressed.len(), 1);
+
+                // Both iterations' sources are kept, though the build passed.
+                let kept = std::fs::read_dir(tmp.path())
+                    .unwrap()
+                    .map(|e| e.unwrap().path())
+                    .find(|p| !p.file_name().unwrap().to_string_lossy().starts_with('.'))
+                    .expect("a sources directory");
+                let second_source = std::fs::read_to_string(kept.join("iter-2.rs")).unwrap();
+                assert!(second_source.contains("v2 fixed"));
+                assert!(kept.join("iter-1.rs").exists());
+
+                // No request and no Architect when building from a spec.
+                let provenance = artifact.provenance.expect("provenance recorded");
+                assert_eq!(provenance.request_id, None);
+                assert!(!provenance.agents.contains_key("architect"));
             }
             other => panic!("Expected Built, got {:?}", other),
         }
     }
 
+    #[tokio::test]
+    async fn later_fix_prompts_list_earlier_fixes() {
+        let engineer = |version: &str, summary: Option<&str>| {
+            let mut resp = serde_json::json!({
+                "source_code": clean_source(version),
+                "wit_definition": "",
+                "policy_yaml": "version: \"1.0\"",
+                "language": "rust"
+            });
+            if let Some(summary) = summary {
+                resp["change_summary"] = summary.into();
+            }
+            resp.to_string()
+        };
+        let qa_fail = |expected: &str| {
+            serde_json::json!({
+                "passed": false,
+                "tests_run": 1,
+                "tests_passed": 0,
+                "tests_failed": 1,
+                "bug_tickets": [{
+                    "target": "engineer",
+                    "ticket_type": "functional_defect",
+                    "input": {"value": expected},
+                    "expected": expected,
+                    "actual": "panic",
+                    "remediation_directive": "Handle it"
+                }]
+            })
+            .to_string()
+        };
+        let qa_pass = serde_json::json!({
+            "passed": true,
+            "tests_run": 1,
+            "tests_passed": 1,
+            "tests_failed": 0,
+            "bug_tickets": []
+        })
+        .to_string();
+        let security_pass = serde_json::json!({
+            "passed": true,
+            "exploits_attempted": 1,
+            "exploits_succeeded": 0,
+            "bug_tickets": []
+        })
+        .to_string();
+
+        let client = StubLlmClient::new(vec![
+            engineer("v1", None),
+            qa_fail("bounds error"),
+            security_pass.clone(),
+            engineer("v2", Some("Added bounds checking on value")),
+            qa_fail("empty error"),
+            security_pass.clone(),
+            engineer("v3", Some("Rejected empty strings")),
+            qa_pass,
+            security_pass,
+        ]);
+
+        let outcome = Orchestrator::new(&client)
+            .run_from_spec(&make_refined_spec())
+            .await;
+        assert!(matches!(outcome, PipelineOutcome::Built(_)), "{outcome:?}");
+
+        let requests = client.requests();
+        let first_fix = &requests[3].messages[0].content;
+        let second_fix = &requests[6].messages[0].content;
+        assert!(!first_fix.contains("Previously attempted fixes"));
+        assert!(second_fix.contains("Previously attempted fixes"));
+        assert!(second_fix.contains("(expected: bounds error): Added bounds checking on value"));
+        assert!(!second_fix.contains("Rejected empty strings"));
+    }
+
     #[tokio::test]
     async fn circuit_breaker_triggers_after_max_iterations() {
+        let client = never_passing_client();
+        let orchestrator = Orchestrator::new(&client);
+        let spec = make_refined_spec();
+
+        let outcome = orchestrator.run_from_spec(&spec).await;
+        match outcome {
+            PipelineOutcome::Failed(PipelineError::CircuitBreaker { attempts, summary }, _) => {
+                assert_eq!(attempts, 3);
+                // The same two tickets every iteration collapse to two entries.
+                assert_eq!(
+                    summary,
+                    "#1: [FunctionalDefect] expected: correct, actual: wrong ×3; \
+                     #2: [SecurityVulnerability] expected: blocked, actual: succeeded ×3"
+                );
+            }
+            other => panic!("Expected Failed(CircuitBreaker), got {:?}", other),
+        }
+
+        // One build, a fix between each of the three QA + Red Team rounds.
+        let fixes = client
+            .requests()
+            .iter()
+            .filter(|r| r.system_prompt.starts_with("You previously built"))
+            .count();
+        assert_eq!((client.requests().len(), fixes), (9, 2));
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_failure_leaves_a_bundle() {
+        let client = never_passing_client();
+        let outcome = Orchestrator::new(&client)
+            .run_from_spec(&make_refined_spec())
+            .await;
+        let PipelineOutcome::Failed(error, report) = outcome else {
+            panic!("Expected Failed, got {outcome:?}");
+        };
+        assert_eq!(error.code(), "circuit_breaker");
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = crate::failures::FailureStore::new(tmp.path().to_path_buf());
+        let dir = store.write(&error, &report).await.unwrap();
+        let id = dir.file_name().unwrap().to_string_lossy().into_owned();
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "failure.json",
+                "iteration-1.json",
+                "iteration-2.json",
+                "iteration-3.json",
+                "spec.json",
+                "tickets.json"
+            ]
+        );
+
+        let bundle = store.show(&id).await.unwrap();
+        assert_eq!(bundle.summary.tool_name, "test_tool");
+        assert_eq!(bundle.summary.error, error.to_string());
+        assert_eq!(bundle.summary.iterations, [1, 2, 3]);
+        assert!(bundle.summary.timings.total_ms >= bundle.summary.timings.qa_ms);
+        assert_eq!(bundle.report.refined_spec.spec.name, "test_tool");
+        assert!(
+            bundle
+                .report
+                .iterations
+                .iter()
+                .all(|o| o.source_code.contains("broken"))
+        );
+        let tickets: Vec<(&str, u32)> = bundle
+            .report
+            .tickets
+            .iter()
+            .map(|r| (r.ticket.expected.as_str(), r.occurrences))
+            .collect();
+        assert_eq!(tickets, [("correct", 3), ("blocked", 3)]);
+        assert_eq!(bundle.report.compile_stderr, None);
+    }
+
+    #[tokio::test]
+    async fn overrides_reach_the_agents() {
+        let client = never_passing_client();
+        let orchestrator = Orchestrator::new(&client).with_overrides(&PipelineOverrides {
+            max_iterations: Some(1),
+            target_language: Some(TargetLanguage::Go),
+            resource_tier: Some(ResourceTier::Extended),
+            token_budget: None,
+        });
+
+        let outcome = orchestrator.run_from_spec(&make_refined_spec()).await;
+        assert!(matches!(
+            outcome,
+            PipelineOutcome::Failed(PipelineError::CircuitBreaker { attempts: 1, .. }, _)
+        ));
+        let requests = client.requests();
+        // Build, QA, Red Team, and no fix.
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0].system_prompt.contains("TinyGo"));
+        assert!(requests[0].messages[0].content.contains("memory_mb: 512"));
+    }
+
+    #[tokio::test]
+    async fn engineer_sees_the_standards_for_its_language() {
+        let client = never_passing_client();
+        let standards = CodingStandards::default()
+            .with_fallback(Some("General rules.".into()))
+            .with_language(TargetLanguage::Go, "Go rules.".into());
+        let orchestrator = Orchestrator::new(&client)
+            .with_standards(standards)
+            .with_overrides(&PipelineOverrides {
+                max_iterations: Some(1),
+                target_language: Some(TargetLanguage::Go),
+                ..Default::default()
+            });
+
+        orchestrator.run_from_spec(&make_refined_spec()).await;
+        let prompt = &client.requests()[0].system_prompt;
+        assert!(prompt.contains("Go rules."), "{prompt}");
+        assert!(!prompt.contains("General rules."), "{prompt}");
+    }
+
+    #[tokio::test]
+    async fn token_budget_stops_the_build() {
+        let client = never_passing_client();
+        let orchestrator = Orchestrator::new(&client).with_overrides(&PipelineOverrides {
+            token_budget: Some(100),
+            ..Default::default()
+        });
+
+        let outcome = orchestrator.run_from_spec(&make_refined_spec()).await;
+        assert!(matches!(
+            outcome,
+            PipelineOutcome::Failed(PipelineError::BudgetExceeded { budget: 100 }, _)
+        ));
+        // Each call may generate no more than what is left of the budget.
+        let requests = client.requests();
+        assert_eq!(requests[0].max_tokens, 100);
+        assert!(requests.iter().all(|r| r.max_tokens <= 100));
+        assert!(requests.len() < 9);
+    }
+
+    /// Answers every agent, with QA and Red Team always filing a ticket.
+    fn never_passing_client() -> StubLlmClient {
         let engineer_resp = serde_json::json!({
-            "source_code": "fn main() { /* broken */ }",
-            "wit_definition": "package test:tool;",
+            "source_code": clean_source("broken"),
+            "wit_definition": "",
             "policy_yaml": "version: \"1.0\"",
             "language": "rust"
         });
@@ -430,33 +1587,313 @@ mod tests {
             }]
         });
 
-        // Each iteration: engineer -> qa_fail -> security_fail -> engineer fix (3 iterations)
-        // Iteration 1: build(0) -> qa(1) -> sec(2) -> fix(3)
-        // Iteration 2: qa(4) -> sec(5) -> fix(6)
-        // Iteration 3: qa(7) -> sec(8) -> circuit breaker
+        // Keyed by agent, so the test does not depend on call order.
+        StubLlmClient::with_matchers(vec![
+            (
+                Matcher::system_prompt("QA Automation Engineer"),
+                qa_fail.to_string(),
+            ),
+            (
+                Matcher::system_prompt("Offensive Security Researcher"),
+                security_fail.to_string(),
+            ),
+            (
+                Matcher::system_prompt("Senior Backend Engineer"),
+                engineer_resp.to_string(),
+            ),
+            (
+                Matcher::system_prompt("You previously built"),
+                engineer_resp.to_string(),
+            ),
+        ])
+    }
+
+    /// QA passes every build; the Red Team finds the same medium-severity
+    /// issue in each.
+    fn medium_finding_client() -> StubLlmClient {
+        let engineer_resp = serde_json::json!({
+            "source_code": clean_source("fetch"),
+            "wit_definition": "",
+            "policy_yaml": "version: \"1.0\"",
+            "language": "rust"
+        });
+        let qa_pass = serde_json::json!({
+            "passed": true,
+            "tests_run": 3,
+            "tests_passed": 3,
+            "tests_failed": 0,
+            "bug_tickets": []
+        });
+        let security_finding = serde_json::json!({
+            "passed": false,
+            "exploits_attempted": 4,
+            "exploits_succeeded": 1,
+            "bug_tickets": [{
+                "target": "engineer",
+                "ticket_type": "security_vulnerability",
+                "severity": "medium",
+                "input": {"value": "a".repeat(64)},
+                "expected": "long input rejected",
+                "actual": "slow response",
+                "remediation_directive": "Cap the input length"
+            }]
+        });
+        StubLlmClient::with_matchers(vec![
+            (
+                Matcher::system_prompt("QA Automation Engineer"),
+                qa_pass.to_string(),
+            ),
+            (
+                Matcher::system_prompt("Offensive Security Researcher"),
+                security_finding.to_string(),
+            ),
+            (
+                Matcher::system_prompt("Senior Backend Engineer"),
+                engineer_resp.to_string(),
+            ),
+            (
+                Matcher::system_prompt("You previously built"),
+                engineer_resp.to_string(),
+            ),
+        ])
+    }
+
+    #[tokio::test]
+    async fn medium_security_findings_ship_under_the_default_policy() {
+        let client = medium_finding_client();
+        let outcome = Orchestrator::new(&client)
+            .run_from_spec(&make_refined_spec())
+            .await;
+        let PipelineOutcome::Built(artifact) = outcome else {
+            panic!("expected Built, got {outcome:?}");
+        };
+        assert_eq!(artifact.build_iterations, 1);
+        assert!(!artifact.security_result.passed);
+        assert_eq!(artifact.ticket_history.len(), 1);
+        assert_eq!(artifact.ticket_history[0].ticket.severity, Severity::Medium);
+    }
+
+    #[tokio::test]
+    async fn medium_security_findings_block_under_a_stricter_policy() {
+        let client = medium_finding_client();
+        let policy = BlockingPolicy {
+            security_vulnerability: Severity::Medium,
+            ..BlockingPolicy::default()
+        };
+        let outcome = Orchestrator::new(&client)
+            .with_blocking_policy(policy)
+            .run_from_spec(&make_refined_spec())
+            .await;
+        let PipelineOutcome::Failed(PipelineError::CircuitBreaker { summary, .. }, _) = outcome
+        else {
+            panic!("expected Failed(CircuitBreaker), got {outcome:?}");
+        };
+        assert_eq!(
+            summary,
+            "#1: [SecurityVulnerability] expected: long input rejected, actual: slow response ×3"
+        );
+
+        // Tickets below their threshold are marked, by their latest filing.
+        let mut history = TicketHistory::new();
+        let mut ticket: BugTicket = serde_json::from_value(serde_json::json!({
+            "target": "engineer",
+            "ticket_type": "functional_defect",
+            "severity": "low",
+            "input": {},
+            "expected": "trimmed",
+            "actual": "untrimmed",
+            "remediation_directive": "Trim"
+        }))
+        .unwrap();
+        history.record(1, std::slice::from_ref(&ticket));
+        assert_eq!(
+            format_ticket_summary(&history, &policy),
+            "#1: [FunctionalDefect] expected: trimmed, actual: untrimmed (non-blocking)"
+        );
+        ticket.severity = Severity::High;
+        history.record(2, &[ticket]);
+        assert_eq!(
+            format_ticket_summary(&history, &policy),
+            "#1: [FunctionalDefect] expected: trimmed, actual: untrimmed ×2"
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_static_checks_skip_qa_and_red_team() {
+        let engineer = |source: String| {
+            serde_json::json!({
+                "source_code": source,
+                "wit_definition": "",
+                "policy_yaml": "version: \"1.0\"",
+                "language": "rust"
+            })
+            .to_string()
+        };
+        let [_, _, qa_pass, security_pass] = happy_path_responses();
+        // Build without an export, fix, then QA and Red Team once.
+        let client = StubLlmClient::new(vec![
+            engineer(clean_source("v1").replace("bindings::export!", "// bindings::export!")),
+            engineer(clean_source("v2 exported")),
+            qa_pass.clone(),
+            security_pass.clone(),
+        ]);
+
+        let outcome = Orchestrator::new(&client)
+            .run_from_spec(&make_refined_spec())
+            .await;
+        let PipelineOutcome::Built(artifact) = outcome else {
+            panic!("Expected Built, got {outcome:?}");
+        };
+        assert_eq!(artifact.build_iterations, 2);
+        assert!(artifact.build_output.source_code.contains("v2 exported"));
+        assert_eq!(artifact.ticket_history.len(), 1);
+        assert_eq!(
+            artifact.ticket_history[0].ticket.input["static_check"],
+            "export_macro"
+        );
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[1].system_prompt.contains("You previously built"));
+        assert!(
+            requests[1].messages[0]
+                .content
+                .contains("Static check `export_macro` failed"),
+            "{}",
+            requests[1].messages[0].content
+        );
+
+        // With the check off, the first build goes to QA as it is.
+        let client = StubLlmClient::new(vec![
+            engineer("fn main() {}".into()),
+            qa_pass,
+            security_pass,
+        ]);
+        let checks = StaticChecks {
+            export_macro: false,
+            bindings_module: false,
+            ..StaticChecks::default()
+        };
+        let outcome = Orchestrator::new(&client)
+            .with_static_checks(checks)
+            .run_from_spec(&make_refined_spec())
+            .await;
+        assert!(matches!(outcome, PipelineOutcome::Built(_)));
+    }
+
+    #[tokio::test]
+    async fn recurring_ticket_is_tracked_in_history() {
+        let engineer_resp = serde_json::json!({
+            "source_code": clean_source("stub"),
+            "wit_definition": "",
+            "policy_yaml": "version: \"1.0\"",
+            "language": "rust"
+        });
+        let qa_fail = |actual: &str| {
+            serde_json::json!({
+                "passed": false,
+                "tests_run": 1,
+                "tests_passed": 0,
+                "tests_failed": 1,
+                "bug_tickets": [{
+                    "target": "engineer",
+                    "ticket_type": "functional_defect",
+                    "input": {"value": "bad"},
+                    "expected": "an error",
+                    "actual": actual,
+                    "remediation_directive": "Validate input"
+                }]
+            })
+        };
+        let qa_pass = serde_json::json!({
+            "passed": true,
+            "tests_run": 1,
+            "tests_passed": 1,
+            "tests_failed": 0,
+            "bug_tickets": []
+        });
+        let security_pass = serde_json::json!({
+            "passed": true,
+            "exploits_attempted": 1,
+            "exploits_succeeded": 0,
+            "bug_tickets": []
+        });
+
+        // The second QA report rewords `actual` but is the same failure.
         let client = StubLlmClient::new(vec![
             engineer_resp.to_string(),
-            qa_fail.to_string(),
-            security_fail.to_string(),
-            engineer_resp.to_string(), // fix attempt 1
-            qa_fail.to_string(),
-            security_fail.to_string(),
-            engineer_resp.to_string(), // fix attempt 2
-            qa_fail.to_string(),
-            security_fail.to_string(),
+            qa_fail("panic").to_string(),
+            security_pass.to_string(),
+            engineer_resp.to_string(),
+            qa_fail("a panic again").to_string(),
+            security_pass.to_string(),
+            engineer_resp.to_string(),
+            qa_pass.to_string(),
+            security_pass.to_string(),
         ]);
 
         let orchestrator = Orchestrator::new(&client);
-        let spec = make_refined_spec();
+        let outcome = orchestrator.run_from_spec(&make_refined_spec()).await;
+        let PipelineOutcome::Built(artifact) = outcome else {
+            panic!("Expected Built, got {outcome:?}");
+        };
 
-        let outcome = orchestrator.run_from_spec(&spec).await;
-        match outcome {
-            PipelineOutcome::Failed(PipelineError::CircuitBreaker { attempts, summary }) => {
-                assert_eq!(attempts, 3);
-                assert!(!summary.is_empty());
-            }
-            other => panic!("Expected Failed(CircuitBreaker), got {:?}", other),
-        }
+        assert_eq!(artifact.build_iterations, 3);
+        assert_eq!(artifact.ticket_history.len(), 1);
+        let record = &artifact.ticket_history[0];
+        assert_eq!(record.occurrences, 2);
+        assert_eq!((record.first_iteration, record.last_iteration), (1, 2));
+        assert_eq!(record.ticket.actual, "a panic again");
+    }
+
+    #[tokio::test]
+    async fn extension_starts_from_the_published_code() {
+        let client = make_happy_path_client();
+        let PipelineOutcome::Built(previous) =
+            Orchestrator::new(&client).run(&make_request()).await
+        else {
+            panic!("first build failed");
+        };
+
+        let mut request = make_request();
+        request.spec.inputs = serde_json::json!({"value": "string", "unit": "string"});
+        let [_, engineer, qa, red_team] = happy_path_responses();
+        let client = StubLlmClient::new(vec![engineer, qa, red_team]);
+        let features = vec!["unit conversion".to_string()];
+
+        let outcome = Orchestrator::new(&client)
+            .run_extension(&request, &previous, &features)
+            .await;
+        let PipelineOutcome::Built(artifact) = outcome else {
+            panic!("Expected Built, got {outcome:?}");
+        };
+        assert_eq!(artifact.spec, request.spec);
+        assert_eq!(artifact.refined_spec.design_notes, "Simple tool");
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 3, "the Architect is skipped");
+        let prompt = &requests[0].messages[0].content;
+        assert!(
+            prompt.contains(&format!(
+                "Current code:\n{}",
+                previous.build_output.source_code
+            )),
+            "{prompt}"
+        );
+        assert!(prompt.contains("unit conversion"), "{prompt}");
+
+        let provenance = artifact.provenance.expect("provenance recorded");
+        assert_eq!(provenance.request_id.as_deref(), Some(request.id.as_str()));
+        assert_eq!(
+            provenance.agents.keys().collect::<Vec<_>>(),
+            [
+                "engineer",
+                "engineer_extend",
+                "engineer_fix",
+                "qa",
+                "red_team"
+            ]
+        );
     }
 
     #[tokio::test]
@@ -473,6 +1910,7 @@ mod tests {
             design_notes: "extend instead".into(),
             extend_target: Some("existing".into()),
             extend_features: Some(vec!["feat_a".into()]),
+            idempotent: false,
         };
 
         let client = StubLlmClient::constant("unused");
@@ -1,23 +1,58 @@
-use crate::cache::ToolCache;
+use chrono::Utc;
+use serde::Deserialize;
+use tracing::Instrument;
+
+use crate::cache::{ToolCache, ToolLocations, ToolLock, WasmDigest};
 use crate::error::PipelineError;
 use crate::types::BuildArtifact;
 
+/// Highest suffix tried for [`NameCollision::Suffix`].
+const MAX_NAME_SUFFIX: u32 = 99;
+
 /// Publishes build artifacts to local cache and (eventually) OCI registries.
 pub struct Publisher {
     cache: ToolCache,
+    on_collision: NameCollision,
+}
+
+/// What to do when a build is published under the name of a tool with a
+/// different spec (`[registry] on_name_collision`). Rebuilding the same
+/// spec always replaces the tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameCollision {
+    /// Fail the publish with [`PipelineError::NameCollision`].
+    Error,
+    /// Publish as `<name>_2`, `<name>_3`, …, recording the requested name in
+    /// [`BuildArtifact::requested_name`].
+    Suffix,
+    /// Replace the existing tool.
+    #[default]
+    Replace,
 }
 
 /// Result of publishing an artifact.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PublishResult {
     pub tool_name: String,
     pub local_path: std::path::PathBuf,
     pub oci_reference: Option<String>,
+    /// Digest of the published `tool.wasm`, when one was published.
+    pub wasm: Option<WasmDigest>,
 }
 
 impl Publisher {
     pub fn new(cache: ToolCache) -> Self {
-        Self { cache }
+        Self {
+            cache,
+            on_collision: NameCollision::default(),
+        }
+    }
+
+    /// Handle builds named like a different published tool per `policy`.
+    pub fn with_collision_policy(mut self, policy: NameCollision) -> Self {
+        self.on_collision = policy;
+        self
     }
 
     /// Initialize the publisher (creates cache directory).
@@ -28,18 +63,60 @@ impl Publisher {
     /// Publish a build artifact.
     ///
     /// Currently stores locally. OCI push will be added when registry
-    /// integration is implemented.
-    pub async fn publish(&self, artifact: &BuildArtifact) -> Result<PublishResult, PipelineError> {
-        let tool_name = artifact.spec.name.clone();
+    /// integration is implemented. In [`NameCollision::Suffix`] mode the
+    /// artifact may be renamed first.
+    ///
+    /// Publishes of one name run one at a time. The artifact is stamped
+    /// `published_at` on entry, so of two racing builds the later one wins
+    /// and the earlier is refused rather than rolling the tool back.
+    pub async fn publish(
+        &self,
+        artifact: &mut BuildArtifact,
+    ) -> Result<PublishResult, PipelineError> {
+        let span = publish_span(artifact);
+        async {
+            artifact.published_at = Some(Utc::now());
+            let claim = self.cache.lock(&artifact.spec.name).await;
+            self.claim_name(artifact).await?;
+            self.store(artifact, None, &claim).await
+        }
+        .instrument(span)
+        .await
+    }
 
-        // Store in local cache
-        let local_path = self.cache.store(artifact).await?;
+    /// Store `artifact` under the name `claim` was taken for, or under the
+    /// suffixed name [`Self::claim_name`] gave it, holding both locks.
+    async fn store(
+        &self,
+        artifact: &BuildArtifact,
+        wasm_path: Option<&std::path::Path>,
+        claim: &ToolLock,
+    ) -> Result<PublishResult, PipelineError> {
+        let tool_name = artifact.spec.name.clone();
 
-        tracing::info!(
-            tool = %tool_name,
-            path = %local_path.display(),
-            "Artifact published to local cache"
-        );
+        let renamed;
+        let lock = if claim.name() == tool_name {
+            claim
+        } else {
+            renamed = self.cache.lock(&tool_name).await;
+            &renamed
+        };
+        let (local_path, wasm) = self.cache.store_locked(lock, artifact, wasm_path).await?;
+
+        match &wasm {
+            Some(digest) => tracing::info!(
+                tool = %tool_name,
+                path = %local_path.display(),
+                wasm_sha256 = %digest.sha256,
+                wasm_size_bytes = digest.size_bytes,
+                "Artifact published to local cache with WASM binary"
+            ),
+            None => tracing::info!(
+                tool = %tool_name,
+                path = %local_path.display(),
+                "Artifact published to local cache"
+            ),
+        }
 
         // OCI publishing stub -- will be implemented in Phase 5
         let oci_reference = None;
@@ -51,34 +128,98 @@ impl Publisher {
             tool_name,
             local_path,
             oci_reference,
+            wasm,
         })
     }
 
+    /// Like [`Self::publish`], storing the compiled `tool.wasm` as well.
     pub async fn publish_with_wasm(
         &self,
-        artifact: &BuildArtifact,
+        artifact: &mut BuildArtifact,
         wasm_path: &std::path::Path,
     ) -> Result<PublishResult, PipelineError> {
-        let tool_name = artifact.spec.name.clone();
-
-        let local_path = self.cache.store(artifact).await?;
+        let span = publish_span(artifact);
+        async {
+            artifact.published_at = Some(Utc::now());
+            let claim = self.cache.lock(&artifact.spec.name).await;
+            self.claim_name(artifact).await?;
+            self.store(artifact, Some(wasm_path), &claim).await
+        }
+        .instrument(span)
+        .await
+    }
 
-        let cached_wasm = local_path.join("tool.wasm");
-        tokio::fs::copy(wasm_path, &cached_wasm).await?;
+    /// Like [`Self::publish_with_wasm`], for a new revision of the tool
+    /// published under `artifact`'s name, such as an extension of it. The
+    /// tool is replaced whatever the collision policy, since its spec was
+    /// changed on purpose.
+    pub async fn republish_with_wasm(
+        &self,
+        artifact: &mut BuildArtifact,
+        wasm_path: &std::path::Path,
+    ) -> Result<PublishResult, PipelineError> {
+        let span = publish_span(artifact);
+        async {
+            artifact.published_at = Some(Utc::now());
+            let lock = self.cache.lock(&artifact.spec.name).await;
+            self.store(artifact, Some(wasm_path), &lock).await
+        }
+        .instrument(span)
+        .await
+    }
 
-        tracing::info!(
-            tool = %tool_name,
-            path = %local_path.display(),
-            "Artifact published to local cache with WASM binary"
-        );
+    /// Apply the collision policy to `artifact`'s name.
+    async fn claim_name(&self, artifact: &mut BuildArtifact) -> Result<(), PipelineError> {
+        let name = artifact.spec.name.clone();
+        let Some(existing) = self.collision(artifact, &name).await? else {
+            return Ok(());
+        };
+        match self.on_collision {
+            NameCollision::Replace => {
+                tracing::info!(tool = %name, "Replacing a published tool with a different spec");
+                Ok(())
+            }
+            NameCollision::Error => Err(PipelineError::NameCollision {
+                name,
+                existing: existing.spec.description,
+            }),
+            NameCollision::Suffix => {
+                for n in 2..=MAX_NAME_SUFFIX {
+                    let candidate = format!("{name}_{n}");
+                    if self.collision(artifact, &candidate).await?.is_none() {
+                        tracing::info!(tool = %name, published_as = %candidate, "Name taken by a different tool; publishing with a suffix");
+                        tracing::Span::current().record("published_as", candidate.as_str());
+                        artifact.spec.name = candidate;
+                        artifact.requested_name = Some(name);
+                        return Ok(());
+                    }
+                }
+                // Every suffix is taken as well.
+                Err(PipelineError::NameCollision {
+                    name,
+                    existing: existing.spec.description,
+                })
+            }
+        }
+    }
 
-        Ok(PublishResult {
-            tool_name,
-            local_path,
-            oci_reference: None,
-        })
+    /// The published tool `name` when its spec differs from `artifact`'s
+    /// under that name.
+    async fn collision(
+        &self,
+        artifact: &BuildArtifact,
+        name: &str,
+    ) -> Result<Option<BuildArtifact>, PipelineError> {
+        let Some(existing) = self.cache.get(name).await? else {
+            return Ok(None);
+        };
+        let mut spec = artifact.spec.clone();
+        spec.name = name.to_string();
+        Ok((existing.spec.spec_hash() != spec.spec_hash()).then_some(existing))
     }
 
+    /// Push a published tool's component, policy and manifest to
+    /// `registry_url` with `oras`, recording the reference in its manifest.
     pub async fn push_oci(
         &self,
         artifact: &BuildArtifact,
@@ -95,7 +236,7 @@ impl Publisher {
 
         for path in [wasm_path, manifest_path.as_path(), policy_path.as_path()] {
             if !path.exists() {
-                return Err(PipelineError::PublishError(format!(
+                return Err(PipelineError::PublishFailed(format!(
                     "Required file missing: {}",
                     path.display()
                 )));
@@ -120,19 +261,24 @@ impl Publisher {
             .output()
             .await
             .map_err(|e| {
-                PipelineError::PublishError(format!(
+                PipelineError::PublishFailed(format!(
                     "Failed to run oras: {e}. Is it installed?"
                 ))
             })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(PipelineError::PublishError(format!(
+            return Err(PipelineError::PublishFailed(format!(
                 "oras push failed: {stderr}"
             )));
         }
 
         tracing::info!(tool = %tool_name, reference = %reference, "Pushed to OCI registry");
+        let locations = ToolLocations {
+            oci_reference: Some(reference.clone()),
+            source_repo_path: None,
+        };
+        self.cache.record_locations(tool_name, &locations).await?;
         Ok(reference)
     }
 
@@ -142,6 +288,16 @@ impl Publisher {
     }
 }
 
+/// The span a publish runs in; `published_as` is only set when the
+/// collision policy renamed the tool.
+fn publish_span(artifact: &BuildArtifact) -> tracing::Span {
+    tracing::info_span!(
+        "publish",
+        tool = %artifact.spec.name,
+        published_as = tracing::field::Empty,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,12 +322,15 @@ mod tests {
                 design_notes: "test".into(),
                 extend_target: None,
                 extend_features: None,
+                idempotent: false,
             },
             build_output: BuildOutput {
                 source_code: "fn main() {}".into(),
                 wit_definition: "package test:tool;".into(),
                 policy_yaml: "version: \"1.0\"".into(),
                 language: "rust".into(),
+                files: vec![],
+                change_summary: None,
             },
             qa_result: QaResult {
                 passed: true,
@@ -179,14 +338,23 @@ mod tests {
                 tests_passed: 5,
                 tests_failed: 0,
                 bug_tickets: vec![],
+                data_quality: vec![],
             },
             security_result: SecurityResult {
                 passed: true,
                 exploits_attempted: 6,
                 exploits_succeeded: 0,
                 bug_tickets: vec![],
+                data_quality: vec![],
             },
             build_iterations: 1,
+            ticket_history: vec![],
+            provenance: None,
+            requested_name: None,
+            revision: 0,
+            published_at: None,
+            iteration_outputs: vec![],
+            test_vectors: vec![],
         }
     }
 
@@ -197,7 +365,7 @@ mod tests {
         let publisher = Publisher::new(cache);
         publisher.init().await.unwrap();
 
-        let artifact = make_artifact();
+        let mut artifact = make_artifact();
 
         let wasm_dir = tmp.path().join("build");
         std::fs::create_dir_all(&wasm_dir).unwrap();
@@ -205,12 +373,15 @@ mod tests {
         std::fs::write(&wasm_path, b"fake wasm bytes").unwrap();
 
         let result = publisher
-            .publish_with_wasm(&artifact, &wasm_path)
+            .publish_with_wasm(&mut artifact, &wasm_path)
             .await
             .unwrap();
 
         assert_eq!(result.tool_name, "published_tool");
         assert!(result.local_path.join("tool.wasm").exists());
+        let digest = result.wasm.unwrap();
+        assert_eq!(digest.size_bytes, 15);
+        assert!(!digest.sha256.is_empty());
     }
 
     #[tokio::test]
@@ -220,8 +391,8 @@ mod tests {
         let publisher = Publisher::new(cache);
         publisher.init().await.unwrap();
 
-        let artifact = make_artifact();
-        let result = publisher.publish(&artifact).await.unwrap();
+        let mut artifact = make_artifact();
+        let result = publisher.publish(&mut artifact).await.unwrap();
 
         assert_eq!(result.tool_name, "published_tool");
         assert!(result.local_path.exists());
@@ -231,4 +402,134 @@ mod tests {
         let cached = publisher.cache().get("published_tool").await.unwrap();
         assert!(cached.is_some());
     }
+
+    /// `make_artifact` with another team's spec under the same name.
+    fn other_tool() -> BuildArtifact {
+        let mut artifact = make_artifact();
+        artifact.spec.description = "Someone else's tool".into();
+        artifact
+    }
+
+    async fn publisher_with(tmp: &TempDir, policy: NameCollision) -> Publisher {
+        let publisher =
+            Publisher::new(ToolCache::new(tmp.path().to_path_buf())).with_collision_policy(policy);
+        publisher.init().await.unwrap();
+        publisher.publish(&mut make_artifact()).await.unwrap();
+        publisher
+    }
+
+    #[tokio::test]
+    async fn collisions_replace_by_default() {
+        let tmp = TempDir::new().unwrap();
+        let publisher = publisher_with(&tmp, NameCollision::default()).await;
+
+        let result = publisher.publish(&mut other_tool()).await.unwrap();
+        assert_eq!(result.tool_name, "published_tool");
+        let cached = publisher.cache().get("published_tool").await.unwrap();
+        assert_eq!(cached.unwrap().spec.description, "Someone else's tool");
+    }
+
+    #[tokio::test]
+    async fn collisions_fail_in_error_mode() {
+        let tmp = TempDir::new().unwrap();
+        let publisher = publisher_with(&tmp, NameCollision::Error).await;
+
+        let err = publisher.publish(&mut other_tool()).await.unwrap_err();
+        assert_eq!(err.code(), "name_collision");
+        assert_eq!(
+            err.to_string(),
+            "a different tool named 'published_tool' is already published: A published tool"
+        );
+        // A rebuild of the same spec is not a collision.
+        publisher.publish(&mut make_artifact()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn republishing_replaces_the_tool_in_any_mode() {
+        let tmp = TempDir::new().unwrap();
+        let publisher = publisher_with(&tmp, NameCollision::Suffix).await;
+        let wasm_path = tmp.path().join("extended.wasm");
+        std::fs::write(&wasm_path, b"extended wasm").unwrap();
+
+        let mut extended = other_tool();
+        extended.revision = 1;
+        let result = publisher
+            .republish_with_wasm(&mut extended, &wasm_path)
+            .await
+            .unwrap();
+        assert_eq!(result.tool_name, "published_tool");
+        let cached = publisher.cache().get("published_tool").await.unwrap();
+        assert_eq!(cached.unwrap().revision, 1);
+    }
+
+    #[tokio::test]
+    async fn collisions_get_a_suffix_in_suffix_mode() {
+        let tmp = TempDir::new().unwrap();
+        let publisher = publisher_with(&tmp, NameCollision::Suffix).await;
+
+        let mut artifact = other_tool();
+        let result = publisher.publish(&mut artifact).await.unwrap();
+        assert_eq!(result.tool_name, "published_tool_2");
+        assert_eq!(artifact.spec.name, "published_tool_2");
+        let cached = publisher.cache().get("published_tool_2").await.unwrap();
+        assert_eq!(
+            cached.unwrap().requested_name.as_deref(),
+            Some("published_tool")
+        );
+
+        // Rebuilding either tool keeps its name; a third spec takes `_3`.
+        let result = publisher.publish(&mut other_tool()).await.unwrap();
+        assert_eq!(result.tool_name, "published_tool_2");
+        let result = publisher.publish(&mut make_artifact()).await.unwrap();
+        assert_eq!(result.tool_name, "published_tool");
+        let mut third = make_artifact();
+        third.spec.description = "A third tool".into();
+        let result = publisher.publish(&mut third).await.unwrap();
+        assert_eq!(result.tool_name, "published_tool_3");
+    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_publishes_leave_one_complete_tool() {
+        let tmp = TempDir::new().unwrap();
+        let tools = tmp.path().join("tools");
+        let publisher = std::sync::Arc::new(Publisher::new(ToolCache::new(tools.clone())));
+        publisher.init().await.unwrap();
+
+        let builds: Vec<_> = (0..10)
+            .map(|i| {
+                let wasm_path = tmp.path().join(format!("build_{i}.wasm"));
+                std::fs::write(&wasm_path, format!("wasm of build {i}")).unwrap();
+                let publisher = std::sync::Arc::clone(&publisher);
+                tokio::spawn(async move {
+                    let mut artifact = make_artifact();
+                    artifact.build_output.source_code = format!("// build {i}");
+                    publisher.publish_with_wasm(&mut artifact, &wasm_path).await
+                })
+            })
+            .collect();
+        let mut published = 0;
+        for build in builds {
+            match build.await.unwrap() {
+                Ok(_) => published += 1,
+                // Overtaken by a later build.
+                Err(e) => assert_eq!(e.code(), "publish_failed", "{e}"),
+            }
+        }
+        assert!(published > 0);
+
+        let cache = publisher.cache();
+        let tool_dir = tools.join("published_tool");
+        let cached = cache.get("published_tool").await.unwrap().unwrap();
+        let source = &cached.build_output.source_code;
+        let build = source.trim_start_matches("// build ");
+        assert_eq!(
+            &std::fs::read_to_string(tool_dir.join("source.rs")).unwrap(),
+            source
+        );
+        let wasm = std::fs::read(tool_dir.join("tool.wasm")).unwrap();
+        assert_eq!(wasm, format!("wasm of build {build}").into_bytes());
+        let digest = cache.wasm_digest("published_tool").await.unwrap().unwrap();
+        assert_eq!(digest.size_bytes, wasm.len() as u64);
+        assert_eq!(cache.list().await.unwrap(), ["published_tool"]);
+        assert_eq!(std::fs::read_dir(&tools).unwrap().count(), 1);
+    }
 }
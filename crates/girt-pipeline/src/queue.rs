@@ -1,13 +1,28 @@
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use girt_core::host::HostCapabilities;
+use girt_core::limits::SizeLimits;
+use girt_core::paths::{self, PathError};
+use serde::Serialize;
 
 use crate::compiler::WasmCompiler;
+use crate::config::{PipelineCaps, SamplingConfig};
 use crate::error::PipelineError;
+use crate::failures::{FailureReport, FailureStore};
 use crate::llm::LlmClient;
 use crate::metrics::PipelineMetrics;
 use crate::orchestrator::{Orchestrator, PipelineOutcome};
-use crate::publish::Publisher;
-use crate::types::{CapabilityRequest, RequestStatus};
+use crate::publish::{PublishResult, Publisher};
+use crate::static_checks::StaticChecks;
+use crate::tool_sync::ToolSync;
+use crate::types::{
+    BlockingPolicy, BuildArtifact, CapabilityRequest, CodingStandards, RequestStatus, WitVersion,
+};
 
 /// File-based queue for capability requests.
 ///
@@ -21,18 +36,94 @@ use crate::types::{CapabilityRequest, RequestStatus};
 /// ```
 ///
 /// Atomic file moves (rename) between directories prevent race conditions.
+///
+/// A failed request goes back to `pending/` with a `retry_after` time until
+/// it has been attempted `max_attempts` times, then to `failed/`.
+///
+/// A request whose spec is identical to one pending or in progress is not
+/// queued again (see [`EnqueueOutcome`]). Enqueues through one `Queue` are
+/// checked one at a time; separate processes queueing the same spec at the
+/// same instant may still both get in.
 pub struct Queue {
     base_dir: PathBuf,
+    limits: SizeLimits,
+    max_attempts: u32,
+    retry_delay: Duration,
+    /// Held from the duplicate check until the request file is written.
+    enqueue_lock: tokio::sync::Mutex<()>,
+}
+
+/// Builds of one request, including the first (`[pipeline] max_request_attempts`).
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 2;
+
+/// Wait before a failed request may be claimed again.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Longest error summary kept in a request's `last_error`.
+const MAX_ERROR_CHARS: usize = 500;
+
+/// What [`Queue::enqueue`] did with a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnqueueOutcome {
+    /// Written to `pending/`. `conflicts` are the ids of pending or
+    /// in-progress requests for the same tool name with a different spec;
+    /// whichever publishes second is renamed or rejected at publish time.
+    Queued { conflicts: Vec<String> },
+    /// A request with an identical spec is already pending or in progress,
+    /// with id `id`. Nothing was written.
+    AlreadyQueued { id: String },
+}
+
+/// How many requests are in each queue directory, from [`Queue::depths`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QueueDepths {
+    pub pending: usize,
+    pub in_progress: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// Where [`Queue::fail`] put a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOutcome {
+    /// Back in `pending/`, to be claimed after its retry time.
+    Retrying,
+    /// Out of attempts; moved to `failed/`.
+    Failed,
 }
 
 impl Queue {
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        Self {
+            base_dir,
+            limits: SizeLimits::default(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            enqueue_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Build a request at most `max_attempts` times before failing it.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Wait `delay` before claiming a failed request again.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Reject specs over `limits` at enqueue time (`[security]` settings).
+    pub fn with_limits(mut self, limits: SizeLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
-    /// Default queue location: ~/.girt/queue/
-    pub fn default_path() -> PathBuf {
-        dirs_path().join("queue")
+    /// Default queue location: `queue/` in the data directory (~/.girt/queue/).
+    pub fn default_path() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("queue"))
     }
 
     fn pending_dir(&self) -> PathBuf {
@@ -60,21 +151,96 @@ impl Queue {
         Ok(())
     }
 
-    /// Enqueue a new capability request.
-    pub async fn enqueue(&self, request: &CapabilityRequest) -> Result<(), PipelineError> {
+    /// Enqueue a new capability request, recording its spec hash.
+    ///
+    /// The spec is size-checked and validated first; oversized or malformed
+    /// specs never reach the queue. A spec identical to a pending or
+    /// in-progress request's is not queued twice.
+    pub async fn enqueue(
+        &self,
+        request: &CapabilityRequest,
+    ) -> Result<EnqueueOutcome, PipelineError> {
+        self.limits.check_spec(&serde_json::to_value(&request.spec)?)?;
+        request.spec.validate()?;
+        let mut request = request.clone();
+        let hash = request.spec.spec_hash();
+        request.spec_hash = Some(hash.clone());
+
+        let _lock = self.enqueue_lock.lock().await;
+        let active = self.active_requests().await?;
+        if let Some(existing) = active.iter().find(|r| r.current_spec_hash() == hash) {
+            tracing::info!(
+                id = %request.id,
+                existing = %existing.id,
+                "Identical request already queued; not enqueued again"
+            );
+            return Ok(EnqueueOutcome::AlreadyQueued {
+                id: existing.id.clone(),
+            });
+        }
+        let conflicts: Vec<String> = active
+            .into_iter()
+            .filter(|r| r.spec.name == request.spec.name)
+            .map(|r| r.id)
+            .collect();
+        if !conflicts.is_empty() {
+            tracing::warn!(
+                id = %request.id,
+                name = %request.spec.name,
+                ?conflicts,
+                "A different spec with this name is already queued"
+            );
+        }
+
         let filename = format!("{}.json", request.id);
         let path = self.pending_dir().join(&filename);
-        let json = serde_json::to_string_pretty(request)?;
+        let json = serde_json::to_string_pretty(&request)?;
         tokio::fs::write(&path, json).await?;
         tracing::info!(id = %request.id, path = %path.display(), "Request enqueued");
-        Ok(())
+        Ok(EnqueueOutcome::Queued { conflicts })
+    }
+
+    /// The pending or in-progress request whose spec hashes to `hash`, if
+    /// any (see [`CapabilitySpec::spec_hash`](girt_core::spec::CapabilitySpec::spec_hash)).
+    pub async fn find_by_spec_hash(
+        &self,
+        hash: &str,
+    ) -> Result<Option<CapabilityRequest>, PipelineError> {
+        Ok(self
+            .active_requests()
+            .await?
+            .into_iter()
+            .find(|r| r.current_spec_hash() == hash))
+    }
+
+    /// Pending and in-progress requests. A file that cannot be read as a
+    /// request, such as one being rewritten by a claim, is skipped.
+    async fn active_requests(&self) -> Result<Vec<CapabilityRequest>, PipelineError> {
+        let mut requests = Vec::new();
+        for dir in [self.pending_dir(), self.in_progress_dir()] {
+            for id in self.list_dir(&dir).await? {
+                let path = dir.join(format!("{id}.json"));
+                let parsed = match tokio::fs::read_to_string(&path).await {
+                    Ok(content) => serde_json::from_str(&content).map_err(PipelineError::from),
+                    Err(e) => Err(e.into()),
+                };
+                match parsed {
+                    Ok(request) => requests.push(request),
+                    Err(e) => {
+                        tracing::debug!(path = %path.display(), error = %e, "Skipping queue entry")
+                    }
+                }
+            }
+        }
+        Ok(requests)
     }
 
-    /// Claim the next pending request by atomically moving it to in_progress.
+    /// Claim the next pending request by atomically moving it to in_progress,
+    /// counting an attempt. Requests waiting to be retried are skipped.
     pub async fn claim_next(&self) -> Result<Option<CapabilityRequest>, PipelineError> {
         let mut entries = tokio::fs::read_dir(self.pending_dir()).await?;
 
-        // Find the first .json file (sorted by name for deterministic ordering)
+        // Sorted by name for deterministic ordering
         let mut files: Vec<PathBuf> = Vec::new();
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -84,14 +250,23 @@ impl Queue {
         }
         files.sort();
 
-        let Some(source_path) = files.first() else {
+        // Find the first request that is due
+        let now = Utc::now();
+        let mut due = None;
+        for path in &files {
+            let content = tokio::fs::read_to_string(path).await?;
+            let request: CapabilityRequest = serde_json::from_str(&content)?;
+            if request.retry_after.is_none_or(|at| at <= now) {
+                due = Some((path, request));
+                break;
+            }
+        }
+        let Some((source_path, mut request)) = due else {
             return Ok(None);
         };
-
-        // Read the request
-        let content = tokio::fs::read_to_string(source_path).await?;
-        let mut request: CapabilityRequest = serde_json::from_str(&content)?;
         request.status = RequestStatus::InProgress;
+        request.attempts += 1;
+        request.retry_after = None;
 
         // Atomic move to in_progress
         let filename = source_path
@@ -114,10 +289,40 @@ impl Queue {
             .await
     }
 
-    /// Mark a request as failed.
-    pub async fn fail(&self, request: &CapabilityRequest) -> Result<(), PipelineError> {
-        self.move_request(request, &self.in_progress_dir(), &self.failed_dir())
-            .await
+    /// Record that a claimed request failed with `error`. It is put back in
+    /// the queue with a retry time if it has attempts left, and otherwise
+    /// moved to failed/. Either way `error` is kept as its `last_error` and
+    /// `last_error_code`.
+    pub async fn fail(
+        &self,
+        request: &CapabilityRequest,
+        error: &PipelineError,
+    ) -> Result<FailOutcome, PipelineError> {
+        let mut request = request.clone();
+        request.last_error = Some(error_summary(&error.to_string()));
+        request.last_error_code = Some(error.code().to_string());
+        let (outcome, to_dir) = if request.attempts < self.max_attempts {
+            let delay = chrono::Duration::from_std(self.retry_delay).unwrap_or_default();
+            request.status = RequestStatus::Pending;
+            request.retry_after = Some(Utc::now() + delay);
+            tracing::info!(
+                id = %request.id,
+                attempts = request.attempts,
+                max_attempts = self.max_attempts,
+                retry_after = ?request.retry_after,
+                "Request will be retried"
+            );
+            (FailOutcome::Retrying, self.pending_dir())
+        } else {
+            request.status = RequestStatus::Failed;
+            (FailOutcome::Failed, self.failed_dir())
+        };
+
+        let path = self.in_progress_dir().join(format!("{}.json", request.id));
+        tokio::fs::write(&path, serde_json::to_string_pretty(&request)?).await?;
+        self.move_request(&request, &self.in_progress_dir(), &to_dir)
+            .await?;
+        Ok(outcome)
     }
 
     /// List pending request IDs.
@@ -130,6 +335,62 @@ impl Queue {
         self.list_dir(&self.in_progress_dir()).await
     }
 
+    /// Count the requests in each directory.
+    pub async fn depths(&self) -> Result<QueueDepths, PipelineError> {
+        Ok(QueueDepths {
+            pending: self.list_dir(&self.pending_dir()).await?.len(),
+            in_progress: self.list_dir(&self.in_progress_dir()).await?.len(),
+            completed: self.list_dir(&self.completed_dir()).await?.len(),
+            failed: self.list_dir(&self.failed_dir()).await?.len(),
+        })
+    }
+
+    /// Every request not yet completed: pending, in progress, and failed,
+    /// in that order and by ID within each.
+    pub async fn requests(&self) -> Result<Vec<CapabilityRequest>, PipelineError> {
+        let mut requests = Vec::new();
+        for dir in [
+            self.pending_dir(),
+            self.in_progress_dir(),
+            self.failed_dir(),
+        ] {
+            for id in self.list_dir(&dir).await? {
+                let content = tokio::fs::read_to_string(dir.join(format!("{id}.json"))).await?;
+                requests.push(serde_json::from_str(&content)?);
+            }
+        }
+        Ok(requests)
+    }
+
+    /// Move in_progress requests claimed at least `older_than` ago back to
+    /// pending. These are left behind by a consumer that crashed or was
+    /// killed mid-build; the interrupted run counted as an attempt when it
+    /// was claimed. Returns the recovered request IDs.
+    pub async fn recover_stale(&self, older_than: Duration) -> Result<Vec<String>, PipelineError> {
+        let mut recovered = Vec::new();
+        for id in self.list_in_progress().await? {
+            let path = self.in_progress_dir().join(format!("{id}.json"));
+            let age = tokio::fs::metadata(&path)
+                .await?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age < older_than {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&path).await?;
+            let mut request: CapabilityRequest = serde_json::from_str(&content)?;
+            request.status = RequestStatus::Pending;
+            tokio::fs::write(&path, serde_json::to_string_pretty(&request)?).await?;
+            tokio::fs::rename(&path, self.pending_dir().join(format!("{id}.json"))).await?;
+
+            tracing::warn!(id = %id, attempts = request.attempts, "Recovered stale in-progress request");
+            recovered.push(id);
+        }
+        Ok(recovered)
+    }
+
     async fn move_request(
         &self,
         request: &CapabilityRequest,
@@ -177,11 +438,55 @@ pub enum ProcessResult {
     Failed(PipelineError),
 }
 
+/// Called after a [`QueueConsumer`] publishes a built tool, e.g. to mirror
+/// it into a repo or load it into a runtime. Failures are the hook's to log;
+/// the request is already complete.
+pub trait PublishHook: Send + Sync {
+    fn published<'a>(
+        &'a self,
+        artifact: &'a BuildArtifact,
+        result: &'a PublishResult,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+}
+
+impl PublishHook for ToolSync {
+    fn published<'a>(
+        &'a self,
+        artifact: &'a BuildArtifact,
+        result: &'a PublishResult,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            let tool = &artifact.spec.name;
+            match self.sync(artifact, result).await {
+                Ok(outcome) => tracing::info!(tool = %tool, ?outcome, "Tool sync finished"),
+                Err(e) => tracing::warn!(tool = %tool, error = %e, "Tool sync failed (non-fatal)"),
+            }
+        })
+    }
+}
+
 pub struct QueueConsumer {
     queue: Queue,
     llm: Arc<dyn LlmClient>,
     publisher: Publisher,
     metrics: Arc<PipelineMetrics>,
+    /// Coding standards injected into the Engineer's system prompt.
+    coding_standards: CodingStandards,
+    /// Bounds on each request's pipeline overrides.
+    caps: PipelineCaps,
+    /// Per-agent sampling settings (`[pipeline.sampling]`).
+    sampling: SamplingConfig,
+    /// Checks on Engineer output (`[pipeline.static_checks]`).
+    static_checks: StaticChecks,
+    /// Which tickets fail a build (`[pipeline.blocking]`).
+    blocking: BlockingPolicy,
+    /// `girt:tool` world tools are built against (`[build] wit_version`).
+    wit_version: WitVersion,
+    hooks: Vec<Arc<dyn PublishHook>>,
+    /// Where failed builds leave their bundles; none are kept when unset.
+    failures: Option<FailureStore>,
+    /// Imports the runtime links, offered to the Engineer.
+    host_capabilities: HostCapabilities,
 }
 
 impl QueueConsumer {
@@ -196,13 +501,99 @@ impl QueueConsumer {
             llm,
             publisher,
             metrics,
+            coding_standards: CodingStandards::default(),
+            caps: PipelineCaps::default(),
+            sampling: SamplingConfig::default(),
+            static_checks: StaticChecks::default(),
+            blocking: BlockingPolicy::default(),
+            wit_version: WitVersion::default(),
+            hooks: Vec::new(),
+            failures: None,
+            host_capabilities: HostCapabilities::default(),
+        }
+    }
+
+    /// Attach coding standards to be passed to the Engineer agent.
+    pub fn with_standards(mut self, standards: CodingStandards) -> Self {
+        self.coding_standards = standards;
+        self
+    }
+
+    /// Cap requests' pipeline overrides at `caps` (`[pipeline.caps]`).
+    pub fn with_caps(mut self, caps: PipelineCaps) -> Self {
+        self.caps = caps;
+        self
+    }
+
+    /// Sample each agent with `sampling` (`[pipeline.sampling]`).
+    pub fn with_sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Run `checks` on Engineer output (`[pipeline.static_checks]`).
+    pub fn with_static_checks(mut self, checks: StaticChecks) -> Self {
+        self.static_checks = checks;
+        self
+    }
+
+    /// Pass builds whose tickets are all below `policy`'s thresholds
+    /// (`[pipeline.blocking]`).
+    pub fn with_blocking_policy(mut self, policy: BlockingPolicy) -> Self {
+        self.blocking = policy;
+        self
+    }
+
+    /// Build tools against `version` of the girt-tool world. The compiler
+    /// passed to [`Self::process`] should scaffold the same version.
+    pub fn with_wit_version(mut self, version: WitVersion) -> Self {
+        self.wit_version = version;
+        self
+    }
+
+    /// Offer the Engineer the imports in `capabilities`, as described by
+    /// `girt_runtime::host_capabilities`.
+    pub fn with_host_capabilities(mut self, capabilities: HostCapabilities) -> Self {
+        self.host_capabilities = capabilities;
+        self
+    }
+
+    /// Keep a bundle of each failed build in `store`.
+    pub fn with_failure_store(mut self, store: FailureStore) -> Self {
+        self.failures = Some(store);
+        self
+    }
+
+    /// The orchestrator for `request`, with its overrides applied.
+    fn orchestrator(&self, request: &CapabilityRequest) -> Orchestrator<'_> {
+        let orchestrator = Orchestrator::new(self.llm.as_ref())
+            .with_standards(self.coding_standards.clone())
+            .with_wit_version(self.wit_version)
+            .with_sampling(self.sampling)
+            .with_static_checks(self.static_checks)
+            .with_blocking_policy(self.blocking)
+            .with_host_capabilities(self.host_capabilities.clone())
+            .with_overrides(&request.pipeline_overrides.capped(&self.caps));
+        match &self.failures {
+            Some(store) => orchestrator.with_failure_store(store),
+            None => orchestrator,
         }
     }
 
+    /// Run `hook` after every successful publish, in the order added.
+    pub fn with_hook(mut self, hook: Arc<dyn PublishHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
     pub fn queue(&self) -> &Queue {
         &self.queue
     }
 
+    pub fn metrics(&self) -> &PipelineMetrics {
+        &self.metrics
+    }
+
     pub async fn process_next(
         &self,
         compiler: &WasmCompiler,
@@ -213,56 +604,76 @@ impl QueueConsumer {
             Some(r) => r,
             None => return Ok(None),
         };
+        self.process(&request, compiler, registry_url, tag)
+            .await
+            .map(Some)
+    }
 
+    /// Build, compile, and publish an already-claimed request.
+    pub async fn process(
+        &self,
+        request: &CapabilityRequest,
+        compiler: &WasmCompiler,
+        registry_url: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<ProcessResult, PipelineError> {
         self.metrics.record_build_started();
         tracing::info!(id = %request.id, name = %request.spec.name, "Processing request");
 
-        let orchestrator = Orchestrator::new(self.llm.as_ref());
-        let outcome = orchestrator.run(&request).await;
+        let outcome = self.orchestrator(request).run(request).await;
 
         match outcome {
-            PipelineOutcome::Built(artifact) => {
+            PipelineOutcome::Built(mut artifact) => {
                 let compile_input = crate::compiler::CompileInput {
                     source_code: artifact.build_output.source_code.clone(),
                     wit_definition: artifact.build_output.wit_definition.clone(),
                     tool_name: artifact.spec.name.clone(),
                     tool_version: "0.1.0".into(),
+                    files: artifact.build_output.files.clone(),
                 };
 
-                let compile_output = compiler.compile(&compile_input).await?;
+                let compile_output = match compiler.compile(&compile_input).await {
+                    Ok(output) => output,
+                    Err(e) => {
+                        self.keep_failure(&e, &FailureReport::compile_failure(&artifact, &e))
+                            .await;
+                        return Err(e);
+                    }
+                };
 
-                self.publisher
-                    .publish_with_wasm(&artifact, &compile_output.wasm_path)
+                let mut publish_result = self
+                    .publisher
+                    .publish_with_wasm(&mut artifact, &compile_output.wasm_path)
                     .await?;
 
-                let oci_reference = if let (Some(url), Some(t)) = (registry_url, tag) {
-                    Some(
+                if let (Some(url), Some(t)) = (registry_url, tag) {
+                    publish_result.oci_reference = Some(
                         self.publisher
                             .push_oci(&artifact, &compile_output.wasm_path, url, t)
                             .await?,
-                    )
-                } else {
-                    None
-                };
+                    );
+                }
 
-                self.queue.complete(&request).await?;
+                self.queue.complete(request).await?;
                 self.metrics
                     .record_build_completed(artifact.build_iterations);
+                self.run_hooks(&artifact, &publish_result).await;
 
-                Ok(Some(ProcessResult::Built {
+                Ok(ProcessResult::Built {
                     name: artifact.spec.name.clone(),
-                    oci_reference,
-                }))
+                    oci_reference: publish_result.oci_reference,
+                })
             }
             PipelineOutcome::RecommendExtend { target, features } => {
-                self.queue.complete(&request).await?;
+                self.queue.complete(request).await?;
                 self.metrics.record_recommend_extend();
-                Ok(Some(ProcessResult::Extended { target, features }))
+                Ok(ProcessResult::Extended { target, features })
             }
-            PipelineOutcome::Failed(e) => {
-                self.queue.fail(&request).await?;
+            PipelineOutcome::Failed(e, report) => {
+                self.keep_failure(&e, &report).await;
+                self.queue.fail(request, &e).await?;
                 self.metrics.record_build_failed();
-                Ok(Some(ProcessResult::Failed(e)))
+                Ok(ProcessResult::Failed(e))
             }
         }
     }
@@ -277,15 +688,15 @@ impl QueueConsumer {
 
         self.metrics.record_build_started();
 
-        let orchestrator = Orchestrator::new(self.llm.as_ref());
-        let outcome = orchestrator.run(&request).await;
+        let outcome = self.orchestrator(&request).run(&request).await;
 
         match outcome {
-            PipelineOutcome::Built(artifact) => {
-                self.publisher.publish(&artifact).await?;
+            PipelineOutcome::Built(mut artifact) => {
+                let publish_result = self.publisher.publish(&mut artifact).await?;
                 self.queue.complete(&request).await?;
                 self.metrics
                     .record_build_completed(artifact.build_iterations);
+                self.run_hooks(&artifact, &publish_result).await;
                 Ok(Some(ProcessResult::Built {
                     name: artifact.spec.name.clone(),
                     oci_reference: None,
@@ -296,19 +707,35 @@ impl QueueConsumer {
                 self.metrics.record_recommend_extend();
                 Ok(Some(ProcessResult::Extended { target, features }))
             }
-            PipelineOutcome::Failed(e) => {
-                self.queue.fail(&request).await?;
+            PipelineOutcome::Failed(e, report) => {
+                self.keep_failure(&e, &report).await;
+                self.queue.fail(&request, &e).await?;
                 self.metrics.record_build_failed();
                 Ok(Some(ProcessResult::Failed(e)))
             }
         }
     }
+
+    async fn keep_failure(&self, error: &PipelineError, report: &FailureReport) {
+        if let Some(store) = &self.failures {
+            store.record(error, report).await;
+        }
+    }
+
+    async fn run_hooks(&self, artifact: &BuildArtifact, result: &PublishResult) {
+        for hook in &self.hooks {
+            hook.published(artifact, result).await;
+        }
+    }
 }
 
-fn dirs_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".girt")
+/// The first line of `error`, cut to `MAX_ERROR_CHARS`.
+fn error_summary(error: &str) -> String {
+    let line = error.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(MAX_ERROR_CHARS) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
 }
 
 #[cfg(test)]
@@ -357,6 +784,126 @@ mod tests {
         assert_eq!(queue.list_in_progress().await.unwrap().len(), 1);
     }
 
+    #[tokio::test]
+    async fn depths_count_each_directory() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+        assert_eq!(queue.depths().await.unwrap(), QueueDepths::default());
+
+        queue.enqueue(&make_request("first_tool")).await.unwrap();
+        queue.enqueue(&make_request("second_tool")).await.unwrap();
+        queue.enqueue(&make_request("third_tool")).await.unwrap();
+        let claimed = queue.claim_next().await.unwrap().unwrap();
+        queue.complete(&claimed).await.unwrap();
+        queue.claim_next().await.unwrap().unwrap();
+
+        assert_eq!(
+            queue.depths().await.unwrap(),
+            QueueDepths {
+                pending: 1,
+                in_progress: 1,
+                completed: 1,
+                failed: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn identical_specs_are_queued_once() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+
+        let first = make_request("dup_tool");
+        let outcome = queue.enqueue(&first).await.unwrap();
+        assert_eq!(outcome, EnqueueOutcome::Queued { conflicts: vec![] });
+        let stored: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(tmp.path().join(format!("pending/{}.json", first.id)))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(stored["spec_hash"], first.spec.spec_hash());
+
+        // Still a duplicate once claimed.
+        queue.claim_next().await.unwrap().unwrap();
+        let again = make_request("dup_tool");
+        assert_eq!(
+            queue.enqueue(&again).await.unwrap(),
+            EnqueueOutcome::AlreadyQueued {
+                id: first.id.clone()
+            }
+        );
+        let found = queue.find_by_spec_hash(&again.spec.spec_hash()).await;
+        assert_eq!(found.unwrap().map(|r| r.id), Some(first.id));
+        assert!(queue.list_pending().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn same_name_with_a_different_spec_is_queued_with_a_conflict() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+
+        let first = make_request("dup_tool");
+        queue.enqueue(&first).await.unwrap();
+        let mut other = make_request("dup_tool");
+        other.spec.description = "Another take on the same tool".into();
+        assert_eq!(
+            queue.enqueue(&other).await.unwrap(),
+            EnqueueOutcome::Queued {
+                conflicts: vec![first.id]
+            }
+        );
+        assert_eq!(queue.list_pending().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn completed_requests_do_not_block_a_new_one() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+
+        queue.enqueue(&make_request("dup_tool")).await.unwrap();
+        let claimed = queue.claim_next().await.unwrap().unwrap();
+        queue.complete(&claimed).await.unwrap();
+
+        let again = make_request("dup_tool");
+        assert!(
+            queue
+                .find_by_spec_hash(&again.spec.spec_hash())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            queue.enqueue(&again).await.unwrap(),
+            EnqueueOutcome::Queued { conflicts: vec![] }
+        );
+        assert_eq!(queue.list_pending().await.unwrap(), [again.id]);
+    }
+
+    #[tokio::test]
+    async fn queue_files_record_the_request_source() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+
+        let mut request = make_request("cli_tool");
+        request.source = RequestSource::Cli;
+        queue.enqueue(&request).await.unwrap();
+        let path = tmp.path().join(format!("pending/{}.json", request.id));
+        let mut stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(stored["source"], "cli");
+
+        // A source this version does not know is built as an operator's.
+        stored["source"] = "scheduler".into();
+        std::fs::write(&path, stored.to_string()).unwrap();
+        let claimed = queue.claim_next().await.unwrap().unwrap();
+        assert_eq!(claimed.source, RequestSource::Operator);
+    }
+
     #[tokio::test]
     async fn complete_moves_to_completed() {
         let tmp = TempDir::new().unwrap();
@@ -373,18 +920,165 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn fail_moves_to_failed() {
+    async fn recover_stale_requeues_only_old_claims() {
         let tmp = TempDir::new().unwrap();
         let queue = Queue::new(tmp.path().to_path_buf());
         queue.init().await.unwrap();
 
+        queue.enqueue(&make_request("test_tool")).await.unwrap();
+        let claimed = queue.claim_next().await.unwrap().unwrap();
+
+        let recovered = queue.recover_stale(Duration::from_secs(3600)).await.unwrap();
+        assert!(recovered.is_empty());
+        assert_eq!(queue.list_in_progress().await.unwrap().len(), 1);
+
+        let recovered = queue.recover_stale(Duration::ZERO).await.unwrap();
+        assert_eq!(recovered, vec![claimed.id.clone()]);
+        let requeued = queue.claim_next().await.unwrap().unwrap();
+        assert_eq!(requeued.id, claimed.id);
+        assert_eq!(claimed.attempts, 1);
+        assert_eq!(requeued.attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn fail_moves_to_failed() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf()).with_max_attempts(1);
+        queue.init().await.unwrap();
+
         let request = make_request("bad_tool");
         queue.enqueue(&request).await.unwrap();
 
         let claimed = queue.claim_next().await.unwrap().unwrap();
-        queue.fail(&claimed).await.unwrap();
+        let error = PipelineError::BudgetExceeded { budget: 100 };
+        let outcome = queue.fail(&claimed, &error).await.unwrap();
+
+        assert_eq!(outcome, FailOutcome::Failed);
+        assert!(queue.list_in_progress().await.unwrap().is_empty());
+        assert!(queue.list_pending().await.unwrap().is_empty());
+        let failed: CapabilityRequest = serde_json::from_str(
+            &std::fs::read_to_string(tmp.path().join(format!("failed/{}.json", claimed.id)))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(failed.status, RequestStatus::Failed);
+        assert_eq!(
+            failed.last_error.as_deref(),
+            Some("token budget of 100 exhausted")
+        );
+        assert_eq!(failed.last_error_code.as_deref(), Some("budget_exceeded"));
+
+        let listed = queue.requests().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].status, RequestStatus::Failed);
+        assert_eq!(
+            listed[0].last_error_code.as_deref(),
+            Some("budget_exceeded")
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_requests_are_retried_until_attempts_run_out() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf())
+            .with_max_attempts(3)
+            .with_retry_delay(Duration::ZERO);
+        queue.init().await.unwrap();
+        queue.enqueue(&make_request("flaky_tool")).await.unwrap();
+
+        let errors = [
+            PipelineError::CompileFailed {
+                stage: "cargo-component build".into(),
+                stderr_tail: "error[E0425]: ...\nerror: aborting".into(),
+            },
+            PipelineError::LlmTransport("connection reset".into()),
+        ];
+        for error in &errors {
+            let claimed = queue.claim_next().await.unwrap().unwrap();
+            let outcome = queue.fail(&claimed, error).await.unwrap();
+            assert_eq!(outcome, FailOutcome::Retrying);
+        }
+
+        let claimed = queue.claim_next().await.unwrap().unwrap();
+        assert_eq!(claimed.attempts, 3);
+        assert_eq!(
+            claimed.last_error.as_deref(),
+            Some("LLM request failed: connection reset")
+        );
+        assert_eq!(claimed.last_error_code.as_deref(), Some("llm_transport"));
+        queue.complete(&claimed).await.unwrap();
 
+        assert!(queue.list_pending().await.unwrap().is_empty());
         assert!(queue.list_in_progress().await.unwrap().is_empty());
+        let completed: CapabilityRequest = serde_json::from_str(
+            &std::fs::read_to_string(tmp.path().join(format!("completed/{}.json", claimed.id)))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(completed.attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn retries_wait_for_their_retry_time() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+        queue.enqueue(&make_request("alpha")).await.unwrap();
+        queue.enqueue(&make_request("beta")).await.unwrap();
+
+        let claimed = queue.claim_next().await.unwrap().unwrap();
+        assert_eq!(
+            queue
+                .fail(&claimed, &PipelineError::QueueError("boom".into()))
+                .await
+                .unwrap(),
+            FailOutcome::Retrying
+        );
+        assert_eq!(queue.list_pending().await.unwrap().len(), 2);
+
+        // The retry is not due for a minute, so the other request goes first
+        // and then nothing is claimable.
+        let next = queue.claim_next().await.unwrap().unwrap();
+        assert_ne!(next.id, claimed.id);
+        assert!(queue.claim_next().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn error_summary_keeps_the_first_line_within_bounds() {
+        assert_eq!(error_summary("first\nsecond"), "first");
+        let long = error_summary(&"é".repeat(MAX_ERROR_CHARS + 10));
+        assert_eq!(long.chars().count(), MAX_ERROR_CHARS + 3);
+    }
+
+    #[tokio::test]
+    async fn enqueue_rejects_invalid_spec() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf());
+        queue.init().await.unwrap();
+
+        let request = make_request("Not A Valid Name");
+        let err = queue.enqueue(&request).await.unwrap_err();
+        assert!(matches!(err, PipelineError::InvalidSpec(_)));
+        assert!(queue.claim_next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn enqueue_rejects_oversized_spec() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().to_path_buf()).with_limits(SizeLimits {
+            max_spec_bytes: 512,
+            ..Default::default()
+        });
+        queue.init().await.unwrap();
+
+        let mut request = make_request("padded");
+        request.spec.inputs = serde_json::json!({ "blob": "x".repeat(1024) });
+        let err = queue.enqueue(&request).await.unwrap_err();
+        assert!(
+            matches!(&err, PipelineError::TooLarge(e) if e.setting == "max_spec_bytes"),
+            "{err}"
+        );
+        assert!(queue.claim_next().await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -455,8 +1149,8 @@ mod tests {
             "design_notes": "Simple tool"
         });
         let engineer_resp = serde_json::json!({
-            "source_code": "fn main() {}",
-            "wit_definition": "package test:tool;",
+            "source_code": crate::static_checks::clean_source("stub"),
+            "wit_definition": "",
             "policy_yaml": "version: \"1.0\"",
             "language": "rust"
         });
@@ -489,4 +1183,117 @@ mod tests {
         assert_eq!(snap.builds_started, 1);
         assert_eq!(snap.builds_completed, 1);
     }
+
+    #[tokio::test]
+    async fn queue_consumer_caps_request_overrides() {
+        use crate::cache::ToolCache;
+        use crate::llm::StubLlmClient;
+        use crate::metrics::PipelineMetrics;
+        use crate::publish::Publisher;
+        use crate::types::{PipelineOverrides, TargetLanguage};
+        use std::sync::Arc;
+
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().join("queue"));
+        queue.init().await.unwrap();
+        let publisher = Publisher::new(ToolCache::new(tmp.path().join("tools")));
+        publisher.init().await.unwrap();
+
+        // Passes as an Engineer build and as QA and Red Team verdicts; the
+        // Architect cannot parse it and passes the spec through.
+        let llm = Arc::new(StubLlmClient::constant(
+            &serde_json::json!({
+                "source_code": crate::static_checks::clean_source("stub"),
+                "wit_definition": "",
+                "policy_yaml": "version: \"1.0\"",
+                "language": "rust",
+                "passed": true,
+                "tests_run": 1,
+                "tests_passed": 1,
+                "tests_failed": 0,
+                "exploits_attempted": 1,
+                "exploits_succeeded": 0,
+                "bug_tickets": []
+            })
+            .to_string(),
+        ));
+        let consumer = QueueConsumer::new(
+            queue,
+            llm.clone(),
+            publisher,
+            Arc::new(PipelineMetrics::new()),
+        )
+        .with_caps(PipelineCaps {
+            token_budget: 1_000,
+            ..Default::default()
+        });
+
+        let request = make_request("test_tool").with_overrides(PipelineOverrides {
+            target_language: Some(TargetLanguage::Go),
+            token_budget: Some(1_000_000),
+            ..Default::default()
+        });
+        consumer.queue().enqueue(&request).await.unwrap();
+        let result = consumer.process_next_no_compile().await.unwrap();
+        assert!(matches!(result, Some(ProcessResult::Built { .. })));
+
+        let requests = llm.requests();
+        // The budget is cut to the cap, and Go is not an allowed language.
+        assert!(requests.iter().all(|r| r.max_tokens <= 1_000));
+        assert_eq!(requests[0].max_tokens, 1_000);
+        assert!(requests.iter().all(|r| !r.system_prompt.contains("TinyGo")));
+    }
+
+    #[tokio::test]
+    async fn queue_consumer_keeps_a_bundle_of_failed_builds() {
+        use crate::cache::ToolCache;
+        use crate::llm::StubLlmClient;
+        use crate::metrics::PipelineMetrics;
+        use crate::publish::Publisher;
+        use std::sync::Arc;
+
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().join("queue"));
+        queue.init().await.unwrap();
+        let publisher = Publisher::new(ToolCache::new(tmp.path().join("tools")));
+        publisher.init().await.unwrap();
+
+        // A build that QA fails every time.
+        let llm = Arc::new(StubLlmClient::constant(
+            &serde_json::json!({
+                "source_code": crate::static_checks::clean_source("stub"),
+                "wit_definition": "",
+                "policy_yaml": "version: \"1.0\"",
+                "language": "rust",
+                "passed": false,
+                "tests_run": 1,
+                "tests_passed": 0,
+                "tests_failed": 1,
+                "exploits_attempted": 0,
+                "exploits_succeeded": 0,
+                "bug_tickets": [{
+                    "target": "engineer",
+                    "ticket_type": "functional_defect",
+                    "input": {},
+                    "expected": "greeting",
+                    "actual": "nothing",
+                    "remediation_directive": "Return a greeting"
+                }]
+            })
+            .to_string(),
+        ));
+        let consumer = QueueConsumer::new(queue, llm, publisher, Arc::new(PipelineMetrics::new()))
+            .with_failure_store(FailureStore::new(tmp.path().join("failures")));
+
+        let request = make_request("test_tool");
+        consumer.queue().enqueue(&request).await.unwrap();
+        let result = consumer.process_next_no_compile().await.unwrap();
+        assert!(matches!(result, Some(ProcessResult::Failed(_))));
+
+        let store = FailureStore::new(tmp.path().join("failures"));
+        let bundles = store.list().await.unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].id, request.id);
+        assert_eq!(bundles[0].error_code, "circuit_breaker");
+    }
 }
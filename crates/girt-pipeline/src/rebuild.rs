@@ -0,0 +1,488 @@
+//! Rebuilding published tools from their stored specs (`girt tools
+//! rebuild`), so prompt and model changes reach tools built before them.
+//!
+//! A rebuild runs the spec in the tool's manifest through the pipeline as an
+//! operator request. The Creation Gate is not consulted, since the tool was
+//! approved when it was first built, but the new policy may grant nothing
+//! beyond that spec. A successful rebuild replaces the tool under its name
+//! with its [`BuildArtifact::revision`] bumped, then runs the publish hooks.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use girt_core::host::HostCapabilities;
+use serde::Serialize;
+
+use crate::cache::ToolCache;
+use crate::compiler::{CompileInput, WasmCompiler};
+use crate::config::SamplingConfig;
+use crate::error::PipelineError;
+use crate::failures::{FailureReport, FailureStore};
+use crate::llm::LlmClient;
+use crate::orchestrator::{Orchestrator, PipelineOutcome};
+use crate::publish::Publisher;
+use crate::queue::PublishHook;
+use crate::static_checks::StaticChecks;
+use crate::types::{
+    BlockingPolicy, BuildArtifact, CapabilityRequest, CodingStandards, PolicyYaml, RequestSource,
+    WitVersion,
+};
+
+/// What became of one tool's rebuild.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RebuildOutcome {
+    /// Republished as `revision`.
+    Built { revision: u32, wasm_sha256: String },
+    /// The published tool is unchanged.
+    Failed { error: String },
+    /// Not rebuilt, e.g. because it was published recently.
+    Skipped { reason: String },
+}
+
+/// A tool's [`RebuildOutcome`], as listed by [`Rebuilder::rebuild_all`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RebuildReport {
+    pub name: String,
+    #[serde(flatten)]
+    pub outcome: RebuildOutcome,
+}
+
+/// Rebuilds published tools through the pipeline and republishes them.
+pub struct Rebuilder {
+    llm: Arc<dyn LlmClient>,
+    publisher: Publisher,
+    coding_standards: CodingStandards,
+    sampling: SamplingConfig,
+    static_checks: StaticChecks,
+    blocking: BlockingPolicy,
+    wit_version: WitVersion,
+    hooks: Vec<Arc<dyn PublishHook>>,
+    failures: Option<FailureStore>,
+    host_capabilities: HostCapabilities,
+}
+
+impl Rebuilder {
+    /// Rebuild the tools in `cache`. Rebuilds replace the tool whatever
+    /// `[registry] on_name_collision` says.
+    pub fn new(llm: Arc<dyn LlmClient>, cache: ToolCache) -> Self {
+        Self {
+            llm,
+            publisher: Publisher::new(cache),
+            coding_standards: CodingStandards::default(),
+            sampling: SamplingConfig::default(),
+            static_checks: StaticChecks::default(),
+            blocking: BlockingPolicy::default(),
+            wit_version: WitVersion::default(),
+            hooks: Vec::new(),
+            failures: None,
+            host_capabilities: HostCapabilities::default(),
+        }
+    }
+
+    /// Attach coding standards to be passed to the Engineer agent.
+    pub fn with_standards(mut self, standards: CodingStandards) -> Self {
+        self.coding_standards = standards;
+        self
+    }
+
+    /// Sample each agent with `sampling` (`[pipeline.sampling]`).
+    pub fn with_sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Run `checks` on Engineer output (`[pipeline.static_checks]`).
+    pub fn with_static_checks(mut self, checks: StaticChecks) -> Self {
+        self.static_checks = checks;
+        self
+    }
+
+    /// Pass rebuilds whose tickets are all below `policy`'s thresholds
+    /// (`[pipeline.blocking]`).
+    pub fn with_blocking_policy(mut self, policy: BlockingPolicy) -> Self {
+        self.blocking = policy;
+        self
+    }
+
+    /// Build against `version` of the girt-tool world.
+    pub fn with_wit_version(mut self, version: WitVersion) -> Self {
+        self.wit_version = version;
+        self
+    }
+
+    /// Offer the Engineer the imports in `capabilities`, as described by
+    /// `girt_runtime::host_capabilities`.
+    pub fn with_host_capabilities(mut self, capabilities: HostCapabilities) -> Self {
+        self.host_capabilities = capabilities;
+        self
+    }
+
+    /// Keep a bundle of each failed rebuild in `store`.
+    pub fn with_failure_store(mut self, store: FailureStore) -> Self {
+        self.failures = Some(store);
+        self
+    }
+
+    /// Run `hook` after every republish, in the order added.
+    pub fn with_hook(mut self, hook: Arc<dyn PublishHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Rebuild every published tool, in name order. With `older_than`,
+    /// tools published more recently are skipped.
+    pub async fn rebuild_all(
+        &self,
+        compiler: &WasmCompiler,
+        older_than: Option<Duration>,
+    ) -> Result<Vec<RebuildReport>, PipelineError> {
+        let mut reports = Vec::new();
+        for name in self.publisher.cache().list().await? {
+            let outcome = self.rebuild(&name, compiler, older_than).await;
+            reports.push(RebuildReport { name, outcome });
+        }
+        Ok(reports)
+    }
+
+    /// Rebuild the published tool `name`. With `older_than`, it is skipped
+    /// if it was published more recently. Tools published before
+    /// [`BuildArtifact::published_at`] was recorded always count as older.
+    pub async fn rebuild(
+        &self,
+        name: &str,
+        compiler: &WasmCompiler,
+        older_than: Option<Duration>,
+    ) -> RebuildOutcome {
+        let previous = match self.publisher.cache().get(name).await {
+            Ok(Some(previous)) => previous,
+            Ok(None) => {
+                return RebuildOutcome::Failed {
+                    error: format!("no published tool named '{name}'"),
+                };
+            }
+            Err(e) => {
+                return RebuildOutcome::Failed {
+                    error: e.to_string(),
+                };
+            }
+        };
+        if let (Some(age), Some(published_at)) = (older_than, previous.published_at) {
+            let cutoff = Utc::now() - chrono::Duration::from_std(age).unwrap_or_default();
+            if published_at > cutoff {
+                return RebuildOutcome::Skipped {
+                    reason: format!(
+                        "published {}",
+                        published_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+                    ),
+                };
+            }
+        }
+
+        tracing::info!(tool = %name, revision = previous.revision, "Rebuilding tool");
+        match self.build(previous, compiler).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::warn!(tool = %name, error = %e, "Rebuild failed; tool unchanged");
+                RebuildOutcome::Failed {
+                    error: e.to_string(),
+                }
+            }
+        }
+    }
+
+    async fn build(
+        &self,
+        previous: BuildArtifact,
+        compiler: &WasmCompiler,
+    ) -> Result<RebuildOutcome, PipelineError> {
+        let name = previous.spec.name.clone();
+        let request = CapabilityRequest::new(previous.spec.clone(), RequestSource::Operator);
+        let orchestrator = Orchestrator::new(self.llm.as_ref())
+            .with_standards(self.coding_standards.clone())
+            .with_sampling(self.sampling)
+            .with_static_checks(self.static_checks)
+            .with_blocking_policy(self.blocking)
+            .with_wit_version(self.wit_version)
+            .with_host_capabilities(self.host_capabilities.clone());
+        let outcome = match &self.failures {
+            Some(store) => orchestrator.with_failure_store(store),
+            None => orchestrator,
+        }
+        .run(&request)
+        .await;
+        let mut artifact = match outcome {
+            PipelineOutcome::Built(artifact) => artifact,
+            PipelineOutcome::RecommendExtend { target, .. } => {
+                return Ok(RebuildOutcome::Skipped {
+                    reason: format!("the Architect recommends extending '{target}' instead"),
+                });
+            }
+            PipelineOutcome::Failed(e, report) => {
+                self.keep_failure(&e, &report).await;
+                return Err(e);
+            }
+        };
+
+        // Published under its old name, even if the Architect renamed it.
+        artifact.spec.name = name.clone();
+        artifact.refined_spec.spec.name = name.clone();
+        artifact.requested_name = previous.requested_name;
+        artifact.revision = previous.revision + 1;
+
+        let violations = PolicyYaml::violations(&artifact.build_output.policy_yaml, &previous.spec);
+        if !violations.is_empty() {
+            let e = PipelineError::PolicyViolation(violations.join("; "));
+            self.keep_failure(&e, &FailureReport::compile_failure(&artifact, &e))
+                .await;
+            return Err(e);
+        }
+
+        let compile_input = CompileInput {
+            source_code: artifact.build_output.source_code.clone(),
+            wit_definition: artifact.build_output.wit_definition.clone(),
+            tool_name: name,
+            tool_version: "0.1.0".into(),
+            files: artifact.build_output.files.clone(),
+        };
+        let compiled = match compiler.compile(&compile_input).await {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                self.keep_failure(&e, &FailureReport::compile_failure(&artifact, &e))
+                    .await;
+                return Err(e);
+            }
+        };
+
+        let published = self
+            .publisher
+            .publish_with_wasm(&mut artifact, &compiled.wasm_path)
+            .await?;
+        for hook in &self.hooks {
+            hook.published(&artifact, &published).await;
+        }
+        Ok(RebuildOutcome::Built {
+            revision: artifact.revision,
+            wasm_sha256: published.wasm.map(|w| w.sha256).unwrap_or_default(),
+        })
+    }
+
+    async fn keep_failure(&self, error: &PipelineError, report: &FailureReport) {
+        if let Some(store) = &self.failures {
+            store.record(error, report).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::BuildSandbox;
+    use crate::llm::StubLlmClient;
+    use crate::types::RefinedSpec;
+    use girt_core::spec::{CapabilitySpec, InputType};
+    use std::pin::Pin;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    fn spec(name: &str) -> CapabilitySpec {
+        CapabilitySpec::builder()
+            .name(name)
+            .description(format!("Test tool: {name}"))
+            .input("value", InputType::String, true)
+            .output("result", InputType::String)
+            .build()
+            .unwrap()
+    }
+
+    /// Passes as an Engineer build, a QA run, and a Red Team audit; as an
+    /// Architect answer it is invalid, so the stored spec is built as is.
+    fn stub_llm(policy_yaml: &str) -> Arc<StubLlmClient> {
+        Arc::new(StubLlmClient::constant(
+            &serde_json::json!({
+                "source_code": crate::static_checks::clean_source("rebuilt"),
+                "wit_definition": "",
+                "policy_yaml": policy_yaml,
+                "language": "rust",
+                "passed": true,
+                "tests_run": 1,
+                "tests_passed": 1,
+                "tests_failed": 0,
+                "exploits_attempted": 1,
+                "exploits_succeeded": 0,
+                "bug_tickets": []
+            })
+            .to_string(),
+        ))
+    }
+
+    #[cfg(unix)]
+    fn compiler(dir: &std::path::Path) -> WasmCompiler {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("fake-cargo-component");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nout=\"${CARGO_TARGET_DIR:-target}/wasm32-wasip1/release\"\n\
+             mkdir -p \"$out\" && printf wasm > \"$out/$(basename \"$PWD\").wasm\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        WasmCompiler::new()
+            .with_cache_dir(dir.join("build-cache"))
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(script.display().to_string())
+    }
+
+    /// Publish `name` as a first build made from `// original`.
+    async fn seed(cache: &ToolCache, name: &str, published_at: Option<chrono::DateTime<Utc>>) {
+        let spec = spec(name);
+        let mut artifact: BuildArtifact = serde_json::from_value(serde_json::json!({
+            "spec": spec,
+            "refined_spec": RefinedSpec {
+                action: crate::types::SpecAction::Build,
+                spec: spec.clone(),
+                design_notes: String::new(),
+                extend_target: None,
+                extend_features: None,
+                idempotent: false,
+            },
+            "build_output": {
+                "source_code": "// original",
+                "wit_definition": "",
+                "policy_yaml": "version: \"1.0\"",
+                "language": "rust"
+            },
+            "qa_result": {"passed": true, "tests_run": 1, "tests_passed": 1, "tests_failed": 0, "bug_tickets": []},
+            "security_result": {"passed": true, "exploits_attempted": 1, "exploits_succeeded": 0, "bug_tickets": []},
+            "build_iterations": 1
+        }))
+        .unwrap();
+        artifact.published_at = published_at;
+        cache.init().await.unwrap();
+        cache.store(&artifact).await.unwrap();
+    }
+
+    /// Records the tools it is told were published.
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl PublishHook for Recorder {
+        fn published<'a>(
+            &'a self,
+            artifact: &'a BuildArtifact,
+            _result: &'a crate::publish::PublishResult,
+        ) -> Pin<Box<dyn std::future::Future<Output = ()> + Send + 'a>> {
+            Box::pin(async move {
+                self.0.lock().unwrap().push(artifact.spec.name.clone());
+            })
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rebuild_republishes_with_a_bumped_revision() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        seed(&cache, "tool_a", None).await;
+        let hook = Arc::new(Recorder::default());
+        let rebuilder = Rebuilder::new(
+            stub_llm("version: \"1.0\""),
+            ToolCache::new(tmp.path().join("tools")),
+        )
+        .with_hook(hook.clone());
+
+        let outcome = rebuilder
+            .rebuild("tool_a", &compiler(tmp.path()), None)
+            .await;
+        assert!(
+            matches!(outcome, RebuildOutcome::Built { revision: 1, .. }),
+            "{outcome:?}"
+        );
+        let rebuilt = cache.get("tool_a").await.unwrap().unwrap();
+        assert_eq!(rebuilt.revision, 1);
+        assert!(rebuilt.build_output.source_code.starts_with("// rebuilt"));
+        assert!(rebuilt.published_at.is_some());
+        assert_eq!(
+            rebuilt.provenance.unwrap().request_source,
+            Some(RequestSource::Operator)
+        );
+        assert!(tmp.path().join("tools/tool_a/tool.wasm").exists());
+        assert_eq!(*hook.0.lock().unwrap(), ["tool_a"]);
+
+        let outcome = rebuilder
+            .rebuild("tool_a", &compiler(tmp.path()), None)
+            .await;
+        assert!(matches!(outcome, RebuildOutcome::Built { revision: 2, .. }));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rebuild_all_skips_recent_tools_and_reports_failures() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        let month_ago = Utc::now() - chrono::Duration::days(40);
+        seed(&cache, "old_tool", Some(month_ago)).await;
+        seed(&cache, "new_tool", Some(Utc::now())).await;
+        seed(&cache, "unstamped_tool", None).await;
+        let rebuilder = Rebuilder::new(stub_llm("version: \"1.0\""), cache);
+
+        let reports = rebuilder
+            .rebuild_all(
+                &compiler(tmp.path()),
+                Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            )
+            .await
+            .unwrap();
+        let statuses: Vec<(&str, &str)> = reports
+            .iter()
+            .map(|r| {
+                let status = match r.outcome {
+                    RebuildOutcome::Built { .. } => "built",
+                    RebuildOutcome::Failed { .. } => "failed",
+                    RebuildOutcome::Skipped { .. } => "skipped",
+                };
+                (r.name.as_str(), status)
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("new_tool", "skipped"),
+                ("old_tool", "built"),
+                ("unstamped_tool", "built")
+            ]
+        );
+
+        let json = serde_json::to_value(&reports[0]).unwrap();
+        assert_eq!(json["name"], "new_tool");
+        assert_eq!(json["status"], "skipped");
+
+        let missing = rebuilder
+            .rebuild("no_such_tool", &compiler(tmp.path()), None)
+            .await;
+        assert!(matches!(missing, RebuildOutcome::Failed { .. }));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rebuild_that_widens_the_policy_leaves_the_tool_alone() {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        seed(&cache, "tool_a", None).await;
+        let wider = "version: \"1.0\"\npermissions:\n  network:\n    allow:\n      - host: evil.example.com\n";
+        let rebuilder = Rebuilder::new(stub_llm(wider), ToolCache::new(tmp.path().join("tools")));
+
+        let outcome = rebuilder
+            .rebuild("tool_a", &compiler(tmp.path()), None)
+            .await;
+        match outcome {
+            RebuildOutcome::Failed { error } => assert!(error.contains("policy"), "{error}"),
+            other => panic!("expected a failed rebuild, got {other:?}"),
+        }
+        let kept = cache.get("tool_a").await.unwrap().unwrap();
+        assert_eq!(kept.revision, 0);
+        assert_eq!(kept.build_output.source_code, "// original");
+    }
+}
@@ -0,0 +1,230 @@
+//! Scratch directories for builds and tool sync.
+//!
+//! `ToolSync` clones into one and `WasmCompiler::without_cache` builds in
+//! one. Both hold generated source and, for a sync, repository
+//! credentials in `.git/config`, so they are kept out of the shared system
+//! temp dir: each is a randomly named `TempDir` under `tmp/` in the data
+//! directory (`~/.girt/tmp`), which is owner-only (0700) and must not be a
+//! symlink.
+//!
+//! A scratch dir is removed when dropped. One left behind by a crash, or
+//! kept on purpose (an uncached build's project dir), is removed by
+//! [`Scratch::sweep`] once it is older than [`STALE_AFTER`].
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use girt_core::paths::{self, PathError};
+use tempfile::TempDir;
+
+use crate::error::PipelineError;
+
+/// Age after which [`Scratch::sweep`] removes a scratch dir.
+pub const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Where scratch directories are created.
+#[derive(Debug, Clone)]
+pub struct Scratch {
+    /// `None` uses the system temp dir, when there is no data directory.
+    root: Option<PathBuf>,
+}
+
+impl Default for Scratch {
+    fn default() -> Self {
+        match Self::default_path() {
+            Ok(root) => Self::new(root),
+            Err(e) => {
+                tracing::warn!("Scratch directories go to the system temp dir: {e}");
+                Self { root: None }
+            }
+        }
+    }
+}
+
+impl Scratch {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: Some(root.into()),
+        }
+    }
+
+    /// Default root: `tmp/` in the data directory (`~/.girt/tmp`).
+    pub fn default_path() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("tmp"))
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// A new, empty, owner-only scratch dir named `{prefix}{random}`.
+    pub fn create(&self, prefix: &str) -> Result<TempDir, PipelineError> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(prefix);
+        #[cfg(unix)]
+        builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+        let Some(root) = &self.root else {
+            return Ok(builder.tempdir()?);
+        };
+        prepare_root(root)?;
+        Ok(builder.tempdir_in(root)?)
+    }
+
+    /// Remove scratch dirs last modified more than `max_age` ago. Returns
+    /// how many were removed.
+    pub fn sweep(&self, max_age: Duration) -> Result<usize, PipelineError> {
+        let Some(root) = &self.root else {
+            return Ok(0);
+        };
+        let entries = match std::fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        check_not_symlink(root)?;
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for entry in entries {
+            let entry = entry?;
+            let meta = entry.metadata()?;
+            let age = meta
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok());
+            if age.is_none_or(|age| age <= max_age) {
+                continue;
+            }
+            let path = entry.path();
+            let outcome = if meta.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+            match outcome {
+                Ok(()) => removed += 1,
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Cannot remove stale scratch dir")
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Create `root` if needed and restrict it to its owner. A symlink is
+/// refused rather than followed.
+fn prepare_root(root: &Path) -> Result<(), PipelineError> {
+    match std::fs::symlink_metadata(root) {
+        Ok(_) => check_not_symlink(root)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut builder = std::fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder.create(root)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(root)?.permissions().mode() & 0o777;
+        if mode != 0o700 {
+            std::fs::set_permissions(root, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_not_symlink(root: &Path) -> Result<(), PipelineError> {
+    let meta = std::fs::symlink_metadata(root)?;
+    if meta.file_type().is_symlink() {
+        return Err(PipelineError::IoError(std::io::Error::other(format!(
+            "scratch directory {} is a symlink",
+            root.display()
+        ))));
+    }
+    if !meta.is_dir() {
+        return Err(PipelineError::IoError(std::io::Error::other(format!(
+            "scratch directory {} is not a directory",
+            root.display()
+        ))));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scratch_dirs_are_created_under_the_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("girt/tmp");
+        let scratch = Scratch::new(&root);
+
+        let dir = scratch.create("girt-sync-weather-").unwrap();
+        assert_eq!(dir.path().parent(), Some(root.as_path()));
+        let name = dir.path().file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("girt-sync-weather-"));
+        assert!(name.len() > "girt-sync-weather-".len());
+
+        let path = dir.path().to_path_buf();
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn root_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("tmp");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::set_permissions(&root, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let dir = Scratch::new(&root).create("girt-build-").unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&root), 0o700);
+        assert_eq!(mode(dir.path()), 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_root_is_refused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let elsewhere = tmp.path().join("elsewhere");
+        std::fs::create_dir(&elsewhere).unwrap();
+        let root = tmp.path().join("tmp");
+        std::os::unix::fs::symlink(&elsewhere, &root).unwrap();
+
+        let scratch = Scratch::new(&root);
+        let err = scratch.create("girt-build-").unwrap_err();
+        assert!(err.to_string().contains("is a symlink"), "{err}");
+        assert!(scratch.sweep(Duration::ZERO).is_err());
+        assert_eq!(std::fs::read_dir(&elsewhere).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn sweep_removes_only_stale_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let scratch = Scratch::new(tmp.path().join("tmp"));
+        assert_eq!(scratch.sweep(STALE_AFTER).unwrap(), 0);
+
+        let orphan = scratch.create("girt-build-").unwrap().keep();
+        std::fs::write(orphan.join("lib.rs"), "fn run() {}").unwrap();
+        let live = scratch.create("girt-sync-").unwrap();
+
+        assert_eq!(scratch.sweep(STALE_AFTER).unwrap(), 0);
+        assert!(orphan.exists());
+
+        let day_old = SystemTime::now() - STALE_AFTER - Duration::from_secs(60);
+        std::fs::File::open(&orphan)
+            .unwrap()
+            .set_modified(day_old)
+            .unwrap();
+        assert_eq!(scratch.sweep(STALE_AFTER).unwrap(), 1);
+        assert!(!orphan.exists());
+        assert!(live.path().exists());
+    }
+}
@@ -0,0 +1,848 @@
+//! Capability specs from OpenAPI 3 documents.
+//!
+//! `girt spec from-openapi` turns operations of a REST API's OpenAPI
+//! document into a [`CapabilitySpec`], instead of restating the document
+//! by hand:
+//!
+//! - Path, query and header parameters, and the fields of a JSON request
+//!   body, become `inputs`. A body that is not an object becomes a single
+//!   `body` input. Credentials are left out.
+//! - The first 2xx response (`default` if there is none) becomes
+//!   `outputs`.
+//! - The server host becomes a `constraints.network` grant, scoped to the
+//!   operations' methods and to their paths up to the first template
+//!   parameter.
+//! - The schemes of the first security requirement become
+//!   `constraints.secrets`, named after the API's title
+//!   (`ISSUE_TRACKER_TOKEN` for a bearer token of "Issue Tracker API").
+//!
+//! Picking several operations makes a multi-action spec: an `action` input
+//! selects the operation, and the other inputs are the union of theirs.
+//!
+//! `$ref`s within the document are inlined, and a recursive one becomes
+//! `{"type": "object"}`. References to other documents are not supported.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use girt_core::spec::{
+    CapabilityConstraints, CapabilitySpec, HTTP_METHODS, MAX_DESCRIPTION_LEN, NetworkGrant,
+};
+use reqwest::Url;
+use serde_json::{Map, Value, json};
+
+use crate::error::PipelineError;
+
+/// How long [`OpenApiDocument::fetch`] waits for the document.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A parsed OpenAPI 3 document.
+#[derive(Debug, Clone)]
+pub struct OpenApiDocument {
+    root: Value,
+    /// Where the document was fetched from. Relative server URLs resolve
+    /// against it.
+    base: Option<Url>,
+}
+
+/// An operation of the document.
+struct Operation<'a> {
+    id: &'a str,
+    method: &'static str,
+    path: &'a str,
+    path_item: &'a Value,
+    op: &'a Value,
+}
+
+/// What an operation authenticates with.
+#[derive(Default)]
+struct Credentials {
+    /// `constraints.secrets` names.
+    secrets: Vec<String>,
+    /// `(in, name)` of the parameters API keys are sent in.
+    params: Vec<(String, String)>,
+}
+
+/// The inputs of one operation.
+#[derive(Default)]
+struct OperationInputs {
+    properties: Map<String, Value>,
+    required: Vec<String>,
+}
+
+impl OpenApiDocument {
+    /// Parse a YAML or JSON document.
+    pub fn parse(text: &str) -> Result<Self, PipelineError> {
+        let root = match serde_json::from_str(text) {
+            Ok(root) => root,
+            Err(_) => serde_yaml::from_str(text)
+                .map(yaml_to_json)
+                .map_err(|e| import_error(format!("not a YAML or JSON document: {e}")))?,
+        };
+        let version = str_field(&root, "openapi");
+        if !version.starts_with("3.") {
+            return Err(import_error(
+                "only OpenAPI 3 documents are supported (expected `openapi: 3.x`)",
+            ));
+        }
+        Ok(Self { root, base: None })
+    }
+
+    /// Fetch and parse the document at `url`.
+    pub async fn fetch(url: &str) -> Result<Self, PipelineError> {
+        let base =
+            Url::parse(url).map_err(|e| import_error(format!("invalid URL '{url}': {e}")))?;
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| import_error(e.to_string()))?;
+        let text = client
+            .get(base.clone())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| import_error(format!("cannot fetch {url}: {e}")))?
+            .text()
+            .await
+            .map_err(|e| import_error(format!("cannot read {url}: {e}")))?;
+        Ok(Self {
+            base: Some(base),
+            ..Self::parse(&text)?
+        })
+    }
+
+    /// Every `operationId` in the document, in path order.
+    pub fn operation_ids(&self) -> Vec<&str> {
+        self.operations().map(|op| op.id).collect()
+    }
+
+    /// A spec for the operations named by `operation_ids`: single-action
+    /// for one, multi-action for several. The tool is named `name`, or
+    /// after the operation (one) or the API's title (several).
+    pub fn import(
+        &self,
+        operation_ids: &[impl AsRef<str>],
+        name: Option<&str>,
+    ) -> Result<CapabilitySpec, PipelineError> {
+        let mut operations: Vec<Operation> = Vec::new();
+        for id in operation_ids {
+            let id = id.as_ref();
+            if operations.iter().any(|op| op.id == id) {
+                continue;
+            }
+            let op = self.operations().find(|op| op.id == id).ok_or_else(|| {
+                import_error(format!(
+                    "no operation '{id}'; the document has: {}",
+                    self.operation_ids().join(", ")
+                ))
+            })?;
+            operations.push(op);
+        }
+        let [first, rest @ ..] = operations.as_slice() else {
+            return Err(import_error("no operation selected"));
+        };
+
+        let mut secrets = Vec::new();
+        let mut endpoints = Vec::new();
+        let mut actions = Vec::new();
+        for op in &operations {
+            let credentials = self.credentials(op)?;
+            for secret in credentials.secrets {
+                if !secrets.contains(&secret) {
+                    secrets.push(secret);
+                }
+            }
+            let (host, prefix) = self.endpoint(op)?;
+            endpoints.push((host, op.method, prefix));
+            actions.push((
+                snake_case(op.id),
+                self.inputs(op, &credentials.params)?,
+                self.output(op)?,
+            ));
+        }
+
+        let title = str_field(&self.root, "info/title");
+        let (default_name, description, inputs, outputs) = if rest.is_empty() {
+            let (action, inputs, output) = actions.swap_remove(0);
+            let description = describe(first);
+            (action, description, object_schema(inputs), output)
+        } else {
+            let described: Vec<String> = operations
+                .iter()
+                .zip(&actions)
+                .map(|(op, (action, ..))| format!("{action}: {}", describe(op)))
+                .collect();
+            let description = format!(
+                "{} operations. {}",
+                if title.is_empty() { "API" } else { title },
+                described.join("; ")
+            );
+            let (inputs, outputs) = merge_actions(actions)?;
+            (snake_case(title), description, inputs, outputs)
+        };
+
+        let spec = CapabilitySpec {
+            name: name.map_or(default_name, str::to_string),
+            description: description.chars().take(MAX_DESCRIPTION_LEN).collect(),
+            inputs,
+            outputs,
+            constraints: CapabilityConstraints {
+                network: network_grants(endpoints),
+                storage: vec![],
+                secrets,
+            },
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn operations(&self) -> impl Iterator<Item = Operation<'_>> {
+        let paths = self.root.get("paths").and_then(Value::as_object);
+        paths.into_iter().flatten().flat_map(|(path, path_item)| {
+            HTTP_METHODS.iter().filter_map(move |&method| {
+                let op = path_item.get(method.to_ascii_lowercase())?;
+                Some(Operation {
+                    id: op.get("operationId")?.as_str()?,
+                    method,
+                    path,
+                    path_item,
+                    op,
+                })
+            })
+        })
+    }
+
+    /// `op`'s parameters and request body fields, except the `Authorization`
+    /// header and the parameters in `credentials`.
+    fn inputs(
+        &self,
+        op: &Operation,
+        credentials: &[(String, String)],
+    ) -> Result<OperationInputs, PipelineError> {
+        // Operation parameters replace path-level ones of the same name.
+        let mut params: Vec<Value> = Vec::new();
+        for param in list(op.path_item.get("parameters")).chain(list(op.op.get("parameters"))) {
+            let param = self.resolve(param)?;
+            let key = |p: &Value| {
+                (
+                    str_field(p, "name").to_string(),
+                    str_field(p, "in").to_string(),
+                )
+            };
+            params.retain(|p| key(p) != key(&param));
+            params.push(param);
+        }
+
+        let mut inputs = OperationInputs::default();
+        for param in &params {
+            let (name, location) = (str_field(param, "name"), str_field(param, "in"));
+            let is_credential = (location == "header"
+                && name.eq_ignore_ascii_case("authorization"))
+                || credentials
+                    .iter()
+                    .any(|(l, n)| l == location && n.eq_ignore_ascii_case(name));
+            if name.is_empty() || location == "cookie" || is_credential {
+                continue;
+            }
+            let mut schema = match param.get("schema") {
+                Some(schema) => schema.clone(),
+                None => media_schema(param.get("content")).unwrap_or(json!({"type": "string"})),
+            };
+            if let (Some(description), Some(schema)) =
+                (param.get("description"), schema.as_object_mut())
+            {
+                schema
+                    .entry("description")
+                    .or_insert_with(|| description.clone());
+            }
+            if location == "path" || param.get("required") == Some(&Value::Bool(true)) {
+                inputs.required.push(name.to_string());
+            }
+            inputs.properties.insert(name.to_string(), schema);
+        }
+
+        let Some(body) = op.op.get("requestBody") else {
+            return Ok(inputs);
+        };
+        let body = self.resolve(body)?;
+        let body_required = body.get("required") == Some(&Value::Bool(true));
+        let Some(schema) = media_schema(body.get("content")) else {
+            return Ok(inputs);
+        };
+        let fields = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .filter(|fields| fields.keys().all(|f| !inputs.properties.contains_key(f)));
+        match fields {
+            Some(fields) => {
+                for (field, field_schema) in fields {
+                    if field_schema.get("readOnly") != Some(&Value::Bool(true)) {
+                        inputs
+                            .properties
+                            .insert(field.clone(), field_schema.clone());
+                    }
+                }
+                if body_required {
+                    let required = list(schema.get("required")).filter_map(Value::as_str);
+                    inputs.required.extend(required.map(str::to_string));
+                }
+            }
+            // Not an object, or its fields clash with parameters.
+            None => {
+                inputs.properties.insert("body".into(), schema);
+                if body_required {
+                    inputs.required.push("body".into());
+                }
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// The schema of `op`'s first 2xx response, else of its `default`
+    /// response. `{"type": "null"}` when that has no body.
+    fn output(&self, op: &Operation) -> Result<Value, PipelineError> {
+        let no_body = json!({"type": "null"});
+        let Some(responses) = op.op.get("responses").and_then(Value::as_object) else {
+            return Ok(no_body);
+        };
+        let code = responses
+            .keys()
+            .filter(|code| code.starts_with('2'))
+            .min()
+            .map_or("default", String::as_str);
+        let Some(response) = responses.get(code) else {
+            return Ok(no_body);
+        };
+        let response = self.resolve(response)?;
+        Ok(media_schema(response.get("content")).unwrap_or(no_body))
+    }
+
+    /// The credentials for the schemes of `op`'s first security requirement.
+    fn credentials(&self, op: &Operation) -> Result<Credentials, PipelineError> {
+        let requirement = op
+            .op
+            .get("security")
+            .or_else(|| self.root.get("security"))
+            .and_then(Value::as_array)
+            .and_then(|alternatives| alternatives.first())
+            .and_then(Value::as_object);
+        let prefix = secret_prefix(str_field(&self.root, "info/title"));
+
+        let mut credentials = Credentials::default();
+        for scheme_name in requirement.into_iter().flat_map(Map::keys) {
+            let scheme = self
+                .root
+                .pointer("/components/securitySchemes")
+                .and_then(|schemes| schemes.get(scheme_name))
+                .ok_or_else(|| {
+                    import_error(format!("security scheme '{scheme_name}' is not defined"))
+                })?;
+            let scheme = self.resolve(scheme)?;
+            let http_scheme = str_field(&scheme, "scheme").to_ascii_lowercase();
+            let suffixes: &[&str] = match (str_field(&scheme, "type"), http_scheme.as_str()) {
+                ("http", "basic") => &["USERNAME", "PASSWORD"],
+                ("apiKey", _) => {
+                    let location = str_field(&scheme, "in").to_string();
+                    let name = str_field(&scheme, "name").to_string();
+                    credentials.params.push((location, name));
+                    &["API_KEY"]
+                }
+                _ => &["TOKEN"],
+            };
+            for suffix in suffixes {
+                let secret = format!("{prefix}_{suffix}");
+                if !credentials.secrets.contains(&secret) {
+                    credentials.secrets.push(secret);
+                }
+            }
+        }
+        Ok(credentials)
+    }
+
+    /// The host `op` is served from, and the path prefix its requests
+    /// share: the server's base path and `op`'s path up to its first
+    /// template parameter.
+    fn endpoint(&self, op: &Operation) -> Result<(String, String), PipelineError> {
+        let server = [op.op, op.path_item, &self.root]
+            .into_iter()
+            .find_map(|v| v.get("servers").and_then(Value::as_array)?.first());
+        let server_url = server.map_or_else(|| "/".to_string(), expand_server_url);
+        let url = match &self.base {
+            Some(base) => base.join(&server_url),
+            None => Url::parse(&server_url),
+        }
+        .map_err(|_| {
+            import_error(format!(
+                "server URL '{server_url}' of {} is not absolute, and the document was not fetched from a URL to resolve it against",
+                op.id
+            ))
+        })?;
+        let host = match (url.scheme(), url.host_str()) {
+            ("http" | "https", Some(host)) => match url.port() {
+                Some(port) => format!("{host}:{port}"),
+                None => host.to_string(),
+            },
+            _ => {
+                return Err(import_error(format!(
+                    "server URL '{server_url}' of {} is not an http(s) URL",
+                    op.id
+                )));
+            }
+        };
+
+        let path = match op.path.find('{') {
+            Some(template) => &op.path[..op.path[..template].rfind('/').map_or(0, |i| i + 1)],
+            None => op.path,
+        };
+        let prefix = format!("{}{path}", url.path().trim_end_matches('/'));
+        Ok((host, prefix))
+    }
+
+    /// `value` with every `$ref` inlined.
+    fn resolve(&self, value: &Value) -> Result<Value, PipelineError> {
+        self.inline(value, &mut Vec::new())
+    }
+
+    /// `value` with `$ref`s inlined, except those already being inlined
+    /// (`seen`), which become `{"type": "object"}`.
+    fn inline(&self, value: &Value, seen: &mut Vec<String>) -> Result<Value, PipelineError> {
+        match value {
+            Value::Object(obj) => {
+                if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+                    if seen.iter().any(|r| r == reference) {
+                        return Ok(json!({"type": "object"}));
+                    }
+                    let target = reference
+                        .strip_prefix('#')
+                        .and_then(|pointer| self.root.pointer(pointer))
+                        .ok_or_else(|| {
+                            import_error(format!(
+                                "cannot resolve $ref '{reference}'; only references within the document are supported"
+                            ))
+                        })?;
+                    seen.push(reference.to_string());
+                    let inlined = self.inline(target, seen);
+                    seen.pop();
+                    return inlined;
+                }
+                obj.iter()
+                    .map(|(key, value)| Ok((key.clone(), self.inline(value, seen)?)))
+                    .collect::<Result<Map<_, _>, _>>()
+                    .map(Value::Object)
+            }
+            Value::Array(items) => items
+                .iter()
+                .map(|item| self.inline(item, seen))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            _ => Ok(value.clone()),
+        }
+    }
+}
+
+fn import_error(message: impl Into<String>) -> PipelineError {
+    PipelineError::SpecImport(message.into())
+}
+
+/// The string at `path` (`/`-separated keys) in `value`, or `""`.
+fn str_field<'a>(value: &'a Value, path: &str) -> &'a str {
+    value
+        .pointer(&format!("/{path}"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn list(value: Option<&Value>) -> impl Iterator<Item = &Value> {
+    value.and_then(Value::as_array).into_iter().flatten()
+}
+
+/// "List issues (GET /repos/{owner}/{repo}/issues)".
+fn describe(op: &Operation) -> String {
+    let summary = ["summary", "description"]
+        .into_iter()
+        .filter_map(|key| str_field(op.op, key).lines().next())
+        .map(|line| line.trim().trim_end_matches('.'))
+        .find(|line| !line.is_empty());
+    match summary {
+        Some(summary) => format!("{summary} ({} {})", op.method, op.path),
+        None => format!("{} {}", op.method, op.path),
+    }
+}
+
+/// The schema of a `content` map's JSON media type, else of its first.
+fn media_schema(content: Option<&Value>) -> Option<Value> {
+    let content = content?.as_object()?;
+    let media = content
+        .get("application/json")
+        .or_else(|| {
+            content
+                .iter()
+                .find(|(media_type, _)| media_type.ends_with("+json"))
+                .map(|(_, media)| media)
+        })
+        .or_else(|| content.values().next())?;
+    media.get("schema").cloned()
+}
+
+/// A server object's URL with its variables set to their defaults.
+fn expand_server_url(server: &Value) -> String {
+    let mut url = str_field(server, "url").to_string();
+    for (name, variable) in server
+        .get("variables")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        url = url.replace(&format!("{{{name}}}"), str_field(variable, "default"));
+    }
+    url
+}
+
+fn object_schema(inputs: OperationInputs) -> Value {
+    json!({
+        "type": "object",
+        "properties": inputs.properties,
+        "required": inputs.required,
+    })
+}
+
+/// Inputs and outputs of a multi-action spec from each action's name,
+/// inputs and output. Inputs only some actions use say which.
+fn merge_actions(
+    actions: Vec<(String, OperationInputs, Value)>,
+) -> Result<(Value, Value), PipelineError> {
+    let names: Vec<&str> = actions.iter().map(|(name, ..)| name.as_str()).collect();
+    let mut properties = Map::new();
+    // Per input: the actions that use it, and those that require it.
+    let mut users: BTreeMap<&str, (Vec<&str>, Vec<&str>)> = BTreeMap::new();
+    for (action, inputs, _) in &actions {
+        for (field, schema) in &inputs.properties {
+            properties
+                .entry(field.clone())
+                .or_insert_with(|| schema.clone());
+            let (used, needed) = users.entry(field).or_default();
+            used.push(action);
+            if inputs.required.contains(field) {
+                needed.push(action);
+            }
+        }
+    }
+    if properties.contains_key("action") {
+        return Err(import_error(
+            "an operation has an 'action' input, which a multi-action spec uses to pick the operation",
+        ));
+    }
+
+    let mut required = vec!["action".to_string()];
+    for (field, (used, needed)) in users {
+        if needed.len() == names.len() {
+            required.push(field.to_string());
+            continue;
+        }
+        let mut notes = Vec::new();
+        if used.len() < names.len() {
+            notes.push(format!("Used by {}.", used.join(", ")));
+        }
+        if !needed.is_empty() {
+            notes.push(format!("Required by {}.", needed.join(", ")));
+        }
+        if let (false, Some(Value::Object(schema))) = (notes.is_empty(), properties.get_mut(field))
+        {
+            let notes = notes.join(" ");
+            let description = match schema.get("description").and_then(Value::as_str) {
+                Some(d) if d.trim_end().ends_with('.') => format!("{} {notes}", d.trim_end()),
+                Some(d) => format!("{}. {notes}", d.trim_end()),
+                None => notes,
+            };
+            schema.insert("description".into(), description.into());
+        }
+    }
+    properties.insert(
+        "action".into(),
+        json!({"type": "string", "enum": names, "description": "The operation to call"}),
+    );
+
+    let outputs: Vec<Value> = actions
+        .into_iter()
+        .map(|(action, _, mut output)| {
+            if let Some(output) = output.as_object_mut() {
+                output.insert("title".into(), action.into());
+            }
+            output
+        })
+        .collect();
+    Ok((
+        json!({"type": "object", "properties": properties, "required": required}),
+        json!({"description": "The response of the chosen action", "oneOf": outputs}),
+    ))
+}
+
+/// One scoped grant per host, covering every method and path prefix its
+/// operations use.
+fn network_grants(endpoints: Vec<(String, &'static str, String)>) -> Vec<NetworkGrant> {
+    let mut hosts: Vec<(String, Vec<&str>, Vec<String>)> = Vec::new();
+    for (host, method, prefix) in endpoints {
+        let index = match hosts.iter().position(|(h, ..)| *h == host) {
+            Some(index) => index,
+            None => {
+                hosts.push((host, vec![], vec![]));
+                hosts.len() - 1
+            }
+        };
+        let (_, methods, prefixes) = &mut hosts[index];
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix);
+        }
+    }
+
+    hosts
+        .into_iter()
+        .map(|(host, mut methods, prefixes)| {
+            methods.sort_by_key(|m| HTTP_METHODS.iter().position(|known| known == m));
+            let mut kept: Vec<String> = prefixes
+                .iter()
+                .filter(|p| {
+                    !prefixes
+                        .iter()
+                        .any(|q| q != *p && p.starts_with(q.as_str()))
+                })
+                .cloned()
+                .collect();
+            kept.sort();
+            if kept.iter().any(|p| p == "/") {
+                kept.clear();
+            }
+            let methods = methods.into_iter().map(str::to_string).collect();
+            NetworkGrant::scoped(host, methods, kept)
+        })
+        .collect()
+}
+
+/// `listIssues`, `list-issues` → `list_issues`; `getHTTPStatus` →
+/// `get_http_status`.
+fn snake_case(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && i > 0 {
+                let prev = chars[i - 1];
+                let next_is_lower = chars.get(i + 1).is_some_and(char::is_ascii_lowercase);
+                if prev.is_ascii_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_ascii_uppercase() && next_is_lower)
+                {
+                    out.push('_');
+                }
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+/// `ISSUE_TRACKER` for "Issue Tracker API".
+fn secret_prefix(title: &str) -> String {
+    let prefix = snake_case(title).to_ascii_uppercase();
+    let prefix = prefix.strip_suffix("_API").unwrap_or(&prefix);
+    if prefix.is_empty() {
+        "API".into()
+    } else {
+        prefix.into()
+    }
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    use serde_yaml::Value as Yaml;
+    match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => b.into(),
+        Yaml::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), ..) => i.into(),
+            (_, Some(u), _) => u.into(),
+            (.., f) => f
+                .and_then(serde_json::Number::from_f64)
+                .map_or(Value::Null, Value::Number),
+        },
+        Yaml::String(s) => s.into(),
+        Yaml::Sequence(items) => items.into_iter().map(yaml_to_json).collect(),
+        Yaml::Mapping(map) => map
+            .into_iter()
+            .map(|(key, value)| (yaml_key(key), yaml_to_json(value)))
+            .collect::<Map<_, _>>()
+            .into(),
+        Yaml::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// A mapping key as a JSON object key; YAML allows unquoted `200:`.
+fn yaml_key(key: serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        other => serde_yaml::to_string(&other)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISSUES: &str = include_str!("../tests/fixtures/openapi/issues.yaml");
+
+    fn issues() -> OpenApiDocument {
+        OpenApiDocument::parse(ISSUES).unwrap()
+    }
+
+    #[test]
+    fn operation_maps_params_responses_host_and_bearer_secret() {
+        let spec = issues().import(&["listIssues"], None).unwrap();
+
+        assert_eq!(spec.name, "list_issues");
+        assert_eq!(
+            spec.description,
+            "List issues in a repository (GET /repos/{owner}/{repo}/issues)"
+        );
+        let properties = &spec.inputs["properties"];
+        assert_eq!(properties["owner"], json!({"type": "string"}));
+        assert_eq!(
+            properties["state"]["enum"],
+            json!(["open", "closed", "all"])
+        );
+        assert_eq!(properties["state"]["description"], "Which issues to list");
+        assert!(properties.get("Authorization").is_none());
+        assert_eq!(spec.inputs["required"], json!(["owner", "repo"]));
+
+        // The response's $ref is inlined; the recursive one is cut off.
+        let issue = &spec.outputs["items"];
+        assert_eq!(issue["properties"]["title"], json!({"type": "string"}));
+        assert_eq!(
+            issue["properties"]["duplicate_of"],
+            json!({"type": "object"})
+        );
+
+        assert_eq!(
+            spec.constraints.network,
+            vec![NetworkGrant::scoped(
+                "us.issues.example.com",
+                vec!["GET".into()],
+                vec!["/v1/repos/".into()],
+            )]
+        );
+        assert_eq!(spec.constraints.secrets, vec!["ISSUE_TRACKER_TOKEN"]);
+    }
+
+    #[test]
+    fn request_body_fields_become_inputs() {
+        let spec = issues()
+            .import(&["createIssue"], Some("open_issue"))
+            .unwrap();
+
+        assert_eq!(spec.name, "open_issue");
+        let properties = spec.inputs["properties"].as_object().unwrap();
+        let mut fields: Vec<&str> = properties.keys().map(String::as_str).collect();
+        fields.sort();
+        assert_eq!(fields, ["body", "labels", "owner", "repo", "title"]);
+        assert_eq!(spec.inputs["required"], json!(["owner", "repo", "title"]));
+        assert_eq!(
+            spec.outputs["properties"]["number"],
+            json!({"type": "integer"})
+        );
+    }
+
+    #[test]
+    fn several_operations_make_a_multi_action_spec() {
+        let spec = issues()
+            .import(&["listIssues", "createIssue", "getHealth"], None)
+            .unwrap();
+
+        assert_eq!(spec.name, "issue_tracker_api");
+        assert!(
+            spec.description.starts_with(
+                "Issue Tracker API operations. list_issues: List issues in a repository"
+            )
+        );
+        let properties = &spec.inputs["properties"];
+        assert_eq!(
+            properties["action"]["enum"],
+            json!(["list_issues", "create_issue", "get_health"])
+        );
+        assert_eq!(spec.inputs["required"], json!(["action"]));
+        assert_eq!(
+            properties["title"]["description"],
+            "Used by create_issue. Required by create_issue."
+        );
+        assert_eq!(
+            properties["state"]["description"],
+            "Which issues to list. Used by list_issues."
+        );
+        let outputs = spec.outputs["oneOf"].as_array().unwrap();
+        assert_eq!(outputs[2], json!({"type": "null", "title": "get_health"}));
+
+        // `getHealth` opts out of security and shares the host.
+        assert_eq!(
+            spec.constraints.network,
+            vec![NetworkGrant::scoped(
+                "us.issues.example.com",
+                vec!["GET".into(), "POST".into()],
+                vec!["/v1/health".into(), "/v1/repos/".into()],
+            )]
+        );
+        assert_eq!(spec.constraints.secrets, vec!["ISSUE_TRACKER_TOKEN"]);
+    }
+
+    #[test]
+    fn json_documents_and_api_keys_are_supported() {
+        let mut doc: Value = serde_yaml::from_str::<serde_yaml::Value>(ISSUES)
+            .map(yaml_to_json)
+            .unwrap();
+        doc["components"]["securitySchemes"]["bearerAuth"] =
+            json!({"type": "apiKey", "in": "query", "name": "api_key"});
+        doc["paths"]["/repos/{owner}/{repo}/issues"]["get"]["parameters"][0] =
+            json!({"name": "api_key", "in": "query", "schema": {"type": "string"}});
+
+        let doc = OpenApiDocument::parse(&doc.to_string()).unwrap();
+        let spec = doc.import(&["listIssues"], None).unwrap();
+
+        assert!(spec.inputs["properties"].get("api_key").is_none());
+        assert_eq!(spec.constraints.secrets, vec!["ISSUE_TRACKER_API_KEY"]);
+    }
+
+    #[test]
+    fn unusable_documents_and_operations_are_rejected() {
+        let doc = issues();
+        let err = doc.import(&["deleteIssue"], None).unwrap_err();
+        assert_eq!(err.code(), "spec_import");
+        let mut available = doc.operation_ids();
+        available.sort();
+        assert_eq!(available, ["createIssue", "getHealth", "listIssues"]);
+        assert!(err.to_string().contains("the document has: "), "{err}");
+
+        let err = OpenApiDocument::parse("swagger: '2.0'\npaths: {}").unwrap_err();
+        assert!(err.to_string().contains("only OpenAPI 3"), "{err}");
+
+        let relative = ISSUES.replace("https://{region}.issues.example.com/v1", "/v1");
+        let err = OpenApiDocument::parse(&relative)
+            .unwrap()
+            .import(&["listIssues"], None)
+            .unwrap_err();
+        assert!(err.to_string().contains("not absolute"), "{err}");
+    }
+
+    #[test]
+    fn names_become_snake_case() {
+        assert_eq!(snake_case("listIssues"), "list_issues");
+        assert_eq!(snake_case("get-HTTPStatus"), "get_http_status");
+        assert_eq!(snake_case("Issue Tracker API"), "issue_tracker_api");
+        assert_eq!(secret_prefix("Issue Tracker API"), "ISSUE_TRACKER");
+        assert_eq!(secret_prefix(""), "API");
+    }
+}
@@ -0,0 +1,352 @@
+//! Cheap checks on the Engineer's output, run before QA and the Red Team.
+//!
+//! Some broken builds can be spotted without an LLM: a component that is
+//! never exported, one that spawns threads `wasm32-wasip1` does not have, or
+//! one that prints a credential. Each failed check becomes a
+//! [`BugTicket`] for the Engineer to fix before QA and Red Team tokens are
+//! spent on the build. Only Rust sources are checked.
+
+use serde::Deserialize;
+
+use crate::types::{BugTicket, BugTicketType, BuildOutput, Severity};
+
+/// Which checks run (`[pipeline.static_checks]`); all do by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct StaticChecks {
+    /// `src/lib.rs` exports the component with `bindings::export!`.
+    #[serde(default = "default_true")]
+    pub export_macro: bool,
+    /// `src/lib.rs` declares the generated `mod bindings;`.
+    #[serde(default = "default_true")]
+    pub bindings_module: bool,
+    /// No `std::thread` or `tokio::spawn`.
+    #[serde(default = "default_true")]
+    pub no_threads: bool,
+    /// No `println!`, `eprintln!` or `dbg!` of a secret, token or key.
+    #[serde(default = "default_true")]
+    pub no_secret_printing: bool,
+    /// A WIT definition, when given, declares `world girt-tool`.
+    #[serde(default = "default_true")]
+    pub wit_world: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for StaticChecks {
+    fn default() -> Self {
+        Self {
+            export_macro: true,
+            bindings_module: true,
+            no_threads: true,
+            no_secret_printing: true,
+            wit_world: true,
+        }
+    }
+}
+
+/// One failed check.
+struct Violation {
+    check: &'static str,
+    ticket_type: BugTicketType,
+    expected: &'static str,
+    actual: String,
+    fix: &'static str,
+}
+
+const PRINT_MACROS: &[&str] = &["println!(", "print!(", "eprintln!(", "eprint!(", "dbg!("];
+const SECRET_WORDS: &[&str] = &[
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "password",
+    "credential",
+    "authorization",
+];
+const THREAD_PATHS: &[&str] = &[
+    "std::thread",
+    "thread::spawn",
+    "tokio::spawn",
+    "tokio::task::spawn",
+];
+
+impl StaticChecks {
+    /// Tickets for every enabled check `output` fails, in the order the
+    /// checks are listed above. Empty for clean output and for targets
+    /// other than Rust.
+    pub fn check(&self, output: &BuildOutput) -> Vec<BugTicket> {
+        if !output.language.eq_ignore_ascii_case("rust") {
+            return Vec::new();
+        }
+        let violations = self.violations(output);
+        let failed: Vec<&str> = violations.iter().map(|v| v.check).collect();
+        violations
+            .into_iter()
+            .map(|v| {
+                let others: Vec<&str> = failed.iter().copied().filter(|c| *c != v.check).collect();
+                let mut directive = format!(
+                    "Static check `{}` failed; QA does not run until it passes. {}",
+                    v.check, v.fix
+                );
+                if !others.is_empty() {
+                    directive.push_str(&format!(
+                        " Also failing, fix them in the same change: {}.",
+                        others.join(", ")
+                    ));
+                }
+                BugTicket {
+                    target: "engineer".into(),
+                    ticket_type: v.ticket_type,
+                    // QA never runs until these pass, so they block under
+                    // any `[pipeline.blocking]` policy.
+                    severity: Severity::Critical,
+                    input: serde_json::json!({ "static_check": v.check }),
+                    expected: v.expected.into(),
+                    actual: v.actual,
+                    remediation_directive: directive,
+                }
+            })
+            .collect()
+    }
+
+    fn violations(&self, output: &BuildOutput) -> Vec<Violation> {
+        let lib = code_lines(&output.source_code);
+        let rust_files: Vec<(&str, &str)> = std::iter::once(("src/lib.rs", &*output.source_code))
+            .chain(
+                output
+                    .files
+                    .iter()
+                    .filter(|f| f.path.ends_with(".rs"))
+                    .map(|f| (f.path.as_str(), f.content.as_str())),
+            )
+            .collect();
+        let mut violations = Vec::new();
+
+        if self.export_macro && !lib.iter().any(|(_, line)| line.contains("export!(")) {
+            violations.push(Violation {
+                check: "export_macro",
+                ticket_type: BugTicketType::FunctionalDefect,
+                expected: "src/lib.rs exports the component",
+                actual: "no export! invocation in src/lib.rs".into(),
+                fix: "End src/lib.rs with `bindings::export!(Component with_types_in bindings);`.",
+            });
+        }
+        if self.bindings_module
+            && !lib.iter().any(|(_, line)| {
+                line.starts_with("mod bindings;")
+                    || line.starts_with("pub mod bindings;")
+                    || line.contains("wit_bindgen::generate!")
+            })
+        {
+            violations.push(Violation {
+                check: "bindings_module",
+                ticket_type: BugTicketType::FunctionalDefect,
+                expected: "src/lib.rs declares the generated bindings",
+                actual: "no `mod bindings;` in src/lib.rs".into(),
+                fix: "Declare `#[allow(warnings)] mod bindings;` and implement `bindings::Guest`.",
+            });
+        }
+        if self.no_threads
+            && let Some(found) = find_line(&rust_files, |line| {
+                THREAD_PATHS.iter().any(|path| line.contains(path))
+            })
+        {
+            violations.push(Violation {
+                check: "no_threads",
+                ticket_type: BugTicketType::FunctionalDefect,
+                expected: "no threads or spawned tasks; wasm32-wasip1 runs one thread",
+                actual: found,
+                fix: "Do the work sequentially inside `run` instead of spawning threads or tasks.",
+            });
+        }
+        if self.no_secret_printing
+            && let Some(found) = find_line(&rust_files, |line| {
+                let lower = line.to_ascii_lowercase();
+                PRINT_MACROS.iter().any(|m| line.contains(m))
+                    && SECRET_WORDS.iter().any(|w| lower.contains(w))
+            })
+        {
+            violations.push(Violation {
+                check: "no_secret_printing",
+                ticket_type: BugTicketType::SecurityVulnerability,
+                expected: "secrets never reach stdout, stderr or logs",
+                actual: found,
+                fix: "Remove the print; never write secrets, tokens or keys anywhere.",
+            });
+        }
+        let wit = output.wit_definition.trim();
+        if self.wit_world && !wit.is_empty() && !wit.contains("world girt-tool") {
+            violations.push(Violation {
+                check: "wit_world",
+                ticket_type: BugTicketType::FunctionalDefect,
+                expected: "the WIT definition declares `world girt-tool`",
+                actual: format!(
+                    "wit_definition declares {}",
+                    wit.lines()
+                        .map(str::trim)
+                        .find(|l| l.starts_with("world "))
+                        .unwrap_or("no world")
+                ),
+                fix: "Use the girt-tool WIT definition from the instructions unchanged, or leave wit_definition empty.",
+            });
+        }
+        violations
+    }
+}
+
+/// `source`'s lines with their 1-based numbers, trimmed, without blank and
+/// `//` comment lines.
+fn code_lines(source: &str) -> Vec<(usize, &str)> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with("//"))
+        .collect()
+}
+
+/// The first code line in `files` matching `matches`, as `path:line: code`.
+fn find_line(files: &[(&str, &str)], matches: impl Fn(&str) -> bool) -> Option<String> {
+    files.iter().find_map(|(path, source)| {
+        code_lines(source)
+            .into_iter()
+            .find(|(_, line)| matches(line))
+            .map(|(n, line)| format!("{path}:{n}: {line}"))
+    })
+}
+
+#[cfg(test)]
+pub(crate) const CLEAN_SOURCE: &str = "#[allow(warnings)]
+mod bindings;
+
+use bindings::Guest;
+
+struct Component;
+
+impl Guest for Component {
+    fn run(input: String) -> Result<String, String> {
+        Ok(input)
+    }
+}
+
+bindings::export!(Component with_types_in bindings);
+";
+
+/// [`CLEAN_SOURCE`] under a `// {marker}` line, for stub Engineer outputs
+/// that tests tell apart.
+#[cfg(test)]
+pub(crate) fn clean_source(marker: &str) -> String {
+    format!("// {marker}\n{CLEAN_SOURCE}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceFile;
+
+    fn output(source: &str) -> BuildOutput {
+        BuildOutput {
+            source_code: source.into(),
+            wit_definition: String::new(),
+            policy_yaml: String::new(),
+            language: "rust".into(),
+            files: vec![],
+            change_summary: None,
+        }
+    }
+
+    fn checks_failed(output: &BuildOutput) -> Vec<String> {
+        StaticChecks::default()
+            .check(output)
+            .into_iter()
+            .map(|t| t.input["static_check"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn clean_source_passes() {
+        assert!(
+            StaticChecks::default()
+                .check(&output(CLEAN_SOURCE))
+                .is_empty()
+        );
+        let mut with_wit = output(CLEAN_SOURCE);
+        with_wit.wit_definition = crate::compiler::default_wit(Default::default()).into();
+        assert!(StaticChecks::default().check(&with_wit).is_empty());
+    }
+
+    #[test]
+    fn each_rule_files_its_ticket() {
+        let no_export = CLEAN_SOURCE.replace("bindings::export!", "// bindings::export!");
+        assert_eq!(checks_failed(&output(&no_export)), ["export_macro"]);
+
+        let no_bindings = CLEAN_SOURCE.replace("mod bindings;", "");
+        assert_eq!(checks_failed(&output(&no_bindings)), ["bindings_module"]);
+
+        let threaded = CLEAN_SOURCE.replace(
+            "Ok(input)",
+            "std::thread::spawn(|| ()).join().ok();\n        Ok(input)",
+        );
+        assert_eq!(checks_failed(&output(&threaded)), ["no_threads"]);
+
+        let mut spawned_in_module = output(CLEAN_SOURCE);
+        spawned_in_module.files.push(SourceFile {
+            path: "src/work.rs".into(),
+            content: "pub fn go() {\n    tokio::spawn(async {});\n}\n".into(),
+        });
+        let tickets = StaticChecks::default().check(&spawned_in_module);
+        assert_eq!(tickets.len(), 1);
+        assert_eq!(tickets[0].actual, "src/work.rs:2: tokio::spawn(async {});");
+
+        let leaky = CLEAN_SOURCE.replace(
+            "Ok(input)",
+            "eprintln!(\"using token {}\", api_token);\n        Ok(input)",
+        );
+        let tickets = StaticChecks::default().check(&output(&leaky));
+        assert_eq!(tickets.len(), 1);
+        assert_eq!(tickets[0].input["static_check"], "no_secret_printing");
+        assert_eq!(tickets[0].ticket_type, BugTicketType::SecurityVulnerability);
+
+        let mut wrong_world = output(CLEAN_SOURCE);
+        wrong_world.wit_definition = "package girt:tool;\n\nworld my-tool {\n}\n".into();
+        let tickets = StaticChecks::default().check(&wrong_world);
+        assert_eq!(tickets.len(), 1);
+        assert_eq!(tickets[0].actual, "wit_definition declares world my-tool {");
+    }
+
+    #[test]
+    fn directives_name_every_failing_check() {
+        let tickets = StaticChecks::default().check(&output("fn main() {}"));
+        assert_eq!(tickets.len(), 2);
+        assert!(
+            tickets[0]
+                .remediation_directive
+                .starts_with("Static check `export_macro` failed"),
+            "{}",
+            tickets[0].remediation_directive
+        );
+        assert!(
+            tickets[0]
+                .remediation_directive
+                .contains("Also failing, fix them in the same change: bindings_module."),
+            "{}",
+            tickets[0].remediation_directive
+        );
+    }
+
+    #[test]
+    fn disabled_checks_and_other_languages_are_skipped() {
+        let checks = StaticChecks {
+            export_macro: false,
+            ..StaticChecks::default()
+        };
+        let no_export = CLEAN_SOURCE.replace("bindings::export!", "// bindings::export!");
+        assert!(checks.check(&output(&no_export)).is_empty());
+
+        let mut go = output("package main");
+        go.language = "go".into();
+        assert!(StaticChecks::default().check(&go).is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+use girt_core::spec::{CapabilitySpec, Field, InputType};
 
 /// Standard library tool specifications.
 ///
@@ -20,205 +20,341 @@ pub fn standard_library() -> Vec<CapabilitySpec> {
 
 /// HTTP client for making authenticated requests to allowed hosts.
 pub fn http_client() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "http_client".into(),
-        description: "General-purpose HTTP client for making GET, POST, PUT, DELETE requests \
-                      to allowed hosts with JSON request/response handling."
-            .into(),
-        inputs: serde_json::json!({
-            "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH"], "required": true},
-            "url": {"type": "string", "required": true},
-            "headers": {"type": "object", "description": "Additional headers"},
-            "body": {"type": "object", "description": "Request body (for POST/PUT/PATCH)"},
-            "auth_service": {"type": "string", "description": "Service name for host_auth_proxy credential injection"}
-        }),
-        outputs: serde_json::json!({
-            "status": {"type": "integer"},
-            "headers": {"type": "object"},
-            "body": {"type": "object"}
-        }),
-        constraints: CapabilityConstraints {
-            network: vec![], // Populated per-instance by the Architect
-            storage: vec![],
-            secrets: vec![],
-        },
-    }
+    // Hosts are populated per-instance by the Architect.
+    CapabilitySpec::builder()
+        .name("http_client")
+        .description(
+            "General-purpose HTTP client for making GET, POST, PUT, DELETE requests \
+             to allowed hosts with JSON request/response handling.",
+        )
+        .input(
+            "method",
+            string().with_enum(["GET", "POST", "PUT", "DELETE", "PATCH"]),
+            true,
+        )
+        .input("url", InputType::String, true)
+        .input(
+            "headers",
+            object().with_description("Additional headers"),
+            false,
+        )
+        .input(
+            "body",
+            object().with_description("Request body (for POST/PUT/PATCH)"),
+            false,
+        )
+        .input(
+            "auth_service",
+            string().with_description("Service name for host_auth_proxy credential injection"),
+            false,
+        )
+        .output("status", InputType::Integer)
+        .output("headers", InputType::Object)
+        .output("body", InputType::Object)
+        .build()
+        .expect(VALID)
 }
 
 /// JSON parsing and transformation tool.
 pub fn json_transform() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "json_transform".into(),
-        description: "Parse, query, and transform JSON data using JSONPath expressions. \
-                      Supports filtering, mapping, flattening, and restructuring."
-            .into(),
-        inputs: serde_json::json!({
-            "data": {"type": "object", "description": "Input JSON data", "required": true},
-            "query": {"type": "string", "description": "JSONPath expression to extract data"},
-            "transform": {"type": "object", "description": "Transformation spec (map, filter, flatten, pick, omit)"}
-        }),
-        outputs: serde_json::json!({
-            "result": {"type": "object", "description": "Transformed output"}
-        }),
-        constraints: CapabilityConstraints::default(),
-    }
+    CapabilitySpec::builder()
+        .name("json_transform")
+        .description(
+            "Parse, query, and transform JSON data using JSONPath expressions. \
+             Supports filtering, mapping, flattening, and restructuring.",
+        )
+        .input("data", object().with_description("Input JSON data"), true)
+        .input(
+            "query",
+            string().with_description("JSONPath expression to extract data"),
+            false,
+        )
+        .input(
+            "transform",
+            object().with_description("Transformation spec (map, filter, flatten, pick, omit)"),
+            false,
+        )
+        .output("result", object().with_description("Transformed output"))
+        .build()
+        .expect(VALID)
 }
 
 /// File I/O within granted paths.
 pub fn file_io() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "file_io".into(),
-        description: "Read and write files within granted storage paths. Supports text and \
-                      binary operations, directory listing, and file metadata."
-            .into(),
-        inputs: serde_json::json!({
-            "operation": {"type": "string", "enum": ["read", "write", "append", "list", "stat", "delete"], "required": true},
-            "path": {"type": "string", "description": "File path (must be within allowed storage paths)", "required": true},
-            "content": {"type": "string", "description": "Content to write (for write/append operations)"},
-            "encoding": {"type": "string", "description": "Text encoding (default: utf-8)"}
-        }),
-        outputs: serde_json::json!({
-            "content": {"type": "string", "description": "File content (for read)"},
-            "entries": {"type": "array", "description": "Directory entries (for list)"},
-            "metadata": {"type": "object", "description": "File metadata (for stat)"},
-            "success": {"type": "boolean"}
-        }),
-        constraints: CapabilityConstraints {
-            network: vec![],
-            storage: vec![], // Populated per-instance
-            secrets: vec![],
-        },
-    }
+    // Storage paths are populated per-instance.
+    CapabilitySpec::builder()
+        .name("file_io")
+        .description(
+            "Read and write files within granted storage paths. Supports text and \
+             binary operations, directory listing, and file metadata.",
+        )
+        .input(
+            "operation",
+            string().with_enum(["read", "write", "append", "list", "stat", "delete"]),
+            true,
+        )
+        .input(
+            "path",
+            string().with_description("File path (must be within allowed storage paths)"),
+            true,
+        )
+        .input(
+            "content",
+            string().with_description("Content to write (for write/append operations)"),
+            false,
+        )
+        .input(
+            "encoding",
+            string().with_description("Text encoding (default: utf-8)"),
+            false,
+        )
+        .output(
+            "content",
+            string().with_description("File content (for read)"),
+        )
+        .output(
+            "entries",
+            array().with_description("Directory entries (for list)"),
+        )
+        .output(
+            "metadata",
+            object().with_description("File metadata (for stat)"),
+        )
+        .output("success", InputType::Boolean)
+        .build()
+        .expect(VALID)
 }
 
 /// GitHub API integration.
 pub fn github_api() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "github_api".into(),
-        description: "Query and manage GitHub resources including issues, pull requests, \
-                      repositories, and actions. Supports filtering, pagination, and CRUD."
-            .into(),
-        inputs: serde_json::json!({
-            "resource": {"type": "string", "enum": ["issues", "pulls", "repos", "actions", "releases"], "required": true},
-            "owner": {"type": "string", "required": true},
-            "repo": {"type": "string", "required": true},
-            "action": {"type": "string", "enum": ["list", "get", "create", "update", "close"], "default": "list"},
-            "filters": {"type": "object", "description": "Resource-specific filters (state, labels, etc.)"},
-            "page": {"type": "integer", "default": 1},
-            "per_page": {"type": "integer", "default": 30}
-        }),
-        outputs: serde_json::json!({
-            "items": {"type": "array"},
-            "total_count": {"type": "integer"},
-            "next_page": {"type": "integer"}
-        }),
-        constraints: CapabilityConstraints {
-            network: vec!["api.github.com".into()],
-            storage: vec![],
-            secrets: vec!["GITHUB_TOKEN".into()],
-        },
-    }
+    CapabilitySpec::builder()
+        .name("github_api")
+        .description(
+            "Query and manage GitHub resources including issues, pull requests, \
+             repositories, and actions. Supports filtering, pagination, and CRUD.",
+        )
+        .input(
+            "resource",
+            string().with_enum(["issues", "pulls", "repos", "actions", "releases"]),
+            true,
+        )
+        .input("owner", InputType::String, true)
+        .input("repo", InputType::String, true)
+        .input(
+            "action",
+            string()
+                .with_enum(["list", "get", "create", "update", "close"])
+                .with_default("list"),
+            false,
+        )
+        .input(
+            "filters",
+            object().with_description("Resource-specific filters (state, labels, etc.)"),
+            false,
+        )
+        .input("page", integer().with_default(1), false)
+        .input("per_page", integer().with_default(30), false)
+        .output("items", InputType::Array)
+        .output("total_count", InputType::Integer)
+        .output("next_page", InputType::Integer)
+        .allow_host("api.github.com")
+        .secret("GITHUB_TOKEN")
+        .build()
+        .expect(VALID)
 }
 
 /// GitLab API integration.
 pub fn gitlab_api() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "gitlab_api".into(),
-        description: "Query and manage GitLab resources including issues, merge requests, \
-                      projects, and pipelines. Supports filtering and pagination."
-            .into(),
-        inputs: serde_json::json!({
-            "resource": {"type": "string", "enum": ["issues", "merge_requests", "projects", "pipelines"], "required": true},
-            "project_id": {"type": "string", "required": true},
-            "action": {"type": "string", "enum": ["list", "get", "create", "update"], "default": "list"},
-            "filters": {"type": "object"},
-            "page": {"type": "integer", "default": 1},
-            "per_page": {"type": "integer", "default": 20}
-        }),
-        outputs: serde_json::json!({
-            "items": {"type": "array"},
-            "total_count": {"type": "integer"},
-            "next_page": {"type": "integer"}
-        }),
-        constraints: CapabilityConstraints {
-            network: vec!["gitlab.com".into()],
-            storage: vec![],
-            secrets: vec!["GITLAB_TOKEN".into()],
-        },
-    }
+    CapabilitySpec::builder()
+        .name("gitlab_api")
+        .description(
+            "Query and manage GitLab resources including issues, merge requests, \
+             projects, and pipelines. Supports filtering and pagination.",
+        )
+        .input(
+            "resource",
+            string().with_enum(["issues", "merge_requests", "projects", "pipelines"]),
+            true,
+        )
+        .input("project_id", InputType::String, true)
+        .input(
+            "action",
+            string()
+                .with_enum(["list", "get", "create", "update"])
+                .with_default("list"),
+            false,
+        )
+        .input("filters", InputType::Object, false)
+        .input("page", integer().with_default(1), false)
+        .input("per_page", integer().with_default(20), false)
+        .output("items", InputType::Array)
+        .output("total_count", InputType::Integer)
+        .output("next_page", InputType::Integer)
+        .allow_host("gitlab.com")
+        .secret("GITLAB_TOKEN")
+        .build()
+        .expect(VALID)
 }
 
 /// Text processing tool.
 pub fn text_processing() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "text_processing".into(),
-        description: "Text manipulation including regex matching, replacement, splitting, \
-                      joining, case conversion, trimming, and encoding/decoding."
-            .into(),
-        inputs: serde_json::json!({
-            "operation": {"type": "string", "enum": ["regex_match", "regex_replace", "split", "join", "case_convert", "trim", "encode", "decode", "template"], "required": true},
-            "text": {"type": "string", "required": true},
-            "pattern": {"type": "string", "description": "Regex pattern (for regex ops)"},
-            "replacement": {"type": "string", "description": "Replacement string (for replace)"},
-            "separator": {"type": "string", "description": "Separator (for split/join)"},
-            "case": {"type": "string", "enum": ["upper", "lower", "title", "snake", "camel", "kebab"]},
-            "encoding": {"type": "string", "enum": ["base64", "url", "html"]}
-        }),
-        outputs: serde_json::json!({
-            "result": {"type": "string"},
-            "matches": {"type": "array", "description": "Regex matches (for regex_match)"},
-            "parts": {"type": "array", "description": "Split parts (for split)"}
-        }),
-        constraints: CapabilityConstraints::default(),
-    }
+    CapabilitySpec::builder()
+        .name("text_processing")
+        .description(
+            "Text manipulation including regex matching, replacement, splitting, \
+             joining, case conversion, trimming, and encoding/decoding.",
+        )
+        .input(
+            "operation",
+            string().with_enum([
+                "regex_match",
+                "regex_replace",
+                "split",
+                "join",
+                "case_convert",
+                "trim",
+                "encode",
+                "decode",
+                "template",
+            ]),
+            true,
+        )
+        .input("text", InputType::String, true)
+        .input(
+            "pattern",
+            string().with_description("Regex pattern (for regex ops)"),
+            false,
+        )
+        .input(
+            "replacement",
+            string().with_description("Replacement string (for replace)"),
+            false,
+        )
+        .input(
+            "separator",
+            string().with_description("Separator (for split/join)"),
+            false,
+        )
+        .input(
+            "case",
+            string().with_enum(["upper", "lower", "title", "snake", "camel", "kebab"]),
+            false,
+        )
+        .input(
+            "encoding",
+            string().with_enum(["base64", "url", "html"]),
+            false,
+        )
+        .output("result", InputType::String)
+        .output(
+            "matches",
+            array().with_description("Regex matches (for regex_match)"),
+        )
+        .output("parts", array().with_description("Split parts (for split)"))
+        .build()
+        .expect(VALID)
 }
 
 /// Cryptographic hash tool.
 pub fn crypto_hash() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "crypto_hash".into(),
-        description: "Compute cryptographic hashes (SHA-256, SHA-512, MD5, BLAKE3) and \
-                      HMAC signatures for data integrity verification."
-            .into(),
-        inputs: serde_json::json!({
-            "operation": {"type": "string", "enum": ["hash", "hmac", "verify"], "required": true},
-            "data": {"type": "string", "required": true},
-            "algorithm": {"type": "string", "enum": ["sha256", "sha512", "md5", "blake3"], "default": "sha256"},
-            "key": {"type": "string", "description": "HMAC key (for hmac operation)"},
-            "expected_hash": {"type": "string", "description": "Hash to verify against (for verify)"}
-        }),
-        outputs: serde_json::json!({
-            "hash": {"type": "string", "description": "Hex-encoded hash"},
-            "verified": {"type": "boolean", "description": "Verification result (for verify)"}
-        }),
-        constraints: CapabilityConstraints::default(),
-    }
+    CapabilitySpec::builder()
+        .name("crypto_hash")
+        .description(
+            "Compute cryptographic hashes (SHA-256, SHA-512, MD5, BLAKE3) and \
+             HMAC signatures for data integrity verification.",
+        )
+        .input(
+            "operation",
+            string().with_enum(["hash", "hmac", "verify"]),
+            true,
+        )
+        .input("data", InputType::String, true)
+        .input(
+            "algorithm",
+            string()
+                .with_enum(["sha256", "sha512", "md5", "blake3"])
+                .with_default("sha256"),
+            false,
+        )
+        .input(
+            "key",
+            string().with_description("HMAC key (for hmac operation)"),
+            false,
+        )
+        .input(
+            "expected_hash",
+            string().with_description("Hash to verify against (for verify)"),
+            false,
+        )
+        .output("hash", string().with_description("Hex-encoded hash"))
+        .output(
+            "verified",
+            Field::new(InputType::Boolean).with_description("Verification result (for verify)"),
+        )
+        .build()
+        .expect(VALID)
 }
 
 /// CSV parsing and generation tool.
 pub fn csv_parser() -> CapabilitySpec {
-    CapabilitySpec {
-        name: "csv_parser".into(),
-        description:
+    CapabilitySpec::builder()
+        .name("csv_parser")
+        .description(
             "Parse CSV data into structured records and generate CSV from structured data. \
-                      Supports custom delimiters, headers, and encoding."
-                .into(),
-        inputs: serde_json::json!({
-            "operation": {"type": "string", "enum": ["parse", "generate"], "required": true},
-            "data": {"type": "string", "description": "CSV text (for parse)"},
-            "records": {"type": "array", "description": "Records to convert to CSV (for generate)"},
-            "delimiter": {"type": "string", "default": ","},
-            "has_headers": {"type": "boolean", "default": true},
-            "columns": {"type": "array", "description": "Column names to select (for parse)"}
-        }),
-        outputs: serde_json::json!({
-            "records": {"type": "array", "description": "Parsed records (for parse)"},
-            "csv": {"type": "string", "description": "Generated CSV (for generate)"},
-            "headers": {"type": "array", "description": "Column headers"},
-            "row_count": {"type": "integer"}
-        }),
-        constraints: CapabilityConstraints::default(),
-    }
+             Supports custom delimiters, headers, and encoding.",
+        )
+        .input("operation", string().with_enum(["parse", "generate"]), true)
+        .input(
+            "data",
+            string().with_description("CSV text (for parse)"),
+            false,
+        )
+        .input(
+            "records",
+            array().with_description("Records to convert to CSV (for generate)"),
+            false,
+        )
+        .input("delimiter", string().with_default(","), false)
+        .input(
+            "has_headers",
+            Field::new(InputType::Boolean).with_default(true),
+            false,
+        )
+        .input(
+            "columns",
+            array().with_description("Column names to select (for parse)"),
+            false,
+        )
+        .output(
+            "records",
+            array().with_description("Parsed records (for parse)"),
+        )
+        .output(
+            "csv",
+            string().with_description("Generated CSV (for generate)"),
+        )
+        .output("headers", array().with_description("Column headers"))
+        .output("row_count", InputType::Integer)
+        .build()
+        .expect(VALID)
+}
+
+const VALID: &str = "standard library specs are valid";
+
+fn string() -> Field {
+    Field::new(InputType::String)
+}
+
+fn integer() -> Field {
+    Field::new(InputType::Integer)
+}
+
+fn object() -> Field {
+    Field::new(InputType::Object)
+}
+
+fn array() -> Field {
+    Field::new(InputType::Array)
 }
 
 #[cfg(test)]
@@ -249,6 +385,37 @@ mod tests {
         assert!(spec.constraints.storage.is_empty());
     }
 
+    #[test]
+    fn github_api_declares_its_fields_as_json_schema() {
+        assert_eq!(
+            github_api().inputs,
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "resource": {"type": "string", "enum": ["issues", "pulls", "repos", "actions", "releases"]},
+                    "owner": {"type": "string"},
+                    "repo": {"type": "string"},
+                    "action": {"type": "string", "enum": ["list", "get", "create", "update", "close"], "default": "list"},
+                    "filters": {"type": "object", "description": "Resource-specific filters (state, labels, etc.)"},
+                    "page": {"type": "integer", "default": 1},
+                    "per_page": {"type": "integer", "default": 30}
+                },
+                "required": ["resource", "owner", "repo"]
+            })
+        );
+        assert_eq!(
+            github_api().outputs,
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "items": {"type": "array"},
+                    "total_count": {"type": "integer"},
+                    "next_page": {"type": "integer"}
+                }
+            })
+        );
+    }
+
     #[test]
     fn stateless_tools_have_no_constraints() {
         let spec = json_transform();
@@ -1,25 +1,53 @@
+use std::collections::{BTreeMap, HashMap};
+
 use chrono::{DateTime, Utc};
-use girt_core::spec::CapabilitySpec;
-use serde::{Deserialize, Serialize};
+pub use girt_core::spec::RequestSource;
+use girt_core::spec::{CapabilitySpec, NetworkGrant};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::PipelineCaps;
+use crate::error::PipelineError;
+use crate::validation::TestVector;
 
 /// A capability request in the build queue.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapabilityRequest {
     pub id: String,
     pub timestamp: DateTime<Utc>,
+    /// Who asked for the tool. A source this version does not know loads as
+    /// [`RequestSource::Operator`], so a queue file written by a newer girt
+    /// still builds.
+    #[serde(deserialize_with = "known_source_or_operator")]
     pub source: RequestSource,
     pub spec: CapabilitySpec,
     pub status: RequestStatus,
     pub priority: Priority,
+    /// Times the request has been claimed for a build.
     pub attempts: u32,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum RequestSource {
-    Operator,
-    Cli,
-    Hook,
+    /// Not claimed again before this time. Set when a failed build is put
+    /// back in the queue for another attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<DateTime<Utc>>,
+    /// Why the most recent attempt failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// [`PipelineError::code`](crate::error::PipelineError::code) of the
+    /// most recent failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_code: Option<String>,
+    /// Budget adjustments for this request's build, bounded by
+    /// `[pipeline.caps]` when applied.
+    #[serde(default, skip_serializing_if = "PipelineOverrides::is_empty")]
+    pub pipeline_overrides: PipelineOverrides,
+    /// [`CapabilitySpec::spec_hash`] of `spec`, recorded when the request
+    /// is queued so duplicates can be found (see [`Queue::find_by_spec_hash`](crate::queue::Queue::find_by_spec_hash)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_hash: Option<String>,
+    /// Notes for the Architect on why the tool is being built, such as the
+    /// test vector a published version stopped passing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -50,18 +78,222 @@ impl CapabilityRequest {
             status: RequestStatus::Pending,
             priority: Priority::default(),
             attempts: 0,
+            retry_after: None,
+            last_error: None,
+            last_error_code: None,
+            pipeline_overrides: PipelineOverrides::default(),
+            spec_hash: None,
+            context: Vec::new(),
         }
     }
+
+    pub fn with_overrides(mut self, overrides: PipelineOverrides) -> Self {
+        self.pipeline_overrides = overrides;
+        self
+    }
+
+    /// Attach `notes` for the Architect (see [`Self::context`]).
+    pub fn with_context(mut self, notes: Vec<String>) -> Self {
+        self.context = notes;
+        self
+    }
+
+    /// The recorded [`Self::spec_hash`], or the hash of `spec` for requests
+    /// queued before it was recorded.
+    pub fn current_spec_hash(&self) -> String {
+        self.spec_hash
+            .clone()
+            .unwrap_or_else(|| self.spec.spec_hash())
+    }
+}
+
+fn known_source_or_operator<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<RequestSource, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    Ok(
+        RequestSource::deserialize(serde_json::Value::from(raw.as_str())).unwrap_or_else(|_| {
+            tracing::warn!(source = %raw, "Unknown request source; treating it as operator");
+            RequestSource::Operator
+        }),
+    )
+}
+
+/// Per-request changes to the build pipeline's budget. Unset fields keep
+/// the pipeline defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineOverrides {
+    /// Build iterations before the circuit breaker trips.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    /// Language the Engineer writes the tool in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_language: Option<TargetLanguage>,
+    /// Resource limits the tool's policy declares.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_tier: Option<ResourceTier>,
+    /// Tokens the whole build may generate across all agent calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<u32>,
+}
+
+impl PipelineOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These overrides reduced to what `caps` permits: numbers and the tier
+    /// are clamped, and a language the caps do not list falls back to the
+    /// default.
+    pub fn capped(&self, caps: &PipelineCaps) -> Self {
+        let mut capped = self.clone();
+        if let Some(n) = capped.max_iterations
+            && n > caps.max_iterations
+        {
+            tracing::warn!(
+                requested = n,
+                cap = caps.max_iterations,
+                "Capping max_iterations"
+            );
+            capped.max_iterations = Some(caps.max_iterations);
+        }
+        if let Some(n) = capped.token_budget
+            && n > caps.token_budget
+        {
+            tracing::warn!(
+                requested = n,
+                cap = caps.token_budget,
+                "Capping token_budget"
+            );
+            capped.token_budget = Some(caps.token_budget);
+        }
+        if let Some(language) = &capped.target_language
+            && !caps.languages.contains(language)
+        {
+            tracing::warn!(%language, "Target language not allowed; using the default");
+            capped.target_language = None;
+        }
+        if let Some(tier) = capped.resource_tier
+            && tier > caps.max_tier
+        {
+            tracing::warn!(?tier, cap = ?caps.max_tier, "Capping resource_tier");
+            capped.resource_tier = Some(caps.max_tier);
+        }
+        capped
+    }
 }
 
 /// The Architect's refined tool specification output.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RefinedSpec {
     pub action: SpecAction,
+    /// Read as written; [`validate_and_normalize`](Self::validate_and_normalize)
+    /// repairs what it can before validating.
+    #[serde(deserialize_with = "CapabilitySpec::deserialize_unchecked")]
     pub spec: CapabilitySpec,
     pub design_notes: String,
     pub extend_target: Option<String>,
     pub extend_features: Option<Vec<String>>,
+    /// The tool is read-only, so a failed call may be retried safely.
+    #[serde(default)]
+    pub idempotent: bool,
+}
+
+impl RefinedSpec {
+    /// Parse the Architect's JSON answer. A lone entry where a list belongs
+    /// (`constraints.*`, `extend_features`) becomes a one-element list, and
+    /// `null` an empty one.
+    pub fn from_architect_json(mut value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let constraints = value
+            .get_mut("spec")
+            .and_then(|spec| spec.get_mut("constraints"))
+            .and_then(|c| c.as_object_mut());
+        for list in constraints.into_iter().flat_map(|c| c.values_mut()) {
+            coerce_string_list(list);
+        }
+        if let Some(features) = value.get_mut("extend_features") {
+            coerce_string_list(features);
+        }
+        serde_json::from_value(value)
+    }
+
+    /// Check a refined spec before anything is built from it, holding it to
+    /// the rules [`CapabilitySpec::validate`] applies to requests.
+    ///
+    /// Names, descriptions, and constraint entries are trimmed, and empty or
+    /// repeated constraint entries dropped. A rename to a name that is not a
+    /// valid tool name keeps `requested`'s name. A `recommend_extend` must
+    /// name the tool to extend.
+    pub fn validate_and_normalize(
+        mut self,
+        requested: &CapabilitySpec,
+    ) -> Result<Self, PipelineError> {
+        let spec = &mut self.spec;
+        spec.name = spec.name.trim().to_string();
+        spec.description = spec.description.trim().to_string();
+        let mut seen = std::collections::HashSet::new();
+        spec.constraints.network = std::mem::take(&mut spec.constraints.network)
+            .iter()
+            .map(NetworkGrant::normalized)
+            .filter(|grant| !grant.host().is_empty() && seen.insert(grant.clone()))
+            .collect();
+        for list in [&mut spec.constraints.storage, &mut spec.constraints.secrets] {
+            let mut seen = std::collections::HashSet::new();
+            *list = std::mem::take(list)
+                .into_iter()
+                .map(|entry| entry.trim().to_string())
+                .filter(|entry| !entry.is_empty() && seen.insert(entry.clone()))
+                .collect();
+        }
+
+        if spec.name != requested.name {
+            let renamed = CapabilitySpec {
+                name: spec.name.clone(),
+                ..requested.clone()
+            };
+            let name_invalid = renamed
+                .validate()
+                .is_err_and(|e| e.errors.iter().any(|f| f.field == "name"));
+            if name_invalid {
+                tracing::warn!(
+                    requested = %requested.name,
+                    refined = %spec.name,
+                    "Architect renamed the tool to an invalid name; keeping the requested name"
+                );
+                spec.name = requested.name.clone();
+            }
+        }
+
+        spec.validate()
+            .map_err(|e| PipelineError::ArchitectOutputInvalid(e.to_string()))?;
+
+        if self.action == SpecAction::RecommendExtend {
+            let target = self.extend_target.as_deref().map(str::trim);
+            match target {
+                Some(target) if !target.is_empty() => {
+                    self.extend_target = Some(target.to_string());
+                }
+                _ => {
+                    return Err(PipelineError::ArchitectOutputInvalid(
+                        "recommend_extend without an extend_target".into(),
+                    ));
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Turn a lone string into a one-element list and `null` into an empty one.
+fn coerce_string_list(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(_) | serde_json::Value::Object(_) => {
+            *value = serde_json::json!([value.take()])
+        }
+        serde_json::Value::Null => *value = serde_json::json!([]),
+        _ => {}
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -72,7 +304,7 @@ pub enum SpecAction {
 }
 
 /// Supported build target languages.
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TargetLanguage {
     #[default]
@@ -91,13 +323,97 @@ impl std::fmt::Display for TargetLanguage {
     }
 }
 
+/// Coding standards for the Engineer, by target language.
+///
+/// A language without its own standards gets the fallback, if any
+/// (`pipeline.coding_standards_path`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodingStandards {
+    by_language: HashMap<TargetLanguage, String>,
+    fallback: Option<String>,
+}
+
+impl CodingStandards {
+    /// Standards for languages that have none of their own.
+    pub fn with_fallback(mut self, standards: Option<String>) -> Self {
+        self.fallback = standards;
+        self
+    }
+
+    /// Standards for code written in `language`.
+    pub fn with_language(mut self, language: TargetLanguage, standards: String) -> Self {
+        self.by_language.insert(language, standards);
+        self
+    }
+
+    /// The standards the Engineer follows when writing `language`.
+    pub fn for_language(&self, language: &TargetLanguage) -> Option<&str> {
+        self.by_language
+            .get(language)
+            .or(self.fallback.as_ref())
+            .map(String::as_str)
+    }
+}
+
+/// Version of the `girt:tool` WIT world new tools are built against.
+///
+/// `0.2.0` reports failures as a `tool-error` record (kind, message,
+/// retryable) rather than a string. The runtime serves both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WitVersion {
+    #[default]
+    #[serde(rename = "0.1.0")]
+    V0_1,
+    #[serde(rename = "0.2.0")]
+    V0_2,
+}
+
+impl std::fmt::Display for WitVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V0_1 => write!(f, "0.1.0"),
+            Self::V0_2 => write!(f, "0.2.0"),
+        }
+    }
+}
+
 /// The Engineer's build output.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildOutput {
+    /// The crate root (`src/lib.rs`).
     pub source_code: String,
     pub wit_definition: String,
     pub policy_yaml: String,
     pub language: String,
+    /// Additional modules and tests, written alongside `source_code`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<SourceFile>,
+    /// The Engineer's one-line account of what a fix changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_summary: Option<String>,
+}
+
+impl BuildOutput {
+    /// All sources as one listing, for prompts that show the code back to
+    /// an agent. Just `source_code` when there are no extra files.
+    pub fn render_sources(&self) -> String {
+        if self.files.is_empty() {
+            return self.source_code.clone();
+        }
+        let mut out = format!("// ── src/lib.rs ──\n{}", self.source_code);
+        for file in &self.files {
+            out.push_str(&format!("\n\n// ── {} ──\n{}", file.path, file.content));
+        }
+        out
+    }
+}
+
+/// An extra file in a multi-file build, relative to the crate root
+/// (`src/helpers.rs`, `tests/run.rs`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceFile {
+    pub path: String,
+    pub content: String,
 }
 
 /// A bug ticket from QA or Red Team back to the Engineer.
@@ -105,6 +421,9 @@ pub struct BuildOutput {
 pub struct BugTicket {
     pub target: String,
     pub ticket_type: BugTicketType,
+    /// How bad the failure is, as judged by whoever filed the ticket.
+    #[serde(default)]
+    pub severity: Severity,
     pub input: serde_json::Value,
     pub expected: String,
     pub actual: String,
@@ -118,34 +437,558 @@ pub enum BugTicketType {
     SecurityVulnerability,
 }
 
+/// How bad a bug ticket's failure is, least to most.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    /// Tickets that give no severity are taken as high.
+    #[default]
+    High,
+    Critical,
+}
+
+/// The least severe ticket of each type that keeps a build from passing,
+/// configured in girt.toml as `[pipeline.blocking]`. Both default to
+/// `high`. Tickets below their type's threshold stay in the ticket history
+/// but are not sent back to the Engineer, and the build ships with them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockingPolicy {
+    #[serde(default)]
+    pub functional_defect: Severity,
+    #[serde(default)]
+    pub security_vulnerability: Severity,
+}
+
+impl BlockingPolicy {
+    /// The blocking threshold for tickets of type `ticket_type`.
+    pub fn threshold(&self, ticket_type: &BugTicketType) -> Severity {
+        match ticket_type {
+            BugTicketType::FunctionalDefect => self.functional_defect,
+            BugTicketType::SecurityVulnerability => self.security_vulnerability,
+        }
+    }
+}
+
+impl BugTicket {
+    /// Whether the ticket keeps a build from passing under `policy`: it is
+    /// at least as severe as its type's threshold.
+    pub fn is_blocking_under(&self, policy: &BlockingPolicy) -> bool {
+        self.severity >= policy.threshold(&self.ticket_type)
+    }
+
+    /// Identity of "the same failure" across fix iterations: ticket type,
+    /// input, and expected outcome.
+    ///
+    /// `actual` and the remediation directive are left out because reviewers
+    /// reword them between iterations. Input object keys are sorted and
+    /// `expected` is compared case- and whitespace-insensitively.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}", self.ticket_type));
+        hasher.update([0]);
+        hasher.update(canonical_json(&self.input));
+        hasher.update([0]);
+        hasher.update(normalize_text(&self.expected));
+        hex::encode(&hasher.finalize()[..8])
+    }
+}
+
+/// Serialize `value` with object keys sorted at every level.
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    format!(
+                        "{}:{}",
+                        serde_json::Value::from(k.as_str()),
+                        canonical_json(v)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        serde_json::Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// One distinct bug ticket across a pipeline run's fix iterations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketRecord {
+    /// See [`BugTicket::fingerprint`].
+    pub fingerprint: String,
+    /// The ticket as most recently filed.
+    pub ticket: BugTicket,
+    pub first_iteration: u32,
+    pub last_iteration: u32,
+    /// Number of iterations in which the ticket was filed.
+    pub occurrences: u32,
+}
+
+/// Distinct tickets filed during a pipeline run, in first-seen order.
+#[derive(Debug, Clone, Default)]
+pub struct TicketHistory {
+    records: Vec<TicketRecord>,
+}
+
+impl TicketHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the tickets filed in `iteration`. A ticket filed twice in the
+    /// same iteration counts once. Returns `(new, recurring)` counts.
+    pub fn record(&mut self, iteration: u32, tickets: &[BugTicket]) -> (usize, usize) {
+        let (mut new, mut recurring) = (0, 0);
+        for ticket in tickets {
+            let fingerprint = ticket.fingerprint();
+            match self
+                .records
+                .iter_mut()
+                .find(|r| r.fingerprint == fingerprint)
+            {
+                Some(record) if record.last_iteration == iteration => {}
+                Some(record) => {
+                    record.ticket = ticket.clone();
+                    record.last_iteration = iteration;
+                    record.occurrences += 1;
+                    recurring += 1;
+                }
+                None => {
+                    self.records.push(TicketRecord {
+                        fingerprint,
+                        ticket: ticket.clone(),
+                        first_iteration: iteration,
+                        last_iteration: iteration,
+                        occurrences: 1,
+                    });
+                    new += 1;
+                }
+            }
+        }
+        (new, recurring)
+    }
+
+    /// How many iterations have filed `ticket` (0 if never recorded).
+    pub fn occurrences(&self, ticket: &BugTicket) -> u32 {
+        let fingerprint = ticket.fingerprint();
+        self.records
+            .iter()
+            .find(|r| r.fingerprint == fingerprint)
+            .map_or(0, |r| r.occurrences)
+    }
+
+    pub fn records(&self) -> &[TicketRecord] {
+        &self.records
+    }
+
+    pub fn into_records(self) -> Vec<TicketRecord> {
+        self.records
+    }
+}
+
+/// One Engineer fix, as summarized for the prompts of later fixes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixAttempt {
+    pub iteration: u32,
+    /// [`BugTicket::fingerprint`] of the ticket the fix addressed.
+    pub fingerprint: String,
+    /// What the ticket expected, so the entry reads without the ticket.
+    pub expected: String,
+    /// The Engineer's `change_summary`, or the change in source size when
+    /// it gave none.
+    pub summary: String,
+}
+
+/// Fixes attempted during a pipeline run, oldest first.
+///
+/// Each fix prompt only carries the latest code and ticket, so without
+/// this the Engineer has no idea what it already tried and will sometimes
+/// revert an earlier fix.
+#[derive(Debug, Clone, Default)]
+pub struct FixHistory {
+    attempts: Vec<FixAttempt>,
+}
+
+impl FixHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the fix made in `iteration` for `ticket`, which turned
+    /// `before` into `after`.
+    pub fn record(
+        &mut self,
+        iteration: u32,
+        ticket: &BugTicket,
+        before: &BuildOutput,
+        after: &BuildOutput,
+    ) {
+        let summary = match after.change_summary.as_deref().map(str::trim) {
+            Some(summary) if !summary.is_empty() => summary.to_string(),
+            _ => format!(
+                "no summary given; source went from {} to {} bytes",
+                before.render_sources().len(),
+                after.render_sources().len()
+            ),
+        };
+        self.attempts.push(FixAttempt {
+            iteration,
+            fingerprint: ticket.fingerprint(),
+            expected: ticket.expected.clone(),
+            summary,
+        });
+    }
+
+    pub fn attempts(&self) -> &[FixAttempt] {
+        &self.attempts
+    }
+
+    /// Prompt section listing earlier fixes; empty before the first fix.
+    pub fn render(&self) -> String {
+        if self.attempts.is_empty() {
+            return String::new();
+        }
+        let mut out = String::from(
+            "Previously attempted fixes (keep them unless this ticket shows one was wrong):",
+        );
+        for attempt in &self.attempts {
+            out.push_str(&format!(
+                "\n- Iteration {}, ticket {} (expected: {}): {}",
+                attempt.iteration, attempt.fingerprint, attempt.expected, attempt.summary
+            ));
+        }
+        out
+    }
+}
+
 /// QA test results.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QaResult {
+    /// Tests ran, none failed, and no bug tickets were filed; derived, not
+    /// taken from the agent's report.
     pub passed: bool,
     pub tests_run: u32,
     pub tests_passed: u32,
     pub tests_failed: u32,
     pub bug_tickets: Vec<BugTicket>,
+    /// Where the agent's report was missing counts or contradicted itself,
+    /// and what was recorded instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_quality: Vec<String>,
+}
+
+impl QaResult {
+    /// Read the QA agent's JSON report and [`normalize`](Self::normalize)
+    /// it. A count the report leaves out is taken as zero, with a note.
+    pub fn from_report(report: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let missing = missing_counts(&report, &["tests_run", "tests_passed", "tests_failed"]);
+        let report: AgentReport<QaCounts> = serde_json::from_value(report)?;
+        let mut result = Self {
+            passed: report.passed,
+            tests_run: report.counts.tests_run,
+            tests_passed: report.counts.tests_passed,
+            tests_failed: report.counts.tests_failed,
+            bug_tickets: report.bug_tickets,
+            data_quality: missing,
+        };
+        result.normalize();
+        Ok(result)
+    }
+
+    /// Make the numbers agree: `tests_run` is raised to at least
+    /// `tests_passed + tests_failed`, and `passed` is derived from the
+    /// counts and tickets. Each correction is noted in `data_quality`.
+    pub fn normalize(&mut self) {
+        let counted = self.tests_passed.saturating_add(self.tests_failed);
+        if self.tests_run < counted {
+            self.data_quality.push(format!(
+                "tests_run was {} but {} tests passed and {} failed; recorded {counted} run",
+                self.tests_run, self.tests_passed, self.tests_failed
+            ));
+            self.tests_run = counted;
+        }
+        let passed = self.tests_run > 0 && self.tests_failed == 0 && self.bug_tickets.is_empty();
+        if self.passed != passed {
+            self.data_quality.push(format!(
+                "reported passed={} with {} of {} tests failed and {} bug tickets; recorded passed={passed}",
+                self.passed,
+                self.tests_failed,
+                self.tests_run,
+                self.bug_tickets.len()
+            ));
+            self.passed = passed;
+        }
+        warn_data_quality("QA", &self.data_quality);
+    }
+
+    /// Whether the build may ship as far as QA is concerned: it passed, or
+    /// tests ran and everything that stopped it passing was filed as
+    /// tickets below their blocking threshold.
+    pub fn passes_under(&self, policy: &BlockingPolicy) -> bool {
+        self.passed || (self.tests_run > 0 && only_non_blocking(&self.bug_tickets, policy))
+    }
 }
 
 /// Red Team audit results.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityResult {
+    /// Exploits were attempted, none succeeded, and no bug tickets were
+    /// filed; derived, not taken from the agent's report.
     pub passed: bool,
     pub exploits_attempted: u32,
     pub exploits_succeeded: u32,
     pub bug_tickets: Vec<BugTicket>,
+    /// Where the agent's report was missing counts or contradicted itself,
+    /// and what was recorded instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_quality: Vec<String>,
+}
+
+impl SecurityResult {
+    /// Read the Red Team agent's JSON report and
+    /// [`normalize`](Self::normalize) it. A count the report leaves out is
+    /// taken as zero, with a note.
+    pub fn from_report(report: serde_json::Value) -> Result<Self, serde_json::Error> {
+        let missing = missing_counts(&report, &["exploits_attempted", "exploits_succeeded"]);
+        let report: AgentReport<SecurityCounts> = serde_json::from_value(report)?;
+        let mut result = Self {
+            passed: report.passed,
+            exploits_attempted: report.counts.exploits_attempted,
+            exploits_succeeded: report.counts.exploits_succeeded,
+            bug_tickets: report.bug_tickets,
+            data_quality: missing,
+        };
+        result.normalize();
+        Ok(result)
+    }
+
+    /// Make the numbers agree: `exploits_attempted` is raised to at least
+    /// `exploits_succeeded`, and `passed` is derived from the counts and
+    /// tickets. Each correction is noted in `data_quality`.
+    pub fn normalize(&mut self) {
+        if self.exploits_attempted < self.exploits_succeeded {
+            self.data_quality.push(format!(
+                "exploits_attempted was {} but {} succeeded; recorded {} attempted",
+                self.exploits_attempted, self.exploits_succeeded, self.exploits_succeeded
+            ));
+            self.exploits_attempted = self.exploits_succeeded;
+        }
+        let passed = self.exploits_attempted > 0
+            && self.exploits_succeeded == 0
+            && self.bug_tickets.is_empty();
+        if self.passed != passed {
+            self.data_quality.push(format!(
+                "reported passed={} with {} of {} exploits succeeded and {} bug tickets; recorded passed={passed}",
+                self.passed,
+                self.exploits_succeeded,
+                self.exploits_attempted,
+                self.bug_tickets.len()
+            ));
+            self.passed = passed;
+        }
+        warn_data_quality("Red Team", &self.data_quality);
+    }
+
+    /// Whether the build may ship as far as the Red Team is concerned: it
+    /// passed, or exploits were attempted and every finding was filed as a
+    /// ticket below its blocking threshold.
+    pub fn passes_under(&self, policy: &BlockingPolicy) -> bool {
+        self.passed || (self.exploits_attempted > 0 && only_non_blocking(&self.bug_tickets, policy))
+    }
+}
+
+/// There are tickets, and none blocks under `policy`.
+fn only_non_blocking(tickets: &[BugTicket], policy: &BlockingPolicy) -> bool {
+    !tickets.is_empty() && !tickets.iter().any(|t| t.is_blocking_under(policy))
+}
+
+/// The fields QA and Red Team reports share, around their own counts.
+#[derive(Deserialize)]
+struct AgentReport<C> {
+    #[serde(default)]
+    passed: bool,
+    #[serde(flatten)]
+    counts: C,
+    #[serde(default)]
+    bug_tickets: Vec<BugTicket>,
+}
+
+#[derive(Deserialize)]
+struct QaCounts {
+    #[serde(default)]
+    tests_run: u32,
+    #[serde(default)]
+    tests_passed: u32,
+    #[serde(default)]
+    tests_failed: u32,
+}
+
+#[derive(Deserialize)]
+struct SecurityCounts {
+    #[serde(default)]
+    exploits_attempted: u32,
+    #[serde(default)]
+    exploits_succeeded: u32,
+}
+
+/// A note for each of `fields` absent from `report`.
+fn missing_counts(report: &serde_json::Value, fields: &[&str]) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|field| report.get(field).is_none_or(serde_json::Value::is_null))
+        .map(|field| format!("{field} was missing; recorded 0"))
+        .collect()
+}
+
+fn warn_data_quality(agent: &str, notes: &[String]) {
+    for note in notes {
+        tracing::warn!(agent, note = %note, "Inconsistent agent report");
+    }
 }
 
 /// The final build artifact ready for publishing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildArtifact {
+    /// Read as stored, without validation, so a manifest written before a
+    /// validation rule was added still loads (see
+    /// [`ToolCache::get`](crate::cache::ToolCache::get)).
+    #[serde(deserialize_with = "CapabilitySpec::deserialize_unchecked")]
     pub spec: CapabilitySpec,
     pub refined_spec: RefinedSpec,
     pub build_output: BuildOutput,
     pub qa_result: QaResult,
     pub security_result: SecurityResult,
     pub build_iterations: u32,
+    /// Every distinct bug ticket filed while building, with recurrence counts.
+    #[serde(default)]
+    pub ticket_history: Vec<TicketRecord>,
+    /// How the tool was built. Absent in manifests written before
+    /// provenance was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// The name the tool was requested under, when it was published under
+    /// another to avoid replacing a different tool (see
+    /// [`NameCollision::Suffix`](crate::publish::NameCollision::Suffix)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_name: Option<String>,
+    /// Times the tool has been rebuilt from its stored spec (see
+    /// [`crate::rebuild`]); 0 for a tool built once.
+    #[serde(default)]
+    pub revision: u32,
+    /// When the tool was last published. Absent in manifests written
+    /// before this was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+    /// The Engineer's output in each iteration, first build first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iteration_outputs: Vec<IterationSnapshot>,
+    /// Inputs paired with what the tool returned for them when it was
+    /// built, re-run by `girt tools validate` to catch upstream drift (see
+    /// [`crate::validation`]). Not carried over by a rebuild.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test_vectors: Vec<TestVector>,
+}
+
+/// What the Engineer produced in one build iteration, without the code
+/// itself (see `[pipeline] keep_iteration_sources` for that).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IterationSnapshot {
+    /// Numbered from 1.
+    pub iteration: u32,
+    /// SHA-256 hex of `src/lib.rs`.
+    pub source_sha256: String,
+    /// Length of `src/lib.rs` in bytes.
+    pub source_len: usize,
+    /// Fingerprints of the tickets this iteration was a fix for (see
+    /// [`BugTicket::fingerprint`]); empty for the first build.
+    pub tickets_addressed: Vec<String>,
+}
+
+impl IterationSnapshot {
+    pub fn new(iteration: u32, output: &BuildOutput, tickets_addressed: Vec<String>) -> Self {
+        Self {
+            iteration,
+            source_sha256: hex::encode(Sha256::digest(output.source_code.as_bytes())),
+            source_len: output.source_code.len(),
+            tickets_addressed,
+        }
+    }
+}
+
+/// What produced a build: girt version, models and prompts, pipeline
+/// settings, and where the time went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Version of girt-pipeline that ran the build.
+    pub girt_version: String,
+    /// Absent for builds started from an already-refined spec.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_source: Option<RequestSource>,
+    /// Keyed by agent: `architect`, `engineer`, `engineer_fix`, `qa`,
+    /// `red_team`. The Architect is missing when it did not run, and
+    /// extensions add `engineer_extend`.
+    pub agents: BTreeMap<String, AgentProvenance>,
+    pub pipeline: PipelineSnapshot,
+    pub timings: StageTimings,
+}
+
+/// The LLM and system prompt one agent ran with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentProvenance {
+    pub provider: String,
+    pub model: String,
+    /// Hex SHA-256 of the system prompt, including any injected coding
+    /// standards.
+    pub prompt_sha256: String,
+}
+
+/// Pipeline settings in effect for a build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineSnapshot {
+    /// Build-fix iterations allowed before the circuit breaker fails the
+    /// build.
+    pub max_iterations: u32,
+}
+
+/// Wall-clock time per pipeline stage, summed over build iterations.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StageTimings {
+    pub architect_ms: u64,
+    /// Initial build plus every fix.
+    pub engineer_ms: u64,
+    pub qa_ms: u64,
+    pub red_team_ms: u64,
+    pub total_ms: u64,
+    /// Some agent call was answered from the LLM cache (`[llm] cache`), so
+    /// these times understate a cold run.
+    #[serde(default)]
+    pub cached: bool,
+    /// Providers that answered the agents' calls, in the order first used.
+    /// More than one when `[[llm.fallback]]` took over part of the run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
 }
 
 /// Wassette policy.yaml content.
@@ -171,6 +1014,12 @@ pub struct NetworkPermissions {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkHost {
     pub host: String,
+    /// HTTP methods allowed on the host; empty allows any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    /// Path prefixes allowed on the host; empty allows any path.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_prefixes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -181,8 +1030,8 @@ pub struct PolicyResources {
     pub max_response_bytes: u64,
 }
 
-/// Predefined resource limit tiers for policy generation.
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Predefined resource limit tiers for policy generation, smallest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ResourceTier {
     /// Minimal resources for simple stateless transforms (64 MB, 5s timeout).
@@ -266,7 +1115,11 @@ impl PolicyYaml {
                         .constraints
                         .network
                         .iter()
-                        .map(|h| NetworkHost { host: h.clone() })
+                        .map(|grant| NetworkHost {
+                            host: grant.host().into(),
+                            methods: grant.methods().to_vec(),
+                            path_prefixes: grant.path_prefixes().to_vec(),
+                        })
                         .collect(),
                 },
                 storage: serde_json::json!({}),
@@ -276,6 +1129,114 @@ impl PolicyYaml {
         }
     }
 
+    /// Environment variable names a policy allows the component to read.
+    ///
+    /// Accepts the Wassette layout in YAML or JSON form, with entries either
+    /// as `{ key: NAME }` maps or bare strings:
+    ///
+    /// ```yaml
+    /// permissions:
+    ///   environment:
+    ///     allow:
+    ///       - key: GITLAB_BASE_URL
+    /// ```
+    ///
+    /// Unparseable policies allow nothing.
+    pub fn env_allowlist(policy_yaml: &str) -> Vec<String> {
+        let Ok(doc) = serde_yaml::from_str::<serde_yaml::Value>(policy_yaml) else {
+            return Vec::new();
+        };
+        let Some(entries) = doc
+            .get("permissions")
+            .and_then(|p| p.get("environment"))
+            .and_then(|e| e.get("allow"))
+            .and_then(|a| a.as_sequence())
+        else {
+            return Vec::new();
+        };
+        entries
+            .iter()
+            .filter_map(|entry| match entry {
+                serde_yaml::Value::String(key) => Some(key.clone()),
+                other => other.get("key").and_then(|k| k.as_str()).map(String::from),
+            })
+            .collect()
+    }
+
+    /// Network hosts a policy allows the component to reach, as written
+    /// under `permissions.network.allow` (`host:port` and `*.domain` forms
+    /// included), with any `methods` and `path_prefixes` scoping. Entries
+    /// that do not parse are skipped; unparseable policies allow nothing.
+    pub fn network_allowlist(policy_yaml: &str) -> Vec<NetworkGrant> {
+        let Ok(doc) = serde_yaml::from_str::<serde_yaml::Value>(policy_yaml) else {
+            return Vec::new();
+        };
+        let Some(entries) = doc
+            .get("permissions")
+            .and_then(|p| p.get("network"))
+            .and_then(|n| n.get("allow"))
+            .and_then(|a| a.as_sequence())
+        else {
+            return Vec::new();
+        };
+        entries
+            .iter()
+            .filter_map(|entry| serde_yaml::from_value::<NetworkGrant>(entry.clone()).ok())
+            .map(|grant| grant.normalized())
+            .collect()
+    }
+
+    /// How `policy_yaml` grants more than `spec` asks for: network hosts
+    /// missing from its constraints or scoped wider than they are there, or
+    /// resources over the hard ceiling.
+    /// Empty when the policy is within the spec.
+    pub fn violations(policy_yaml: &str, spec: &CapabilitySpec) -> Vec<String> {
+        let doc = match serde_yaml::from_str::<serde_yaml::Value>(policy_yaml) {
+            Ok(doc) => doc,
+            Err(e) => return vec![format!("policy is not valid YAML: {e}")],
+        };
+        let granted = &spec.constraints.network;
+        let mut violations: Vec<String> = Self::network_allowlist(policy_yaml)
+            .into_iter()
+            .filter(|grant| !granted.iter().any(|g| g.covers(grant)))
+            .map(|grant| {
+                let host = grant.host();
+                if granted.iter().any(|g| g.host().eq_ignore_ascii_case(host)) {
+                    format!("network grant '{grant}' is wider than the spec's constraints allow")
+                } else {
+                    format!("network host '{host}' is not in the spec's constraints")
+                }
+            })
+            .collect();
+        if let Some(resources) = doc.get("resources") {
+            match serde_yaml::from_value::<PolicyResources>(resources.clone()) {
+                Ok(resources) => violations.extend(resources.validate().err()),
+                Err(e) => violations.push(format!("resources: {e}")),
+            }
+        }
+        violations
+    }
+
+    /// The policy's `resources.max_response_bytes`, if it sets one.
+    pub fn max_response_bytes(policy_yaml: &str) -> Option<u64> {
+        serde_yaml::from_str::<serde_yaml::Value>(policy_yaml)
+            .ok()?
+            .get("resources")?
+            .get("max_response_bytes")?
+            .as_u64()
+    }
+
+    /// The policy's `resources.timeout_seconds` in milliseconds, if it
+    /// sets one.
+    pub fn timeout_ms(policy_yaml: &str) -> Option<u64> {
+        serde_yaml::from_str::<serde_yaml::Value>(policy_yaml)
+            .ok()?
+            .get("resources")?
+            .get("timeout_seconds")?
+            .as_u64()
+            .map(|seconds| seconds * 1000)
+    }
+
     /// Infer the appropriate resource tier from a spec's constraints.
     pub fn infer_tier(spec: &CapabilitySpec) -> ResourceTier {
         let has_network = !spec.constraints.network.is_empty();
@@ -290,3 +1251,625 @@ impl PolicyYaml {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(input: serde_json::Value, expected: &str, actual: &str) -> BugTicket {
+        BugTicket {
+            target: "engineer".into(),
+            ticket_type: BugTicketType::FunctionalDefect,
+            severity: Severity::High,
+            input,
+            expected: expected.into(),
+            actual: actual.into(),
+            remediation_directive: "fix it".into(),
+        }
+    }
+
+    fn refined(name: &str) -> RefinedSpec {
+        RefinedSpec::from_architect_json(serde_json::json!({
+            "action": "build",
+            "spec": {
+                "name": name,
+                "description": "Query issues",
+                "constraints": {"network": [" api.github.com", "api.github.com", ""]}
+            },
+            "design_notes": "",
+            "extend_target": null,
+            "extend_features": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn refined_spec_keeps_valid_renames_only() {
+        let requested = CapabilitySpec {
+            name: "fetch_issues".into(),
+            description: "Fetch issues".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: Default::default(),
+        };
+
+        let renamed = refined("github_issues")
+            .validate_and_normalize(&requested)
+            .unwrap();
+        assert_eq!(renamed.spec.name, "github_issues");
+        assert_eq!(renamed.spec.constraints.network, ["api.github.com"]);
+        assert_eq!(renamed.extend_features, Some(vec![]));
+
+        let invalid = refined("GitHub Issues")
+            .validate_and_normalize(&requested)
+            .unwrap();
+        assert_eq!(invalid.spec.name, "fetch_issues");
+    }
+
+    #[test]
+    fn fingerprint_ignores_rewording_and_key_order() {
+        let a = ticket(
+            serde_json::json!({"a": 1, "b": [2]}),
+            "Returns 3",
+            "returned 4",
+        );
+        let b = ticket(
+            serde_json::from_str(r#"{"b": [2], "a": 1}"#).unwrap(),
+            "  returns   3 ",
+            "got 5 instead",
+        );
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        let c = ticket(
+            serde_json::json!({"a": 1, "b": [2]}),
+            "Returns 4",
+            "returned 4",
+        );
+        assert_ne!(a.fingerprint(), c.fingerprint());
+
+        let mut d = a.clone();
+        d.ticket_type = BugTicketType::SecurityVulnerability;
+        assert_ne!(a.fingerprint(), d.fingerprint());
+    }
+
+    #[test]
+    fn ticket_history_counts_recurrences_per_iteration() {
+        let repeat = ticket(serde_json::json!({"x": 1}), "ok", "err");
+        let other = ticket(serde_json::json!({"x": 2}), "ok", "err");
+        let mut history = TicketHistory::new();
+
+        assert_eq!(history.record(1, &[repeat.clone(), repeat.clone()]), (1, 0));
+        assert_eq!(history.record(2, &[repeat.clone(), other.clone()]), (1, 1));
+        assert_eq!(history.record(3, std::slice::from_ref(&repeat)), (0, 1));
+
+        assert_eq!(history.occurrences(&repeat), 3);
+        assert_eq!(history.occurrences(&other), 1);
+        let records = history.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            (records[0].first_iteration, records[0].last_iteration),
+            (1, 3)
+        );
+    }
+
+    #[test]
+    fn overrides_are_capped() {
+        let caps = PipelineCaps::default();
+        let greedy = PipelineOverrides {
+            max_iterations: Some(50),
+            target_language: Some(TargetLanguage::Go),
+            resource_tier: Some(ResourceTier::Extended),
+            token_budget: Some(u32::MAX),
+        };
+        assert_eq!(
+            greedy.capped(&caps),
+            PipelineOverrides {
+                max_iterations: Some(caps.max_iterations),
+                target_language: None,
+                resource_tier: Some(ResourceTier::Standard),
+                token_budget: Some(caps.token_budget),
+            }
+        );
+
+        let modest = PipelineOverrides {
+            max_iterations: Some(1),
+            target_language: Some(TargetLanguage::Rust),
+            resource_tier: Some(ResourceTier::Minimal),
+            token_budget: Some(20_000),
+        };
+        assert_eq!(modest.capped(&caps), modest);
+    }
+
+    #[test]
+    fn requests_without_overrides_still_load() {
+        let spec = CapabilitySpec {
+            name: "t".into(),
+            description: "t".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: Default::default(),
+        };
+        let plain = CapabilityRequest::new(spec.clone(), RequestSource::Cli);
+        let json = serde_json::to_value(&plain).unwrap();
+        assert!(json.get("pipeline_overrides").is_none());
+        let loaded: CapabilityRequest = serde_json::from_value(json).unwrap();
+        assert!(loaded.pipeline_overrides.is_empty());
+
+        let overrides = PipelineOverrides {
+            max_iterations: Some(5),
+            ..Default::default()
+        };
+        let request = CapabilityRequest::new(spec, RequestSource::Cli).with_overrides(overrides);
+        let json = serde_json::to_string(&request).unwrap();
+        let loaded: CapabilityRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.pipeline_overrides.max_iterations, Some(5));
+    }
+
+    #[test]
+    fn request_sources_round_trip_and_unknown_ones_load_as_operator() {
+        let spec = CapabilitySpec {
+            name: "t".into(),
+            description: "t".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: Default::default(),
+        };
+        for source in [
+            RequestSource::Agent,
+            RequestSource::Operator,
+            RequestSource::Cli,
+            RequestSource::Hook,
+        ] {
+            let mut json =
+                serde_json::to_value(CapabilityRequest::new(spec.clone(), source)).unwrap();
+            assert_eq!(json["source"], source.as_str());
+            let loaded: CapabilityRequest = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(loaded.source, source);
+
+            json["source"] = "scheduler".into();
+            let loaded: CapabilityRequest = serde_json::from_value(json).unwrap();
+            assert_eq!(loaded.source, RequestSource::Operator);
+        }
+    }
+
+    fn build(source: &str, change_summary: Option<&str>) -> BuildOutput {
+        BuildOutput {
+            source_code: source.into(),
+            wit_definition: String::new(),
+            policy_yaml: String::new(),
+            language: "rust".into(),
+            files: vec![],
+            change_summary: change_summary.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn fix_history_grows_one_line_per_fix() {
+        let mut history = FixHistory::new();
+        assert_eq!(history.render(), "");
+
+        let first = ticket(serde_json::json!({"x": -1}), "error response", "panic");
+        history.record(
+            1,
+            &first,
+            &build("v1", None),
+            &build("v2", Some("Reject negative input ")),
+        );
+        let after_one = history.render();
+        assert!(after_one.starts_with("Previously attempted fixes"));
+        assert!(after_one.contains(&format!(
+            "- Iteration 1, ticket {} (expected: error response): Reject negative input",
+            first.fingerprint()
+        )));
+
+        let second = ticket(serde_json::json!({"x": 0}), "zero", "one");
+        history.record(2, &second, &build("v2", None), &build("v2 longer", None));
+        let after_two = history.render();
+        assert!(after_two.starts_with(&after_one));
+        assert!(after_two.ends_with("no summary given; source went from 2 to 9 bytes"));
+        assert_eq!(history.attempts().len(), 2);
+    }
+
+    #[test]
+    fn env_allowlist_reads_yaml_keys() {
+        let policy = r#"
+version: "1.0"
+permissions:
+  environment:
+    allow:
+      - key: GITLAB_BASE_URL
+      - GITLAB_PROJECT
+"#;
+        assert_eq!(
+            PolicyYaml::env_allowlist(policy),
+            vec!["GITLAB_BASE_URL", "GITLAB_PROJECT"]
+        );
+    }
+
+    #[test]
+    fn policy_violations_compare_against_the_spec() {
+        let spec = CapabilitySpec {
+            name: "fetch".into(),
+            description: "Fetch a page".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: girt_core::spec::CapabilityConstraints {
+                network: vec!["example.com".into()],
+                ..Default::default()
+            },
+        };
+        let generated = serde_yaml::to_string(&PolicyYaml::from_spec(&spec)).unwrap();
+        assert!(PolicyYaml::violations(&generated, &spec).is_empty());
+
+        let wider = r#"
+permissions:
+  network:
+    allow:
+      - host: example.com
+      - host: evil.example
+resources:
+  memory_mb: 4096
+  fuel: 1
+  timeout_seconds: 5
+  max_response_bytes: 1024
+"#;
+        assert_eq!(
+            PolicyYaml::violations(wider, &spec),
+            vec![
+                "network host 'evil.example' is not in the spec's constraints",
+                "memory_mb 4096 exceeds maximum 1024",
+            ]
+        );
+        assert_eq!(PolicyYaml::violations(": not yaml [", &spec).len(), 1);
+    }
+
+    #[test]
+    fn scoped_grants_reach_the_policy_and_bound_it() {
+        let spec = CapabilitySpec {
+            name: "gh_issues".into(),
+            description: "List GitHub issues".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: girt_core::spec::CapabilityConstraints {
+                network: vec![NetworkGrant::scoped(
+                    "api.github.com",
+                    vec!["GET".into()],
+                    vec!["/repos/".into()],
+                )],
+                ..Default::default()
+            },
+        };
+        let generated = serde_yaml::to_string(&PolicyYaml::from_spec(&spec)).unwrap();
+        assert_eq!(
+            PolicyYaml::network_allowlist(&generated),
+            spec.constraints.network
+        );
+        assert!(PolicyYaml::violations(&generated, &spec).is_empty());
+
+        let narrower = r#"
+permissions:
+  network:
+    allow:
+      - host: api.github.com
+        methods: [get]
+        path_prefixes: [/repos/girt/]
+"#;
+        assert!(PolicyYaml::violations(narrower, &spec).is_empty());
+
+        let wider = r#"
+permissions:
+  network:
+    allow:
+      - host: api.github.com
+        methods: [GET, DELETE]
+        path_prefixes: [/repos/]
+      - api.github.com
+"#;
+        assert_eq!(
+            PolicyYaml::violations(wider, &spec),
+            vec![
+                "network grant 'api.github.com (GET, DELETE) under /repos/' is wider than the spec's constraints allow",
+                "network grant 'api.github.com' is wider than the spec's constraints allow",
+            ]
+        );
+    }
+
+    #[test]
+    fn architect_grants_are_normalized() {
+        let requested = CapabilitySpec {
+            name: "gh_issues".into(),
+            description: "List GitHub issues".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: Default::default(),
+        };
+        let refined = |network: serde_json::Value| {
+            RefinedSpec::from_architect_json(serde_json::json!({
+                "action": "build",
+                "spec": {
+                    "name": "gh_issues",
+                    "description": "List GitHub issues",
+                    "constraints": {"network": network}
+                },
+                "design_notes": ""
+            }))
+            .unwrap()
+            .validate_and_normalize(&requested)
+            .unwrap()
+            .spec
+            .constraints
+            .network
+        };
+        let scoped =
+            NetworkGrant::scoped("api.github.com", vec!["GET".into()], vec!["/repos/".into()]);
+
+        assert_eq!(
+            refined(serde_json::json!([
+                " api.github.com ",
+                {"host": "api.github.com", "methods": ["get", "GET "], "path_prefixes": ["/repos/"]},
+                {"host": "api.github.com", "methods": ["GET"], "path_prefixes": ["/repos/"]},
+                {"host": "example.com", "methods": [], "path_prefixes": []},
+            ])),
+            vec![
+                "api.github.com".into(),
+                scoped.clone(),
+                "example.com".into()
+            ]
+        );
+        assert_eq!(
+            refined(serde_json::json!({
+                "host": "api.github.com", "methods": ["GET"], "path_prefixes": ["/repos/"]
+            })),
+            vec![scoped]
+        );
+    }
+
+    #[test]
+    fn env_allowlist_reads_json_fallback_policy() {
+        let policy = r#"{"version":"1.0","permissions":{"network":{"allow":[]},"environment":{"allow":[{"key":"API_URL"}]}}}"#;
+        assert_eq!(PolicyYaml::env_allowlist(policy), vec!["API_URL"]);
+    }
+
+    #[test]
+    fn network_allowlist_and_response_cap_read_generated_policy() {
+        let spec = CapabilitySpec {
+            name: "t".into(),
+            description: "t".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: girt_core::spec::CapabilityConstraints {
+                network: vec!["api.github.com".into(), "*.example.com:8443".into()],
+                ..Default::default()
+            },
+        };
+        let generated = serde_yaml::to_string(&PolicyYaml::from_spec(&spec)).unwrap();
+        assert_eq!(
+            PolicyYaml::network_allowlist(&generated),
+            vec!["api.github.com", "*.example.com:8443"]
+        );
+        assert_eq!(PolicyYaml::max_response_bytes(&generated), Some(5_242_880));
+        assert_eq!(PolicyYaml::timeout_ms(&generated), Some(15_000));
+
+        assert!(PolicyYaml::network_allowlist(": not yaml [").is_empty());
+        assert_eq!(PolicyYaml::max_response_bytes("version: \"1.0\""), None);
+        assert_eq!(PolicyYaml::timeout_ms("version: \"1.0\""), None);
+    }
+
+    #[test]
+    fn env_allowlist_defaults_to_nothing() {
+        assert!(PolicyYaml::env_allowlist("version: \"1.0\"").is_empty());
+        assert!(PolicyYaml::env_allowlist(": not yaml [").is_empty());
+        let spec = CapabilitySpec {
+            name: "t".into(),
+            description: "t".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: Default::default(),
+        };
+        let generated = serde_json::to_string(&PolicyYaml::from_spec(&spec)).unwrap();
+        assert!(PolicyYaml::env_allowlist(&generated).is_empty());
+    }
+
+    fn qa(report: serde_json::Value) -> QaResult {
+        QaResult::from_report(report).unwrap()
+    }
+
+    #[test]
+    fn consistent_qa_reports_are_kept_as_they_are() {
+        let result = qa(serde_json::json!({
+            "passed": true,
+            "tests_run": 5,
+            "tests_passed": 5,
+            "tests_failed": 0,
+            "bug_tickets": []
+        }));
+        assert!(result.passed);
+        assert_eq!(result.tests_run, 5);
+        assert!(result.data_quality.is_empty());
+    }
+
+    #[test]
+    fn qa_passed_is_derived_from_failures_and_tickets() {
+        let failures = qa(serde_json::json!({
+            "passed": true,
+            "tests_run": 5,
+            "tests_passed": 2,
+            "tests_failed": 3,
+            "bug_tickets": []
+        }));
+        assert!(!failures.passed);
+        assert_eq!(
+            failures.data_quality,
+            [
+                "reported passed=true with 3 of 5 tests failed and 0 bug tickets; recorded passed=false"
+            ]
+        );
+
+        let tickets = qa(serde_json::json!({
+            "passed": true,
+            "tests_run": 5,
+            "tests_passed": 5,
+            "tests_failed": 0,
+            "bug_tickets": [ticket(serde_json::json!({}), "greeting", "nothing")]
+        }));
+        assert!(!tickets.passed);
+        assert_eq!(tickets.data_quality.len(), 1);
+
+        // Nothing failed and nothing to fix: a pass, whatever was reported.
+        let clean = qa(serde_json::json!({
+            "passed": false,
+            "tests_run": 4,
+            "tests_passed": 4,
+            "tests_failed": 0,
+            "bug_tickets": []
+        }));
+        assert!(clean.passed);
+    }
+
+    #[test]
+    fn qa_tests_run_is_raised_to_the_tests_counted() {
+        let result = qa(serde_json::json!({
+            "passed": true,
+            "tests_run": 3,
+            "tests_passed": 5,
+            "tests_failed": 0,
+            "bug_tickets": []
+        }));
+        assert_eq!(result.tests_run, 5);
+        assert!(result.passed);
+        assert_eq!(
+            result.data_quality,
+            ["tests_run was 3 but 5 tests passed and 0 failed; recorded 5 run"]
+        );
+    }
+
+    #[test]
+    fn missing_counts_are_zero_with_a_note() {
+        let partial = qa(serde_json::json!({
+            "passed": true,
+            "tests_passed": 4,
+            "bug_tickets": []
+        }));
+        assert_eq!(
+            (
+                partial.tests_run,
+                partial.tests_passed,
+                partial.tests_failed
+            ),
+            (4, 4, 0)
+        );
+        assert!(partial.passed);
+        assert_eq!(
+            partial.data_quality,
+            [
+                "tests_run was missing; recorded 0",
+                "tests_failed was missing; recorded 0",
+                "tests_run was 0 but 4 tests passed and 0 failed; recorded 4 run",
+            ]
+        );
+
+        // A report that counts nothing shows nothing was tested.
+        let empty = qa(serde_json::json!({ "passed": true }));
+        assert!(!empty.passed);
+        assert_eq!(empty.tests_run, 0);
+        assert_eq!(empty.data_quality.len(), 4);
+    }
+
+    #[test]
+    fn medium_security_tickets_block_only_under_a_stricter_policy() {
+        let mut finding = ticket(
+            serde_json::json!({"url": "http://169.254.169.254/"}),
+            "blocked",
+            "fetched",
+        );
+        finding.ticket_type = BugTicketType::SecurityVulnerability;
+        finding.severity = Severity::Medium;
+        let strict = BlockingPolicy {
+            security_vulnerability: Severity::Medium,
+            ..BlockingPolicy::default()
+        };
+        assert!(!finding.is_blocking_under(&BlockingPolicy::default()));
+        assert!(finding.is_blocking_under(&strict));
+
+        let result = SecurityResult::from_report(serde_json::json!({
+            "passed": false,
+            "exploits_attempted": 4,
+            "exploits_succeeded": 1,
+            "bug_tickets": [finding]
+        }))
+        .unwrap();
+        assert!(!result.passed);
+        assert!(result.passes_under(&BlockingPolicy::default()));
+        assert!(!result.passes_under(&strict));
+    }
+
+    #[test]
+    fn tickets_without_a_severity_block_by_default() {
+        let mut report =
+            serde_json::to_value(ticket(serde_json::json!({}), "ok", "panic")).unwrap();
+        report.as_object_mut().unwrap().remove("severity");
+        let filed: BugTicket = serde_json::from_value(report).unwrap();
+        assert_eq!(filed.severity, Severity::High);
+        assert!(filed.is_blocking_under(&BlockingPolicy::default()));
+
+        // Failures with no ticket to account for them still fail.
+        let failures = qa(serde_json::json!({
+            "tests_run": 5,
+            "tests_passed": 3,
+            "tests_failed": 2,
+            "bug_tickets": []
+        }));
+        assert!(!failures.passes_under(&BlockingPolicy {
+            functional_defect: Severity::Critical,
+            ..BlockingPolicy::default()
+        }));
+    }
+
+    #[test]
+    fn security_reports_are_normalized_the_same_way() {
+        let result = SecurityResult::from_report(serde_json::json!({
+            "passed": true,
+            "exploits_attempted": 1,
+            "exploits_succeeded": 2,
+            "bug_tickets": []
+        }))
+        .unwrap();
+        assert_eq!(result.exploits_attempted, 2);
+        assert!(!result.passed);
+        assert_eq!(
+            result.data_quality,
+            [
+                "exploits_attempted was 1 but 2 succeeded; recorded 2 attempted",
+                "reported passed=true with 2 of 2 exploits succeeded and 0 bug tickets; recorded passed=false",
+            ]
+        );
+
+        let missing = SecurityResult::from_report(serde_json::json!({
+            "passed": true,
+            "exploits_attempted": 6,
+            "bug_tickets": []
+        }))
+        .unwrap();
+        assert!(missing.passed);
+        assert_eq!(missing.exploits_succeeded, 0);
+        assert_eq!(
+            missing.data_quality,
+            ["exploits_succeeded was missing; recorded 0"]
+        );
+    }
+
+    #[test]
+    fn results_without_data_quality_notes_still_load() {
+        let result: QaResult = serde_json::from_value(serde_json::json!({
+            "passed": true,
+            "tests_run": 1,
+            "tests_passed": 1,
+            "tests_failed": 0,
+            "bug_tickets": []
+        }))
+        .unwrap();
+        assert!(result.data_quality.is_empty());
+        let serialized = serde_json::to_value(&result).unwrap();
+        assert!(serialized.get("data_quality").is_none());
+    }
+}
@@ -0,0 +1,676 @@
+//! Re-running published tools' test vectors against them (`girt tools
+//! validate`), so a tool whose upstream API changed shape is caught before
+//! an agent starts getting confusing errors from it.
+//!
+//! A [`TestVector`] pairs an input with what the tool returned for it when it
+//! was built. Live data changes, so only the shape of the output is
+//! compared: the keys and JSON types the vector recorded must still be
+//! there, while values may differ and new keys are fine. A call that used to
+//! succeed and now fails, or the reverse, is drift too.
+//!
+//! The outcome is recorded on the tool's runtime metadata through a
+//! [`ToolRunner`], where the proxy's `list_tools` and `girt tools stats`
+//! show it. With a rebuild queue, a drifting tool is queued to be built
+//! again from its stored spec, with the failing vectors passed to the
+//! Architect as [`CapabilityRequest::context`].
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cache::ToolCache;
+use crate::error::PipelineError;
+use crate::queue::{EnqueueOutcome, Queue};
+use crate::types::{BuildArtifact, CapabilityRequest, RequestSource};
+
+/// An input and what the tool returned for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestVector {
+    pub input: Value,
+    pub expected: VectorOutcome,
+}
+
+/// What a tool call returned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum VectorOutcome {
+    Ok { output: Value },
+    Error { message: String },
+}
+
+impl TestVector {
+    /// How `actual` differs in shape from what the vector expects; empty if
+    /// it still matches.
+    pub fn drift(&self, actual: &VectorOutcome) -> Vec<String> {
+        match (&self.expected, actual) {
+            (VectorOutcome::Ok { output: expected }, VectorOutcome::Ok { output }) => {
+                let mut differences = Vec::new();
+                shape_drift("$", expected, output, &mut differences);
+                differences
+            }
+            (VectorOutcome::Ok { .. }, VectorOutcome::Error { message }) => {
+                vec![format!("the call now fails: {message}")]
+            }
+            (VectorOutcome::Error { message }, VectorOutcome::Ok { .. }) => {
+                vec![format!(
+                    "the call no longer fails (it failed with: {message})"
+                )]
+            }
+            (VectorOutcome::Error { .. }, VectorOutcome::Error { .. }) => Vec::new(),
+        }
+    }
+}
+
+/// Push a note to `differences` for each way `actual` lacks the shape of
+/// `expected`. `null` in `expected` matches anything; arrays are compared
+/// by their first elements, and an empty array matches any.
+fn shape_drift(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Null, _) => {}
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                let path = format!("{path}.{key}");
+                match actual.get(key) {
+                    Some(actual) => shape_drift(&path, expected, actual, differences),
+                    None => differences.push(format!("{path} is missing")),
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if let (Some(expected), Some(actual)) = (expected.first(), actual.first()) {
+                shape_drift(&format!("{path}[0]"), expected, actual, differences);
+            }
+        }
+        (expected, actual) if json_type(expected) != json_type(actual) => {
+            differences.push(format!(
+                "{path} was {}, now {}",
+                json_type(expected),
+                json_type(actual)
+            ));
+        }
+        _ => {}
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// A test vector whose outcome has drifted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VectorDrift {
+    pub input: Value,
+    pub expected: VectorOutcome,
+    pub actual: VectorOutcome,
+    pub differences: Vec<String>,
+}
+
+/// What became of one tool's validation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ValidationOutcome {
+    /// Every vector still matches.
+    Passed { vectors: usize },
+    /// Some vectors no longer match. `rebuild` is the id of the request
+    /// queued to rebuild the tool, if one was.
+    Drifted {
+        vectors: usize,
+        drift: Vec<VectorDrift>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rebuild: Option<String>,
+    },
+    /// The tool could not be validated.
+    Failed { error: String },
+    /// Not validated, e.g. because it has no test vectors.
+    Skipped { reason: String },
+}
+
+/// A tool's [`ValidationOutcome`], as listed by [`Revalidator::validate_all`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationReport {
+    pub name: String,
+    #[serde(flatten)]
+    pub outcome: ValidationOutcome,
+}
+
+/// Calls tools and keeps their validation state, e.g. in the runtime's
+/// component metadata.
+pub trait ToolRunner: Send + Sync {
+    /// Call `name` with `input`. `Err` is the error the call failed with.
+    fn call<'a>(
+        &'a self,
+        name: &'a str,
+        input: &'a Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, String>> + Send + 'a>>;
+
+    /// When `name` was last validated (Unix ms), if ever.
+    fn last_validated_at<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Option<u64>> + Send + 'a>>;
+
+    /// Record that `name` was validated at `at` (Unix ms), and whether
+    /// drift was found.
+    fn record<'a>(
+        &'a self,
+        name: &'a str,
+        at: u64,
+        drift_detected: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+    /// Called before [`Revalidator::validate_all`] validates anything, e.g.
+    /// to pick up tools published since the last run.
+    fn refresh<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async {})
+    }
+}
+
+/// Re-runs the test vectors of the tools in a [`ToolCache`].
+pub struct Revalidator {
+    cache: ToolCache,
+    runner: Arc<dyn ToolRunner>,
+    rebuild_queue: Option<Queue>,
+}
+
+impl Revalidator {
+    pub fn new(cache: ToolCache, runner: Arc<dyn ToolRunner>) -> Self {
+        Self {
+            cache,
+            runner,
+            rebuild_queue: None,
+        }
+    }
+
+    /// Queue a rebuild of each tool found drifting
+    /// (`[pipeline] revalidate_rebuild`). The rebuilt tool is published
+    /// like any other, so `[registry] on_name_collision` applies.
+    pub fn with_rebuild_queue(mut self, queue: Queue) -> Self {
+        self.rebuild_queue = Some(queue);
+        self
+    }
+
+    /// Validate every published tool, in name order. With `older_than`,
+    /// tools validated more recently are skipped.
+    pub async fn validate_all(
+        &self,
+        older_than: Option<Duration>,
+    ) -> Result<Vec<ValidationReport>, PipelineError> {
+        self.runner.refresh().await;
+        let mut reports = Vec::new();
+        for name in self.cache.list().await? {
+            let outcome = self.validate_if_due(&name, older_than).await;
+            reports.push(ValidationReport { name, outcome });
+        }
+        Ok(reports)
+    }
+
+    async fn validate_if_due(&self, name: &str, older_than: Option<Duration>) -> ValidationOutcome {
+        if let Some(age) = older_than
+            && let Some(validated_at) = self.runner.last_validated_at(name).await
+            && now_ms().saturating_sub(validated_at) < age.as_millis() as u64
+        {
+            return ValidationOutcome::Skipped {
+                reason: "validated recently".into(),
+            };
+        }
+        self.validate(name).await
+    }
+
+    /// Re-run the test vectors of the published tool `name` and record the
+    /// outcome.
+    pub async fn validate(&self, name: &str) -> ValidationOutcome {
+        let artifact = match self.cache.get(name).await {
+            Ok(Some(artifact)) => artifact,
+            Ok(None) => {
+                return ValidationOutcome::Failed {
+                    error: format!("no published tool named '{name}'"),
+                };
+            }
+            Err(e) => {
+                return ValidationOutcome::Failed {
+                    error: e.to_string(),
+                };
+            }
+        };
+        if artifact.test_vectors.is_empty() {
+            return ValidationOutcome::Skipped {
+                reason: "no test vectors recorded".into(),
+            };
+        }
+
+        let mut drift = Vec::new();
+        for vector in &artifact.test_vectors {
+            let actual = match self.runner.call(name, &vector.input).await {
+                Ok(output) => VectorOutcome::Ok { output },
+                Err(message) => VectorOutcome::Error { message },
+            };
+            let differences = vector.drift(&actual);
+            if !differences.is_empty() {
+                drift.push(VectorDrift {
+                    input: vector.input.clone(),
+                    expected: vector.expected.clone(),
+                    actual,
+                    differences,
+                });
+            }
+        }
+
+        if let Err(e) = self.runner.record(name, now_ms(), !drift.is_empty()).await {
+            tracing::warn!(tool = %name, error = %e, "Failed to record validation");
+        }
+        let vectors = artifact.test_vectors.len();
+        if drift.is_empty() {
+            tracing::info!(tool = %name, vectors, "Tool passed validation");
+            return ValidationOutcome::Passed { vectors };
+        }
+
+        tracing::warn!(tool = %name, drifted = drift.len(), vectors, "Tool output has drifted");
+        let rebuild = match &self.rebuild_queue {
+            Some(queue) => self.queue_rebuild(queue, &artifact, &drift).await,
+            None => None,
+        };
+        ValidationOutcome::Drifted {
+            vectors,
+            drift,
+            rebuild,
+        }
+    }
+
+    /// Queue `artifact`'s spec for a rebuild with `drift` as context, and
+    /// return the id of the request building it.
+    async fn queue_rebuild(
+        &self,
+        queue: &Queue,
+        artifact: &BuildArtifact,
+        drift: &[VectorDrift],
+    ) -> Option<String> {
+        let context = drift
+            .iter()
+            .map(|drift| {
+                format!(
+                    "The published tool no longer passes this test vector; the API it \
+                     calls may have changed: {}",
+                    serde_json::to_string(drift).unwrap_or_default()
+                )
+            })
+            .collect();
+        let request = CapabilityRequest::new(artifact.spec.clone(), RequestSource::Operator)
+            .with_context(context);
+        match queue.enqueue(&request).await {
+            Ok(EnqueueOutcome::Queued { .. }) => {
+                tracing::info!(tool = %artifact.spec.name, id = %request.id, "Rebuild queued");
+                Some(request.id)
+            }
+            Ok(EnqueueOutcome::AlreadyQueued { id }) => Some(id),
+            Err(e) => {
+                tracing::warn!(tool = %artifact.spec.name, error = %e, "Failed to queue rebuild");
+                None
+            }
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        BuildOutput, QaResult, RefinedSpec, RequestStatus, SecurityResult, SpecAction,
+    };
+    use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    /// A weather lookup whose upstream API returns `response` for every
+    /// city except "Atlantis".
+    struct FixtureTool {
+        response: Value,
+        calls: Mutex<usize>,
+        validated: Mutex<HashMap<String, (u64, bool)>>,
+    }
+
+    impl FixtureTool {
+        fn new(response: Value) -> Arc<Self> {
+            Arc::new(Self {
+                response,
+                calls: Mutex::new(0),
+                validated: Mutex::new(HashMap::new()),
+            })
+        }
+
+        fn drift_detected(&self, name: &str) -> Option<bool> {
+            self.validated.lock().unwrap().get(name).map(|v| v.1)
+        }
+    }
+
+    impl ToolRunner for FixtureTool {
+        fn call<'a>(
+            &'a self,
+            _name: &'a str,
+            input: &'a Value,
+        ) -> Pin<Box<dyn Future<Output = Result<Value, String>> + Send + 'a>> {
+            *self.calls.lock().unwrap() += 1;
+            let result = match input["city"].as_str() {
+                Some("Atlantis") => Err("404 Not Found".to_string()),
+                _ => Ok(self.response.clone()),
+            };
+            Box::pin(async move { result })
+        }
+
+        fn last_validated_at<'a>(
+            &'a self,
+            name: &'a str,
+        ) -> Pin<Box<dyn Future<Output = Option<u64>> + Send + 'a>> {
+            let at = self.validated.lock().unwrap().get(name).map(|v| v.0);
+            Box::pin(async move { at })
+        }
+
+        fn record<'a>(
+            &'a self,
+            name: &'a str,
+            at: u64,
+            drift_detected: bool,
+        ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>> {
+            self.validated
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), (at, drift_detected));
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn weather() -> Value {
+        json!({
+            "city": "Berlin",
+            "current": { "temperature": 21.5, "conditions": "cloudy" },
+            "forecast": [{ "day": "Mon", "high": 23 }],
+            "alerts": null
+        })
+    }
+
+    fn vectors() -> Vec<TestVector> {
+        vec![
+            TestVector {
+                input: json!({ "city": "Berlin" }),
+                expected: VectorOutcome::Ok { output: weather() },
+            },
+            TestVector {
+                input: json!({ "city": "Atlantis" }),
+                expected: VectorOutcome::Error {
+                    message: "404 Not Found".into(),
+                },
+            },
+        ]
+    }
+
+    fn make_artifact(name: &str, test_vectors: Vec<TestVector>) -> BuildArtifact {
+        let spec = CapabilitySpec {
+            name: name.into(),
+            description: format!("Test tool: {name}"),
+            inputs: Value::Null,
+            outputs: Value::Null,
+            constraints: CapabilityConstraints::default(),
+        };
+        BuildArtifact {
+            spec: spec.clone(),
+            refined_spec: RefinedSpec {
+                action: SpecAction::Build,
+                spec,
+                design_notes: "test".into(),
+                extend_target: None,
+                extend_features: None,
+                idempotent: true,
+            },
+            build_output: BuildOutput {
+                source_code: "fn main() {}".into(),
+                wit_definition: String::new(),
+                policy_yaml: String::new(),
+                language: "rust".into(),
+                files: vec![],
+                change_summary: None,
+            },
+            qa_result: QaResult {
+                passed: true,
+                tests_run: 2,
+                tests_passed: 2,
+                tests_failed: 0,
+                bug_tickets: vec![],
+                data_quality: vec![],
+            },
+            security_result: SecurityResult {
+                passed: true,
+                exploits_attempted: 0,
+                exploits_succeeded: 0,
+                bug_tickets: vec![],
+                data_quality: vec![],
+            },
+            build_iterations: 1,
+            ticket_history: vec![],
+            provenance: None,
+            requested_name: None,
+            revision: 0,
+            published_at: None,
+            iteration_outputs: vec![],
+            test_vectors,
+        }
+    }
+
+    async fn cache_with(artifacts: &[BuildArtifact]) -> (TempDir, ToolCache) {
+        let tmp = TempDir::new().unwrap();
+        let cache = ToolCache::new(tmp.path().join("tools"));
+        cache.init().await.unwrap();
+        for artifact in artifacts {
+            cache.store(artifact).await.unwrap();
+        }
+        (tmp, cache)
+    }
+
+    #[test]
+    fn values_may_change_and_keys_may_be_added() {
+        let vector = &vectors()[0];
+        let mut output = weather();
+        output["current"]["temperature"] = json!(-3);
+        output["current"]["conditions"] = json!("snow");
+        output["forecast"] = json!([{ "day": "Tue", "high": 1, "low": -8 }]);
+        output["alerts"] = json!(["Ice"]);
+        output["updated"] = json!("2026-10-16T12:00:00Z");
+
+        assert_eq!(
+            vector.drift(&VectorOutcome::Ok { output }),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn missing_keys_and_changed_types_are_drift() {
+        let vector = &vectors()[0];
+        let output = json!({
+            "city": "Berlin",
+            "current": { "temp_c": 21.5, "conditions": "cloudy" },
+            "forecast": [{ "day": "Mon", "high": "23" }],
+            "alerts": null,
+        });
+
+        assert_eq!(
+            vector.drift(&VectorOutcome::Ok { output }),
+            [
+                "$.current.temperature is missing",
+                "$.forecast[0].high was a number, now a string",
+            ]
+        );
+        assert_eq!(
+            vector.drift(&VectorOutcome::Ok {
+                output: json!(["Berlin"])
+            }),
+            ["$ was an object, now an array"]
+        );
+    }
+
+    #[test]
+    fn a_changed_outcome_is_drift() {
+        let [succeeded, failed] = vectors().try_into().unwrap();
+        let error = VectorOutcome::Error {
+            message: "401 Unauthorized".into(),
+        };
+        assert_eq!(
+            succeeded.drift(&error),
+            ["the call now fails: 401 Unauthorized"]
+        );
+        assert!(failed.drift(&error).is_empty());
+        assert_eq!(
+            failed.drift(&VectorOutcome::Ok { output: weather() }),
+            ["the call no longer fails (it failed with: 404 Not Found)"]
+        );
+    }
+
+    #[tokio::test]
+    async fn matching_vectors_pass_and_are_recorded() {
+        let (_tmp, cache) = cache_with(&[make_artifact("weather", vectors())]).await;
+        let tool = FixtureTool::new(weather());
+
+        let outcome = Revalidator::new(cache, tool.clone())
+            .validate("weather")
+            .await;
+        assert_eq!(outcome, ValidationOutcome::Passed { vectors: 2 });
+        assert_eq!(*tool.calls.lock().unwrap(), 2);
+        assert_eq!(tool.drift_detected("weather"), Some(false));
+    }
+
+    #[tokio::test]
+    async fn mutated_expectations_are_flagged_as_drift() {
+        let mut mutated = vectors();
+        mutated[0].expected = VectorOutcome::Ok {
+            output: json!({ "city": "Berlin", "current": { "humidity": 40 } }),
+        };
+        let (_tmp, cache) = cache_with(&[make_artifact("weather", mutated)]).await;
+        let tool = FixtureTool::new(weather());
+
+        let ValidationOutcome::Drifted {
+            vectors,
+            drift,
+            rebuild,
+        } = Revalidator::new(cache, tool.clone())
+            .validate("weather")
+            .await
+        else {
+            panic!("expected drift");
+        };
+        assert_eq!(vectors, 2);
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].input, json!({ "city": "Berlin" }));
+        assert_eq!(drift[0].differences, ["$.current.humidity is missing"]);
+        assert_eq!(rebuild, None);
+        assert_eq!(tool.drift_detected("weather"), Some(true));
+    }
+
+    #[tokio::test]
+    async fn drift_queues_a_rebuild_with_the_failing_vector() {
+        let (tmp, cache) = cache_with(&[make_artifact("weather", vectors())]).await;
+        let queue = Queue::new(tmp.path().join("queue"));
+        queue.init().await.unwrap();
+        // The upstream API renamed `current` to `now`.
+        let tool = FixtureTool::new(json!({
+            "city": "Berlin",
+            "now": { "temperature": 21.5, "conditions": "cloudy" },
+            "forecast": [],
+        }));
+        let revalidator =
+            Revalidator::new(cache, tool).with_rebuild_queue(Queue::new(tmp.path().join("queue")));
+
+        let ValidationOutcome::Drifted { rebuild, .. } = revalidator.validate("weather").await
+        else {
+            panic!("expected drift");
+        };
+        let requests = queue.requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(rebuild.as_deref(), Some(request.id.as_str()));
+        assert_eq!(request.status, RequestStatus::Pending);
+        assert_eq!(request.spec.name, "weather");
+        assert_eq!(request.context.len(), 1);
+        assert!(
+            request.context[0].contains("$.current is missing"),
+            "{}",
+            request.context[0]
+        );
+
+        // Still drifting on the next run: the queued rebuild is reused.
+        let ValidationOutcome::Drifted { rebuild: again, .. } =
+            revalidator.validate("weather").await
+        else {
+            panic!("expected drift");
+        };
+        assert_eq!(again, rebuild);
+        assert_eq!(queue.requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn validate_all_skips_tools_without_vectors_or_validated_recently() {
+        let (_tmp, cache) = cache_with(&[
+            make_artifact("weather", vectors()),
+            make_artifact("handmade", vec![]),
+        ])
+        .await;
+        let tool = FixtureTool::new(weather());
+        let revalidator = Revalidator::new(cache, tool.clone());
+
+        let reports = revalidator.validate_all(None).await.unwrap();
+        let outcomes: Vec<_> = reports
+            .iter()
+            .map(|r| (r.name.as_str(), &r.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                (
+                    "handmade",
+                    &ValidationOutcome::Skipped {
+                        reason: "no test vectors recorded".into()
+                    }
+                ),
+                ("weather", &ValidationOutcome::Passed { vectors: 2 }),
+            ]
+        );
+
+        let day = Duration::from_secs(24 * 60 * 60);
+        let reports = revalidator.validate_all(Some(day)).await.unwrap();
+        assert_eq!(
+            reports[1].outcome,
+            ValidationOutcome::Skipped {
+                reason: "validated recently".into()
+            }
+        );
+        assert_eq!(*tool.calls.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn reports_serialize_with_a_status() {
+        let report = ValidationReport {
+            name: "weather".into(),
+            outcome: ValidationOutcome::Passed { vectors: 2 },
+        };
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({ "name": "weather", "status": "passed", "vectors": 2 })
+        );
+    }
+}
@@ -0,0 +1,450 @@
+//! Long-running queue consumer behind `girt worker`.
+//!
+//! Drains the build queue independently of the MCP proxy, so a separate
+//! process can act as a build farm. What happens to a built tool beyond
+//! publishing to the tool cache is up to the consumer's
+//! [`PublishHook`](crate::queue::PublishHook)s; `girt worker` stores it in
+//! the runtime's component store under `~/.girt`, where a proxy restores it
+//! with `load_persisted`.
+//!
+//! With [`Worker::with_revalidation`], the worker also re-runs the test
+//! vectors of published tools on a schedule (see [`crate::validation`]).
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinSet;
+
+use crate::compiler::WasmCompiler;
+use crate::error::PipelineError;
+use crate::queue::{ProcessResult, QueueConsumer};
+use crate::types::CapabilityRequest;
+use crate::validation::{Revalidator, ValidationOutcome};
+
+/// Longest wait between looks for tools due to be re-validated, so tools
+/// published since the last look are not left for a whole interval.
+const REVALIDATION_CHECK: Duration = Duration::from_secs(60 * 60);
+
+/// How a [`Worker`] paces itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerOptions {
+    /// Requests built at the same time.
+    pub concurrency: usize,
+    /// Wait between queue checks while idle.
+    pub poll_interval: Duration,
+    /// In-progress requests claimed longer ago than this are assumed
+    /// abandoned by a dead worker and put back in the queue.
+    pub stale_after: Duration,
+}
+
+impl Default for WorkerOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            poll_interval: Duration::from_secs(5),
+            stale_after: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// What a [`Worker::run`] did before it shut down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerSummary {
+    pub built: usize,
+    pub extended: usize,
+    /// Requests the pipeline gave up on, or that errored while compiling
+    /// or publishing.
+    pub failed: usize,
+    /// Stale in-progress requests put back in the queue.
+    pub recovered: usize,
+    /// Tools whose test vectors were re-run.
+    pub validated: usize,
+    /// Validated tools whose output had drifted.
+    pub drifted: usize,
+}
+
+/// Claims requests from a queue and builds them until told to stop.
+pub struct Worker {
+    consumer: Arc<QueueConsumer>,
+    compiler: Arc<WasmCompiler>,
+    options: WorkerOptions,
+    revalidation: Option<(Revalidator, Duration)>,
+}
+
+impl Worker {
+    pub fn new(consumer: QueueConsumer, compiler: WasmCompiler) -> Self {
+        Self {
+            consumer: Arc::new(consumer),
+            compiler: Arc::new(compiler),
+            options: WorkerOptions::default(),
+            revalidation: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: WorkerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Re-validate each published tool once it was last validated more than
+    /// `interval` ago (`[pipeline] revalidate_interval`).
+    pub fn with_revalidation(mut self, revalidator: Revalidator, interval: Duration) -> Self {
+        self.revalidation = Some((revalidator, interval));
+        self
+    }
+
+    /// Process the queue until `shutdown` resolves, then wait for in-flight
+    /// builds to finish. Nothing new is claimed after shutdown.
+    pub async fn run(
+        &self,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<WorkerSummary, PipelineError> {
+        let queue = self.consumer.queue();
+        queue.init().await?;
+
+        let mut summary = WorkerSummary::default();
+        let concurrency = self.options.concurrency.max(1);
+        let mut in_flight: JoinSet<(CapabilityRequest, Result<ProcessResult, PipelineError>)> =
+            JoinSet::new();
+        tokio::pin!(shutdown);
+        let mut next_revalidation = Instant::now();
+
+        loop {
+            if let Some((revalidator, interval)) = &self.revalidation
+                && Instant::now() >= next_revalidation
+            {
+                revalidate(revalidator, *interval, &mut summary).await;
+                next_revalidation = Instant::now() + (*interval).min(REVALIDATION_CHECK);
+            }
+
+            // Only with nothing in flight can every in-progress entry be
+            // someone else's, so that is when stale ones are recovered.
+            if in_flight.is_empty() {
+                summary.recovered += queue.recover_stale(self.options.stale_after).await?.len();
+            }
+
+            while in_flight.len() < concurrency {
+                let Some(request) = queue.claim_next().await? else {
+                    break;
+                };
+                let consumer = Arc::clone(&self.consumer);
+                let compiler = Arc::clone(&self.compiler);
+                in_flight.spawn(async move {
+                    let result = consumer.process(&request, &compiler, None, None).await;
+                    (request, result)
+                });
+            }
+
+            tokio::select! {
+                _ = &mut shutdown => break,
+                Some(joined) = in_flight.join_next(), if !in_flight.is_empty() => {
+                    self.finish(joined, &mut summary).await;
+                }
+                _ = tokio::time::sleep(self.options.poll_interval), if in_flight.len() < concurrency => {}
+            }
+        }
+
+        if !in_flight.is_empty() {
+            tracing::info!(
+                in_flight = in_flight.len(),
+                "Shutting down after in-flight builds"
+            );
+        }
+        while let Some(joined) = in_flight.join_next().await {
+            self.finish(joined, &mut summary).await;
+        }
+        Ok(summary)
+    }
+
+    async fn finish(
+        &self,
+        joined: Result<
+            (CapabilityRequest, Result<ProcessResult, PipelineError>),
+            tokio::task::JoinError,
+        >,
+        summary: &mut WorkerSummary,
+    ) {
+        let (request, result) = match joined {
+            Ok(done) => done,
+            Err(e) => {
+                // The request stays in progress and is recovered once stale.
+                tracing::error!(error = %e, "Build task panicked");
+                summary.failed += 1;
+                return;
+            }
+        };
+        match result {
+            Ok(ProcessResult::Built { name, .. }) => {
+                tracing::info!(id = %request.id, tool = %name, "Tool built");
+                summary.built += 1;
+            }
+            Ok(ProcessResult::Extended { target, .. }) => {
+                tracing::info!(id = %request.id, %target, "Extension recommended");
+                summary.extended += 1;
+            }
+            Ok(ProcessResult::Failed(e)) => {
+                tracing::warn!(id = %request.id, error = %e, "Build failed");
+                summary.failed += 1;
+            }
+            Err(e) => {
+                // Compile or publish error: the pipeline left the request in
+                // progress, so fail it (and maybe retry) rather than leave it
+                // to be recovered and rebuilt forever.
+                tracing::warn!(id = %request.id, error = %e, "Build errored");
+                self.consumer.metrics().record_build_failed();
+                if let Err(e) = self.consumer.queue().fail(&request, &e).await {
+                    tracing::warn!(id = %request.id, error = %e, "Failed to move request out of in_progress/");
+                }
+                summary.failed += 1;
+            }
+        }
+    }
+}
+
+/// Validate the tools due for it, counting them in `summary`.
+async fn revalidate(revalidator: &Revalidator, interval: Duration, summary: &mut WorkerSummary) {
+    let reports = match revalidator.validate_all(Some(interval)).await {
+        Ok(reports) => reports,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to list tools to re-validate");
+            return;
+        }
+    };
+    for report in reports {
+        match report.outcome {
+            ValidationOutcome::Passed { .. } => summary.validated += 1,
+            ValidationOutcome::Drifted { .. } => {
+                summary.validated += 1;
+                summary.drifted += 1;
+            }
+            ValidationOutcome::Failed { error } => {
+                tracing::warn!(tool = %report.name, %error, "Re-validation failed");
+            }
+            ValidationOutcome::Skipped { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ToolCache;
+    use crate::compiler::BuildSandbox;
+    use crate::llm::{LlmClient, LlmRequest, LlmResponse, StubLlmClient};
+    use crate::metrics::PipelineMetrics;
+    use crate::publish::Publisher;
+    use crate::queue::Queue;
+    use crate::types::RequestSource;
+    use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    fn make_request(name: &str) -> CapabilityRequest {
+        CapabilityRequest::new(
+            CapabilitySpec {
+                name: name.into(),
+                description: format!("Test tool: {name}"),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints::default(),
+            },
+            RequestSource::Operator,
+        )
+    }
+
+    /// One response that parses as an Engineer build, a passing QA run, and
+    /// a passing Red Team audit, so concurrent requests can share a stub.
+    /// It is not a valid Architect answer, so each spec passes through
+    /// unrefined and keeps its name.
+    fn stub_llm() -> Arc<StubLlmClient> {
+        Arc::new(StubLlmClient::constant(
+            &serde_json::json!({
+                "source_code": crate::static_checks::clean_source("stub"),
+                "wit_definition": "",
+                "policy_yaml": "version: \"1.0\"",
+                "language": "rust",
+                "passed": true,
+                "tests_run": 1,
+                "tests_passed": 1,
+                "tests_failed": 0,
+                "exploits_attempted": 1,
+                "exploits_succeeded": 0,
+                "bug_tickets": []
+            })
+            .to_string(),
+        ))
+    }
+
+    #[cfg(unix)]
+    fn fake_cargo_component(dir: &std::path::Path) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("fake-cargo-component");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nout=\"${CARGO_TARGET_DIR:-target}/wasm32-wasip1/release\"\n\
+             mkdir -p \"$out\" && printf wasm > \"$out/$(basename \"$PWD\").wasm\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script.display().to_string()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn worker_drains_the_queue_and_recovers_stale_entries() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().join("queue"));
+        queue.init().await.unwrap();
+        for name in ["tool_a", "tool_b", "tool_c"] {
+            queue.enqueue(&make_request(name)).await.unwrap();
+        }
+        // Left in progress by a worker that died mid-build.
+        queue.enqueue(&make_request("tool_d")).await.unwrap();
+        let abandoned = queue.claim_next().await.unwrap().unwrap();
+
+        let cache_dir = tmp.path().join("tools");
+        let publisher = Publisher::new(ToolCache::new(cache_dir.clone()));
+        publisher.init().await.unwrap();
+        let consumer = QueueConsumer::new(
+            queue,
+            stub_llm(),
+            publisher,
+            Arc::new(PipelineMetrics::new()),
+        );
+        let compiler = WasmCompiler::new()
+            .with_cache_dir(tmp.path().join("build-cache"))
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(fake_cargo_component(tmp.path()));
+        let worker = Worker::new(consumer, compiler).with_options(WorkerOptions {
+            concurrency: 2,
+            poll_interval: Duration::from_millis(20),
+            stale_after: Duration::ZERO,
+        });
+
+        // Stop once every request has been built.
+        let completed = tmp.path().join("queue/completed");
+        let drained = async {
+            while std::fs::read_dir(&completed).unwrap().count() < 4 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        let summary = tokio::time::timeout(Duration::from_secs(30), worker.run(drained))
+            .await
+            .expect("worker drains within the timeout")
+            .unwrap();
+
+        assert_eq!(summary.built, 4);
+        assert_eq!(summary.failed, 0);
+        assert!(summary.recovered >= 1);
+        let queue = worker.consumer.queue();
+        assert!(queue.list_pending().await.unwrap().is_empty());
+        assert!(queue.list_in_progress().await.unwrap().is_empty());
+        for name in ["tool_a", "tool_b", "tool_c", "tool_d"] {
+            assert!(cache_dir.join(name).join("tool.wasm").exists(), "{name}");
+        }
+        let recovered: CapabilityRequest = serde_json::from_str(
+            &std::fs::read_to_string(completed.join(format!("{}.json", abandoned.id))).unwrap(),
+        )
+        .unwrap();
+        // Claimed by the dead worker, then again by this one.
+        assert_eq!(recovered.attempts, 2);
+    }
+
+    /// Answers like [`stub_llm`] after a delay, counting the calls in
+    /// flight at once.
+    struct SlowLlm {
+        inner: Arc<StubLlmClient>,
+        delay: Duration,
+        active: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl LlmClient for SlowLlm {
+        fn chat<'a>(
+            &'a self,
+            request: &'a LlmRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+            Box::pin(async move {
+                let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(active, Ordering::SeqCst);
+                tokio::time::sleep(self.delay).await;
+                self.active.fetch_sub(1, Ordering::SeqCst);
+                self.inner.chat(request).await
+            })
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn builds_overlap_up_to_the_concurrency_limit() {
+        let tmp = TempDir::new().unwrap();
+        let queue = Queue::new(tmp.path().join("queue"));
+        queue.init().await.unwrap();
+        for name in ["tool_a", "tool_b", "tool_c"] {
+            queue.enqueue(&make_request(name)).await.unwrap();
+        }
+
+        let llm = Arc::new(SlowLlm {
+            inner: stub_llm(),
+            delay: Duration::from_millis(100),
+            active: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+        let metrics = Arc::new(PipelineMetrics::new());
+        let publisher = Publisher::new(ToolCache::new(tmp.path().join("tools")));
+        publisher.init().await.unwrap();
+        let consumer = QueueConsumer::new(
+            queue,
+            Arc::clone(&llm) as Arc<dyn LlmClient>,
+            publisher,
+            Arc::clone(&metrics),
+        );
+        let compiler = WasmCompiler::new()
+            .without_cache()
+            .with_sandbox(BuildSandbox::Off)
+            .with_binary(fake_cargo_component(tmp.path()));
+        let worker = Worker::new(consumer, compiler).with_options(WorkerOptions {
+            concurrency: 2,
+            poll_interval: Duration::from_millis(20),
+            ..WorkerOptions::default()
+        });
+
+        let completed = tmp.path().join("queue/completed");
+        let drained = async {
+            while std::fs::read_dir(&completed).unwrap().count() < 3 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        let summary = tokio::time::timeout(Duration::from_secs(30), worker.run(drained))
+            .await
+            .expect("worker drains within the timeout")
+            .unwrap();
+
+        // Two pipelines waited on the LLM at once; the third never joined them.
+        assert_eq!(llm.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(summary.built, 3);
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.builds_started, 3);
+        assert_eq!(snapshot.builds_completed, 3);
+        assert_eq!(snapshot.builds_failed, 0);
+    }
+
+    #[tokio::test]
+    async fn shutdown_before_any_work_returns_immediately() {
+        let tmp = TempDir::new().unwrap();
+        let consumer = QueueConsumer::new(
+            Queue::new(tmp.path().join("queue")),
+            stub_llm(),
+            Publisher::new(ToolCache::new(tmp.path().join("tools"))),
+            Arc::new(PipelineMetrics::new()),
+        );
+        let summary = Worker::new(consumer, WasmCompiler::new())
+            .run(std::future::ready(()))
+            .await
+            .unwrap();
+        assert_eq!(summary, WorkerSummary::default());
+    }
+}
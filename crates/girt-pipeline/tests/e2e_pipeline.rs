@@ -54,8 +54,13 @@ fn check_prerequisites() {
 
 fn make_llm_client() -> Arc<dyn girt_pipeline::llm::LlmClient> {
     Arc::new(
-        AnthropicLlmClient::from_env_or("claude-sonnet-4-5".into(), None)
-            .expect("Failed to initialise Anthropic LLM client"),
+        AnthropicLlmClient::from_env_or(
+            "claude-sonnet-4-5".into(),
+            None,
+            None,
+            &Default::default(),
+        )
+        .expect("Failed to initialise Anthropic LLM client"),
     )
 }
 
@@ -0,0 +1,20 @@
+#[allow(warnings)]
+mod bindings;
+
+use bindings::Guest;
+
+struct Component;
+
+impl Guest for Component {
+    /// `{"name": "Ada"}` -> `{"greeting": "Hello, Ada!"}`
+    fn run(input: String) -> Result<String, String> {
+        let input: serde_json::Value =
+            serde_json::from_str(&input).map_err(|e| format!("Invalid input: {e}"))?;
+        let name = input["name"]
+            .as_str()
+            .ok_or("Missing required string field 'name'")?;
+        Ok(serde_json::json!({ "greeting": format!("Hello, {name}!") }).to_string())
+    }
+}
+
+bindings::export!(Component with_types_in bindings);
@@ -0,0 +1,918 @@
+//! Pending human approvals for capability requests and tool calls.
+//!
+//! When the Creation Gate answers `Ask`, the proxy parks the spec here under
+//! a random `approval_token` and returns the token to the agent. The request
+//! is resumed by an operator running `girt approve <token>` /
+//! `girt reject <token>`, or, with `[security] agent_approvals` on, by the
+//! `approve_capability` built-in tool. An Execution Gate `Ask` parks the
+//! call the same way, with the called tool's spec and a [`PendingCall`];
+//! approving it runs the call. Calls, requests an `always_ask` rule sent to
+//! a person, and every request while agent approvals are off, are
+//! [operator-only](PendingApproval::operator_only): the agent that made them
+//! cannot answer them through `approve_capability`.
+//!
+//! The proxy holds pending approvals in memory. With a spool directory
+//! configured (`~/.girt/approvals/` for `girt serve`), each one is also
+//! written out as `<token>.json` so the CLI, running as a separate process,
+//! can list them and record a resolution that the proxy then picks up.
+//! A proxy started with [`ApprovalStore::restore`] adopts the requests an
+//! earlier run left there, so a restart does not strand them. Spooled
+//! approvals resolve once across every proxy sharing the dir: the one that
+//! removes the spool file acts on it.
+//!
+//! Whoever answers sees [`PendingApproval::message`]: the gate's prompt with
+//! the spec's constraints, the requested resource tier, and, for a spec that
+//! would replace a loaded tool, how it differs and where that tool's source
+//! was synced. The agent is asked to relay it, and chat platforms cap
+//! message length, so it is cut to [`MAX_MESSAGE_CHARS`].
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use girt_core::paths::{self, PathError};
+use girt_core::spec::{CapabilitySpec, NetworkGrant, SpecChange, SpecDiff};
+use girt_pipeline::types::{PipelineOverrides, ResourceTier};
+use serde::{Deserialize, Serialize};
+
+use crate::extend::Extension;
+use crate::request_log::now_ms;
+
+/// How an operator answered a pending approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Approve,
+    Reject,
+}
+
+/// Longest [`PendingApproval::message`] the proxy hands out. Discord's
+/// limit; most chat platforms allow at least this much.
+pub const MAX_MESSAGE_CHARS: usize = 2_000;
+
+/// A tool call waiting on approval: the tool is the approval's spec.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingCall {
+    pub arguments: serde_json::Value,
+}
+
+/// What a human is asked to approve.
+#[derive(Debug, Clone)]
+pub struct ApprovalRequest {
+    pub spec: CapabilitySpec,
+    /// Pipeline overrides the request asked for, applied if it is approved.
+    pub pipeline: PipelineOverrides,
+    /// The gate's prompt.
+    pub prompt: String,
+    /// How the spec differs from the loaded tool it would replace.
+    pub spec_diff: Option<SpecDiff>,
+    /// Where the replaced tool's source was synced.
+    pub source_url: Option<String>,
+    /// Set for an `extend_capability` request: what to add to the tool.
+    pub extension: Option<Extension>,
+    /// Set for a tool call the Execution Gate asked about.
+    pub call: Option<PendingCall>,
+    /// Only an operator may resolve it, with `girt approve` / `girt reject`.
+    pub operator_only: bool,
+}
+
+impl ApprovalRequest {
+    pub fn new(spec: CapabilitySpec, prompt: impl Into<String>) -> Self {
+        Self {
+            spec,
+            pipeline: PipelineOverrides::default(),
+            prompt: prompt.into(),
+            spec_diff: None,
+            source_url: None,
+            extension: None,
+            call: None,
+            operator_only: false,
+        }
+    }
+
+    pub fn with_pipeline(mut self, pipeline: PipelineOverrides) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub fn with_spec_diff(mut self, diff: Option<SpecDiff>) -> Self {
+        self.spec_diff = diff;
+        self
+    }
+
+    pub fn with_source_url(mut self, url: Option<String>) -> Self {
+        self.source_url = url;
+        self
+    }
+
+    pub fn with_extension(mut self, extension: Option<Extension>) -> Self {
+        self.extension = extension;
+        self
+    }
+
+    pub fn with_call(mut self, call: Option<PendingCall>) -> Self {
+        self.call = call;
+        self
+    }
+
+    pub fn with_operator_only(mut self, operator_only: bool) -> Self {
+        self.operator_only = operator_only;
+        self
+    }
+}
+
+/// A capability request or tool call waiting for a human decision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub token: String,
+    pub spec: CapabilitySpec,
+    /// Pipeline overrides the request asked for, applied if it is approved.
+    #[serde(default, skip_serializing_if = "PipelineOverrides::is_empty")]
+    pub pipeline: PipelineOverrides,
+    /// The gate's prompt, shown to whoever resolves the request.
+    pub prompt: String,
+    /// How the spec differs from the loaded tool it would replace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_diff: Option<SpecDiff>,
+    /// Where the replaced tool's source was synced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// Set for an `extend_capability` request: what to add to the tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<Extension>,
+    /// Set for a tool call the Execution Gate asked about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call: Option<PendingCall>,
+    /// Only an operator may resolve it, through the spool: the agent's
+    /// `approve_capability` is refused, so it cannot answer for itself a
+    /// call or a request that has to go to a person.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub operator_only: bool,
+    pub created_at_ms: u64,
+    pub expires_at_ms: u64,
+    /// Set by `girt approve` / `girt reject` in the spool file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+}
+
+impl PendingApproval {
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expires_at_ms
+    }
+
+    /// The request as text for whoever answers it, at most `max_chars`
+    /// characters. A message that does not fit is cut at a line break and
+    /// points to the replaced tool's source, or to the full record.
+    pub fn message(&self, max_chars: usize) -> String {
+        let question = match (&self.call, &self.extension) {
+            (Some(call), _) => format!(
+                "Approve calling `{}` with {}?",
+                self.spec.name, call.arguments
+            ),
+            (None, Some(extension)) => format!(
+                "Approve extending `{}` with {}?",
+                self.spec.name,
+                extension.features.join("; ")
+            ),
+            (None, None) => format!("Approve building `{}`?", self.spec.name),
+        };
+        let mut body = vec![
+            format!("{question} {}", self.prompt),
+            String::new(),
+            self.spec.description.clone(),
+            String::new(),
+        ];
+        let constraints = &self.spec.constraints;
+        let network: Vec<String> = constraints.network.iter().map(|g| g.to_string()).collect();
+        for (label, entries) in [
+            ("Network hosts", &network),
+            ("Storage paths", &constraints.storage),
+            ("Secrets", &constraints.secrets),
+        ] {
+            if entries.is_empty() {
+                body.push(format!("{label}: none"));
+            } else {
+                body.push(format!("{label}:"));
+                body.extend(entries.iter().map(|entry| format!("- {entry}")));
+            }
+        }
+        // A call runs the tool as it was built; nothing is built for it.
+        if self.call.is_none() {
+            body.push(match self.pipeline.resource_tier {
+                Some(tier) => tier_line(tier),
+                None => "Resource tier: chosen by the Architect".into(),
+            });
+        }
+
+        if let Some(diff) = &self.spec_diff {
+            body.push(String::new());
+            if diff.is_empty() {
+                body.push("Replaces the loaded tool; fields and constraints are unchanged.".into());
+            } else {
+                body.push("Replaces the loaded tool:".into());
+                body.extend(diff.inputs.iter().map(|c| change_line("input ", c)));
+                body.extend(diff.outputs.iter().map(|c| change_line("output ", c)));
+                body.extend(diff.constraints.iter().map(|c| change_line("", c)));
+            }
+        }
+        if let Some(url) = &self.source_url {
+            body.push(format!("Current source: {url}"));
+        }
+
+        let footer = format!(
+            "Answer with `girt approve {0}` or `girt reject {0}`.",
+            self.token
+        );
+        let pointer = match &self.source_url {
+            Some(url) => format!("… (truncated; see {url})"),
+            None => "… (truncated; see `girt approvals pending --json`)".into(),
+        };
+        fit_message(&body.join("\n"), &footer, &pointer, max_chars)
+    }
+}
+
+fn tier_line(tier: ResourceTier) -> String {
+    let name = match tier {
+        ResourceTier::Minimal => "minimal",
+        ResourceTier::Standard => "standard",
+        ResourceTier::Extended => "extended",
+    };
+    let resources = tier.to_resources();
+    format!(
+        "Resource tier: {name} ({} MB, {}s timeout)",
+        resources.memory_mb, resources.timeout_seconds
+    )
+}
+
+/// One [`SpecChange`] as a bullet; `kind` prefixes field keys. Network
+/// grants read as they do in the constraint list.
+fn change_line(kind: &str, change: &SpecChange) -> String {
+    let (SpecChange::Added { key, .. }
+    | SpecChange::Removed { key, .. }
+    | SpecChange::Changed { key, .. }) = change;
+    let network = kind.is_empty() && key == "network";
+    let plain = |value: &serde_json::Value| match value.as_str() {
+        Some(s) => s.to_string(),
+        None if network => serde_json::from_value::<NetworkGrant>(value.clone())
+            .map_or_else(|_| value.to_string(), |grant| grant.to_string()),
+        None => value.to_string(),
+    };
+    match change {
+        SpecChange::Added { key, value } => format!("- {kind}{key}: added {}", plain(value)),
+        SpecChange::Removed { key, value } => format!("- {kind}{key}: removed {}", plain(value)),
+        SpecChange::Changed { key, before, after } => {
+            format!("- {kind}{key}: {} -> {}", plain(before), plain(after))
+        }
+    }
+}
+
+/// `body` and `footer`, dropping the end of `body` for `pointer` when both
+/// do not fit in `max_chars`.
+fn fit_message(body: &str, footer: &str, pointer: &str, max_chars: usize) -> String {
+    let full = format!("{body}\n\n{footer}");
+    if full.chars().count() <= max_chars {
+        return full;
+    }
+    let reserved = pointer.chars().count() + footer.chars().count() + 3;
+    let Some(budget) = max_chars.checked_sub(reserved) else {
+        return full.chars().take(max_chars).collect();
+    };
+    let kept: String = body.chars().take(budget).collect();
+    let kept = match kept.rfind('\n') {
+        Some(end) => kept[..end].trim_end(),
+        None => kept.as_str(),
+    };
+    format!("{kept}\n{pointer}\n\n{footer}")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApprovalError {
+    #[error("unknown approval token '{0}'")]
+    UnknownToken(String),
+
+    #[error("approval token '{0}' has expired")]
+    Expired(String),
+
+    #[error("approval token '{0}' was already resolved")]
+    AlreadyResolved(String),
+
+    #[error("approval spool I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("approval spool serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Default approval window when none is configured.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// In-memory map of pending approvals, optionally mirrored to a spool dir.
+pub struct ApprovalStore {
+    pending: Mutex<HashMap<String, PendingApproval>>,
+    /// Tokens whose spool file could not be written, so no other proxy can
+    /// hold them.
+    unspooled: Mutex<HashSet<String>>,
+    ttl: Duration,
+    spool_dir: Option<PathBuf>,
+}
+
+impl ApprovalStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            unspooled: Mutex::new(HashSet::new()),
+            ttl,
+            spool_dir: None,
+        }
+    }
+
+    /// Default spool location: `~/.girt/approvals/`.
+    pub fn default_spool_dir() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("approvals"))
+    }
+
+    /// Mirror pending approvals to `dir` so the CLI can resolve them.
+    pub fn with_spool_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spool_dir = Some(dir.into());
+        self
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Whether pending approvals are mirrored to a spool dir, so an
+    /// operator can resolve them with `girt approve` / `girt reject`.
+    pub fn is_spooled(&self) -> bool {
+        self.spool_dir.is_some()
+    }
+
+    /// Park `request` and return the new pending approval with its token.
+    pub fn create(&self, request: ApprovalRequest) -> PendingApproval {
+        let created_at_ms = now_ms();
+        let approval = PendingApproval {
+            token: uuid::Uuid::new_v4().simple().to_string(),
+            spec: request.spec,
+            pipeline: request.pipeline,
+            prompt: request.prompt,
+            spec_diff: request.spec_diff,
+            source_url: request.source_url,
+            extension: request.extension,
+            call: request.call,
+            operator_only: request.operator_only,
+            created_at_ms,
+            expires_at_ms: created_at_ms.saturating_add(self.ttl.as_millis() as u64),
+            resolution: None,
+        };
+        if let Some(dir) = &self.spool_dir
+            && let Err(e) = write_spool_file(dir, &approval)
+        {
+            // The agent-facing tool still works; only the CLI path is lost.
+            tracing::warn!(dir = %dir.display(), error = %e, "Failed to spool pending approval");
+            lock(&self.unspooled).insert(approval.token.clone());
+        }
+        self.lock().insert(approval.token.clone(), approval.clone());
+        approval
+    }
+
+    /// Remove `token` from the pending set so it can be acted on.
+    ///
+    /// Each token resolves at most once: a second `take`, here or in another
+    /// proxy sharing the spool dir, reports `UnknownToken`. Expired tokens
+    /// are dropped and reported as `Expired`.
+    pub fn take(&self, token: &str) -> Result<PendingApproval, ApprovalError> {
+        let approval = self
+            .lock()
+            .remove(token)
+            .ok_or_else(|| ApprovalError::UnknownToken(token.to_string()))?;
+        let unspooled = lock(&self.unspooled).remove(token);
+        if !self.remove_spool_file(token) && !unspooled {
+            return Err(ApprovalError::UnknownToken(token.to_string()));
+        }
+        if approval.is_expired(now_ms()) {
+            return Err(ApprovalError::Expired(token.to_string()));
+        }
+        Ok(approval)
+    }
+
+    /// The pending approval `token`, expired or not. Unlike
+    /// [`pending`](Self::pending) this prunes nothing, so a later
+    /// [`take`](Self::take) still tells an expired token from an unknown one.
+    pub fn get(&self, token: &str) -> Option<PendingApproval> {
+        self.lock().get(token).cloned()
+    }
+
+    /// Pending approvals that have not expired, oldest first.
+    pub fn pending(&self) -> Vec<PendingApproval> {
+        self.prune_expired();
+        let mut pending: Vec<_> = self.lock().values().cloned().collect();
+        pending.sort_by_key(|a| a.created_at_ms);
+        pending
+    }
+
+    /// Drop expired approvals, returning how many were removed.
+    pub fn prune_expired(&self) -> usize {
+        let now = now_ms();
+        let expired: Vec<String> = {
+            let mut pending = self.lock();
+            let expired: Vec<String> = pending
+                .values()
+                .filter(|a| a.is_expired(now))
+                .map(|a| a.token.clone())
+                .collect();
+            for token in &expired {
+                pending.remove(token);
+            }
+            expired
+        };
+        for token in &expired {
+            tracing::info!(token = %token, "Pending approval expired");
+            self.remove_spool_file(token);
+        }
+        expired.len()
+    }
+
+    /// Adopt the approvals spooled by earlier runs, including any resolved
+    /// while no proxy was watching, so the approval watcher can act on
+    /// them. Expired ones are removed. Returns how many were adopted.
+    pub fn restore(&self) -> Result<usize, ApprovalError> {
+        let Some(dir) = &self.spool_dir else {
+            return Ok(0);
+        };
+        let now = now_ms();
+        let mut adopted = 0;
+        for approval in read_spool_dir(dir)? {
+            if approval.is_expired(now) {
+                tracing::info!(token = %approval.token, "Pending approval expired while no proxy was running");
+                self.remove_spool_file(&approval.token);
+                continue;
+            }
+            if let std::collections::hash_map::Entry::Vacant(entry) =
+                self.lock().entry(approval.token.clone())
+            {
+                entry.insert(approval);
+                adopted += 1;
+            }
+        }
+        Ok(adopted)
+    }
+
+    /// Resolutions recorded in the spool dir for tokens this store holds.
+    pub fn spooled_resolutions(&self) -> Vec<(String, Resolution)> {
+        let Some(dir) = &self.spool_dir else {
+            return Vec::new();
+        };
+        let tokens: Vec<String> = self.lock().keys().cloned().collect();
+        tokens
+            .into_iter()
+            .filter_map(|token| {
+                let approval = read_spool_file(dir, &token).ok()?;
+                Some((token, approval.resolution?))
+            })
+            .collect()
+    }
+
+    /// Remove `token`'s spool file. False when another proxy already did.
+    fn remove_spool_file(&self, token: &str) -> bool {
+        let Some(dir) = &self.spool_dir else {
+            return true;
+        };
+        !matches!(
+            std::fs::remove_file(spool_path(dir, token)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, PendingApproval>> {
+        lock(&self.pending)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// ── Spool (CLI side) ──────────────────────────────────────────────────────────
+
+/// List unexpired, unresolved approvals in a spool dir, oldest first.
+pub fn list_spooled(dir: &Path) -> Result<Vec<PendingApproval>, ApprovalError> {
+    let now = now_ms();
+    let mut pending: Vec<_> = read_spool_dir(dir)?
+        .into_iter()
+        .filter(|a| a.resolution.is_none() && !a.is_expired(now))
+        .collect();
+    pending.sort_by_key(|a| a.created_at_ms);
+    Ok(pending)
+}
+
+/// Record `resolution` for `token` in a spool dir. The serving proxy applies
+/// it on its next poll.
+pub fn resolve_spooled(
+    dir: &Path,
+    token: &str,
+    resolution: Resolution,
+) -> Result<PendingApproval, ApprovalError> {
+    // Tokens are hex; anything else cannot name a spool file.
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ApprovalError::UnknownToken(token.to_string()));
+    }
+    let mut approval = match read_spool_file(dir, token) {
+        Ok(approval) => approval,
+        Err(ApprovalError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ApprovalError::UnknownToken(token.to_string()));
+        }
+        Err(e) => return Err(e),
+    };
+    if approval.resolution.is_some() {
+        return Err(ApprovalError::AlreadyResolved(token.to_string()));
+    }
+    if approval.is_expired(now_ms()) {
+        return Err(ApprovalError::Expired(token.to_string()));
+    }
+    approval.resolution = Some(resolution);
+    write_spool_file(dir, &approval)?;
+    Ok(approval)
+}
+
+/// Every approval in a spool dir, resolved and expired ones included.
+fn read_spool_dir(dir: &Path) -> Result<Vec<PendingApproval>, ApprovalError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut approvals = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            // Taken by a proxy since the dir was listed.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        approvals.push(serde_json::from_slice(&bytes)?);
+    }
+    Ok(approvals)
+}
+
+fn spool_path(dir: &Path, token: &str) -> PathBuf {
+    dir.join(format!("{token}.json"))
+}
+
+fn read_spool_file(dir: &Path, token: &str) -> Result<PendingApproval, ApprovalError> {
+    let bytes = std::fs::read(spool_path(dir, token))?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Write via a temp file so a concurrent reader never sees a partial file.
+fn write_spool_file(dir: &Path, approval: &PendingApproval) -> Result<(), ApprovalError> {
+    std::fs::create_dir_all(dir)?;
+    let path = spool_path(dir, &approval.token);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(approval)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_core::spec::CapabilityConstraints;
+    use tempfile::TempDir;
+
+    fn spec() -> CapabilitySpec {
+        CapabilitySpec::builder()
+            .name("word_count")
+            .description("Count the words in a string")
+            .build()
+            .unwrap()
+    }
+
+    /// A request to replace a loaded tool, with every section filled in.
+    fn replacement() -> PendingApproval {
+        PendingApproval {
+            token: "abc123".into(),
+            spec: CapabilitySpec {
+                name: "weather_lookup".into(),
+                description: "Current weather for a city".into(),
+                inputs: serde_json::Value::Null,
+                outputs: serde_json::Value::Null,
+                constraints: CapabilityConstraints {
+                    network: vec!["api.open-meteo.com".into()],
+                    storage: vec![],
+                    secrets: vec!["WEATHER_KEY".into()],
+                },
+            },
+            pipeline: PipelineOverrides {
+                resource_tier: Some(ResourceTier::Minimal),
+                ..Default::default()
+            },
+            prompt: "The tool reads a secret.".into(),
+            spec_diff: Some(SpecDiff {
+                inputs: vec![SpecChange::Added {
+                    key: "units".into(),
+                    value: "string".into(),
+                }],
+                outputs: vec![],
+                constraints: vec![SpecChange::Added {
+                    key: "secrets".into(),
+                    value: "WEATHER_KEY".into(),
+                }],
+            }),
+            source_url: Some("https://github.com/org/tools/tree/main/tools/weather_lookup".into()),
+            extension: None,
+            call: None,
+            operator_only: false,
+            created_at_ms: 0,
+            expires_at_ms: 0,
+            resolution: None,
+        }
+    }
+
+    #[test]
+    fn message_lists_constraints_tier_and_changes() {
+        assert_eq!(
+            replacement().message(MAX_MESSAGE_CHARS),
+            "\
+Approve building `weather_lookup`? The tool reads a secret.
+
+Current weather for a city
+
+Network hosts:
+- api.open-meteo.com
+Storage paths: none
+Secrets:
+- WEATHER_KEY
+Resource tier: minimal (64 MB, 5s timeout)
+
+Replaces the loaded tool:
+- input units: added string
+- secrets: added WEATHER_KEY
+Current source: https://github.com/org/tools/tree/main/tools/weather_lookup
+
+Answer with `girt approve abc123` or `girt reject abc123`."
+        );
+
+        let new_tool = store_message(ApprovalRequest::new(spec(), "Build it?"));
+        assert!(new_tool.contains("Network hosts: none\n"), "{new_tool}");
+        assert!(
+            new_tool.contains("Resource tier: chosen by the Architect"),
+            "{new_tool}"
+        );
+        assert!(!new_tool.contains("Replaces"), "{new_tool}");
+
+        let extension = store_message(ApprovalRequest::new(spec(), "Build it?").with_extension(
+            Some(Extension {
+                features: vec!["skip numbers".into(), "count lines".into()],
+            }),
+        ));
+        assert!(
+            extension.starts_with(
+                "Approve extending `word_count` with skip numbers; count lines? Build it?"
+            ),
+            "{extension}"
+        );
+
+        let call = store_message(ApprovalRequest::new(spec(), "Run it?").with_call(Some(
+            PendingCall {
+                arguments: serde_json::json!({ "text": "one two" }),
+            },
+        )));
+        assert!(
+            call.starts_with(r#"Approve calling `word_count` with {"text":"one two"}? Run it?"#),
+            "{call}"
+        );
+        assert!(!call.contains("Resource tier"), "{call}");
+    }
+
+    #[test]
+    fn message_shows_the_scope_of_network_grants() {
+        let grant =
+            NetworkGrant::scoped("api.github.com", vec!["GET".into()], vec!["/repos/".into()]);
+        let mut approval = replacement();
+        approval.spec.constraints.network = vec![grant.clone()];
+        approval.spec_diff = Some(SpecDiff {
+            constraints: vec![SpecChange::Added {
+                key: "network".into(),
+                value: serde_json::to_value(&grant).unwrap(),
+            }],
+            ..Default::default()
+        });
+        let message = approval.message(MAX_MESSAGE_CHARS);
+        assert!(
+            message.contains("Network hosts:\n- api.github.com (GET) under /repos/\n"),
+            "{message}"
+        );
+        assert!(
+            message.contains("- network: added api.github.com (GET) under /repos/\n"),
+            "{message}"
+        );
+    }
+
+    fn store_message(request: ApprovalRequest) -> String {
+        ApprovalStore::new(DEFAULT_TTL)
+            .create(request)
+            .message(MAX_MESSAGE_CHARS)
+    }
+
+    #[test]
+    fn long_messages_are_cut_at_a_line_break() {
+        let approval = replacement();
+        assert_eq!(
+            approval.message(270),
+            "\
+Approve building `weather_lookup`? The tool reads a secret.
+
+Current weather for a city
+
+Network hosts:
+- api.open-meteo.com
+… (truncated; see https://github.com/org/tools/tree/main/tools/weather_lookup)
+
+Answer with `girt approve abc123` or `girt reject abc123`."
+        );
+
+        let mut crowded = approval;
+        crowded.source_url = None;
+        crowded.spec.constraints.network = (0..500)
+            .map(|i| format!("host{i}.example.com").into())
+            .collect();
+        let message = crowded.message(MAX_MESSAGE_CHARS);
+        assert!(message.chars().count() <= MAX_MESSAGE_CHARS);
+        assert!(
+            message.ends_with(
+                "\n… (truncated; see `girt approvals pending --json`)\n\n\
+                 Answer with `girt approve abc123` or `girt reject abc123`."
+            ),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn approved_token_yields_the_original_spec_once() {
+        let store = ApprovalStore::new(DEFAULT_TTL);
+        let approval = store.create(ApprovalRequest::new(spec(), "Build word_count?"));
+
+        assert_eq!(store.pending().len(), 1);
+        assert_eq!(store.take(&approval.token).unwrap().spec, spec());
+        assert!(store.pending().is_empty());
+        assert!(matches!(
+            store.take(&approval.token),
+            Err(ApprovalError::UnknownToken(_))
+        ));
+    }
+
+    #[test]
+    fn pipeline_overrides_survive_the_spool() {
+        let tmp = TempDir::new().unwrap();
+        let store = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        let pipeline = PipelineOverrides {
+            max_iterations: Some(2),
+            ..Default::default()
+        };
+        let approval =
+            store.create(ApprovalRequest::new(spec(), "prompt").with_pipeline(pipeline.clone()));
+
+        assert_eq!(list_spooled(tmp.path()).unwrap()[0].pipeline, pipeline);
+        assert_eq!(store.take(&approval.token).unwrap().pipeline, pipeline);
+    }
+
+    #[test]
+    fn unknown_token_is_rejected() {
+        let store = ApprovalStore::new(DEFAULT_TTL);
+        store.create(ApprovalRequest::new(spec(), "prompt"));
+
+        assert!(matches!(
+            store.take("not-a-token"),
+            Err(ApprovalError::UnknownToken(_))
+        ));
+        assert_eq!(store.pending().len(), 1);
+    }
+
+    #[test]
+    fn expired_tokens_are_pruned_and_cannot_be_taken() {
+        let tmp = TempDir::new().unwrap();
+        let store = ApprovalStore::new(Duration::ZERO).with_spool_dir(tmp.path());
+        let first = store.create(ApprovalRequest::new(spec(), "prompt"));
+        let second = store.create(ApprovalRequest::new(spec(), "prompt"));
+        // Looking a token up does not prune it.
+        assert!(store.get(&second.token).is_some());
+
+        assert!(matches!(
+            store.take(&first.token),
+            Err(ApprovalError::Expired(_))
+        ));
+        assert!(store.pending().is_empty());
+        assert!(!spool_path(tmp.path(), &second.token).exists());
+        assert!(matches!(
+            resolve_spooled(tmp.path(), &second.token, Resolution::Approve),
+            Err(ApprovalError::UnknownToken(_))
+        ));
+    }
+
+    #[test]
+    fn cli_resolutions_round_trip_through_the_spool() {
+        let tmp = TempDir::new().unwrap();
+        let store = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        let approve = store.create(ApprovalRequest::new(spec(), "first"));
+        let reject = store.create(ApprovalRequest::new(spec(), "second"));
+
+        let listed = list_spooled(tmp.path()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(store.spooled_resolutions().is_empty());
+
+        resolve_spooled(tmp.path(), &approve.token, Resolution::Approve).unwrap();
+        resolve_spooled(tmp.path(), &reject.token, Resolution::Reject).unwrap();
+        assert!(matches!(
+            resolve_spooled(tmp.path(), &reject.token, Resolution::Approve),
+            Err(ApprovalError::AlreadyResolved(_))
+        ));
+        assert!(list_spooled(tmp.path()).unwrap().is_empty());
+
+        let mut resolutions = store.spooled_resolutions();
+        resolutions.sort_by_key(|(token, _)| *token == reject.token);
+        assert_eq!(
+            resolutions,
+            vec![
+                (approve.token.clone(), Resolution::Approve),
+                (reject.token.clone(), Resolution::Reject),
+            ]
+        );
+
+        store.take(&approve.token).unwrap();
+        assert!(!spool_path(tmp.path(), &approve.token).exists());
+    }
+
+    #[test]
+    fn restarted_store_applies_resolutions_recorded_while_down() {
+        let tmp = TempDir::new().unwrap();
+        let before = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        let resolved = before.create(ApprovalRequest::new(spec(), "first"));
+        let waiting = before.create(ApprovalRequest::new(spec(), "second"));
+        drop(before);
+
+        // The operator answers between the proxy's runs.
+        resolve_spooled(tmp.path(), &resolved.token, Resolution::Approve).unwrap();
+
+        let after = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        assert_eq!(after.restore().unwrap(), 2);
+        assert_eq!(
+            after.spooled_resolutions(),
+            vec![(resolved.token.clone(), Resolution::Approve)]
+        );
+        assert_eq!(after.take(&resolved.token).unwrap().spec, spec());
+
+        // The next poll picks up a resolution recorded after the restart.
+        resolve_spooled(tmp.path(), &waiting.token, Resolution::Reject).unwrap();
+        assert_eq!(
+            after.spooled_resolutions(),
+            vec![(waiting.token.clone(), Resolution::Reject)]
+        );
+        assert_eq!(after.take(&waiting.token).unwrap().prompt, "second");
+        assert!(list_spooled(tmp.path()).unwrap().is_empty());
+        assert_eq!(after.restore().unwrap(), 0);
+    }
+
+    #[test]
+    fn restore_drops_approvals_that_expired_while_down() {
+        let tmp = TempDir::new().unwrap();
+        let approval = ApprovalStore::new(Duration::ZERO)
+            .with_spool_dir(tmp.path())
+            .create(ApprovalRequest::new(spec(), "prompt"));
+
+        let store = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        assert_eq!(store.restore().unwrap(), 0);
+        assert!(store.pending().is_empty());
+        assert!(!spool_path(tmp.path(), &approval.token).exists());
+    }
+
+    #[test]
+    fn spooled_approvals_resolve_in_one_proxy_only() {
+        let tmp = TempDir::new().unwrap();
+        let first = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        let approval = first.create(ApprovalRequest::new(spec(), "prompt"));
+        let second = ApprovalStore::new(DEFAULT_TTL).with_spool_dir(tmp.path());
+        second.restore().unwrap();
+
+        second.take(&approval.token).unwrap();
+        assert!(matches!(
+            first.take(&approval.token),
+            Err(ApprovalError::UnknownToken(_))
+        ));
+    }
+
+    #[test]
+    fn resolving_unknown_or_malformed_tokens_fails() {
+        let tmp = TempDir::new().unwrap();
+        for token in ["deadbeef", "../etc/passwd", ""] {
+            assert!(matches!(
+                resolve_spooled(tmp.path(), token, Resolution::Reject),
+                Err(ApprovalError::UnknownToken(_))
+            ));
+        }
+    }
+}
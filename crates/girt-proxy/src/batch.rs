@@ -0,0 +1,331 @@
+//! `girt_batch`: several tool calls in one MCP request.
+//!
+//! An agent running a multi-step workflow otherwise pays an MCP round trip
+//! per call. A batch saves the round trips only: every entry still goes
+//! through the Execution Gate on its own, exactly as a lone `tools/call`
+//! would. In `parallel` mode the entries run side by side, a bounded number
+//! at a time. An entry the gate denies or wants a person to decide fails by
+//! itself without holding up the rest.
+
+use rmcp::ErrorData as McpError;
+use rmcp::model::{CallToolResult, Tool};
+use serde::{Deserialize, Serialize};
+
+/// Name of the built-in.
+pub const BATCH_TOOL: &str = "girt_batch";
+
+/// How the entries of a batch are run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    /// One after another, in order.
+    #[default]
+    Sequential,
+    /// At the same time, up to `[server] batch_parallelism` at once.
+    Parallel,
+}
+
+/// One entry of a batch.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchCall {
+    pub tool: String,
+    #[serde(default)]
+    pub args: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `girt_batch` arguments.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchRequest {
+    pub calls: Vec<BatchCall>,
+    #[serde(default)]
+    pub mode: BatchMode,
+}
+
+impl BatchRequest {
+    /// Parse `girt_batch` arguments. Empty batches and ones with more than
+    /// `max_calls` entries are `invalid_params`.
+    pub fn parse(
+        arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+        max_calls: usize,
+    ) -> Result<Self, McpError> {
+        let arguments = serde_json::Value::Object(arguments.cloned().unwrap_or_default());
+        let request: Self = serde_json::from_value(arguments)
+            .map_err(|e| McpError::invalid_params(format!("girt_batch: {e}"), None))?;
+        if request.calls.is_empty() {
+            return Err(McpError::invalid_params(
+                "girt_batch needs at least one call",
+                None,
+            ));
+        }
+        if request.calls.len() > max_calls {
+            return Err(McpError::invalid_params(
+                format!(
+                    "girt_batch has {} calls; at most {max_calls} are allowed in one batch",
+                    request.calls.len()
+                ),
+                Some(serde_json::json!({
+                    "calls": request.calls.len(),
+                    "max_calls": max_calls,
+                })),
+            ));
+        }
+        Ok(request)
+    }
+}
+
+/// How one entry of a batch ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallStatus {
+    /// The gate allowed the call and the tool succeeded.
+    Allow,
+    /// The gate denied the call.
+    Deny,
+    /// The call was not run to a result: the tool failed, the gate wanted a
+    /// person to decide, or the entry itself was invalid.
+    Error,
+}
+
+/// One entry's result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CallOutcome {
+    pub tool: String,
+    pub status: CallStatus,
+    pub duration_ms: u64,
+    /// The tool's output, or the gate's decision for a call it did not
+    /// allow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CallOutcome {
+    /// Classify what handling one call as a lone `tools/call` returned,
+    /// given the Execution Gate decision it reached (its
+    /// [`Decision::status`](girt_core::decision::Decision::status)).
+    pub fn new(
+        tool: String,
+        decision: Option<&str>,
+        result: Result<CallToolResult, McpError>,
+        duration_ms: u64,
+    ) -> Self {
+        let (status, result, error) = match result {
+            Err(e) => (CallStatus::Error, None, Some(e.message.into_owned())),
+            Ok(result) => {
+                let failed = result.is_error == Some(true);
+                let payload = Some(payload(&result));
+                match decision {
+                    Some("allowed") if !failed => (CallStatus::Allow, payload, None),
+                    Some("allowed") => (
+                        CallStatus::Error,
+                        payload,
+                        Some(format!("{tool} returned an error")),
+                    ),
+                    Some("denied") => (CallStatus::Deny, payload, None),
+                    Some("ask") => (
+                        CallStatus::Error,
+                        payload,
+                        Some(format!(
+                            "the Execution Gate wants a person to decide; call {tool} on its own"
+                        )),
+                    ),
+                    other => (
+                        CallStatus::Error,
+                        payload,
+                        Some(format!(
+                            "the Execution Gate did not allow the call ({}); call {tool} on its own",
+                            other.unwrap_or("no decision")
+                        )),
+                    ),
+                }
+            }
+        };
+        Self {
+            tool,
+            status,
+            duration_ms,
+            result,
+            error,
+        }
+    }
+}
+
+/// A tool result's JSON payload: its text content parsed as JSON, or the
+/// text itself when it is not JSON.
+fn payload(result: &CallToolResult) -> serde_json::Value {
+    let text = result
+        .content
+        .first()
+        .and_then(|c| c.as_text())
+        .map(|t| t.text.as_str())
+        .unwrap_or_default();
+    serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::from(text))
+}
+
+/// The `girt_batch` response: one result per call, in the order given,
+/// with counts by status.
+pub fn render(mode: BatchMode, outcomes: &[CallOutcome]) -> serde_json::Value {
+    let count = |status| outcomes.iter().filter(|o| o.status == status).count();
+    serde_json::json!({
+        "mode": mode,
+        "allowed": count(CallStatus::Allow),
+        "denied": count(CallStatus::Deny),
+        "failed": count(CallStatus::Error),
+        "results": outcomes,
+    })
+}
+
+/// Build the JSON schema for the girt_batch tool.
+pub fn batch_tool(max_calls: usize) -> Tool {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "calls": {
+                "type": "array",
+                "minItems": 1,
+                "maxItems": max_calls,
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "tool": { "type": "string", "description": "Name of a loaded tool" },
+                        "args": { "type": "object", "description": "The tool's arguments" }
+                    },
+                    "required": ["tool"],
+                    "additionalProperties": false
+                }
+            },
+            "mode": {
+                "type": "string",
+                "enum": ["sequential", "parallel"],
+                "description": "Run the calls in order (default) or at the same time"
+            }
+        },
+        "required": ["calls"],
+        "additionalProperties": false
+    });
+
+    Tool {
+        name: BATCH_TOOL.into(),
+        title: None,
+        description: Some(
+            "Call several loaded tools in one request. Each call is checked by the \
+             Execution Gate on its own; one that is denied or needs a person's approval \
+             fails without stopping the others. Returns a result per call with its \
+             status (allow, deny or error) and duration. Built-in tools cannot be batched."
+                .into(),
+        ),
+        input_schema: schema.as_object().cloned().unwrap_or_default().into(),
+        output_schema: None,
+        annotations: None,
+        execution: None,
+        icons: None,
+        meta: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+
+    fn args(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    fn tool_result(value: serde_json::Value, is_error: bool) -> CallToolResult {
+        CallToolResult {
+            content: vec![Content::text(value.to_string())],
+            structured_content: None,
+            is_error: Some(is_error),
+            meta: None,
+        }
+    }
+
+    #[test]
+    fn batches_parse_with_sequential_as_the_default_mode() {
+        let request = BatchRequest::parse(
+            Some(&args(serde_json::json!({
+                "calls": [
+                    { "tool": "a", "args": { "x": 1 } },
+                    { "tool": "b" }
+                ]
+            }))),
+            4,
+        )
+        .unwrap();
+        assert_eq!(request.mode, BatchMode::Sequential);
+        assert_eq!(request.calls[0].args["x"], 1);
+        assert!(request.calls[1].args.is_empty());
+
+        let parallel = args(serde_json::json!({ "calls": [{ "tool": "a" }], "mode": "parallel" }));
+        let request = BatchRequest::parse(Some(&parallel), 4).unwrap();
+        assert_eq!(request.mode, BatchMode::Parallel);
+    }
+
+    #[test]
+    fn empty_oversized_and_malformed_batches_are_refused() {
+        assert!(BatchRequest::parse(None, 4).is_err());
+        let empty = args(serde_json::json!({ "calls": [] }));
+        assert!(BatchRequest::parse(Some(&empty), 4).is_err());
+
+        let calls: Vec<_> = (0..5)
+            .map(|i| serde_json::json!({ "tool": format!("t{i}") }))
+            .collect();
+        let err =
+            BatchRequest::parse(Some(&args(serde_json::json!({ "calls": calls }))), 4).unwrap_err();
+        assert!(err.message.contains("at most 4"), "{}", err.message);
+        assert_eq!(err.data.unwrap()["calls"], 5);
+
+        let typo = args(serde_json::json!({ "calls": [{ "tool": "a", "arguments": {} }] }));
+        assert!(BatchRequest::parse(Some(&typo), 4).is_err());
+    }
+
+    #[test]
+    fn outcomes_follow_the_gate_decision_and_tool_result() {
+        let ok = || Ok(tool_result(serde_json::json!({ "n": 1 }), false));
+        let allowed = CallOutcome::new("a".into(), Some("allowed"), ok(), 3);
+        assert_eq!(allowed.status, CallStatus::Allow);
+        assert_eq!(allowed.result, Some(serde_json::json!({ "n": 1 })));
+
+        let failed = CallOutcome::new(
+            "a".into(),
+            Some("allowed"),
+            Ok(tool_result("boom".into(), true)),
+            3,
+        );
+        assert_eq!(failed.status, CallStatus::Error);
+        assert_eq!(failed.result, Some("boom".into()));
+
+        let denied = CallOutcome::new(
+            "a".into(),
+            Some("denied"),
+            Ok(tool_result(serde_json::json!({ "status": "denied" }), true)),
+            1,
+        );
+        assert_eq!(denied.status, CallStatus::Deny);
+
+        let asked = CallOutcome::new("a".into(), Some("ask"), ok(), 1);
+        assert_eq!(asked.status, CallStatus::Error);
+        assert!(asked.error.unwrap().contains("wants a person to decide"));
+
+        let missing = CallOutcome::new(
+            "nope".into(),
+            None,
+            Err(McpError::invalid_params("Tool 'nope' not found", None)),
+            0,
+        );
+        assert_eq!(missing.status, CallStatus::Error);
+        assert_eq!(missing.error.as_deref(), Some("Tool 'nope' not found"));
+
+        let rendered = render(BatchMode::Parallel, &[allowed, denied, missing]);
+        assert_eq!(rendered["mode"], "parallel");
+        assert_eq!(rendered["allowed"], 1);
+        assert_eq!(rendered["denied"], 1);
+        assert_eq!(rendered["failed"], 1);
+        assert_eq!(rendered["results"][1]["status"], "deny");
+        assert!(rendered["results"][2].get("result").is_none());
+    }
+}
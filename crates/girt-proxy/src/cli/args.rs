@@ -0,0 +1,216 @@
+//! Parsing and validating `girt` command-line arguments.
+//!
+//! Value parsers here run while clap parses, so a bad flag is reported
+//! with the usage line before any subcommand starts.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+/// Why a `girt tools call` could not build its arguments object.
+#[derive(Debug, thiserror::Error)]
+pub enum CallArgsError {
+    #[error("{origin} is not valid JSON: {source}")]
+    InvalidJson {
+        origin: String,
+        source: serde_json::Error,
+    },
+
+    #[error("{origin} must be a JSON object, not {found}")]
+    NotAnObject { origin: String, found: &'static str },
+
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// The arguments object of a tool call: `--args` or `--args-file`, then
+/// each `--arg` pair, later keys replacing earlier ones.
+pub fn call_arguments(
+    args: Option<&str>,
+    args_file: Option<&Path>,
+    pairs: Vec<(String, Value)>,
+) -> Result<Value, CallArgsError> {
+    let base = match (args, args_file) {
+        (Some(text), _) => Some(("--args".to_string(), text.to_string())),
+        (None, Some(path)) => {
+            let text = std::fs::read_to_string(path).map_err(|source| CallArgsError::Read {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            Some((path.display().to_string(), text))
+        }
+        (None, None) => None,
+    };
+    let mut object = match base {
+        None => Map::new(),
+        Some((origin, text)) => match serde_json::from_str(&text) {
+            Ok(Value::Object(object)) => object,
+            Ok(other) => {
+                return Err(CallArgsError::NotAnObject {
+                    origin,
+                    found: json_type(&other),
+                });
+            }
+            Err(source) => return Err(CallArgsError::InvalidJson { origin, source }),
+        },
+    };
+    object.extend(pairs);
+    Ok(Value::Object(object))
+}
+
+/// Parse a `--arg key=value` pair. The value is coerced by
+/// [`coerce_value`].
+pub fn parse_arg_pair(text: &str) -> Result<(String, Value), String> {
+    match text.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), coerce_value(value))),
+        _ => Err(format!("expected key=value, got '{text}'")),
+    }
+}
+
+/// The JSON a `--arg` value stands for: `42`, `true`, `null`, `[1, 2]` and
+/// other valid JSON are taken as written, anything else is a string. Quote
+/// a value (`'"42"'`) to pass it as a string anyway.
+pub fn coerce_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Value parser for a path that must name an existing file.
+pub fn existing_file(text: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(text);
+    if path.is_file() {
+        Ok(path)
+    } else if path.exists() {
+        Err(format!("'{text}' is not a file"))
+    } else {
+        Err(format!("'{text}' does not exist"))
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn arg_values_that_are_json_keep_their_type() {
+        assert_eq!(coerce_value("42"), json!(42));
+        assert_eq!(coerce_value("-1.5"), json!(-1.5));
+        assert_eq!(coerce_value("true"), json!(true));
+        assert_eq!(coerce_value("null"), Value::Null);
+        assert_eq!(coerce_value("[1, \"a\"]"), json!([1, "a"]));
+        assert_eq!(coerce_value("{\"n\": 1}"), json!({"n": 1}));
+        assert_eq!(coerce_value("\"42\""), json!("42"));
+    }
+
+    #[test]
+    fn other_arg_values_are_strings() {
+        assert_eq!(coerce_value("Berlin"), json!("Berlin"));
+        assert_eq!(coerce_value(""), json!(""));
+        assert_eq!(coerce_value("True"), json!("True"));
+        assert_eq!(coerce_value("[unclosed"), json!("[unclosed"));
+        assert_eq!(
+            coerce_value("https://example.com/?a=b"),
+            json!("https://example.com/?a=b")
+        );
+    }
+
+    #[test]
+    fn arg_pairs_split_on_the_first_equals_sign() {
+        assert_eq!(
+            parse_arg_pair("query=a=b").unwrap(),
+            ("query".to_string(), json!("a=b"))
+        );
+        assert_eq!(
+            parse_arg_pair("limit=10").unwrap(),
+            ("limit".to_string(), json!(10))
+        );
+        assert!(parse_arg_pair("limit").is_err());
+        assert!(parse_arg_pair("=10").is_err());
+    }
+
+    #[test]
+    fn pairs_override_the_args_object() {
+        let pairs = vec![
+            ("city".to_string(), json!("Paris")),
+            ("days".to_string(), json!(3)),
+        ];
+        let args = call_arguments(
+            Some(r#"{"city": "Berlin", "units": "metric"}"#),
+            None,
+            pairs,
+        );
+        assert_eq!(
+            args.unwrap(),
+            json!({"city": "Paris", "units": "metric", "days": 3})
+        );
+        assert_eq!(call_arguments(None, None, Vec::new()).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn args_file_is_read_as_the_base_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("args.json");
+        std::fs::write(&path, r#"{"city": "Berlin"}"#).unwrap();
+
+        let pairs = vec![("days".to_string(), json!(2))];
+        let args = call_arguments(None, Some(&path), pairs).unwrap();
+        assert_eq!(args, json!({"city": "Berlin", "days": 2}));
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        let err = call_arguments(None, Some(&path), Vec::new()).unwrap_err();
+        assert!(matches!(
+            err,
+            CallArgsError::NotAnObject {
+                found: "an array",
+                ..
+            }
+        ));
+        assert!(
+            err.to_string()
+                .ends_with("must be a JSON object, not an array")
+        );
+    }
+
+    #[test]
+    fn invalid_args_name_their_origin() {
+        let err = call_arguments(Some("{city"), None, Vec::new()).unwrap_err();
+        assert!(
+            err.to_string().starts_with("--args is not valid JSON"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn existing_file_rejects_missing_paths_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("spec.json");
+        std::fs::write(&file, "{}").unwrap();
+
+        assert_eq!(existing_file(file.to_str().unwrap()), Ok(file.clone()));
+        let missing = dir.path().join("missing.json");
+        assert!(
+            existing_file(missing.to_str().unwrap())
+                .unwrap_err()
+                .ends_with("does not exist")
+        );
+        assert!(
+            existing_file(dir.path().to_str().unwrap())
+                .unwrap_err()
+                .ends_with("is not a file")
+        );
+    }
+}
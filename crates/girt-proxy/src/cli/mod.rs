@@ -0,0 +1,4 @@
+//! Support code for the `girt` command-line subcommands.
+
+pub mod args;
+pub mod output;
@@ -0,0 +1,236 @@
+//! Machine-readable CLI output (`girt --json`).
+//!
+//! By default subcommands print for a person, mostly to stderr. With the
+//! global `--json` flag each prints one JSON document to stdout instead,
+//! with the same exit code. The structs here are that document for the
+//! subcommands whose output is not already a stored record:
+//!
+//! | Subcommand              | JSON                                      |
+//! |-------------------------|-------------------------------------------|
+//! | `auth status`/`logout`  | [`AuthStatus`]                            |
+//! | `doctor`                | array of [`DoctorCheck`]                  |
+//! | `health`                | `HealthReport`                            |
+//! | `enqueue`               | [`Enqueued`]                              |
+//! | `tools add`             | [`Added`]                                 |
+//! | `tools deprecate`       | [`Deprecated`]                            |
+//! | `tools release`         | [`Released`]                              |
+//! | `tools list`            | array of `ComponentMeta`                  |
+//! | `approve` / `reject`    | [`Resolved`]                              |
+//! | `tools history`         | array of `InvocationRecord`, newest first |
+//! | `tools stats`           | array of `ToolStats`                      |
+//! | `approvals pending`     | array of `PendingApproval`                |
+//! | `queue list`            | array of `CapabilityRequest`              |
+//! | `stats`                 | `StateDocument`                           |
+//! | `failures list`         | array of `FailureSummary`, newest first   |
+//! | `failures show`         | `FailureBundle`                           |
+//! | `failures diff`         | [`IterationDiff`]                         |
+//! | `config validate`       | array of `EffectiveValue`                 |
+//! | `spec from-openapi`     | `CapabilitySpec`                          |
+//! | `tools call` / `replay` | the tool's result                         |
+//!
+//! Fields may be added but are not renamed or removed.
+
+use girt_pipeline::tool_sync::SyncOutcome;
+use girt_secrets::TokenStatus;
+use serde::Serialize;
+
+use crate::approvals::Resolution;
+
+/// How a subcommand reports its result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    /// The format selected by the `--json` flag.
+    pub fn from_json_flag(json: bool) -> Self {
+        if json { Self::Json } else { Self::Text }
+    }
+
+    /// Print `value` to stdout as JSON, or hand it to `text` to describe.
+    pub fn emit<T: Serialize>(self, value: &T, text: impl FnOnce(&T)) -> serde_json::Result<()> {
+        match self {
+            Self::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            Self::Text => text(value),
+        }
+        Ok(())
+    }
+}
+
+/// Stored OAuth credentials for one provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthStatus {
+    pub provider: String,
+    pub logged_in: bool,
+    /// Expired or expiring within 5 minutes; refreshed on next use.
+    pub expired: bool,
+    /// `null` when logged out or the token never expires.
+    pub expires_at_unix: Option<u64>,
+    pub has_refresh_token: bool,
+    /// First characters of the access token; `null` when logged out.
+    pub token_prefix: Option<String>,
+}
+
+impl AuthStatus {
+    pub fn new(provider: &str, token: Option<&TokenStatus>) -> Self {
+        Self {
+            provider: provider.to_string(),
+            logged_in: token.is_some(),
+            expired: token.is_some_and(|t| t.is_expired),
+            expires_at_unix: token
+                .map(|t| t.expires_at_unix)
+                .filter(|&expires| expires != 0),
+            has_refresh_token: token.is_some_and(|t| t.has_refresh_token),
+            token_prefix: token.map(|t| t.access_token_prefix.clone()),
+        }
+    }
+}
+
+/// One `girt doctor` check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub passed: bool,
+    /// What was found, or why the check failed.
+    pub detail: String,
+}
+
+impl DoctorCheck {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: true,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn fail(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// A request added to the build queue by `girt enqueue`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Enqueued {
+    /// The new request, or the identical one already queued.
+    pub id: String,
+    pub name: String,
+    /// An identical spec was pending or in progress, so nothing was added.
+    pub already_queued: bool,
+    /// Queued requests for the same name with a different spec.
+    pub conflicts: Vec<String>,
+}
+
+/// A handwritten tool compiled and loaded by `girt tools add`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Added {
+    pub name: String,
+    pub wasm_sha256: String,
+    /// The Creation Gate's decision, or `skipped` with `--skip-gate`.
+    pub gate: String,
+    /// `null` when no registry repo is configured or the sync failed.
+    pub tool_sync: Option<SyncOutcome>,
+}
+
+/// A bundled component stored by `girt tools add-builtin`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuiltinAdded {
+    pub name: String,
+    pub component_id: String,
+    pub wasm_sha256: String,
+}
+
+/// A tool marked deprecated by `girt tools deprecate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Deprecated {
+    pub name: String,
+    pub replaced_by: Option<String>,
+}
+
+/// A tool released from quarantine by `girt tools release`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Released {
+    pub name: String,
+}
+
+/// An approval resolved by `girt approve` or `girt reject`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Resolved {
+    pub token: String,
+    pub name: String,
+    pub resolution: Resolution,
+}
+
+/// Two iterations of a build compared by `girt failures diff`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IterationDiff {
+    pub id: String,
+    pub from: u32,
+    pub to: u32,
+    /// Unified diff of `src/lib.rs`; empty when they are the same.
+    pub diff: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(expires_at_unix: u64) -> TokenStatus {
+        TokenStatus {
+            access_token_prefix: "sk-ant-oat01-abc".into(),
+            expires_at_unix,
+            is_expired: false,
+            has_refresh_token: true,
+        }
+    }
+
+    #[test]
+    fn auth_status_json_shape() {
+        let status = AuthStatus::new("anthropic", Some(&token(1_900_000_000)));
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            serde_json::json!({
+                "provider": "anthropic",
+                "logged_in": true,
+                "expired": false,
+                "expires_at_unix": 1_900_000_000u64,
+                "has_refresh_token": true,
+                "token_prefix": "sk-ant-oat01-abc",
+            })
+        );
+    }
+
+    #[test]
+    fn logged_out_and_non_expiring_tokens_use_null() {
+        let logged_out = serde_json::to_value(AuthStatus::new("github", None)).unwrap();
+        assert_eq!(logged_out["logged_in"], false);
+        assert!(logged_out["expires_at_unix"].is_null());
+        assert!(logged_out["token_prefix"].is_null());
+
+        let never = serde_json::to_value(AuthStatus::new("github", Some(&token(0)))).unwrap();
+        assert_eq!(never["logged_in"], true);
+        assert!(never["expires_at_unix"].is_null());
+    }
+
+    #[test]
+    fn doctor_check_json_shape() {
+        let checks = vec![
+            DoctorCheck::pass("config", "./girt.toml"),
+            DoctorCheck::fail("cargo_component", "not found on PATH"),
+        ];
+        assert_eq!(
+            serde_json::to_value(&checks).unwrap(),
+            serde_json::json!([
+                { "name": "config", "passed": true, "detail": "./girt.toml" },
+                { "name": "cargo_component", "passed": false, "detail": "not found on PATH" },
+            ])
+        );
+    }
+}
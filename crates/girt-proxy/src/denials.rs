@@ -0,0 +1,120 @@
+//! Recent Creation Gate denials, keyed by spec name.
+//!
+//! Agents often resubmit a denied capability with the same problem, such as
+//! wildcard network access. The proxy remembers why each name was denied so
+//! that the gate LLM sees the earlier reason when judging a resubmission,
+//! and the Architect sees it when a changed request goes on to be built.
+//! The index lives in memory only.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::request_log::now_ms;
+
+/// How long a denial is remembered when no window is configured.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Distinct reasons kept per name; older ones are dropped.
+const MAX_PER_NAME: usize = 3;
+
+/// Why a capability name was denied, and when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denial {
+    pub reason: String,
+    pub denied_at_ms: u64,
+}
+
+/// Denials within a time window, per requested spec name.
+pub struct RecentDenials {
+    window: Duration,
+    by_name: Mutex<HashMap<String, Vec<Denial>>>,
+}
+
+impl Default for RecentDenials {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl RecentDenials {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            by_name: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember that a request named `name` was denied for `reason`.
+    pub fn record(&self, name: &str, reason: &str) {
+        self.record_at(name, reason, now_ms());
+    }
+
+    fn record_at(&self, name: &str, reason: &str, at_ms: u64) {
+        let mut by_name = self.by_name.lock().unwrap_or_else(|e| e.into_inner());
+        let denials = by_name.entry(name.to_string()).or_default();
+        // The same reason again only refreshes its timestamp.
+        denials.retain(|d| d.reason != reason);
+        denials.push(Denial {
+            reason: reason.to_string(),
+            denied_at_ms: at_ms,
+        });
+        let excess = denials.len().saturating_sub(MAX_PER_NAME);
+        denials.drain(..excess);
+    }
+
+    /// Denials of `name` still within the window, oldest first.
+    pub fn for_name(&self, name: &str) -> Vec<Denial> {
+        let cutoff = now_ms().saturating_sub(self.window.as_millis() as u64);
+        let mut by_name = self.by_name.lock().unwrap_or_else(|e| e.into_inner());
+        by_name.retain(|_, denials| {
+            denials.retain(|d| d.denied_at_ms >= cutoff);
+            !denials.is_empty()
+        });
+        by_name.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Just the reasons from [`Self::for_name`].
+    pub fn reasons(&self, name: &str) -> Vec<String> {
+        self.for_name(name).into_iter().map(|d| d.reason).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denials_are_kept_per_name() {
+        let denials = RecentDenials::default();
+        denials.record("http_fetch", "Policy deny: Wildcard network access");
+
+        assert_eq!(
+            denials.reasons("http_fetch"),
+            vec!["Policy deny: Wildcard network access"]
+        );
+        assert!(denials.for_name("word_count").is_empty());
+    }
+
+    #[test]
+    fn repeated_reasons_are_merged_and_old_ones_dropped() {
+        let denials = RecentDenials::default();
+        let now = now_ms();
+        for (i, reason) in ["a", "b", "a", "c", "d"].iter().enumerate() {
+            denials.record_at("tool", reason, now - 10 + i as u64);
+        }
+
+        assert_eq!(denials.reasons("tool"), vec!["a", "c", "d"]);
+    }
+
+    #[test]
+    fn denials_outside_the_window_expire() {
+        let denials = RecentDenials::new(Duration::from_secs(60));
+        let now = now_ms();
+        denials.record_at("old", "stale", now - 61_000);
+        denials.record_at("new", "fresh", now - 1_000);
+
+        assert!(denials.for_name("old").is_empty());
+        assert_eq!(denials.reasons("new"), vec!["fresh"]);
+    }
+}
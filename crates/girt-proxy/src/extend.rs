@@ -0,0 +1,230 @@
+//! `extend_capability`: add features to a tool GIRT already built.
+//!
+//! Without it, an agent told to extend a tool (a `recommend_extend` build
+//! outcome, or a Defer to `extend_tool`) can only re-request the whole
+//! capability. Here it names the tool and what to add. The proxy applies
+//! the additions to the tool's published spec and puts the result through
+//! the Creation Gate like any fresh request, so new constraints count
+//! against the same policy rules and budget. An allowed extension is built
+//! from the tool's published source and replaces it as a new revision.
+
+use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+use rmcp::ErrorData as McpError;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+
+/// Name of the built-in.
+pub const EXTEND_TOOL: &str = "extend_capability";
+
+/// What an extension adds to its tool's code, kept with a pending approval
+/// so an approved extension is built as one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extension {
+    pub features: Vec<String>,
+}
+
+/// `extend_capability` arguments.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtendRequest {
+    pub target_tool: String,
+    pub features: Vec<String>,
+    /// Input fields to add, or to redefine, keyed by name.
+    #[serde(default)]
+    pub new_inputs: serde_json::Map<String, serde_json::Value>,
+    /// Constraint entries to add to the tool's.
+    #[serde(default)]
+    pub new_constraints: CapabilityConstraints,
+}
+
+impl ExtendRequest {
+    /// Parse `extend_capability` arguments. A request without features, or
+    /// with a blank one, is `invalid_params`.
+    pub fn parse(
+        arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<Self, McpError> {
+        let arguments = serde_json::Value::Object(arguments.cloned().unwrap_or_default());
+        let request: Self = serde_json::from_value(arguments)
+            .map_err(|e| McpError::invalid_params(format!("{EXTEND_TOOL}: {e}"), None))?;
+        if request.features.is_empty() || request.features.iter().any(|f| f.trim().is_empty()) {
+            return Err(McpError::invalid_params(
+                format!("{EXTEND_TOOL} needs at least one feature, and no blank ones"),
+                None,
+            ));
+        }
+        Ok(request)
+    }
+
+    /// `spec` with this request's additions: new inputs merged into its
+    /// inputs, new constraint entries appended to its own, and the features
+    /// noted in its description.
+    pub fn apply(&self, spec: &CapabilitySpec) -> CapabilitySpec {
+        let mut extended = spec.clone();
+        extended.description = format!(
+            "{}\n\nExtended with: {}",
+            spec.description,
+            self.features.join("; ")
+        );
+        if !self.new_inputs.is_empty() {
+            let mut inputs = spec.inputs.as_object().cloned().unwrap_or_default();
+            inputs.extend(self.new_inputs.clone());
+            extended.inputs = serde_json::Value::Object(inputs);
+        }
+        let (constraints, added) = (&mut extended.constraints, &self.new_constraints);
+        for grant in &added.network {
+            if !constraints.network.contains(grant) {
+                constraints.network.push(grant.clone());
+            }
+        }
+        for (entries, additions) in [
+            (&mut constraints.storage, &added.storage),
+            (&mut constraints.secrets, &added.secrets),
+        ] {
+            for entry in additions {
+                if !entries.contains(entry) {
+                    entries.push(entry.clone());
+                }
+            }
+        }
+        extended
+    }
+
+    /// The features to build into the tool.
+    pub fn extension(&self) -> Extension {
+        Extension {
+            features: self.features.clone(),
+        }
+    }
+}
+
+/// Build the JSON schema for the extend_capability tool.
+pub fn extend_capability_tool() -> Tool {
+    let list = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "target_tool": {
+                "type": "string",
+                "description": "Name of a GIRT-built tool to extend"
+            },
+            "features": {
+                "type": "array",
+                "items": { "type": "string" },
+                "minItems": 1,
+                "description": "What the tool should do that it does not yet, one entry per feature"
+            },
+            "new_inputs": {
+                "type": "object",
+                "description": "Input fields to add to the tool's input schema"
+            },
+            "new_constraints": {
+                "type": "object",
+                "description": "Network hosts, storage paths and secrets to add to the tool's constraints",
+                "properties": { "network": list, "storage": list, "secrets": list },
+                "additionalProperties": false
+            }
+        },
+        "required": ["target_tool", "features"],
+        "additionalProperties": false
+    });
+
+    Tool {
+        name: EXTEND_TOOL.into(),
+        title: None,
+        description: Some(
+            "Add features to a tool GIRT already built, instead of requesting a new one. \
+             The tool's spec with the additions goes through the same Creation Gate as \
+             request_capability, and the response shows what it adds. If allowed, the tool \
+             is rebuilt from its current source and replaced under the same name."
+                .into(),
+        ),
+        input_schema: schema.as_object().cloned().unwrap_or_default().into(),
+        output_schema: None,
+        annotations: None,
+        execution: None,
+        icons: None,
+        meta: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_core::spec::SpecChange;
+
+    fn args(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    fn spec() -> CapabilitySpec {
+        CapabilitySpec {
+            name: "gh_issues".into(),
+            description: "List a repo's GitHub issues".into(),
+            inputs: serde_json::json!({ "repo": "string" }),
+            outputs: serde_json::json!({ "issues": "array" }),
+            constraints: CapabilityConstraints {
+                network: vec!["api.github.com".into()],
+                storage: vec![],
+                secrets: vec!["GITHUB_TOKEN".into()],
+            },
+        }
+    }
+
+    #[test]
+    fn additions_are_merged_into_the_spec() {
+        let request = ExtendRequest::parse(Some(&args(serde_json::json!({
+            "target_tool": "gh_issues",
+            "features": ["filter by label", "include pull requests"],
+            "new_inputs": { "label": "string" },
+            "new_constraints": { "network": ["api.github.com", "uploads.github.com"] }
+        }))))
+        .unwrap();
+
+        let extended = request.apply(&spec());
+        assert_eq!(extended.name, "gh_issues");
+        assert!(
+            extended
+                .description
+                .ends_with("Extended with: filter by label; include pull requests"),
+            "{}",
+            extended.description
+        );
+        assert_eq!(
+            extended.inputs,
+            serde_json::json!({ "repo": "string", "label": "string" })
+        );
+        assert_eq!(
+            extended.constraints.network,
+            ["api.github.com", "uploads.github.com"]
+        );
+        assert_eq!(extended.constraints.secrets, ["GITHUB_TOKEN"]);
+
+        let diff = spec().diff(&extended);
+        assert_eq!(diff.inputs.len(), 1);
+        assert!(matches!(
+            &diff.constraints[..],
+            [SpecChange::Added { key, .. }] if key == "network"
+        ));
+        assert_eq!(
+            request.extension(),
+            Extension {
+                features: vec!["filter by label".into(), "include pull requests".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn requests_without_features_or_with_unknown_fields_are_refused() {
+        assert!(ExtendRequest::parse(None).is_err());
+        for bad in [
+            serde_json::json!({ "target_tool": "gh_issues", "features": [] }),
+            serde_json::json!({ "target_tool": "gh_issues", "features": [" "] }),
+            serde_json::json!({ "target_tool": "gh_issues", "features": ["x"], "name": "y" }),
+        ] {
+            assert!(
+                ExtendRequest::parse(Some(&args(bad.clone()))).is_err(),
+                "{bad}"
+            );
+        }
+    }
+}
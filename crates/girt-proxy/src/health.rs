@@ -0,0 +1,471 @@
+//! `girt health`: whether GIRT can serve, for supervisors (systemd,
+//! container orchestrators) that otherwise only know the process exists.
+//!
+//! A [`HealthCheck`] runs [`Probe`]s in order. Each failed probe reports the
+//! status it was given: [`HealthStatus::Unhealthy`] for what GIRT cannot
+//! serve without, such as writable storage, and [`HealthStatus::Degraded`]
+//! for what only limits it, such as an unreachable LLM provider while built
+//! tools stay callable. The report takes the worst status of its probes.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use girt_pipeline::llm::LlmClient;
+use serde::{Deserialize, Serialize};
+
+use crate::request_log::now_ms;
+
+/// How long an [`LlmProbe`] reuses its last answer.
+pub const LLM_PROBE_TTL: Duration = Duration::from_secs(60);
+
+/// The state of GIRT, or of one probe. Ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    /// Serving, with something unavailable.
+    Degraded,
+    /// Not able to serve.
+    Unhealthy,
+}
+
+impl HealthStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a [`Probe`] found, or why it failed.
+pub type ProbeFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
+
+/// One thing GIRT needs to serve.
+pub trait Probe: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The status reported when the probe fails.
+    fn impact(&self) -> HealthStatus;
+
+    fn check(&self) -> ProbeFuture<'_>;
+}
+
+/// One probe's part of a [`HealthReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProbeResult {
+    pub name: String,
+    pub status: HealthStatus,
+    /// What was found, or why the probe failed.
+    pub detail: String,
+}
+
+/// The result of [`HealthCheck::run`] (`girt health --json`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// The worst status among `checks`.
+    pub status: HealthStatus,
+    pub checks: Vec<ProbeResult>,
+}
+
+impl HealthReport {
+    /// Whether GIRT can serve requests, possibly degraded.
+    pub fn is_serving(&self) -> bool {
+        self.status != HealthStatus::Unhealthy
+    }
+}
+
+/// The probes behind a [`HealthReport`].
+#[derive(Default)]
+pub struct HealthCheck {
+    probes: Vec<Box<dyn Probe>>,
+}
+
+impl HealthCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_probe(mut self, probe: impl Probe + 'static) -> Self {
+        self.probes.push(Box::new(probe));
+        self
+    }
+
+    /// Run every probe in order.
+    pub async fn run(&self) -> HealthReport {
+        let mut checks = Vec::with_capacity(self.probes.len());
+        for probe in &self.probes {
+            let (status, detail) = match probe.check().await {
+                Ok(detail) => (HealthStatus::Healthy, detail),
+                Err(detail) => (probe.impact(), detail),
+            };
+            checks.push(ProbeResult {
+                name: probe.name().to_string(),
+                status,
+                detail,
+            });
+        }
+        let status = checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(HealthStatus::Healthy);
+        HealthReport { status, checks }
+    }
+}
+
+/// A probe that calls `check`, reporting `impact` when it fails.
+pub fn probe<F>(name: &str, impact: HealthStatus, check: F) -> FnProbe<F>
+where
+    F: Fn() -> Result<String, String> + Send + Sync,
+{
+    FnProbe {
+        name: name.to_string(),
+        impact,
+        check,
+    }
+}
+
+/// A probe that fails with `detail`, for a check that could not be set up.
+pub fn failed(name: &str, impact: HealthStatus, detail: impl Into<String>) -> impl Probe + 'static {
+    let detail = detail.into();
+    probe(name, impact, move || Err(detail.clone()))
+}
+
+/// A synchronous probe, from [`probe`].
+pub struct FnProbe<F> {
+    name: String,
+    impact: HealthStatus,
+    check: F,
+}
+
+impl<F> Probe for FnProbe<F>
+where
+    F: Fn() -> Result<String, String> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn impact(&self) -> HealthStatus {
+        self.impact
+    }
+
+    fn check(&self) -> ProbeFuture<'_> {
+        let result = (self.check)();
+        Box::pin(async move { result })
+    }
+}
+
+/// Checks that a directory exists, or can be created, and takes a file.
+/// Unhealthy on failure unless given another impact.
+pub struct StorageProbe {
+    name: String,
+    dir: PathBuf,
+    impact: HealthStatus,
+}
+
+impl StorageProbe {
+    pub fn new(name: &str, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.to_string(),
+            dir: dir.into(),
+            impact: HealthStatus::Unhealthy,
+        }
+    }
+
+    /// Report `impact` instead when the directory is not writable.
+    pub fn with_impact(mut self, impact: HealthStatus) -> Self {
+        self.impact = impact;
+        self
+    }
+}
+
+impl Probe for StorageProbe {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn impact(&self) -> HealthStatus {
+        self.impact
+    }
+
+    fn check(&self) -> ProbeFuture<'_> {
+        Box::pin(async move {
+            let dir = self.dir.display();
+            let probe_file = self.dir.join(format!(".health-{}", uuid::Uuid::new_v4()));
+            let written = async {
+                tokio::fs::create_dir_all(&self.dir).await?;
+                tokio::fs::write(&probe_file, b"ok").await?;
+                tokio::fs::remove_file(&probe_file).await
+            };
+            match written.await {
+                Ok(()) => Ok(format!("{dir} is writable")),
+                Err(e) => Err(format!("{dir} is not writable: {e}")),
+            }
+        })
+    }
+}
+
+/// Checks the LLM provider answers ([`LlmClient::ping`]). Degraded on
+/// failure: tools already built stay callable without it.
+///
+/// An answer is reused for [`LLM_PROBE_TTL`], so frequent polling does not
+/// call the provider every time. With a cache file the answer is shared
+/// with later processes too, as each `girt health` run is its own process.
+pub struct LlmProbe {
+    client: Arc<dyn LlmClient>,
+    ttl: Duration,
+    cache_file: Option<PathBuf>,
+    last: Mutex<Option<CachedPing>>,
+}
+
+/// An [`LlmProbe`] answer and when it was given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPing {
+    checked_at_ms: u64,
+    result: Result<String, String>,
+}
+
+impl LlmProbe {
+    pub fn new(client: Arc<dyn LlmClient>) -> Self {
+        Self {
+            client,
+            ttl: LLM_PROBE_TTL,
+            cache_file: None,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Reuse an answer for `ttl` instead of [`LLM_PROBE_TTL`].
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Share answers with other processes through `path`.
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_file = Some(path.into());
+        self
+    }
+
+    /// The last answer, if it is recent enough to reuse.
+    fn cached(&self, now_ms: u64) -> Option<CachedPing> {
+        let fresh = |ping: &CachedPing| {
+            now_ms.saturating_sub(ping.checked_at_ms) < self.ttl.as_millis() as u64
+        };
+        let last = self.last.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        last.or_else(|| {
+            let content = std::fs::read_to_string(self.cache_file.as_ref()?).ok()?;
+            serde_json::from_str(&content).ok()
+        })
+        .filter(fresh)
+    }
+
+    fn remember(&self, ping: CachedPing) {
+        if let Some(path) = &self.cache_file
+            && let Err(e) = serde_json::to_vec(&ping)
+                .map_err(std::io::Error::other)
+                .and_then(|json| std::fs::write(path, json))
+        {
+            tracing::debug!(path = %path.display(), error = %e, "Failed to cache the LLM health check");
+        }
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = Some(ping);
+    }
+}
+
+impl Probe for LlmProbe {
+    fn name(&self) -> &str {
+        "llm"
+    }
+
+    fn impact(&self) -> HealthStatus {
+        HealthStatus::Degraded
+    }
+
+    fn check(&self) -> ProbeFuture<'_> {
+        Box::pin(async move {
+            let now = now_ms();
+            if let Some(ping) = self.cached(now) {
+                return ping.result;
+            }
+            let provider = format!("{} ({})", self.client.provider(), self.client.model());
+            let result = match self.client.ping().await {
+                Ok(()) => Ok(format!("{provider} is reachable")),
+                Err(e) => Err(format!("{provider} is unreachable: {e}")),
+            };
+            self.remember(CachedPing {
+                checked_at_ms: now,
+                result: result.clone(),
+            });
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_pipeline::error::PipelineError;
+    use girt_pipeline::llm::{LlmRequest, LlmResponse};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn passing(name: &str, impact: HealthStatus) -> impl Probe + 'static {
+        probe(name, impact, || Ok("fine".into()))
+    }
+
+    fn failing(name: &str, impact: HealthStatus) -> impl Probe + 'static {
+        failed(name, impact, "broken")
+    }
+
+    #[tokio::test]
+    async fn the_worst_failed_probe_sets_the_status() {
+        use HealthStatus::{Degraded, Healthy, Unhealthy};
+
+        let report = HealthCheck::new().run().await;
+        assert_eq!(report.status, Healthy);
+
+        let healthy = HealthCheck::new()
+            .with_probe(passing("storage", Unhealthy))
+            .with_probe(passing("llm", Degraded));
+        assert_eq!(healthy.run().await.status, Healthy);
+
+        // LLM down with cached tools callable: still serving.
+        let degraded = HealthCheck::new()
+            .with_probe(passing("storage", Unhealthy))
+            .with_probe(failing("llm", Degraded));
+        let report = degraded.run().await;
+        assert_eq!(report.status, Degraded);
+        assert!(report.is_serving());
+        assert_eq!(
+            report.checks[1],
+            ProbeResult {
+                name: "llm".into(),
+                status: Degraded,
+                detail: "broken".into(),
+            }
+        );
+
+        let unhealthy = HealthCheck::new()
+            .with_probe(failing("storage", Unhealthy))
+            .with_probe(failing("llm", Degraded));
+        let report = unhealthy.run().await;
+        assert_eq!(report.status, Unhealthy);
+        assert!(!report.is_serving());
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "status": "unhealthy",
+                "checks": [
+                    { "name": "storage", "status": "unhealthy", "detail": "broken" },
+                    { "name": "llm", "status": "degraded", "detail": "broken" },
+                ],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn storage_must_be_writable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let report = HealthCheck::new()
+            .with_probe(StorageProbe::new("storage", tmp.path().join("data")))
+            .run()
+            .await;
+        assert_eq!(report.status, HealthStatus::Healthy);
+        assert_eq!(
+            std::fs::read_dir(tmp.path().join("data")).unwrap().count(),
+            0
+        );
+
+        // A directory cannot be made under a file.
+        let file = tmp.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let spool = StorageProbe::new("approval_spool", file.join("spool"))
+            .with_impact(HealthStatus::Degraded);
+        let report = HealthCheck::new().with_probe(spool).run().await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.checks[0].detail.contains("is not writable"));
+    }
+
+    /// Answers pings as told, counting them.
+    struct PingCounter {
+        up: bool,
+        pings: AtomicUsize,
+    }
+
+    impl LlmClient for PingCounter {
+        fn chat<'a>(
+            &'a self,
+            _request: &'a LlmRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<LlmResponse, PipelineError>> + Send + 'a>> {
+            unreachable!("health checks do not chat")
+        }
+
+        fn ping(&self) -> Pin<Box<dyn Future<Output = Result<(), PipelineError>> + Send + '_>> {
+            self.pings.fetch_add(1, Ordering::SeqCst);
+            let up = self.up;
+            Box::pin(async move {
+                if up {
+                    Ok(())
+                } else {
+                    Err(PipelineError::LlmTransport("connection refused".into()))
+                }
+            })
+        }
+    }
+
+    fn llm(up: bool) -> Arc<PingCounter> {
+        Arc::new(PingCounter {
+            up,
+            pings: AtomicUsize::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn llm_answers_are_reused_within_the_ttl() {
+        let down = llm(false);
+        let probe = LlmProbe::new(down.clone());
+        let detail = probe.check().await.unwrap_err();
+        assert!(detail.contains("connection refused"), "{detail}");
+        assert!(probe.check().await.is_err());
+        assert_eq!(down.pings.load(Ordering::SeqCst), 1);
+
+        let up = llm(true);
+        let probe = LlmProbe::new(up.clone()).with_ttl(Duration::ZERO);
+        assert_eq!(
+            probe.check().await.unwrap(),
+            "unknown (unknown) is reachable"
+        );
+        probe.check().await.unwrap();
+        assert_eq!(up.pings.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn llm_answers_are_shared_through_the_cache_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cache_file = tmp.path().join("health-llm.json");
+        let up = llm(true);
+
+        let first = LlmProbe::new(up.clone()).with_cache_file(&cache_file);
+        first.check().await.unwrap();
+        let second = LlmProbe::new(up.clone()).with_cache_file(&cache_file);
+        second.check().await.unwrap();
+        assert_eq!(up.pings.load(Ordering::SeqCst), 1);
+
+        let stale = LlmProbe::new(up.clone())
+            .with_cache_file(&cache_file)
+            .with_ttl(Duration::ZERO);
+        stale.check().await.unwrap();
+        assert_eq!(up.pings.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,245 @@
+//! Capability builds the proxy is running, keyed by spec hash.
+//!
+//! An agent that calls `request_capability` again before its first build
+//! finishes would otherwise start a second pipeline for the same spec, and
+//! the two would collide at publish time. Each build is registered here
+//! from before it waits for a build slot until it ends; a request for an
+//! identical spec in the meantime is answered with the running build's
+//! request id. Entries live in memory only and go when the build ends, so
+//! asking again afterwards is handled as a fresh request.
+//!
+//! Each entry also says which phase its build is in, for the
+//! `girt://state` document.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use girt_core::spec::CapabilitySpec;
+use serde::Serialize;
+
+use crate::request_log::now_ms;
+
+/// A build registered in [`InFlightBuilds`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InFlight {
+    pub request_id: String,
+    pub tool_name: String,
+}
+
+/// Where a running build has got to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildPhase {
+    /// Waiting for a build slot.
+    Waiting,
+    /// In the Architect, Engineer, QA, and Red Team loop.
+    Pipeline,
+    Compiling,
+    /// Publishing and loading the compiled tool.
+    Publishing,
+}
+
+/// A build in [`InFlightBuilds`], with its phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunningBuild {
+    pub request_id: String,
+    pub tool_name: String,
+    pub phase: BuildPhase,
+    pub started_at_ms: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    build: InFlight,
+    phase: BuildPhase,
+    started_at_ms: u64,
+}
+
+/// Running builds by [`CapabilitySpec::spec_hash`].
+#[derive(Debug, Default)]
+pub struct InFlightBuilds {
+    by_hash: Mutex<HashMap<String, Entry>>,
+}
+
+impl InFlightBuilds {
+    /// Register a build of `spec` as `request_id` until the returned guard
+    /// is dropped. Fails with the registered build when one for an
+    /// identical spec is already running.
+    pub fn start(
+        self: &Arc<Self>,
+        spec: &CapabilitySpec,
+        request_id: &str,
+    ) -> Result<InFlightGuard, InFlight> {
+        let hash = spec.spec_hash();
+        let mut by_hash = self.by_hash.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = by_hash.get(&hash) {
+            return Err(existing.build.clone());
+        }
+        by_hash.insert(
+            hash.clone(),
+            Entry {
+                build: InFlight {
+                    request_id: request_id.to_string(),
+                    tool_name: spec.name.clone(),
+                },
+                phase: BuildPhase::Waiting,
+                started_at_ms: now_ms(),
+            },
+        );
+        Ok(InFlightGuard {
+            builds: Arc::clone(self),
+            hash,
+        })
+    }
+
+    /// The running build of a spec identical to `spec`, if any.
+    pub fn find(&self, spec: &CapabilitySpec) -> Option<InFlight> {
+        let by_hash = self.by_hash.lock().unwrap_or_else(|e| e.into_inner());
+        by_hash.get(&spec.spec_hash()).map(|e| e.build.clone())
+    }
+
+    /// Running builds named like `spec` but with a different spec, by
+    /// request id.
+    pub fn conflicts(&self, spec: &CapabilitySpec) -> Vec<InFlight> {
+        let hash = spec.spec_hash();
+        let by_hash = self.by_hash.lock().unwrap_or_else(|e| e.into_inner());
+        let mut conflicts: Vec<InFlight> = by_hash
+            .iter()
+            .filter(|(h, e)| **h != hash && e.build.tool_name == spec.name)
+            .map(|(_, e)| e.build.clone())
+            .collect();
+        conflicts.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+        conflicts
+    }
+
+    /// Every running build, oldest first.
+    pub fn running(&self) -> Vec<RunningBuild> {
+        let by_hash = self.by_hash.lock().unwrap_or_else(|e| e.into_inner());
+        let mut running: Vec<RunningBuild> = by_hash
+            .values()
+            .map(|e| RunningBuild {
+                request_id: e.build.request_id.clone(),
+                tool_name: e.build.tool_name.clone(),
+                phase: e.phase,
+                started_at_ms: e.started_at_ms,
+            })
+            .collect();
+        running.sort_by(|a, b| {
+            (a.started_at_ms, &a.request_id).cmp(&(b.started_at_ms, &b.request_id))
+        });
+        running
+    }
+}
+
+/// Keeps a build registered in [`InFlightBuilds`] while held.
+pub struct InFlightGuard {
+    builds: Arc<InFlightBuilds>,
+    hash: String,
+}
+
+impl InFlightGuard {
+    /// Record that the build has moved on to `phase`.
+    pub fn set_phase(&self, phase: BuildPhase) {
+        let mut by_hash = self
+            .builds
+            .by_hash
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = by_hash.get_mut(&self.hash) {
+            entry.phase = phase;
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut by_hash = self
+            .builds
+            .by_hash
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        by_hash.remove(&self.hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use girt_core::spec::CapabilityConstraints;
+
+    use super::*;
+
+    fn spec(name: &str, description: &str) -> CapabilitySpec {
+        CapabilitySpec {
+            name: name.into(),
+            description: description.into(),
+            inputs: serde_json::json!({ "type": "object" }),
+            outputs: serde_json::json!({ "type": "object" }),
+            constraints: CapabilityConstraints::default(),
+        }
+    }
+
+    #[test]
+    fn identical_specs_share_one_build() {
+        let builds = Arc::new(InFlightBuilds::default());
+        let _first = builds
+            .start(&spec("word_count", "Count words"), "req_1")
+            .unwrap();
+
+        let Err(existing) = builds.start(&spec("word_count", "Count words"), "req_2") else {
+            panic!("a second build of the same spec started");
+        };
+        assert_eq!(existing.request_id, "req_1");
+        assert_eq!(
+            builds.find(&spec("word_count", "Count words")),
+            Some(existing)
+        );
+    }
+
+    #[test]
+    fn a_different_spec_under_the_same_name_is_a_conflict() {
+        let builds = Arc::new(InFlightBuilds::default());
+        let _first = builds
+            .start(&spec("word_count", "Count words"), "req_1")
+            .unwrap();
+        let other = spec("word_count", "Count words and lines");
+
+        let _second = builds.start(&other, "req_2").unwrap();
+        let conflicts = builds.conflicts(&other);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].request_id, "req_1");
+        assert!(
+            builds
+                .conflicts(&spec("line_count", "Count lines"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn finished_builds_no_longer_block_requests() {
+        let builds = Arc::new(InFlightBuilds::default());
+        let word_count = spec("word_count", "Count words");
+        drop(builds.start(&word_count, "req_1").unwrap());
+
+        assert_eq!(builds.find(&word_count), None);
+        assert!(builds.start(&word_count, "req_2").is_ok());
+    }
+
+    #[test]
+    fn running_builds_report_their_phase() {
+        let builds = Arc::new(InFlightBuilds::default());
+        let guard = builds
+            .start(&spec("word_count", "Count words"), "req_1")
+            .unwrap();
+        assert_eq!(builds.running()[0].phase, BuildPhase::Waiting);
+
+        guard.set_phase(BuildPhase::Compiling);
+        let running = builds.running();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].request_id, "req_1");
+        assert_eq!(running[0].tool_name, "word_count");
+        assert_eq!(running[0].phase, BuildPhase::Compiling);
+
+        drop(guard);
+        assert!(builds.running().is_empty());
+    }
+}
@@ -0,0 +1,18 @@
+pub mod approvals;
+pub mod batch;
+pub mod cli;
+pub mod denials;
+pub mod evaluator;
+pub mod extend;
+pub mod health;
+pub mod history;
+pub mod inflight;
+pub mod peers;
+pub mod proxy;
+pub mod quarantine;
+pub mod reload;
+pub mod request_log;
+pub mod schema;
+pub mod services;
+pub mod state;
+pub mod telemetry;
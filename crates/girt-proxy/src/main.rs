@@ -1,22 +1,67 @@
-use std::path::PathBuf;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
+use clap_complete::env::EnvCompleter;
+use clap_complete::{ArgValueCandidates, CompleteEnv, CompletionCandidate};
+use girt_core::audit::AuditLog;
+use girt_core::decision::{Decision, GateKind};
 use girt_core::engine::DecisionEngine;
-use girt_pipeline::cache::ToolCache;
-use girt_pipeline::config::GirtConfig;
-use girt_pipeline::publish::Publisher;
-use girt_runtime::LifecycleManager;
-use girt_secrets::{AnthropicOAuthStore, OAuthMode};
+use girt_core::layers::cli_check::CliCheckLayer;
+use girt_core::paths::{self, PathError};
+use girt_core::spec::{CapabilitySpec, GateInput};
+use girt_pipeline::cache::{self, ToolCache};
+use girt_pipeline::compiler::WasmCompiler;
+use girt_pipeline::config::{
+    ConfigLayers, ConfigWatcher, EgressMode, GirtConfig, Profile, RuntimeConfig,
+};
+use girt_pipeline::failures::{FailureBundle, FailureStore, FailureSummary, line_diff};
+use girt_pipeline::handwritten::{self, HandwrittenTool};
+use girt_pipeline::llm::LlmClient;
+use girt_pipeline::metrics::PipelineMetrics;
+use girt_pipeline::orchestrator::{DryRunReport, Orchestrator};
+use girt_pipeline::publish::{PublishResult, Publisher};
+use girt_pipeline::queue::{EnqueueOutcome, PublishHook, Queue, QueueConsumer};
+use girt_pipeline::rebuild::{RebuildOutcome, RebuildReport, Rebuilder};
+use girt_pipeline::scratch::{self, Scratch};
+use girt_pipeline::spec_import::OpenApiDocument;
+use girt_pipeline::tool_sync::ToolSync;
+use girt_pipeline::types::{
+    BuildArtifact, CapabilityRequest, PipelineOverrides, RequestSource, RequestStatus,
+    ResourceTier, SpecAction, TargetLanguage,
+};
+use girt_pipeline::validation::{Revalidator, ToolRunner, ValidationOutcome, ValidationReport};
+use girt_pipeline::worker::{Worker, WorkerOptions};
+use girt_proxy::approvals::{self, ApprovalStore, Resolution};
+use girt_proxy::cli::args::{call_arguments, existing_file, parse_arg_pair};
+use girt_proxy::cli::output::{
+    Added, AuthStatus, BuiltinAdded, Deprecated, DoctorCheck, Enqueued, IterationDiff,
+    OutputFormat, Released, Resolved,
+};
+use girt_proxy::denials::RecentDenials;
+use girt_proxy::evaluator::GateLlmEvaluator;
+use girt_proxy::health::{self, HealthCheck, HealthStatus, LlmProbe, StorageProbe};
+use girt_proxy::history::{
+    self, InvocationHistory, InvocationRecord, InvocationStatus, Redactor, ToolStats,
+    is_secret_name,
+};
+use girt_proxy::proxy::{GirtProxy, component_meta};
+use girt_proxy::quarantine::recheck_restored;
+use girt_proxy::reload;
+use girt_proxy::request_log::{RequestLog, now_ms};
+use girt_proxy::state::{ApprovalBacklog, StateDocument, ToolCounts};
+use girt_proxy::telemetry;
+use girt_runtime::bundled;
+use girt_runtime::storage::ComponentStorage;
+use girt_runtime::{CallOptions, ComponentMeta, EngineConfig, LifecycleManager, host_capabilities};
+use girt_secrets::{
+    AnthropicOAuthStore, GitHubOAuthStore, OAuthMode, OAuthProviderSpec, OAuthTokenStore,
+};
 use rmcp::ServiceExt;
-use tracing_subscriber::{EnvFilter, fmt};
-
-mod evaluator;
-mod proxy;
-
-use evaluator::GateLlmEvaluator;
-use proxy::GirtProxy;
 
 // ── CLI ───────────────────────────────────────────────────────────────────────
 
@@ -34,6 +79,22 @@ struct Cli {
     #[arg(long, global = true)]
     config: Option<PathBuf>,
 
+    /// Merge `girt.<name>.toml`, next to girt.toml, over it. Defaults to
+    /// `GIRT_PROFILE`.
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Use girt.toml alone when the profile has no overlay file, instead of
+    /// failing.
+    #[arg(long, global = true)]
+    profile_optional: bool,
+
+    /// Print results to stdout as JSON instead of text (auth, tools,
+    /// approvals, enqueue, build, queue, stats, failures, config, doctor,
+    /// health).
+    #[arg(long, global = true)]
+    json: bool,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -41,60 +102,525 @@ struct Cli {
 #[derive(Subcommand)]
 enum Command {
     /// Run the MCP proxy server on stdio (default when no subcommand is given).
-    Serve,
-    /// Manage Anthropic OAuth credentials.
+    Serve {
+        /// Re-read girt.toml every few seconds and apply policy rules and
+        /// tool env changes without restarting.
+        #[arg(long)]
+        watch_config: bool,
+        /// Build each tool from scratch instead of in the shared
+        /// `~/.girt/build-cache/` workspace.
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Manage OAuth credentials (Anthropic, GitHub).
     Auth {
         #[command(subcommand)]
         action: AuthCommand,
     },
+    /// Add, inspect, and run tools directly. Only `add` consults a decision
+    /// gate.
+    Tools {
+        #[command(subcommand)]
+        action: ToolsCommand,
+    },
+    /// Approve a capability request that is waiting on `approval_token`.
+    Approve {
+        token: String,
+    },
+    /// Reject a capability request that is waiting on `approval_token`.
+    Reject {
+        token: String,
+    },
+    /// Inspect capability requests awaiting human approval.
+    Approvals {
+        #[command(subcommand)]
+        action: ApprovalsCommand,
+    },
+    /// Build queued capability requests until interrupted (Ctrl-C finishes
+    /// in-flight builds first). Built tools are stored under `~/.girt`,
+    /// where a proxy restores them on startup.
+    Worker {
+        /// Requests built at the same time. Defaults to `[pipeline]
+        /// concurrent_builds`.
+        #[arg(long)]
+        concurrency: Option<usize>,
+        /// How often to check an empty queue (`"5s"`, `"1m"`, `"1m 30s"`).
+        #[arg(long, default_value = "5s", value_parser = humantime::parse_duration)]
+        poll_interval: Duration,
+    },
+    /// Queue a capability request for `girt worker`. Override flags are
+    /// bounded by `[pipeline.caps]` when the request is built.
+    Enqueue {
+        /// Tool name (snake_case).
+        name: String,
+        /// What the tool does and why it is needed.
+        #[arg(long)]
+        description: String,
+        /// Build iterations before giving up.
+        #[arg(long)]
+        max_iterations: Option<u32>,
+        /// Language to write the tool in (`rust`, `go`, `assemblyscript`).
+        #[arg(long, value_parser = parse_snake_case::<TargetLanguage>)]
+        language: Option<TargetLanguage>,
+        /// Resource limits for the tool (`minimal`, `standard`, `extended`).
+        #[arg(long, value_parser = parse_snake_case::<ResourceTier>)]
+        tier: Option<ResourceTier>,
+        /// Tokens the build may generate across all agent calls.
+        #[arg(long)]
+        token_budget: Option<u32>,
+    },
+    /// Build a capability spec: queue it for `girt worker`, or with
+    /// `--dry-run` print what the build would start from without spending
+    /// tokens on code.
+    Build {
+        /// Capability spec as JSON (`name`, `description`, `inputs`,
+        /// `outputs`, `constraints`).
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        spec: PathBuf,
+        /// Run only the Architect and print the refined spec, plan,
+        /// resource tier and policy.
+        #[arg(long)]
+        dry_run: bool,
+        /// Resource limits for the tool (`minimal`, `standard`, `extended`).
+        #[arg(long, value_parser = parse_snake_case::<ResourceTier>)]
+        tier: Option<ResourceTier>,
+    },
+    /// Write capability specs from existing API descriptions.
+    Spec {
+        #[command(subcommand)]
+        action: SpecCommand,
+    },
+    /// Inspect the build queue.
+    Queue {
+        #[command(subcommand)]
+        action: QueueCommand,
+    },
+    /// Count persisted tools, queued requests, and pending approvals. With
+    /// `--json`, print them as in a running proxy's `girt://state` resource,
+    /// without the running builds and gate decisions only it knows.
+    Stats,
+    /// Inspect what failed builds left in `~/.girt/failures/`, and the
+    /// iteration sources of builds kept with `keep_iteration_sources`.
+    Failures {
+        #[command(subcommand)]
+        action: FailuresCommand,
+    },
+    /// Check girt.toml and any `--profile` overlay.
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Check the configuration, credentials, and toolchain GIRT needs.
+    /// Exits non-zero if any check fails.
+    Doctor,
+    /// Report whether GIRT can serve, for supervisors: config, storage,
+    /// stored components, LLM reachability, and the approval spool. Exits
+    /// non-zero only when unhealthy; degraded (e.g. the LLM is unreachable
+    /// but built tools are callable) exits zero.
+    Health,
+    /// Print the shell code that completes `girt` commands, flags, and
+    /// persisted tool names, e.g. `source <(girt completions bash)` in
+    /// `~/.bashrc`. Re-source it after upgrading girt.
+    Completions { shell: CompletionShell },
+    /// Print the `girt(1)` man page, or with `--out-dir` write a page per
+    /// subcommand there.
+    Man {
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Validate the merged config and print each value that is set, with the
+    /// file it came from.
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum SpecCommand {
+    /// Turn OpenAPI 3 operations into a capability spec for `girt build` or
+    /// `request_capability`. Several `--operation`s make a multi-action spec.
+    FromOpenapi {
+        /// Fetch the OpenAPI document (YAML or JSON) from this URL.
+        #[arg(
+            long,
+            required_unless_present = "file",
+            conflicts_with = "file",
+            value_hint = ValueHint::Url
+        )]
+        url: Option<String>,
+        /// Read the OpenAPI document (YAML or JSON) from this file.
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        file: Option<PathBuf>,
+        /// `operationId` to import; repeat to import several.
+        #[arg(long = "operation", required = true)]
+        operations: Vec<String>,
+        /// Tool name. Defaults to the operation id, or the API title when
+        /// importing several, in snake_case.
+        #[arg(long)]
+        name: Option<String>,
+        /// Write the spec to this file instead of stdout.
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApprovalsCommand {
+    /// List pending approvals from running proxies.
+    Pending,
+}
+
+#[derive(Subcommand)]
+enum QueueCommand {
+    /// List pending, in-progress, and failed requests. Failed ones show
+    /// their error code and last error.
+    List,
+}
+
+#[derive(Subcommand)]
+enum FailuresCommand {
+    /// List the kept failure bundles, most recent first.
+    List,
+    /// Print a bundle's error, timings, and tickets, and where its files are.
+    Show {
+        /// Request id, as printed by `girt failures list`.
+        id: String,
+    },
+    /// Diff `src/lib.rs` between two iterations of a build.
+    Diff {
+        /// Request id.
+        id: String,
+        /// The two iterations to compare, e.g. `1,2`.
+        #[arg(long, value_delimiter = ',', required = true)]
+        iters: Vec<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolsCommand {
+    /// Compile a handwritten tool and load it like a built one, without the
+    /// LLM pipeline. The Creation Gate still sees it as an operator request.
+    Add {
+        /// MCP tool name; must match the spec's `name`.
+        #[arg(long)]
+        name: String,
+        /// The tool's `src/lib.rs`, built against the default girt-tool world.
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        source: PathBuf,
+        /// Capability spec as JSON (`name`, `description`, `inputs`,
+        /// `outputs`, `constraints`).
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        spec: PathBuf,
+        /// policy.yaml to ship with the tool. It may grant nothing beyond
+        /// the spec's constraints. Generated from them when omitted.
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        policy: Option<PathBuf>,
+        /// Test vectors for `girt tools validate` to re-run, as a JSON list
+        /// of `{"input": {..}, "expected": {"outcome": "ok", "output": ..}}`
+        /// (or `{"outcome": "error", "message": ..}`).
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        vectors: Option<PathBuf>,
+        /// Add the tool without consulting the Creation Gate.
+        #[arg(long)]
+        skip_gate: bool,
+    },
+    /// Store one of GIRT's bundled components (`echo`: `girt_echo`, a
+    /// diagnostic that returns its input with what the sandbox sees). Its
+    /// calls skip the Execution Gate. A running proxy picks it up on
+    /// restart.
+    AddBuiltin {
+        /// Bundled component name.
+        #[arg(value_parser = bundled_names())]
+        name: String,
+    },
+    /// Invoke a persisted tool and print its JSON result.
+    Call {
+        /// MCP tool name.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: String,
+        /// Arguments as a JSON object.
+        #[arg(long, conflicts_with = "args_file")]
+        args: Option<String>,
+        /// Read the arguments object from this JSON file.
+        #[arg(long, value_parser = existing_file, value_hint = ValueHint::FilePath)]
+        args_file: Option<PathBuf>,
+        /// Set one argument, over `--args` or `--args-file`; repeatable.
+        /// Values that are valid JSON (`10`, `true`, `["a"]`) keep their
+        /// type, anything else is a string.
+        #[arg(long = "arg", value_name = "KEY=VALUE", value_parser = parse_arg_pair)]
+        arg_pairs: Vec<(String, serde_json::Value)>,
+        /// Use a fixed wall clock and seeded random so the run can be
+        /// reproduced exactly.
+        #[arg(long)]
+        deterministic: bool,
+        /// Wall-clock time seen by the tool in deterministic mode (Unix ms).
+        #[arg(long, requires = "deterministic")]
+        time_ms: Option<u64>,
+        /// Random seed in deterministic mode.
+        #[arg(long, requires = "deterministic")]
+        seed: Option<u64>,
+    },
+    /// List the persisted tools, flagging deprecated, drifting, and
+    /// quarantined ones.
+    List,
+    /// Print a persisted tool's metadata: build and load times, WASM
+    /// integrity, and where its artifact and source were published.
+    Inspect {
+        /// MCP tool name.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: String,
+    },
+    /// Mark a persisted tool deprecated. Calls keep working, with a warning,
+    /// until `[tools] deprecation_grace` has passed. A running proxy picks
+    /// this up on restart.
+    Deprecate {
+        /// MCP tool name.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: String,
+        /// Tool agents should call instead.
+        #[arg(long)]
+        replaced_by: Option<String>,
+    },
+    /// Release a tool quarantined because a policy rule denied it on
+    /// restore. It is no longer re-checked against the rules. A running
+    /// proxy picks this up on restart.
+    Release {
+        /// MCP tool name.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: String,
+    },
+    /// Print a tool's recorded calls, most recent first. Needs
+    /// `[server] record_invocations = true`.
+    History {
+        /// MCP tool name.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: String,
+    },
+    /// Summarize each tool's recorded calls: count, errors, p95 duration.
+    /// Needs `[server] record_invocations = true`.
+    Stats {
+        /// MCP tool name; every recorded tool when omitted.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: Option<String>,
+        /// Also show p95 peak memory against the limit, and p95 fuel with
+        /// `[runtime] fuel_metering`, to size resource tiers by.
+        #[arg(long)]
+        resources: bool,
+    },
+    /// Re-run a recorded call with its recorded (redacted) arguments.
+    Replay {
+        /// MCP tool name.
+        #[arg(add = ArgValueCandidates::new(tool_name_candidates))]
+        name: String,
+        /// Entry to replay, as numbered by `girt tools history`.
+        #[arg(long, default_value_t = 0)]
+        index: usize,
+    },
+    /// Re-run the pipeline on a published tool's stored spec and replace it,
+    /// e.g. after a prompt or model change. Skips the Creation Gate; the new
+    /// policy may still grant nothing beyond the spec. A running proxy picks
+    /// rebuilt tools up on restart.
+    Rebuild {
+        /// MCP tool name.
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(tool_name_candidates)
+        )]
+        name: Option<String>,
+        /// Rebuild every published tool.
+        #[arg(long)]
+        all: bool,
+        /// Skip tools published more recently than this (e.g. `30d`,
+        /// `12h`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        if_older_than: Option<Duration>,
+    },
+    /// Re-run the test vectors recorded with a published tool and flag it if
+    /// its output no longer has the recorded shape, e.g. because an upstream
+    /// API changed. Exits non-zero on drift. A running proxy shows the flag
+    /// in `list_tools` after a restart.
+    Validate {
+        /// MCP tool name.
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(tool_name_candidates)
+        )]
+        name: Option<String>,
+        /// Validate every published tool.
+        #[arg(long)]
+        all: bool,
+        /// Queue a rebuild of each drifting tool with the failing vectors
+        /// attached, as `[pipeline] revalidate_rebuild` does.
+        #[arg(long)]
+        rebuild: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum AuthCommand {
-    /// Authenticate with Anthropic via OAuth 2.0 (PKCE).
+    /// Authenticate via OAuth 2.0.
     ///
-    /// Opens a browser authorization URL. After authorizing, paste the
-    /// `code#state` response string at the prompt.
+    /// Anthropic (default) uses PKCE: open the printed authorization URL, then
+    /// paste the `code#state` response string at the prompt. GitHub uses the
+    /// device-code flow and requires `GIRT_GITHUB_CLIENT_ID`.
     Login {
-        /// Use Console mode to create an API key instead of a Max subscription token.
+        #[arg(long, value_enum, default_value_t = AuthProvider::Anthropic)]
+        provider: AuthProvider,
+        /// Use Console mode to create an API key instead of a Max subscription token (Anthropic only).
         #[arg(long)]
         console: bool,
+        /// OAuth scopes to request (GitHub only).
+        #[arg(long, default_value = "repo read:org")]
+        scopes: String,
     },
     /// Show the status of stored credentials.
-    Status,
+    Status {
+        #[arg(long, value_enum, default_value_t = AuthProvider::Anthropic)]
+        provider: AuthProvider,
+    },
     /// Remove stored credentials.
-    Logout,
+    Logout {
+        #[arg(long, value_enum, default_value_t = AuthProvider::Anthropic)]
+        provider: AuthProvider,
+    },
+}
+
+/// Shells `girt completions` writes for.
+#[derive(Clone, Copy, ValueEnum)]
+enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// OAuth providers supported by `girt auth`.
+#[derive(Clone, Copy, ValueEnum)]
+enum AuthProvider {
+    Anthropic,
+    #[value(name = "github")]
+    GitHub,
 }
 
 // ── Entry point ───────────────────────────────────────────────────────────────
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Logs go to stderr — stdout is reserved for MCP stdio transport.
-    fmt()
-        .with_env_filter(EnvFilter::from_env("GIRT_LOG"))
-        .with_writer(std::io::stderr)
-        .init();
-
+    // Answers the completion requests of a shell set up by `girt
+    // completions`, then exits; a normal run passes through.
+    CompleteEnv::with_factory(Cli::command).complete();
     let cli = Cli::parse();
+    // Commands report a missing or broken girt.toml themselves.
+    let config_source = ConfigSource::from_cli(&cli)?;
+    let telemetry_config = config_source
+        .resolve()
+        .ok()
+        .and_then(|path| config_source.load_from(&path).ok())
+        .map(|config| config.telemetry);
+    let _telemetry = telemetry::init(telemetry_config.as_ref());
+    match paths::migrate_legacy_data() {
+        Ok(Some((from, to))) => {
+            tracing::info!(from = %from.display(), to = %to.display(), "Moved girt data directory");
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(error = %e, "Cannot move ~/.girt; still using it"),
+    }
+    let format = OutputFormat::from_json_flag(cli.json);
 
     match cli.command {
-        None | Some(Command::Serve) => run_serve(cli.config).await,
-        Some(Command::Auth { action }) => run_auth(action).await,
+        None => run_serve(config_source, false, false).await,
+        Some(Command::Serve {
+            watch_config,
+            no_cache,
+        }) => run_serve(config_source, watch_config, no_cache).await,
+        Some(Command::Auth { action }) => run_auth(action, format).await,
+        Some(Command::Tools { action }) => run_tools(config_source, action, format).await,
+        Some(Command::Approve { token }) => run_resolve(&token, Resolution::Approve, format),
+        Some(Command::Reject { token }) => run_resolve(&token, Resolution::Reject, format),
+        Some(Command::Approvals {
+            action: ApprovalsCommand::Pending,
+        }) => run_approvals_pending(format),
+        Some(Command::Worker {
+            concurrency,
+            poll_interval,
+        }) => run_worker(config_source, concurrency, poll_interval).await,
+        Some(Command::Enqueue {
+            name,
+            description,
+            max_iterations,
+            language,
+            tier,
+            token_budget,
+        }) => {
+            let overrides = PipelineOverrides {
+                max_iterations,
+                target_language: language,
+                resource_tier: tier,
+                token_budget,
+            };
+            run_enqueue(config_source, name, description, overrides, format).await
+        }
+        Some(Command::Build {
+            spec,
+            dry_run,
+            tier,
+        }) => run_build(config_source, &spec, dry_run, tier, format).await,
+        Some(Command::Spec {
+            action:
+                SpecCommand::FromOpenapi {
+                    url,
+                    file,
+                    operations,
+                    name,
+                    output,
+                },
+        }) => {
+            let source = match (url, file) {
+                (Some(url), _) => OpenApiSource::Url(url),
+                (None, Some(file)) => OpenApiSource::File(file),
+                (None, None) => unreachable!("clap requires --url or --file"),
+            };
+            let output = output.as_deref();
+            run_spec_from_openapi(source, &operations, name.as_deref(), output, format).await
+        }
+        Some(Command::Queue {
+            action: QueueCommand::List,
+        }) => run_queue_list(format).await,
+        Some(Command::Stats) => run_stats(format).await,
+        Some(Command::Failures {
+            action: FailuresCommand::List,
+        }) => run_failures_list(format).await,
+        Some(Command::Failures {
+            action: FailuresCommand::Show { id },
+        }) => run_failures_show(&id, format).await,
+        Some(Command::Failures {
+            action: FailuresCommand::Diff { id, iters },
+        }) => run_failures_diff(&id, &iters, format).await,
+        Some(Command::Config {
+            action: ConfigCommand::Validate,
+        }) => run_config_validate(config_source, format),
+        Some(Command::Doctor) => run_doctor(config_source, format).await,
+        Some(Command::Health) => run_health(config_source, format).await,
+        Some(Command::Completions { shell }) => run_completions(shell),
+        Some(Command::Man { out_dir }) => run_man(out_dir.as_deref()),
     }
 }
 
 // ── Serve ─────────────────────────────────────────────────────────────────────
 
 /// Run the MCP proxy server on stdio.
-async fn run_serve(config_flag: Option<PathBuf>) -> Result<()> {
-    let config_path = resolve_config(config_flag).context("Failed to locate girt.toml")?;
+async fn run_serve(config_source: ConfigSource, watch_config: bool, no_cache: bool) -> Result<()> {
+    let config_path = config_source
+        .resolve()
+        .context("Failed to locate girt.toml")?;
 
     tracing::info!(config = %config_path.display(), "Starting GIRT MCP proxy");
 
     // Load config
-    let config = GirtConfig::from_file(&config_path)
-        .with_context(|| format!("Failed to load config from {}", config_path.display()))?;
+    let config = config_source.load_from(&config_path)?;
     tracing::info!(
         provider = ?config.llm.provider,
         model = %config.llm.model,
@@ -115,30 +641,145 @@ async fn run_serve(config_flag: Option<PathBuf>) -> Result<()> {
     // Load optional coding standards (injected into Engineer's system prompt)
     let coding_standards = config.load_coding_standards();
 
-    // Initialize the Hookwise decision engine with real LLM evaluators.
-    // Both gates share the same underlying client via Arc.
-    let engine = Arc::new(DecisionEngine::with_real_llm(
-        Box::new(GateLlmEvaluator::new(Arc::clone(&llm))),
-        Box::new(GateLlmEvaluator::new(Arc::clone(&llm))),
-    ));
-    tracing::info!("Decision engine initialized with real LLM evaluator");
+    // The proxy records Creation Gate denials; the gate sees them when the
+    // same name is requested again, along with how the request differs from
+    // an already built tool of that name.
+    let recent_denials = Arc::new(RecentDenials::default());
+    let engine = decision_engine(&config, Arc::clone(&llm), Arc::clone(&recent_denials)).await?;
 
     // Initialize tool cache and publisher
-    let cache = ToolCache::new(ToolCache::default_path());
+    let cache = ToolCache::new(ToolCache::default_path()?);
     cache.init().await?;
-    let publisher = Arc::new(Publisher::new(cache));
+    let publisher =
+        Arc::new(Publisher::new(cache).with_collision_policy(config.registry.on_name_collision));
     tracing::info!("Tool cache initialized");
 
     // Initialize girt-runtime (ADR-010)
-    let runtime = Arc::new(
-        LifecycleManager::new(None).context("Failed to initialize girt-runtime")?,
-    );
+    let runtime = LifecycleManager::with_engine_config(None, &engine_config(&config.runtime))
+        .context("Failed to initialize girt-runtime")?
+        .with_tool_env(config.tools.env.clone())
+        .with_egress_proxy(config.runtime.egress_mode == EgressMode::Proxy)
+        .with_max_response_bytes(config.runtime.max_response_bytes)
+        .with_oversize_response(config.runtime.oversize_response);
+    let faults = config
+        .runtime
+        .fault_injection
+        .clone()
+        .with_env(|var| std::env::var(var).ok())
+        .and_then(|faults| faults.injector())
+        .context("Refusing to start with runtime.fault_injection")?;
+    let runtime = Arc::new(match faults {
+        Some(injector) => {
+            tracing::warn!(faults = %injector, "Fault injection enabled: tool calls will fail on purpose");
+            runtime.with_fault_injector(injector)
+        }
+        None => runtime,
+    });
     // Restore components built in previous sessions
     runtime.load_persisted().await;
+    if dev_tools_requested() {
+        load_dev_tools(&runtime).await;
+    }
+    // Policy rules added since the tools were built apply to them too.
+    if config.security.recheck_on_restore {
+        let cache = ToolCache::new(ToolCache::default_path()?);
+        let quarantined = recheck_restored(&engine, &runtime, &cache).await;
+        if !quarantined.is_empty() {
+            tracing::warn!(
+                tools = ?quarantined,
+                "Quarantined restored tools denied by the policy rules; see `girt tools list`"
+            );
+        }
+    }
     tracing::info!("girt-runtime initialized");
+    sweep_scratch();
+
+    // Optional mirroring of published tools into a git repo
+    let tool_sync = ToolSync::from_config(&config.registry).map(Arc::new);
+    if let Some(sync) = &tool_sync {
+        tracing::info!(repo = %sync.repo_url(), "Tool sync enabled");
+    }
 
     // Create proxy handler
-    let proxy = GirtProxy::new(engine, llm, publisher, runtime, coding_standards);
+    let proxy = GirtProxy::new(
+        Arc::clone(&engine),
+        llm,
+        publisher,
+        Arc::clone(&runtime),
+        coding_standards,
+        tool_sync,
+    )
+    .with_server_config(&config.server)
+    .with_security_config(&config.security)
+    .with_tools_config(&config.tools)
+    .with_pipeline_config(&config.pipeline)
+    .with_build_config(&config.build)
+    .with_recent_denials(recent_denials)
+    .with_failure_store(
+        FailureStore::new(FailureStore::default_path()?)
+            .with_iteration_sources(config.pipeline.keep_iteration_sources),
+    )
+    .with_build_queue(Queue::new(Queue::default_path()?));
+    let compiler = WasmCompiler::new()
+        .with_wit_version(config.build.wit_version)
+        .with_sandbox(config.build.sandbox)
+        .with_host_capabilities(host_capabilities());
+    let proxy = if no_cache || !config.pipeline.build_cache {
+        tracing::info!("Build cache disabled; tools compile from scratch");
+        proxy.with_compiler(compiler.without_cache())
+    } else {
+        proxy.with_compiler(compiler)
+    };
+    let approvals = ApprovalStore::new(std::time::Duration::from_secs(
+        config.server.approval_ttl_secs,
+    ))
+    .with_spool_dir(ApprovalStore::default_spool_dir()?);
+    // Requests left waiting by an earlier run are resolved by this one.
+    match approvals.restore() {
+        Ok(0) => {}
+        Ok(adopted) => tracing::info!(adopted, "Restored pending approvals from the spool"),
+        Err(e) => tracing::warn!(error = %e, "Failed to restore pending approvals"),
+    }
+    let proxy = proxy.with_approvals(approvals);
+    let proxy = if config.server.request_log {
+        let path = RequestLog::default_path()?;
+        let log = RequestLog::open(&path)
+            .with_context(|| format!("Failed to open request log {}", path.display()))?;
+        tracing::info!(path = %log.path().display(), "Request log enabled");
+        proxy.with_request_log(log)
+    } else {
+        proxy
+    };
+    let proxy = if config.server.record_invocations {
+        // Secret-looking per-tool env values are redacted along with the
+        // proxy's own.
+        let tool_secrets = config.tools.env.values().flat_map(|vars| {
+            vars.iter()
+                .filter(|(key, _)| is_secret_name(key))
+                .map(|(_, value)| value.clone())
+        });
+        let history = InvocationHistory::new(
+            InvocationHistory::default_dir()?,
+            config.server.invocation_history,
+        )
+        .with_redactor(Redactor::from_env().with_values(tool_secrets));
+        tracing::info!(dir = %history.dir().display(), "Invocation history enabled");
+        proxy.with_invocation_history(history)
+    } else {
+        proxy
+    };
+
+    if watch_config {
+        let watcher = ConfigWatcher::new(&config_path, config_source.profile.clone())
+            .with_context(|| format!("Failed to watch {}", config_path.display()))?;
+        reload::spawn_config_watcher(watcher, config, engine, runtime);
+    }
+
+    // The Creation Gate defers only to tools restored above or built later.
+    proxy.refresh_known_tools().await;
+
+    // Pick up `girt approve` / `girt reject` from the approval spool
+    proxy.spawn_approval_watcher();
 
     // Serve on stdio (agent connects here)
     let stdio = rmcp::transport::io::stdio();
@@ -152,6 +793,52 @@ async fn run_serve(config_flag: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
+/// The Hookwise decision engine with real LLM evaluators, both gates sharing
+/// `llm`, with girt.toml's policy rules loaded.
+async fn decision_engine(
+    config: &GirtConfig,
+    llm: Arc<dyn LlmClient>,
+    recent_denials: Arc<RecentDenials>,
+) -> Result<Arc<DecisionEngine>> {
+    let min_allow_confidence = config.security.min_allow_confidence;
+    let engine = DecisionEngine::with_real_llm(
+        Box::new(
+            GateLlmEvaluator::new(Arc::clone(&llm))
+                .with_min_allow_confidence(min_allow_confidence)
+                .with_recent_denials(recent_denials)
+                .with_tool_cache(ToolCache::new(ToolCache::default_path()?)),
+        ),
+        Box::new(GateLlmEvaluator::new(llm).with_min_allow_confidence(min_allow_confidence)),
+    )
+    .with_cli_check(CliCheckLayer::with_extra_utilities(
+        config.cli_check.utilities.clone(),
+    ))
+    .with_constraint_budget(config.security.budget.clone())
+    .with_always_ask(config.security.always_ask.clone())
+    .with_execution_allow_ttl(config.security.execution_allow_ttl);
+    let audit_path = audit_log_path()?;
+    let engine = if config.security.audit_log {
+        let log = AuditLog::open(&audit_path)
+            .with_context(|| format!("Failed to open audit log {}", audit_path.display()))?;
+        tracing::info!(path = %log.path().display(), "Decision audit log enabled");
+        Arc::new(engine.with_audit_log(log))
+    } else {
+        Arc::new(engine)
+    };
+    engine
+        .reload_policy(config.policy.deny.clone(), config.policy.allow.clone())
+        .await;
+    // After reload_policy, which clears the caches.
+    if let Some(max_age) = config.security.warm_cache_from_audit {
+        engine
+            .warm_from_audit(&audit_path, max_age)
+            .await
+            .with_context(|| format!("Failed to read audit log {}", audit_path.display()))?;
+    }
+    tracing::info!("Decision engine initialized with real LLM evaluator");
+    Ok(engine)
+}
+
 /// Check `AnthropicOAuthStore` and, if it holds a valid token and
 /// `ANTHROPIC_API_KEY` is not already set, inject it into the process environment.
 ///
@@ -163,7 +850,13 @@ async fn inject_oauth_token_if_needed() {
         return;
     }
 
-    let store = AnthropicOAuthStore::new();
+    let store = match AnthropicOAuthStore::new() {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!(error = %e, "Cannot locate GIRT OAuth tokens — continuing without them");
+            return;
+        }
+    };
     match store.get_valid_token().await {
         Ok(Some(token)) => {
             tracing::info!("Injecting GIRT OAuth token into ANTHROPIC_API_KEY");
@@ -181,20 +874,33 @@ async fn inject_oauth_token_if_needed() {
 
 // ── Auth subcommands ──────────────────────────────────────────────────────────
 
-async fn run_auth(action: AuthCommand) -> Result<()> {
-    let store = AnthropicOAuthStore::new();
-
+async fn run_auth(action: AuthCommand, format: OutputFormat) -> Result<()> {
     match action {
-        AuthCommand::Login { console } => {
+        AuthCommand::Login {
+            provider: AuthProvider::Anthropic,
+            console,
+            ..
+        } => {
             let mode = if console {
                 OAuthMode::Console
             } else {
                 OAuthMode::Max
             };
-            run_auth_login(&store, mode).await
+            run_auth_login(&AnthropicOAuthStore::new()?, mode).await
         }
-        AuthCommand::Status => run_auth_status(&store).await,
-        AuthCommand::Logout => run_auth_logout(&store),
+        AuthCommand::Login {
+            provider: AuthProvider::GitHub,
+            scopes,
+            ..
+        } => run_auth_login_github(&GitHubOAuthStore::new()?, &scopes).await,
+        AuthCommand::Status { provider } => match provider {
+            AuthProvider::Anthropic => run_auth_status(&AnthropicOAuthStore::new()?, format).await,
+            AuthProvider::GitHub => run_auth_status(&GitHubOAuthStore::new()?, format).await,
+        },
+        AuthCommand::Logout { provider } => match provider {
+            AuthProvider::Anthropic => run_auth_logout(&AnthropicOAuthStore::new()?, format),
+            AuthProvider::GitHub => run_auth_logout(&GitHubOAuthStore::new()?, format),
+        },
     }
 }
 
@@ -230,80 +936,1837 @@ async fn run_auth_login(store: &AnthropicOAuthStore, mode: OAuthMode) -> Result<
         .await
         .context("Failed to exchange code for tokens")?;
 
-    eprintln!("\n✓ Authenticated successfully. Credentials saved to ~/.config/girt/auth.json");
+    eprintln!(
+        "\n✓ Authenticated successfully. Credentials saved to {}",
+        store.token_path().display()
+    );
     Ok(())
 }
 
-async fn run_auth_status(store: &AnthropicOAuthStore) -> Result<()> {
-    match store.status().await.context("Failed to read credentials")? {
-        None => {
-            eprintln!("Not logged in. Run `girt auth login` to authenticate.");
-        }
-        Some(status) => {
-            let state = if status.is_expired {
-                "⚠ expired (will auto-refresh on next use)"
-            } else {
-                "✓ valid"
-            };
-            eprintln!("Status: {state}");
-            eprintln!("Token:  {}…", status.access_token_prefix);
-            let expires = chrono_from_unix(status.expires_at_unix);
-            eprintln!("Expiry: {expires}");
+async fn run_auth_login_github(store: &GitHubOAuthStore, scopes: &str) -> Result<()> {
+    eprintln!("Starting GitHub device login (scopes: {scopes})...\n");
+
+    let device = GitHubOAuthStore::start_device_flow(scopes)
+        .await
+        .context("Failed to start GitHub device flow")?;
+
+    eprintln!("1. Open this URL in your browser:\n");
+    eprintln!("   {}\n", device.verification_uri);
+    eprintln!("2. Enter the code:  {}\n", device.user_code);
+    eprintln!("Waiting for authorization...");
+
+    store
+        .complete_device_login(&device)
+        .await
+        .context("GitHub device authorization failed")?;
+
+    eprintln!(
+        "\n✓ Authenticated successfully. Credentials saved to {}",
+        store.token_path().display()
+    );
+    Ok(())
+}
+
+async fn run_auth_status<P: OAuthProviderSpec>(
+    store: &OAuthTokenStore<P>,
+    format: OutputFormat,
+) -> Result<()> {
+    let token = store.status().await.context("Failed to read credentials")?;
+    let status = AuthStatus::new(store.provider_name(), token.as_ref());
+    format.emit(&status, |status| {
+        let Some(prefix) = &status.token_prefix else {
             eprintln!(
-                "Refresh token: {}",
-                if status.has_refresh_token { "stored" } else { "not stored" }
+                "Not logged in to {}. Run `girt auth login --provider {}` to authenticate.",
+                status.provider, status.provider
             );
-        }
-    }
+            return;
+        };
+        let state = if status.expired {
+            "⚠ expired (will auto-refresh on next use)"
+        } else {
+            "✓ valid"
+        };
+        eprintln!("Status: {state}");
+        eprintln!("Token:  {prefix}…");
+        let expires = match status.expires_at_unix {
+            Some(expires_at) => chrono_from_unix(expires_at),
+            None => "never".to_string(),
+        };
+        eprintln!("Expiry: {expires}");
+        let refresh = if status.has_refresh_token {
+            "stored"
+        } else {
+            "not stored"
+        };
+        eprintln!("Refresh token: {refresh}");
+    })?;
     Ok(())
 }
 
-fn run_auth_logout(store: &AnthropicOAuthStore) -> Result<()> {
+fn run_auth_logout<P: OAuthProviderSpec>(
+    store: &OAuthTokenStore<P>,
+    format: OutputFormat,
+) -> Result<()> {
     store.logout().context("Failed to remove credentials")?;
-    eprintln!("✓ Credentials removed.");
+    let status = AuthStatus::new(store.provider_name(), None);
+    format.emit(&status, |_| eprintln!("✓ Credentials removed."))?;
     Ok(())
 }
 
-// ── Helpers ───────────────────────────────────────────────────────────────────
+// ── Tools subcommands ─────────────────────────────────────────────────────────
 
-/// Resolve config path using standard search order:
-/// 1. Explicit --config flag
-/// 2. ./girt.toml (relative to cwd)
-/// 3. ~/.config/girt/girt.toml (user-level installation)
-fn resolve_config(explicit: Option<PathBuf>) -> anyhow::Result<PathBuf> {
-    if let Some(p) = explicit {
-        return Ok(p);
+async fn run_tools(
+    config_source: ConfigSource,
+    action: ToolsCommand,
+    format: OutputFormat,
+) -> Result<()> {
+    match action {
+        ToolsCommand::Add {
+            name,
+            source,
+            spec,
+            policy,
+            vectors,
+            skip_gate,
+        } => {
+            let paths = AddPaths {
+                source,
+                spec,
+                policy,
+                vectors,
+            };
+            run_tools_add(config_source, &name, paths, skip_gate, format).await
+        }
+        ToolsCommand::AddBuiltin { name } => run_tools_add_builtin(&name, format).await,
+        ToolsCommand::Call {
+            name,
+            args,
+            args_file,
+            arg_pairs,
+            deterministic,
+            time_ms,
+            seed,
+        } => {
+            let args = call_arguments(args.as_deref(), args_file.as_deref(), arg_pairs)?;
+            let options = CallOptions {
+                deterministic,
+                fixed_time_ms: time_ms,
+                rng_seed: seed,
+            };
+            run_tools_call(config_source, &name, &args, &options).await
+        }
+        ToolsCommand::List => run_tools_list(format),
+        ToolsCommand::Inspect { name } => run_tools_inspect(&name, format),
+        ToolsCommand::Deprecate { name, replaced_by } => {
+            run_tools_deprecate(&name, replaced_by, format).await
+        }
+        ToolsCommand::Release { name } => run_tools_release(&name, format).await,
+        ToolsCommand::History { name } => run_tools_history(&name, format),
+        ToolsCommand::Stats { name, resources } => run_tools_stats(name, resources, format),
+        ToolsCommand::Replay { name, index } => run_tools_replay(config_source, &name, index).await,
+        ToolsCommand::Rebuild {
+            name,
+            all: _,
+            if_older_than,
+        } => run_tools_rebuild(config_source, name, if_older_than, format).await,
+        ToolsCommand::Validate {
+            name,
+            all: _,
+            rebuild,
+        } => run_tools_validate(config_source, name, rebuild, format).await,
     }
-    let local = PathBuf::from("girt.toml");
-    if local.exists() {
-        return Ok(local);
+}
+
+/// Source files for `girt tools add`.
+struct AddPaths {
+    source: PathBuf,
+    spec: PathBuf,
+    policy: Option<PathBuf>,
+    vectors: Option<PathBuf>,
+}
+
+/// Gate, compile, publish, and load a handwritten tool, then sync it to the
+/// registry repo if one is configured.
+async fn run_tools_add(
+    config_source: ConfigSource,
+    name: &str,
+    paths: AddPaths,
+    skip_gate: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let config = config_source.load()?;
+
+    let read = |path: &PathBuf| {
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    };
+    let spec: CapabilitySpec = serde_json::from_str(&read(&paths.spec)?)
+        .with_context(|| format!("{} is not a capability spec", paths.spec.display()))?;
+    anyhow::ensure!(
+        spec.name == name,
+        "{} names the tool '{}', not '{name}'",
+        paths.spec.display(),
+        spec.name
+    );
+    let tool = HandwrittenTool {
+        spec,
+        source_code: read(&paths.source)?,
+        policy_yaml: paths.policy.as_ref().map(read).transpose()?,
+        test_vectors: match &paths.vectors {
+            Some(path) => serde_json::from_str(&read(path)?)
+                .with_context(|| format!("{} is not a list of test vectors", path.display()))?,
+            None => Vec::new(),
+        },
+    };
+
+    let gate = if skip_gate {
+        "skipped"
+    } else {
+        inject_oauth_token_if_needed().await;
+        let llm = config
+            .build_llm_client()
+            .context("Failed to initialize LLM client")?;
+        let engine = decision_engine(&config, llm, Arc::new(RecentDenials::default())).await?;
+        let input = GateInput::creation(tool.spec.clone(), RequestSource::Operator);
+        let result = engine.evaluate(GateKind::Creation, &input).await?;
+        match result.decision {
+            Decision::Allow => "allowed",
+            Decision::Deny { reason } => {
+                anyhow::bail!("The Creation Gate denied '{name}': {reason}")
+            }
+            Decision::Ask { prompt, .. } => anyhow::bail!(
+                "The Creation Gate wants a person to decide: {prompt}\n\
+                 Re-run with --skip-gate to add '{name}' anyway."
+            ),
+            Decision::AllowModified { changes, .. } => anyhow::bail!(
+                "The Creation Gate would allow '{name}' only with changes: {}\n\
+                 Apply them to the spec, or re-run with --skip-gate to add it as is.",
+                changes.join("; ")
+            ),
+            Decision::Defer { target } => anyhow::bail!(
+                "The Creation Gate deferred '{name}' to {}",
+                serde_json::to_string(&target)?
+            ),
+        }
+    };
+
+    let compiler = WasmCompiler::new()
+        .with_wit_version(config.build.wit_version)
+        .with_sandbox(config.build.sandbox)
+        .with_host_capabilities(host_capabilities());
+    let compiler = if config.pipeline.build_cache {
+        compiler
+    } else {
+        compiler.without_cache()
+    };
+    let mut built = handwritten::build(tool, &compiler)
+        .await
+        .with_context(|| format!("Failed to build '{name}'"))?;
+
+    let publisher = Publisher::new(ToolCache::new(ToolCache::default_path()?))
+        .with_collision_policy(config.registry.on_name_collision);
+    publisher.init().await?;
+    let published = publisher
+        .publish_with_wasm(&mut built.artifact, &built.compiled.wasm_path)
+        .await?;
+
+    // Synced first, so the runtime metadata records where to.
+    let tool_sync = match ToolSync::from_config(&config.registry) {
+        Some(sync) => match sync.sync(&built.artifact, &published).await {
+            Ok(outcome) => Some(outcome),
+            Err(e) => {
+                eprintln!("warning: tool sync to {} failed: {e}", sync.repo_url());
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Stored where a proxy's `load_persisted` finds it on restart.
+    let runtime = LifecycleManager::with_engine_config(None, &engine_config(&config.runtime))
+        .context("Failed to initialize girt-runtime")?;
+    runtime
+        .load_component(
+            &published.local_path.join("tool.wasm"),
+            located_component_meta(&built.artifact, &published).await,
+        )
+        .await
+        .with_context(|| format!("Failed to store '{name}'"))?;
+
+    let added = Added {
+        name: published.tool_name.clone(),
+        wasm_sha256: published.wasm.map(|w| w.sha256).unwrap_or_default(),
+        gate: gate.to_string(),
+        tool_sync,
+    };
+    format.emit(&added, |added| {
+        eprintln!(
+            "Added '{}' (wasm sha256 {}). A running proxy picks it up on restart.",
+            added.name, added.wasm_sha256
+        );
+    })?;
+    Ok(())
+}
+
+/// Names `girt tools add-builtin` accepts.
+fn bundled_names() -> clap::builder::PossibleValuesParser {
+    clap::builder::PossibleValuesParser::new(bundled::ALL.iter().map(|component| component.name))
+}
+
+/// Store the bundled component `name` where a proxy's `load_persisted`
+/// finds it.
+async fn run_tools_add_builtin(name: &str, format: OutputFormat) -> Result<()> {
+    let component =
+        bundled::find(name).with_context(|| format!("No bundled component named '{name}'"))?;
+    let runtime = LifecycleManager::new(None).context("Failed to initialize girt-runtime")?;
+    let component_id = runtime
+        .load_bundled(component.meta())
+        .await
+        .with_context(|| format!("Failed to store '{}'", component.tool_name))?;
+
+    let added = BuiltinAdded {
+        name: component.tool_name.to_string(),
+        component_id,
+        wasm_sha256: component.hash(),
+    };
+    format.emit(&added, |added| {
+        eprintln!(
+            "Added '{}' ({}). A running proxy picks it up on restart.",
+            added.name, added.component_id
+        );
+    })?;
+    Ok(())
+}
+
+/// Rebuild `name`, or every published tool when `None`, and print how each
+/// went. Fails if any rebuild failed.
+async fn run_tools_rebuild(
+    config_source: ConfigSource,
+    name: Option<String>,
+    older_than: Option<Duration>,
+    format: OutputFormat,
+) -> Result<()> {
+    let config = config_source.load()?;
+
+    inject_oauth_token_if_needed().await;
+    let llm = config
+        .build_llm_client()
+        .context("Failed to initialize LLM client")?;
+
+    // Stores each rebuilt component where a proxy's `load_persisted` finds it.
+    let runtime = LifecycleManager::with_engine_config(None, &engine_config(&config.runtime))
+        .context("Failed to initialize girt-runtime")?;
+    let mut rebuilder = Rebuilder::new(llm, ToolCache::new(ToolCache::default_path()?))
+        .with_standards(config.load_coding_standards())
+        .with_sampling(config.pipeline.sampling)
+        .with_static_checks(config.pipeline.static_checks)
+        .with_blocking_policy(config.pipeline.blocking)
+        .with_wit_version(config.build.wit_version)
+        .with_host_capabilities(host_capabilities())
+        .with_failure_store(
+            FailureStore::new(FailureStore::default_path()?)
+                .with_iteration_sources(config.pipeline.keep_iteration_sources),
+        );
+    // Synced first, so the runtime metadata records where to.
+    if let Some(sync) = ToolSync::from_config(&config.registry) {
+        rebuilder = rebuilder.with_hook(Arc::new(sync));
     }
-    if let Some(home) = dirs::home_dir() {
-        let user_config = home.join(".config").join("girt").join("girt.toml");
-        if user_config.exists() {
-            return Ok(user_config);
+    rebuilder = rebuilder.with_hook(Arc::new(RuntimeLoader(runtime)));
+
+    let compiler = WasmCompiler::new()
+        .with_wit_version(config.build.wit_version)
+        .with_sandbox(config.build.sandbox)
+        .with_host_capabilities(host_capabilities());
+    let compiler = if config.pipeline.build_cache {
+        compiler
+    } else {
+        compiler.without_cache()
+    };
+    let reports = match name {
+        Some(name) => {
+            let outcome = rebuilder.rebuild(&name, &compiler, older_than).await;
+            vec![RebuildReport { name, outcome }]
+        }
+        None => rebuilder.rebuild_all(&compiler, older_than).await?,
+    };
+
+    let failed = reports
+        .iter()
+        .filter(|r| matches!(r.outcome, RebuildOutcome::Failed { .. }))
+        .count();
+    format.emit(&reports, |reports| {
+        if reports.is_empty() {
+            eprintln!("No tools are published.");
+        }
+        let (mut built, mut skipped) = (0, 0);
+        for report in reports {
+            match &report.outcome {
+                RebuildOutcome::Built {
+                    revision,
+                    wasm_sha256,
+                } => {
+                    built += 1;
+                    println!(
+                        "{}  built  revision {revision}  wasm sha256 {wasm_sha256}",
+                        report.name
+                    );
+                }
+                RebuildOutcome::Failed { error } => {
+                    println!("{}  failed  {error}", report.name);
+                }
+                RebuildOutcome::Skipped { reason } => {
+                    skipped += 1;
+                    println!("{}  skipped  {reason}", report.name);
+                }
+            }
         }
+        eprintln!("{built} built, {failed} failed, {skipped} skipped.");
+    })?;
+    anyhow::ensure!(failed == 0, "{failed} of {} rebuilds failed", reports.len());
+    Ok(())
+}
+
+async fn run_tools_call(
+    config_source: ConfigSource,
+    name: &str,
+    args: &serde_json::Value,
+    options: &CallOptions,
+) -> Result<()> {
+    // Size limits come from girt.toml when there is one; it is optional here.
+    let config = optional_config(&config_source)?;
+    config
+        .as_ref()
+        .map(|config| config.security.size_limits())
+        .unwrap_or_default()
+        .check_arguments(args)?;
+    let runtime = calling_runtime(config.as_ref()).await?;
+
+    let result = runtime
+        .call_tool_with_options(name, args, options)
+        .await
+        .with_context(|| format!("Tool '{name}' failed"))?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+/// girt.toml, if there is one.
+fn optional_config(config_source: &ConfigSource) -> Result<Option<GirtConfig>> {
+    match config_source.resolve() {
+        Ok(path) => Ok(Some(config_source.load_from(&path)?)),
+        Err(_) => Ok(None),
     }
-    anyhow::bail!(
-        "No girt.toml found. Looked in: ./girt.toml and ~/.config/girt/girt.toml\n\
-         Run from the girt repo directory, or copy girt.toml to ~/.config/girt/girt.toml"
-    )
 }
 
-/// Format a Unix timestamp for human display (no chrono dep needed — manual).
-fn chrono_from_unix(unix_secs: u64) -> String {
-    use std::time::{Duration, UNIX_EPOCH};
-    let dt = UNIX_EPOCH + Duration::from_secs(unix_secs);
-    match dt.duration_since(std::time::SystemTime::now()) {
-        Ok(remaining) => {
-            let mins = remaining.as_secs() / 60;
-            if mins < 60 {
-                format!("in {mins} minutes")
+/// A runtime with every persisted tool loaded, to call tools outside a
+/// proxy. Tool env and runtime settings come from `config`, if any.
+async fn calling_runtime(config: Option<&GirtConfig>) -> Result<LifecycleManager> {
+    let (tool_env, runtime_config) = match config {
+        Some(config) => (config.tools.env.clone(), config.runtime.clone()),
+        None => Default::default(),
+    };
+    let runtime = LifecycleManager::with_engine_config(None, &engine_config(&runtime_config))
+        .context("Failed to initialize girt-runtime")?
+        .with_tool_env(tool_env)
+        .with_egress_proxy(runtime_config.egress_mode == EgressMode::Proxy)
+        .with_max_response_bytes(runtime_config.max_response_bytes)
+        .with_oversize_response(runtime_config.oversize_response);
+    runtime.load_persisted().await;
+    Ok(runtime)
+}
+
+/// A [`Revalidator`] for the published tools that calls them through
+/// `runtime`, queueing a rebuild of each drifting tool if `rebuild`.
+async fn revalidator(
+    config: Option<&GirtConfig>,
+    runtime: LifecycleManager,
+    rebuild: bool,
+) -> Result<Revalidator> {
+    let revalidator = Revalidator::new(
+        ToolCache::new(ToolCache::default_path()?),
+        Arc::new(RuntimeRunner(runtime)),
+    );
+    if !rebuild {
+        return Ok(revalidator);
+    }
+    let mut queue = Queue::new(Queue::default_path()?);
+    if let Some(config) = config {
+        queue = queue
+            .with_limits(config.security.size_limits())
+            .with_max_attempts(config.pipeline.max_request_attempts);
+    }
+    queue.init().await?;
+    Ok(revalidator.with_rebuild_queue(queue))
+}
+
+/// Re-run the test vectors of `name`, or of every published tool.
+async fn run_tools_validate(
+    config_source: ConfigSource,
+    name: Option<String>,
+    rebuild: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let config = optional_config(&config_source)?;
+    let rebuild = rebuild
+        || config
+            .as_ref()
+            .is_some_and(|config| config.pipeline.revalidate_rebuild);
+    let runtime = calling_runtime(config.as_ref()).await?;
+    let revalidator = revalidator(config.as_ref(), runtime, rebuild).await?;
+    let reports = match name {
+        Some(name) => {
+            let outcome = revalidator.validate(&name).await;
+            vec![ValidationReport { name, outcome }]
+        }
+        None => revalidator.validate_all(None).await?,
+    };
+
+    let drifted = reports
+        .iter()
+        .filter(|r| matches!(r.outcome, ValidationOutcome::Drifted { .. }))
+        .count();
+    let failed = reports
+        .iter()
+        .filter(|r| matches!(r.outcome, ValidationOutcome::Failed { .. }))
+        .count();
+    format.emit(&reports, |reports| {
+        if reports.is_empty() {
+            eprintln!("No tools are published.");
+        }
+        let (mut passed, mut skipped) = (0, 0);
+        for report in reports {
+            match &report.outcome {
+                ValidationOutcome::Passed { vectors } => {
+                    passed += 1;
+                    println!("{}  passed  {vectors} vector(s)", report.name);
+                }
+                ValidationOutcome::Drifted {
+                    vectors,
+                    drift,
+                    rebuild,
+                } => {
+                    println!(
+                        "{}  DRIFT  {} of {vectors} vector(s)",
+                        report.name,
+                        drift.len()
+                    );
+                    for vector in drift {
+                        for difference in &vector.differences {
+                            println!("    {}: {difference}", vector.input);
+                        }
+                    }
+                    if let Some(id) = rebuild {
+                        println!("    rebuild queued as {id}");
+                    }
+                }
+                ValidationOutcome::Failed { error } => {
+                    println!("{}  failed  {error}", report.name);
+                }
+                ValidationOutcome::Skipped { reason } => {
+                    skipped += 1;
+                    println!("{}  skipped  {reason}", report.name);
+                }
+            }
+        }
+        eprintln!("{passed} passed, {drifted} drifted, {failed} failed, {skipped} skipped.");
+    })?;
+    anyhow::ensure!(
+        drifted + failed == 0,
+        "{drifted} tool(s) drifted and {failed} could not be validated"
+    );
+    Ok(())
+}
+
+/// The stored metadata of the tool named `name`, if it is persisted.
+fn persisted_meta(storage: &ComponentStorage, name: &str) -> Result<Option<ComponentMeta>> {
+    Ok(storage
+        .list_component_ids()?
+        .iter()
+        .filter_map(|id| storage.load_meta(id).ok())
+        .find(|meta| meta.tool_name == name))
+}
+
+/// The stored metadata of every persisted tool, one per name, by name.
+fn persisted_tools(storage: &ComponentStorage) -> Result<Vec<ComponentMeta>> {
+    let mut metas: Vec<ComponentMeta> = storage
+        .list_component_ids()?
+        .iter()
+        .filter_map(|id| storage.load_meta(id).ok())
+        .collect();
+    metas.sort_by(|a, b| a.tool_name.cmp(&b.tool_name));
+    metas.dedup_by(|a, b| a.tool_name == b.tool_name);
+    Ok(metas)
+}
+
+fn run_tools_list(format: OutputFormat) -> Result<()> {
+    let storage = ComponentStorage::new(ComponentStorage::default_path()?);
+    let metas = persisted_tools(&storage)?;
+    format.emit(&metas, |metas| {
+        if metas.is_empty() {
+            eprintln!("No persisted tools.");
+        }
+        for meta in metas {
+            let mut flags = String::new();
+            if meta.deprecated {
+                flags.push_str(" [DEPRECATED]");
+            }
+            if meta.drift_detected {
+                flags.push_str(" [DRIFT DETECTED]");
+            }
+            if let Some(reason) = &meta.quarantined {
+                flags.push_str(&format!(" [QUARANTINED: {reason}]"));
+            }
+            println!("{}{flags}  {}", meta.tool_name, meta.description);
+        }
+    })?;
+    Ok(())
+}
+
+/// Print the stored metadata of the tool named `name`.
+fn run_tools_inspect(name: &str, format: OutputFormat) -> Result<()> {
+    let storage = ComponentStorage::new(ComponentStorage::default_path()?);
+    let meta = persisted_meta(&storage, name)?
+        .with_context(|| format!("No persisted tool named '{name}'"))?;
+    format.emit(&meta, |meta| {
+        println!("{}  {}", meta.tool_name, meta.description);
+        println!("Component:  {}", meta.component_id);
+        println!(
+            "WASM:       sha256 {} ({} bytes)",
+            meta.wasm_hash, meta.wasm_size_bytes
+        );
+        println!("Built at:   {}", chrono_from_unix(meta.built_at / 1000));
+        println!("Loaded at:  {}", chrono_from_unix(meta.loaded_at / 1000));
+        let unset = "(not published)";
+        println!(
+            "OCI:        {}",
+            meta.oci_reference.as_deref().unwrap_or(unset)
+        );
+        println!(
+            "Source:     {}",
+            meta.source_repo_path.as_deref().unwrap_or(unset)
+        );
+        if let Some(at) = meta.last_validated_at {
+            let outcome = if meta.drift_detected {
+                "drift detected"
             } else {
-                let hours = mins / 60;
-                format!("in {hours}h {}m", mins % 60)
+                "passed"
+            };
+            println!("Validated:  {} ({outcome})", chrono_from_unix(at / 1000));
+        }
+        if meta.deprecated {
+            match &meta.replaced_by {
+                Some(replacement) => println!("Deprecated in favour of '{replacement}'."),
+                None => println!("Deprecated."),
             }
         }
-        Err(_) => "already expired".to_string(),
+        if let Some(reason) = &meta.quarantined {
+            println!("Quarantined: {reason}");
+        }
+    })?;
+    Ok(())
+}
+
+async fn run_tools_deprecate(
+    name: &str,
+    replaced_by: Option<String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let runtime = LifecycleManager::new(None).context("Failed to initialize girt-runtime")?;
+    let meta = runtime
+        .deprecate(name, replaced_by)
+        .await
+        .with_context(|| format!("Failed to deprecate '{name}'"))?;
+
+    // Keep the tool cache manifest in step with the runtime metadata.
+    let cache = ToolCache::new(ToolCache::default_path()?);
+    cache
+        .mark_deprecated(
+            name,
+            meta.replaced_by.as_deref(),
+            meta.deprecated_at.unwrap_or_default(),
+        )
+        .await?;
+
+    let deprecated = Deprecated {
+        name: name.to_string(),
+        replaced_by: meta.replaced_by,
+    };
+    format.emit(&deprecated, |deprecated| match &deprecated.replaced_by {
+        Some(replacement) => eprintln!("Deprecated '{name}' in favour of '{replacement}'."),
+        None => eprintln!("Deprecated '{name}'."),
+    })?;
+    Ok(())
+}
+
+async fn run_tools_release(name: &str, format: OutputFormat) -> Result<()> {
+    let runtime = LifecycleManager::new(None).context("Failed to initialize girt-runtime")?;
+    runtime
+        .release(name)
+        .await
+        .with_context(|| format!("Failed to release '{name}'"))?;
+    let released = Released {
+        name: name.to_string(),
+    };
+    format.emit(&released, |_| {
+        eprintln!("Released '{name}' from quarantine; a running proxy picks this up on restart.")
+    })?;
+    Ok(())
+}
+
+/// Recorded calls, newest first; a call's position is its replay index.
+fn run_tools_history(name: &str, format: OutputFormat) -> Result<()> {
+    let mut records = history::load(&InvocationHistory::default_dir()?, name)?;
+    records.reverse();
+    format.emit(&records, |records| print_history(name, records))?;
+    Ok(())
+}
+
+fn print_history(name: &str, records: &[InvocationRecord]) {
+    if records.is_empty() {
+        eprintln!("No recorded calls for '{name}'.");
+        return;
+    }
+    for (index, record) in records.iter().enumerate() {
+        let status = match record.status {
+            InvocationStatus::Ok => "ok",
+            InvocationStatus::Error => "error",
+        };
+        println!(
+            "[{index}] {}  {status}  {}ms",
+            chrono_from_unix(record.timestamp_ms / 1000),
+            record.duration_ms
+        );
+        println!("    args: {}", record.arguments);
+        if let Some(error) = &record.error {
+            println!("    error: {error}");
+        }
+    }
+}
+
+/// Per-tool summaries of the recorded calls.
+fn run_tools_stats(name: Option<String>, resources: bool, format: OutputFormat) -> Result<()> {
+    let dir = InvocationHistory::default_dir()?;
+    let names = match name {
+        Some(name) => vec![name],
+        None => history::recorded_tools(&dir)?,
+    };
+    let storage = ComponentStorage::new(ComponentStorage::default_path()?);
+    let mut stats = Vec::new();
+    for name in &names {
+        let records = history::load(&dir, name)?;
+        if records.is_empty() {
+            continue;
+        }
+        let tool = ToolStats::new(name, &records, resources);
+        stats.push(match persisted_meta(&storage, name)? {
+            Some(meta) => tool.with_validation(meta.last_validated_at, meta.drift_detected),
+            None => tool,
+        });
+    }
+    format.emit(&stats, |stats| print_stats(stats, resources))?;
+    Ok(())
+}
+
+fn print_stats(stats: &[ToolStats], resources: bool) {
+    if stats.is_empty() {
+        eprintln!("No recorded calls. Set [server] record_invocations = true to keep them.");
+        return;
+    }
+    const MIB: f64 = 1024.0 * 1024.0;
+    for tool in stats {
+        let drift = if tool.drift_detected {
+            "  [DRIFT DETECTED]"
+        } else {
+            ""
+        };
+        println!(
+            "{}  {} calls, {} errors, p95 {}ms{drift}",
+            tool.tool, tool.calls, tool.errors, tool.p95_duration_ms
+        );
+        if !resources {
+            continue;
+        }
+        match &tool.resources {
+            Some(usage) => {
+                let fuel = match usage.p95_fuel_consumed {
+                    Some(fuel) => fuel.to_string(),
+                    None => "not metered".into(),
+                };
+                println!(
+                    "    memory p95 {:.1} MiB, max {:.1} MiB of {:.0} MiB ({:.0}%); fuel p95 {fuel}; over {} calls",
+                    usage.p95_peak_memory_bytes as f64 / MIB,
+                    usage.max_peak_memory_bytes as f64 / MIB,
+                    usage.memory_limit_bytes as f64 / MIB,
+                    usage.p95_memory_fraction() * 100.0,
+                    usage.calls
+                );
+            }
+            None => println!("    no resource usage recorded"),
+        }
+    }
+}
+
+/// Re-run a recorded call through a fresh runtime, like `girt tools call`.
+async fn run_tools_replay(config_source: ConfigSource, name: &str, index: usize) -> Result<()> {
+    let records = history::load(&InvocationHistory::default_dir()?, name)?;
+    let record = records.iter().rev().nth(index).with_context(|| {
+        format!(
+            "'{name}' has {} recorded call(s), no index {index}",
+            records.len()
+        )
+    })?;
+    eprintln!("Replaying '{name}' with {}", record.arguments);
+    run_tools_call(
+        config_source,
+        name,
+        &record.arguments,
+        &CallOptions::default(),
+    )
+    .await
+}
+
+// ── Approval subcommands ──────────────────────────────────────────────────────
+
+/// Record an operator decision; the proxy that issued the token applies it.
+fn run_resolve(token: &str, resolution: Resolution, format: OutputFormat) -> Result<()> {
+    let approval =
+        approvals::resolve_spooled(&ApprovalStore::default_spool_dir()?, token, resolution)?;
+    let resolved = Resolved {
+        token: approval.token,
+        name: approval.spec.name,
+        resolution,
+    };
+    format.emit(&resolved, |resolved| {
+        let verb = match resolved.resolution {
+            Resolution::Approve => "Approved",
+            Resolution::Reject => "Rejected",
+        };
+        eprintln!(
+            "{verb} '{}'. The proxy will apply this within a few seconds.",
+            resolved.name
+        );
+    })?;
+    Ok(())
+}
+
+fn run_approvals_pending(format: OutputFormat) -> Result<()> {
+    let pending = approvals::list_spooled(&ApprovalStore::default_spool_dir()?)?;
+    format.emit(&pending, |pending| {
+        if pending.is_empty() {
+            eprintln!("No pending approvals.");
+        }
+        for approval in pending {
+            println!("{}  {}", approval.token, approval.spec.name);
+            for line in approval.message(usize::MAX).lines() {
+                println!("    {line}");
+            }
+            let expires = chrono_from_unix(approval.expires_at_ms / 1000);
+            println!("    expires {expires}");
+        }
+    })?;
+    Ok(())
+}
+
+// ── Worker ────────────────────────────────────────────────────────────────────
+
+/// Drain the build queue until SIGINT.
+async fn run_worker(
+    config_source: ConfigSource,
+    concurrency: Option<usize>,
+    poll_interval: Duration,
+) -> Result<()> {
+    let config = config_source.load()?;
+
+    inject_oauth_token_if_needed().await;
+    let llm = config
+        .build_llm_client()
+        .context("Failed to initialize LLM client")?;
+
+    let cache = ToolCache::new(ToolCache::default_path()?);
+    let publisher = Publisher::new(cache).with_collision_policy(config.registry.on_name_collision);
+    publisher.init().await?;
+
+    // Stores each built component where a proxy's `load_persisted` finds it.
+    let runtime = LifecycleManager::with_engine_config(None, &engine_config(&config.runtime))
+        .context("Failed to initialize girt-runtime")?;
+
+    sweep_scratch();
+    let metrics = Arc::new(PipelineMetrics::new());
+    let queue_path = Queue::default_path()?;
+    let queue = Queue::new(queue_path.clone())
+        .with_limits(config.security.size_limits())
+        .with_max_attempts(config.pipeline.max_request_attempts);
+    let mut consumer = QueueConsumer::new(queue, llm, publisher, Arc::clone(&metrics))
+        .with_standards(config.load_coding_standards())
+        .with_caps(config.pipeline.caps.clone())
+        .with_sampling(config.pipeline.sampling)
+        .with_static_checks(config.pipeline.static_checks)
+        .with_blocking_policy(config.pipeline.blocking)
+        .with_wit_version(config.build.wit_version)
+        .with_host_capabilities(host_capabilities())
+        .with_failure_store(
+            FailureStore::new(FailureStore::default_path()?)
+                .with_iteration_sources(config.pipeline.keep_iteration_sources),
+        );
+    // Synced first, so the runtime metadata records where to.
+    if let Some(sync) = ToolSync::from_config(&config.registry) {
+        tracing::info!(repo = %sync.repo_url(), "Tool sync enabled");
+        consumer = consumer.with_hook(Arc::new(sync));
+    }
+    consumer = consumer.with_hook(Arc::new(RuntimeLoader(runtime)));
+
+    let compiler = WasmCompiler::new()
+        .with_wit_version(config.build.wit_version)
+        .with_sandbox(config.build.sandbox)
+        .with_host_capabilities(host_capabilities());
+    let compiler = if config.pipeline.build_cache {
+        compiler
+    } else {
+        compiler.without_cache()
+    };
+    let concurrency = concurrency.unwrap_or(config.pipeline.concurrent_builds);
+    let mut worker = Worker::new(consumer, compiler).with_options(WorkerOptions {
+        concurrency,
+        poll_interval,
+        ..WorkerOptions::default()
+    });
+    if let Some(interval) = config.pipeline.revalidate_interval {
+        let runtime = calling_runtime(Some(&config)).await?;
+        let revalidator =
+            revalidator(Some(&config), runtime, config.pipeline.revalidate_rebuild).await?;
+        tracing::info!(?interval, "Re-validating published tools");
+        worker = worker.with_revalidation(revalidator, interval);
+    }
+
+    tracing::info!(
+        concurrency,
+        ?poll_interval,
+        queue = %queue_path.display(),
+        "GIRT worker started"
+    );
+    let shutdown = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %e, "Cannot listen for Ctrl-C; stop the worker with SIGTERM");
+            std::future::pending::<()>().await;
+        }
+        tracing::info!("Interrupted; finishing in-flight builds");
+    };
+    let summary = worker.run(shutdown).await?;
+
+    tracing::info!(?summary, metrics = ?metrics.snapshot(), "GIRT worker stopped");
+    Ok(())
+}
+
+/// Add a request to the build queue without going through the Creation Gate.
+async fn run_enqueue(
+    config_source: ConfigSource,
+    name: String,
+    description: String,
+    overrides: PipelineOverrides,
+    format: OutputFormat,
+) -> Result<()> {
+    let config = config_source.load()?;
+
+    let spec = CapabilitySpec {
+        name,
+        description,
+        inputs: serde_json::Value::Null,
+        outputs: serde_json::Value::Null,
+        constraints: Default::default(),
+    };
+    enqueue(&config, spec, overrides, format).await
+}
+
+/// Queue `spec` for `girt worker`, printing the request id.
+async fn enqueue(
+    config: &GirtConfig,
+    spec: CapabilitySpec,
+    overrides: PipelineOverrides,
+    format: OutputFormat,
+) -> Result<()> {
+    let capped = overrides.capped(&config.pipeline.caps);
+    if capped != overrides {
+        eprintln!("Some overrides exceed [pipeline.caps] and will be reduced when built.");
+    }
+    let request = CapabilityRequest::new(spec, RequestSource::Cli).with_overrides(overrides);
+
+    let queue = Queue::new(Queue::default_path()?).with_limits(config.security.size_limits());
+    queue.init().await?;
+    let enqueued = match queue.enqueue(&request).await? {
+        EnqueueOutcome::Queued { conflicts } => Enqueued {
+            id: request.id,
+            name: request.spec.name,
+            already_queued: false,
+            conflicts,
+        },
+        EnqueueOutcome::AlreadyQueued { id } => Enqueued {
+            id,
+            name: request.spec.name,
+            already_queued: true,
+            conflicts: vec![],
+        },
+    };
+    format.emit(&enqueued, |enqueued| {
+        println!("{}", enqueued.id);
+        if enqueued.already_queued {
+            eprintln!("'{}' is already queued with this spec.", enqueued.name);
+            return;
+        }
+        eprintln!("Queued '{}'. Run `girt worker` to build it.", enqueued.name);
+        if !enqueued.conflicts.is_empty() {
+            eprintln!(
+                "Also queued under this name with a different spec: {}",
+                enqueued.conflicts.join(", ")
+            );
+        }
+    })?;
+    Ok(())
+}
+
+/// Queue the spec in `spec_path`, or with `dry_run` refine it and print the
+/// report without building.
+async fn run_build(
+    config_source: ConfigSource,
+    spec_path: &Path,
+    dry_run: bool,
+    tier: Option<ResourceTier>,
+    format: OutputFormat,
+) -> Result<()> {
+    let config = config_source.load()?;
+
+    let spec = std::fs::read_to_string(spec_path)
+        .with_context(|| format!("Failed to read {}", spec_path.display()))?;
+    let spec: CapabilitySpec = serde_json::from_str(&spec)
+        .with_context(|| format!("{} is not a capability spec", spec_path.display()))?;
+    let overrides = PipelineOverrides {
+        resource_tier: tier,
+        ..PipelineOverrides::default()
+    };
+    if !dry_run {
+        return enqueue(&config, spec, overrides, format).await;
+    }
+
+    inject_oauth_token_if_needed().await;
+    let llm = config
+        .build_llm_client()
+        .context("Failed to initialize LLM client")?;
+    let request = CapabilityRequest::new(spec, RequestSource::Cli).with_overrides(overrides);
+    let report = Orchestrator::new(llm.as_ref())
+        .with_standards(config.load_coding_standards())
+        .with_sampling(config.pipeline.sampling)
+        .with_static_checks(config.pipeline.static_checks)
+        .with_wit_version(config.build.wit_version)
+        .with_host_capabilities(host_capabilities())
+        .with_overrides(&request.pipeline_overrides.capped(&config.pipeline.caps))
+        .dry_run(&request)
+        .await;
+    format.emit(&report, print_dry_run)?;
+    Ok(())
+}
+
+fn print_dry_run(report: &DryRunReport) {
+    let refined = &report.refined_spec;
+    let spec = &refined.spec;
+    println!("{}  {}", spec.name, spec.description);
+    if refined.action == SpecAction::RecommendExtend {
+        println!(
+            "The Architect recommends extending '{}' instead: {}",
+            refined.extend_target.as_deref().unwrap_or_default(),
+            refined
+                .extend_features
+                .as_deref()
+                .unwrap_or_default()
+                .join("; ")
+        );
+    }
+    let constraints = &spec.constraints;
+    let network: Vec<String> = constraints.network.iter().map(|g| g.to_string()).collect();
+    for (label, entries) in [
+        ("Network", &network),
+        ("Storage", &constraints.storage),
+        ("Secrets", &constraints.secrets),
+    ] {
+        if !entries.is_empty() {
+            println!("{label}: {}", entries.join(", "));
+        }
+    }
+    let limits = &report.policy.resources;
+    println!(
+        "Resource tier: {:?} (memory {} MB, fuel {}, timeout {}s, responses up to {} bytes)",
+        report.resource_tier,
+        limits.memory_mb,
+        limits.fuel,
+        limits.timeout_seconds,
+        limits.max_response_bytes
+    );
+    if let Some(plan) = &report.plan {
+        println!("Plan:");
+        for line in plan.lines() {
+            println!("    {line}");
+        }
+    }
+    let cached = if report.timings.cached {
+        " (cached)"
+    } else {
+        ""
+    };
+    println!(
+        "Architect: {}ms, {} tokens{cached}",
+        report.timings.architect_ms, report.tokens_used
+    );
+}
+
+/// Print the requests in the build queue that have not completed.
+async fn run_queue_list(format: OutputFormat) -> Result<()> {
+    let queue = Queue::new(Queue::default_path()?);
+    queue.init().await?;
+    let requests = queue.requests().await?;
+    format.emit(&requests, |requests| {
+        if requests.is_empty() {
+            eprintln!("The build queue is empty.");
+        }
+        for request in requests {
+            let status = match request.status {
+                RequestStatus::Pending => "pending",
+                RequestStatus::InProgress => "in_progress",
+                RequestStatus::Completed => "completed",
+                RequestStatus::Failed => "failed",
+            };
+            println!(
+                "{}  {status}  {}  attempts: {}",
+                request.id, request.spec.name, request.attempts
+            );
+            if request.status == RequestStatus::Failed {
+                let code = request.last_error_code.as_deref().unwrap_or("unknown");
+                let error = request.last_error.as_deref().unwrap_or_default();
+                println!("    {code}: {error}");
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Print the state a proxy would report, as far as it is on disk.
+async fn run_stats(format: OutputFormat) -> Result<()> {
+    let storage = ComponentStorage::new(ComponentStorage::default_path()?);
+    let mut metas: Vec<ComponentMeta> = storage
+        .list_component_ids()?
+        .iter()
+        .filter_map(|id| storage.load_meta(id).ok())
+        .collect();
+    metas.sort_by(|a, b| a.tool_name.cmp(&b.tool_name));
+    metas.dedup_by(|a, b| a.tool_name == b.tool_name);
+    let mut tools = ToolCounts::default();
+    for meta in &metas {
+        tools.add(meta.deprecated, meta.drift_detected);
+    }
+
+    let queue = Queue::new(Queue::default_path()?);
+    queue.init().await?;
+    let pending = approvals::list_spooled(&ApprovalStore::default_spool_dir()?)?;
+    let state = StateDocument {
+        generated_at_ms: now_ms(),
+        tools,
+        queue: Some(queue.depths().await?),
+        builds: None,
+        decisions: None,
+        approvals: ApprovalBacklog::of(&pending),
+    };
+    format.emit(&state, |state| {
+        let tools = &state.tools;
+        println!(
+            "Tools:     {} persisted ({} deprecated, {} drifted)",
+            tools.loaded, tools.deprecated, tools.drift_detected
+        );
+        if let Some(queue) = &state.queue {
+            println!(
+                "Queue:     {} pending, {} in progress, {} completed, {} failed",
+                queue.pending, queue.in_progress, queue.completed, queue.failed
+            );
+        }
+        println!("Approvals: {} pending", state.approvals.pending);
+    })?;
+    Ok(())
+}
+
+/// Loads each published tool into a runtime, persisting it to the
+/// component store.
+struct RuntimeLoader(LifecycleManager);
+
+impl PublishHook for RuntimeLoader {
+    fn published<'a>(
+        &'a self,
+        artifact: &'a BuildArtifact,
+        result: &'a PublishResult,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            if result.wasm.is_none() {
+                return;
+            }
+            let wasm_path = result.local_path.join("tool.wasm");
+            let meta = located_component_meta(artifact, result).await;
+            if let Err(e) = self.0.load_component(&wasm_path, meta).await {
+                tracing::error!(tool = %artifact.spec.name, error = %e, "Failed to store component");
+            }
+        })
+    }
+}
+
+/// Calls tools for a [`Revalidator`] and records the outcome in their
+/// component metadata.
+struct RuntimeRunner(LifecycleManager);
+
+impl ToolRunner for RuntimeRunner {
+    fn call<'a>(
+        &'a self,
+        name: &'a str,
+        input: &'a serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, String>> + Send + 'a>> {
+        Box::pin(async move {
+            self.0
+                .call_tool(name, input)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn last_validated_at<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Option<u64>> + Send + 'a>> {
+        Box::pin(async move { self.0.tool_meta(name).await?.last_validated_at })
+    }
+
+    fn record<'a>(
+        &'a self,
+        name: &'a str,
+        at: u64,
+        drift_detected: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>> {
+        Box::pin(async move {
+            self.0
+                .update_meta(name, |meta| {
+                    meta.last_validated_at = Some(at);
+                    meta.drift_detected = drift_detected;
+                })
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn refresh<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        // Tools built or rebuilt since the last run replace what is loaded.
+        Box::pin(self.0.load_persisted())
+    }
+}
+
+/// [`component_meta`] plus the locations recorded in the tool's manifest
+/// by an OCI push or tool sync.
+async fn located_component_meta(artifact: &BuildArtifact, result: &PublishResult) -> ComponentMeta {
+    let mut meta = component_meta(artifact, result);
+    match cache::locations(&result.local_path).await {
+        Ok(locations) => {
+            meta.oci_reference = locations.oci_reference.or(meta.oci_reference);
+            meta.source_repo_path = locations.source_repo_path;
+        }
+        Err(e) => {
+            tracing::warn!(tool = %artifact.spec.name, error = %e, "Failed to read tool locations")
+        }
+    }
+    meta
+}
+
+// ── Failures ──────────────────────────────────────────────────────────────────
+
+/// Print the kept failure bundles.
+async fn run_failures_list(format: OutputFormat) -> Result<()> {
+    let store = FailureStore::new(FailureStore::default_path()?);
+    let failures = store.list().await?;
+    format.emit(&failures, |failures| {
+        if failures.is_empty() {
+            eprintln!("No failed builds are kept.");
+        }
+        for failure in failures {
+            println!(
+                "{}  {}  {}  iterations: {}",
+                failure.id,
+                failure.tool_name,
+                failure.failed_at.format("%Y-%m-%d %H:%M:%S"),
+                failure.iterations.len() + failure.omitted_iterations.len()
+            );
+            println!("    {}: {}", failure.error_code, failure.error);
+        }
+    })?;
+    Ok(())
+}
+
+/// Print one failure bundle.
+async fn run_failures_show(id: &str, format: OutputFormat) -> Result<()> {
+    let store = FailureStore::new(FailureStore::default_path()?);
+    let bundle = store
+        .show(id)
+        .await
+        .with_context(|| format!("No failure bundle for '{id}'"))?;
+    let dir = store.dir().join(id);
+    format.emit(&bundle, |bundle| print_failure(bundle, &dir))?;
+    Ok(())
+}
+
+/// Print the change in `src/lib.rs` between two iterations of a build.
+async fn run_failures_diff(id: &str, iters: &[u32], format: OutputFormat) -> Result<()> {
+    let &[from, to] = iters else {
+        anyhow::bail!("--iters takes two iterations, e.g. --iters 1,2");
+    };
+    let store = FailureStore::new(FailureStore::default_path()?);
+    let old = store.iteration_source(id, from).await?;
+    let new = store.iteration_source(id, to).await?;
+    let (old_name, new_name) = (format!("iter-{from}.rs"), format!("iter-{to}.rs"));
+    let diff = IterationDiff {
+        id: id.to_string(),
+        from,
+        to,
+        diff: line_diff(&old, &old_name, &new, &new_name),
+    };
+    format.emit(&diff, |diff| {
+        if diff.diff.is_empty() {
+            eprintln!("Iterations {from} and {to} have the same source.");
+        }
+        print!("{}", diff.diff);
+    })?;
+    Ok(())
+}
+
+fn print_failure(bundle: &FailureBundle, dir: &Path) {
+    let FailureSummary {
+        id,
+        tool_name,
+        error,
+        error_code,
+        failed_at,
+        timings: t,
+        iterations,
+        omitted_iterations,
+    } = &bundle.summary;
+    println!("{id}  {tool_name}  failed {failed_at}");
+    println!("    {error_code}: {error}");
+    println!(
+        "Timings: architect {}ms, engineer {}ms, QA {}ms, red team {}ms, total {}ms",
+        t.architect_ms, t.engineer_ms, t.qa_ms, t.red_team_ms, t.total_ms
+    );
+    println!("Iterations kept: {iterations:?}");
+    if !omitted_iterations.is_empty() {
+        println!("Iterations over the size cap: {omitted_iterations:?}");
+    }
+    for record in &bundle.report.tickets {
+        let ticket = &record.ticket;
+        println!(
+            "  [{:?}] expected: {}, actual: {} (iterations {}-{})",
+            ticket.ticket_type,
+            ticket.expected,
+            ticket.actual,
+            record.first_iteration,
+            record.last_iteration
+        );
+    }
+    if let Some(stderr) = &bundle.report.compile_stderr {
+        println!("Compiler output:\n{stderr}");
+    }
+    println!("Files: {}", dir.display());
+}
+
+// ── Spec ──────────────────────────────────────────────────────────────────────
+
+/// Where `girt spec from-openapi` reads the document from.
+enum OpenApiSource {
+    Url(String),
+    File(PathBuf),
+}
+
+/// Write the spec for `operations` of an OpenAPI document to `output`, or
+/// print it.
+async fn run_spec_from_openapi(
+    source: OpenApiSource,
+    operations: &[String],
+    name: Option<&str>,
+    output: Option<&Path>,
+    format: OutputFormat,
+) -> Result<()> {
+    let document = match &source {
+        OpenApiSource::Url(url) => OpenApiDocument::fetch(url).await?,
+        OpenApiSource::File(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            OpenApiDocument::parse(&text)
+                .with_context(|| format!("{} is not a usable OpenAPI document", path.display()))?
+        }
+    };
+    let spec = document
+        .import(operations, name)
+        .context("Cannot build a capability spec from the document")?;
+
+    let Some(output) = output else {
+        println!("{}", serde_json::to_string_pretty(&spec)?);
+        return Ok(());
+    };
+    std::fs::write(output, serde_json::to_string_pretty(&spec)? + "\n")
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    format.emit(&spec, |spec| {
+        eprintln!("Wrote spec '{}' to {}", spec.name, output.display());
+        let secrets = &spec.constraints.secrets;
+        if !secrets.is_empty() {
+            eprintln!("Secrets it expects: {}", secrets.join(", "));
+        }
+    })?;
+    Ok(())
+}
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+/// Merge and validate the config, then list where each setting came from.
+fn run_config_validate(config_source: ConfigSource, format: OutputFormat) -> Result<()> {
+    let path = config_source
+        .resolve()
+        .context("Failed to locate girt.toml")?;
+    let layers = ConfigLayers::read(&path, config_source.profile.as_ref())
+        .with_context(|| format!("Failed to load config from {}", path.display()))?;
+    layers.config()?;
+
+    format.emit(&layers.effective(), |values| {
+        for value in values {
+            println!(
+                "{} = {}  # {}",
+                value.key,
+                value.value,
+                value.source.display()
+            );
+        }
+        let files: Vec<_> = layers.files().map(|f| f.display().to_string()).collect();
+        eprintln!("Config is valid ({})", files.join(" + "));
+    })?;
+    Ok(())
+}
+
+// ── Doctor ────────────────────────────────────────────────────────────────────
+
+/// Check what `girt serve` and `girt worker` need, without starting either.
+async fn run_doctor(config_source: ConfigSource, format: OutputFormat) -> Result<()> {
+    let mut checks = Vec::new();
+
+    let config = match config_source.resolve() {
+        Ok(path) => match GirtConfig::load(&path, config_source.profile.as_ref()) {
+            Ok(config) => {
+                checks.push(DoctorCheck::pass("config", path.display().to_string()));
+                Some(config)
+            }
+            Err(e) => {
+                let detail = format!("{}: {e}", path.display());
+                checks.push(DoctorCheck::fail("config", detail));
+                None
+            }
+        },
+        Err(e) => {
+            checks.push(DoctorCheck::fail("config", e.to_string()));
+            None
+        }
+    };
+
+    match &config {
+        Some(config) => {
+            inject_oauth_token_if_needed().await;
+            checks.push(match config.build_llm_client() {
+                Ok(llm) => DoctorCheck::pass(
+                    "llm_credentials",
+                    format!("{} ({})", llm.provider(), llm.model()),
+                ),
+                Err(e) => DoctorCheck::fail("llm_credentials", e.to_string()),
+            });
+        }
+        None => checks.push(DoctorCheck::fail(
+            "llm_credentials",
+            "skipped: no usable girt.toml",
+        )),
+    }
+
+    checks.push(
+        match std::process::Command::new("cargo-component")
+            .arg("--version")
+            .output()
+        {
+            Ok(output) if output.status.success() => DoctorCheck::pass(
+                "cargo_component",
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            ),
+            Ok(output) => DoctorCheck::fail(
+                "cargo_component",
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ),
+            Err(e) => DoctorCheck::fail("cargo_component", format!("cargo-component: {e}")),
+        },
+    );
+
+    let runtime_config = config
+        .as_ref()
+        .map(|config| engine_config(&config.runtime))
+        .unwrap_or_default();
+    checks.push(
+        match LifecycleManager::with_engine_config(None, &runtime_config) {
+            Ok(runtime) => DoctorCheck::pass(
+                "runtime",
+                format!("wasmtime engine initialized: {}", runtime.engine_config()),
+            ),
+            Err(e) => DoctorCheck::fail("runtime", e.to_string()),
+        },
+    );
+
+    format.emit(&checks, |checks| {
+        for check in checks {
+            let mark = if check.passed { "✓" } else { "✗" };
+            eprintln!("{mark} {}: {}", check.name, check.detail);
+        }
+    })?;
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} check(s) failed");
+    }
+    Ok(())
+}
+
+// ── Health ────────────────────────────────────────────────────────────────────
+
+/// Probe what a running proxy depends on and report how well it can serve.
+async fn run_health(config_source: ConfigSource, format: OutputFormat) -> Result<()> {
+    let loaded = config_source
+        .resolve()
+        .and_then(|path| Ok((config_source.load_from(&path)?, path)))
+        .map_err(|e| format!("{e:#}"));
+    let config_found = loaded
+        .as_ref()
+        .map(|(_, path)| path.display().to_string())
+        .map_err(Clone::clone);
+    let config = loaded.ok().map(|(config, _)| config);
+    let mut check = HealthCheck::new().with_probe(health::probe(
+        "config",
+        HealthStatus::Unhealthy,
+        move || config_found.clone(),
+    ));
+
+    let data_dir = paths::data_dir();
+    check = match &data_dir {
+        Ok(dir) => check.with_probe(StorageProbe::new("storage", dir)),
+        Err(e) => check.with_probe(health::failed(
+            "storage",
+            HealthStatus::Unhealthy,
+            e.to_string(),
+        )),
+    };
+
+    let runtime_config = config
+        .as_ref()
+        .map(|config| engine_config(&config.runtime))
+        .unwrap_or_default();
+    check = check.with_probe(health::probe(
+        "components",
+        HealthStatus::Unhealthy,
+        move || {
+            LifecycleManager::with_engine_config(None, &runtime_config)
+                .map_err(|e| format!("runtime: {e}"))?;
+            let dir = ComponentStorage::default_path().map_err(|e| e.to_string())?;
+            let stored = ComponentStorage::new(dir)
+                .list_component_ids()
+                .map_err(|e| e.to_string())?;
+            Ok(format!(
+                "runtime initialized; {} component(s) to load",
+                stored.len()
+            ))
+        },
+    ));
+
+    let llm = match &config {
+        Some(config) => {
+            inject_oauth_token_if_needed().await;
+            config.build_llm_client().map_err(|e| e.to_string())
+        }
+        None => Err("skipped: no usable girt.toml".to_string()),
+    };
+    check = match llm {
+        Ok(llm) => {
+            let probe = LlmProbe::new(llm);
+            check.with_probe(match &data_dir {
+                Ok(dir) => probe.with_cache_file(dir.join("health-llm.json")),
+                Err(_) => probe,
+            })
+        }
+        Err(e) => check.with_probe(health::failed("llm", HealthStatus::Degraded, e)),
+    };
+
+    // Without the spool, `girt approve` cannot reach the proxy; only the
+    // agent's approve_capability can, and only for Creation Gate asks with
+    // [security] agent_approvals on.
+    check = match ApprovalStore::default_spool_dir() {
+        Ok(dir) => check.with_probe(
+            StorageProbe::new("approval_spool", dir).with_impact(HealthStatus::Degraded),
+        ),
+        Err(e) => check.with_probe(health::failed(
+            "approval_spool",
+            HealthStatus::Degraded,
+            e.to_string(),
+        )),
+    };
+
+    let report = check.run().await;
+    format.emit(&report, |report| {
+        for check in &report.checks {
+            let mark = match check.status {
+                HealthStatus::Healthy => "✓",
+                HealthStatus::Degraded => "!",
+                HealthStatus::Unhealthy => "✗",
+            };
+            eprintln!("{mark} {}: {}", check.name, check.detail);
+        }
+        eprintln!("GIRT is {}", report.status);
+    })?;
+    if !report.is_serving() {
+        anyhow::bail!("GIRT is unhealthy");
+    }
+    Ok(())
+}
+
+// ── Completions ───────────────────────────────────────────────────────────────
+
+impl CompletionShell {
+    fn completer(self) -> &'static dyn EnvCompleter {
+        match self {
+            Self::Bash => &clap_complete::env::Bash,
+            Self::Zsh => &clap_complete::env::Zsh,
+            Self::Fish => &clap_complete::env::Fish,
+        }
+    }
+}
+
+/// Print `shell`'s registration script. While the user types, the shell
+/// runs `COMPLETE=<shell> girt -- <words>`, which `main` answers through
+/// [`CompleteEnv`].
+fn run_completions(shell: CompletionShell) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    shell
+        .completer()
+        .write_registration("COMPLETE", "girt", "girt", "girt", &mut stdout)?;
+    Ok(())
+}
+
+/// Print the top-level man page, or write one per subcommand to `out_dir`.
+fn run_man(out_dir: Option<&Path>) -> Result<()> {
+    match out_dir {
+        None => clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout().lock())?,
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            clap_mangen::generate_to(Cli::command(), dir)
+                .with_context(|| format!("Failed to write man pages to {}", dir.display()))?;
+            eprintln!("Wrote man pages to {}", dir.display());
+        }
+    }
+    Ok(())
+}
+
+/// Persisted tool names with their descriptions, for completing `girt
+/// tools` arguments. Empty when the component metadata cannot be read.
+fn tool_name_candidates() -> Vec<CompletionCandidate> {
+    let Ok(dir) = ComponentStorage::default_path() else {
+        return Vec::new();
+    };
+    persisted_tools(&ComponentStorage::new(dir))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|meta| CompletionCandidate::new(meta.tool_name).help(Some(meta.description.into())))
+        .collect()
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Parse a CLI value the way girt.toml spells it, e.g. `assemblyscript`.
+fn parse_snake_case<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown value '{value}'"))
+}
+
+/// `GIRT_DEV_TOOLS=1`: load every bundled component on start.
+fn dev_tools_requested() -> bool {
+    std::env::var("GIRT_DEV_TOOLS").is_ok_and(|value| value == "1")
+}
+
+/// Load the bundled components for `GIRT_DEV_TOOLS=1`. Only debug builds
+/// do; a release build says why it ignores the variable.
+async fn load_dev_tools(runtime: &LifecycleManager) {
+    if !cfg!(debug_assertions) {
+        tracing::warn!(
+            "GIRT_DEV_TOOLS is ignored in release builds; use `girt tools add-builtin` instead"
+        );
+        return;
+    }
+    for component in bundled::ALL {
+        match runtime.load_bundled(component.meta()).await {
+            Ok(component_id) => tracing::info!(component_id, "Dev tool loaded"),
+            Err(e) => {
+                tracing::warn!(tool = component.tool_name, error = %e, "Cannot load dev tool")
+            }
+        }
+    }
+}
+
+/// Remove scratch dirs left behind by earlier runs.
+fn sweep_scratch() {
+    match Scratch::default().sweep(scratch::STALE_AFTER) {
+        Ok(0) => {}
+        Ok(removed) => tracing::info!(removed, "Removed stale scratch directories"),
+        Err(e) => tracing::warn!(error = %e, "Cannot sweep scratch directories"),
+    }
+}
+
+/// The Wasmtime engine settings in `[runtime]`.
+fn engine_config(runtime: &RuntimeConfig) -> EngineConfig {
+    EngineConfig {
+        pooling_allocator: runtime.pooling_allocator,
+        max_memory_mb: runtime.max_memory_mb,
+        max_instances: runtime.max_instances,
+        epoch_tick_ms: runtime.epoch_tick_ms,
+        native_unwind_info: runtime.native_unwind_info,
+        fuel_metering: runtime.fuel_metering,
+    }
+}
+
+/// Decision audit log location: `audit.jsonl` in the data directory.
+fn audit_log_path() -> Result<PathBuf, PathError> {
+    Ok(paths::data_dir()?.join("audit.jsonl"))
+}
+
+/// Where girt.toml comes from: `--config` (or the default search) plus the
+/// overlay of `--profile` / `GIRT_PROFILE`, if one is selected.
+struct ConfigSource {
+    path: Option<PathBuf>,
+    profile: Option<Profile>,
+}
+
+impl ConfigSource {
+    fn from_cli(cli: &Cli) -> Result<Self> {
+        let name = cli.profile.clone().or_else(|| {
+            std::env::var("GIRT_PROFILE")
+                .ok()
+                .filter(|name| !name.is_empty())
+        });
+        let profile = name
+            .map(|name| Profile::new(name).map(|p| p.with_optional(cli.profile_optional)))
+            .transpose()?;
+        Ok(Self {
+            path: cli.config.clone(),
+            profile,
+        })
+    }
+
+    /// The base girt.toml.
+    fn resolve(&self) -> Result<PathBuf> {
+        resolve_config(self.path.clone())
+    }
+
+    /// Locate and load the config, with the profile's overlay merged in.
+    fn load(&self) -> Result<GirtConfig> {
+        let path = self.resolve().context("Failed to locate girt.toml")?;
+        self.load_from(&path)
+    }
+
+    fn load_from(&self, path: &Path) -> Result<GirtConfig> {
+        GirtConfig::load(path, self.profile.as_ref())
+            .with_context(|| format!("Failed to load config from {}", path.display()))
+    }
+}
+
+/// Resolve config path using standard search order:
+/// 1. Explicit --config flag
+/// 2. ./girt.toml (relative to cwd)
+/// 3. girt.toml in the config directory (user-level installation;
+///    `~/.config/girt` unless `GIRT_HOME` or `XDG_CONFIG_HOME` is set)
+fn resolve_config(explicit: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    if let Some(p) = explicit {
+        return Ok(p);
+    }
+    let local = PathBuf::from("girt.toml");
+    if local.exists() {
+        return Ok(local);
+    }
+    let user_config = match paths::config_dir() {
+        Ok(dir) => dir.join("girt.toml"),
+        Err(e) => anyhow::bail!("No ./girt.toml found, and {e}"),
+    };
+    if user_config.exists() {
+        return Ok(user_config);
+    }
+    anyhow::bail!(
+        "No girt.toml found. Looked in: ./girt.toml and {config}\n\
+         Run from the girt repo directory, or copy girt.toml to {config}",
+        config = user_config.display()
+    )
+}
+
+/// Format a Unix timestamp for human display (no chrono dep needed — manual).
+fn chrono_from_unix(unix_secs: u64) -> String {
+    use std::time::{Duration, UNIX_EPOCH};
+    let dt = UNIX_EPOCH + Duration::from_secs(unix_secs);
+    match dt.duration_since(std::time::SystemTime::now()) {
+        Ok(remaining) => {
+            let mins = remaining.as_secs() / 60;
+            if mins < 60 {
+                format!("in {mins} minutes")
+            } else {
+                let hours = mins / 60;
+                format!("in {hours}h {}m", mins % 60)
+            }
+        }
+        Err(_) => "already expired".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn completions_register_for_each_shell() {
+        for shell in CompletionShell::value_variants() {
+            let mut script = Vec::new();
+            shell
+                .completer()
+                .write_registration("COMPLETE", "girt", "girt", "girt", &mut script)
+                .unwrap();
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains("COMPLETE"), "{script}");
+        }
+    }
+
+    #[test]
+    fn completions_offer_subcommands_and_shells() {
+        let complete = |words: &[&str]| -> Vec<String> {
+            let args = words.iter().map(std::ffi::OsString::from).collect();
+            clap_complete::engine::complete(&mut Cli::command(), args, words.len() - 1, None)
+                .unwrap()
+                .iter()
+                .map(|c| c.get_value().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert!(complete(&["girt", "tools", "ca"]).contains(&"call".to_string()));
+        assert_eq!(complete(&["girt", "completions", "f"]), ["fish"]);
+        assert_eq!(complete(&["girt", "tools", "add-builtin", "e"]), ["echo"]);
+    }
+
+    #[test]
+    fn man_page_renders() {
+        let mut page = Vec::new();
+        clap_mangen::Man::new(Cli::command())
+            .render(&mut page)
+            .unwrap();
+        assert!(String::from_utf8(page).unwrap().contains("girt"));
     }
 }
@@ -0,0 +1,168 @@
+//! Connected MCP clients, for notifications the proxy sends on its own.
+//!
+//! Each connection registers its peer in `initialize` under a fresh id. A
+//! peer is dropped once its transport has closed or a notification to it
+//! fails, so a reconnecting client no longer replaces the other connections
+//! and a dead one no longer makes every `tools/list_changed` fail.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rmcp::{Peer, RoleServer};
+
+/// Live client connections, keyed by connection id.
+#[derive(Default)]
+pub struct PeerRegistry {
+    next_id: AtomicU64,
+    peers: Mutex<HashMap<u64, Peer<RoleServer>>>,
+}
+
+impl PeerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a newly initialized connection, returning its id.
+    pub fn register(&self, peer: Peer<RoleServer>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.lock().insert(id, peer);
+        tracing::debug!(connection = id, "Client connected");
+        id
+    }
+
+    /// Forget connection `id`. Returns whether it was registered.
+    pub fn remove(&self, id: u64) -> bool {
+        self.lock().remove(&id).is_some()
+    }
+
+    /// Number of connections whose transport is still open.
+    pub fn connected(&self) -> usize {
+        self.live().len()
+    }
+
+    /// Send `tools/list_changed` to every live connection, dropping those
+    /// the send fails for. Returns how many were notified.
+    pub async fn notify_tools_changed(&self) -> usize {
+        let mut notified = 0;
+        for (id, peer) in self.live() {
+            match peer.notify_tool_list_changed().await {
+                Ok(()) => notified += 1,
+                Err(e) => {
+                    tracing::warn!(connection = id, error = %e, "Dropping client after failed notification");
+                    self.remove(id);
+                }
+            }
+        }
+        notified
+    }
+
+    /// Drop connections whose transport has closed, and return the rest.
+    fn live(&self) -> Vec<(u64, Peer<RoleServer>)> {
+        let mut peers = self.lock();
+        peers.retain(|id, peer| {
+            let open = !peer.is_transport_closed();
+            if !open {
+                tracing::debug!(connection = id, "Client disconnected");
+            }
+            open
+        });
+        let mut live: Vec<_> = peers.iter().map(|(id, peer)| (*id, peer.clone())).collect();
+        live.sort_by_key(|(id, _)| *id);
+        live
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Peer<RoleServer>>> {
+        self.peers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    use rmcp::service::{NotificationContext, RunningService};
+    use rmcp::{ClientHandler, RoleClient, ServerHandler, ServiceExt};
+
+    use super::*;
+
+    /// A server with no tools; only its peer handle is used.
+    struct Server;
+
+    impl ServerHandler for Server {}
+
+    /// A client counting the `tools/list_changed` notifications it gets.
+    #[derive(Clone, Default)]
+    struct Client {
+        notifications: Arc<AtomicUsize>,
+    }
+
+    impl ClientHandler for Client {
+        async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+            self.notifications.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Connect a client over an in-memory pipe and register the server's
+    /// end of it.
+    async fn connect(
+        registry: &PeerRegistry,
+    ) -> (RunningService<RoleClient, Client>, Arc<AtomicUsize>) {
+        let (server_io, client_io) = tokio::io::duplex(4096);
+        let server = tokio::spawn(async move { Server.serve(server_io).await.unwrap() });
+        let client = Client::default();
+        let notifications = Arc::clone(&client.notifications);
+        let client = client.serve(client_io).await.unwrap();
+        let server = server.await.unwrap();
+        registry.register(server.peer().clone());
+        // Keep the server loop running after `server` goes out of scope.
+        tokio::spawn(server.waiting());
+        (client, notifications)
+    }
+
+    async fn wait_for(count: &AtomicUsize, expected: usize) {
+        for _ in 0..100 {
+            if count.load(Ordering::SeqCst) == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "expected {expected} notifications, got {}",
+            count.load(Ordering::SeqCst)
+        );
+    }
+
+    #[tokio::test]
+    async fn notifications_reach_every_client() {
+        let registry = PeerRegistry::new();
+        let (_first, first_count) = connect(&registry).await;
+        let (_second, second_count) = connect(&registry).await;
+        assert_eq!(registry.connected(), 2);
+
+        assert_eq!(registry.notify_tools_changed().await, 2);
+        wait_for(&first_count, 1).await;
+        wait_for(&second_count, 1).await;
+    }
+
+    #[tokio::test]
+    async fn disconnected_clients_are_pruned() {
+        let registry = PeerRegistry::new();
+        let (first, _) = connect(&registry).await;
+        let (_second, second_count) = connect(&registry).await;
+
+        first.cancel().await.unwrap();
+        for _ in 0..100 {
+            if registry.connected() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(registry.connected(), 1);
+
+        assert_eq!(registry.notify_tools_changed().await, 1);
+        wait_for(&second_count, 1).await;
+    }
+}
@@ -1,37 +1,148 @@
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use girt_core::decision::{Decision, GateKind};
+use girt_core::decision::{Decision, DecisionLayer, DeferTarget, GateKind, LayeredDecision};
 use girt_core::engine::DecisionEngine;
-use girt_core::spec::{CapabilitySpec, ExecutionRequest, GateInput};
+use girt_core::error::SizeLimitError;
+use girt_core::layers::similarity::KnownSpec;
+use girt_core::limits::{SizeLimits, Truncation};
+use girt_core::reserved::BUILTIN_TOOL_NAMES;
+use girt_core::spec::{
+    CapabilitySpec, ExecutionRequest, GateInput, RawCapabilitySpec, SpecDiff, ToolProfile,
+};
+use girt_core::usage::ResourceUsage;
+use girt_pipeline::compiler::{CompileInput, WasmCompiler};
+use girt_pipeline::config::{
+    BuildConfig, PipelineCaps, PipelineConfig, SamplingConfig, SecurityConfig, ServerConfig,
+    ToolsConfig,
+};
+use girt_pipeline::error::PipelineError;
+use girt_pipeline::failures::{FailureReport, FailureStore};
 use girt_pipeline::llm::LlmClient;
 use girt_pipeline::orchestrator::{Orchestrator, PipelineOutcome};
-use girt_pipeline::publish::Publisher;
-use girt_pipeline::types::{CapabilityRequest, RequestSource};
-use girt_runtime::{ComponentMeta, LifecycleManager};
+use girt_pipeline::publish::{PublishResult, Publisher};
+use girt_pipeline::queue::Queue;
+use girt_pipeline::static_checks::StaticChecks;
+use girt_pipeline::stdlib::standard_library;
+use girt_pipeline::tool_sync::ToolSync;
+use girt_pipeline::types::{
+    BlockingPolicy, BuildArtifact, CapabilityRequest, CodingStandards, PipelineOverrides,
+    PolicyYaml, RequestSource, WitVersion,
+};
+use girt_runtime::{CallOptions, ComponentMeta, LifecycleManager, RuntimeError, host_capabilities};
+use girt_secrets::store::{EnvSecretStore, SecretStore};
 use rmcp::{
-    ErrorData as McpError, Peer, RoleServer, ServerHandler,
+    ErrorData as McpError, RoleServer, ServerHandler,
     model::{
-        CallToolRequestParams, CallToolResult, CompleteRequestParams, CompleteResult, Content,
-        GetPromptRequestParams, GetPromptResult, InitializeRequestParams, InitializeResult,
-        ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, ListToolsResult,
-        PaginatedRequestParams, ReadResourceRequestParams, ReadResourceResult, ServerCapabilities,
-        ServerInfo, Tool,
+        AnnotateAble, CallToolRequestParams, CallToolResult, CompleteRequestParams, CompleteResult,
+        Content, ErrorCode, GetPromptRequestParams, GetPromptResult, InitializeRequestParams,
+        InitializeResult, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        ListToolsResult, Meta, PaginatedRequestParams, RawResource, ReadResourceRequestParams,
+        ReadResourceResult, ResourceContents, ServerCapabilities, ServerInfo, Tool,
     },
     service::RequestContext,
 };
-use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tracing::Instrument;
+
+use crate::approvals::{
+    ApprovalError, ApprovalRequest, ApprovalStore, DEFAULT_TTL, MAX_MESSAGE_CHARS, PendingApproval,
+    PendingCall, Resolution,
+};
+use crate::batch::{self, BATCH_TOOL, BatchCall, BatchMode, BatchRequest, CallOutcome, batch_tool};
+use crate::denials::RecentDenials;
+use crate::extend::{EXTEND_TOOL, ExtendRequest, Extension, extend_capability_tool};
+use crate::history::InvocationHistory;
+use crate::inflight::{BuildPhase, InFlight, InFlightBuilds};
+use crate::peers::PeerRegistry;
+use crate::quarantine;
+use crate::request_log::{RequestLog, RequestRecord, RequestTrace, argument_sizes, now_ms};
+use crate::schema::validate_arguments;
+use crate::services::{LIST_SERVICES_TOOL, ServiceListing, list_services_tool};
+use crate::state::{ApprovalBacklog, DecisionCounts, STATE_URI, StateDocument, ToolCounts};
 
 /// MCP proxy that routes agent requests through the Hookwise decision engine
 /// and executes approved tool calls via the embedded girt-runtime (ADR-010).
+#[derive(Clone)]
 pub struct GirtProxy {
     engine: Arc<DecisionEngine>,
     llm: Arc<dyn LlmClient>,
     publisher: Arc<Publisher>,
     runtime: Arc<LifecycleManager>,
     /// Coding standards injected into the Engineer's system prompt.
-    coding_standards: Option<String>,
-    /// Server peer for sending tools/list_changed notifications.
-    server_peer: Arc<Mutex<Option<Peer<RoleServer>>>>,
+    coding_standards: CodingStandards,
+    /// Mirrors published tools into `registry.source_repo`, when configured.
+    tool_sync: Option<Arc<ToolSync>>,
+    /// Compiles generated source into a WASM component.
+    compiler: Arc<WasmCompiler>,
+    /// Capability requests parked on an `Ask`, keyed by approval token.
+    approvals: Arc<ApprovalStore>,
+    /// Attach JSON payloads as `structured_content` (`[server] structured_results`).
+    structured_results: bool,
+    /// Wrap runtime tool results in an envelope (`[server] result_envelope`).
+    result_envelope: bool,
+    /// Attach the layer cascade trace to decisions (`[security] include_decision_trace`).
+    include_decision_trace: bool,
+    /// Let `approve_capability` answer Creation Gate asks
+    /// (`[security] agent_approvals`).
+    agent_approvals: bool,
+    /// Confidence an LLM gate Allow needs (`[security] min_allow_confidence`),
+    /// reported by `girt_info`.
+    min_allow_confidence: f64,
+    /// Check arguments against the tool's input schema (`[server] validate_arguments`).
+    validate_arguments: bool,
+    /// Spec and argument size caps (`[security] max_*`).
+    limits: SizeLimits,
+    /// Extra attempts for idempotent tools on transient errors (`[server] tool_retry_attempts`).
+    tool_retry_attempts: u32,
+    /// Tools per `tools/list` page (`[server] tools_page_size`); 0 disables paging.
+    tools_page_size: usize,
+    /// Most calls in one `girt_batch` (`[server] batch_max_calls`).
+    batch_max_calls: usize,
+    /// Calls of a parallel `girt_batch` run at once (`[server] batch_parallelism`).
+    batch_parallelism: usize,
+    /// Report each call's resource usage (`[server] include_resource_usage`).
+    include_resource_usage: bool,
+    /// How long deprecated tools stay callable (`[tools] deprecation_grace`).
+    deprecation_grace: Duration,
+    /// JSONL log of handled requests (`[server] request_log`).
+    request_log: Option<Arc<RequestLog>>,
+    /// Recent calls per tool (`[server] record_invocations`).
+    history: Option<Arc<InvocationHistory>>,
+    /// Why recent capability requests were denied, by spec name.
+    recent_denials: Arc<RecentDenials>,
+    /// Recent gate decisions, for the `girt://state` document.
+    decisions: Arc<DecisionCounts>,
+    /// Bounds on per-request pipeline overrides (`[pipeline.caps]`).
+    pipeline_caps: PipelineCaps,
+    /// Per-agent sampling settings (`[pipeline.sampling]`).
+    sampling: SamplingConfig,
+    /// Checks on Engineer output (`[pipeline.static_checks]`).
+    static_checks: StaticChecks,
+    /// Which tickets fail a build (`[pipeline.blocking]`).
+    blocking: BlockingPolicy,
+    /// `girt:tool` world new tools are built against (`[build] wit_version`).
+    wit_version: WitVersion,
+    /// Permits for builds run at once (`[pipeline] concurrent_builds`).
+    /// Requests beyond that wait for a running build to finish.
+    build_slots: Arc<tokio::sync::Semaphore>,
+    /// Connected clients, for tools/list_changed notifications.
+    peers: Arc<PeerRegistry>,
+    /// Where failed builds leave their bundles; none are kept when unset.
+    failures: Option<Arc<FailureStore>>,
+    /// Builds running or waiting for a slot, so a repeated request for the
+    /// same spec is not built twice.
+    in_flight: Arc<InFlightBuilds>,
+    /// `girt worker`'s queue, checked for identical requests pending or in
+    /// progress there.
+    build_queue: Option<Arc<Queue>>,
+    /// Credentials tools authenticate with, for `girt_list_services`.
+    secrets: Arc<dyn SecretStore>,
+    /// Tools loaded in the runtime as of the last tool list change, for the
+    /// synchronous [`ServerHandler::get_info`].
+    loaded_tools: Arc<AtomicUsize>,
 }
 
 impl GirtProxy {
@@ -40,7 +151,8 @@ impl GirtProxy {
         llm: Arc<dyn LlmClient>,
         publisher: Arc<Publisher>,
         runtime: Arc<LifecycleManager>,
-        coding_standards: Option<String>,
+        coding_standards: CodingStandards,
+        tool_sync: Option<Arc<ToolSync>>,
     ) -> Self {
         Self {
             engine,
@@ -48,27 +160,273 @@ impl GirtProxy {
             publisher,
             runtime,
             coding_standards,
-            server_peer: Arc::new(Mutex::new(None)),
+            tool_sync,
+            compiler: Arc::new(WasmCompiler::new()),
+            approvals: Arc::new(ApprovalStore::new(DEFAULT_TTL)),
+            structured_results: true,
+            result_envelope: false,
+            include_decision_trace: false,
+            agent_approvals: false,
+            min_allow_confidence: SecurityConfig::default().min_allow_confidence,
+            validate_arguments: true,
+            limits: SizeLimits::default(),
+            tool_retry_attempts: 2,
+            tools_page_size: 50,
+            batch_max_calls: ServerConfig::default().batch_max_calls,
+            batch_parallelism: ServerConfig::default().batch_parallelism,
+            include_resource_usage: false,
+            deprecation_grace: ToolsConfig::default().deprecation_grace,
+            request_log: None,
+            history: None,
+            recent_denials: Arc::new(RecentDenials::default()),
+            decisions: Arc::new(DecisionCounts::default()),
+            pipeline_caps: PipelineCaps::default(),
+            sampling: SamplingConfig::default(),
+            static_checks: StaticChecks::default(),
+            blocking: BlockingPolicy::default(),
+            wit_version: WitVersion::default(),
+            build_slots: Arc::new(tokio::sync::Semaphore::new(
+                PipelineConfig::default().concurrent_builds,
+            )),
+            peers: Arc::new(PeerRegistry::new()),
+            failures: None,
+            in_flight: Arc::new(InFlightBuilds::default()),
+            build_queue: None,
+            secrets: Arc::new(EnvSecretStore::new()),
+            loaded_tools: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Apply `[server]` settings from girt.toml.
+    pub fn with_server_config(mut self, config: &ServerConfig) -> Self {
+        self.structured_results = config.structured_results;
+        self.result_envelope = config.result_envelope;
+        self.validate_arguments = config.validate_arguments;
+        self.tool_retry_attempts = config.tool_retry_attempts;
+        self.tools_page_size = config.tools_page_size;
+        self.batch_max_calls = config.batch_max_calls;
+        self.batch_parallelism = config.batch_parallelism;
+        self.include_resource_usage = config.include_resource_usage;
+        self
+    }
+
+    /// Apply `[security]` settings from girt.toml.
+    pub fn with_security_config(mut self, config: &SecurityConfig) -> Self {
+        self.include_decision_trace = config.include_decision_trace;
+        self.agent_approvals = config.agent_approvals;
+        self.min_allow_confidence = config.min_allow_confidence;
+        self.limits = config.size_limits();
+        self
+    }
+
+    /// Apply `[pipeline]` settings from girt.toml.
+    pub fn with_pipeline_config(mut self, config: &PipelineConfig) -> Self {
+        self.pipeline_caps = config.caps.clone();
+        self.sampling = config.sampling;
+        self.static_checks = config.static_checks;
+        self.blocking = config.blocking;
+        self.build_slots = Arc::new(tokio::sync::Semaphore::new(config.concurrent_builds.max(1)));
+        self
+    }
+
+    /// Apply `[build]` settings from girt.toml. The compiler passed to
+    /// [`Self::with_compiler`] should scaffold the same `wit_version`.
+    pub fn with_build_config(mut self, config: &BuildConfig) -> Self {
+        self.wit_version = config.wit_version;
+        self
+    }
+
+    /// Apply `[tools]` settings from girt.toml.
+    pub fn with_tools_config(mut self, config: &ToolsConfig) -> Self {
+        self.deprecation_grace = config.deprecation_grace;
+        self
+    }
+
+    /// Compile built tools with `compiler` instead of the default
+    /// `cargo-component` on PATH.
+    pub fn with_compiler(mut self, compiler: WasmCompiler) -> Self {
+        self.compiler = Arc::new(compiler);
+        self
+    }
+
+    /// Park `Ask`ed capability requests in `store` (see [`ApprovalStore`]).
+    pub fn with_approvals(mut self, store: ApprovalStore) -> Self {
+        self.approvals = Arc::new(store);
+        self
+    }
+
+    /// Append a line per handled request to `log`.
+    pub fn with_request_log(mut self, log: RequestLog) -> Self {
+        self.request_log = Some(Arc::new(log));
+        self
+    }
+
+    /// Keep each tool's recent calls in `history`.
+    pub fn with_invocation_history(mut self, history: InvocationHistory) -> Self {
+        self.history = Some(Arc::new(history));
+        self
+    }
+
+    /// Use `store` for `girt_list_services` instead of the environment.
+    pub fn with_secret_store(mut self, store: Arc<dyn SecretStore>) -> Self {
+        self.secrets = store;
+        self
+    }
+
+    /// Keep a bundle of each failed build in `store`.
+    pub fn with_failure_store(mut self, store: FailureStore) -> Self {
+        self.failures = Some(Arc::new(store));
+        self
+    }
+
+    /// Answer requests for a spec already pending or in progress in
+    /// `queue` with that request instead of building it again.
+    pub fn with_build_queue(mut self, queue: Queue) -> Self {
+        self.build_queue = Some(Arc::new(queue));
+        self
+    }
+
+    /// Record Creation Gate denials in `denials`, shared with the gate's
+    /// LLM evaluator (see [`GateLlmEvaluator::with_recent_denials`](crate::evaluator::GateLlmEvaluator::with_recent_denials)).
+    pub fn with_recent_denials(mut self, denials: Arc<RecentDenials>) -> Self {
+        self.recent_denials = denials;
+        self
+    }
+
+    /// Clients currently connected to this proxy, across all transports.
+    pub fn connected_clients(&self) -> usize {
+        self.peers.connected()
+    }
+
+    /// Recount the tools loaded in the runtime for the `initialize`
+    /// instructions.
+    pub async fn refresh_status(&self) {
+        let loaded = self.runtime.list_tools().await.len();
+        self.loaded_tools.store(loaded, Ordering::Relaxed);
+    }
+
+    /// The `initialize` instructions: what GIRT is, followed by the state an
+    /// agent should plan around, e.g. "Loaded tools: 12. Creation gate:
+    /// llm+hitl. Build budget: up to 5 iterations, 200000 tokens."
+    fn instructions(&self) -> String {
+        let approvers = match (self.agent_approvals, self.approvals.is_spooled()) {
+            (true, true) => "approve_capability or the operator's `girt approve`",
+            (true, false) => "approve_capability",
+            (false, _) => "the operator's `girt approve`",
+        };
+        format!(
+            "GIRT MCP Proxy -- Generative Isolated Runtime for Tools\n\n\
+             Loaded tools: {loaded}. Creation gate: {mode}+hitl; requests it asks about \
+             wait for {approvers}. Tool calls the execution gate asks about, and anything \
+             an always_ask rule matches, wait for the operator's `girt approve`. \
+             Build budget: up to {iterations} iterations, {tokens} tokens. \
+             request_capability returns once the tool is built and loaded. \
+             Call girt_info for the gate's rules before requesting a tool.",
+            loaded = self.loaded_tools.load(Ordering::Relaxed),
+            mode = self.creation_gate_mode(),
+            iterations = self.pipeline_caps.max_iterations,
+            tokens = self.pipeline_caps.token_budget,
+        )
+    }
+
+    /// How the Creation Gate's LLM layer decides: `llm`, or `stub` when no
+    /// provider is configured.
+    fn creation_gate_mode(&self) -> &'static str {
+        if self.llm.provider() == "stub" {
+            "stub"
+        } else {
+            "llm"
+        }
+    }
+
+    fn json_result(&self, value: serde_json::Value, is_error: bool) -> CallToolResult {
+        make_tool_result(value, is_error, self.structured_results)
+    }
+
+    /// Render a gate result, adding the layer trace when configured or when
+    /// debug logging is on.
+    fn gate_result_json(&self, gate_result: &LayeredDecision) -> serde_json::Value {
+        let mut value = decision_to_json(&gate_result.decision);
+        if self.include_decision_trace || tracing::enabled!(tracing::Level::DEBUG) {
+            value["trace"] =
+                serde_json::to_value(&gate_result.consulted_layers).unwrap_or_default();
+        }
+        value
+    }
+
+    /// Render a Defer result with what the agent can do instead. For
+    /// `extend_tool` this looks up the referenced tool in the runtime, so
+    /// the agent gets its input schema without another round trip.
+    async fn deferral_json(
+        &self,
+        gate_result: &LayeredDecision,
+        target: &DeferTarget,
+    ) -> serde_json::Value {
+        let meta = match target {
+            DeferTarget::ExtendTool { tool_name, .. } => self.runtime.tool_meta(tool_name).await,
+            _ => None,
+        };
+        let mut value = self.gate_result_json(gate_result);
+        for (key, field) in deferral_details(target, meta.as_ref()) {
+            value[key] = field;
         }
+        value
     }
 }
 
+/// How often the approval spool is checked for CLI resolutions.
+const APPROVAL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Whether `name` is a tool implemented by the proxy itself. These are
+/// dispatched before the runtime lookup and never reach the Execution
+/// Gate's unknown-tool check.
+fn is_builtin_tool(name: &str) -> bool {
+    BUILTIN_TOOL_NAMES.contains(&name)
+}
+
+/// The built-in tools, as listed by `tools/list`.
+fn builtin_tools(batch_max_calls: usize) -> Vec<Tool> {
+    vec![
+        request_capability_tool(),
+        tool_info_tool(),
+        info_tool(),
+        approve_capability_tool(),
+        batch_tool(batch_max_calls),
+        list_services_tool(),
+        extend_capability_tool(),
+    ]
+}
+
 fn girt_capabilities() -> ServerCapabilities {
     ServerCapabilities {
         tools: Some(Default::default()),
+        resources: Some(Default::default()),
         ..Default::default()
     }
 }
 
-fn girt_info() -> InitializeResult {
+fn girt_info(instructions: String) -> InitializeResult {
     InitializeResult {
         protocol_version: Default::default(),
         capabilities: girt_capabilities(),
         server_info: rmcp::model::Implementation::from_build_env(),
-        instructions: Some("GIRT MCP Proxy -- Generative Isolated Runtime for Tools".into()),
+        instructions: Some(instructions),
     }
 }
 
+/// The `girt://state` resource, as listed.
+fn state_resource() -> rmcp::model::Resource {
+    let mut resource = RawResource::new(STATE_URI, "state");
+    resource.title = Some("GIRT state".into());
+    resource.description = Some(
+        "Loaded tools, build queue depths, running builds and their phase, recent gate \
+         decisions by outcome, and the approval backlog, as JSON."
+            .into(),
+    );
+    resource.mime_type = Some("application/json".into());
+    resource.no_annotation()
+}
+
 /// Build the JSON schema for the request_capability tool.
 fn request_capability_tool() -> Tool {
     let schema = serde_json::json!({
@@ -98,6 +456,25 @@ fn request_capability_tool() -> Tool {
                     "storage": { "type": "array", "items": { "type": "string" } },
                     "secrets": { "type": "array", "items": { "type": "string" } }
                 }
+            },
+            "force_rebuild": {
+                "type": "boolean",
+                "description": "Build even if a loaded tool already covers this spec"
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Return the refined spec, plan, resource tier and policy the build would use, without building"
+            },
+            "pipeline": {
+                "type": "object",
+                "description": "Optional build budget for this tool, bounded by the operator's caps",
+                "properties": {
+                    "max_iterations": { "type": "integer", "minimum": 1 },
+                    "target_language": { "type": "string", "enum": ["rust", "go", "assemblyscript"] },
+                    "resource_tier": { "type": "string", "enum": ["minimal", "standard", "extended"] },
+                    "token_budget": { "type": "integer", "minimum": 1 }
+                },
+                "additionalProperties": false
             }
         },
         "required": ["name", "description"]
@@ -121,37 +498,257 @@ fn request_capability_tool() -> Tool {
     }
 }
 
-/// Format a decision into MCP-compatible content.
-fn decision_to_content(decision: &Decision) -> Vec<Content> {
-    let json = match decision {
-        Decision::Allow => serde_json::json!({
-            "status": "allowed",
-            "message": "Request approved"
-        }),
-        Decision::Deny { reason } => serde_json::json!({
-            "status": "denied",
-            "reason": reason
-        }),
-        Decision::Defer { target } => serde_json::json!({
-            "status": "deferred",
-            "target": target
-        }),
-        Decision::Ask { prompt, context } => serde_json::json!({
-            "status": "ask",
-            "prompt": prompt,
-            "context": context
-        }),
+/// Build the JSON schema for the girt_tool_info tool.
+fn tool_info_tool() -> Tool {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "name": {
+                "type": "string",
+                "description": "MCP name of a loaded GIRT-built tool"
+            }
+        },
+        "required": ["name"]
+    });
+
+    Tool {
+        name: "girt_tool_info".into(),
+        title: None,
+        description: Some(
+            "Show metadata for a GIRT-built tool: component id, build time, \
+             WASM integrity (SHA-256 and size), and where its artifact and \
+             source were published."
+                .into(),
+        ),
+        input_schema: schema.as_object().cloned().unwrap_or_default().into(),
+        output_schema: None,
+        annotations: None,
+        execution: None,
+        icons: None,
+        meta: None,
+    }
+}
+
+/// Build the JSON schema for the girt_info tool.
+fn info_tool() -> Tool {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {}
+    });
+
+    Tool {
+        name: "girt_info".into(),
+        title: None,
+        description: Some(
+            "Describe how this GIRT proxy decides and builds: the Creation Gate's \
+             deny rules and constraint budget, build budgets and languages, whether \
+             approvals are available, the standard library tools, and how many \
+             tools are loaded. Read this before calling request_capability."
+                .into(),
+        ),
+        input_schema: schema.as_object().cloned().unwrap_or_default().into(),
+        output_schema: None,
+        annotations: None,
+        execution: None,
+        icons: None,
+        meta: None,
+    }
+}
+
+/// Build the JSON schema for the approve_capability tool.
+fn approve_capability_tool() -> Tool {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "approval_token": {
+                "type": "string",
+                "description": "Token from a request_capability response with status \"ask\""
+            },
+            "approve": {
+                "type": "boolean",
+                "description": "true to build the tool, false to reject the request"
+            },
+            "reason": {
+                "type": "string",
+                "description": "Why the request was rejected (optional)"
+            },
+            "spec": {
+                "type": "object",
+                "description": "A narrowed capability spec to build instead of the requested one, \
+                                e.g. without a secret the user will not grant. It may only \
+                                remove grants (optional, approve only)"
+            },
+            "changes": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "How spec differs from the request, reported back with the build"
+            }
+        },
+        "required": ["approval_token", "approve"]
+    });
+
+    Tool {
+        name: "approve_capability".into(),
+        title: None,
+        description: Some(
+            "Resolve a capability request that is waiting for human approval. \
+             Approving runs the build pipeline, on a narrowed spec if one is given; \
+             rejecting records a denial. Only works where the operator has enabled \
+             agent approvals; tool calls and requests held by an always_ask rule can \
+             only ever be answered by an operator. Only call this with the user's \
+             explicit answer."
+                .into(),
+        ),
+        input_schema: schema.as_object().cloned().unwrap_or_default().into(),
+        output_schema: None,
+        annotations: None,
+        execution: None,
+        icons: None,
+        meta: None,
+    }
+}
+
+/// Render a decision as the JSON payload returned to the agent: its
+/// canonical serialization, tagged by `status`.
+fn decision_to_json(decision: &Decision) -> serde_json::Value {
+    serde_json::to_value(decision).unwrap_or_default()
+}
+
+/// Fields added to a Defer response: `next_steps` for every target, plus the
+/// referenced tool (`meta`, when it is loaded) for `extend_tool`.
+fn deferral_details(
+    target: &DeferTarget,
+    meta: Option<&ComponentMeta>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut details = serde_json::Map::new();
+    let next_steps = match target {
+        DeferTarget::ExtendTool {
+            tool_name,
+            suggested_features,
+        } => {
+            let extend = if suggested_features.is_empty() {
+                format!(
+                    "To change what it does, call extend_capability with target_tool '{tool_name}' and the features to add"
+                )
+            } else {
+                format!(
+                    "To add {}, call extend_capability with target_tool '{tool_name}' and them as features",
+                    suggested_features.join(", ")
+                )
+            };
+            match meta {
+                Some(meta) => {
+                    details.insert(
+                        "tool".into(),
+                        serde_json::json!({
+                            "name": meta.tool_name,
+                            "description": meta.description,
+                            "input_schema": meta.input_schema,
+                        }),
+                    );
+                    vec![
+                        format!(
+                            "Call '{tool_name}' directly; tool.input_schema describes its arguments"
+                        ),
+                        extend,
+                    ]
+                }
+                None => vec![format!("'{tool_name}' is not loaded in this proxy"), extend],
+            }
+        }
+        DeferTarget::UseCli {
+            binary,
+            example_invocation,
+            ..
+        } => {
+            details.insert("command".into(), example_invocation.clone().into());
+            vec![format!(
+                "Run `{binary}` with your shell tool instead, adapting: {example_invocation}"
+            )]
+        }
+        DeferTarget::RegistryTool {
+            registry,
+            tool_name,
+            version,
+        } => vec![
+            format!("Ask an operator to install '{tool_name}' {version} from {registry}"),
+            format!("Once it is loaded, call '{tool_name}' directly"),
+        ],
     };
+    details.insert("next_steps".into(), serde_json::json!(next_steps));
+    details
+}
+
+/// Classify a failed call for spans and the request log.
+fn error_class(
+    result: &Result<CallToolResult, McpError>,
+    decision: Option<&str>,
+) -> Option<&'static str> {
+    match result {
+        Ok(r) if r.is_error == Some(true) => Some(if decision == Some("denied") {
+            "denied"
+        } else {
+            "tool_error"
+        }),
+        Ok(_) => None,
+        Err(e) if e.code == ErrorCode::INVALID_REQUEST => Some("invalid_request"),
+        Err(e) if e.code == ErrorCode::INVALID_PARAMS => Some("invalid_params"),
+        Err(e) if e.code == ErrorCode::INTERNAL_ERROR => Some("internal_error"),
+        Err(_) => Some("mcp_error"),
+    }
+}
 
-    vec![Content::text(json.to_string())]
+/// Add the request id to an error returned to the client so users can quote
+/// it in bug reports. Successful results are left untouched.
+fn tag_with_request_id(
+    result: Result<CallToolResult, McpError>,
+    request_id: &str,
+) -> Result<CallToolResult, McpError> {
+    match result {
+        Err(mut e) => {
+            e.message = format!("{} (request_id: {request_id})", e.message).into();
+            Err(e)
+        }
+        Ok(mut r) if r.is_error == Some(true) => {
+            if let Some(serde_json::Value::Object(map)) = &mut r.structured_content {
+                map.insert("request_id".into(), request_id.into());
+            }
+            if let Some(text) = r.content.first().and_then(|c| c.as_text()) {
+                let tagged = match serde_json::from_str::<serde_json::Value>(&text.text) {
+                    Ok(serde_json::Value::Object(mut map)) => {
+                        map.insert("request_id".into(), request_id.into());
+                        serde_json::Value::Object(map).to_string()
+                    }
+                    _ => format!("{} (request_id: {request_id})", text.text),
+                };
+                r.content[0] = Content::text(tagged);
+            }
+            Ok(r)
+        }
+        ok => ok,
+    }
 }
 
 /// Convert girt-runtime component metadata to an MCP Tool definition.
+///
+/// Deprecated tools get a suffix on their description so agents pick the
+/// replacement without having to call the old tool first. So do tools whose
+/// last validation found drift, so an agent can expect odd results.
 fn component_meta_to_tool(meta: &ComponentMeta) -> Tool {
+    let mut description = match (meta.deprecated, &meta.replaced_by) {
+        (true, Some(replacement)) => {
+            format!("{} [DEPRECATED — use {replacement}]", meta.description)
+        }
+        (true, None) => format!("{} [DEPRECATED]", meta.description),
+        (false, _) => meta.description.clone(),
+    };
+    if meta.drift_detected {
+        description.push_str(" [DRIFT DETECTED — output may not match this description]");
+    }
     Tool {
         name: meta.tool_name.clone().into(),
         title: None,
-        description: Some(meta.description.clone().into()),
+        description: Some(description.into()),
         input_schema: meta
             .input_schema
             .as_object()
@@ -166,152 +763,616 @@ fn component_meta_to_tool(meta: &ComponentMeta) -> Tool {
     }
 }
 
-fn make_tool_result(content: Vec<Content>, is_error: bool) -> CallToolResult {
-    CallToolResult {
-        content,
-        structured_content: None,
-        is_error: Some(is_error),
-        meta: None,
+/// Runtime metadata for a freshly published tool.
+pub fn component_meta(artifact: &BuildArtifact, publish_result: &PublishResult) -> ComponentMeta {
+    ComponentMeta {
+        component_id: format!("{}@0.1.0", artifact.spec.name),
+        tool_name: artifact.spec.name.clone(),
+        description: artifact.spec.description.clone(),
+        input_schema: artifact.spec.inputs.clone(),
+        // Cross-checked and back-filled by storage on load
+        wasm_hash: publish_result
+            .wasm
+            .as_ref()
+            .map(|d| d.sha256.clone())
+            .unwrap_or_default(),
+        wasm_size_bytes: 0,
+        built_at: now_ms(),
+        loaded_at: 0,
+        allowed_env: PolicyYaml::env_allowlist(&artifact.build_output.policy_yaml),
+        allowed_hosts: PolicyYaml::network_allowlist(&artifact.build_output.policy_yaml),
+        max_response_bytes: PolicyYaml::max_response_bytes(&artifact.build_output.policy_yaml),
+        timeout_ms: PolicyYaml::timeout_ms(&artifact.build_output.policy_yaml),
+        idempotent: artifact.refined_spec.idempotent,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: publish_result.oci_reference.clone(),
+        // Filled in when tool sync has mirrored the tool
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
     }
 }
 
-impl ServerHandler for GirtProxy {
-    async fn initialize(
-        &self,
-        _request: InitializeRequestParams,
-        context: RequestContext<RoleServer>,
-    ) -> Result<InitializeResult, McpError> {
-        // Capture the server peer for later notifications
-        let mut peer_lock = self.server_peer.lock().await;
-        *peer_lock = Some(context.peer.clone());
-        Ok(girt_info())
+/// The version in a tool's component id (`fetch_url@0.1.0`).
+fn tool_version(meta: &ComponentMeta) -> Option<String> {
+    let (_, version) = meta.component_id.rsplit_once('@')?;
+    Some(version.to_string())
+}
+
+/// What the Execution Gate needs to know about a loaded tool. Allowed
+/// environment variables count as secrets: any of them may hold one.
+fn tool_profile(meta: &ComponentMeta) -> ToolProfile {
+    ToolProfile {
+        idempotent: meta.idempotent,
+        description: meta.description.clone(),
+        secrets: meta.allowed_env.clone(),
     }
+}
 
-    async fn list_tools(
-        &self,
-        _request: Option<PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ListToolsResult, McpError> {
-        tracing::debug!("Listing tools");
+/// Build a tool result from a JSON payload, without notes or an envelope.
+fn make_tool_result(value: serde_json::Value, is_error: bool, structured: bool) -> CallToolResult {
+    ResultBuilder::new(structured).json(value, is_error)
+}
 
-        let mut tools = vec![request_capability_tool()];
+/// Builds a tool result from a JSON payload or a tool's error.
+///
+/// The payload, or the error message, is always rendered as text for
+/// clients that predate `structuredContent`. When `structured` is set it is
+/// also attached as structured content: an object payload as is, any other
+/// JSON value as `{"result": value}` (MCP requires an object), and an error
+/// as `{"error": message}`. Notes on the call (retry attempts, truncation,
+/// deprecation, resource usage) are added alongside.
+///
+/// With an [envelope](Self::with_envelope) (`[server] result_envelope`),
+/// the structured content is `{"ok": true, "data": payload, "meta": ...}`
+/// or `{"ok": false, "error": {"message", "kind"}, "meta": ...}` instead,
+/// and the notes go in `meta`.
+#[derive(Debug, Clone, Default)]
+struct ResultBuilder {
+    structured: bool,
+    envelope: Option<EnvelopeMeta>,
+    truncation: Option<serde_json::Value>,
+    notes: serde_json::Map<String, serde_json::Value>,
+}
 
-        // Live tools from girt-runtime (built by pipeline, persisted across restarts)
-        for meta in self.runtime.list_tools().await {
-            tools.push(component_meta_to_tool(&meta));
+/// What a result envelope's `meta` reports besides the call's notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EnvelopeMeta {
+    duration_ms: u64,
+    /// The version in the tool's component id (`fetch_url@0.1.0`).
+    tool_version: Option<String>,
+    /// Whether the result was served from a cache rather than a call.
+    /// Tool results are never cached, so always false today.
+    cached: bool,
+}
+
+impl ResultBuilder {
+    fn new(structured: bool) -> Self {
+        Self {
+            structured,
+            ..Self::default()
         }
+    }
 
-        Ok(ListToolsResult { tools, next_cursor: None, meta: None })
+    fn with_envelope(mut self, envelope: Option<EnvelopeMeta>) -> Self {
+        self.envelope = envelope;
+        self
     }
 
-    async fn call_tool(
-        &self,
-        request: CallToolRequestParams,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<CallToolResult, McpError> {
-        let tool_name: &str = &request.name;
+    /// Note a retried call's attempt count.
+    fn with_attempts(self, attempts: u32) -> Self {
+        let attempts = (attempts > 1).then(|| attempts.into());
+        self.with_note("attempts", attempts)
+    }
 
-        // Handle GIRT built-in tools
-        if tool_name == "request_capability" {
-            return self.handle_request_capability(request).await;
+    /// Note that the response was truncated, and from what size.
+    fn with_truncation(mut self, truncated: Option<&Truncation>) -> Self {
+        self.truncation = truncated.map(|t| serde_json::to_value(t).unwrap_or_default());
+        self
+    }
+
+    fn with_deprecation(self, warning: Option<&serde_json::Value>) -> Self {
+        self.with_note("deprecation", warning.cloned())
+    }
+
+    fn with_resource_usage(self, usage: Option<&ResourceUsage>) -> Self {
+        let usage = usage.map(|u| serde_json::to_value(u).unwrap_or_default());
+        self.with_note("resource_usage", usage)
+    }
+
+    fn with_note(mut self, key: &str, value: Option<serde_json::Value>) -> Self {
+        if let Some(value) = value {
+            self.notes.insert(key.into(), value);
         }
+        self
+    }
 
-        // Run Execution Gate on all other tool calls
-        let exec_input = GateInput::Execution(ExecutionRequest {
-            tool_name: tool_name.to_string(),
-            arguments: request
-                .arguments
-                .as_ref()
-                .map(|args| serde_json::to_value(args).unwrap_or_default())
-                .unwrap_or(serde_json::Value::Null),
-        });
+    fn json(self, value: serde_json::Value, is_error: bool) -> CallToolResult {
+        let text = value.to_string();
+        let structured = match (&self.envelope, value) {
+            (Some(_), value) => serde_json::json!({ "ok": !is_error, "data": value }),
+            (None, value @ serde_json::Value::Object(_)) => value,
+            (None, other) => serde_json::json!({ "result": other }),
+        };
+        self.finish(text, is_error, structured)
+    }
 
-        tracing::info!(tool = %tool_name, "Evaluating tool call through Execution Gate");
+    /// An error result from a tool's own error message. The text content
+    /// stays the raw message.
+    fn tool_error(self, message: String) -> CallToolResult {
+        self.error_of_kind(message, "tool_error")
+    }
 
-        let gate_result = self
-            .engine
-            .evaluate(GateKind::Execution, &exec_input)
-            .await
-            .map_err(|e| McpError::internal_error(format!("Decision engine error: {e}"), None))?;
+    /// An error result for a call that failed with `message`, reported in
+    /// an envelope as `kind`.
+    fn error_of_kind(self, message: String, kind: &str) -> CallToolResult {
+        self.error(message.clone(), message, kind, None)
+    }
 
-        tracing::info!(
-            tool = %tool_name,
-            decision = ?gate_result.decision,
-            layer = %gate_result.layer,
-            "Execution Gate decision"
-        );
+    /// A structured error from a `girt:tool@0.2.0` tool.
+    fn tool_failure(self, kind: &str, message: &str, retryable: bool) -> CallToolResult {
+        let text = format!("{kind} error: {message}");
+        self.error(text, message.into(), kind, Some(retryable))
+    }
 
-        match &gate_result.decision {
-            Decision::Allow => {
-                tracing::info!(tool = %tool_name, "Execution Gate passed — invoking via girt-runtime");
+    /// Without an envelope, only a 0.2.0 tool's failure (which says
+    /// whether it is `retryable`) reports its `kind`.
+    fn error(
+        self,
+        text: String,
+        message: String,
+        kind: &str,
+        retryable: Option<bool>,
+    ) -> CallToolResult {
+        let mut error = serde_json::Map::new();
+        let enveloped = self.envelope.is_some();
+        error.insert(
+            if enveloped { "message" } else { "error" }.into(),
+            message.into(),
+        );
+        if enveloped || retryable.is_some() {
+            error.insert("kind".into(), kind.into());
+        }
+        if let Some(retryable) = retryable {
+            error.insert("retryable".into(), retryable.into());
+        }
+        let structured = if enveloped {
+            serde_json::json!({ "ok": false, "error": error })
+        } else {
+            serde_json::Value::Object(error)
+        };
+        self.finish(text, true, structured)
+    }
 
-                let args = request
-                    .arguments
-                    .as_ref()
-                    .map(|a| serde_json::to_value(a).unwrap_or(serde_json::Value::Null))
-                    .unwrap_or(serde_json::Value::Null);
-
-                match self.runtime.call_tool(tool_name, &args).await {
-                    Ok(result) => Ok(make_tool_result(
-                        vec![Content::text(result.to_string())],
-                        false,
-                    )),
-                    Err(girt_runtime::RuntimeError::ToolError(msg)) => {
-                        tracing::warn!(tool = %tool_name, error = %msg, "Tool returned error");
-                        Ok(make_tool_result(vec![Content::text(msg)], true))
+    fn finish(
+        self,
+        text: String,
+        is_error: bool,
+        mut structured: serde_json::Value,
+    ) -> CallToolResult {
+        let Self {
+            structured: attach,
+            envelope,
+            truncation,
+            mut notes,
+        } = self;
+        let structured_content = attach.then(|| {
+            match envelope {
+                Some(envelope) => {
+                    let mut meta = serde_json::json!({
+                        "duration_ms": envelope.duration_ms,
+                        "truncated": truncation.is_some(),
+                        "tool_version": envelope.tool_version,
+                        "cached": envelope.cached,
+                    });
+                    if let Some(truncation) = truncation {
+                        notes.insert("truncation".into(), truncation);
                     }
-                    Err(girt_runtime::RuntimeError::ToolNotFound(_)) => {
-                        Err(McpError::invalid_request(
-                            format!("Tool '{tool_name}' not found in girt-runtime"),
-                            None,
-                        ))
+                    if let serde_json::Value::Object(meta) = &mut meta {
+                        meta.extend(notes);
                     }
-                    Err(e) => {
-                        tracing::error!(tool = %tool_name, error = %e, "girt-runtime invocation failed");
-                        Err(McpError::internal_error(format!("Runtime error: {e}"), None))
+                    structured["meta"] = meta;
+                }
+                None => {
+                    if let Some(truncation) = truncation {
+                        notes.insert("truncated".into(), truncation);
+                    }
+                    if let serde_json::Value::Object(map) = &mut structured {
+                        map.extend(notes);
                     }
                 }
             }
-            Decision::Deny { .. } => {
-                tracing::warn!(tool = %tool_name, "Tool call denied");
-                Ok(make_tool_result(
-                    decision_to_content(&gate_result.decision),
-                    true,
-                ))
-            }
-            _ => Ok(make_tool_result(
-                decision_to_content(&gate_result.decision),
-                false,
-            )),
+            structured
+        });
+        CallToolResult {
+            content: vec![Content::text(text)],
+            structured_content,
+            is_error: Some(is_error),
+            meta: None,
         }
     }
+}
 
-    async fn list_resources(
-        &self,
-        _request: Option<PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ListResourcesResult, McpError> {
-        Ok(ListResourcesResult { resources: vec![], next_cursor: None, meta: None })
-    }
+/// A call's arguments as the gate, the argument checks and the tool see
+/// them. Omitted arguments are no arguments: `{}` satisfies an object schema
+/// without required fields, where null would not, and a call with either
+/// gets the same cached decision.
+fn call_arguments(
+    arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> serde_json::Value {
+    serde_json::Value::Object(arguments.cloned().unwrap_or_default())
+}
 
-    async fn read_resource(
-        &self,
-        _request: ReadResourceRequestParams,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ReadResourceResult, McpError> {
-        Err(McpError::invalid_request("No resources available", None))
-    }
+/// The Execution Gate input for calling `tool_name` with `arguments`, as
+/// given by [`call_arguments`].
+fn execution_input(
+    tool_name: &str,
+    arguments: &serde_json::Value,
+    meta: Option<&ComponentMeta>,
+) -> GateInput {
+    GateInput::Execution(ExecutionRequest {
+        tool_name: tool_name.to_string(),
+        arguments: arguments.clone(),
+        tool: meta.map(tool_profile),
+    })
+}
 
-    async fn list_prompts(
-        &self,
-        _request: Option<PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ListPromptsResult, McpError> {
-        Ok(ListPromptsResult { prompts: vec![], next_cursor: None, meta: None })
+/// `response` with what the agent needs to have `approval` answered.
+fn with_approval(mut response: serde_json::Value, approval: &PendingApproval) -> serde_json::Value {
+    response["approval_token"] = approval.token.clone().into();
+    response["expires_at_ms"] = approval.expires_at_ms.into();
+    response["approval_message"] = approval.message(MAX_MESSAGE_CHARS).into();
+    response["message"] = if approval.operator_only {
+        format!(
+            "Show the user approval_message. Only an operator can answer it, \
+             by running `girt approve {token}` or `girt reject {token}`",
+            token = approval.token
+        )
+    } else {
+        format!(
+            "Show the user approval_message, then call approve_capability \
+             with this token, or have an operator run `girt approve {}`",
+            approval.token
+        )
     }
+    .into();
+    response
+}
 
-    async fn get_prompt(
-        &self,
+/// The answer to a request for a spec that is already being built.
+fn already_queued_json(existing: &InFlight) -> serde_json::Value {
+    serde_json::json!({
+        "status": "already_queued",
+        "request_id": existing.request_id,
+        "tool_name": existing.tool_name,
+        "message": "An identical request is already being built; the tool will \
+                    appear in tools/list when it is ready",
+    })
+}
+
+/// Parse and validate `request_capability` arguments.
+///
+/// Validation failures are reported as `invalid_params` with the offending
+/// fields listed in the error data.
+fn parse_capability_spec(
+    arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> Result<CapabilitySpec, McpError> {
+    let arguments = arguments.ok_or_else(|| {
+        McpError::invalid_params(
+            "request_capability requires a capability spec with at least name and description",
+            None,
+        )
+    })?;
+    // Read the spec as written first, so validation errors keep their fields.
+    let spec: RawCapabilitySpec =
+        serde_json::from_value(serde_json::Value::Object(arguments.clone()))
+            .map_err(|e| McpError::invalid_params(format!("Invalid capability spec: {e}"), None))?;
+    CapabilitySpec::try_from(spec).map_err(|e| {
+        McpError::invalid_params(e.to_string(), Some(serde_json::json!({ "errors": e.errors })))
+    })
+}
+
+/// The optional `pipeline` object of a `request_capability` call.
+fn parse_pipeline_overrides(
+    arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> Result<PipelineOverrides, McpError> {
+    match arguments.and_then(|a| a.get("pipeline")) {
+        None | Some(serde_json::Value::Null) => Ok(PipelineOverrides::default()),
+        Some(value) => serde_json::from_value(value.clone()).map_err(|e| {
+            McpError::invalid_params(format!("Invalid pipeline overrides: {e}"), None)
+        }),
+    }
+}
+
+/// Reject a payload over a `[security]` size limit, reporting the measured
+/// size in the error data.
+fn too_large(tool_name: &str, error: SizeLimitError) -> McpError {
+    tracing::warn!(tool = %tool_name, %error, "Rejected oversized request");
+    McpError::invalid_params(
+        format!("Request to '{tool_name}' is too large: {error}"),
+        serde_json::to_value(&error).ok(),
+    )
+}
+
+fn tool_not_found(tool_name: &str) -> McpError {
+    McpError::invalid_request(format!("Tool '{tool_name}' not found in girt-runtime"), None)
+}
+
+/// `_meta` key of a `tools/list` request that limits the listing to tool
+/// names starting with its value. MCP defines no filter, so this is ours.
+pub const NAME_PREFIX_META: &str = "girt/namePrefix";
+
+/// The cursor and name-prefix filter of a `tools/list` request.
+///
+/// On the wire `_meta` is lifted out of the params into `request_meta`
+/// (the handler context's), so both places are checked.
+fn list_tools_params(
+    request: Option<&PaginatedRequestParams>,
+    request_meta: &Meta,
+) -> (Option<String>, Option<String>) {
+    let prefix = [request.and_then(|r| r.meta.as_ref()), Some(request_meta)]
+        .into_iter()
+        .flatten()
+        .find_map(|meta| meta.get(NAME_PREFIX_META)?.as_str().map(str::to_owned));
+    (request.and_then(|r| r.cursor.clone()), prefix)
+}
+
+/// One `tools/list` page: the tools named after `cursor` (the last name on
+/// the previous page) that start with `prefix`, in name order. Returns the
+/// page and, if tools remain, the cursor for the next one.
+///
+/// Because the cursor is a name rather than an offset, tools added or
+/// removed between pages never cause duplicates.
+fn paginate_tools(
+    mut tools: Vec<Tool>,
+    cursor: Option<&str>,
+    prefix: Option<&str>,
+    page_size: usize,
+) -> (Vec<Tool>, Option<String>) {
+    tools.retain(|tool| {
+        prefix.is_none_or(|prefix| tool.name.starts_with(prefix))
+            && cursor.is_none_or(|cursor| &*tool.name > cursor)
+    });
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    if page_size == 0 || tools.len() <= page_size {
+        return (tools, None);
+    }
+    tools.truncate(page_size);
+    let next_cursor = tools.last().map(|tool| tool.name.to_string());
+    (tools, next_cursor)
+}
+
+/// Refuse a call to a tool quarantined on restore (see
+/// [`crate::quarantine`]), saying how to release it.
+fn quarantine_check(meta: &ComponentMeta) -> Result<(), McpError> {
+    let Some(reason) = &meta.quarantined else {
+        return Ok(());
+    };
+    let tool_name = &meta.tool_name;
+    tracing::warn!(tool = %tool_name, %reason, "Refused call to quarantined tool");
+    Err(McpError::invalid_request(
+        format!(
+            "Tool '{tool_name}' is quarantined ({reason}); an operator can release it with \
+             `girt tools release {tool_name}`"
+        ),
+        Some(serde_json::json!({ "quarantined": reason })),
+    ))
+}
+
+/// Check a tool's deprecation status at `now` (Unix ms).
+///
+/// Returns `Ok(None)` for a current tool and `Ok(Some(warning))` for a
+/// deprecated one still within `grace`; once the grace period has passed
+/// the call is refused with an error naming the replacement.
+fn deprecation_notice(
+    meta: &ComponentMeta,
+    grace: Duration,
+    now: u64,
+) -> Result<Option<serde_json::Value>, McpError> {
+    if !meta.deprecated {
+        return Ok(None);
+    }
+    let tool_name = &meta.tool_name;
+    let instead = match &meta.replaced_by {
+        Some(replacement) => format!("; use '{replacement}' instead"),
+        None => String::new(),
+    };
+    // Without a timestamp the grace period never starts.
+    let retired_at = meta
+        .deprecated_at
+        .map(|at| at.saturating_add(grace.as_millis() as u64));
+
+    if retired_at.is_some_and(|retired_at| now >= retired_at) {
+        tracing::warn!(tool = %tool_name, "Refused call to retired tool");
+        return Err(McpError::invalid_request(
+            format!("Tool '{tool_name}' is deprecated and no longer callable{instead}"),
+            Some(serde_json::json!({
+                "replaced_by": meta.replaced_by,
+                "deprecated_at": meta.deprecated_at,
+            })),
+        ));
+    }
+    Ok(Some(serde_json::json!({
+        "message": format!("Tool '{tool_name}' is deprecated{instead}"),
+        "replaced_by": meta.replaced_by,
+        "deprecated_at": meta.deprecated_at,
+        "retired_at": retired_at,
+    })))
+}
+
+/// Delay before the first retry of an idempotent tool; doubles per attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Whether a tool's error message describes a transient failure worth
+/// retrying: an HTTP 5xx status, a timeout, or a reset connection.
+fn is_retryable(message: &str) -> bool {
+    let lower = message.to_ascii_lowercase();
+    if ["timeout", "timed out", "connection reset"]
+        .iter()
+        .any(|needle| lower.contains(needle))
+    {
+        return true;
+    }
+    lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|token| {
+            token.len() == 3 && token.starts_with('5') && token.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
+/// Whether a failed call is worth retrying. A `girt:tool@0.2.0` tool says
+/// so itself; a 0.1.0 tool's error message is judged by [`is_retryable`].
+fn is_transient(error: &RuntimeError) -> bool {
+    match error {
+        RuntimeError::ToolError(msg) => is_retryable(msg),
+        RuntimeError::ToolFailure { retryable, .. } => *retryable,
+        _ => false,
+    }
+}
+
+/// Run `call`, retrying up to `retries` more times while it fails with a
+/// [transient](is_transient) tool error. Returns the last outcome and the
+/// number of attempts made.
+async fn call_with_retry<F, Fut>(
+    retries: u32,
+    base_delay: Duration,
+    mut call: F,
+) -> (Result<serde_json::Value, RuntimeError>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<serde_json::Value, RuntimeError>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let outcome = call().await;
+        match &outcome {
+            Err(e) if attempts <= retries && is_transient(e) => {
+                let delay = base_delay * 2u32.saturating_pow(attempts - 1);
+                tracing::info!(attempt = attempts, error = %e, ?delay, "Retrying idempotent tool");
+                tokio::time::sleep(delay).await;
+            }
+            _ => return (outcome, attempts),
+        }
+    }
+}
+
+impl ServerHandler for GirtProxy {
+    async fn initialize(
+        &self,
+        _request: InitializeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<InitializeResult, McpError> {
+        // Keep the peer for later notifications.
+        self.peers.register(context.peer.clone());
+        self.refresh_status().await;
+        Ok(girt_info(self.instructions()))
+    }
+
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let trace = RequestTrace::new();
+        let span = tracing::debug_span!(
+            "mcp_request",
+            request_id = %trace.request_id,
+            method = "list_tools",
+        );
+        span.in_scope(|| tracing::debug!("Listing tools"));
+        let started = Instant::now();
+
+        let mut tools = builtin_tools(self.batch_max_calls);
+
+        // Live tools from girt-runtime (built by pipeline, persisted across
+        // restarts), less any a built-in shadows or a quarantine hides.
+        for meta in self.runtime.list_tools().instrument(span).await {
+            if !is_builtin_tool(&meta.tool_name) && meta.quarantined.is_none() {
+                tools.push(component_meta_to_tool(&meta));
+            }
+        }
+
+        let (cursor, prefix) = list_tools_params(request.as_ref(), &context.meta);
+        let (tools, next_cursor) = paginate_tools(
+            tools,
+            cursor.as_deref(),
+            prefix.as_deref(),
+            self.tools_page_size,
+        );
+
+        if let Some(log) = &self.request_log {
+            log.append(&RequestRecord {
+                timestamp_ms: now_ms(),
+                request_id: trace.request_id,
+                method: "list_tools",
+                tool_name: None,
+                decision: None,
+                duration_ms: started.elapsed().as_millis() as u64,
+                error_class: None,
+                argument_bytes: 0,
+                argument_sizes: Default::default(),
+            });
+        }
+
+        Ok(ListToolsResult { tools, next_cursor, meta: None })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.handle_call(request).await
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult { resources: vec![state_resource()], next_cursor: None, meta: None })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        if request.uri != STATE_URI {
+            return Err(McpError::resource_not_found(
+                format!("No resource at '{}'", request.uri),
+                None,
+            ));
+        }
+        let document = self.state_document().await;
+        let text = serde_json::to_string_pretty(&document)
+            .map_err(|e| McpError::internal_error(format!("State document: {e}"), None))?;
+        let contents = ResourceContents::TextResourceContents {
+            uri: STATE_URI.into(),
+            mime_type: Some("application/json".into()),
+            text,
+            meta: None,
+        };
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult { prompts: vec![], next_cursor: None, meta: None })
+    }
+
+    async fn get_prompt(
+        &self,
         _request: GetPromptRequestParams,
         _context: RequestContext<RoleServer>,
     ) -> Result<GetPromptResult, McpError> {
@@ -335,7 +1396,7 @@ impl ServerHandler for GirtProxy {
     }
 
     fn get_info(&self) -> ServerInfo {
-        let result = girt_info();
+        let result = girt_info(self.instructions());
         ServerInfo {
             protocol_version: result.protocol_version,
             capabilities: result.capabilities,
@@ -346,30 +1407,464 @@ impl ServerHandler for GirtProxy {
 }
 
 impl GirtProxy {
-    async fn handle_request_capability(
+    /// Handle a `tools/call` request inside a per-request span, logging it
+    /// to the request log and tagging errors with the request id.
+    async fn handle_call(
+        &self,
+        request: CallToolRequestParams,
+    ) -> Result<CallToolResult, McpError> {
+        let trace = RequestTrace::new();
+        let tool_name = request.name.to_string();
+        let method = if tool_name == "request_capability" {
+            "request_capability"
+        } else {
+            "call_tool"
+        };
+        let (argument_bytes, argument_sizes) = argument_sizes(request.arguments.as_ref());
+        let span = tracing::info_span!(
+            "mcp_request",
+            request_id = %trace.request_id,
+            method,
+            tool = %tool_name,
+            decision = tracing::field::Empty,
+            runtime_ms = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+            error_class = tracing::field::Empty,
+        );
+
+        let started = Instant::now();
+        let result = if tool_name == BATCH_TOOL {
+            self.handle_batch(request).instrument(span.clone()).await
+        } else {
+            self.dispatch_call(request, &trace)
+                .instrument(span.clone())
+                .await
+        };
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let error_class = error_class(&result, trace.decision());
+        span.record("duration_ms", duration_ms);
+        if let Some(class) = error_class {
+            span.record("error_class", class);
+        }
+        if let Some(log) = &self.request_log {
+            log.append(&RequestRecord {
+                timestamp_ms: now_ms(),
+                request_id: trace.request_id.clone(),
+                method,
+                tool_name: Some(tool_name),
+                decision: trace.decision(),
+                duration_ms,
+                error_class,
+                argument_bytes,
+                argument_sizes,
+            });
+        }
+
+        tag_with_request_id(result, &trace.request_id)
+    }
+
+    async fn dispatch_call(
+        &self,
+        request: CallToolRequestParams,
+        trace: &RequestTrace,
+    ) -> Result<CallToolResult, McpError> {
+        let tool_name: &str = &request.name;
+
+        // Oversized payloads are refused before any gate, LLM, or runtime work.
+        let raw_args = request
+            .arguments
+            .as_ref()
+            .map(|args| serde_json::Value::Object(args.clone()))
+            .unwrap_or(serde_json::Value::Null);
+        let size_check = if tool_name == "request_capability" || tool_name == EXTEND_TOOL {
+            self.limits.check_spec(&raw_args)
+        } else {
+            self.limits.check_arguments(&raw_args)
+        };
+        size_check.map_err(|e| too_large(tool_name, e))?;
+
+        // Handle GIRT built-in tools. These always win: a runtime tool of the
+        // same name (one loaded before the name was reserved) is never called.
+        if tool_name == "request_capability" {
+            return self.handle_request_capability(request, trace).await;
+        }
+        if tool_name == "girt_tool_info" {
+            return self.handle_tool_info(request).await;
+        }
+        if tool_name == "girt_info" {
+            return Ok(self.json_result(self.info().await, false));
+        }
+        if tool_name == "approve_capability" {
+            return self.handle_approve_capability(request).await;
+        }
+        if tool_name == LIST_SERVICES_TOOL {
+            return self.handle_list_services().await;
+        }
+        if tool_name == EXTEND_TOOL {
+            return self.handle_extend_capability(request, trace).await;
+        }
+
+        self.call_runtime_tool(&request, trace, true).await
+    }
+
+    /// Call a tool loaded in the runtime: through the Execution Gate when
+    /// `gated`, or straight away for a call a person already approved.
+    async fn call_runtime_tool(
+        &self,
+        request: &CallToolRequestParams,
+        trace: &RequestTrace,
+        gated: bool,
+    ) -> Result<CallToolResult, McpError> {
+        let tool_name: &str = &request.name;
+
+        // Unknown tools never reach the gate: no LLM/HITL cost for garbage names.
+        self.ensure_tool_loaded(tool_name).await?;
+
+        // Quarantined and retired tools are refused outright; deprecated ones
+        // run with a warning.
+        let meta = self.runtime.tool_meta(tool_name).await;
+        if let Some(meta) = &meta {
+            quarantine_check(meta)?;
+        }
+        let deprecation = match &meta {
+            Some(meta) => deprecation_notice(meta, self.deprecation_grace, now_ms())?,
+            None => None,
+        };
+
+        let args = call_arguments(request.arguments.as_ref());
+
+        // Bundled first-party tools are part of GIRT, like the built-ins:
+        // they skip the Execution Gate, whose default policy denies their
+        // reserved names.
+        if self.runtime.is_bundled(tool_name).await {
+            tracing::info!(tool = %tool_name, "Bundled tool — invoking via girt-runtime");
+            trace.set_decision(Decision::Allow.status());
+        } else if !gated {
+            tracing::info!(tool = %tool_name, "Approved call — invoking via girt-runtime");
+            trace.set_decision(Decision::Allow.status());
+        } else if let Some(refused) = self
+            .gate_execution(tool_name, &args, meta.as_ref(), trace)
+            .await?
+        {
+            return Ok(refused);
+        }
+
+        self.check_arguments(tool_name, &args).await?;
+
+        // Only tools marked read-only are safe to run twice.
+        let retries = match &meta {
+            Some(meta) if meta.idempotent => self.tool_retry_attempts,
+            _ => 0,
+        };
+
+        let started = Instant::now();
+        let last_trace = Mutex::new(None);
+        let (outcome, attempts) = call_with_retry(retries, RETRY_BASE_DELAY, || {
+            let (runtime, args, last_trace) = (&self.runtime, &args, &last_trace);
+            async move {
+                let (result, trace) = runtime
+                    .call_tool_traced(tool_name, args, &CallOptions::default())
+                    .await;
+                *last_trace.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some((trace.resources, trace.truncated));
+                result
+            }
+        })
+        .await;
+        let runtime_ms = started.elapsed().as_millis() as u64;
+        tracing::Span::current().record("runtime_ms", runtime_ms);
+        let last_trace = last_trace.into_inner().unwrap_or_else(|e| e.into_inner());
+        let usage = last_trace.map(|(usage, _)| usage);
+        let truncated = last_trace.and_then(|(_, truncated)| truncated);
+        if let Some(history) = &self.history {
+            let error = outcome.as_ref().err().map(|e| match e {
+                RuntimeError::ToolError(msg) => msg.clone(),
+                RuntimeError::ToolFailure { kind, message, .. } => {
+                    format!("{kind}: {message}")
+                }
+                other => other.to_string(),
+            });
+            history.record(
+                tool_name,
+                &args,
+                error.as_deref(),
+                runtime_ms,
+                usage.as_ref(),
+            );
+        }
+        let reported = usage.filter(|_| self.include_resource_usage);
+        let envelope = self.result_envelope.then(|| EnvelopeMeta {
+            duration_ms: runtime_ms,
+            tool_version: meta.as_ref().and_then(tool_version),
+            cached: false,
+        });
+        let results = ResultBuilder::new(self.structured_results)
+            .with_envelope(envelope)
+            .with_attempts(attempts)
+            .with_truncation(truncated.as_ref())
+            .with_deprecation(deprecation.as_ref())
+            .with_resource_usage(reported.as_ref());
+
+        match outcome {
+            Ok(result) => Ok(results.json(result, false)),
+            Err(RuntimeError::ToolError(msg)) => {
+                tracing::warn!(tool = %tool_name, error = %msg, attempts, "Tool returned error");
+                Ok(results.tool_error(msg))
+            }
+            Err(RuntimeError::ToolFailure {
+                kind,
+                message,
+                retryable,
+            }) => {
+                tracing::warn!(tool = %tool_name, %kind, error = %message, attempts, "Tool returned error");
+                Ok(results.tool_failure(&kind, &message, retryable))
+            }
+            Err(e @ RuntimeError::ResponseTooLarge { .. }) => {
+                tracing::warn!(tool = %tool_name, error = %e, attempts, "Tool response too large");
+                Ok(results.error_of_kind(e.to_string(), "response_too_large"))
+            }
+            Err(e @ RuntimeError::Timeout { .. }) => {
+                tracing::warn!(tool = %tool_name, error = %e, attempts, "Tool timed out");
+                Ok(results.error_of_kind(e.to_string(), "timeout"))
+            }
+            Err(RuntimeError::ToolNotFound(_)) => {
+                // Unloaded between the check above and the call.
+                self.engine.invalidate_execution(tool_name).await;
+                self.refresh_known_tools().await;
+                Err(tool_not_found(tool_name))
+            }
+            Err(e) => {
+                tracing::error!(tool = %tool_name, error = %e, "girt-runtime invocation failed");
+                Err(McpError::internal_error(format!("Runtime error: {e}"), None))
+            }
+        }
+    }
+
+    /// Run a tool call through the Execution Gate. Returns `None` if it may
+    /// go ahead, or the result to answer with if it was denied or is
+    /// waiting on a person. A call the gate asks about is parked in the
+    /// approval store until someone answers.
+    async fn gate_execution(
+        &self,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        meta: Option<&ComponentMeta>,
+        trace: &RequestTrace,
+    ) -> Result<Option<CallToolResult>, McpError> {
+        let exec_input = execution_input(tool_name, arguments, meta);
+
+        tracing::info!(tool = %tool_name, "Evaluating tool call through Execution Gate");
+
+        let gate_result = self
+            .engine
+            .evaluate(GateKind::Execution, &exec_input)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Decision engine error: {e}"), None))?;
+
+        tracing::info!(
+            tool = %tool_name,
+            decision = ?gate_result.decision,
+            layer = %gate_result.layer,
+            "Execution Gate decision"
+        );
+        trace.set_decision(gate_result.decision.status());
+        self.decisions
+            .record(GateKind::Execution, gate_result.decision.status());
+
+        let denied = match &gate_result.decision {
+            Decision::Allow => {
+                tracing::info!(tool = %tool_name, "Execution Gate passed — invoking via girt-runtime");
+                return Ok(None);
+            }
+            Decision::Deny { .. } => {
+                tracing::warn!(tool = %tool_name, "Tool call denied");
+                true
+            }
+            Decision::Ask { prompt, .. } => {
+                if let (Some(meta), GateInput::Execution(exec)) = (meta, exec_input) {
+                    let call = PendingCall {
+                        arguments: exec.arguments,
+                    };
+                    let spec = quarantine::restored_spec(meta, None);
+                    let request = ApprovalRequest::new(spec, prompt.clone())
+                        .with_call(Some(call))
+                        .with_operator_only(true);
+                    let approval = self.approvals.create(request);
+                    tracing::info!(
+                        token = %approval.token,
+                        tool = %tool_name,
+                        "Tool call awaiting approval"
+                    );
+                    let response = with_approval(self.gate_result_json(&gate_result), &approval);
+                    return Ok(Some(self.json_result(response, false)));
+                }
+                false
+            }
+            _ => false,
+        };
+        let result = self.gate_result_json(&gate_result);
+        Ok(Some(self.json_result(result, denied)))
+    }
+
+    /// List the services loaded tools can authenticate to.
+    async fn handle_list_services(&self) -> Result<CallToolResult, McpError> {
+        let tools = self.runtime.list_tools().await;
+        let grants = tools
+            .iter()
+            .map(|meta| (meta.tool_name.as_str(), meta.allowed_env.as_slice()));
+        let listing = ServiceListing::collect(
+            self.secrets.as_ref(),
+            |service| self.engine.secret_denied(service),
+            grants,
+        )
+        .await
+        .map_err(|e| McpError::internal_error(format!("Secret store error: {e}"), None))?;
+        Ok(self.json_result(serde_json::to_value(listing).unwrap_or_default(), false))
+    }
+
+    /// Run a `girt_batch`: each entry as its own gated call, in order or up
+    /// to `batch_parallelism` at once.
+    async fn handle_batch(
         &self,
         request: CallToolRequestParams,
     ) -> Result<CallToolResult, McpError> {
-        let spec: CapabilitySpec = request
+        let raw_args = request
             .arguments
             .as_ref()
-            .map(|args| serde_json::from_value(serde_json::to_value(args).unwrap_or_default()))
-            .transpose()
-            .map_err(|e| McpError::invalid_params(format!("Invalid capability spec: {e}"), None))?
-            .unwrap_or_else(|| CapabilitySpec {
-                name: "unknown".into(),
-                description: "No description provided".into(),
-                inputs: serde_json::Value::Null,
-                outputs: serde_json::Value::Null,
-                constraints: Default::default(),
+            .map(|args| serde_json::Value::Object(args.clone()))
+            .unwrap_or(serde_json::Value::Null);
+        self.limits
+            .check_arguments(&raw_args)
+            .map_err(|e| too_large(BATCH_TOOL, e))?;
+        let BatchRequest { calls, mode } =
+            BatchRequest::parse(request.arguments.as_ref(), self.batch_max_calls)?;
+        tracing::info!(calls = calls.len(), ?mode, "Running tool batch");
+
+        let outcomes = match mode {
+            BatchMode::Sequential => {
+                let mut outcomes = Vec::with_capacity(calls.len());
+                for call in calls {
+                    outcomes.push(self.batch_call(call).await);
+                }
+                outcomes
+            }
+            BatchMode::Parallel => self.batch_parallel(calls).await?,
+        };
+        Ok(self.json_result(batch::render(mode, &outcomes), false))
+    }
+
+    /// Run batch entries side by side, at most `batch_parallelism` at a
+    /// time, returning their outcomes in the order given.
+    async fn batch_parallel(&self, calls: Vec<BatchCall>) -> Result<Vec<CallOutcome>, McpError> {
+        let parallelism = self.batch_parallelism.max(1);
+        let mut outcomes: Vec<Option<CallOutcome>> = vec![None; calls.len()];
+        let mut pending = calls.into_iter().enumerate();
+        let mut in_flight = JoinSet::new();
+
+        loop {
+            while in_flight.len() < parallelism {
+                let Some((index, call)) = pending.next() else {
+                    break;
+                };
+                let proxy = self.clone();
+                in_flight
+                    .spawn(async move { (index, proxy.batch_call(call).await) }.in_current_span());
+            }
+            let Some(joined) = in_flight.join_next().await else {
+                break;
+            };
+            let (index, outcome) = joined
+                .map_err(|e| McpError::internal_error(format!("Batch call panicked: {e}"), None))?;
+            outcomes[index] = Some(outcome);
+        }
+        Ok(outcomes.into_iter().flatten().collect())
+    }
+
+    /// One `girt_batch` entry, handled as a lone `tools/call` would be, in
+    /// its own span.
+    async fn batch_call(&self, call: BatchCall) -> CallOutcome {
+        let trace = RequestTrace::new();
+        let span = tracing::info_span!(
+            "batch_call",
+            request_id = %trace.request_id,
+            tool = %call.tool,
+            decision = tracing::field::Empty,
+            runtime_ms = tracing::field::Empty,
+        );
+
+        let started = Instant::now();
+        let result = if is_builtin_tool(&call.tool) {
+            Err(McpError::invalid_params(
+                format!("'{}' is built in and cannot be batched", call.tool),
+                None,
+            ))
+        } else {
+            let request = CallToolRequestParams {
+                meta: None,
+                name: call.tool.clone().into(),
+                arguments: Some(call.args),
+                task: None,
+            };
+            self.dispatch_call(request, &trace).instrument(span).await
+        };
+        let duration_ms = started.elapsed().as_millis() as u64;
+        CallOutcome::new(call.tool, trace.decision(), result, duration_ms)
+    }
+
+    async fn handle_request_capability(
+        &self,
+        request: CallToolRequestParams,
+        trace: &RequestTrace,
+    ) -> Result<CallToolResult, McpError> {
+        let spec = parse_capability_spec(request.arguments.as_ref())?;
+        let pipeline = parse_pipeline_overrides(request.arguments.as_ref())?;
+        let flag = |name: &str| {
+            request
+                .arguments
+                .as_ref()
+                .and_then(|a| a.get(name))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        };
+        let (force_rebuild, dry_run) = (flag("force_rebuild"), flag("dry_run"));
+
+        // Agents that lost context re-request tools they already have. A
+        // loaded tool offering everything asked for is answered without the
+        // gate or the pipeline.
+        let existing_diff = self.existing_spec_diff(&spec).await;
+        if let Some(diff) = existing_diff.as_ref().filter(|d| d.only_removes())
+            && !force_rebuild
+        {
+            tracing::info!(name = %spec.name, "Requested capability is already loaded");
+            trace.set_decision("already_available");
+            let response = serde_json::json!({
+                "status": "already_available",
+                "tool_name": spec.name,
+                "diff": diff,
+                "message": "This tool is already loaded; call it directly, \
+                            or pass force_rebuild: true to build it again",
             });
+            return Ok(self.json_result(response, false));
+        }
+
+        // Agents that give up waiting ask again while the first build runs.
+        if let Some(existing) = self.queued_duplicate(&spec).await {
+            tracing::info!(
+                name = %spec.name,
+                request_id = %existing.request_id,
+                "Identical capability request is already being built"
+            );
+            trace.set_decision("already_queued");
+            return Ok(self.json_result(already_queued_json(&existing), false));
+        }
 
         tracing::info!(
             name = %spec.name,
             "Evaluating capability request through Creation Gate"
         );
 
-        let input = GateInput::Creation(spec.clone());
+        let input = GateInput::creation(spec.clone(), RequestSource::Agent);
 
         let gate_result = self
             .engine
@@ -382,56 +1877,691 @@ impl GirtProxy {
             layer = %gate_result.layer,
             "Creation Gate decision"
         );
+        trace.set_decision(gate_result.decision.status());
+        self.decisions
+            .record(GateKind::Creation, gate_result.decision.status());
 
-        match &gate_result.decision {
-            Decision::Allow => {
-                // Creation allowed -- trigger build pipeline
-                self.trigger_build(spec).await
+        // A dry run previews anything the gate did not deny, without
+        // building it or asking for approval.
+        if dry_run && !matches!(gate_result.decision, Decision::Deny { .. }) {
+            let request =
+                CapabilityRequest::new(spec, RequestSource::Agent).with_overrides(pipeline);
+            let report = self.orchestrator(&request).dry_run(&request).await;
+            let response = serde_json::json!({
+                "status": "dry_run",
+                "decision": gate_result.decision.status(),
+                "report": report,
+            });
+            return Ok(self.json_result(response, false));
+        }
+
+        self.answer_creation(&gate_result, spec, pipeline, existing_diff, None)
+            .await
+    }
+
+    /// A build of a spec identical to `spec` that is running here or queued
+    /// for `girt worker`.
+    async fn queued_duplicate(&self, spec: &CapabilitySpec) -> Option<InFlight> {
+        if let Some(existing) = self.in_flight.find(spec) {
+            return Some(existing);
+        }
+        let queue = self.build_queue.as_ref()?;
+        match queue.find_by_spec_hash(&spec.spec_hash()).await {
+            Ok(queued) => queued.map(|request| InFlight {
+                request_id: request.id,
+                tool_name: request.spec.name,
+            }),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to check the build queue for duplicates");
+                None
             }
-            Decision::Deny { .. } => Ok(make_tool_result(
-                decision_to_content(&gate_result.decision),
-                true,
-            )),
-            _ => Ok(make_tool_result(
-                decision_to_content(&gate_result.decision),
-                false,
-            )),
         }
     }
 
-    /// Trigger the build pipeline for an approved capability request.
-    async fn trigger_build(&self, spec: CapabilitySpec) -> Result<CallToolResult, McpError> {
-        let cap_request = CapabilityRequest::new(spec, RequestSource::Operator);
-        let tool_name = cap_request.spec.name.clone();
+    /// Extend a published tool: gate its spec with the requested additions
+    /// like a fresh request, then build the extension from its source.
+    async fn handle_extend_capability(
+        &self,
+        request: CallToolRequestParams,
+        trace: &RequestTrace,
+    ) -> Result<CallToolResult, McpError> {
+        let extend = ExtendRequest::parse(request.arguments.as_ref())?;
+        let previous = self
+            .publisher
+            .cache()
+            .get(&extend.target_tool)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Tool cache error: {e}"), None))?
+            .ok_or_else(|| {
+                McpError::invalid_params(
+                    format!(
+                        "'{}' is not a tool GIRT built, so it cannot be extended; \
+                         use request_capability instead",
+                        extend.target_tool
+                    ),
+                    None,
+                )
+            })?;
+
+        let spec = extend.apply(&previous.spec);
+        spec.validate().map_err(|e| {
+            McpError::invalid_params(
+                e.to_string(),
+                Some(serde_json::json!({ "errors": e.errors })),
+            )
+        })?;
+        let diff = previous.spec.diff(&spec);
 
         tracing::info!(
-            id = %cap_request.id,
-            tool = %tool_name,
-            "Triggering build pipeline"
+            name = %spec.name,
+            features = ?extend.features,
+            "Evaluating capability extension through Creation Gate"
         );
 
-        let orchestrator = Orchestrator::new(self.llm.as_ref())
-            .with_standards(self.coding_standards.clone());
-        let outcome = orchestrator.run(&cap_request).await;
+        let input = GateInput::creation(spec.clone(), RequestSource::Agent);
+        let gate_result = self
+            .engine
+            .evaluate(GateKind::Creation, &input)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Decision engine error: {e}"), None))?;
 
-        match outcome {
-            PipelineOutcome::Built(artifact) => {
-                tracing::info!(
-                    tool = %tool_name,
-                    iterations = artifact.build_iterations,
-                    "Build pipeline succeeded — compiling WASM"
-                );
+        tracing::info!(
+            decision = ?gate_result.decision,
+            layer = %gate_result.layer,
+            "Creation Gate decision"
+        );
+        trace.set_decision(gate_result.decision.status());
+        self.decisions
+            .record(GateKind::Creation, gate_result.decision.status());
 
-                // Compile source → .wasm
-                let compile_input = girt_pipeline::compiler::CompileInput {
-                    source_code: artifact.build_output.source_code.clone(),
+        let extension = Some(extend.extension());
+        self.answer_creation(
+            &gate_result,
+            spec,
+            PipelineOverrides::default(),
+            Some(diff),
+            extension,
+        )
+        .await
+    }
+
+    /// Act on the Creation Gate's decision for `spec`: build it on Allow,
+    /// build the gate's narrowed spec on AllowModified, park it for a person
+    /// on Ask, report a Deny or Defer. `spec_diff` is
+    /// how it differs from the tool it would replace, and is shown in every
+    /// answer but a build; `extension` is set when it extends that tool.
+    async fn answer_creation(
+        &self,
+        gate_result: &LayeredDecision,
+        spec: CapabilitySpec,
+        pipeline: PipelineOverrides,
+        spec_diff: Option<SpecDiff>,
+        extension: Option<Extension>,
+    ) -> Result<CallToolResult, McpError> {
+        let with_diff = |mut response: serde_json::Value| {
+            if let Some(diff) = &spec_diff {
+                response["spec_diff"] = serde_json::json!(diff);
+            }
+            response
+        };
+        match &gate_result.decision {
+            Decision::Allow => {
+                // Creation allowed -- trigger build pipeline
+                self.trigger_build(spec, pipeline, extension, Vec::new())
+                    .await
+            }
+            Decision::AllowModified {
+                spec: modified,
+                changes,
+            } => {
+                tracing::info!(
+                    tool = %spec.name,
+                    changes = ?changes,
+                    "Creation Gate narrowed the requested spec"
+                );
+                self.trigger_build(modified.clone(), pipeline, extension, changes.clone())
+                    .await
+            }
+            Decision::Deny { reason } => {
+                self.recent_denials.record(&spec.name, reason);
+                Ok(self.json_result(with_diff(self.gate_result_json(gate_result)), true))
+            }
+            Decision::Ask { prompt, .. } => {
+                // Park the spec so a human can answer the prompt later.
+                let source_url = match (&spec_diff, &self.tool_sync) {
+                    (Some(_), Some(sync)) => Some(sync.source_url(&spec.name)),
+                    _ => None,
+                };
+                // The agent made the request, so it may only answer it when
+                // the operator allows that, and never under an always_ask rule.
+                let operator_only =
+                    !self.agent_approvals || gate_result.layer == DecisionLayer::ForceAsk;
+                let request = ApprovalRequest::new(spec, prompt.clone())
+                    .with_pipeline(pipeline)
+                    .with_spec_diff(spec_diff.clone())
+                    .with_source_url(source_url)
+                    .with_extension(extension)
+                    .with_operator_only(operator_only);
+                let approval = self.approvals.create(request);
+                tracing::info!(
+                    token = %approval.token,
+                    tool = %approval.spec.name,
+                    "Capability request awaiting approval"
+                );
+                let response =
+                    with_approval(with_diff(self.gate_result_json(gate_result)), &approval);
+                Ok(self.json_result(response, false))
+            }
+            Decision::Defer { target } => {
+                let response = self.deferral_json(gate_result, target).await;
+                Ok(self.json_result(with_diff(response), false))
+            }
+        }
+    }
+
+    /// How `spec` differs from the loaded tool of the same name, if any.
+    async fn existing_spec_diff(&self, spec: &CapabilitySpec) -> Option<SpecDiff> {
+        // A quarantined tool cannot be called, so it is not available.
+        match self.runtime.tool_meta(&spec.name).await {
+            Some(meta) if meta.quarantined.is_none() => {}
+            _ => return None,
+        }
+        match self.publisher.cache().get(&spec.name).await {
+            Ok(artifact) => artifact.map(|a| a.spec.diff(spec)),
+            Err(e) => {
+                tracing::warn!(tool = %spec.name, error = %e, "Failed to read tool manifest");
+                None
+            }
+        }
+    }
+
+    /// Resolve a pending approval on behalf of the agent's user.
+    async fn handle_approve_capability(
+        &self,
+        request: CallToolRequestParams,
+    ) -> Result<CallToolResult, McpError> {
+        let args = request.arguments.as_ref();
+        let token = args
+            .and_then(|a| a.get("approval_token"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::invalid_params("Missing required field: approval_token", None))?;
+        let approve = args
+            .and_then(|a| a.get("approve"))
+            .and_then(|v| v.as_bool())
+            .ok_or_else(|| McpError::invalid_params("Missing required field: approve", None))?;
+        let reason = args
+            .and_then(|a| a.get("reason"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+
+        let modified = match args.and_then(|a| a.get("spec")) {
+            None | Some(serde_json::Value::Null) => None,
+            Some(_) if !approve => {
+                return Err(McpError::invalid_params(
+                    "spec can only be given when approving",
+                    None,
+                ));
+            }
+            Some(spec) => {
+                let spec = parse_capability_spec(spec.as_object())?;
+                let changes = args
+                    .and_then(|a| a.get("changes"))
+                    .and_then(|v| v.as_array())
+                    .map(|changes| {
+                        changes
+                            .iter()
+                            .filter_map(|c| c.as_str())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Some((spec, changes))
+            }
+        };
+        // An unknown or expired token is reported by resolve_approval.
+        if let Some(pending) = self.approvals.get(token) {
+            // Also covers requests parked before agent approvals were
+            // turned off.
+            if pending.operator_only || !self.agent_approvals {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Only an operator can answer this request, by running \
+                         `girt approve {token}` or `girt reject {token}`"
+                    ),
+                    None,
+                ));
+            }
+            // A narrowed spec must pass what the gate would have checked.
+            if let Some((spec, _)) = &modified {
+                let input = GateInput::creation(pending.spec, RequestSource::Agent);
+                self.engine
+                    .check_modified_spec(&input, spec)
+                    .await
+                    .map_err(|problem| {
+                        McpError::invalid_params(
+                            format!("The changed spec cannot be built: {problem}"),
+                            None,
+                        )
+                    })?;
+            }
+        }
+
+        let resolution = if approve {
+            Resolution::Approve
+        } else {
+            Resolution::Reject
+        };
+        self.resolve_approval(token, resolution, reason, modified)
+            .await
+    }
+
+    /// Act on a resolved approval: build on approve, from `modified` if the
+    /// approver narrowed the spec, and cache a Deny on reject. A parked
+    /// tool call runs on approve instead.
+    async fn resolve_approval(
+        &self,
+        token: &str,
+        resolution: Resolution,
+        reason: Option<String>,
+        modified: Option<(CapabilitySpec, Vec<String>)>,
+    ) -> Result<CallToolResult, McpError> {
+        let approval = self.approvals.take(token).map_err(|e| match e {
+            ApprovalError::UnknownToken(_) | ApprovalError::Expired(_) => {
+                McpError::invalid_params(e.to_string(), None)
+            }
+            other => McpError::internal_error(other.to_string(), None),
+        })?;
+        tracing::info!(
+            token = %token,
+            tool = %approval.spec.name,
+            ?resolution,
+            "Pending approval resolved"
+        );
+        if let Some(call) = approval.call {
+            return self
+                .resolve_call(&approval.spec.name, call, resolution, reason)
+                .await;
+        }
+
+        match resolution {
+            Resolution::Approve => {
+                let (spec, changes) = modified.unwrap_or((approval.spec, Vec::new()));
+                self.trigger_build(spec, approval.pipeline, approval.extension, changes)
+                    .await
+            }
+            Resolution::Reject => {
+                let reason = reason.unwrap_or_else(|| "Rejected by operator".into());
+                self.recent_denials.record(&approval.spec.name, &reason);
+                let input = GateInput::creation(approval.spec, RequestSource::Agent);
+                self.engine
+                    .creation_cache()
+                    .store_for(
+                        input.subject(),
+                        input.hash(),
+                        Decision::Deny {
+                            reason: reason.clone(),
+                        },
+                    )
+                    .await;
+                let response = serde_json::json!({
+                    "status": "rejected",
+                    "tool_name": input.subject(),
+                    "reason": reason,
+                });
+                Ok(self.json_result(response, true))
+            }
+        }
+    }
+
+    /// Act on a resolved tool call: run it, past the Execution Gate, on
+    /// approve, and cache a Deny for the same call on reject. `force_ask`
+    /// runs before the cache, so an `always_ask` tool is asked again every
+    /// time; the cached Deny answers repeats of calls asked by later layers.
+    async fn resolve_call(
+        &self,
+        tool_name: &str,
+        call: PendingCall,
+        resolution: Resolution,
+        reason: Option<String>,
+    ) -> Result<CallToolResult, McpError> {
+        let arguments = match call.arguments {
+            serde_json::Value::Object(args) => Some(args),
+            _ => None,
+        };
+        match resolution {
+            Resolution::Approve => {
+                let request = CallToolRequestParams {
+                    meta: None,
+                    name: tool_name.to_string().into(),
+                    arguments,
+                    task: None,
+                };
+                self.call_runtime_tool(&request, &RequestTrace::new(), false)
+                    .await
+            }
+            Resolution::Reject => {
+                let reason = reason.unwrap_or_else(|| "Rejected by operator".into());
+                let meta = self.runtime.tool_meta(tool_name).await;
+                let arguments = call_arguments(arguments.as_ref());
+                let input = execution_input(tool_name, &arguments, meta.as_ref());
+                self.engine
+                    .execution_cache()
+                    .store_for(
+                        input.subject(),
+                        input.hash(),
+                        Decision::Deny {
+                            reason: reason.clone(),
+                        },
+                    )
+                    .await;
+                let response = serde_json::json!({
+                    "status": "rejected",
+                    "tool_name": tool_name,
+                    "reason": reason,
+                });
+                Ok(self.json_result(response, true))
+            }
+        }
+    }
+
+    /// Apply `girt approve` / `girt reject` resolutions from the approval
+    /// spool in the background, and expire stale approvals.
+    pub fn spawn_approval_watcher(&self) -> tokio::task::JoinHandle<()> {
+        let proxy = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(APPROVAL_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                proxy.approvals.prune_expired();
+                for (token, resolution) in proxy.approvals.spooled_resolutions() {
+                    match proxy.resolve_approval(&token, resolution, None, None).await {
+                        Ok(result) => tracing::info!(
+                            token = %token,
+                            is_error = ?result.is_error,
+                            "Applied operator approval"
+                        ),
+                        Err(e) => tracing::warn!(token = %token, error = %e, "Failed to apply operator approval"),
+                    }
+                }
+            }
+        })
+    }
+
+    /// Reject arguments that violate the tool's `input_schema` before the
+    /// runtime spends an instantiation on them.
+    async fn check_arguments(
+        &self,
+        tool_name: &str,
+        args: &serde_json::Value,
+    ) -> Result<(), McpError> {
+        if !self.validate_arguments {
+            return Ok(());
+        }
+        let Some(meta) = self.runtime.tool_meta(tool_name).await else {
+            return Ok(());
+        };
+        let violations = validate_arguments(&meta.input_schema, args);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        tracing::warn!(tool = %tool_name, ?violations, "Rejected arguments that violate input_schema");
+        Err(McpError::invalid_params(
+            format!("Invalid arguments for '{tool_name}': {}", violations.join("; ")),
+            Some(serde_json::json!({ "violations": violations })),
+        ))
+    }
+
+    /// Reject calls to tools that are neither built-in nor loaded in the
+    /// runtime, dropping any cached gate decisions left over for the name.
+    async fn ensure_tool_loaded(&self, tool_name: &str) -> Result<(), McpError> {
+        if is_builtin_tool(tool_name) || self.runtime.has_tool(tool_name).await {
+            return Ok(());
+        }
+        let stale = self.engine.invalidate_execution(tool_name).await;
+        self.refresh_known_tools().await;
+        tracing::warn!(tool = %tool_name, stale_cache_entries = stale, "Call to unknown tool rejected");
+        Err(tool_not_found(tool_name))
+    }
+
+    /// Report runtime metadata and WASM integrity for a loaded tool.
+    async fn handle_tool_info(
+        &self,
+        request: CallToolRequestParams,
+    ) -> Result<CallToolResult, McpError> {
+        let name = request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get("name"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::invalid_params("Missing required field: name", None))?;
+
+        let Some(meta) = self.runtime.tool_meta(name).await else {
+            return Err(McpError::invalid_params(
+                format!("Tool '{name}' is not loaded"),
+                None,
+            ));
+        };
+
+        // The manifest digest is absent for tools loaded outside the publish path.
+        let manifest_wasm = match self.publisher.cache().wasm_digest(name).await {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::warn!(tool = %name, error = %e, "Failed to read tool manifest");
+                None
+            }
+        };
+        let integrity = match &manifest_wasm {
+            Some(d) if d.sha256 == meta.wasm_hash => "verified",
+            Some(_) => "mismatch",
+            None => "unknown",
+        };
+
+        let response = serde_json::json!({
+            "tool_name": meta.tool_name,
+            "component_id": meta.component_id,
+            "description": meta.description,
+            "wasm_hash": meta.wasm_hash,
+            "wasm_size_bytes": meta.wasm_size_bytes,
+            "built_at": meta.built_at,
+            "loaded_at": meta.loaded_at,
+            "deprecated": meta.deprecated,
+            "replaced_by": meta.replaced_by,
+            "deprecated_at": meta.deprecated_at,
+            "oci_reference": meta.oci_reference,
+            "source_repo_path": meta.source_repo_path,
+            "manifest_wasm": manifest_wasm,
+            "integrity": integrity,
+        });
+        Ok(self.json_result(response, false))
+    }
+
+    /// The rules this proxy builds and gates by, for `girt_info`. Assembled
+    /// from settings an agent may know: deny rules are described, never
+    /// shown as patterns, and no credentials or paths are included.
+    async fn info(&self) -> serde_json::Value {
+        let budget = self.engine.constraint_budget();
+        let caps = &self.pipeline_caps;
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "creation_gate": {
+                "mode": self.creation_gate_mode(),
+                "min_allow_confidence": self.min_allow_confidence,
+                "deny_rules": self.engine.creation_deny_rules(),
+                "constraint_budget": budget.limits_for(RequestSource::Agent),
+                "secrets_require_operator": budget.secrets_require_operator,
+            },
+            "pipeline": {
+                "max_iterations": caps.max_iterations,
+                "token_budget": caps.token_budget,
+                "languages": caps.languages,
+                "max_tier": caps.max_tier,
+                "wit_version": self.wit_version,
+            },
+            "approvals": {
+                "available": true,
+                "ttl_secs": self.approvals.ttl().as_secs(),
+                "operator_cli": self.approvals.is_spooled(),
+                "agent_approvals": self.agent_approvals,
+            },
+            "stdlib_tools": standard_library()
+                .into_iter()
+                .map(|spec| spec.name)
+                .collect::<Vec<_>>(),
+            "loaded_tools": self.runtime.list_tools().await.len(),
+            "fault_injection": self.runtime.fault_injector().map(|f| f.describe()),
+        })
+    }
+
+    /// The `girt://state` document, as of now.
+    async fn state_document(&self) -> StateDocument {
+        let mut tools = ToolCounts::default();
+        for meta in self.runtime.list_tools().await {
+            tools.add(meta.deprecated, meta.drift_detected);
+        }
+        let queue = match &self.build_queue {
+            Some(queue) => queue
+                .depths()
+                .await
+                .inspect_err(|e| tracing::warn!(error = %e, "Failed to count the build queue"))
+                .ok(),
+            None => None,
+        };
+        StateDocument {
+            generated_at_ms: now_ms(),
+            tools,
+            queue,
+            builds: Some(self.in_flight.running()),
+            decisions: Some(self.decisions.summary()),
+            approvals: ApprovalBacklog::of(&self.approvals.pending()),
+        }
+    }
+
+    /// An orchestrator for `request`, configured like this proxy.
+    fn orchestrator(&self, request: &CapabilityRequest) -> Orchestrator<'_> {
+        let orchestrator = Orchestrator::new(self.llm.as_ref())
+            .with_standards(self.coding_standards.clone())
+            .with_previous_denials(self.recent_denials.reasons(&request.spec.name))
+            .with_wit_version(self.wit_version)
+            .with_sampling(self.sampling)
+            .with_static_checks(self.static_checks)
+            .with_blocking_policy(self.blocking)
+            .with_host_capabilities(host_capabilities())
+            .with_overrides(&request.pipeline_overrides.capped(&self.pipeline_caps));
+        match self.failures.as_deref() {
+            Some(store) => orchestrator.with_failure_store(store),
+            None => orchestrator,
+        }
+    }
+
+    /// Trigger the build pipeline for an approved capability request. With
+    /// `extension`, the published tool named like `spec` is extended into
+    /// it and replaced as its next revision. `changes` says how the gate or
+    /// an approver narrowed the requested spec into `spec`, and is reported
+    /// in every answer.
+    async fn trigger_build(
+        &self,
+        spec: CapabilitySpec,
+        pipeline: PipelineOverrides,
+        extension: Option<Extension>,
+        changes: Vec<String>,
+    ) -> Result<CallToolResult, McpError> {
+        let cap_request =
+            CapabilityRequest::new(spec, RequestSource::Agent).with_overrides(pipeline);
+        let tool_name = cap_request.spec.name.clone();
+
+        let with_changes = |mut response: serde_json::Value| {
+            if !changes.is_empty() {
+                response["changes"] = serde_json::json!(changes);
+            }
+            response
+        };
+
+        // Registered until this build ends, including its wait for a slot.
+        let in_flight = match self.in_flight.start(&cap_request.spec, &cap_request.id) {
+            Ok(guard) => guard,
+            Err(existing) => {
+                let response = with_changes(already_queued_json(&existing));
+                return Ok(self.json_result(response, false));
+            }
+        };
+        // Builds of another spec under this name are not deduplicated, but
+        // the agent is told it will not get the name if one publishes first.
+        let conflicts = self.in_flight.conflicts(&cap_request.spec);
+        if !conflicts.is_empty() {
+            tracing::warn!(
+                tool = %tool_name,
+                conflicts = ?conflicts,
+                "A different spec with this name is already being built"
+            );
+        }
+        let with_conflicts = |mut response: serde_json::Value| {
+            if !conflicts.is_empty() {
+                response["conflicts"] = serde_json::json!(conflicts);
+            }
+            with_changes(response)
+        };
+
+        // Held until the tool is loaded; the semaphore is never closed.
+        let _slot = self
+            .build_slots
+            .acquire()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Build slots closed: {e}"), None))?;
+        in_flight.set_phase(BuildPhase::Pipeline);
+        tracing::info!(
+            id = %cap_request.id,
+            tool = %tool_name,
+            "Triggering build pipeline"
+        );
+
+        let orchestrator = self.orchestrator(&cap_request);
+        let (outcome, previous) = match &extension {
+            None => (orchestrator.run(&cap_request).await, None),
+            Some(extension) => {
+                let previous = self
+                    .publisher
+                    .cache()
+                    .get(&tool_name)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("Tool cache error: {e}"), None))?
+                    .ok_or_else(|| {
+                        McpError::invalid_params(
+                            format!(
+                                "'{tool_name}' is no longer published, so it cannot be extended"
+                            ),
+                            None,
+                        )
+                    })?;
+                let outcome = orchestrator
+                    .run_extension(&cap_request, &previous, &extension.features)
+                    .await;
+                (outcome, Some(previous))
+            }
+        };
+
+        match outcome {
+            PipelineOutcome::Built(mut artifact) => {
+                // An extension replaces the tool it extends as its next revision.
+                if let Some(previous) = &previous {
+                    artifact.requested_name = previous.requested_name.clone();
+                    artifact.revision = previous.revision + 1;
+                }
+                tracing::info!(
+                    tool = %tool_name,
+                    iterations = artifact.build_iterations,
+                    "Build pipeline succeeded — compiling WASM"
+                );
+
+                // Compile source → .wasm
+                in_flight.set_phase(BuildPhase::Compiling);
+                let compile_input = CompileInput {
+                    source_code: artifact.build_output.source_code.clone(),
                     wit_definition: String::new(), // uses default girt-tool world
                     tool_name: artifact.spec.name.clone(),
                     tool_version: "0.1.0".into(),
+                    files: artifact.build_output.files.clone(),
                 };
-                let compiler = girt_pipeline::compiler::WasmCompiler::new();
-
-                match compiler.compile(&compile_input).await {
+                match self.compiler.compile(&compile_input).await {
                     Ok(compiled) => {
                         tracing::info!(
                             tool = %tool_name,
@@ -440,63 +2570,91 @@ impl GirtProxy {
                         );
 
                         // Publish with wasm
-                        let publish_result = match self
-                            .publisher
-                            .publish_with_wasm(&artifact, &compiled.wasm_path)
-                            .await
-                        {
+                        in_flight.set_phase(BuildPhase::Publishing);
+                        let published = if previous.is_some() {
+                            self.publisher
+                                .republish_with_wasm(&mut artifact, &compiled.wasm_path)
+                                .await
+                        } else {
+                            self.publisher
+                                .publish_with_wasm(&mut artifact, &compiled.wasm_path)
+                                .await
+                        };
+                        let publish_result = match published {
                             Ok(r) => r,
                             Err(e) => {
                                 tracing::error!(error = %e, "Failed to publish artifact");
-                                return Ok(make_tool_result(
-                                    vec![Content::text(format!(
-                                        r#"{{"status":"publish_failed","error":"{e}"}}"#
-                                    ))],
-                                    true,
-                                ));
+                                let response = serde_json::json!({
+                                    "status": "publish_failed",
+                                    "tool_name": tool_name,
+                                    "error": e.to_string(),
+                                    "error_code": e.code(),
+                                });
+                                return Ok(self.json_result(with_conflicts(response), true));
                             }
                         };
 
                         // Load into girt-runtime
                         let wasm_path = publish_result.local_path.join("tool.wasm");
-                        let meta = girt_runtime::ComponentMeta {
-                            component_id: format!("{}@0.1.0", artifact.spec.name),
-                            tool_name: artifact.spec.name.clone(),
-                            description: artifact.spec.description.clone(),
-                            input_schema: artifact.spec.inputs.clone(),
-                            wasm_hash: String::new(), // computed by storage
-                            built_at: std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .map(|d| d.as_millis() as u64)
-                                .unwrap_or(0),
-                        };
+                        let meta = component_meta(&artifact, &publish_result);
+
+                        // Decisions cached for a previous build of this name no longer apply.
+                        self.engine.invalidate_execution(&artifact.spec.name).await;
 
+                        let mut wasm_hash = None;
+                        // A tool that failed to load is not mirrored.
+                        let mut tool_sync = "skipped";
                         if let Err(e) = self.runtime.load_component(&wasm_path, meta).await {
                             tracing::error!(error = %e, tool = %tool_name, "Failed to load component into runtime");
                         } else {
+                            // Mirror into the source repo without delaying the
+                            // response. Only now, so the path it records lands
+                            // on the meta just loaded.
+                            tool_sync = self.spawn_tool_sync(&artifact, &publish_result);
+                            wasm_hash = self
+                                .runtime
+                                .tool_meta(&artifact.spec.name)
+                                .await
+                                .map(|m| m.wasm_hash);
                             // Notify agent that tool list changed
                             self.notify_tools_changed().await;
                         }
 
-                        let response = serde_json::json!({
-                            "status": "built",
+                        let status = if previous.is_some() {
+                            "extended"
+                        } else {
+                            "built"
+                        };
+                        let mut response = serde_json::json!({
+                            "status": status,
                             "tool_name": publish_result.tool_name,
+                            "revision": artifact.revision,
+                            "wasm_hash": wasm_hash,
+                            "tool_sync": tool_sync,
                             "build_iterations": artifact.build_iterations,
                             "tests_run": artifact.qa_result.tests_run,
                             "tests_passed": artifact.qa_result.tests_passed,
                             "exploits_attempted": artifact.security_result.exploits_attempted,
                             "exploits_succeeded": artifact.security_result.exploits_succeeded,
                         });
-                        Ok(make_tool_result(vec![Content::text(response.to_string())], false))
+                        // Published under another name so it does not
+                        // replace a different tool.
+                        if let Some(requested) = &artifact.requested_name {
+                            response["requested_name"] = requested.clone().into();
+                        }
+                        Ok(self.json_result(with_conflicts(response), false))
                     }
                     Err(e) => {
                         tracing::error!(tool = %tool_name, error = %e, "WASM compilation failed");
-                        Ok(make_tool_result(
-                            vec![Content::text(format!(
-                                r#"{{"status":"compile_failed","tool_name":"{tool_name}","error":"{e}"}}"#
-                            ))],
-                            true,
-                        ))
+                        self.keep_failure(&e, &FailureReport::compile_failure(&artifact, &e))
+                            .await;
+                        let response = serde_json::json!({
+                            "status": "compile_failed",
+                            "tool_name": tool_name,
+                            "error": e.to_string(),
+                            "error_code": e.code(),
+                        });
+                        Ok(self.json_result(with_conflicts(response), true))
                     }
                 }
             }
@@ -510,42 +2668,1237 @@ impl GirtProxy {
                     "status": "recommend_extend",
                     "target_tool": target,
                     "features": features,
-                    "message": format!("Consider extending '{}' instead of building a new tool", target),
+                    "message": format!(
+                        "Consider extending '{target}' instead of building a new tool: \
+                         call extend_capability with target_tool '{target}' and these features"
+                    ),
                 });
-                Ok(make_tool_result(
-                    vec![Content::text(response.to_string())],
-                    false,
-                ))
+                Ok(self.json_result(with_conflicts(response), false))
             }
-            PipelineOutcome::Failed(e) => {
+            PipelineOutcome::Failed(e, report) => {
                 tracing::error!(
                     tool = %tool_name,
                     error = %e,
                     "Build pipeline failed"
                 );
+                self.keep_failure(&e, &report).await;
                 let response = serde_json::json!({
                     "status": "build_failed",
                     "error": e.to_string(),
+                    "error_code": e.code(),
                 });
-                Ok(make_tool_result(
-                    vec![Content::text(response.to_string())],
-                    true,
-                ))
+                Ok(self.json_result(with_conflicts(response), true))
             }
         }
     }
 
-    /// Send a tools/list_changed notification to the connected agent.
+    async fn keep_failure(&self, error: &PipelineError, report: &FailureReport) {
+        if let Some(store) = &self.failures {
+            store.record(error, report).await;
+        }
+    }
+
+    /// Start a background sync of a published tool to the source repo.
+    ///
+    /// Call once the tool is loaded: a finished sync records the source repo
+    /// path on the loaded tool's meta. Returns the sync state for the build
+    /// response: `"disabled"` when no source repo is configured, otherwise
+    /// `"scheduled"`. The outcome is logged when the task finishes; failures
+    /// never fail the build.
+    fn spawn_tool_sync(
+        &self,
+        artifact: &BuildArtifact,
+        publish_result: &PublishResult,
+    ) -> &'static str {
+        let Some(tool_sync) = self.tool_sync.clone() else {
+            return "disabled";
+        };
+        let artifact = artifact.clone();
+        let publish_result = publish_result.clone();
+        let runtime = Arc::clone(&self.runtime);
+        tokio::spawn(async move {
+            let tool = &artifact.spec.name;
+            match tool_sync.sync(&artifact, &publish_result).await {
+                Ok(outcome) => {
+                    tracing::info!(tool = %tool, ?outcome, "Tool sync finished");
+                    let path = tool_sync.source_url(tool);
+                    let recorded = runtime
+                        .update_meta(tool, |meta| meta.source_repo_path = Some(path.clone()))
+                        .await;
+                    if let Err(e) = recorded {
+                        tracing::warn!(tool = %tool, error = %e, "Failed to record source repo path");
+                    }
+                }
+                Err(e) => tracing::warn!(tool = %tool, error = %e, "Tool sync failed (non-fatal)"),
+            }
+        });
+        "scheduled"
+    }
+
+    /// Tell the decision engine which tools are loaded now, so the Creation
+    /// Gate only defers to tools an agent can call: quarantined ones are left
+    /// out. Called at startup and whenever a tool is loaded or found missing.
+    pub async fn refresh_known_tools(&self) {
+        let tools = self
+            .runtime
+            .list_tools()
+            .await
+            .iter()
+            .filter(|meta| meta.quarantined.is_none())
+            .map(|meta| KnownSpec::new(&meta.tool_name, &meta.description))
+            .collect();
+        self.engine.refresh_known_tools(tools);
+    }
+
+    /// Send a tools/list_changed notification to every connected client.
     async fn notify_tools_changed(&self) {
-        let peer_lock = self.server_peer.lock().await;
-        if let Some(peer) = peer_lock.as_ref() {
-            if let Err(e) = peer.notify_tool_list_changed().await {
-                tracing::warn!(error = %e, "Failed to send tools/list_changed notification");
+        self.refresh_known_tools().await;
+        self.refresh_status().await;
+        let notified = self.peers.notify_tools_changed().await;
+        tracing::info!(clients = notified, "Sent tools/list_changed notification");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_core::layers::policy::{ConstraintPatterns, PolicyPattern};
+    use girt_pipeline::cache::ToolCache;
+    use girt_pipeline::llm::StubLlmClient;
+    use girt_pipeline::types::ResourceTier;
+    use tempfile::TempDir;
+
+    fn test_proxy(tmp: &TempDir) -> GirtProxy {
+        let runtime = LifecycleManager::new(Some(tmp.path().join("components"))).unwrap();
+        GirtProxy::new(
+            Arc::new(DecisionEngine::with_defaults()),
+            Arc::new(StubLlmClient::constant("stub response")),
+            Arc::new(Publisher::new(ToolCache::new(tmp.path().join("tools")))),
+            Arc::new(runtime),
+            CodingStandards::default(),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn unknown_tool_is_rejected_before_the_gate() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+
+        let err = proxy.ensure_tool_loaded("no_such_tool").await.unwrap_err();
+        assert!(err.message.contains("no_such_tool"));
+        // Nothing was evaluated, so nothing was cached.
+        assert!(proxy.engine.execution_cache().is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn removed_tool_drops_cached_allow() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+
+        // Simulate an Allow cached while the tool was still loaded.
+        let input = GateInput::Execution(ExecutionRequest {
+            tool_name: "removed_tool".into(),
+            arguments: serde_json::json!({"x": 1}),
+            tool: None,
+        });
+        proxy
+            .engine
+            .execution_cache()
+            .store_for("removed_tool", input.hash(), Decision::Allow)
+            .await;
+
+        assert!(proxy.ensure_tool_loaded("removed_tool").await.is_err());
+        assert!(proxy.engine.execution_cache().is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn unloaded_tool_is_no_longer_deferred_to() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+        let meta = ComponentMeta {
+            component_id: "weather_forecast@0.1.0".into(),
+            tool_name: "weather_forecast".into(),
+            description: "Get weather forecast data for a city".into(),
+            deprecated: false,
+            ..deprecated_meta(None, None)
+        };
+        let echo = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/echo.wasm");
+        proxy
+            .runtime
+            .load_component(std::path::Path::new(echo), meta)
+            .await
+            .unwrap();
+        proxy.refresh_known_tools().await;
+
+        let input = GateInput::creation(
+            CapabilitySpec::builder()
+                .name("city_weather_forecast")
+                .description("Get the weather forecast for a city")
+                .build()
+                .unwrap(),
+            RequestSource::Agent,
+        );
+        let evaluate = || proxy.engine.evaluate(GateKind::Creation, &input);
+        let before = evaluate().await.unwrap();
+        assert!(
+            matches!(before.decision, Decision::Defer { .. }),
+            "{before:?}"
+        );
+
+        proxy
+            .runtime
+            .unload_component("weather_forecast@0.1.0")
+            .await
+            .unwrap();
+        assert!(proxy.ensure_tool_loaded("weather_forecast").await.is_err());
+        let after = evaluate().await.unwrap();
+        assert!(
+            !matches!(after.decision, Decision::Defer { .. }),
+            "{after:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn builtins_are_not_short_circuited() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+
+        for name in BUILTIN_TOOL_NAMES {
+            assert!(proxy.ensure_tool_loaded(name).await.is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn listed_builtins_are_the_reserved_ones() {
+        let mut listed: Vec<String> = builtin_tools(16)
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .collect();
+        listed.sort();
+        let mut reserved = BUILTIN_TOOL_NAMES.to_vec();
+        reserved.sort();
+        assert_eq!(listed, reserved);
+    }
+
+    #[tokio::test]
+    async fn decision_trace_included_when_configured() {
+        let tmp = TempDir::new().unwrap();
+        let input = GateInput::Execution(ExecutionRequest {
+            tool_name: "shell_exec".into(),
+            arguments: serde_json::Value::Null,
+            tool: None,
+        });
+        let proxy = test_proxy(&tmp).with_security_config(&SecurityConfig {
+            include_decision_trace: true,
+            ..Default::default()
+        });
+        let gate_result = proxy
+            .engine
+            .evaluate(GateKind::Execution, &input)
+            .await
+            .unwrap();
+
+        let value = proxy.gate_result_json(&gate_result);
+        assert_eq!(value["status"], "denied");
+        let trace = value["trace"].as_array().unwrap();
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0]["layer"], "policy_rules");
+        assert_eq!(trace[0]["outcome"], "decided");
+    }
+
+    #[tokio::test]
+    async fn decision_trace_omitted_by_default() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+        let input = GateInput::Execution(ExecutionRequest {
+            tool_name: "shell_exec".into(),
+            arguments: serde_json::Value::Null,
+            tool: None,
+        });
+        let gate_result = proxy
+            .engine
+            .evaluate(GateKind::Execution, &input)
+            .await
+            .unwrap();
+
+        // No subscriber is installed in tests, so debug is not enabled.
+        assert!(proxy.gate_result_json(&gate_result).get("trace").is_none());
+    }
+
+    #[tokio::test]
+    async fn girt_info_describes_rules_without_their_patterns() {
+        let tmp = TempDir::new().unwrap();
+        let approvals = ApprovalStore::new(Duration::from_secs(600))
+            .with_spool_dir(tmp.path().join("private-spool"));
+        let proxy = test_proxy(&tmp).with_approvals(approvals);
+        proxy
+            .engine
+            .reload_policy(
+                vec![PolicyPattern {
+                    description: "No paste sites".into(),
+                    name_pattern: Some(r"(?i)pastebin_upload".into()),
+                    description_pattern: None,
+                    constraint_patterns: Some(ConstraintPatterns {
+                        network_deny: Some(vec![r"pastebin\.com".into()]),
+                        storage_deny: None,
+                        secrets_deny: None,
+                    }),
+                }],
+                vec![],
+            )
+            .await;
+
+        let result = proxy
+            .handle_call(call_params(serde_json::json!({
+                "name": "girt_info",
+                "arguments": {}
+            })))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        let info = result.structured_content.unwrap();
+
+        let gate = &info["creation_gate"];
+        assert_eq!(gate["mode"], "stub");
+        let deny_rules = gate["deny_rules"].as_array().unwrap();
+        assert!(
+            deny_rules.contains(&"No paste sites".into()),
+            "{deny_rules:?}"
+        );
+        assert_eq!(gate["constraint_budget"]["max_network_hosts"], 8);
+        assert_eq!(gate["secrets_require_operator"], true);
+        assert_eq!(info["pipeline"]["max_iterations"], 5);
+        assert_eq!(info["pipeline"]["languages"], serde_json::json!(["rust"]));
+        assert_eq!(info["pipeline"]["wit_version"], "0.1.0");
+        assert_eq!(info["approvals"]["ttl_secs"], 600);
+        assert_eq!(info["approvals"]["operator_cli"], true);
+        assert_eq!(info["approvals"]["agent_approvals"], false);
+        let stdlib = info["stdlib_tools"].as_array().unwrap();
+        assert!(stdlib.contains(&"http_client".into()), "{stdlib:?}");
+        assert_eq!(info["loaded_tools"], 0);
+        assert!(info["fault_injection"].is_null());
+
+        // Patterns and local paths stay out of what the agent sees.
+        let text = info.to_string();
+        for hidden in ["pastebin", "private-spool"] {
+            assert!(!text.contains(hidden), "{hidden} leaked: {text}");
+        }
+    }
+
+    fn call_params(value: serde_json::Value) -> CallToolRequestParams {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn request_log_records_each_call_without_argument_values() {
+        let tmp = TempDir::new().unwrap();
+        let log = RequestLog::open(tmp.path().join("requests.jsonl")).unwrap();
+        let log_path = log.path().to_path_buf();
+        let proxy = test_proxy(&tmp).with_request_log(log);
+
+        let err = proxy
+            .handle_call(call_params(serde_json::json!({
+                "name": "no_such_tool",
+                "arguments": { "api_token": "hunter2" }
+            })))
+            .await
+            .unwrap_err();
+        proxy
+            .handle_call(call_params(serde_json::json!({
+                "name": "girt_tool_info",
+                "arguments": { "name": "also_missing" }
+            })))
+            .await
+            .ok();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&log_path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+
+        let first = &lines[0];
+        assert_eq!(first["tool_name"], "no_such_tool");
+        assert_eq!(first["error_class"], "invalid_request");
+        assert_eq!(first["argument_sizes"]["api_token"], "\"hunter2\"".len());
+        assert!(!lines[0].to_string().contains("hunter2"));
+
+        // The client sees the same id that was logged.
+        let request_id = first["request_id"].as_str().unwrap();
+        assert!(err.message.contains(request_id));
+        assert_ne!(lines[1]["request_id"], first["request_id"]);
+    }
+
+    #[test]
+    fn error_results_are_tagged_with_request_id() {
+        let denied = make_tool_result(
+            decision_to_json(&Decision::Deny {
+                reason: "no".into(),
+            }),
+            true,
+            true,
+        );
+        let tagged = tag_with_request_id(Ok(denied), "req-1").unwrap();
+        let text: serde_json::Value = serde_json::from_str(&text_of(&tagged)).unwrap();
+        assert_eq!(text["request_id"], "req-1");
+        assert_eq!(tagged.structured_content.unwrap()["request_id"], "req-1");
+
+        let raw = ResultBuilder::new(false).tool_error("boom".into());
+        let raw = tag_with_request_id(Ok(raw), "req-2").unwrap();
+        assert_eq!(text_of(&raw), "boom (request_id: req-2)");
+
+        let ok = make_tool_result(serde_json::json!({"x": 1}), false, true);
+        let untouched = tag_with_request_id(Ok(ok), "req-3").unwrap();
+        assert!(!text_of(&untouched).contains("req-3"));
+    }
+
+    #[test]
+    fn request_capability_rejects_missing_and_malformed_specs() {
+        let err = parse_capability_spec(None).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+
+        let args = serde_json::json!({ "name": "Fetch URL", "description": "" });
+        let err = parse_capability_spec(args.as_object()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        let fields: Vec<_> = err.data.as_ref().unwrap()["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["field"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(fields, vec!["name", "description"]);
+
+        let args = serde_json::json!({ "name": "fetch_url", "description": "Fetch a URL" });
+        assert_eq!(parse_capability_spec(args.as_object()).unwrap().name, "fetch_url");
+    }
+
+    #[test]
+    fn request_capability_pipeline_overrides_are_optional_and_strict() {
+        let args = serde_json::json!({ "name": "fetch_url", "description": "Fetch a URL" });
+        let overrides = parse_pipeline_overrides(args.as_object()).unwrap();
+        assert!(overrides.is_empty());
+
+        let args = serde_json::json!({
+            "name": "fetch_url",
+            "description": "Fetch a URL",
+            "pipeline": { "max_iterations": 2, "resource_tier": "minimal" }
+        });
+        let overrides = parse_pipeline_overrides(args.as_object()).unwrap();
+        assert_eq!(overrides.max_iterations, Some(2));
+        assert_eq!(overrides.resource_tier, Some(ResourceTier::Minimal));
+
+        let args = serde_json::json!({ "pipeline": { "iterations": 2 } });
+        let err = parse_pipeline_overrides(args.as_object()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn argument_check_skips_tools_without_known_schema() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+
+        // Not loaded, so there is no schema to check against.
+        let args = serde_json::json!({ "anything": [1, 2, 3] });
+        assert!(proxy.check_arguments("unknown_tool", &args).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn oversized_payloads_are_rejected_before_the_gate() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp).with_security_config(&SecurityConfig {
+            max_spec_bytes: 256,
+            max_argument_bytes: 64,
+            ..Default::default()
+        });
+
+        let err = proxy
+            .handle_call(call_params(serde_json::json!({
+                "name": "request_capability",
+                "arguments": { "name": "big_tool", "description": "x".repeat(300) }
+            })))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        let data = err.data.expect("error data");
+        assert_eq!(data["setting"], "max_spec_bytes");
+        assert_eq!(data["limit"], 256);
+        assert!(data["measured"].as_u64().unwrap() > 256);
+
+        // Checked even before the unknown-tool check.
+        let err = proxy
+            .handle_call(call_params(serde_json::json!({
+                "name": "no_such_tool",
+                "arguments": { "blob": "x".repeat(100) }
+            })))
+            .await
+            .unwrap_err();
+        assert_eq!(err.data.expect("error data")["setting"], "max_argument_bytes");
+
+        assert!(proxy.engine.creation_cache().is_empty().await);
+        assert!(proxy.engine.execution_cache().is_empty().await);
+    }
+
+    fn ask_request() -> CallToolRequestParams {
+        call_params(serde_json::json!({
+            "name": "request_capability",
+            "arguments": { "name": "line_count", "description": "Count lines in a string" }
+        }))
+    }
+
+    fn approve_params(token: &str, approve: bool) -> CallToolRequestParams {
+        call_params(serde_json::json!({
+            "name": "approve_capability",
+            "arguments": { "approval_token": token, "approve": approve }
+        }))
+    }
+
+    #[tokio::test]
+    async fn ask_response_carries_an_approval_token() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+
+        // The stub LLM evaluator answers Ask for unlisted specs.
+        let result = proxy.handle_call(ask_request()).await.unwrap();
+        let response: serde_json::Value = serde_json::from_str(&text_of(&result)).unwrap();
+
+        assert_eq!(response["status"], "ask");
+        let token = response["approval_token"].as_str().unwrap();
+        assert!(response["expires_at_ms"].as_u64().unwrap() > 0);
+        assert_eq!(proxy.approvals.pending()[0].token, token);
+        let message = response["approval_message"].as_str().unwrap();
+        assert!(message.starts_with("Approve building `line_count`?"));
+        assert!(message.contains(&format!("girt approve {token}")));
+    }
+
+    /// [`test_proxy`] with `[security] agent_approvals` on.
+    fn agent_approving_proxy(tmp: &TempDir) -> GirtProxy {
+        test_proxy(tmp).with_security_config(&SecurityConfig {
+            agent_approvals: true,
+            ..SecurityConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn rejected_approval_denies_the_repeat_request() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = agent_approving_proxy(&tmp);
+
+        let result = proxy.handle_call(ask_request()).await.unwrap();
+        let response: serde_json::Value = serde_json::from_str(&text_of(&result)).unwrap();
+        let token = response["approval_token"].as_str().unwrap();
+
+        let rejected = proxy.handle_call(approve_params(token, false)).await.unwrap();
+        assert_eq!(rejected.is_error, Some(true));
+        assert!(text_of(&rejected).contains("\"rejected\""));
+
+        let repeat = proxy.handle_call(ask_request()).await.unwrap();
+        assert_eq!(repeat.is_error, Some(true));
+        assert!(text_of(&repeat).contains("Rejected by operator"));
+    }
+
+    #[tokio::test]
+    async fn unknown_and_expired_tokens_are_invalid_params() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = agent_approving_proxy(&tmp).with_approvals(ApprovalStore::new(Duration::ZERO));
+
+        let err = proxy
+            .handle_call(approve_params("no-such-token", true))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("unknown approval token"), "{}", err.message);
+
+        let result = proxy.handle_call(ask_request()).await.unwrap();
+        let response: serde_json::Value = serde_json::from_str(&text_of(&result)).unwrap();
+        let token = response["approval_token"].as_str().unwrap();
+        let err = proxy.handle_call(approve_params(token, true)).await.unwrap_err();
+        assert!(err.message.contains("expired"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn only_an_operator_answers_asks_by_default() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+
+        let result = proxy.handle_call(ask_request()).await.unwrap();
+        let response: serde_json::Value = serde_json::from_str(&text_of(&result)).unwrap();
+        let token = response["approval_token"].as_str().unwrap();
+        assert!(proxy.approvals.pending()[0].operator_only);
+        assert!(
+            response["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Show the user approval_message. Only an operator"),
+            "{response}"
+        );
+
+        for approve in [true, false] {
+            let err = proxy
+                .handle_call(approve_params(token, approve))
+                .await
+                .unwrap_err();
+            assert!(err.message.contains("Only an operator"), "{}", err.message);
+        }
+        assert_eq!(proxy.approvals.pending().len(), 1);
+    }
+
+    fn text_of(result: &CallToolResult) -> String {
+        result.content[0]
+            .as_text()
+            .map(|t| t.text.clone())
+            .expect("text content")
+    }
+
+    #[test]
+    fn structured_content_matches_text_for_allow() {
+        let result = make_tool_result(decision_to_json(&Decision::Allow), false, true);
+        let structured = result.structured_content.clone().unwrap();
+        let text: serde_json::Value = serde_json::from_str(&text_of(&result)).unwrap();
+
+        assert_eq!(structured, text);
+        assert_eq!(structured["status"], "allowed");
+        assert_eq!(result.is_error, Some(false));
+    }
+
+    #[test]
+    fn structured_content_matches_text_for_deny() {
+        let decision = Decision::Deny {
+            reason: "matches deny pattern".into(),
+        };
+        let result = make_tool_result(decision_to_json(&decision), true, true);
+        let structured = result.structured_content.clone().unwrap();
+        let text: serde_json::Value = serde_json::from_str(&text_of(&result)).unwrap();
+
+        assert_eq!(structured, text);
+        assert_eq!(structured["reason"], "matches deny pattern");
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[test]
+    fn defer_target_is_structured() {
+        let decision = Decision::Defer {
+            target: DeferTarget::UseCli {
+                binary: "jq".into(),
+                description: "JSON processor".into(),
+                example_invocation: "jq . data.json".into(),
+            },
+        };
+        let result = make_tool_result(decision_to_json(&decision), false, true);
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["status"], "deferred");
+        assert_eq!(structured["target"]["kind"], "use_cli");
+        assert_eq!(structured["target"]["binary"], "jq");
+    }
+
+    fn deferred(target: DeferTarget) -> LayeredDecision {
+        LayeredDecision {
+            decision: Decision::Defer { target },
+            layer: girt_core::decision::DecisionLayer::Similarity,
+            rationale: None,
+            consulted_layers: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn deferral_to_a_loaded_tool_embeds_its_schema() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "repo": { "type": "string" } },
+            "required": ["repo"]
+        });
+        let meta = ComponentMeta {
+            component_id: "github_issues@0.1.0".into(),
+            tool_name: "github_issues".into(),
+            description: "List GitHub issues".into(),
+            input_schema: schema.clone(),
+            deprecated: false,
+            ..deprecated_meta(None, None)
+        };
+        let echo = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/echo.wasm");
+        proxy
+            .runtime
+            .load_component(std::path::Path::new(echo), meta)
+            .await
+            .unwrap();
+
+        let target = DeferTarget::ExtendTool {
+            tool_name: "github_issues".into(),
+            suggested_features: vec!["list labels".into()],
+        };
+        let response = proxy
+            .deferral_json(&deferred(target.clone()), &target)
+            .await;
+
+        assert_eq!(response["status"], "deferred");
+        assert_eq!(response["tool"]["name"], "github_issues");
+        assert_eq!(response["tool"]["input_schema"], schema);
+        let steps = response["next_steps"].as_array().unwrap();
+        assert!(steps[0].as_str().unwrap().contains("directly"), "{steps:?}");
+        assert!(
+            steps[1].as_str().unwrap().contains("list labels"),
+            "{steps:?}"
+        );
+        assert!(
+            steps[1]
+                .as_str()
+                .unwrap()
+                .contains("call extend_capability"),
+            "{steps:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn instructions_report_loaded_tools_after_a_load() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+        proxy.refresh_status().await;
+        let instructions = proxy.get_info().instructions.unwrap();
+        assert!(instructions.starts_with("GIRT MCP Proxy"), "{instructions}");
+        assert!(instructions.contains("Loaded tools: 0."), "{instructions}");
+        assert!(
+            instructions.contains("Creation gate: stub+hitl"),
+            "{instructions}"
+        );
+        assert!(
+            instructions.contains("up to 5 iterations, 200000 tokens"),
+            "{instructions}"
+        );
+
+        let echo = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/echo.wasm");
+        proxy
+            .runtime
+            .load_component(
+                std::path::Path::new(echo),
+                ComponentMeta {
+                    deprecated: false,
+                    ..deprecated_meta(None, None)
+                },
+            )
+            .await
+            .unwrap();
+        // The snapshot only moves when the tool list is announced as changed.
+        assert!(
+            proxy
+                .get_info()
+                .instructions
+                .unwrap()
+                .contains("Loaded tools: 0.")
+        );
+        proxy.notify_tools_changed().await;
+        let instructions = proxy.get_info().instructions.unwrap();
+        assert!(instructions.contains("Loaded tools: 1."), "{instructions}");
+    }
+
+    #[tokio::test]
+    async fn deferral_to_a_missing_tool_says_so() {
+        let tmp = TempDir::new().unwrap();
+        let proxy = test_proxy(&tmp);
+        let target = DeferTarget::ExtendTool {
+            tool_name: "github_issues".into(),
+            suggested_features: vec![],
+        };
+        let response = proxy
+            .deferral_json(&deferred(target.clone()), &target)
+            .await;
+
+        assert!(response.get("tool").is_none());
+        assert_eq!(
+            response["next_steps"][0],
+            "'github_issues' is not loaded in this proxy"
+        );
+    }
+
+    #[test]
+    fn cli_deferral_names_the_command() {
+        let target = DeferTarget::UseCli {
+            binary: "jq".into(),
+            description: "JSON processor".into(),
+            example_invocation: "jq . data.json".into(),
+        };
+        let details = deferral_details(&target, None);
+        assert_eq!(details["command"], "jq . data.json");
+        let step = details["next_steps"][0].as_str().unwrap();
+        assert!(
+            step.contains("`jq`") && step.contains("jq . data.json"),
+            "{step}"
+        );
+    }
+
+    #[test]
+    fn tool_error_keeps_raw_text_and_structures_message() {
+        let result = ResultBuilder::new(true).tool_error("invalid JSON: eof".into());
+        assert_eq!(text_of(&result), "invalid JSON: eof");
+        assert_eq!(
+            result.structured_content.unwrap(),
+            serde_json::json!({ "error": "invalid JSON: eof" })
+        );
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[test]
+    fn tool_failure_structures_kind_and_retryable() {
+        let result = ResultBuilder::new(true).tool_failure("upstream", "HTTP 503", true);
+        assert_eq!(text_of(&result), "upstream error: HTTP 503");
+        assert_eq!(
+            result.structured_content.unwrap(),
+            serde_json::json!({ "error": "HTTP 503", "kind": "upstream", "retryable": true })
+        );
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[test]
+    fn envelope_wraps_data_and_errors_with_meta() {
+        let envelope = || {
+            ResultBuilder::new(true).with_envelope(Some(EnvelopeMeta {
+                duration_ms: 12,
+                tool_version: Some("0.1.0".into()),
+                cached: false,
+            }))
+        };
+        let meta = serde_json::json!({
+            "duration_ms": 12,
+            "truncated": false,
+            "tool_version": "0.1.0",
+            "cached": false,
+        });
+
+        // A null answer is still a success, and the text stays the raw data.
+        let result = envelope().json(serde_json::Value::Null, false);
+        assert_eq!(text_of(&result), "null");
+        assert_eq!(
+            result.structured_content.unwrap(),
+            serde_json::json!({ "ok": true, "data": null, "meta": meta })
+        );
+
+        let result = envelope().with_attempts(3).tool_error("boom".into());
+        assert_eq!(text_of(&result), "boom");
+        assert_eq!(result.is_error, Some(true));
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["ok"], false);
+        assert_eq!(
+            structured["error"],
+            serde_json::json!({ "message": "boom", "kind": "tool_error" })
+        );
+        assert_eq!(structured["meta"]["attempts"], 3);
+
+        let result = envelope().tool_failure("upstream", "HTTP 503", true);
+        assert_eq!(
+            result.structured_content.unwrap()["error"],
+            serde_json::json!({ "message": "HTTP 503", "kind": "upstream", "retryable": true })
+        );
+    }
+
+    #[test]
+    fn non_object_tool_output_is_wrapped() {
+        let result = make_tool_result(serde_json::json!(212.0), false, true);
+        assert_eq!(text_of(&result), "212.0");
+        assert_eq!(
+            result.structured_content.unwrap(),
+            serde_json::json!({ "result": 212.0 })
+        );
+    }
+
+    #[test]
+    fn structured_results_disabled_leaves_text_only() {
+        let result = make_tool_result(decision_to_json(&Decision::Allow), false, false);
+        assert!(result.structured_content.is_none());
+        assert!(text_of(&result).contains("allowed"));
+
+        let error = ResultBuilder::new(false).tool_error("boom".into());
+        assert!(error.structured_content.is_none());
+    }
+
+    #[test]
+    fn retryable_errors_are_transient_ones() {
+        assert!(is_retryable("upstream returned 502 Bad Gateway"));
+        assert!(is_retryable("HTTP status: 503"));
+        assert!(is_retryable("request timed out after 30s"));
+        assert!(is_retryable("Connection reset by peer"));
+        assert!(is_retryable("deadline: Timeout"));
+
+        assert!(!is_retryable("HTTP 404 Not Found"));
+        assert!(!is_retryable("invalid JSON at byte 5021"));
+        assert!(!is_retryable("missing field 'url'"));
+    }
+
+    /// A call that fails with `error` `failures` times, then succeeds.
+    fn flaky(
+        failures: u32,
+        error: &'static str,
+    ) -> (
+        Arc<std::sync::atomic::AtomicU32>,
+        impl FnMut() -> std::future::Ready<Result<serde_json::Value, RuntimeError>>,
+    ) {
+        let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = calls.clone();
+        let call = move || {
+            let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::future::ready(if n < failures {
+                Err(RuntimeError::ToolError(error.into()))
             } else {
-                tracing::info!("Sent tools/list_changed notification");
+                Ok(serde_json::json!({ "ok": true }))
+            })
+        };
+        (calls, call)
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_until_success() {
+        let (calls, call) = flaky(2, "upstream returned 502");
+        let (outcome, attempts) = call_with_retry(2, Duration::ZERO, call).await;
+        assert_eq!(outcome.unwrap(), serde_json::json!({ "ok": true }));
+        assert_eq!(attempts, 3);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_at_the_configured_limit() {
+        let (_, call) = flaky(5, "connection reset");
+        let (outcome, attempts) = call_with_retry(2, Duration::ZERO, call).await;
+        assert!(matches!(outcome, Err(RuntimeError::ToolError(_))));
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_and_zero_retries_run_once() {
+        let (_, call) = flaky(1, "HTTP 404 Not Found");
+        let (outcome, attempts) = call_with_retry(2, Duration::ZERO, call).await;
+        assert!(outcome.is_err());
+        assert_eq!(attempts, 1);
+
+        let (_, call) = flaky(1, "upstream returned 502");
+        let (outcome, attempts) = call_with_retry(0, Duration::ZERO, call).await;
+        assert!(outcome.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn structured_failures_retry_only_when_marked_retryable() {
+        let failure = |retryable| {
+            move || {
+                std::future::ready(Err(RuntimeError::ToolFailure {
+                    kind: "upstream".into(),
+                    // Would look transient to a 0.1.0 tool.
+                    message: "HTTP 503".into(),
+                    retryable,
+                }))
+            }
+        };
+        let (_, attempts) = call_with_retry(2, Duration::ZERO, failure(true)).await;
+        assert_eq!(attempts, 3);
+        let (_, attempts) = call_with_retry(2, Duration::ZERO, failure(false)).await;
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn attempts_are_recorded_only_after_a_retry() {
+        let once = ResultBuilder::new(true)
+            .with_attempts(1)
+            .json(serde_json::json!({}), false);
+        assert!(once.structured_content.unwrap().get("attempts").is_none());
+
+        let retried = ResultBuilder::new(true)
+            .with_attempts(3)
+            .tool_error("boom".into());
+        assert_eq!(retried.structured_content.unwrap()["attempts"], 3);
+    }
+
+    #[test]
+    fn resource_usage_lands_in_structured_content() {
+        let usage = ResourceUsage {
+            wall_time_ms: 4,
+            peak_memory_bytes: 33 << 16,
+            memory_limit_bytes: 512 << 20,
+            fuel_consumed: None,
+        };
+        let result = ResultBuilder::new(true)
+            .with_resource_usage(Some(&usage))
+            .tool_error("boom".into());
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["resource_usage"]["peak_memory_bytes"], 33 << 16);
+        assert!(structured["resource_usage"].get("fuel_consumed").is_none());
+
+        let plain = ResultBuilder::new(true)
+            .with_resource_usage(None)
+            .tool_error("boom".into());
+        assert!(
+            plain
+                .structured_content
+                .unwrap()
+                .get("resource_usage")
+                .is_none()
+        );
+    }
+
+    fn deprecated_meta(replaced_by: Option<&str>, deprecated_at: Option<u64>) -> ComponentMeta {
+        ComponentMeta {
+            component_id: "old_fetch@0.1.0".into(),
+            tool_name: "old_fetch".into(),
+            description: "Fetch a URL".into(),
+            input_schema: serde_json::json!({ "type": "object" }),
+            wasm_hash: String::new(),
+            wasm_size_bytes: 0,
+            built_at: 0,
+            loaded_at: 0,
+            allowed_env: vec![],
+            allowed_hosts: vec![],
+            max_response_bytes: None,
+            timeout_ms: None,
+            idempotent: false,
+            deprecated: true,
+            replaced_by: replaced_by.map(Into::into),
+            deprecated_at,
+            oci_reference: None,
+            source_repo_path: None,
+            last_validated_at: None,
+            drift_detected: false,
+            quarantined: None,
+            quarantine_released: false,
+        }
+    }
+
+    #[test]
+    fn deprecated_tools_are_listed_with_a_suffix() {
+        let tool = component_meta_to_tool(&deprecated_meta(Some("fetch_url"), Some(0)));
+        assert_eq!(
+            tool.description.as_deref(),
+            Some("Fetch a URL [DEPRECATED — use fetch_url]")
+        );
+
+        let tool = component_meta_to_tool(&deprecated_meta(None, Some(0)));
+        assert_eq!(
+            tool.description.as_deref(),
+            Some("Fetch a URL [DEPRECATED]")
+        );
+
+        let current = ComponentMeta {
+            deprecated: false,
+            ..deprecated_meta(None, None)
+        };
+        assert_eq!(
+            component_meta_to_tool(&current).description.as_deref(),
+            Some("Fetch a URL")
+        );
+    }
+
+    #[test]
+    fn drifted_tools_are_listed_with_a_suffix() {
+        let drifted = ComponentMeta {
+            drift_detected: true,
+            ..deprecated_meta(Some("fetch_url"), Some(0))
+        };
+        assert_eq!(
+            component_meta_to_tool(&drifted).description.as_deref(),
+            Some(
+                "Fetch a URL [DEPRECATED — use fetch_url] \
+                 [DRIFT DETECTED — output may not match this description]"
+            )
+        );
+    }
+
+    #[test]
+    fn deprecated_tool_warns_within_grace_and_is_refused_after() {
+        let grace = Duration::from_secs(60);
+        let meta = deprecated_meta(Some("fetch_url"), Some(1_000));
+
+        let warning = deprecation_notice(&meta, grace, 60_999).unwrap().unwrap();
+        assert_eq!(warning["replaced_by"], "fetch_url");
+        assert_eq!(warning["deprecated_at"], 1_000);
+        assert_eq!(warning["retired_at"], 61_000);
+        assert_eq!(
+            warning["message"],
+            "Tool 'old_fetch' is deprecated; use 'fetch_url' instead"
+        );
+
+        let err = deprecation_notice(&meta, grace, 61_000).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert!(
+            err.message.contains("use 'fetch_url' instead"),
+            "{}",
+            err.message
+        );
+        assert_eq!(err.data.unwrap()["replaced_by"], "fetch_url");
+
+        // Not deprecated, or deprecated without a timestamp: never refused.
+        let current = ComponentMeta {
+            deprecated: false,
+            ..meta.clone()
+        };
+        assert!(
+            deprecation_notice(&current, grace, u64::MAX)
+                .unwrap()
+                .is_none()
+        );
+        let undated = deprecated_meta(None, None);
+        assert!(
+            deprecation_notice(&undated, grace, u64::MAX)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn quarantined_tool_is_refused_with_how_to_release_it() {
+        let current = deprecated_meta(None, None);
+        assert!(quarantine_check(&current).is_ok());
+
+        let quarantined = ComponentMeta {
+            quarantined: Some("Policy rule: internal hosts".into()),
+            ..current
+        };
+        let err = quarantine_check(&quarantined).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert!(
+            err.message.contains("girt tools release old_fetch"),
+            "{}",
+            err.message
+        );
+        assert_eq!(
+            err.data.unwrap()["quarantined"],
+            "Policy rule: internal hosts"
+        );
+    }
+
+    #[test]
+    fn deprecation_warning_lands_in_structured_content() {
+        let warning = serde_json::json!({ "replaced_by": "fetch_url" });
+        let result = ResultBuilder::new(true)
+            .with_deprecation(Some(&warning))
+            .json(serde_json::json!({ "ok": true }), false);
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["ok"], true);
+        assert_eq!(structured["deprecation"], warning);
+
+        let plain = ResultBuilder::new(true)
+            .with_deprecation(None)
+            .tool_error("boom".into());
+        assert!(
+            plain
+                .structured_content
+                .unwrap()
+                .get("deprecation")
+                .is_none()
+        );
+    }
+
+    fn synthetic_tools(count: usize) -> Vec<Tool> {
+        let mut tools = vec![
+            request_capability_tool(),
+            tool_info_tool(),
+            info_tool(),
+            approve_capability_tool(),
+            batch_tool(16),
+            list_services_tool(),
+            extend_capability_tool(),
+        ];
+        tools.extend((0..count).map(|i| {
+            component_meta_to_tool(&ComponentMeta {
+                component_id: format!("tool_{i:03}@0.1.0"),
+                tool_name: format!("tool_{i:03}"),
+                deprecated: false,
+                ..deprecated_meta(None, None)
+            })
+        }));
+        tools
+    }
+
+    /// Follow `next_cursor` from the first page to the last.
+    fn all_pages(tools: &[Tool], prefix: Option<&str>, page_size: usize) -> Vec<Vec<String>> {
+        let mut pages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next) = paginate_tools(tools.to_vec(), cursor.as_deref(), prefix, page_size);
+            pages.push(page.iter().map(|t| t.name.to_string()).collect());
+            match next {
+                Some(next) => cursor = Some(next),
+                None => return pages,
             }
-        } else {
-            tracing::warn!("No server peer available for tools/list_changed notification");
         }
     }
+
+    #[test]
+    fn tools_are_listed_across_pages_without_duplicates() {
+        use std::collections::BTreeSet;
+
+        let tools = synthetic_tools(120);
+        let pages = all_pages(&tools, None, 50);
+
+        let sizes: Vec<usize> = pages.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![50, 50, 27]);
+
+        let listed: Vec<String> = pages.concat();
+        let unique: BTreeSet<String> = listed.iter().cloned().collect();
+        assert_eq!(unique.len(), listed.len(), "duplicate tools listed");
+        let expected: BTreeSet<String> = tools.iter().map(|t| t.name.to_string()).collect();
+        assert_eq!(unique, expected);
+
+        // A single page when paging is off or everything fits.
+        assert_eq!(all_pages(&tools, None, 0).len(), 1);
+        assert_eq!(all_pages(&tools, None, 127).len(), 1);
+    }
+
+    #[test]
+    fn tool_set_changes_between_pages_do_not_duplicate() {
+        let tools = synthetic_tools(120);
+        let (first, cursor) = paginate_tools(tools.clone(), None, None, 50);
+        let cursor = cursor.unwrap();
+
+        // The cursor's own tool is removed and one is added on each side of it.
+        let mut changed: Vec<Tool> = tools
+            .into_iter()
+            .filter(|t| t.name != cursor.as_str())
+            .collect();
+        for name in ["aaa_new", "zzz_new"] {
+            changed.push(component_meta_to_tool(&ComponentMeta {
+                tool_name: name.into(),
+                deprecated: false,
+                ..deprecated_meta(None, None)
+            }));
+        }
+
+        let (second, _) = paginate_tools(changed, Some(&cursor), None, 50);
+        let first: Vec<&str> = first.iter().map(|t| t.name.as_ref()).collect();
+        assert!(second.iter().all(|t| !first.contains(&t.name.as_ref())));
+        assert!(second.iter().all(|t| t.name != "aaa_new"));
+
+        // A cursor past every name yields an empty last page.
+        let (page, next) = paginate_tools(synthetic_tools(3), Some("~"), None, 50);
+        assert!(page.is_empty());
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn name_prefix_filters_before_paging() {
+        let tools = synthetic_tools(120);
+        let pages = all_pages(&tools, Some("tool_1"), 8);
+        let listed = pages.concat();
+        // tool_100 ..= tool_119
+        assert_eq!(listed.len(), 20);
+        assert!(listed.iter().all(|name| name.starts_with("tool_1")));
+        assert_eq!(pages.len(), 3);
+    }
+
+    #[test]
+    fn list_params_read_cursor_and_prefix() {
+        let request: PaginatedRequestParams = serde_json::from_value(serde_json::json!({
+            "cursor": "tool_049",
+            "_meta": { "girt/namePrefix": "tool_" }
+        }))
+        .unwrap();
+        assert_eq!(
+            list_tools_params(Some(&request), &Meta::new()),
+            (Some("tool_049".into()), Some("tool_".into()))
+        );
+        assert_eq!(list_tools_params(None, &Meta::new()), (None, None));
+
+        let mut lifted = Meta::new();
+        lifted.insert(NAME_PREFIX_META.into(), "echo".into());
+        assert_eq!(
+            list_tools_params(None, &lifted),
+            (None, Some("echo".into()))
+        );
+    }
 }
@@ -0,0 +1,119 @@
+//! Re-checking restored tools against the current policy rules.
+//!
+//! A tool is gated once, when it is requested. Deny patterns added to
+//! girt.toml afterwards would otherwise never reach the tools already
+//! stored, so on startup every restored tool's spec (from the build cache
+//! when it has one, otherwise rebuilt from its stored metadata) is put
+//! through the policy rules again. A tool they deny is quarantined: it
+//! stays stored and shows up in `girt tools list`, but it is not listed to
+//! agents and calls to it are refused, until an operator runs
+//! `girt tools release <name>` or removes it.
+//!
+//! Only the policy rules are consulted. The decision cache and the LLM
+//! evaluator already had their say when the tool was built.
+
+use girt_core::engine::DecisionEngine;
+use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+use girt_pipeline::cache::ToolCache;
+use girt_runtime::LifecycleManager;
+use girt_runtime::storage::ComponentMeta;
+
+/// Re-check every restored tool, quarantining those the policy rules now
+/// deny and lifting quarantines they no longer call for. Bundled tools and
+/// tools an operator released are skipped. Returns the names of the tools
+/// left quarantined.
+pub async fn recheck_restored(
+    engine: &DecisionEngine,
+    runtime: &LifecycleManager,
+    cache: &ToolCache,
+) -> Vec<String> {
+    let mut quarantined = Vec::new();
+    for meta in runtime.list_tools().await {
+        let name = meta.tool_name.clone();
+        if meta.quarantine_released || runtime.is_bundled(&name).await {
+            continue;
+        }
+        let built = match cache.get(&name).await {
+            Ok(artifact) => artifact.map(|artifact| artifact.spec),
+            Err(e) => {
+                tracing::warn!(tool = %name, error = %e, "Failed to read cached spec; re-checking stored metadata only");
+                None
+            }
+        };
+        let spec = restored_spec(&meta, built);
+        let denial = engine.policy_denial(&spec).await;
+        if denial != meta.quarantined
+            && let Err(e) = runtime.set_quarantine(&name, denial.clone()).await
+        {
+            tracing::warn!(tool = %name, error = %e, "Failed to update quarantine");
+            continue;
+        }
+        if denial.is_some() {
+            quarantined.push(name);
+        }
+    }
+    quarantined.sort();
+    quarantined
+}
+
+/// The spec a restored tool is re-checked as: the one it was built from,
+/// if known, widened by the hosts and secrets its stored metadata grants.
+pub(crate) fn restored_spec(meta: &ComponentMeta, built: Option<CapabilitySpec>) -> CapabilitySpec {
+    let mut spec = built.unwrap_or_else(|| CapabilitySpec {
+        name: meta.tool_name.clone(),
+        description: meta.description.clone(),
+        inputs: meta.input_schema.clone(),
+        outputs: serde_json::Value::Null,
+        constraints: CapabilityConstraints::default(),
+    });
+    for grant in &meta.allowed_hosts {
+        if !spec.constraints.network.contains(grant) {
+            spec.constraints.network.push(grant.clone());
+        }
+    }
+    for secret in &meta.allowed_env {
+        if !spec.constraints.secrets.contains(secret) {
+            spec.constraints.secrets.push(secret.clone());
+        }
+    }
+    spec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_core::spec::NetworkGrant;
+
+    #[test]
+    fn stored_grants_widen_the_built_spec() {
+        let mut meta = girt_runtime::bundled::ECHO.meta();
+        meta.tool_name = "billing".into();
+        meta.allowed_hosts = vec!["billing.internal.corp".into(), "api.example.com".into()];
+        meta.allowed_env = vec!["BILLING_TOKEN".into()];
+
+        let spec = restored_spec(&meta, None);
+        assert_eq!(spec.name, "billing");
+        assert_eq!(spec.constraints.network, meta.allowed_hosts);
+        assert_eq!(spec.constraints.secrets, vec!["BILLING_TOKEN".to_string()]);
+
+        let built = CapabilitySpec {
+            name: "billing".into(),
+            description: "Look up invoices".into(),
+            inputs: serde_json::Value::Null,
+            outputs: serde_json::Value::Null,
+            constraints: CapabilityConstraints {
+                network: vec![NetworkGrant::from("api.example.com")],
+                ..Default::default()
+            },
+        };
+        let spec = restored_spec(&meta, Some(built));
+        assert_eq!(spec.description, "Look up invoices");
+        assert_eq!(
+            spec.constraints.network,
+            vec![
+                NetworkGrant::from("api.example.com"),
+                NetworkGrant::from("billing.internal.corp"),
+            ]
+        );
+    }
+}
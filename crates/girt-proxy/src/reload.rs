@@ -0,0 +1,113 @@
+//! Hot reload of girt.toml for `girt serve --watch-config`.
+//!
+//! Only settings that can be swapped without dropping the agent's MCP
+//! session are applied: policy rules and per-tool env injection. Changes to
+//! anything else (LLM provider, registry, server options) are logged and
+//! ignored until the next restart.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use girt_core::engine::DecisionEngine;
+use girt_pipeline::config::{ConfigWatcher, GirtConfig};
+use girt_runtime::LifecycleManager;
+
+/// How often girt.toml is re-read.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Apply the hot-reloadable subset of `newer`.
+///
+/// `running` is the config the proxy was started with; it is used to report
+/// settings that changed but need a restart.
+pub async fn apply_config(
+    engine: &DecisionEngine,
+    runtime: &LifecycleManager,
+    running: &GirtConfig,
+    newer: &GirtConfig,
+) {
+    for key in running.restart_required_changes(newer) {
+        tracing::warn!(setting = key, "Config change ignored: requires a restart");
+    }
+
+    engine
+        .reload_policy(newer.policy.deny.clone(), newer.policy.allow.clone())
+        .await;
+    runtime.set_tool_env(newer.tools.env.clone());
+
+    tracing::info!(
+        deny_rules = newer.policy.deny.len(),
+        allow_rules = newer.policy.allow.len(),
+        tools_with_env = newer.tools.env.len(),
+        "Config reloaded"
+    );
+}
+
+/// Poll girt.toml in the background and apply changes as they appear.
+///
+/// Parse errors are logged and the previously applied config stays active.
+pub fn spawn_config_watcher(
+    mut watcher: ConfigWatcher,
+    running: GirtConfig,
+    engine: Arc<DecisionEngine>,
+    runtime: Arc<LifecycleManager>,
+) -> tokio::task::JoinHandle<()> {
+    tracing::info!(path = %watcher.path().display(), "Watching config for changes");
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match watcher.poll() {
+                Ok(None) => {}
+                Ok(Some(newer)) => apply_config(&engine, &runtime, &running, &newer).await,
+                Err(e) => tracing::warn!(
+                    path = %watcher.path().display(),
+                    error = %e,
+                    "Failed to reload config; keeping previous settings"
+                ),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_core::decision::{Decision, GateKind};
+    use girt_core::spec::{ExecutionRequest, GateInput};
+    use tempfile::TempDir;
+
+    const BASE: &str = "[llm]\nprovider = \"stub\"\n";
+
+    #[tokio::test]
+    async fn added_deny_pattern_applies_to_next_evaluation() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("girt.toml");
+        std::fs::write(&path, BASE).unwrap();
+
+        let running = GirtConfig::load(&path, None).unwrap();
+        let mut watcher = ConfigWatcher::new(&path, None).unwrap();
+        let engine = DecisionEngine::with_defaults();
+        let runtime = LifecycleManager::new(Some(tmp.path().join("components"))).unwrap();
+        let input = GateInput::Execution(ExecutionRequest {
+            tool_name: "crypto_miner".into(),
+            arguments: serde_json::Value::Null,
+            tool: None,
+        });
+
+        let before = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert!(!matches!(before.decision, Decision::Deny { .. }));
+
+        std::fs::write(
+            &path,
+            format!(
+                "{BASE}\n[[policy.deny]]\ndescription = \"No crypto miners\"\nname_pattern = \"(?i)miner\"\n"
+            ),
+        )
+        .unwrap();
+        let newer = watcher.poll().unwrap().expect("change detected");
+        apply_config(&engine, &runtime, &running, &newer).await;
+
+        let after = engine.evaluate(GateKind::Execution, &input).await.unwrap();
+        assert!(matches!(after.decision, Decision::Deny { .. }));
+    }
+}
@@ -0,0 +1,195 @@
+//! Per-call request tracing and the optional JSONL request log
+//! (`[server] request_log = true`).
+//!
+//! Each handled MCP request gets a generated request id that is attached to
+//! its tracing span, echoed in error responses, and written to the log so an
+//! agent-side failure can be matched to GIRT's view of the same call.
+//! Argument *sizes* are logged, never argument values.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use girt_core::paths::{self, PathError};
+use serde::Serialize;
+
+/// Per-call state shared between the request wrapper and the handlers.
+pub struct RequestTrace {
+    pub request_id: String,
+    decision: OnceLock<&'static str>,
+}
+
+impl RequestTrace {
+    pub fn new() -> Self {
+        Self {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            decision: OnceLock::new(),
+        }
+    }
+
+    /// Record the gate decision for this call (first one wins) and attach it
+    /// to the current span.
+    pub fn set_decision(&self, decision: &'static str) {
+        if self.decision.set(decision).is_ok() {
+            tracing::Span::current().record("decision", decision);
+        }
+    }
+
+    pub fn decision(&self) -> Option<&'static str> {
+        self.decision.get().copied()
+    }
+}
+
+impl Default for RequestTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One line of the request log.
+#[derive(Debug, Serialize)]
+pub struct RequestRecord {
+    pub timestamp_ms: u64,
+    pub request_id: String,
+    pub method: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decision: Option<&'static str>,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_class: Option<&'static str>,
+    /// Size of the serialized arguments object.
+    pub argument_bytes: usize,
+    /// Serialized size of each top-level argument, by key.
+    pub argument_sizes: BTreeMap<String, usize>,
+}
+
+/// Append-only JSONL log of handled MCP requests.
+pub struct RequestLog {
+    path: PathBuf,
+    file: Mutex<std::fs::File>,
+}
+
+impl RequestLog {
+    /// Default location: `~/.girt/requests.jsonl`.
+    pub fn default_path() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("requests.jsonl"))
+    }
+
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write one record. Failures are logged, never surfaced to the agent.
+    pub fn append(&self, record: &RequestRecord) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to serialize request log record");
+                return;
+            }
+        };
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.write_all(&line) {
+            tracing::warn!(path = %self.path.display(), error = %e, "Failed to write request log");
+        }
+    }
+}
+
+/// Serialized sizes of a call's arguments: total, and per top-level key.
+pub fn argument_sizes(
+    arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> (usize, BTreeMap<String, usize>) {
+    let Some(arguments) = arguments else {
+        return (0, BTreeMap::new());
+    };
+    let per_key = arguments
+        .iter()
+        .map(|(key, value)| (key.clone(), value.to_string().len()))
+        .collect();
+    let total = serde_json::to_string(arguments).map_or(0, |s| s.len());
+    (total, per_key)
+}
+
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn argument_sizes_never_include_values() {
+        let args = serde_json::json!({ "token": "hunter2", "count": 3 });
+        let (total, per_key) = argument_sizes(args.as_object());
+
+        assert_eq!(total, args.to_string().len());
+        assert_eq!(per_key["token"], "\"hunter2\"".len());
+        assert_eq!(per_key["count"], 1);
+
+        let record = RequestRecord {
+            timestamp_ms: 0,
+            request_id: "id".into(),
+            method: "call_tool",
+            tool_name: Some("t".into()),
+            decision: None,
+            duration_ms: 0,
+            error_class: None,
+            argument_bytes: total,
+            argument_sizes: per_key,
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.contains("hunter2"));
+    }
+
+    #[test]
+    fn append_writes_one_line_per_record() {
+        let tmp = TempDir::new().unwrap();
+        let log = RequestLog::open(tmp.path().join("nested/requests.jsonl")).unwrap();
+        for i in 0..2 {
+            log.append(&RequestRecord {
+                timestamp_ms: i,
+                request_id: format!("req-{i}"),
+                method: "list_tools",
+                tool_name: None,
+                decision: None,
+                duration_ms: 1,
+                error_class: None,
+                argument_bytes: 0,
+                argument_sizes: BTreeMap::new(),
+            });
+        }
+
+        let contents = std::fs::read_to_string(log.path()).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["request_id"], "req-1");
+        assert!(lines[0].get("tool_name").is_none());
+    }
+}
@@ -0,0 +1,179 @@
+//! Argument validation against a tool's `input_schema`.
+//!
+//! A hand-rolled subset of JSON Schema: `type`, `required`, `enum`,
+//! `properties`, and `items`. Anything else is ignored. Schemas that do not
+//! look like JSON Schema at all (the pipeline sometimes emits
+//! `{"field": "string"}` sketches) are skipped entirely.
+
+use serde_json::Value;
+
+/// Validate `args` against `schema`, returning one message per violation.
+/// An empty result means the arguments are acceptable.
+pub fn validate_arguments(schema: &Value, args: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    if is_json_schema(schema) {
+        check(schema, args, "arguments", &mut violations);
+    }
+    violations
+}
+
+/// Whether `schema` uses JSON Schema keywords rather than being a sketch.
+fn is_json_schema(schema: &Value) -> bool {
+    schema.as_object().is_some_and(|obj| {
+        ["type", "properties", "required", "enum", "items"]
+            .iter()
+            .any(|k| obj.contains_key(*k))
+    })
+}
+
+fn check(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    if !is_json_schema(schema) {
+        return;
+    }
+
+    if let Some(expected) = schema.get("type")
+        && !matches_type(expected, value)
+    {
+        violations.push(format!(
+            "{path}: expected {}, got {}",
+            describe_type(expected),
+            type_name(value)
+        ));
+        // Nested checks are meaningless once the type is wrong.
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        let options: Vec<String> = allowed.iter().map(Value::to_string).collect();
+        violations.push(format!("{path}: must be one of {}", options.join(", ")));
+    }
+
+    if let Some(obj) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !obj.contains_key(key) {
+                    violations.push(format!("{path}: missing required field '{key}'"));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, prop_schema) in properties {
+                if let Some(prop) = obj.get(key) {
+                    check(prop_schema, prop, &format!("{path}.{key}"), violations);
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check(items, item, &format!("{path}[{i}]"), violations);
+        }
+    }
+}
+
+fn matches_type(expected: &Value, value: &Value) -> bool {
+    match expected {
+        Value::String(t) => matches_type_name(t, value),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|t| matches_type_name(t, value)),
+        // Unrecognized `type` shape: don't second-guess it.
+        _ => true,
+    }
+}
+
+fn matches_type_name(name: &str, value: &Value) -> bool {
+    match name {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        // Unknown type names are treated leniently.
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &Value) -> String {
+    match expected {
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("?").to_string(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "celsius": { "type": "number" },
+                "unit": { "type": "string", "enum": ["C", "F"] },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["celsius"]
+        })
+    }
+
+    #[test]
+    fn valid_arguments_pass() {
+        let args = json!({ "celsius": 21.5, "unit": "C", "tags": ["a"] });
+        assert!(validate_arguments(&schema(), &args).is_empty());
+    }
+
+    #[test]
+    fn missing_required_field_is_reported() {
+        assert_eq!(
+            validate_arguments(&schema(), &json!({ "unit": "C" })),
+            vec!["arguments: missing required field 'celsius'"]
+        );
+    }
+
+    #[test]
+    fn wrong_types_and_enum_values_are_reported() {
+        let args = json!({ "celsius": "hot", "unit": "K", "tags": [1] });
+        let mut violations = validate_arguments(&schema(), &args);
+        violations.sort();
+        assert_eq!(
+            violations,
+            vec![
+                "arguments.celsius: expected number, got string",
+                "arguments.tags[0]: expected string, got integer",
+                "arguments.unit: must be one of \"C\", \"F\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn sketch_and_empty_schemas_are_skipped() {
+        let sketch = json!({ "action": "string (encode or decode)" });
+        assert!(validate_arguments(&sketch, &json!({ "other": 1 })).is_empty());
+        assert!(validate_arguments(&Value::Null, &json!(42)).is_empty());
+        assert!(validate_arguments(&json!({}), &json!({})).is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! `girt_list_services`: which services loaded tools can authenticate to.
+//!
+//! The http_client pattern takes an `auth_service` name, and an agent that
+//! guesses one gets an opaque NotFound. This lists the services the secret
+//! store holds a credential for, less any a `secrets_deny` policy rule
+//! refuses, and which loaded tools are granted which of them. Only service
+//! names are listed: never credential values, never the environment
+//! variables behind them.
+
+use std::collections::BTreeMap;
+
+use girt_secrets::error::SecretError;
+use girt_secrets::store::SecretStore;
+use rmcp::model::{Tool, ToolAnnotations};
+use serde::Serialize;
+
+/// Name of the built-in.
+pub const LIST_SERVICES_TOOL: &str = "girt_list_services";
+
+/// The `girt_list_services` response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceListing {
+    /// Secret store backend, e.g. `env`.
+    pub backend: String,
+    /// Services with a credential that policy does not deny, sorted.
+    pub services: Vec<String>,
+    /// Loaded tools granted at least one of `services`, with the ones
+    /// they are granted.
+    pub tools: BTreeMap<String, Vec<String>>,
+}
+
+impl ServiceListing {
+    /// List `store`'s services not `denied` by policy, and which of them
+    /// each tool in `grants` (tool name, granted env vars) can use.
+    pub async fn collect<'a>(
+        store: &dyn SecretStore,
+        denied: impl Fn(&str) -> bool,
+        grants: impl IntoIterator<Item = (&'a str, &'a [String])>,
+    ) -> Result<Self, SecretError> {
+        let mut services: Vec<String> = store
+            .list_services()
+            .await?
+            .into_iter()
+            .filter(|service| !denied(service))
+            .collect();
+        services.sort();
+        services.dedup();
+
+        let tools = grants
+            .into_iter()
+            .filter_map(|(tool, env)| {
+                let mut granted: Vec<String> = env
+                    .iter()
+                    .filter_map(|var| store.service_for_env_var(var))
+                    .filter(|service| services.contains(service))
+                    .collect();
+                granted.sort();
+                granted.dedup();
+                (!granted.is_empty()).then(|| (tool.to_string(), granted))
+            })
+            .collect();
+
+        Ok(Self {
+            backend: store.backend_name().into(),
+            services,
+            tools,
+        })
+    }
+}
+
+/// Build the JSON schema for the girt_list_services tool.
+pub fn list_services_tool() -> Tool {
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {}
+    });
+
+    Tool {
+        name: LIST_SERVICES_TOOL.into(),
+        title: None,
+        description: Some(
+            "List the services tools can authenticate to: the auth_service names with a \
+             stored credential that policy allows, and which loaded tools are granted \
+             which of them. Never returns credentials. Call this before picking an \
+             auth_service for a capability request."
+                .into(),
+        ),
+        input_schema: schema.as_object().cloned().unwrap_or_default().into(),
+        output_schema: None,
+        annotations: Some(ToolAnnotations::new().read_only(true)),
+        execution: None,
+        icons: None,
+        meta: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_secrets::store::MemorySecretStore;
+    use std::collections::HashMap;
+
+    fn store() -> MemorySecretStore {
+        MemorySecretStore::new(HashMap::from([
+            ("github".into(), "gh-secret-value".into()),
+            ("openai".into(), "sk-secret-value".into()),
+            ("aws".into(), "aws-secret-value".into()),
+        ]))
+    }
+
+    #[tokio::test]
+    async fn lists_allowed_services_and_tool_grants_without_values() {
+        let gh_issues = vec!["GITHUB_TOKEN".to_string()];
+        let summarize = vec![
+            "OPENAI_API_KEY".to_string(),
+            "AWS_TOKEN".to_string(),
+            "HOME".to_string(),
+        ];
+        let listing = ServiceListing::collect(
+            &store(),
+            |service| service == "aws",
+            [
+                ("gh_issues", gh_issues.as_slice()),
+                ("summarize", summarize.as_slice()),
+                ("echo", &[][..]),
+            ],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(listing.backend, "memory");
+        assert_eq!(listing.services, ["github", "openai"]);
+        assert_eq!(
+            listing.tools,
+            BTreeMap::from([
+                ("gh_issues".to_string(), vec!["github".to_string()]),
+                ("summarize".to_string(), vec!["openai".to_string()]),
+            ])
+        );
+
+        let json = serde_json::to_string(&listing).unwrap();
+        for leaked in ["secret-value", "GITHUB_TOKEN", "OPENAI_API_KEY", "aws"] {
+            assert!(!json.contains(leaked), "{leaked} in {json}");
+        }
+    }
+
+    #[test]
+    fn tool_is_marked_read_only() {
+        let tool = list_services_tool();
+        assert_eq!(tool.annotations.unwrap().read_only_hint, Some(true));
+    }
+}
@@ -0,0 +1,192 @@
+//! The `girt://state` resource: what a proxy is doing right now, in one
+//! document, for status dashboards polling over MCP. `girt stats` prints
+//! the parts that can be read without a running proxy.
+//!
+//! The document is assembled on demand from the runtime, the build queue,
+//! the running builds, and the pending approvals. Only the gate decision
+//! counts are kept for it, in memory, over a sliding window.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use girt_core::decision::GateKind;
+use girt_pipeline::queue::QueueDepths;
+use serde::Serialize;
+
+use crate::approvals::PendingApproval;
+use crate::inflight::RunningBuild;
+use crate::request_log::now_ms;
+
+/// URI the document is read at.
+pub const STATE_URI: &str = "girt://state";
+
+/// How far back gate decisions are counted when no window is configured.
+pub const DEFAULT_DECISION_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Decisions kept at most; the oldest go first.
+const MAX_DECISIONS: usize = 10_000;
+
+/// Gate decisions within a time window, by gate and outcome.
+pub struct DecisionCounts {
+    window: Duration,
+    recent: Mutex<VecDeque<(u64, GateKind, &'static str)>>,
+}
+
+impl Default for DecisionCounts {
+    fn default() -> Self {
+        Self::new(DEFAULT_DECISION_WINDOW)
+    }
+}
+
+impl DecisionCounts {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Count a `gate` decision with status `outcome` (`"allowed"`,
+    /// `"denied"`, ...).
+    pub fn record(&self, gate: GateKind, outcome: &'static str) {
+        self.record_at(gate, outcome, now_ms());
+    }
+
+    fn record_at(&self, gate: GateKind, outcome: &'static str, at_ms: u64) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == MAX_DECISIONS {
+            recent.pop_front();
+        }
+        recent.push_back((at_ms, gate, outcome));
+    }
+
+    /// Decisions still within the window, counted by outcome.
+    pub fn summary(&self) -> DecisionSummary {
+        let cutoff = now_ms().saturating_sub(self.window.as_millis() as u64);
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        while recent.front().is_some_and(|(at, _, _)| *at < cutoff) {
+            recent.pop_front();
+        }
+        let mut summary = DecisionSummary {
+            window_secs: self.window.as_secs(),
+            ..DecisionSummary::default()
+        };
+        for (_, gate, outcome) in recent.iter() {
+            let counts = match gate {
+                GateKind::Creation => &mut summary.creation,
+                GateKind::Execution => &mut summary.execution,
+            };
+            *counts.entry(outcome.to_string()).or_default() += 1;
+        }
+        summary
+    }
+}
+
+/// Gate decisions of the last `window_secs`, by outcome.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DecisionSummary {
+    pub window_secs: u64,
+    pub creation: BTreeMap<String, u64>,
+    pub execution: BTreeMap<String, u64>,
+}
+
+/// Loaded tools, and how many of them need attention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ToolCounts {
+    pub loaded: usize,
+    pub deprecated: usize,
+    pub drift_detected: usize,
+}
+
+impl ToolCounts {
+    /// Count one more tool.
+    pub fn add(&mut self, deprecated: bool, drift_detected: bool) {
+        self.loaded += 1;
+        self.deprecated += usize::from(deprecated);
+        self.drift_detected += usize::from(drift_detected);
+    }
+}
+
+/// Capability requests waiting on a human.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ApprovalBacklog {
+    pub pending: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_created_at_ms: Option<u64>,
+}
+
+impl ApprovalBacklog {
+    pub fn of(pending: &[PendingApproval]) -> Self {
+        Self {
+            pending: pending.len(),
+            oldest_created_at_ms: pending.iter().map(|a| a.created_at_ms).min(),
+        }
+    }
+}
+
+/// The `girt://state` document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateDocument {
+    pub generated_at_ms: u64,
+    pub tools: ToolCounts,
+    /// Requests in each build queue directory. Absent without a queue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<QueueDepths>,
+    /// Builds the proxy is running, oldest first. Only a running proxy
+    /// knows these.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builds: Option<Vec<RunningBuild>>,
+    /// Only a running proxy knows these.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decisions: Option<DecisionSummary>,
+    pub approvals: ApprovalBacklog,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decisions_are_counted_per_gate_and_outcome() {
+        let counts = DecisionCounts::default();
+        counts.record(GateKind::Execution, "allowed");
+        counts.record(GateKind::Execution, "allowed");
+        counts.record(GateKind::Execution, "denied");
+        counts.record(GateKind::Creation, "ask");
+
+        let summary = counts.summary();
+        assert_eq!(summary.window_secs, 3600);
+        assert_eq!(summary.execution["allowed"], 2);
+        assert_eq!(summary.execution["denied"], 1);
+        assert_eq!(summary.creation["ask"], 1);
+        assert!(!summary.creation.contains_key("allowed"));
+    }
+
+    #[test]
+    fn decisions_outside_the_window_are_forgotten() {
+        let counts = DecisionCounts::new(Duration::from_secs(60));
+        counts.record_at(GateKind::Execution, "denied", now_ms() - 120_000);
+        counts.record(GateKind::Execution, "allowed");
+
+        let summary = counts.summary();
+        assert_eq!(summary.execution.len(), 1);
+        assert_eq!(summary.execution["allowed"], 1);
+    }
+
+    #[test]
+    fn tools_are_counted_with_their_flags() {
+        let mut tools = ToolCounts::default();
+        tools.add(false, false);
+        tools.add(true, false);
+        tools.add(true, true);
+        assert_eq!(
+            tools,
+            ToolCounts {
+                loaded: 3,
+                deprecated: 2,
+                drift_detected: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Tracing subscriber setup.
+//!
+//! Logs always go to stderr, filtered by `GIRT_LOG` — stdout is reserved
+//! for MCP stdio transport. Binaries built with the `otel` feature also
+//! export INFO-level spans over OTLP/HTTP when `[telemetry] otlp_endpoint`
+//! is set: each MCP request's `mcp_request` span is the root, with the
+//! Creation/Execution `gate`, `pipeline` phases, `llm.chat`, `compile`,
+//! `publish`, and `tool_invocation` spans under it.
+
+use girt_pipeline::config::TelemetryConfig;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, fmt};
+
+/// Flushes and stops span export when dropped. Keep it alive until exit.
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to flush exported spans: {e}");
+        }
+    }
+}
+
+/// Install the global subscriber. `config` is `None` when girt.toml could
+/// not be read; export is then off.
+pub fn init(config: Option<&TelemetryConfig>) -> TelemetryGuard {
+    let logs = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::from_env("GIRT_LOG"));
+    let endpoint = config.and_then(|c| c.otlp_endpoint.as_deref());
+
+    #[cfg(feature = "otel")]
+    {
+        let provider = match config {
+            Some(TelemetryConfig {
+                otlp_endpoint: Some(endpoint),
+                service_name,
+            }) => Some(otel::provider(endpoint, service_name)),
+            _ => None,
+        };
+        let spans = match &provider {
+            Some(Ok(provider)) => Some(otel::layer(provider)),
+            _ => None,
+        };
+        tracing_subscriber::registry().with(logs).with(spans).init();
+        match provider {
+            Some(Ok(provider)) => {
+                tracing::info!(
+                    endpoint = endpoint.unwrap_or_default(),
+                    "Exporting spans over OTLP"
+                );
+                TelemetryGuard {
+                    provider: Some(provider),
+                }
+            }
+            Some(Err(e)) => {
+                tracing::warn!("Cannot export spans: {e}");
+                TelemetryGuard::default()
+            }
+            None => TelemetryGuard::default(),
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry().with(logs).init();
+        if endpoint.is_some() {
+            tracing::warn!(
+                "telemetry.otlp_endpoint is set, but this girt was built without the otel feature; spans are not exported"
+            );
+        }
+        TelemetryGuard::default()
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing::Subscriber;
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// A tracer provider batching spans to the OTLP/HTTP `endpoint`.
+    pub(super) fn provider(
+        endpoint: &str,
+        service_name: &str,
+    ) -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()?;
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name.to_string())
+                    .build(),
+            )
+            .build())
+    }
+
+    /// Export INFO and above, whatever `GIRT_LOG` lets through to stderr.
+    pub(super) fn layer<S>(provider: &SdkTracerProvider) -> impl Layer<S> + use<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("girt"))
+            .with_filter(LevelFilter::INFO)
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use girt_core::spec::{CapabilitySpec, InputType};
+    use girt_pipeline::llm::{Matcher, StubLlmClient};
+    use girt_pipeline::orchestrator::Orchestrator;
+    use girt_pipeline::types::{CapabilityRequest, RequestSource};
+    use opentelemetry::trace::SpanId;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+    use tracing::Instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    use super::otel;
+
+    /// A build output the static checks accept.
+    fn engineer() -> String {
+        serde_json::json!({
+            "source_code": "#[allow(warnings)]\nmod bindings;\n\nstruct Component;\n\n\
+                            bindings::export!(Component with_types_in bindings);\n",
+            "wit_definition": "",
+            "policy_yaml": "version: \"1.0\"",
+            "language": "rust"
+        })
+        .to_string()
+    }
+
+    fn spec() -> CapabilitySpec {
+        CapabilitySpec::builder()
+            .name("test_tool")
+            .description("A test tool")
+            .input("value", InputType::String, true)
+            .output("result", InputType::String)
+            .build()
+            .unwrap()
+    }
+
+    /// Names of `span` and its ancestors, innermost first.
+    fn lineage(span: &SpanData, spans: &HashMap<SpanId, &SpanData>) -> Vec<String> {
+        let mut names = vec![span.name.to_string()];
+        let mut parent = span.parent_span_id;
+        while let Some(span) = spans.get(&parent) {
+            names.push(span.name.to_string());
+            parent = span.parent_span_id;
+        }
+        names
+    }
+
+    #[tokio::test]
+    async fn pipeline_spans_nest_under_the_request_span() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let _subscriber = tracing_subscriber::registry()
+            .with(otel::layer(&provider))
+            .set_default();
+
+        // The Engineer's output passes the static checks, but no answer
+        // passes QA, so every phase runs until the circuit breaker.
+        let client = StubLlmClient::with_matchers(vec![
+            (
+                Matcher::system_prompt("Senior Backend Engineer"),
+                engineer(),
+            ),
+            (Matcher::custom(|_| true), "not json".into()),
+        ]);
+        let request = CapabilityRequest::new(spec(), RequestSource::Agent);
+        Orchestrator::new(&client)
+            .run(&request)
+            .instrument(tracing::info_span!(
+                "mcp_request",
+                tool = "request_capability"
+            ))
+            .await;
+        provider.force_flush().unwrap();
+
+        let exported = exporter.get_finished_spans().unwrap();
+        let spans: HashMap<SpanId, &SpanData> = exported
+            .iter()
+            .map(|span| (span.span_context.span_id(), span))
+            .collect();
+        let lineages: BTreeSet<Vec<String>> = exported
+            .iter()
+            .filter(|span| span.name == "llm.chat")
+            .map(|span| lineage(span, &spans))
+            .collect();
+        let expected: BTreeSet<Vec<String>> = ["architect", "engineer", "qa", "red_team"]
+            .into_iter()
+            .map(|phase| {
+                ["llm.chat", phase, "pipeline", "mcp_request"]
+                    .map(String::from)
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(lineages, expected);
+
+        let chat = exported.iter().find(|s| s.name == "llm.chat").unwrap();
+        let model = chat
+            .attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == "model")
+            .map(|kv| kv.value.to_string());
+        assert_eq!(model.as_deref(), Some("stub"));
+    }
+}
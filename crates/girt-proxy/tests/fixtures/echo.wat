@@ -0,0 +1,31 @@
+;; Minimal girt-tool component: `run(input) -> ok(input)`.
+;;
+;; Source for echo.wasm, the prebuilt fixture used by proxy_harness.rs.
+;; Regenerate with: wasm-tools parse echo.wat -o echo.wasm
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+
+    ;; Bump allocator; the component is instantiated fresh for every call.
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+
+    ;; result<string, string> is returned via a pointer to
+    ;; { tag: u8 @0, ptr: i32 @4, len: i32 @8 }.
+    (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+      (i32.store8 (i32.const 16) (i32.const 0))
+      (i32.store (i32.const 20) (local.get $ptr))
+      (i32.store (i32.const 24) (local.get $len))
+      (i32.const 16))
+  )
+  (core instance $i (instantiate $m))
+  (func (export "run") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $i "run") (memory $i "memory") (realloc (func $i "realloc"))))
+)
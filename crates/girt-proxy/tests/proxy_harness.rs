@@ -0,0 +1,2047 @@
+//! End-to-end tests for `GirtProxy` through the rmcp `ServerHandler` path.
+//!
+//! Each test starts a proxy wired to a real `DecisionEngine` and
+//! `LifecycleManager`, connects an in-process MCP client over an in-memory
+//! duplex pipe, and drives initialize → list_tools → call_tool exactly as an
+//! agent would.
+//!
+//! Tools are backed by `tests/fixtures/echo.wasm`, a prebuilt component whose
+//! `run` returns its input unchanged (source: `echo.wat`), by `fail.wasm`,
+//! or by `fail.wasm`, which returns its input as the error (source:
+//! `fail.wat`). The bundled `girt_echo` component is the one girt-runtime
+//! embeds.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use girt_core::engine::DecisionEngine;
+use girt_core::faults::{FaultInjector, FaultKind};
+use girt_core::layers::force_ask::AlwaysAsk;
+use girt_core::layers::policy::{ConstraintPatterns, PolicyPattern};
+use girt_core::spec::{CapabilityConstraints, CapabilitySpec};
+use girt_pipeline::cache::ToolCache;
+use girt_pipeline::compiler::{BuildSandbox, WasmCompiler};
+use girt_pipeline::config::{SecurityConfig, ServerConfig};
+use girt_pipeline::llm::{LlmClient, Matcher, StubLlmClient};
+use girt_pipeline::publish::Publisher;
+use girt_pipeline::queue::Queue;
+use girt_pipeline::tool_sync::ToolSync;
+use girt_pipeline::types::{CapabilityRequest, CodingStandards, RequestSource};
+use girt_proxy::approvals::{self, ApprovalStore, Resolution};
+use girt_proxy::history::{self, InvocationHistory, InvocationStatus};
+use girt_proxy::proxy::{GirtProxy, NAME_PREFIX_META};
+use girt_proxy::quarantine;
+use girt_runtime::bundled;
+use girt_runtime::storage::ComponentStorage;
+use girt_runtime::{ComponentMeta, LifecycleManager};
+use girt_secrets::store::{MemorySecretStore, SecretStore};
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, Meta, PaginatedRequestParams, ReadResourceRequestParams,
+    ResourceContents,
+};
+use rmcp::service::RunningService;
+use rmcp::{RoleClient, ServiceExt};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn echo_fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/echo.wasm")
+}
+
+fn fail_fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fail.wasm")
+}
+
+/// Allow-list a tool name in both gates so calls skip the deferring stubs.
+fn allow(name_pattern: &str) -> PolicyPattern {
+    PolicyPattern {
+        description: format!("test allow {name_pattern}"),
+        name_pattern: Some(name_pattern.into()),
+        description_pattern: None,
+        constraint_patterns: None,
+    }
+}
+
+fn echo_meta(tool_name: &str) -> ComponentMeta {
+    ComponentMeta {
+        component_id: format!("{tool_name}@0.1.0"),
+        tool_name: tool_name.into(),
+        description: "Returns its input unchanged".into(),
+        input_schema: json!({
+            "type": "object",
+            "properties": { "message": { "type": "string" } }
+        }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    }
+}
+
+fn call_params(value: serde_json::Value) -> CallToolRequestParams {
+    serde_json::from_value(value).unwrap()
+}
+
+fn json_of(result: &CallToolResult) -> serde_json::Value {
+    let text = &result.content[0].as_text().expect("text content").text;
+    serde_json::from_str(text).expect("JSON content")
+}
+
+/// A proxy under test plus the MCP client connected to it.
+struct Harness {
+    client: RunningService<RoleClient, ()>,
+    proxy: GirtProxy,
+    tmp: TempDir,
+}
+
+struct HarnessBuilder {
+    tmp: TempDir,
+    llm: Arc<dyn LlmClient>,
+    preload: Vec<(PathBuf, ComponentMeta)>,
+    persisted: Vec<(PathBuf, ComponentMeta)>,
+    compiler: Option<WasmCompiler>,
+    server: Option<ServerConfig>,
+    history: bool,
+    secrets: Option<Arc<dyn SecretStore>>,
+    build_queue: bool,
+    always_ask: AlwaysAsk,
+    bundled: bool,
+    deny: Vec<PolicyPattern>,
+    recheck: bool,
+    faults: Option<FaultInjector>,
+    approval_spool: bool,
+    agent_approvals: bool,
+    tool_sync: Option<ToolSync>,
+}
+
+impl Harness {
+    fn builder() -> HarnessBuilder {
+        Self::builder_in(TempDir::new().unwrap())
+    }
+
+    fn builder_in(tmp: TempDir) -> HarnessBuilder {
+        HarnessBuilder {
+            tmp,
+            llm: Arc::new(StubLlmClient::constant("stub response")),
+            preload: vec![],
+            persisted: vec![],
+            compiler: None,
+            server: None,
+            history: false,
+            secrets: None,
+            build_queue: false,
+            always_ask: AlwaysAsk::default(),
+            bundled: false,
+            deny: vec![],
+            recheck: false,
+            faults: None,
+            approval_spool: false,
+            agent_approvals: false,
+            tool_sync: None,
+        }
+    }
+
+    /// Shut this proxy down and configure a new one over the same
+    /// directory, as a restart would.
+    fn restart(self) -> HarnessBuilder {
+        Self::builder_in(self.tmp)
+    }
+
+    fn path(&self) -> &Path {
+        self.tmp.path()
+    }
+
+    /// Connect another MCP client to the same proxy.
+    async fn connect(&self) -> RunningService<RoleClient, ()> {
+        connect(&self.proxy).await
+    }
+
+    /// The `girt://state` document.
+    async fn state(&self) -> serde_json::Value {
+        let read = self
+            .client
+            .read_resource(ReadResourceRequestParams {
+                meta: None,
+                uri: "girt://state".into(),
+            })
+            .await
+            .expect("read_resource");
+        let ResourceContents::TextResourceContents { text, .. } = &read.contents[0] else {
+            panic!("state is not text: {read:?}");
+        };
+        serde_json::from_str(text).unwrap()
+    }
+
+    async fn call(&self, params: serde_json::Value) -> CallToolResult {
+        self.client
+            .call_tool(call_params(params))
+            .await
+            .expect("call_tool")
+    }
+}
+
+impl HarnessBuilder {
+    /// Load the echo fixture under `tool_name` before the client connects.
+    fn with_echo_tool(self, tool_name: &str) -> Self {
+        self.with_tool(echo_fixture(), echo_meta(tool_name))
+    }
+
+    /// Load `fixture` with `meta` before the client connects.
+    fn with_tool(mut self, fixture: PathBuf, meta: ComponentMeta) -> Self {
+        self.preload.push((fixture, meta));
+        self
+    }
+
+    /// Store `fixture` with `meta` as if a previous session had built it,
+    /// so it is restored from disk rather than loaded.
+    fn with_persisted_tool(mut self, fixture: PathBuf, meta: ComponentMeta) -> Self {
+        self.persisted.push((fixture, meta));
+        self
+    }
+
+    fn with_llm(mut self, llm: impl LlmClient + 'static) -> Self {
+        self.llm = Arc::new(llm);
+        self
+    }
+
+    /// Like [`Self::with_llm`], keeping a handle to inspect requests.
+    fn with_shared_llm(mut self, llm: Arc<StubLlmClient>) -> Self {
+        self.llm = llm;
+        self
+    }
+
+    fn with_compiler(mut self, compiler: WasmCompiler) -> Self {
+        self.compiler = Some(compiler);
+        self
+    }
+
+    fn with_server_config(mut self, config: ServerConfig) -> Self {
+        self.server = Some(config);
+        self
+    }
+
+    /// Record invocations under `history/` in the harness directory.
+    fn with_invocation_history(mut self) -> Self {
+        self.history = true;
+        self
+    }
+
+    fn with_secret_store(mut self, store: impl SecretStore + 'static) -> Self {
+        self.secrets = Some(Arc::new(store));
+        self
+    }
+
+    /// Queue builds under `queue/` in the harness directory.
+    fn with_build_queue(mut self) -> Self {
+        self.build_queue = true;
+        self
+    }
+
+    /// Configure `[security.always_ask]`.
+    fn with_always_ask(mut self, always_ask: AlwaysAsk) -> Self {
+        self.always_ask = always_ask;
+        self
+    }
+
+    /// Load every bundled component, as `GIRT_DEV_TOOLS=1` does.
+    fn with_bundled_tools(mut self) -> Self {
+        self.bundled = true;
+        self
+    }
+
+    /// Break calls as `[runtime.fault_injection]` does.
+    fn with_fault_injector(mut self, injector: FaultInjector) -> Self {
+        self.faults = Some(injector);
+        self
+    }
+
+    /// Spool approvals under `approvals/` in the harness directory and
+    /// apply operator resolutions from it, as `girt serve` does.
+    fn with_approval_spool(mut self) -> Self {
+        self.approval_spool = true;
+        self
+    }
+
+    /// Let the agent answer Creation Gate asks, as
+    /// `[security] agent_approvals` does.
+    fn with_agent_approvals(mut self) -> Self {
+        self.agent_approvals = true;
+        self
+    }
+
+    /// Mirror built tools with `tool_sync`, as `[registry] source_repo` does.
+    fn with_tool_sync(mut self, tool_sync: ToolSync) -> Self {
+        self.tool_sync = Some(tool_sync);
+        self
+    }
+
+    /// Add a policy deny rule.
+    fn with_deny(mut self, pattern: PolicyPattern) -> Self {
+        self.deny.push(pattern);
+        self
+    }
+
+    /// Re-check restored tools against the policy rules, as
+    /// `[security] recheck_on_restore` does.
+    fn with_restore_recheck(mut self) -> Self {
+        self.recheck = true;
+        self
+    }
+
+    fn path(&self) -> &Path {
+        self.tmp.path()
+    }
+
+    async fn start(self) -> Harness {
+        let engine = Arc::new(DecisionEngine::with_defaults().with_always_ask(self.always_ask));
+        engine
+            .reload_policy(
+                self.deny,
+                vec![allow(
+                    "^(echo|word_count|flaky_.*|slow_.*|old_echo|retired_echo)$",
+                )],
+            )
+            .await;
+
+        let storage = ComponentStorage::new(self.tmp.path().join("components"));
+        storage.init().unwrap();
+        for (fixture, mut meta) in self.persisted {
+            storage.store(&fixture, &mut meta).unwrap();
+        }
+        let mut runtime = LifecycleManager::new(Some(self.tmp.path().join("components"))).unwrap();
+        if let Some(injector) = self.faults {
+            runtime = runtime.with_fault_injector(injector);
+        }
+        let runtime = Arc::new(runtime);
+        runtime.load_persisted().await;
+        if self.recheck {
+            let cache = ToolCache::new(self.tmp.path().join("tools"));
+            quarantine::recheck_restored(&engine, &runtime, &cache).await;
+        }
+        for (fixture, meta) in self.preload {
+            runtime.load_component(&fixture, meta).await.unwrap();
+        }
+        if self.bundled {
+            for component in bundled::ALL {
+                runtime.load_bundled(component.meta()).await.unwrap();
+            }
+        }
+
+        let publisher = Publisher::new(ToolCache::new(self.tmp.path().join("tools")));
+        publisher.init().await.unwrap();
+
+        let mut proxy = GirtProxy::new(
+            engine,
+            self.llm,
+            Arc::new(publisher),
+            runtime,
+            CodingStandards::default(),
+            self.tool_sync.map(Arc::new),
+        );
+        if let Some(compiler) = self.compiler {
+            proxy = proxy.with_compiler(compiler);
+        }
+        if let Some(server) = &self.server {
+            proxy = proxy.with_server_config(server);
+        }
+        if let Some(secrets) = self.secrets {
+            proxy = proxy.with_secret_store(secrets);
+        }
+        if self.agent_approvals {
+            proxy = proxy.with_security_config(&SecurityConfig {
+                agent_approvals: true,
+                ..SecurityConfig::default()
+            });
+        }
+        if self.build_queue {
+            let queue = Queue::new(self.tmp.path().join("queue"));
+            queue.init().await.unwrap();
+            proxy = proxy.with_build_queue(queue);
+        }
+        if self.history {
+            proxy = proxy.with_invocation_history(InvocationHistory::new(
+                self.tmp.path().join("history"),
+                20,
+            ));
+        }
+        if self.approval_spool {
+            proxy = proxy.with_approvals(
+                ApprovalStore::new(approvals::DEFAULT_TTL)
+                    .with_spool_dir(self.tmp.path().join("approvals")),
+            );
+            proxy.spawn_approval_watcher();
+        }
+
+        Harness {
+            client: connect(&proxy).await,
+            proxy,
+            tmp: self.tmp,
+        }
+    }
+}
+
+/// Serve `proxy` to a new in-process client over an in-memory duplex pipe.
+async fn connect(proxy: &GirtProxy) -> RunningService<RoleClient, ()> {
+    let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+    let proxy = proxy.clone();
+    tokio::spawn(async move {
+        let server = proxy.serve(server_io).await.expect("server initialize");
+        let _ = server.waiting().await;
+    });
+    ().serve(client_io).await.expect("client initialize")
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn list_tools_includes_loaded_echo_tool() {
+    let harness = Harness::builder().with_echo_tool("echo").start().await;
+
+    let tools = harness.client.list_all_tools().await.unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_ref()).collect();
+
+    assert!(names.contains(&"request_capability"), "{names:?}");
+    assert!(names.contains(&"echo"), "{names:?}");
+}
+
+#[tokio::test]
+async fn builtins_win_over_a_persisted_tool_of_the_same_name() {
+    // Built before `girt_info` was reserved, so it is still on disk.
+    let harness = Harness::builder()
+        .with_persisted_tool(echo_fixture(), echo_meta("girt_info"))
+        .start()
+        .await;
+
+    let tools = harness.client.list_all_tools().await.unwrap();
+    let listed = tools.iter().filter(|t| t.name == "girt_info").count();
+    assert_eq!(listed, 1);
+    let info = tools.iter().find(|t| t.name == "girt_info").unwrap();
+    assert_ne!(
+        info.description.as_deref(),
+        Some("Returns its input unchanged")
+    );
+
+    let result = harness
+        .call(json!({ "name": "girt_info", "arguments": { "message": "hi" } }))
+        .await;
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let response = json_of(&result);
+    assert!(response.get("version").is_some(), "{response}");
+    assert!(response.get("message").is_none(), "{response}");
+}
+
+#[tokio::test]
+async fn tool_info_says_where_a_tool_was_published() {
+    let harness = Harness::builder()
+        .with_persisted_tool(
+            echo_fixture(),
+            ComponentMeta {
+                oci_reference: Some("ghcr.io/acme/girt-tools/echo:v1".into()),
+                source_repo_path: Some("https://github.com/acme/tools/tree/main/tools/echo".into()),
+                ..echo_meta("echo")
+            },
+        )
+        .with_echo_tool("echo_local")
+        .start()
+        .await;
+
+    let info = |name: &str| json!({ "name": "girt_tool_info", "arguments": { "name": name } });
+    let published = json_of(&harness.call(info("echo")).await);
+    assert_eq!(
+        published["oci_reference"],
+        "ghcr.io/acme/girt-tools/echo:v1"
+    );
+    assert_eq!(
+        published["source_repo_path"],
+        "https://github.com/acme/tools/tree/main/tools/echo"
+    );
+
+    let local = json_of(&harness.call(info("echo_local")).await);
+    assert!(local["oci_reference"].is_null(), "{local}");
+    assert!(local["source_repo_path"].is_null(), "{local}");
+}
+
+#[tokio::test]
+async fn list_tools_pages_follow_the_cursor() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_echo_tool("echo_b")
+        .with_echo_tool("echo_c")
+        .with_server_config(ServerConfig {
+            tools_page_size: 2,
+            ..ServerConfig::default()
+        })
+        .start()
+        .await;
+
+    let first = harness.client.list_tools(None).await.unwrap();
+    assert_eq!(first.tools.len(), 2);
+    let cursor = first.next_cursor.expect("more pages");
+    assert_eq!(cursor, first.tools[1].name);
+
+    // The client follows next_cursor through every page.
+    let mut names: Vec<String> = harness
+        .client
+        .list_all_tools()
+        .await
+        .unwrap()
+        .iter()
+        .map(|t| t.name.to_string())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "approve_capability",
+            "echo",
+            "echo_b",
+            "echo_c",
+            "extend_capability",
+            "girt_batch",
+            "girt_info",
+            "girt_list_services",
+            "girt_tool_info",
+            "request_capability"
+        ]
+    );
+
+    let mut meta = Meta::new();
+    meta.insert(NAME_PREFIX_META.into(), "echo_".into());
+    let filtered = harness
+        .client
+        .list_tools(Some(PaginatedRequestParams {
+            meta: Some(meta),
+            cursor: None,
+        }))
+        .await
+        .unwrap();
+    let filtered: Vec<&str> = filtered.tools.iter().map(|t| t.name.as_ref()).collect();
+    assert_eq!(filtered, vec!["echo_b", "echo_c"]);
+}
+
+#[tokio::test]
+async fn each_client_connection_is_tracked_until_it_closes() {
+    let harness = Harness::builder().start().await;
+    let second = harness.connect().await;
+    assert_eq!(harness.proxy.connected_clients(), 2);
+
+    second.cancel().await.unwrap();
+    for _ in 0..100 {
+        if harness.proxy.connected_clients() == 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(harness.proxy.connected_clients(), 1);
+}
+
+#[tokio::test]
+async fn state_resource_reports_tools_and_queue_depth() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_build_queue()
+        .start()
+        .await;
+    let queue = Queue::new(harness.path().join("queue"));
+    let spec = CapabilitySpec {
+        name: "word_count".into(),
+        description: "Count the words in a string".into(),
+        inputs: json!({ "type": "object" }),
+        outputs: json!({ "type": "object" }),
+        constraints: CapabilityConstraints::default(),
+    };
+    queue
+        .enqueue(&CapabilityRequest::new(spec, RequestSource::Operator))
+        .await
+        .unwrap();
+
+    let resources = harness.client.list_all_resources().await.unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].uri, "girt://state");
+
+    let state = harness.state().await;
+    assert_eq!(state["tools"]["loaded"], 1, "{state}");
+    assert_eq!(state["queue"]["pending"], 1, "{state}");
+    assert_eq!(state["queue"]["in_progress"], 0, "{state}");
+    assert_eq!(state["builds"], json!([]));
+    assert_eq!(state["approvals"]["pending"], 0);
+
+    // Calls are counted by their gate decision.
+    harness
+        .call(json!({ "name": "echo", "arguments": {} }))
+        .await;
+    let state = harness.state().await;
+    assert_eq!(state["decisions"]["execution"]["allowed"], 1, "{state}");
+
+    let missing = harness
+        .client
+        .read_resource(ReadResourceRequestParams {
+            meta: None,
+            uri: "girt://nothing".into(),
+        })
+        .await;
+    assert!(missing.is_err());
+}
+
+#[tokio::test]
+async fn calling_echo_returns_its_arguments() {
+    let harness = Harness::builder().with_echo_tool("echo").start().await;
+
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": { "message": "hello" } }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(json_of(&result), json!({ "message": "hello" }));
+}
+
+#[tokio::test]
+async fn tool_without_parameters_can_be_called_without_arguments() {
+    let harness = Harness::builder()
+        .with_tool(
+            echo_fixture(),
+            ComponentMeta {
+                input_schema: json!({ "type": "object", "properties": {} }),
+                ..echo_meta("echo")
+            },
+        )
+        .start()
+        .await;
+
+    let result = harness.call(json!({ "name": "echo" })).await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    assert_eq!(json_of(&result), json!({}));
+}
+
+#[tokio::test]
+async fn recorded_call_replays_to_the_same_result() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_invocation_history()
+        .start()
+        .await;
+    let arguments = json!({ "message": "hello", "count": 2 });
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": arguments }))
+        .await;
+
+    let records = history::load(&harness.path().join("history"), "echo").unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].status, InvocationStatus::Ok);
+    assert_eq!(records[0].arguments, arguments);
+
+    // What `girt tools replay` does: a fresh runtime over the same store.
+    let runtime = LifecycleManager::new(Some(harness.path().join("components"))).unwrap();
+    runtime.load_persisted().await;
+    let replayed = runtime
+        .call_tool("echo", &records[0].arguments)
+        .await
+        .unwrap();
+    assert_eq!(replayed, json_of(&result));
+}
+
+#[tokio::test]
+async fn denied_tool_returns_is_error() {
+    // Loaded, so the call reaches the Execution Gate, where the default
+    // shell-execution policy denies it.
+    let harness = Harness::builder()
+        .with_echo_tool("shell_exec")
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "shell_exec", "arguments": { "message": "ls" } }))
+        .await;
+
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(json_of(&result)["status"], "denied");
+}
+
+#[tokio::test]
+async fn idempotent_tool_is_retried_on_transient_errors() {
+    let harness = Harness::builder()
+        .with_tool(
+            fail_fixture(),
+            ComponentMeta {
+                idempotent: true,
+                ..echo_meta("flaky_lookup")
+            },
+        )
+        .with_tool(fail_fixture(), echo_meta("flaky_send"))
+        .start()
+        .await;
+
+    // Every call fails; the default two retries make three attempts.
+    let transient = json!({ "message": "upstream returned 502" });
+    let result = harness
+        .call(json!({ "name": "flaky_lookup", "arguments": transient }))
+        .await;
+    assert_eq!(result.is_error, Some(true));
+    let structured = result.structured_content.expect("structured content");
+    assert_eq!(structured["attempts"], 3);
+
+    // A permanent error is not retried.
+    let result = harness
+        .call(json!({ "name": "flaky_lookup", "arguments": { "message": "HTTP 404" } }))
+        .await;
+    assert!(result.structured_content.unwrap().get("attempts").is_none());
+
+    // Without the hint, even a transient error runs once.
+    let result = harness
+        .call(json!({ "name": "flaky_send", "arguments": transient }))
+        .await;
+    assert_eq!(result.is_error, Some(true));
+    assert!(result.structured_content.unwrap().get("attempts").is_none());
+}
+
+#[tokio::test]
+async fn oversized_responses_are_truncated_and_say_so() {
+    let harness = Harness::builder()
+        .with_tool(
+            echo_fixture(),
+            ComponentMeta {
+                max_response_bytes: Some(64),
+                ..echo_meta("echo")
+            },
+        )
+        .start()
+        .await;
+
+    let message = "x".repeat(1000);
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": { "message": message } }))
+        .await;
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let structured = result.structured_content.expect("structured content");
+    let text = structured["result"].as_str().expect("truncated text");
+    assert!(text.len() <= 64, "{text}");
+    assert!(text.contains("[truncated: 1014 bytes, limit 64]"), "{text}");
+    assert_eq!(
+        structured["truncated"],
+        json!({ "original_bytes": 1014, "limit_bytes": 64 })
+    );
+
+    let small = harness
+        .call(json!({ "name": "echo", "arguments": { "message": "hi" } }))
+        .await;
+    let structured = small.structured_content.expect("structured content");
+    assert_eq!(structured["message"], "hi");
+    assert!(structured.get("truncated").is_none());
+}
+
+fn enveloped() -> ServerConfig {
+    ServerConfig {
+        result_envelope: true,
+        ..ServerConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn enveloped_results_carry_data_and_meta() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_server_config(enveloped())
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": { "message": "hello" } }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    // The text content stays the raw output.
+    assert_eq!(json_of(&result), json!({ "message": "hello" }));
+    let structured = result.structured_content.expect("structured content");
+    assert_eq!(structured["ok"], true);
+    assert_eq!(structured["data"], json!({ "message": "hello" }));
+    let meta = &structured["meta"];
+    assert!(meta["duration_ms"].is_u64(), "{meta}");
+    assert_eq!(meta["truncated"], false);
+    assert_eq!(meta["tool_version"], "0.1.0");
+    assert_eq!(meta["cached"], false);
+}
+
+#[tokio::test]
+async fn enveloped_guest_errors_share_the_shape() {
+    let harness = Harness::builder()
+        .with_tool(fail_fixture(), echo_meta("flaky_send"))
+        .with_server_config(enveloped())
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "flaky_send", "arguments": { "message": "HTTP 404" } }))
+        .await;
+
+    assert_eq!(result.is_error, Some(true));
+    let structured = result.structured_content.expect("structured content");
+    assert_eq!(structured["ok"], false);
+    assert!(structured.get("data").is_none(), "{structured}");
+    assert_eq!(structured["error"]["kind"], "tool_error");
+    let message = structured["error"]["message"].as_str().unwrap();
+    assert!(message.contains("HTTP 404"), "{message}");
+    assert_eq!(structured["meta"]["tool_version"], "0.1.0");
+}
+
+#[tokio::test]
+async fn enveloped_truncation_is_flagged_in_meta() {
+    let harness = Harness::builder()
+        .with_tool(
+            echo_fixture(),
+            ComponentMeta {
+                max_response_bytes: Some(64),
+                ..echo_meta("echo")
+            },
+        )
+        .with_server_config(enveloped())
+        .start()
+        .await;
+
+    let message = "x".repeat(1000);
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": { "message": message } }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let structured = result.structured_content.expect("structured content");
+    assert_eq!(structured["ok"], true);
+    let text = structured["data"].as_str().expect("truncated text");
+    assert!(text.contains("[truncated: 1014 bytes, limit 64]"), "{text}");
+    assert_eq!(structured["meta"]["truncated"], true);
+    assert_eq!(
+        structured["meta"]["truncation"],
+        json!({ "original_bytes": 1014, "limit_bytes": 64 })
+    );
+}
+
+#[tokio::test]
+async fn deprecated_tool_warns_until_its_grace_period_ends() {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let deprecated = |tool_name: &str, deprecated_at: u64| ComponentMeta {
+        deprecated: true,
+        replaced_by: Some("echo".into()),
+        deprecated_at: Some(deprecated_at),
+        oci_reference: None,
+        source_repo_path: None,
+        ..echo_meta(tool_name)
+    };
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_tool(echo_fixture(), deprecated("old_echo", now_ms))
+        // Well past the default seven-day grace period.
+        .with_tool(echo_fixture(), deprecated("retired_echo", 1))
+        .start()
+        .await;
+
+    let tools = harness.client.list_all_tools().await.unwrap();
+    let old = tools.iter().find(|t| t.name == "old_echo").unwrap();
+    assert_eq!(
+        old.description.as_deref(),
+        Some("Returns its input unchanged [DEPRECATED — use echo]")
+    );
+
+    let result = harness
+        .call(json!({ "name": "old_echo", "arguments": { "message": "hi" } }))
+        .await;
+    assert_eq!(result.is_error, Some(false));
+    let structured = result.structured_content.expect("structured content");
+    assert_eq!(structured["message"], "hi");
+    assert_eq!(structured["deprecation"]["replaced_by"], "echo");
+    assert_eq!(structured["deprecation"]["deprecated_at"], now_ms);
+
+    let err = harness
+        .client
+        .call_tool(call_params(
+            json!({ "name": "retired_echo", "arguments": { "message": "hi" } }),
+        ))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("use 'echo' instead"), "{err:?}");
+}
+
+#[tokio::test]
+async fn restored_tool_denied_by_a_new_rule_is_quarantined_until_released() {
+    let internal = ComponentMeta {
+        allowed_hosts: vec!["billing.internal.corp".into()],
+        ..echo_meta("echo")
+    };
+    let deny_internal = || PolicyPattern {
+        description: "no internal hosts".into(),
+        name_pattern: None,
+        description_pattern: None,
+        constraint_patterns: Some(ConstraintPatterns {
+            network_deny: Some(vec![r"\.internal\.corp$".into()]),
+            storage_deny: None,
+            secrets_deny: None,
+        }),
+    };
+    let listed = |tools: &[rmcp::model::Tool]| tools.iter().any(|t| t.name == "echo");
+    let call = json!({ "name": "echo", "arguments": { "message": "hi" } });
+
+    let harness = Harness::builder()
+        .with_persisted_tool(echo_fixture(), internal)
+        .with_restore_recheck()
+        .start()
+        .await;
+    assert!(listed(&harness.client.list_all_tools().await.unwrap()));
+
+    // The deny rule arrives with the next restart.
+    let harness = harness
+        .restart()
+        .with_deny(deny_internal())
+        .with_restore_recheck()
+        .start()
+        .await;
+    assert!(!listed(&harness.client.list_all_tools().await.unwrap()));
+    let err = harness
+        .client
+        .call_tool(call_params(call.clone()))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("quarantined"), "{err:?}");
+    assert!(
+        format!("{err:?}").contains("girt tools release echo"),
+        "{err:?}"
+    );
+    let storage = ComponentStorage::new(harness.path().join("components"));
+    let stored = storage.load_meta("echo@0.1.0").unwrap();
+    assert_eq!(
+        stored.quarantined.as_deref(),
+        Some("Policy rule: no internal hosts")
+    );
+
+    // Released tools stay released, even under the same rule.
+    LifecycleManager::new(Some(harness.path().join("components")))
+        .unwrap()
+        .release("echo")
+        .await
+        .unwrap();
+    let harness = harness
+        .restart()
+        .with_deny(deny_internal())
+        .with_restore_recheck()
+        .start()
+        .await;
+    assert!(listed(&harness.client.list_all_tools().await.unwrap()));
+    let result = harness.call(call).await;
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+}
+
+#[tokio::test]
+async fn list_services_names_services_and_tool_grants() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_tool(
+            echo_fixture(),
+            ComponentMeta {
+                allowed_env: vec!["GITHUB_TOKEN".into()],
+                ..echo_meta("gh_issues")
+            },
+        )
+        .with_tool(
+            echo_fixture(),
+            ComponentMeta {
+                allowed_env: vec!["OPENAI_API_KEY".into(), "NPM_TOKEN".into()],
+                ..echo_meta("summarize")
+            },
+        )
+        .with_secret_store(MemorySecretStore::new(
+            [("github", "ghp_value"), ("openai", "sk-value")]
+                .into_iter()
+                .map(|(service, value)| (service.into(), value.into()))
+                .collect(),
+        ))
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "girt_list_services", "arguments": {} }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    assert_eq!(
+        json_of(&result),
+        json!({
+            "backend": "memory",
+            "services": ["github", "openai"],
+            "tools": { "gh_issues": ["github"], "summarize": ["openai"] }
+        })
+    );
+
+    let tools = harness.client.list_all_tools().await.unwrap();
+    let tool = tools
+        .iter()
+        .find(|t| t.name == "girt_list_services")
+        .unwrap();
+    assert_eq!(
+        tool.annotations.as_ref().unwrap().read_only_hint,
+        Some(true)
+    );
+}
+
+#[tokio::test]
+async fn batch_reports_each_call_on_its_own() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_echo_tool("shell_exec")
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({
+            "name": "girt_batch",
+            "arguments": { "calls": [
+                { "tool": "echo", "args": { "message": "hi" } },
+                { "tool": "shell_exec", "args": { "message": "ls" } },
+                { "tool": "no_such_tool" },
+                { "tool": "girt_info" }
+            ] }
+        }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let response = json_of(&result);
+    assert_eq!(response["mode"], "sequential");
+    let results = response["results"].as_array().unwrap();
+    let statuses: Vec<&str> = results
+        .iter()
+        .map(|r| r["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["allow", "deny", "error", "error"]);
+    assert_eq!(results[0]["result"], json!({ "message": "hi" }));
+    assert_eq!(results[1]["result"]["status"], "denied");
+    assert!(
+        results[3]["error"].as_str().unwrap().contains("built in"),
+        "{results:?}"
+    );
+    assert_eq!(response["allowed"], 1);
+    assert_eq!(response["denied"], 1);
+    assert_eq!(response["failed"], 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn parallel_batch_overlaps_its_calls() {
+    // Every call runs into its time limit on the paused clock, so how long
+    // a batch takes says how many of its calls overlapped.
+    let slow = |tool_name: &str| ComponentMeta {
+        timeout_ms: Some(10_000),
+        ..echo_meta(tool_name)
+    };
+    let harness = Harness::builder()
+        .with_tool(echo_fixture(), slow("slow_a"))
+        .with_tool(echo_fixture(), slow("slow_b"))
+        .with_tool(echo_fixture(), slow("slow_c"))
+        .with_fault_injector(FaultInjector::new(1.0, vec![FaultKind::Timeout], 1))
+        .start()
+        .await;
+    let batch = |mode: &str| {
+        json!({
+            "name": "girt_batch",
+            "arguments": {
+                "mode": mode,
+                "calls": [
+                    { "tool": "slow_a", "args": { "message": "a" } },
+                    { "tool": "slow_b", "args": { "message": "b" } },
+                    { "tool": "slow_c", "args": { "message": "c" } }
+                ]
+            }
+        })
+    };
+
+    let started = tokio::time::Instant::now();
+    let response = json_of(&harness.call(batch("parallel")).await);
+    assert!(started.elapsed() < Duration::from_secs(20), "{response}");
+
+    assert_eq!(response["failed"], 3, "{response}");
+    // Results keep the order the calls were given in.
+    let tools: Vec<&str> = response["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["tool"].as_str().unwrap())
+        .collect();
+    assert_eq!(tools, ["slow_a", "slow_b", "slow_c"]);
+
+    let started = tokio::time::Instant::now();
+    let sequential = json_of(&harness.call(batch("sequential")).await);
+    assert!(started.elapsed() >= Duration::from_secs(30), "{sequential}");
+}
+
+#[tokio::test]
+async fn batch_larger_than_the_limit_is_refused() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_server_config(ServerConfig {
+            batch_max_calls: 2,
+            ..ServerConfig::default()
+        })
+        .start()
+        .await;
+    let calls: Vec<_> = (0..3)
+        .map(|i| json!({ "tool": "echo", "args": { "message": format!("{i}") } }))
+        .collect();
+
+    let err = harness
+        .client
+        .call_tool(call_params(
+            json!({ "name": "girt_batch", "arguments": { "calls": calls } }),
+        ))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("at most 2"), "{err:?}");
+
+    let calls = &calls[..2];
+    let result = harness
+        .call(json!({ "name": "girt_batch", "arguments": { "calls": calls } }))
+        .await;
+    assert_eq!(json_of(&result)["allowed"], 2, "{result:?}");
+}
+
+/// Write a `cargo-component` stand-in that "builds" the echo fixture.
+#[cfg(unix)]
+fn fake_cargo_component(dir: &Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = dir.join("cargo-component");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nout=\"${{CARGO_TARGET_DIR:-target}}/wasm32-wasip1/release\"\nmkdir -p \"$out\" && cp '{}' \"$out/$(basename \"$PWD\").wasm\"\n",
+            echo_fixture().display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+/// LLM responses for one clean pass of Architect → Engineer → QA → Red Team.
+#[cfg(unix)]
+fn stub_pipeline(name: &str, description: &str) -> StubLlmClient {
+    StubLlmClient::new(stub_responses(name, description).to_vec())
+}
+
+/// Like [`stub_pipeline`], answering each agent by its system prompt, so
+/// builds and extensions, which skip the Architect, can share it.
+#[cfg(unix)]
+fn keyed_stub_pipeline(name: &str, description: &str) -> StubLlmClient {
+    let [architect, engineer, qa, red_team] = stub_responses(name, description);
+    StubLlmClient::with_matchers(vec![
+        (
+            Matcher::system_prompt("Chief Software Architect"),
+            architect,
+        ),
+        (
+            Matcher::system_prompt("Senior Backend Engineer"),
+            engineer.clone(),
+        ),
+        (
+            Matcher::system_prompt("Extend it with the features"),
+            engineer,
+        ),
+        (Matcher::system_prompt("QA Automation Engineer"), qa),
+        (
+            Matcher::system_prompt("Offensive Security Researcher"),
+            red_team,
+        ),
+    ])
+}
+
+/// Architect, Engineer, QA and Red Team answers for a clean build.
+#[cfg(unix)]
+fn stub_responses(name: &str, description: &str) -> [String; 4] {
+    let spec = json!({
+        "name": name,
+        "description": description,
+        "inputs": {},
+        "outputs": {},
+        "constraints": { "network": [], "storage": [], "secrets": [] }
+    });
+    [
+        json!({ "action": "build", "spec": spec, "design_notes": "stub" }).to_string(),
+        json!({
+            "source_code": "// built by the stub pipeline\n\
+                            #[allow(warnings)]\nmod bindings;\n\
+                            bindings::export!(Component with_types_in bindings);\n",
+            "wit_definition": "",
+            "policy_yaml": "version: \"1.0\"",
+            "language": "rust"
+        })
+        .to_string(),
+        json!({
+            "passed": true,
+            "tests_run": 3,
+            "tests_passed": 3,
+            "tests_failed": 0,
+            "bug_tickets": []
+        })
+        .to_string(),
+        json!({
+            "passed": true,
+            "exploits_attempted": 2,
+            "exploits_succeeded": 0,
+            "bug_tickets": []
+        })
+        .to_string(),
+    ]
+}
+
+/// A harness whose builds go through `llm` and the fake `cargo-component`.
+#[cfg(unix)]
+async fn building_harness(llm: StubLlmClient) -> Harness {
+    start_building(Harness::builder().with_llm(llm)).await
+}
+
+#[cfg(unix)]
+async fn start_building(builder: HarnessBuilder) -> Harness {
+    let compiler = WasmCompiler::new()
+        .with_cache_dir(builder.path().join("build-cache"))
+        .with_sandbox(BuildSandbox::Off)
+        .with_binary(fake_cargo_component(builder.path()).display().to_string());
+    builder.with_compiler(compiler).start().await
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn request_capability_with_stub_pipeline_builds_and_loads_tool() {
+    let harness =
+        building_harness(stub_pipeline("word_count", "Count the words in a string")).await;
+
+    let result = harness
+        .call(json!({
+            "name": "request_capability",
+            "arguments": {
+                "name": "word_count",
+                "description": "Count the words in a string"
+            }
+        }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let response = json_of(&result);
+    assert_eq!(response["status"], "built");
+    assert_eq!(response["tool_name"], "word_count");
+    assert_eq!(response["tests_passed"], 3);
+
+    let tools = harness.client.list_all_tools().await.unwrap();
+    assert!(tools.iter().any(|t| t.name == "word_count"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn failed_compile_reports_its_error_code() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let builder =
+        Harness::builder().with_llm(stub_pipeline("word_count", "Count the words in a string"));
+    let script = builder.path().join("cargo-component");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\necho 'error[E0425]: cannot find value' >&2\nexit 1\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let compiler = WasmCompiler::new()
+        .with_cache_dir(builder.path().join("build-cache"))
+        .with_sandbox(BuildSandbox::Off)
+        .with_binary(script.display().to_string());
+    let harness = builder.with_compiler(compiler).start().await;
+
+    let result = harness.call(word_count_request(json!({}))).await;
+    assert_eq!(result.is_error, Some(true));
+    let response = json_of(&result);
+    assert_eq!(response["status"], "compile_failed");
+    assert_eq!(response["error_code"], "compile_failed");
+    assert!(
+        response["error"]
+            .as_str()
+            .unwrap()
+            .contains("error[E0425]: cannot find value"),
+        "{response}"
+    );
+}
+
+/// `word_count`, built once through a harness sharing `llm`.
+#[cfg(unix)]
+async fn built_word_count(llm: &Arc<StubLlmClient>) -> Harness {
+    let harness = start_building(Harness::builder().with_shared_llm(Arc::clone(llm))).await;
+    let built = harness.call(word_count_request(json!({}))).await;
+    assert_eq!(json_of(&built)["status"], "built", "{built:?}");
+    harness
+}
+
+/// An empty bare git repository under `dir`, as a sync target.
+#[cfg(unix)]
+fn bare_repo(dir: &Path) -> String {
+    let path = dir.join("remote.git");
+    let status = std::process::Command::new("git")
+        .args(["init", "--quiet", "--bare"])
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    path.display().to_string()
+}
+
+/// `git cat-file -t <rev>` in `repo`, or `None` when `rev` does not exist.
+#[cfg(unix)]
+fn object_type(repo: &str, rev: &str) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["--git-dir", repo, "cat-file", "-t", rev])
+        .output()
+        .unwrap();
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Wait for the background sync to put `tool`'s directory on `repo`'s main.
+#[cfg(unix)]
+async fn wait_for_sync(repo: &str, tool: &str) -> bool {
+    for _ in 0..100 {
+        if object_type(repo, &format!("main:tools/{tool}")).as_deref() == Some("tree") {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    false
+}
+
+fn word_count_request(extra: serde_json::Value) -> serde_json::Value {
+    let mut arguments = json!({
+        "name": "word_count",
+        "description": "Count the words in a string",
+        "inputs": {},
+        "outputs": {}
+    });
+    arguments
+        .as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    json!({ "name": "request_capability", "arguments": arguments })
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn built_tool_is_synced_to_the_source_repo() {
+    let builder =
+        Harness::builder().with_llm(stub_pipeline("word_count", "Count the words in a string"));
+    let remote = bare_repo(builder.path());
+    let harness = start_building(builder.with_tool_sync(ToolSync::new(&remote))).await;
+
+    let result = harness.call(word_count_request(json!({}))).await;
+    let response = json_of(&result);
+    assert_eq!(response["status"], "built", "{result:?}");
+    assert_eq!(response["tool_sync"], "scheduled");
+
+    assert!(
+        wait_for_sync(&remote, "word_count").await,
+        "tools/word_count never reached main"
+    );
+}
+
+/// `tool`'s `source_repo_path` per `girt_tool_info`, once it is set.
+#[cfg(unix)]
+async fn wait_for_source_path(harness: &Harness, tool: &str) -> serde_json::Value {
+    let info = json!({ "name": "girt_tool_info", "arguments": { "name": tool } });
+    let mut path = serde_json::Value::Null;
+    for _ in 0..100 {
+        path = json_of(&harness.call(info.clone()).await)["source_repo_path"].clone();
+        if !path.is_null() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    path
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn synced_tool_keeps_its_source_path_across_rebuilds() {
+    let builder = Harness::builder().with_llm(keyed_stub_pipeline(
+        "word_count",
+        "Count the words in a string",
+    ));
+    let remote = bare_repo(builder.path());
+    let harness = start_building(builder.with_tool_sync(ToolSync::new(&remote))).await;
+    let expected = json!(format!("{remote} (main:tools/word_count)"));
+
+    // The sync records the path on the tool it was started for.
+    let built = harness.call(word_count_request(json!({}))).await;
+    assert_eq!(json_of(&built)["status"], "built", "{built:?}");
+    assert!(wait_for_sync(&remote, "word_count").await);
+    assert_eq!(wait_for_source_path(&harness, "word_count").await, expected);
+
+    // The previous build's sync finished before the rebuilt tool was
+    // loaded; the path it recorded is still there.
+    let forced = json!({ "force_rebuild": true });
+    let rebuilt = harness.call(word_count_request(forced)).await;
+    assert_eq!(json_of(&rebuilt)["status"], "built", "{rebuilt:?}");
+    let info = json!({ "name": "girt_tool_info", "arguments": { "name": "word_count" } });
+    assert_eq!(
+        json_of(&harness.call(info).await)["source_repo_path"],
+        expected
+    );
+    assert_eq!(wait_for_source_path(&harness, "word_count").await, expected);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn rerequesting_a_loaded_tool_skips_the_gate_and_pipeline() {
+    let llm = Arc::new(stub_pipeline("word_count", "Count the words in a string"));
+    let harness = built_word_count(&llm).await;
+    let llm_calls = llm.requests().len();
+
+    let again = harness.call(word_count_request(json!({}))).await;
+    assert_eq!(again.is_error, Some(false));
+    let response = json_of(&again);
+    assert_eq!(response["status"], "already_available");
+    assert_eq!(response["tool_name"], "word_count");
+    assert_eq!(response["diff"], json!({}));
+    assert_eq!(llm.requests().len(), llm_calls);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn quarantined_tool_is_not_offered_for_a_matching_request() {
+    let llm = Arc::new(stub_pipeline("word_count", "Count the words in a string"));
+    let harness = built_word_count(&llm).await;
+    // Quarantined as a new deny rule would on restore.
+    let storage = ComponentStorage::new(harness.path().join("components"));
+    for id in storage.list_component_ids().unwrap() {
+        let mut meta = storage.load_meta(&id).unwrap();
+        meta.quarantined = Some("Policy rule: no word counts".into());
+        storage.save_meta(&meta).unwrap();
+    }
+    let harness = start_building(harness.restart().with_shared_llm(Arc::clone(&llm))).await;
+    // As `girt serve` does at startup.
+    harness.proxy.refresh_known_tools().await;
+
+    let similar = harness
+        .call(json!({
+            "name": "request_capability",
+            "arguments": { "name": "count_string_words", "description": "Count words in a string" }
+        }))
+        .await;
+    let response = json_of(&similar);
+    assert_ne!(response["status"], "deferred", "{response}");
+
+    let again = harness.call(word_count_request(json!({}))).await;
+    let response = json_of(&again);
+    assert_ne!(response["status"], "already_available", "{response}");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn identical_request_during_a_build_is_not_built_twice() {
+    let llm = Arc::new(keyed_stub_pipeline(
+        "word_count",
+        "Count the words in a string",
+    ));
+    let builder = Harness::builder().with_shared_llm(Arc::clone(&llm));
+    // A compile slow enough that the second request arrives mid-build.
+    let script = fake_cargo_component(builder.path());
+    let fake = std::fs::read_to_string(&script).unwrap();
+    std::fs::write(
+        &script,
+        fake.replacen("#!/bin/sh\n", "#!/bin/sh\nsleep 2\n", 1),
+    )
+    .unwrap();
+    let compiler = WasmCompiler::new()
+        .with_cache_dir(builder.path().join("build-cache"))
+        .with_sandbox(BuildSandbox::Off)
+        .with_binary(script.display().to_string());
+    let harness = builder.with_compiler(compiler).start().await;
+    let other = harness.connect().await;
+
+    let second = async {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        other
+            .call_tool(call_params(word_count_request(json!({}))))
+            .await
+            .expect("call_tool")
+    };
+    let (first, second) = tokio::join!(harness.call(word_count_request(json!({}))), second);
+
+    assert_eq!(json_of(&first)["status"], "built", "{first:?}");
+    assert_eq!(second.is_error, Some(false));
+    let response = json_of(&second);
+    assert_eq!(response["status"], "already_queued", "{response}");
+    assert_eq!(response["tool_name"], "word_count");
+    assert!(response["request_id"].as_str().unwrap().starts_with("req_"));
+    let architect_calls = llm
+        .requests()
+        .iter()
+        .filter(|r| r.system_prompt.contains("Chief Software Architect"))
+        .count();
+    assert_eq!(architect_calls, 1);
+
+    // Once the build is done, asking again finds the tool.
+    let again = harness.call(word_count_request(json!({}))).await;
+    assert_eq!(json_of(&again)["status"], "already_available");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn request_with_a_new_input_field_is_built() {
+    let llm = Arc::new(stub_pipeline("word_count", "Count the words in a string"));
+    let harness = built_word_count(&llm).await;
+    let llm_calls = llm.requests().len();
+
+    let wider = json!({ "inputs": { "separator": "string" } });
+    let result = harness.call(word_count_request(wider)).await;
+    assert_eq!(json_of(&result)["status"], "built", "{result:?}");
+    assert!(llm.requests().len() > llm_calls);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn dry_run_reports_the_plan_without_building() {
+    let llm = Arc::new(keyed_stub_pipeline(
+        "word_count",
+        "Count the words in a string",
+    ));
+    let harness = start_building(Harness::builder().with_shared_llm(Arc::clone(&llm))).await;
+
+    let result = harness
+        .call(word_count_request(json!({ "dry_run": true })))
+        .await;
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let response = json_of(&result);
+    assert_eq!(response["status"], "dry_run");
+    assert_eq!(response["decision"], "allowed");
+    let report = &response["report"];
+    assert_eq!(report["refined_spec"]["spec"]["name"], "word_count");
+    assert_eq!(report["plan"], "stub");
+    assert_eq!(report["resource_tier"], "minimal");
+    assert_eq!(report["policy"]["version"], "1.0");
+
+    let prompts: Vec<String> = llm
+        .requests()
+        .into_iter()
+        .map(|r| r.system_prompt)
+        .collect();
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].contains("Chief Software Architect"));
+    let tools = harness.client.list_all_tools().await.unwrap();
+    assert!(!tools.iter().any(|t| t.name == "word_count"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn force_rebuild_builds_a_loaded_tool_again() {
+    let llm = Arc::new(stub_pipeline("word_count", "Count the words in a string"));
+    let harness = built_word_count(&llm).await;
+    let llm_calls = llm.requests().len();
+
+    let forced = json!({ "force_rebuild": true });
+    let result = harness.call(word_count_request(forced)).await;
+    assert_eq!(json_of(&result)["status"], "built", "{result:?}");
+    assert!(llm.requests().len() > llm_calls);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn resubmitted_request_tells_the_architect_why_it_was_denied() {
+    let llm = Arc::new(stub_pipeline("word_count", "Count the words in a string"));
+    let harness = start_building(Harness::builder().with_shared_llm(Arc::clone(&llm))).await;
+    let request = |network: serde_json::Value| {
+        json!({
+            "name": "request_capability",
+            "arguments": {
+                "name": "word_count",
+                "description": "Count the words in a string",
+                "constraints": { "network": network }
+            }
+        })
+    };
+
+    let denied = harness.call(request(json!(["*"]))).await;
+    assert_eq!(json_of(&denied)["status"], "denied");
+    let built = harness.call(request(json!([]))).await;
+    assert_eq!(json_of(&built)["status"], "built", "{built:?}");
+
+    let architect = &llm.requests()[0].messages[0].content;
+    assert!(
+        architect.contains(
+            "A previous version of this request was denied because: \
+             Policy rule: Wildcard network access"
+        ),
+        "{architect}"
+    );
+}
+
+fn extend_word_count(arguments: serde_json::Value) -> serde_json::Value {
+    let mut request = json!({ "target_tool": "word_count", "features": ["count unique words"] });
+    request
+        .as_object_mut()
+        .unwrap()
+        .extend(arguments.as_object().unwrap().clone());
+    json!({ "name": "extend_capability", "arguments": request })
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn extension_is_built_from_the_published_source() {
+    let llm = Arc::new(keyed_stub_pipeline(
+        "word_count",
+        "Count the words in a string",
+    ));
+    let harness = built_word_count(&llm).await;
+    let llm_calls = llm.requests().len();
+
+    let result = harness
+        .call(extend_word_count(
+            json!({ "new_inputs": { "unique": "boolean" } }),
+        ))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let response = json_of(&result);
+    assert_eq!(response["status"], "extended");
+    assert_eq!(response["tool_name"], "word_count");
+    assert_eq!(response["revision"], 1);
+
+    // The Engineer extended the published code; the Architect was skipped.
+    let requests = &llm.requests()[llm_calls..];
+    assert_eq!(requests.len(), 3, "{requests:?}");
+    assert!(
+        requests[0]
+            .system_prompt
+            .contains("Extend it with the features")
+    );
+    let prompt = &requests[0].messages[0].content;
+    assert!(prompt.contains("built by the stub pipeline"), "{prompt}");
+    assert!(prompt.contains("count unique words"), "{prompt}");
+
+    let published = ToolCache::new(harness.path().join("tools"))
+        .get("word_count")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(published.revision, 1);
+    assert_eq!(published.spec.inputs, json!({ "unique": "boolean" }));
+    let tools = harness.client.list_all_tools().await.unwrap();
+    assert!(tools.iter().any(|t| t.name == "word_count"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn extension_widening_network_access_is_denied() {
+    let llm = Arc::new(keyed_stub_pipeline(
+        "word_count",
+        "Count the words in a string",
+    ));
+    let harness = built_word_count(&llm).await;
+    let llm_calls = llm.requests().len();
+
+    let result = harness
+        .call(extend_word_count(
+            json!({ "new_constraints": { "network": ["*"] } }),
+        ))
+        .await;
+
+    assert_eq!(result.is_error, Some(true), "{result:?}");
+    let response = json_of(&result);
+    assert_eq!(response["status"], "denied");
+    assert_eq!(
+        response["spec_diff"]["constraints"],
+        json!([{ "change": "added", "key": "network", "value": "*" }])
+    );
+    assert_eq!(llm.requests().len(), llm_calls);
+    let published = ToolCache::new(harness.path().join("tools"))
+        .get("word_count")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(published.revision, 0);
+}
+
+#[tokio::test]
+async fn only_girt_built_tools_can_be_extended() {
+    let harness = Harness::builder().with_echo_tool("echo").start().await;
+
+    let err = harness
+        .client
+        .call_tool(call_params(json!({
+            "name": "extend_capability",
+            "arguments": { "target_tool": "echo", "features": ["shout"] }
+        })))
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{err:?}").contains("use request_capability instead"),
+        "{err:?}"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn approved_ask_resumes_the_build() {
+    // Not allow-listed, so the stub gate evaluator answers Ask.
+    let builder = Harness::builder()
+        .with_llm(stub_pipeline("line_count", "Count lines in a string"))
+        .with_agent_approvals();
+    let harness = start_building(builder).await;
+
+    let asked = harness
+        .call(json!({
+            "name": "request_capability",
+            "arguments": { "name": "line_count", "description": "Count lines in a string" }
+        }))
+        .await;
+    let asked = json_of(&asked);
+    assert_eq!(asked["status"], "ask");
+    let token = asked["approval_token"].as_str().expect("approval_token");
+
+    let result = harness
+        .call(json!({
+            "name": "approve_capability",
+            "arguments": { "approval_token": token, "approve": true }
+        }))
+        .await;
+
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    assert_eq!(json_of(&result)["status"], "built");
+    let tools = harness.client.list_all_tools().await.unwrap();
+    assert!(tools.iter().any(|t| t.name == "line_count"));
+
+    // Tokens are single-use.
+    let again = harness
+        .client
+        .call_tool(call_params(json!({
+            "name": "approve_capability",
+            "arguments": { "approval_token": token, "approve": true }
+        })))
+        .await;
+    assert!(again.is_err());
+}
+
+#[tokio::test]
+async fn the_agent_cannot_approve_its_own_request_by_default() {
+    let harness = Harness::builder().start().await;
+    // Not allow-listed, so the stub gate evaluator answers Ask.
+    let asked = json_of(
+        &harness
+            .call(json!({
+                "name": "request_capability",
+                "arguments": { "name": "line_count", "description": "Count lines in a string" }
+            }))
+            .await,
+    );
+    assert_eq!(asked["status"], "ask", "{asked}");
+    let token = asked["approval_token"].as_str().unwrap();
+    assert!(
+        asked["message"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("girt approve {token}")),
+        "{asked}"
+    );
+
+    let err = harness
+        .client
+        .call_tool(call_params(json!({
+            "name": "approve_capability",
+            "arguments": { "approval_token": token, "approve": true }
+        })))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("Only an operator"), "{err:?}");
+    assert_eq!(harness.state().await["approvals"]["pending"], 1);
+}
+
+#[tokio::test]
+async fn an_approval_cannot_widen_or_swap_the_requested_spec() {
+    let harness = Harness::builder().with_agent_approvals().start().await;
+    let requested = json!({
+        "name": "line_count",
+        "description": "Count lines in a string",
+        "constraints": { "network": ["api.example.com"] }
+    });
+    let asked = json_of(
+        &harness
+            .call(json!({ "name": "request_capability", "arguments": requested }))
+            .await,
+    );
+    assert_eq!(asked["status"], "ask", "{asked}");
+    let token = asked["approval_token"].clone();
+
+    let mut widened = requested.clone();
+    widened["constraints"]["secrets"] = json!(["GITHUB_TOKEN"]);
+    let mut swapped = requested.clone();
+    swapped["description"] = "Upload a string to a paste site".into();
+    for (spec, problem) in [
+        (widened, "adds to the request: secret GITHUB_TOKEN"),
+        (swapped, "changes the description"),
+    ] {
+        let err = harness
+            .client
+            .call_tool(call_params(json!({
+                "name": "approve_capability",
+                "arguments": { "approval_token": token, "approve": true, "spec": spec }
+            })))
+            .await
+            .unwrap_err();
+        assert!(format!("{err:?}").contains(problem), "{err:?}");
+    }
+    // Refused changes leave the request waiting.
+    assert_eq!(harness.state().await["approvals"]["pending"], 1);
+}
+
+#[tokio::test]
+async fn always_ask_tools_go_to_a_person_before_running() {
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_echo_tool("deploy_echo")
+        .with_always_ask(AlwaysAsk {
+            creation: vec![],
+            execution: vec!["deploy_*".into()],
+        })
+        .with_invocation_history()
+        .with_approval_spool()
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "deploy_echo", "arguments": { "text": "hi" } }))
+        .await;
+    let response = json_of(&result);
+    assert_eq!(response["status"], "ask", "{response}");
+    let prompt = response["prompt"].as_str().unwrap();
+    assert!(prompt.contains("always-ask rule 'deploy_*'"), "{prompt}");
+    let message = response["approval_message"].as_str().unwrap();
+    assert!(
+        message.starts_with(r#"Approve calling `deploy_echo` with {"text":"hi"}?"#),
+        "{message}"
+    );
+    let token = response["approval_token"].as_str().unwrap();
+    assert!(
+        response["message"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("girt approve {token}")),
+        "{response}"
+    );
+
+    // The agent cannot answer for the person, either way.
+    for approve in [true, false] {
+        let err = harness
+            .client
+            .call_tool(call_params(json!({
+                "name": "approve_capability",
+                "arguments": { "approval_token": token, "approve": approve }
+            })))
+            .await
+            .unwrap_err();
+        assert!(format!("{err:?}").contains("Only an operator"), "{err:?}");
+    }
+    assert_eq!(harness.state().await["approvals"]["pending"], 1);
+    let history_dir = harness.path().join("history");
+    assert!(
+        history::load(&history_dir, "deploy_echo")
+            .unwrap()
+            .is_empty()
+    );
+
+    // An operator's approval runs the call it was asked about.
+    approvals::resolve_spooled(
+        &harness.path().join("approvals"),
+        token,
+        Resolution::Approve,
+    )
+    .unwrap();
+    let mut records = vec![];
+    for _ in 0..50 {
+        records = history::load(&history_dir, "deploy_echo").unwrap();
+        if !records.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(records.len(), 1, "the approved call never ran");
+    assert_eq!(records[0].arguments, json!({ "text": "hi" }));
+
+    // The next call is asked about again.
+    let result = harness
+        .call(json!({ "name": "deploy_echo", "arguments": { "text": "hi" } }))
+        .await;
+    assert_eq!(json_of(&result)["status"], "ask");
+
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": { "text": "hi" } }))
+        .await;
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    assert_ne!(json_of(&result)["status"], "ask");
+}
+
+#[tokio::test]
+async fn rejecting_a_call_without_arguments_also_denies_empty_ones() {
+    // No policy rule covers `other_echo`, so the gate asks about it.
+    let harness = Harness::builder()
+        .with_echo_tool("other_echo")
+        .with_approval_spool()
+        .start()
+        .await;
+
+    let result = harness.call(json!({ "name": "other_echo" })).await;
+    let response = json_of(&result);
+    assert_eq!(response["status"], "ask", "{response}");
+    let token = response["approval_token"].as_str().unwrap();
+
+    approvals::resolve_spooled(&harness.path().join("approvals"), token, Resolution::Reject)
+        .unwrap();
+    for _ in 0..50 {
+        if harness.state().await["approvals"]["pending"] == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    // Omitted arguments and `{}` are the same call to the gate.
+    let result = harness
+        .call(json!({ "name": "other_echo", "arguments": {} }))
+        .await;
+    assert_eq!(result.is_error, Some(true), "{result:?}");
+    assert!(
+        json_of(&result)
+            .to_string()
+            .contains("Rejected by operator"),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn always_ask_wins_over_a_policy_allow() {
+    // The harness policy allows `echo` by name.
+    let harness = Harness::builder()
+        .with_echo_tool("echo")
+        .with_always_ask(AlwaysAsk {
+            creation: vec![],
+            execution: vec!["echo".into()],
+        })
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "echo", "arguments": { "text": "hi" } }))
+        .await;
+    let response = json_of(&result);
+    assert_eq!(response["status"], "ask", "{response}");
+    assert!(response["approval_token"].is_string(), "{response}");
+}
+
+#[tokio::test]
+async fn always_ask_capability_requests_wait_for_approval() {
+    let harness = Harness::builder()
+        .with_always_ask(AlwaysAsk {
+            creation: vec!["deploy_*".into()],
+            execution: vec![],
+        })
+        .start()
+        .await;
+
+    let asked = harness
+        .call(json!({
+            "name": "request_capability",
+            "arguments": { "name": "deploy_site", "description": "Deploy the site" }
+        }))
+        .await;
+    let asked = json_of(&asked);
+    assert_eq!(asked["status"], "ask", "{asked}");
+    assert!(asked["approval_token"].is_string(), "{asked}");
+    let message = asked["approval_message"].as_str().unwrap();
+    assert!(
+        message.starts_with(
+            "Approve building `deploy_site`? Capability request 'deploy_site' matches \
+             the always-ask rule 'deploy_*'"
+        ),
+        "{message}"
+    );
+    assert_eq!(harness.state().await["approvals"]["pending"], 1);
+    let err = harness
+        .client
+        .call_tool(call_params(json!({
+            "name": "approve_capability",
+            "arguments": { "approval_token": asked["approval_token"], "approve": true }
+        })))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("Only an operator"), "{err:?}");
+    assert_eq!(harness.state().await["approvals"]["pending"], 1);
+
+    // The stub gate evaluator asks about this one too, but not by rule.
+    let asked = harness
+        .call(json!({
+            "name": "request_capability",
+            "arguments": { "name": "line_count", "description": "Count lines in a string" }
+        }))
+        .await;
+    let prompt = json_of(&asked)["prompt"].as_str().unwrap().to_string();
+    assert!(!prompt.contains("always-ask"), "{prompt}");
+}
+
+#[tokio::test]
+async fn bundled_echo_runs_without_the_execution_gate() {
+    // The default policy denies `girt_*` names; the bundled tool is GIRT's
+    // own, so its calls never reach it.
+    let harness = Harness::builder().with_bundled_tools().start().await;
+
+    let tools = harness.client.list_all_tools().await.unwrap();
+    assert!(tools.iter().any(|t| t.name == "girt_echo"), "{tools:?}");
+
+    let arguments = json!({ "message": "hello" });
+    let result = harness
+        .call(json!({ "name": "girt_echo", "arguments": arguments }))
+        .await;
+    assert_eq!(result.is_error, Some(false), "{result:?}");
+    let response = json_of(&result);
+    assert_eq!(response["input"], arguments);
+    assert_eq!(response["input_bytes"], arguments.to_string().len());
+    assert!(response["now_ms"].as_u64().unwrap() > 0, "{response}");
+    assert_eq!(response["env"], json!({}));
+
+    let batch = harness
+        .call(json!({
+            "name": "girt_batch",
+            "arguments": { "calls": [{ "tool": "girt_echo", "args": arguments }] }
+        }))
+        .await;
+    let results = &json_of(&batch)["results"];
+    assert_eq!(results[0]["status"], "allow", "{results}");
+    assert_eq!(results[0]["result"]["input"], arguments);
+}
+
+#[tokio::test]
+async fn other_wasm_under_a_bundled_name_is_still_gated() {
+    let harness = Harness::builder()
+        .with_persisted_tool(echo_fixture(), bundled::ECHO.meta())
+        .start()
+        .await;
+
+    let result = harness
+        .call(json!({ "name": "girt_echo", "arguments": { "message": "hi" } }))
+        .await;
+    assert_eq!(result.is_error, Some(true), "{result:?}");
+    assert_eq!(json_of(&result)["status"], "denied");
+}
@@ -72,6 +72,61 @@ fn auth_status_no_credentials_exits_cleanly() {
     );
 }
 
+#[test]
+fn auth_status_json_without_credentials() {
+    let home = tempfile::TempDir::new().unwrap();
+    let output = girt()
+        .args(["auth", "status", "--json"])
+        .env("HOME", home.path())
+        .output()
+        .expect("failed to execute girt auth status --json");
+
+    assert!(output.status.success());
+    let status: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be JSON");
+    assert_eq!(
+        status,
+        serde_json::json!({
+            "provider": "anthropic",
+            "logged_in": false,
+            "expired": false,
+            "expires_at_unix": null,
+            "has_refresh_token": false,
+            "token_prefix": null,
+        })
+    );
+}
+
+// ── Doctor ────────────────────────────────────────────────────────────────────
+
+#[test]
+fn doctor_json_lists_checks_and_fails_without_config() {
+    let home = tempfile::TempDir::new().unwrap();
+    let output = girt()
+        .args(["doctor", "--json"])
+        .current_dir(home.path())
+        .env("HOME", home.path())
+        .output()
+        .expect("failed to execute girt doctor --json");
+
+    assert!(
+        !output.status.success(),
+        "doctor should exit non-zero when a check fails"
+    );
+    let checks: Vec<serde_json::Value> =
+        serde_json::from_slice(&output.stdout).expect("stdout should be a JSON array");
+    let names: Vec<&str> = checks.iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        ["config", "llm_credentials", "cargo_component", "runtime"]
+    );
+    for check in &checks {
+        assert!(check["passed"].is_boolean(), "{check}");
+        assert!(check["detail"].is_string(), "{check}");
+    }
+    assert_eq!(checks[0]["passed"], false);
+}
+
 // ── Serve with missing config ─────────────────────────────────────────────────
 
 #[test]
@@ -0,0 +1,206 @@
+;; girt_echo: first-party diagnostic tool bundled with GIRT.
+;;
+;; Returns its input together with what the sandbox looks like from inside:
+;;
+;;   ok({"input": <input>, "input_bytes": N, "now_ms": N,
+;;       "env": {"NAME": true, ...}})
+;;
+;; `env` lists the names of the environment variables the tool can see.
+;; Values are never read back out, so a secret passed to the tool is not
+;; echoed.
+;;
+;; Source for girt_echo.wasm, which girt-runtime embeds (see `bundled.rs`).
+;; Regenerate with: cargo xtask components
+(component
+  (import "girt:host/utils@0.1.0" (instance $utils
+    (export "now-ms" (func (result u64)))
+  ))
+  (import "wasi:cli/environment@0.2.0" (instance $environment
+    (export "get-environment" (func (result (list (tuple string string)))))
+  ))
+
+  ;; Memory and allocator live in their own instance so the imports can be
+  ;; lowered against them before the main module is instantiated. Unlike the
+  ;; test fixtures, the allocator grows memory, so inputs of any size fit.
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local $end i32)
+      (local $have i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (local.set $end (i32.add (local.get $ptr) (local.get 3)))
+      (local.set $have (i32.shl (memory.size) (i32.const 16)))
+      (if (i32.gt_u (local.get $end) (local.get $have))
+        (then
+          (if (i32.eq
+                (memory.grow
+                  (i32.shr_u
+                    (i32.add (i32.sub (local.get $end) (local.get $have)) (i32.const 65535))
+                    (i32.const 16)))
+                (i32.const -1))
+            (then unreachable))))
+      (global.set $heap (local.get $end))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (alias export $utils "now-ms" (func $now_ms))
+  (alias export $environment "get-environment" (func $get_environment))
+  (core func $now_ms_lowered (canon lower (func $now_ms)))
+  (core func $get_environment_lowered
+    (canon lower (func $get_environment)
+      (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (core instance $host
+    (export "now-ms" (func $now_ms_lowered))
+    (export "get-environment" (func $get_environment_lowered)))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "libc" "realloc" (func $realloc (param i32 i32 i32 i32) (result i32)))
+    (import "host" "now-ms" (func $now_ms (result i64)))
+    (import "host" "get-environment" (func $get_environment (param i32)))
+
+    (data (i32.const 512) "{\"input\":")
+    (data (i32.const 528) ",\"input_bytes\":")
+    (data (i32.const 544) ",\"now_ms\":")
+    (data (i32.const 560) ",\"env\":{")
+    (data (i32.const 576) "\":true")
+    (data (i32.const 584) "}}")
+    (data (i32.const 592) "null")
+    (data (i32.const 600) "0123456789abcdef")
+
+    ;; Copy `n` bytes from `src` to `p`, returning the end pointer.
+    (func $write (param $p i32) (param $src i32) (param $n i32) (result i32)
+      (memory.copy (local.get $p) (local.get $src) (local.get $n))
+      (i32.add (local.get $p) (local.get $n)))
+
+    ;; Write `v` in decimal at `p`, returning the end pointer.
+    (func $write_u64 (param $v i64) (param $p i32) (result i32)
+      (local $t i32)
+      (local.set $t (i32.const 960))
+      (loop $digits
+        (local.set $t (i32.sub (local.get $t) (i32.const 1)))
+        (i32.store8 (local.get $t)
+          (i32.add (i32.const 48)
+            (i32.wrap_i64 (i64.rem_u (local.get $v) (i64.const 10)))))
+        (local.set $v (i64.div_u (local.get $v) (i64.const 10)))
+        (br_if $digits (i64.ne (local.get $v) (i64.const 0))))
+      (call $write (local.get $p) (local.get $t) (i32.sub (i32.const 960) (local.get $t))))
+
+    ;; Write the `n` bytes at `src` as the body of a JSON string at `p`,
+    ;; escaping quotes, backslashes and control characters. Writes at most
+    ;; 6 bytes per input byte; returns the end pointer.
+    (func $write_escaped (param $p i32) (param $src i32) (param $n i32) (result i32)
+      (local $end i32)
+      (local $b i32)
+      (local.set $end (i32.add (local.get $src) (local.get $n)))
+      (block $done
+        (loop $bytes
+          (br_if $done (i32.ge_u (local.get $src) (local.get $end)))
+          (local.set $b (i32.load8_u (local.get $src)))
+          (if (i32.lt_u (local.get $b) (i32.const 32))
+            (then
+              ;; \u00XX
+              (i32.store8 (local.get $p) (i32.const 92))
+              (i32.store8 offset=1 (local.get $p) (i32.const 117))
+              (i32.store8 offset=2 (local.get $p) (i32.const 48))
+              (i32.store8 offset=3 (local.get $p) (i32.const 48))
+              (i32.store8 offset=4 (local.get $p)
+                (i32.load8_u offset=600 (i32.shr_u (local.get $b) (i32.const 4))))
+              (i32.store8 offset=5 (local.get $p)
+                (i32.load8_u offset=600 (i32.and (local.get $b) (i32.const 15))))
+              (local.set $p (i32.add (local.get $p) (i32.const 6))))
+            (else
+              (if (i32.or
+                    (i32.eq (local.get $b) (i32.const 34))
+                    (i32.eq (local.get $b) (i32.const 92)))
+                (then
+                  (i32.store8 (local.get $p) (i32.const 92))
+                  (local.set $p (i32.add (local.get $p) (i32.const 1)))))
+              (i32.store8 (local.get $p) (local.get $b))
+              (local.set $p (i32.add (local.get $p) (i32.const 1)))))
+          (local.set $src (i32.add (local.get $src) (i32.const 1)))
+          (br $bytes)))
+      (local.get $p))
+
+    (func (export "run") (param $input i32) (param $input_len i32) (result i32)
+      (local $now i64)
+      (local $env i32)
+      (local $env_len i32)
+      (local $entry i32)
+      (local $i i32)
+      (local $size i32)
+      (local $out i32)
+      (local $p i32)
+      (local.set $now (call $now_ms))
+
+      ;; list<tuple<string, string>>: { ptr @80, len @84 }, each entry
+      ;; { name ptr, name len, value ptr, value len }
+      (call $get_environment (i32.const 80))
+      (local.set $env (i32.load (i32.const 80)))
+      (local.set $env_len (i32.load (i32.const 84)))
+
+      ;; Size the output: the fixed text, two u64s, the input (or `null`)
+      ;; and each name escaped at worst, plus its `,"":true`.
+      (local.set $size (i32.add (local.get $input_len) (i32.const 128)))
+      (block $sized
+        (loop $names
+          (br_if $sized (i32.ge_u (local.get $i) (local.get $env_len)))
+          (local.set $entry (i32.add (local.get $env) (i32.shl (local.get $i) (i32.const 4))))
+          (local.set $size
+            (i32.add (local.get $size)
+              (i32.add (i32.mul (i32.load offset=4 (local.get $entry)) (i32.const 6))
+                (i32.const 8))))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $names)))
+      (local.set $out (call $realloc (i32.const 0) (i32.const 0) (i32.const 1) (local.get $size)))
+
+      (local.set $p (call $write (local.get $out) (i32.const 512) (i32.const 9)))
+      (if (i32.eqz (local.get $input_len))
+        (then (local.set $p (call $write (local.get $p) (i32.const 592) (i32.const 4))))
+        (else
+          (local.set $p (call $write (local.get $p) (local.get $input) (local.get $input_len)))))
+      (local.set $p (call $write (local.get $p) (i32.const 528) (i32.const 15)))
+      (local.set $p (call $write_u64 (i64.extend_i32_u (local.get $input_len)) (local.get $p)))
+      (local.set $p (call $write (local.get $p) (i32.const 544) (i32.const 10)))
+      (local.set $p (call $write_u64 (local.get $now) (local.get $p)))
+      (local.set $p (call $write (local.get $p) (i32.const 560) (i32.const 8)))
+
+      (local.set $i (i32.const 0))
+      (block $listed
+        (loop $names
+          (br_if $listed (i32.ge_u (local.get $i) (local.get $env_len)))
+          (local.set $entry (i32.add (local.get $env) (i32.shl (local.get $i) (i32.const 4))))
+          (if (local.get $i)
+            (then
+              (i32.store8 (local.get $p) (i32.const 44))
+              (local.set $p (i32.add (local.get $p) (i32.const 1)))))
+          (i32.store8 (local.get $p) (i32.const 34))
+          (local.set $p
+            (call $write_escaped
+              (i32.add (local.get $p) (i32.const 1))
+              (i32.load (local.get $entry))
+              (i32.load offset=4 (local.get $entry))))
+          (local.set $p (call $write (local.get $p) (i32.const 576) (i32.const 6)))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $names)))
+      (local.set $p (call $write (local.get $p) (i32.const 584) (i32.const 2)))
+
+      ;; result<string, string>: { tag @16, ptr @20, len @24 }
+      (i32.store8 (i32.const 16) (i32.const 0))
+      (i32.store (i32.const 20) (local.get $out))
+      (i32.store (i32.const 24) (i32.sub (local.get $p) (local.get $out)))
+      (i32.const 16))
+  )
+  (core instance $i (instantiate $m
+    (with "libc" (instance $libc))
+    (with "host" (instance $host))))
+
+  (func (export "run") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $i "run") (memory $libc "memory") (realloc (func $libc "realloc"))))
+)
@@ -0,0 +1,137 @@
+//! First-party components that ship inside GIRT.
+//!
+//! Each one's source lives in `components/<name>/` and is assembled into the
+//! checked-in `.wasm` next to it by `cargo xtask components`; the bytes are
+//! embedded here. Their tool names are in the reserved `girt_` space, which
+//! [`LifecycleManager::load_component`](crate::LifecycleManager::load_component)
+//! refuses, so they are loaded with
+//! [`LifecycleManager::load_bundled`](crate::LifecycleManager::load_bundled).
+//! A stored component only counts as bundled if its wasm is byte-for-byte
+//! the embedded one; anything else under a bundled name is treated like
+//! any other tool squatting on a reserved name.
+
+use sha2::{Digest, Sha256};
+
+use crate::storage::ComponentMeta;
+
+/// A component embedded in the GIRT binary.
+#[derive(Debug)]
+pub struct BundledComponent {
+    /// Short name, as in `girt tools add-builtin <name>`.
+    pub name: &'static str,
+    pub tool_name: &'static str,
+    pub description: &'static str,
+    pub wasm: &'static [u8],
+}
+
+/// `girt_echo`: returns its input along with the input size, the current
+/// time, and the names of the environment variables it can see. For
+/// checking the runtime and the proxy path end to end.
+pub const ECHO: BundledComponent = BundledComponent {
+    name: "echo",
+    tool_name: "girt_echo",
+    description: "Diagnostic tool: returns its input along with what the sandbox sees \
+                  (input size in bytes, current time in Unix ms, and which environment \
+                  variables are set; names only, never values).",
+    wasm: include_bytes!("../components/echo/girt_echo.wasm"),
+};
+
+/// Every bundled component.
+pub const ALL: &[BundledComponent] = &[ECHO];
+
+/// The bundled component with this short name or tool name.
+pub fn find(name: &str) -> Option<&'static BundledComponent> {
+    ALL.iter()
+        .find(|component| component.name == name || component.tool_name == name)
+}
+
+/// The bundled component that `wasm`, loaded as `tool_name`, is.
+pub fn identify(tool_name: &str, wasm: &[u8]) -> Option<&'static BundledComponent> {
+    ALL.iter()
+        .find(|component| component.tool_name == tool_name && component.wasm == wasm)
+}
+
+impl BundledComponent {
+    /// SHA-256 hex of the embedded wasm.
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.wasm))
+    }
+
+    /// Metadata to load the component with. The component id carries a
+    /// prefix of the hash, so a newer GIRT never picks up the precompiled
+    /// cache of an older build.
+    pub fn meta(&self) -> ComponentMeta {
+        ComponentMeta {
+            component_id: format!("{}@{}", self.tool_name, &self.hash()[..12]),
+            tool_name: self.tool_name.into(),
+            description: self.description.into(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "description": "Any arguments; they are returned as `input`."
+            }),
+            wasm_hash: String::new(),
+            wasm_size_bytes: 0,
+            built_at: 0,
+            loaded_at: 0,
+            allowed_env: vec![],
+            allowed_hosts: vec![],
+            max_response_bytes: None,
+            timeout_ms: None,
+            idempotent: true,
+            deprecated: false,
+            replaced_by: None,
+            deprecated_at: None,
+            oci_reference: None,
+            source_repo_path: None,
+            last_validated_at: None,
+            drift_detected: false,
+            quarantined: None,
+            quarantine_released: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use girt_core::reserved;
+
+    #[test]
+    fn bundled_names_are_reserved() {
+        for component in ALL {
+            assert!(
+                reserved::conflict(component.tool_name).is_some(),
+                "{}",
+                component.tool_name
+            );
+            assert!(component.wasm.starts_with(b"\0asm"), "{}", component.name);
+        }
+    }
+
+    #[test]
+    fn components_are_found_by_either_name() {
+        assert_eq!(find("echo").unwrap().tool_name, "girt_echo");
+        assert_eq!(find("girt_echo").unwrap().name, "echo");
+        assert!(find("girt_info").is_none());
+    }
+
+    #[test]
+    fn only_the_exact_wasm_is_identified() {
+        assert!(identify("girt_echo", ECHO.wasm).is_some());
+        assert!(identify("echo", ECHO.wasm).is_none());
+
+        let mut tampered = ECHO.wasm.to_vec();
+        tampered.push(0);
+        assert!(identify("girt_echo", &tampered).is_none());
+    }
+
+    #[test]
+    fn component_id_follows_the_wasm() {
+        let meta = ECHO.meta();
+        assert_eq!(meta.tool_name, "girt_echo");
+        assert_eq!(
+            meta.component_id,
+            format!("girt_echo@{}", &ECHO.hash()[..12])
+        );
+    }
+}
@@ -0,0 +1,391 @@
+//! Host-side egress proxy for component HTTP (`[runtime] egress_mode = "proxy"`).
+//!
+//! By default a component's outgoing `wasi:http` requests are sent as the
+//! component made them, and its network policy is only checked at the
+//! Creation Gate. With the egress proxy enabled, every request is handled
+//! by an [`EgressProxy`] on the host instead, which:
+//!
+//! - refuses hosts that are not on the tool's allowlist, and methods or
+//!   paths outside the scope a [`NetworkGrant`] gives the host,
+//! - adds `Host`, `User-Agent`, and `X-Girt-Tool` headers,
+//! - ends the response body with an error once it passes the policy's
+//!   `max_response_bytes`, and
+//! - records method, host, status, bytes, and duration of each request in
+//!   the [`InvocationTrace`] and as a tracing event.
+//!
+//! Enforcement and logging then hold whatever the generated code does.
+//! Calls to a tool whose grants are scoped to some methods or paths go
+//! through the proxy in direct mode too, as nothing else can hold it to
+//! the scope.
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, ready};
+use std::time::Instant;
+
+use bytes::Bytes;
+use girt_core::limits::Truncation;
+use girt_core::spec::NetworkGrant;
+use girt_core::usage::ResourceUsage;
+use http::HeaderValue;
+use http::header::{HOST, USER_AGENT};
+use http_body::{Body, Frame};
+use http_body_util::BodyExt;
+use serde::Serialize;
+use wasmtime_wasi_http::HttpResult;
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::body::{HyperIncomingBody, HyperOutgoingBody};
+use wasmtime_wasi_http::types::{
+    HostFutureIncomingResponse, IncomingResponse, OutgoingRequestConfig,
+    default_send_request_handler,
+};
+
+/// `User-Agent` sent when the component sets none.
+const DEFAULT_USER_AGENT: &str = concat!("girt/", env!("CARGO_PKG_VERSION"));
+
+/// Names the tool that made a request.
+const TOOL_HEADER: &str = "x-girt-tool";
+
+/// One outgoing HTTP request made by a component.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EgressRecord {
+    pub method: String,
+    pub host: String,
+    /// Absent when the request was blocked or got no response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Response body bytes passed to the component.
+    pub bytes: u64,
+    pub duration_ms: u64,
+    /// Why the request was blocked, failed, or had its body cut off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What a tool did during one call, from
+/// [`LifecycleManager::call_tool_traced`](crate::LifecycleManager::call_tool_traced).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct InvocationTrace {
+    /// Outgoing HTTP requests, in the order they finished. Always empty in
+    /// direct egress mode, unless the tool has scoped network grants.
+    pub egress: Vec<EgressRecord>,
+    /// What the call cost to run, also when it failed.
+    pub resources: ResourceUsage,
+    /// Set when the response was cut down to its `max_response_bytes`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<Truncation>,
+    /// `girt:host/utils` log lines dropped over the per-call limit.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub logs_dropped: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// One tool call's egress policy, and the log of its requests.
+#[derive(Clone)]
+pub struct EgressProxy {
+    tool_name: String,
+    allowed_hosts: Vec<NetworkGrant>,
+    max_response_bytes: Option<u64>,
+    records: Arc<Mutex<Vec<EgressRecord>>>,
+}
+
+impl EgressProxy {
+    pub fn new(
+        tool_name: impl Into<String>,
+        allowed_hosts: Vec<NetworkGrant>,
+        max_response_bytes: Option<u64>,
+    ) -> Self {
+        Self {
+            tool_name: tool_name.into(),
+            allowed_hosts,
+            max_response_bytes,
+            records: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Requests recorded so far, leaving the log empty.
+    pub fn take_records(&self) -> Vec<EgressRecord> {
+        std::mem::take(&mut *self.records.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Whether the allowlist admits `host` on `port`, for some method and
+    /// path.
+    pub fn allows(&self, host: &str, port: u16) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|grant| host_matches(grant.host(), host, port))
+    }
+
+    /// Whether a grant for `host` on `port` admits a `method` request for
+    /// `path`.
+    pub fn allows_request(&self, host: &str, port: u16, method: &str, path: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|grant| host_matches(grant.host(), host, port) && grant.allows(method, path))
+    }
+
+    /// The `send_request` hook of [`WasiHttpView`](wasmtime_wasi_http::WasiHttpView):
+    /// hand the request to [`Self::send`] in the background.
+    pub(crate) fn send_request(
+        &self,
+        request: http::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        let proxy = self.clone();
+        let handle =
+            wasmtime_wasi::runtime::spawn(async move { Ok(proxy.send(request, config).await) });
+        Ok(HostFutureIncomingResponse::pending(handle))
+    }
+
+    /// Check `request` against the allowlist and the scope of its grants,
+    /// add the standard headers, send it, and cap the response body.
+    pub async fn send(
+        &self,
+        mut request: http::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> Result<IncomingResponse, ErrorCode> {
+        let started = Instant::now();
+        let uri = request.uri();
+        let host = uri.host().unwrap_or_default().to_string();
+        let port = uri
+            .port_u16()
+            .unwrap_or(if config.use_tls { 443 } else { 80 });
+        let mut record = EgressRecord {
+            method: request.method().to_string(),
+            host: host.clone(),
+            status: None,
+            bytes: 0,
+            duration_ms: 0,
+            error: None,
+        };
+
+        if !self.allows(&host, port) {
+            record.error = Some(format!("{host}:{port} is not on the network allowlist"));
+            self.finish(record, started);
+            return Err(ErrorCode::HttpRequestDenied);
+        }
+        let path = uri.path().to_string();
+        if !self.allows_request(&host, port, &record.method, &path) {
+            record.error = Some(format!(
+                "{} {path} is outside the scope granted for {host}",
+                record.method
+            ));
+            self.finish(record, started);
+            return Err(ErrorCode::HttpRequestDenied);
+        }
+
+        let authority = uri.authority().map(|a| a.to_string());
+        let headers = request.headers_mut();
+        if let Some(authority) = authority
+            && !headers.contains_key(HOST)
+            && let Ok(value) = HeaderValue::from_str(&authority)
+        {
+            headers.insert(HOST, value);
+        }
+        if !headers.contains_key(USER_AGENT) {
+            headers.insert(USER_AGENT, HeaderValue::from_static(DEFAULT_USER_AGENT));
+        }
+        if let Ok(value) = HeaderValue::from_str(&self.tool_name) {
+            headers.insert(TOOL_HEADER, value);
+        }
+
+        match default_send_request_handler(request, config).await {
+            Ok(mut response) => {
+                record.status = Some(response.resp.status().as_u16());
+                let log = PendingRecord {
+                    proxy: self.clone(),
+                    record,
+                    started,
+                };
+                let limit = self.max_response_bytes;
+                response.resp = response
+                    .resp
+                    .map(|body| CappedBody::new(body, limit, log).boxed());
+                Ok(response)
+            }
+            Err(code) => {
+                record.error = Some(format!("{code:?}"));
+                self.finish(record, started);
+                Err(code)
+            }
+        }
+    }
+
+    /// Log a finished request.
+    fn finish(&self, mut record: EgressRecord, started: Instant) {
+        record.duration_ms = started.elapsed().as_millis() as u64;
+        tracing::info!(
+            tool = %self.tool_name,
+            method = %record.method,
+            host = %record.host,
+            status = record.status,
+            bytes = record.bytes,
+            duration_ms = record.duration_ms,
+            error = record.error.as_deref(),
+            "Egress request"
+        );
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record);
+    }
+}
+
+/// Whether allowlist `entry` (`host`, `host:port`, `*.domain`, or `*`)
+/// admits `host` on `port`. Host names compare case-insensitively.
+fn host_matches(entry: &str, host: &str, port: u16) -> bool {
+    let (pattern, entry_port) = match entry.rsplit_once(':') {
+        Some((pattern, p)) => match p.parse::<u16>() {
+            Ok(p) => (pattern, Some(p)),
+            Err(_) => return false,
+        },
+        None => (entry, None),
+    };
+    if entry_port.is_some_and(|p| p != port) {
+        return false;
+    }
+    let host = host.to_ascii_lowercase();
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => pattern == "*" || pattern == host,
+    }
+}
+
+/// A request whose response body is still being read. Logged when the
+/// body is dropped, so the byte count and duration are final.
+struct PendingRecord {
+    proxy: EgressProxy,
+    record: EgressRecord,
+    started: Instant,
+}
+
+impl Drop for PendingRecord {
+    fn drop(&mut self) {
+        self.proxy
+            .finish(std::mem::take(&mut self.record), self.started);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CapState {
+    Open,
+    /// The cap was hit; the next poll reports the error.
+    Exceeded,
+    Done,
+}
+
+/// A response body that fails once more than `limit` bytes have arrived.
+/// The bytes up to the limit are still delivered.
+struct CappedBody {
+    inner: HyperIncomingBody,
+    limit: Option<u64>,
+    /// Bytes received from upstream, including any past the limit.
+    received: u64,
+    state: CapState,
+    log: PendingRecord,
+}
+
+impl CappedBody {
+    fn new(inner: HyperIncomingBody, limit: Option<u64>, log: PendingRecord) -> Self {
+        Self {
+            inner,
+            limit,
+            received: 0,
+            state: CapState::Open,
+            log,
+        }
+    }
+}
+
+impl Body for CappedBody {
+    type Data = Bytes;
+    type Error = ErrorCode;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, ErrorCode>>> {
+        let this = self.get_mut();
+        match this.state {
+            CapState::Open => {}
+            CapState::Exceeded => {
+                this.state = CapState::Done;
+                return Poll::Ready(Some(Err(ErrorCode::HttpResponseBodySize(Some(
+                    this.received,
+                )))));
+            }
+            CapState::Done => return Poll::Ready(None),
+        }
+
+        let frame = match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(code)) => {
+                this.log.record.error = Some(format!("{code:?}"));
+                this.state = CapState::Done;
+                return Poll::Ready(Some(Err(code)));
+            }
+            None => {
+                this.state = CapState::Done;
+                return Poll::Ready(None);
+            }
+        };
+        let Some(data) = frame.data_ref() else {
+            return Poll::Ready(Some(Ok(frame)));
+        };
+
+        let len = data.len() as u64;
+        this.received += len;
+        let delivered = this.log.record.bytes;
+        let Some(limit) = this.limit.filter(|limit| delivered + len > *limit) else {
+            this.log.record.bytes += len;
+            return Poll::Ready(Some(Ok(frame)));
+        };
+
+        // Over the cap: pass on what still fits, then fail.
+        this.log.record.error = Some(format!(
+            "response body exceeded max_response_bytes ({limit})"
+        ));
+        let room = limit - delivered;
+        this.log.record.bytes = limit;
+        if room == 0 {
+            this.state = CapState::Done;
+            return Poll::Ready(Some(Err(ErrorCode::HttpResponseBodySize(Some(
+                this.received,
+            )))));
+        }
+        this.state = CapState::Exceeded;
+        Poll::Ready(Some(Ok(Frame::data(data.slice(..room as usize)))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match self.state {
+            CapState::Open => self.inner.is_end_stream(),
+            CapState::Exceeded => false,
+            CapState::Done => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist_entries_match_host_port_and_subdomains() {
+        assert!(host_matches("api.github.com", "API.github.com", 443));
+        assert!(!host_matches("api.github.com", "github.com", 443));
+        assert!(host_matches("localhost:8080", "localhost", 8080));
+        assert!(!host_matches("localhost:8080", "localhost", 80));
+        assert!(host_matches("*.example.com", "a.b.example.com", 443));
+        assert!(!host_matches("*.example.com", "example.com", 443));
+        assert!(!host_matches("*.example.com", "badexample.com", 443));
+        assert!(host_matches("*", "anything.test", 80));
+        assert!(!host_matches("host:notaport", "host", 80));
+    }
+}
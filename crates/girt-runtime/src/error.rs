@@ -1,3 +1,4 @@
+use girt_core::error::SizeLimitError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,12 +21,39 @@ pub enum RuntimeError {
     #[error("Tool returned error: {0}")]
     ToolError(String),
 
+    /// A structured error from a `girt:tool@0.2.0` component.
+    #[error("Tool returned {kind} error: {message}")]
+    ToolFailure {
+        kind: String,
+        message: String,
+        retryable: bool,
+    },
+
+    /// A response over the tool's `max_response_bytes`, with
+    /// `oversize_response = "error"`.
+    #[error("Tool response too large: {tool}: {source}")]
+    ResponseTooLarge {
+        tool: String,
+        #[source]
+        source: SizeLimitError,
+    },
+
+    /// A call still running when its deadline passed.
+    #[error("Tool timed out: {tool} did not finish within {timeout_ms} ms")]
+    Timeout { tool: String, timeout_ms: u64 },
+
+    #[error("Unsupported girt:tool world: {0}")]
+    UnsupportedWorld(String),
+
     #[error("Storage error: {0}")]
     StorageError(String),
 
     #[error("Invalid component metadata: {0}")]
     InvalidMetadata(String),
 
+    #[error("Tool is not quarantined: {0}")]
+    NotQuarantined(String),
+
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 
@@ -0,0 +1,271 @@
+//! `girt:host/utils` (`wit/host/utils.wit`): clock, UUID, and logging
+//! helpers a component may import instead of reimplementing them.
+//!
+//! - `now-ms` reads the call's wall clock, so a deterministic call sees its
+//!   fixed time.
+//! - `deadline-ms` is what is left of the call's time budget, so polling
+//!   loops can stop before the call is cut off. A deterministic call sees
+//!   its whole budget on every read.
+//! - `uuid-v4` draws from host entropy, or from the call's seed in a
+//!   deterministic call.
+//! - `log` emits a tracing event tagged with the component id. A call may
+//!   log [`MAX_LOG_LINES_PER_CALL`] lines of up to [`MAX_LOG_LINE_CHARS`]
+//!   characters; later lines are dropped and counted in
+//!   [`InvocationTrace::logs_dropped`](crate::InvocationTrace::logs_dropped).
+//!
+//! The interface is linked for every component. Components that do not
+//! import it are unaffected.
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use girt_core::host::HostImport;
+use uuid::Uuid;
+use wasmtime::StoreContextMut;
+use wasmtime::component::Linker;
+
+use crate::wasistate::{CallOptions, WasiState, seeded_bytes};
+
+/// Name components import the interface by.
+pub const INTERFACE: &str = "girt:host/utils@0.1.0";
+
+/// Log lines one call may emit before the rest are dropped.
+pub const MAX_LOG_LINES_PER_CALL: u32 = 100;
+
+/// Longest log line, in characters; longer lines are cut.
+pub const MAX_LOG_LINE_CHARS: usize = 2048;
+
+/// Seed perturbation so UUIDs don't mirror the WASI random streams.
+const UUID_STREAM: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// Define [`INTERFACE`] in `linker`.
+pub(crate) fn add_to_linker(linker: &mut Linker<WasiState>) -> Result<()> {
+    let mut utils = linker.instance(INTERFACE)?;
+    utils.func_wrap("now-ms", |store: StoreContextMut<'_, WasiState>, (): ()| {
+        Ok((store.data().host_utils().now_ms(),))
+    })?;
+    utils.func_wrap(
+        "deadline-ms",
+        |store: StoreContextMut<'_, WasiState>, (): ()| {
+            Ok((store.data().host_utils().deadline_ms(),))
+        },
+    )?;
+    utils.func_wrap(
+        "uuid-v4",
+        |mut store: StoreContextMut<'_, WasiState>, (): ()| {
+            Ok((store.data_mut().host_utils_mut().uuid_v4(),))
+        },
+    )?;
+    utils.func_wrap(
+        "log",
+        |mut store: StoreContextMut<'_, WasiState>, (level, message): (String, String)| {
+            store.data_mut().host_utils_mut().log(&level, &message);
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
+
+/// [`INTERFACE`] as offered to generated code. Signatures are those of
+/// `wit/host/utils.wit`.
+pub fn capability() -> HostImport {
+    HostImport::new(INTERFACE, "Clock, UUID, and logging helpers.")
+        .function(
+            "now-ms",
+            "func() -> u64",
+            "wall-clock time in Unix milliseconds.",
+        )
+        .function(
+            "deadline-ms",
+            "func() -> u64",
+            "milliseconds left before the call is cut off; stop polling or \
+             retrying when it runs low. The maximum u64 when the call has no deadline.",
+        )
+        .function(
+            "uuid-v4",
+            "func() -> string",
+            "a random UUID, e.g. for idempotency keys.",
+        )
+        .function(
+            "log",
+            "func(level: string, message: string)",
+            &format!(
+                "a log line on the host; level is \"trace\", \"debug\", \"info\", \"warn\", \
+                 or \"error\". Only the first {MAX_LOG_LINES_PER_CALL} lines of a call are \
+                 kept, so log sparingly."
+            ),
+        )
+}
+
+/// Per-call state behind [`INTERFACE`].
+#[derive(Debug, Default)]
+pub(crate) struct HostUtils {
+    component_id: String,
+    /// Set in deterministic calls.
+    fixed_time_ms: Option<u64>,
+    /// Seeded bytes UUIDs are cut from in deterministic calls, and how
+    /// many have been used.
+    seeded: Option<(Vec<u8>, usize)>,
+    deadline: Option<Instant>,
+    /// The budget at the start of a deterministic call, reported on every
+    /// read.
+    fixed_budget_ms: Option<u64>,
+    logged: u32,
+    dropped: u32,
+}
+
+impl HostUtils {
+    pub(crate) fn new(options: &CallOptions) -> Self {
+        let deterministic = options.deterministic;
+        Self {
+            fixed_time_ms: deterministic.then(|| options.fixed_time_ms.unwrap_or(0)),
+            seeded: deterministic
+                .then(|| (seeded_bytes(options.rng_seed.unwrap_or(0) ^ UUID_STREAM), 0)),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn with_component_id(mut self, component_id: impl Into<String>) -> Self {
+        self.component_id = component_id.into();
+        self
+    }
+
+    pub(crate) fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        if self.fixed_time_ms.is_some() {
+            self.fixed_budget_ms = Some(remaining_ms(deadline));
+        }
+        self
+    }
+
+    pub(crate) fn deadline_ms(&self) -> u64 {
+        match (self.fixed_budget_ms, self.deadline) {
+            (Some(budget), _) => budget,
+            (None, Some(deadline)) => remaining_ms(deadline),
+            (None, None) => u64::MAX,
+        }
+    }
+
+    pub(crate) fn now_ms(&self) -> u64 {
+        self.fixed_time_ms.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0)
+        })
+    }
+
+    pub(crate) fn uuid_v4(&mut self) -> String {
+        let Some((bytes, used)) = &mut self.seeded else {
+            return Uuid::new_v4().to_string();
+        };
+        let mut random = [0u8; 16];
+        for byte in &mut random {
+            *byte = bytes[*used % bytes.len()];
+            *used += 1;
+        }
+        uuid::Builder::from_random_bytes(random)
+            .into_uuid()
+            .to_string()
+    }
+
+    pub(crate) fn log(&mut self, level: &str, message: &str) {
+        if self.logged >= MAX_LOG_LINES_PER_CALL {
+            if self.dropped == 0 {
+                tracing::warn!(
+                    component = %self.component_id,
+                    limit = MAX_LOG_LINES_PER_CALL,
+                    "Tool log limit reached; dropping its further lines for this call"
+                );
+            }
+            self.dropped += 1;
+            return;
+        }
+        self.logged += 1;
+
+        let message = match message.char_indices().nth(MAX_LOG_LINE_CHARS) {
+            Some((end, _)) => &message[..end],
+            None => message,
+        };
+        let component = &self.component_id;
+        match level.to_ascii_lowercase().as_str() {
+            "trace" => tracing::trace!(component = %component, "{message}"),
+            "debug" => tracing::debug!(component = %component, "{message}"),
+            "warn" => tracing::warn!(component = %component, "{message}"),
+            "error" => tracing::error!(component = %component, "{message}"),
+            _ => tracing::info!(component = %component, "{message}"),
+        }
+    }
+
+    /// Log lines dropped over the per-call limit.
+    pub(crate) fn logs_dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+fn remaining_ms(deadline: Instant) -> u64 {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    u64::try_from(remaining.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn logs_past_the_limit_are_dropped_and_counted() {
+        let mut utils = HostUtils::new(&CallOptions::default()).with_component_id("chatty@0.1.0");
+        for _ in 0..MAX_LOG_LINES_PER_CALL + 5 {
+            utils.log("info", "line");
+        }
+        assert_eq!(utils.logged, MAX_LOG_LINES_PER_CALL);
+        assert_eq!(utils.logs_dropped(), 5);
+    }
+
+    #[test]
+    fn the_capability_matches_the_wit() {
+        let wit = include_str!("../wit/host/utils.wit");
+        let capability = capability();
+        assert_eq!(capability.functions.len(), wit.matches(": func(").count());
+        for function in &capability.functions {
+            assert!(
+                wit.contains(&format!("{}: {};", function.name, function.signature)),
+                "{} is not in utils.wit",
+                function.name
+            );
+        }
+    }
+
+    #[test]
+    fn deterministic_calls_fix_time_and_seed_uuids() {
+        let options = CallOptions::deterministic()
+            .with_fixed_time_ms(1_700_000_000_000)
+            .with_rng_seed(7);
+        let (mut a, mut b) = (HostUtils::new(&options), HostUtils::new(&options));
+        assert_eq!(a.now_ms(), 1_700_000_000_000);
+        let first = a.uuid_v4();
+        assert_eq!(first, b.uuid_v4());
+        assert_ne!(first, a.uuid_v4());
+        assert_eq!(Uuid::parse_str(&first).unwrap().get_version_num(), 4);
+    }
+
+    #[test]
+    fn deadline_counts_down_unless_the_call_is_deterministic() {
+        assert_eq!(
+            HostUtils::new(&CallOptions::default()).deadline_ms(),
+            u64::MAX
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let utils = HostUtils::new(&CallOptions::default()).with_deadline(deadline);
+        let left = utils.deadline_ms();
+        assert!(left <= 10_000 && left > 9_000, "{left}");
+
+        let utils = HostUtils::new(&CallOptions::deterministic()).with_deadline(deadline);
+        let budget = utils.deadline_ms();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(utils.deadline_ms(), budget);
+    }
+}
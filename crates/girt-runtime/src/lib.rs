@@ -31,8 +31,24 @@
 //!         "properties": { "url": { "type": "string" } },
 //!         "required": ["url"]
 //!     }),
-//!     wasm_hash: "abc123".into(),
+//!     wasm_hash: String::new(), // computed on store
+//!     wasm_size_bytes: 0,
 //!     built_at: 0,
+//!     loaded_at: 0,
+//!     allowed_env: vec![],
+//!     allowed_hosts: vec![],
+//!     max_response_bytes: None,
+//!     timeout_ms: None,
+//!     idempotent: false,
+//!     deprecated: false,
+//!     replaced_by: None,
+//!     deprecated_at: None,
+//!     oci_reference: None,
+//!     source_repo_path: None,
+//!     last_validated_at: None,
+//!     drift_detected: false,
+//!     quarantined: None,
+//!     quarantine_released: false,
 //! };
 //! manager.load_component(Path::new("/path/to/tool.wasm"), meta).await?;
 //!
@@ -42,12 +58,21 @@
 //! # }
 //! ```
 
+pub mod bundled;
+pub mod egress;
 pub mod error;
+pub mod host_utils;
 pub mod lifecycle;
 pub mod runtime_context;
 pub mod storage;
 pub mod wasistate;
+pub mod world;
 
+pub use bundled::BundledComponent;
+pub use egress::{EgressRecord, InvocationTrace};
 pub use error::RuntimeError;
 pub use lifecycle::LifecycleManager;
+pub use runtime_context::{EngineConfig, host_capabilities};
 pub use storage::ComponentMeta;
+pub use wasistate::CallOptions;
+pub use world::WorldVersion;
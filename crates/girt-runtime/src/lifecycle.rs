@@ -4,20 +4,39 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
+use girt_core::faults::{self, FaultInjector, FaultKind};
+use girt_core::limits::OversizeResponse;
+use girt_core::reserved;
+use girt_core::usage::{HIGH_USAGE_FRACTION, HighUsageTracker};
 use tokio::sync::RwLock;
+use tracing::Instrument;
 use wasmtime::Store;
 use wasmtime::component::{InstancePre, Val};
 
+use crate::bundled;
+use crate::egress::{EgressProxy, InvocationTrace};
 use crate::error::RuntimeError;
-use crate::runtime_context::RuntimeContext;
-use crate::storage::{ComponentMeta, ComponentStorage};
-use crate::wasistate::WasiState;
+use crate::runtime_context::{EngineConfig, RuntimeContext};
+use crate::storage::{ComponentMeta, ComponentStorage, now_ms};
+use crate::wasistate::{CallOptions, WasiState};
+use crate::world::WorldVersion;
+
+/// Longest a call may run when neither its policy nor
+/// [`LifecycleManager::with_call_timeout`] sets a shorter limit. Matches the
+/// largest `timeout_seconds` a policy may grant.
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(120);
 
 /// A component that has been compiled and is ready for instantiation.
 struct LoadedComponent {
     instance_pre: InstancePre<WasiState>,
+    /// Which `girt:tool` world the component implements.
+    world: WorldVersion,
     meta: ComponentMeta,
+    /// One of GIRT's own [bundled] components.
+    bundled: bool,
 }
 
 /// The GIRT embedded WASM runtime.
@@ -38,12 +57,38 @@ pub struct LifecycleManager {
     components: RwLock<HashMap<String, LoadedComponent>>,
     /// tool_name → component_id (one tool per component for now)
     tool_index: RwLock<HashMap<String, String>>,
+    /// tool_name → configured env vars (`[tools.env.<tool_name>]`)
+    tool_env: std::sync::RwLock<HashMap<String, HashMap<String, String>>>,
+    /// Route component HTTP through the host egress proxy.
+    egress_proxy: bool,
+    /// Breaks a fraction of calls (`[runtime.fault_injection]`).
+    faults: Option<FaultInjector>,
+    /// Tools whose calls keep running close to the memory limit.
+    high_usage: HighUsageTracker,
+    /// Cap on every tool's response, on top of its own `max_response_bytes`.
+    max_response_bytes: Option<u64>,
+    /// What a response over its limit turns into.
+    oversize_response: OversizeResponse,
+    /// Cap on every call's run time, on top of its own `timeout_ms`.
+    call_timeout: Duration,
 }
 
 impl LifecycleManager {
     pub fn new(storage_dir: Option<std::path::PathBuf>) -> anyhow::Result<Self> {
-        let runtime = Arc::new(RuntimeContext::new()?);
-        let base_dir = storage_dir.unwrap_or_else(ComponentStorage::default_path);
+        Self::with_engine_config(storage_dir, &EngineConfig::default())
+    }
+
+    /// Like [`Self::new`], with the Wasmtime engine built from `config`
+    /// (`[runtime]` in girt.toml).
+    pub fn with_engine_config(
+        storage_dir: Option<std::path::PathBuf>,
+        config: &EngineConfig,
+    ) -> anyhow::Result<Self> {
+        let runtime = Arc::new(RuntimeContext::with_config(config)?);
+        let base_dir = match storage_dir {
+            Some(dir) => dir,
+            None => ComponentStorage::default_path()?,
+        };
         let storage = ComponentStorage::new(base_dir);
         storage.init()?;
         Ok(Self {
@@ -51,22 +96,96 @@ impl LifecycleManager {
             storage,
             components: RwLock::new(HashMap::new()),
             tool_index: RwLock::new(HashMap::new()),
+            tool_env: std::sync::RwLock::new(HashMap::new()),
+            egress_proxy: false,
+            faults: None,
+            high_usage: HighUsageTracker::new(),
+            max_response_bytes: None,
+            oversize_response: OversizeResponse::default(),
+            call_timeout: DEFAULT_CALL_TIMEOUT,
         })
     }
 
+    /// The settings the Wasmtime engine was built with.
+    pub fn engine_config(&self) -> &EngineConfig {
+        &self.runtime.config
+    }
+
+    /// Send every outgoing HTTP request through an [`EgressProxy`] that
+    /// enforces the component's `allowed_hosts` and `max_response_bytes`
+    /// and logs the request (`[runtime] egress_mode = "proxy"`). Tools with
+    /// scoped network grants use the proxy either way.
+    pub fn with_egress_proxy(mut self, enabled: bool) -> Self {
+        self.egress_proxy = enabled;
+        self
+    }
+
+    /// Cap every tool's response at `limit` bytes, including tools whose
+    /// policy sets no `max_response_bytes` or a larger one.
+    pub fn with_max_response_bytes(mut self, limit: u64) -> Self {
+        self.max_response_bytes = Some(limit);
+        self
+    }
+
+    /// Stop every call after `timeout`, including calls whose policy allows
+    /// longer ([`DEFAULT_CALL_TIMEOUT`] by default).
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    /// Fail or truncate responses over their limit (truncate by default).
+    pub fn with_oversize_response(mut self, oversize: OversizeResponse) -> Self {
+        self.oversize_response = oversize;
+        self
+    }
+
+    /// Replace the results of a fraction of calls with faults chosen by
+    /// `injector`, for testing how callers cope with misbehaving tools.
+    pub fn with_fault_injector(mut self, injector: FaultInjector) -> Self {
+        self.faults = Some(injector);
+        self
+    }
+
+    /// The fault injector in use, if any.
+    pub fn fault_injector(&self) -> Option<&FaultInjector> {
+        self.faults.as_ref()
+    }
+
+    /// Set the per-tool environment variables injected on each invocation.
+    ///
+    /// Only keys listed in a component's `allowed_env` reach the component;
+    /// the rest are dropped at call time.
+    pub fn with_tool_env(self, tool_env: HashMap<String, HashMap<String, String>>) -> Self {
+        self.set_tool_env(tool_env);
+        self
+    }
+
+    /// Replace the per-tool environment variables (e.g. on config reload).
+    /// Applies from the next invocation on.
+    pub fn set_tool_env(&self, tool_env: HashMap<String, HashMap<String, String>>) {
+        *self.tool_env.write().unwrap_or_else(|e| e.into_inner()) = tool_env;
+    }
+
     /// Load a previously built tool into the runtime from a .wasm path.
     ///
     /// The caller must also provide metadata written by the pipeline. If the
     /// component_id is already loaded, this is a no-op (returns quickly).
     ///
     /// After this returns, the tool appears in `list_tools()` and is callable
-    /// via `call_tool()`.
+    /// via `call_tool()`. A tool named after a GIRT built-in is refused (see
+    /// [`girt_core::reserved`]).
     pub async fn load_component(
         &self,
         wasm_path: &Path,
-        meta: ComponentMeta,
+        mut meta: ComponentMeta,
     ) -> Result<String, RuntimeError> {
         let component_id = meta.component_id.clone();
+        if let Some(conflict) = reserved::conflict(&meta.tool_name) {
+            return Err(RuntimeError::InvalidMetadata(format!(
+                "{component_id}: tool name {conflict}"
+            )));
+        }
 
         // Fast path: already loaded
         {
@@ -79,14 +198,71 @@ impl LifecycleManager {
 
         tracing::info!(component_id, path = %wasm_path.display(), "Loading component");
 
-        // Store wasm + metadata on disk
-        self.storage.store(wasm_path, &meta)?;
+        // Store wasm + metadata on disk (fills in hash, size, load time)
+        let wasm_hash = self.storage.store(wasm_path, &mut meta)?;
+        self.register_stored(meta, wasm_hash, false).await
+    }
+
+    /// Load one of GIRT's own [bundled] components, which have reserved
+    /// names. `meta` is usually [`BundledComponent::meta`](bundled::BundledComponent::meta);
+    /// its tool name picks the component and its wasm is always the
+    /// embedded one. Calls to it skip the proxy's Execution Gate (see
+    /// [`Self::is_bundled`]).
+    pub async fn load_bundled(&self, mut meta: ComponentMeta) -> Result<String, RuntimeError> {
+        let component_id = meta.component_id.clone();
+        let Some(component) = bundled::ALL
+            .iter()
+            .find(|component| component.tool_name == meta.tool_name)
+        else {
+            return Err(RuntimeError::InvalidMetadata(format!(
+                "{component_id}: `{}` is not a bundled component",
+                meta.tool_name
+            )));
+        };
+
+        {
+            let guard = self.components.read().await;
+            if guard.get(&component_id).is_some_and(|c| c.bundled) {
+                tracing::debug!(component_id, "Component already loaded");
+                return Ok(component_id);
+            }
+        }
+
+        tracing::info!(
+            component_id,
+            bundled = component.name,
+            "Loading bundled component"
+        );
+        let wasm_hash = self.storage.store_bytes(component.wasm, &mut meta)?;
+        self.register_stored(meta, wasm_hash, true).await
+    }
+
+    /// Compile a component just stored for `meta` and make it callable.
+    async fn register_stored(
+        &self,
+        meta: ComponentMeta,
+        wasm_hash: String,
+        bundled: bool,
+    ) -> Result<String, RuntimeError> {
+        let component_id = meta.component_id.clone();
+        tracing::debug!(
+            component_id,
+            wasm_hash,
+            wasm_size_bytes = meta.wasm_size_bytes,
+            build_to_load_ms = meta.loaded_at.saturating_sub(meta.built_at),
+            "Component stored"
+        );
 
         // Compile (or load from cache)
         let component = self
             .storage
             .load_or_compile(&component_id, &self.runtime.engine)?;
 
+        // Which girt:tool world it implements decides how errors decode
+        let world = WorldVersion::detect(&component, &self.runtime.engine).inspect_err(|e| {
+            tracing::warn!(component_id, "Cannot load component: {e}");
+        })?;
+
         // Pre-instantiate (expensive; done once per component)
         let instance_pre = self
             .runtime
@@ -95,18 +271,35 @@ impl LifecycleManager {
             .map_err(|e| RuntimeError::InstantiationFailed(format!("{component_id}: {e}")))?;
 
         let tool_name = meta.tool_name.clone();
+        let scoped = scoped_grants(&meta);
+        if !self.egress_proxy && !scoped.is_empty() {
+            tracing::info!(
+                component_id,
+                tool_name,
+                grants = ?scoped,
+                "Network grants are scoped; this tool's HTTP goes through the egress proxy"
+            );
+        }
 
         // Register
         {
             let mut components = self.components.write().await;
-            components.insert(component_id.clone(), LoadedComponent { instance_pre, meta });
+            components.insert(
+                component_id.clone(),
+                LoadedComponent {
+                    instance_pre,
+                    world,
+                    meta,
+                    bundled,
+                },
+            );
         }
         {
             let mut index = self.tool_index.write().await;
             index.insert(tool_name.clone(), component_id.clone());
         }
 
-        tracing::info!(component_id, tool_name, "Component loaded and ready");
+        tracing::info!(component_id, tool_name, %world, "Component loaded and ready");
         Ok(component_id)
     }
 
@@ -146,6 +339,14 @@ impl LifecycleManager {
                 }
             };
 
+            let world = match WorldVersion::detect(&component, &self.runtime.engine) {
+                Ok(world) => world,
+                Err(e) => {
+                    tracing::warn!(component_id = id, "Skipping component: {e}");
+                    continue;
+                }
+            };
+
             let instance_pre = match self.runtime.linker.instantiate_pre(&component) {
                 Ok(ip) => ip,
                 Err(e) => {
@@ -155,9 +356,27 @@ impl LifecycleManager {
             };
 
             let tool_name = meta.tool_name.clone();
+            let bundled = reserved::conflict(&tool_name).is_some()
+                && std::fs::read(&wasm_path)
+                    .is_ok_and(|wasm| bundled::identify(&tool_name, &wasm).is_some());
+            if let Some(conflict) = reserved::conflict(&tool_name).filter(|_| !bundled) {
+                // Stored before the name was reserved. Kept so it can be
+                // removed; the proxy never dispatches to it.
+                tracing::warn!(
+                    component_id = id,
+                    tool_name,
+                    "Persisted tool name {conflict}; it is shadowed and should be removed"
+                );
+            }
             {
                 let mut components = self.components.write().await;
-                components.insert(id.clone(), LoadedComponent { instance_pre, meta });
+                let loaded = LoadedComponent {
+                    instance_pre,
+                    world,
+                    meta,
+                    bundled,
+                };
+                components.insert(id.clone(), loaded);
             }
             {
                 let mut index = self.tool_index.write().await;
@@ -181,6 +400,97 @@ impl LifecycleManager {
         Ok(())
     }
 
+    /// Mark a tool deprecated, optionally naming its replacement.
+    ///
+    /// Deprecating an already deprecated tool only changes `replaced_by`;
+    /// the grace period keeps running from the original `deprecated_at`.
+    pub async fn deprecate(
+        &self,
+        tool_name: &str,
+        replaced_by: Option<String>,
+    ) -> Result<ComponentMeta, RuntimeError> {
+        let now = now_ms();
+        let meta = self
+            .update_meta(tool_name, |meta| {
+                meta.deprecated = true;
+                meta.replaced_by = replaced_by.clone();
+                meta.deprecated_at = Some(meta.deprecated_at.unwrap_or(now));
+            })
+            .await?;
+        tracing::info!(tool_name, ?replaced_by, "Tool deprecated");
+        Ok(meta)
+    }
+
+    /// Quarantine `tool_name` because a policy rule denies it (`reason`),
+    /// or with `None` lift a quarantine the rules no longer call for.
+    pub async fn set_quarantine(
+        &self,
+        tool_name: &str,
+        reason: Option<String>,
+    ) -> Result<ComponentMeta, RuntimeError> {
+        let meta = self
+            .update_meta(tool_name, |meta| meta.quarantined = reason.clone())
+            .await?;
+        match &reason {
+            Some(reason) => tracing::warn!(tool_name, %reason, "Tool quarantined"),
+            None => tracing::info!(tool_name, "Tool no longer quarantined"),
+        }
+        Ok(meta)
+    }
+
+    /// Release a quarantined tool for good: it is listed and callable again
+    /// from the next restore, which no longer re-checks it.
+    pub async fn release(&self, tool_name: &str) -> Result<ComponentMeta, RuntimeError> {
+        let quarantined = AtomicBool::new(false);
+        let meta = self
+            .update_meta(tool_name, |meta| {
+                if meta.quarantined.take().is_some() {
+                    quarantined.store(true, Ordering::Relaxed);
+                    meta.quarantine_released = true;
+                }
+            })
+            .await?;
+        if !quarantined.load(Ordering::Relaxed) {
+            return Err(RuntimeError::NotQuarantined(tool_name.to_string()));
+        }
+        tracing::info!(tool_name, "Tool released from quarantine");
+        Ok(meta)
+    }
+
+    /// Apply `update` to the metadata of every persisted component serving
+    /// `tool_name` and, if loaded, to the in-memory metadata. Returns the
+    /// updated metadata.
+    pub async fn update_meta(
+        &self,
+        tool_name: &str,
+        update: impl Fn(&mut ComponentMeta),
+    ) -> Result<ComponentMeta, RuntimeError> {
+        let mut updated = None;
+        for id in self.storage.list_component_ids()? {
+            let mut meta = match self.storage.load_meta(&id) {
+                Ok(meta) if meta.tool_name == tool_name => meta,
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::warn!(component_id = id, "Failed to load metadata: {e}");
+                    continue;
+                }
+            };
+            update(&mut meta);
+            self.storage.save_meta(&meta)?;
+            updated = Some(meta);
+        }
+
+        let loaded_id = self.tool_index.read().await.get(tool_name).cloned();
+        if let Some(id) = loaded_id
+            && let Some(loaded) = self.components.write().await.get_mut(&id)
+        {
+            update(&mut loaded.meta);
+            updated = Some(loaded.meta.clone());
+        }
+
+        updated.ok_or_else(|| RuntimeError::ToolNotFound(tool_name.to_string()))
+    }
+
     /// Return MCP-style tool metadata for all loaded components.
     pub async fn list_tools(&self) -> Vec<ComponentMeta> {
         let components = self.components.read().await;
@@ -190,12 +500,112 @@ impl LifecycleManager {
     /// Invoke a tool by MCP tool name.
     ///
     /// The `args` value is serialized to JSON and passed to the component's
-    /// `run(input: string) -> result<string, string>` export. The returned
-    /// string is expected to be a JSON value.
+    /// `run(input: string) -> result<string, E>` export. The returned string
+    /// is expected to be a JSON value. An error string (`girt:tool@0.1.0`)
+    /// becomes [`RuntimeError::ToolError`]; a `tool-error` record
+    /// (`girt:tool@0.2.0`) becomes [`RuntimeError::ToolFailure`].
     pub async fn call_tool(
         &self,
         tool_name: &str,
         args: &serde_json::Value,
+    ) -> Result<serde_json::Value, RuntimeError> {
+        self.call_tool_with_options(tool_name, args, &CallOptions::default())
+            .await
+    }
+
+    /// Invoke a tool with per-call options.
+    ///
+    /// With [`CallOptions::deterministic`] set, the component sees a fixed
+    /// wall clock and seeded random sources, so a run can be replayed
+    /// exactly. Proxy calls use [`Self::call_tool`] and keep real clocks.
+    pub async fn call_tool_with_options(
+        &self,
+        tool_name: &str,
+        args: &serde_json::Value,
+        options: &CallOptions,
+    ) -> Result<serde_json::Value, RuntimeError> {
+        self.call_tool_traced(tool_name, args, options).await.0
+    }
+
+    /// Invoke a tool, also returning the HTTP requests it made, the
+    /// resources it used, and whether its response was truncated.
+    ///
+    /// Requests are only traced when they go through the egress proxy
+    /// ([`Self::with_egress_proxy`], or a tool with scoped network grants);
+    /// the trace is kept when the call fails.
+    /// A tool whose calls keep using over 80% of the memory limit is logged
+    /// as a warning.
+    pub async fn call_tool_traced(
+        &self,
+        tool_name: &str,
+        args: &serde_json::Value,
+        options: &CallOptions,
+    ) -> (Result<serde_json::Value, RuntimeError>, InvocationTrace) {
+        let span = tracing::info_span!(
+            "tool_invocation",
+            tool = tool_name,
+            deterministic = options.deterministic,
+            egress_requests = tracing::field::Empty,
+            peak_memory_bytes = tracing::field::Empty,
+            fuel_consumed = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        let mut egress = None;
+        let mut trace = InvocationTrace::default();
+        let fault = self.faults.as_ref().and_then(FaultInjector::next_fault);
+        if let Some(kind) = fault {
+            // Logged inside the caller's span, which carries its request id.
+            span.in_scope(|| tracing::warn!(tool = tool_name, fault = ?kind, "Injected fault"));
+        }
+        let started = Instant::now();
+        let mut result = if fault == Some(FaultKind::Timeout) {
+            self.injected_timeout(tool_name).await
+        } else {
+            self.invoke(tool_name, args, options, &mut egress, &mut trace)
+                .instrument(span.clone())
+                .await
+        };
+        let resources = &mut trace.resources;
+        resources.wall_time_ms = started.elapsed().as_millis() as u64;
+        if let Some(kind) = fault {
+            result = inject_fault(kind, tool_name, result);
+        }
+        if let Some(streak) = self.high_usage.observe(tool_name, resources) {
+            span.in_scope(|| {
+                tracing::warn!(
+                    tool = tool_name,
+                    streak,
+                    peak_memory_bytes = resources.peak_memory_bytes,
+                    memory_limit_bytes = resources.memory_limit_bytes,
+                    "Tool keeps using over {:.0}% of its memory limit; consider a larger tier or runtime.max_memory_mb",
+                    HIGH_USAGE_FRACTION * 100.0
+                )
+            });
+        }
+        let resources = trace.resources;
+        trace.egress = egress.map(|e| e.take_records()).unwrap_or_default();
+        span.record("egress_requests", trace.egress.len());
+        span.record("peak_memory_bytes", resources.peak_memory_bytes);
+        if let Some(fuel) = resources.fuel_consumed {
+            span.record("fuel_consumed", fuel);
+        }
+        if let Err(e) = &result {
+            span.record("error", tracing::field::display(e));
+        }
+        (result, trace)
+    }
+
+    /// Run one call. Sets `egress` to the call's proxy when it has one, and
+    /// fills in `trace` with the memory and fuel it used, whether its
+    /// response was cut down to the limit, and how many log lines it had
+    /// dropped. The egress records and wall time are left to the caller.
+    async fn invoke(
+        &self,
+        tool_name: &str,
+        args: &serde_json::Value,
+        options: &CallOptions,
+        egress: &mut Option<EgressProxy>,
+        trace: &mut InvocationTrace,
     ) -> Result<serde_json::Value, RuntimeError> {
         // Resolve tool → component
         let component_id = {
@@ -206,72 +616,261 @@ impl LifecycleManager {
                 .ok_or_else(|| RuntimeError::ToolNotFound(tool_name.to_string()))?
         };
 
-        let instance_pre = {
+        let (instance_pre, world, meta) = {
             let components = self.components.read().await;
             components
                 .get(&component_id)
-                .map(|c| c.instance_pre.clone())
+                .map(|c| (c.instance_pre.clone(), c.world, c.meta.clone()))
                 .ok_or_else(|| RuntimeError::ComponentNotFound(component_id.clone()))?
         };
 
-        tracing::debug!(tool_name, component_id, "Invoking tool");
+        tracing::debug!(
+            tool_name,
+            component_id,
+            deterministic = options.deterministic,
+            "Invoking tool"
+        );
 
         // Create fresh per-invocation state
-        let wasi_state = WasiState::new()
-            .map_err(|e| RuntimeError::InvocationFailed(e.to_string()))?;
+        let env = {
+            let tool_env = self.tool_env.read().unwrap_or_else(|e| e.into_inner());
+            resolve_tool_env(tool_name, tool_env.get(tool_name), &meta.allowed_env)
+        };
+        // The whole call, instantiation included, has to fit in the
+        // timeout; outgoing HTTP is cut short to leave it time to return.
+        let timeout = self.timeout_for(&meta);
+        let deadline = Instant::now() + timeout;
+        let mut wasi_state = WasiState::with_options(&env, options)
+            .map_err(|e| RuntimeError::InvocationFailed(e.to_string()))?
+            .with_max_memory_bytes(self.runtime.config.max_memory_bytes())
+            .with_component_id(&component_id)
+            .with_deadline(deadline);
+        let response_limit = meta
+            .max_response_bytes
+            .into_iter()
+            .chain(self.max_response_bytes)
+            .min();
+        if let Some(proxy) = call_egress(self.egress_proxy, tool_name, meta) {
+            wasi_state = wasi_state.with_egress(proxy.clone());
+            *egress = Some(proxy);
+        }
         let mut store = Store::new(&self.runtime.engine, wasi_state);
+        store.limiter(|state| state.limits());
+        if self.runtime.config.epoch_tick_ms > 0 {
+            // Yield to other tasks at every tick rather than hold the thread.
+            store.epoch_deadline_async_yield_and_update(1);
+        }
+        let metered = self.runtime.config.fuel_metering;
+        if metered {
+            // Metered, not limited: the call only has to report what it burned.
+            store
+                .set_fuel(u64::MAX)
+                .map_err(|e| RuntimeError::InvocationFailed(format!("{tool_name}: {e}")))?;
+        }
+        let resources = &mut trace.resources;
+        resources.memory_limit_bytes = self.runtime.config.max_memory_bytes() as u64;
+
+        // Only noticed when the component yields: while it waits on I/O, or
+        // at each epoch tick with `epoch_tick_ms` set.
+        let call = run(&mut store, tool_name, &instance_pre, world, args);
+        let result = match tokio::time::timeout_at(deadline.into(), call).await {
+            Ok(result) => result,
+            Err(_) => Err(RuntimeError::Timeout {
+                tool: tool_name.to_string(),
+                timeout_ms: timeout.as_millis() as u64,
+            }),
+        };
+        resources.peak_memory_bytes = store.data().peak_memory_bytes() as u64;
+        trace.logs_dropped = store.data().logs_dropped();
+        if metered {
+            resources.fuel_consumed = store.get_fuel().ok().map(|left| u64::MAX - left);
+        }
+        let mut output = result?;
+        if let Some(limit) = response_limit {
+            trace.truncated = self
+                .oversize_response
+                .apply(&mut output, usize::try_from(limit).unwrap_or(usize::MAX))
+                .map_err(|source| RuntimeError::ResponseTooLarge {
+                    tool: tool_name.to_string(),
+                    source,
+                })?;
+            if let Some(truncation) = &trace.truncated {
+                tracing::warn!(
+                    tool = tool_name,
+                    original_bytes = truncation.original_bytes,
+                    limit_bytes = truncation.limit_bytes,
+                    "Truncated oversized tool response"
+                );
+            }
+        }
+        // Tools should return JSON; anything else, including a truncated
+        // response, is passed on as a string.
+        Ok(serde_json::from_str(&output).unwrap_or(serde_json::Value::String(output)))
+    }
 
-        // Instantiate
-        let instance = instance_pre
-            .instantiate_async(&mut store)
-            .await
-            .map_err(|e| RuntimeError::InstantiationFailed(format!("{tool_name}: {e}")))?;
-
-        // Get the `run` export
-        let run_func = instance
-            .get_func(&mut store, "run")
-            .ok_or_else(|| RuntimeError::InvocationFailed(
-                format!("{tool_name}: no 'run' export found; component may not implement girt-tool world")
-            ))?;
-
-        // Serialize args to JSON string (the component model boundary)
-        let input_json = serde_json::to_string(args)?;
-
-        // Call run(input: string) → result<string, string>
-        let params = [Val::String(input_json)];
-        let mut results = vec![Val::Bool(false)]; // placeholder; overwritten by call
-
-        run_func
-            .call_async(&mut store, &params, &mut results)
-            .await
-            .map_err(|e| RuntimeError::InvocationFailed(format!("{tool_name}: {e}")))?;
+    /// Return the metadata of a loaded tool by MCP tool name.
+    /// How long a call to the tool with `meta` may run: its own
+    /// `timeout_ms`, capped by the call timeout.
+    fn timeout_for(&self, meta: &ComponentMeta) -> Duration {
+        meta.timeout_ms
+            .map(Duration::from_millis)
+            .map_or(self.call_timeout, |t| t.min(self.call_timeout))
+    }
 
-        // Required after any component call that may return results
-        run_func
-            .post_return_async(&mut store)
+    /// An injected [`FaultKind::Timeout`]: the tool is not run, but the call
+    /// takes its whole time limit and then fails as a call that ran into it
+    /// would.
+    async fn injected_timeout(&self, tool_name: &str) -> Result<serde_json::Value, RuntimeError> {
+        let meta = self
+            .tool_meta(tool_name)
             .await
-            .map_err(|e| RuntimeError::InvocationFailed(format!("{tool_name} post_return: {e}")))?;
-
-        // Decode result<string, string>
-        let output_json = extract_run_result(tool_name, results)?;
-
-        // Parse output as JSON (tools should return valid JSON)
-        let output_value: serde_json::Value = serde_json::from_str(&output_json).unwrap_or_else(
-            |_| serde_json::Value::String(output_json),
-        );
+            .ok_or_else(|| RuntimeError::ToolNotFound(tool_name.to_string()))?;
+        let timeout = self.timeout_for(&meta);
+        tokio::time::sleep(timeout).await;
+        Err(RuntimeError::Timeout {
+            tool: tool_name.to_string(),
+            timeout_ms: timeout.as_millis() as u64,
+        })
+    }
 
-        Ok(output_value)
+    pub async fn tool_meta(&self, tool_name: &str) -> Option<ComponentMeta> {
+        let component_id = self.tool_index.read().await.get(tool_name).cloned()?;
+        let components = self.components.read().await;
+        components.get(&component_id).map(|c| c.meta.clone())
     }
 
     /// Return true if the named tool is currently loaded.
     pub async fn has_tool(&self, tool_name: &str) -> bool {
         self.tool_index.read().await.contains_key(tool_name)
     }
+
+    /// Whether the named tool is one of GIRT's own [bundled] components,
+    /// loaded with [`Self::load_bundled`] or restored with its exact wasm.
+    pub async fn is_bundled(&self, tool_name: &str) -> bool {
+        let Some(component_id) = self.tool_index.read().await.get(tool_name).cloned() else {
+            return false;
+        };
+        let components = self.components.read().await;
+        components.get(&component_id).is_some_and(|c| c.bundled)
+    }
+}
+
+/// Instantiate a component in `store` and call its `run` export with `args`,
+/// returning the output string.
+async fn run(
+    store: &mut Store<WasiState>,
+    tool_name: &str,
+    instance_pre: &InstancePre<WasiState>,
+    world: WorldVersion,
+    args: &serde_json::Value,
+) -> Result<String, RuntimeError> {
+    // Instantiate
+    let instance = instance_pre
+        .instantiate_async(&mut *store)
+        .await
+        .map_err(|e| RuntimeError::InstantiationFailed(format!("{tool_name}: {e}")))?;
+
+    // Get the `run` export
+    let run_func = instance
+        .get_func(&mut *store, "run")
+        .ok_or_else(|| RuntimeError::InvocationFailed(
+            format!("{tool_name}: no 'run' export found; component may not implement girt-tool world")
+        ))?;
+
+    // Serialize args to JSON string (the component model boundary)
+    let input_json = serde_json::to_string(args)?;
+
+    // Call run(input: string) → result<string, E>
+    let params = [Val::String(input_json)];
+    let mut results = vec![Val::Bool(false)]; // placeholder; overwritten by call
+
+    run_func
+        .call_async(&mut *store, &params, &mut results)
+        .await
+        .map_err(|e| RuntimeError::InvocationFailed(format!("{tool_name}: {e}")))?;
+
+    // Required after any component call that may return results
+    run_func
+        .post_return_async(&mut *store)
+        .await
+        .map_err(|e| RuntimeError::InvocationFailed(format!("{tool_name} post_return: {e}")))?;
+
+    // Decode result<string, E> according to the component's world
+    extract_run_result(tool_name, world, results)
+}
+
+/// What a call returns when `kind` is injected into it. A timeout has
+/// already happened by the time the call returns
+/// ([`LifecycleManager::injected_timeout`]).
+fn inject_fault(
+    kind: FaultKind,
+    tool_name: &str,
+    result: Result<serde_json::Value, RuntimeError>,
+) -> Result<serde_json::Value, RuntimeError> {
+    match (kind, result) {
+        (FaultKind::Timeout, result) => result,
+        (FaultKind::Error, _) => Err(RuntimeError::ToolError(format!(
+            "{tool_name}: failed (injected fault)"
+        ))),
+        (FaultKind::Garbage, Ok(output)) => Ok(faults::garble(&output)),
+        // A failed call has no response to garble; passing off a garbled
+        // nothing as success would hide both the fault and the failure.
+        (FaultKind::Garbage, Err(e)) => Err(RuntimeError::ToolError(format!(
+            "{tool_name}: garbled response (injected fault) to a failed call: {e}"
+        ))),
+    }
+}
+
+/// The network grants in `meta` limited to some methods or path prefixes,
+/// which only the egress proxy can enforce.
+fn scoped_grants(meta: &ComponentMeta) -> Vec<String> {
+    meta.allowed_hosts
+        .iter()
+        .filter(|grant| grant.is_scoped())
+        .map(ToString::to_string)
+        .collect()
 }
 
-/// Extract the string value from `result<string, string>` Val.
+/// The egress proxy one call to `tool_name` sends its HTTP through: always
+/// in proxy mode, and in direct mode when a grant is scoped, as only the
+/// proxy sees a request's method and path.
+fn call_egress(proxy_mode: bool, tool_name: &str, meta: ComponentMeta) -> Option<EgressProxy> {
+    let scoped = meta.allowed_hosts.iter().any(|grant| grant.is_scoped());
+    (proxy_mode || scoped)
+        .then(|| EgressProxy::new(tool_name, meta.allowed_hosts, meta.max_response_bytes))
+}
+
+/// Filter a tool's configured env vars down to the keys its policy allows.
+///
+/// Output is sorted by key so the component sees a stable environment.
+fn resolve_tool_env(
+    tool_name: &str,
+    configured: Option<&HashMap<String, String>>,
+    allowed: &[String],
+) -> Vec<(String, String)> {
+    let Some(configured) = configured else {
+        return Vec::new();
+    };
+    let mut env: Vec<(String, String)> = configured
+        .iter()
+        .filter(|(key, _)| {
+            let permitted = allowed.iter().any(|a| a == *key);
+            if !permitted {
+                tracing::debug!(tool_name, key = %key, "Env var not allowed by policy; dropped");
+            }
+            permitted
+        })
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    env.sort();
+    env
+}
+
+/// Extract the string value from a `result<string, E>` Val, decoding `E`
+/// as `world` defines it.
 fn extract_run_result(
     tool_name: &str,
+    world: WorldVersion,
     results: Vec<Val>,
 ) -> Result<String, RuntimeError> {
     match results.into_iter().next() {
@@ -281,12 +880,8 @@ fn extract_run_result(
                 "{tool_name}: expected string in Ok variant, got {other:?}"
             ))),
         },
-        Some(Val::Result(Err(Some(boxed)))) => match *boxed {
-            Val::String(e) => Err(RuntimeError::ToolError(e)),
-            other => Err(RuntimeError::ToolError(format!("{other:?}"))),
-        },
+        Some(Val::Result(Err(payload))) => Err(world.decode_error(payload.map(|b| *b))),
         Some(Val::Result(Ok(None))) => Ok("null".into()),
-        Some(Val::Result(Err(None))) => Err(RuntimeError::ToolError("(no error detail)".into())),
         Some(other) => Err(RuntimeError::InvocationFailed(format!(
             "{tool_name}: unexpected return Val: {other:?}"
         ))),
@@ -295,3 +890,123 @@ fn extract_run_result(
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_tool_env_keeps_only_allowed_keys() {
+        let configured = HashMap::from([
+            ("GITLAB_BASE_URL".to_string(), "https://gitlab.example.com".to_string()),
+            ("GITLAB_TOKEN".to_string(), "secret".to_string()),
+            ("API_REGION".to_string(), "eu".to_string()),
+        ]);
+        let allowed = vec!["GITLAB_BASE_URL".to_string(), "API_REGION".to_string()];
+
+        let env = resolve_tool_env("gitlab", Some(&configured), &allowed);
+        assert_eq!(
+            env,
+            vec![
+                ("API_REGION".to_string(), "eu".to_string()),
+                ("GITLAB_BASE_URL".to_string(), "https://gitlab.example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_tool_env_empty_without_config_or_allowlist() {
+        assert!(resolve_tool_env("t", None, &["KEY".to_string()]).is_empty());
+
+        let configured = HashMap::from([("KEY".to_string(), "v".to_string())]);
+        assert!(resolve_tool_env("t", Some(&configured), &[]).is_empty());
+    }
+
+    #[test]
+    fn scoped_grants_lists_only_grants_the_proxy_must_enforce() {
+        use girt_core::spec::NetworkGrant;
+
+        let mut meta = bundled::ECHO.meta();
+        meta.allowed_hosts = vec![
+            NetworkGrant::from("api.open-meteo.com"),
+            NetworkGrant::scoped("api.github.com", vec!["GET".into()], vec!["/repos/".into()]),
+        ];
+        assert_eq!(
+            scoped_grants(&meta),
+            vec!["api.github.com (GET) under /repos/".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn scoped_grants_are_enforced_in_direct_mode() {
+        use bytes::Bytes;
+        use girt_core::spec::NetworkGrant;
+        use http_body_util::{BodyExt, Empty};
+        use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+        use wasmtime_wasi_http::types::OutgoingRequestConfig;
+
+        let mut meta = bundled::ECHO.meta();
+        meta.allowed_hosts = vec![NetworkGrant::from("api.open-meteo.com")];
+        assert!(call_egress(false, "echo", meta.clone()).is_none());
+
+        meta.allowed_hosts = vec![NetworkGrant::scoped(
+            "127.0.0.1:9",
+            vec!["GET".into()],
+            vec!["/repos/".into()],
+        )];
+        let proxy = call_egress(false, "echo", meta).expect("scoped grants need the proxy");
+        let body = Empty::<Bytes>::new()
+            .map_err(|never| -> ErrorCode { match never {} })
+            .boxed();
+        let request = http::Request::delete("http://127.0.0.1:9/repos/girt")
+            .body(body)
+            .unwrap();
+        let config = OutgoingRequestConfig {
+            use_tls: false,
+            connect_timeout: Duration::from_secs(5),
+            first_byte_timeout: Duration::from_secs(5),
+            between_bytes_timeout: Duration::from_secs(5),
+        };
+        let result = proxy.send(request, config).await;
+        assert!(matches!(result, Err(ErrorCode::HttpRequestDenied)));
+        let error = proxy.take_records().remove(0).error.unwrap();
+        assert!(
+            error.starts_with("DELETE /repos/girt is outside the scope"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn quarantine_is_stored_until_released() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut meta = bundled::ECHO.meta();
+        meta.component_id = "echo@0.1.0".into();
+        meta.tool_name = "echo".into();
+        let storage = ComponentStorage::new(tmp.path().to_path_buf());
+        storage.init().unwrap();
+        storage.store_bytes(bundled::ECHO.wasm, &mut meta).unwrap();
+
+        let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+        manager.load_persisted().await;
+        let err = manager.release("echo").await.unwrap_err();
+        assert!(matches!(err, RuntimeError::NotQuarantined(_)), "{err}");
+
+        let reason = "Policy rule: Internal hosts".to_string();
+        manager
+            .set_quarantine("echo", Some(reason.clone()))
+            .await
+            .unwrap();
+        let stored = storage.load_meta("echo@0.1.0").unwrap();
+        assert_eq!(stored.quarantined.as_deref(), Some(reason.as_str()));
+
+        let released = manager.release("echo").await.unwrap();
+        assert!(released.quarantined.is_none() && released.quarantine_released);
+        let stored = storage.load_meta("echo@0.1.0").unwrap();
+        assert!(stored.quarantined.is_none() && stored.quarantine_released);
+        let loaded = manager.tool_meta("echo").await.unwrap();
+        assert!(loaded.quarantined.is_none() && loaded.quarantine_released);
+
+        let err = manager.release("missing").await.unwrap_err();
+        assert!(matches!(err, RuntimeError::ToolNotFound(_)), "{err}");
+    }
+}
@@ -1,30 +1,167 @@
 // Ported from microsoft/wassette (MIT License)
 // Copyright (c) Microsoft Corporation.
 
+use std::fmt;
+use std::time::Duration;
+
 use anyhow::Result;
-use wasmtime::{Config, Engine};
+use girt_core::host::{HostCapabilities, HostImport};
 use wasmtime::component::Linker;
+use wasmtime::{Config, Engine, InstanceAllocationStrategy, PoolingAllocationConfig};
 
+use crate::host_utils;
 use crate::wasistate::WasiState;
 
+/// Pooling slots reserved per component instance. A `cargo-component` tool
+/// instantiates its own module, the WASI adapter, and a few shims.
+const CORE_INSTANCES_PER_COMPONENT: u32 = 16;
+const MEMORIES_PER_COMPONENT: u32 = 4;
+const TABLES_PER_COMPONENT: u32 = 4;
+/// Instantiating and calling a component each run on a fiber stack.
+const STACKS_PER_COMPONENT: u32 = 2;
+
+/// Wasmtime engine settings (`[runtime]` in girt.toml).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineConfig {
+    /// Preallocate slots for instances and their memories up front instead
+    /// of allocating them on every call.
+    pub pooling_allocator: bool,
+    /// Largest size any one linear memory may grow to, in MiB.
+    pub max_memory_mb: u32,
+    /// Component instances alive at once. Only the pooling allocator is
+    /// bounded by it.
+    pub max_instances: u32,
+    /// How often a running component yields to other tasks, in
+    /// milliseconds. `0` lets it run until it returns.
+    pub epoch_tick_ms: u64,
+    /// Generate native unwind info, for host backtraces through WASM frames.
+    pub native_unwind_info: bool,
+    /// Count the fuel each call burns. Calls are not limited by it; the
+    /// count only ends up in the call's resource usage.
+    pub fuel_metering: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            pooling_allocator: false,
+            max_memory_mb: 512,
+            max_instances: 100,
+            epoch_tick_ms: 0,
+            native_unwind_info: true,
+            fuel_metering: false,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn max_memory_bytes(&self) -> usize {
+        self.max_memory_mb as usize * 1024 * 1024
+    }
+}
+
+impl fmt::Display for EngineConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pooling_allocator {
+            write!(f, "pooling allocator ({} instances)", self.max_instances)?;
+        } else {
+            write!(f, "on-demand allocator")?;
+        }
+        write!(f, ", memories up to {} MiB", self.max_memory_mb)?;
+        match self.epoch_tick_ms {
+            0 => write!(f, ", no epoch ticks")?,
+            ms => write!(f, ", epoch tick every {ms}ms")?,
+        }
+        let unwind = if self.native_unwind_info { "on" } else { "off" };
+        write!(f, ", native unwind info {unwind}")?;
+        if self.fuel_metering {
+            write!(f, ", fuel metering")?;
+        }
+        Ok(())
+    }
+}
+
+/// `wasi:http/outgoing-handler`, the interface tools send HTTP requests
+/// through.
+const OUTGOING_HANDLER: &str = "wasi:http/outgoing-handler@0.2.0";
+
+/// The imports [`RuntimeContext`] links that generated code is offered.
+/// The rest of WASI p2 (clocks, random, stdio) is linked too, but tools
+/// reach it through their language's standard library.
+///
+/// Update this alongside the linker in [`RuntimeContext::with_config`]:
+/// the Engineer's prompt is rendered from it.
+pub fn host_capabilities() -> HostCapabilities {
+    HostCapabilities {
+        imports: vec![
+            HostImport::new(
+                OUTGOING_HANDLER,
+                "Outgoing HTTP; request and response types come from wasi:http/types@0.2.0.",
+            )
+            .gated_function(
+                "handle",
+                "func(request: outgoing-request, options: option<request-options>) \
+                 -> result<future-incoming-response, error-code>",
+                "send a request. Only hosts in the spec's network constraints are reachable.",
+            ),
+            host_utils::capability(),
+        ],
+    }
+}
+
 /// Shared Wasmtime engine and linker.
 ///
 /// `RuntimeContext` is constructed once and shared across all component
 /// loads and invocations. The engine is thread-safe; the linker is
-/// pre-configured with WASI p2 and WASI HTTP host functions.
+/// pre-configured with WASI p2, WASI HTTP, and `girt:host/utils` host
+/// functions.
 pub struct RuntimeContext {
     pub engine: Engine,
     pub linker: Linker<WasiState>,
+    /// The settings `engine` was built with.
+    pub config: EngineConfig,
 }
 
 impl RuntimeContext {
     pub fn new() -> Result<Self> {
+        Self::with_config(&EngineConfig::default())
+    }
+
+    /// Build the engine with `engine_config`. Fails rather than building an
+    /// engine that would trap on the first call.
+    pub fn with_config(engine_config: &EngineConfig) -> Result<Self> {
+        anyhow::ensure!(
+            engine_config.max_memory_mb > 0,
+            "max_memory_mb must be at least 1"
+        );
+        anyhow::ensure!(
+            !engine_config.pooling_allocator || engine_config.max_instances > 0,
+            "the pooling allocator needs max_instances of at least 1"
+        );
+
         let mut config = Config::new();
         config.wasm_component_model(true);
         config.async_support(true);
-        // Future: config.consume_fuel(true) for CPU limits
+        config.native_unwind_info(engine_config.native_unwind_info);
+        config.epoch_interruption(engine_config.epoch_tick_ms > 0);
+        config.consume_fuel(engine_config.fuel_metering);
+        if engine_config.pooling_allocator {
+            let instances = engine_config.max_instances;
+            let mut pooling = PoolingAllocationConfig::default();
+            pooling
+                .total_component_instances(instances)
+                .total_core_instances(instances.saturating_mul(CORE_INSTANCES_PER_COMPONENT))
+                .total_memories(instances.saturating_mul(MEMORIES_PER_COMPONENT))
+                .total_tables(instances.saturating_mul(TABLES_PER_COMPONENT))
+                .total_stacks(instances.saturating_mul(STACKS_PER_COMPONENT))
+                .max_memory_size(engine_config.max_memory_bytes());
+            config.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+        }
 
         let engine = Engine::new(&config)?;
+        if engine_config.epoch_tick_ms > 0 {
+            spawn_epoch_ticker(&engine, Duration::from_millis(engine_config.epoch_tick_ms))?;
+        }
         let mut linker: Linker<WasiState> = Linker::new(&engine);
 
         // Wire WASI p2 host functions (filesystem, clocks, random, stdio, …)
@@ -33,9 +170,16 @@ impl RuntimeContext {
         // Wire WASI HTTP host functions (outgoing HTTP requests)
         wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
 
-        tracing::debug!("RuntimeContext initialized (component-model + async + WASI p2 + HTTP)");
+        // Clock, UUID, and logging helpers for components that import them
+        host_utils::add_to_linker(&mut linker)?;
 
-        Ok(Self { engine, linker })
+        tracing::debug!(config = %engine_config, "RuntimeContext initialized (component-model + async + WASI p2 + HTTP + host utils)");
+
+        Ok(Self {
+            engine,
+            linker,
+            config: engine_config.clone(),
+        })
     }
 }
 
@@ -44,3 +188,20 @@ impl Default for RuntimeContext {
         Self::new().expect("RuntimeContext::new should not fail with default config")
     }
 }
+
+/// Advance `engine`'s epoch every `tick` until the engine is dropped.
+fn spawn_epoch_ticker(engine: &Engine, tick: Duration) -> Result<()> {
+    let engine = engine.weak();
+    std::thread::Builder::new()
+        .name("girt-epoch".into())
+        .spawn(move || {
+            loop {
+                std::thread::sleep(tick);
+                match engine.upgrade() {
+                    Some(engine) => engine.increment_epoch(),
+                    None => break,
+                }
+            }
+        })?;
+    Ok(())
+}
@@ -4,6 +4,8 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use girt_core::paths::{self, PathError};
+use girt_core::spec::NetworkGrant;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use wasmtime::Engine;
@@ -28,10 +30,72 @@ pub struct ComponentMeta {
     pub description: String,
     /// JSON Schema for tool inputs (displayed in list_tools)
     pub input_schema: serde_json::Value,
-    /// SHA-256 hex of the .wasm bytes (for cache validation)
+    /// SHA-256 hex of the .wasm bytes (for cache validation).
+    /// Filled in by [`ComponentStorage::store`]; callers may leave it empty.
     pub wasm_hash: String,
+    /// Size of the stored .wasm in bytes. Filled in by [`ComponentStorage::store`].
+    #[serde(default)]
+    pub wasm_size_bytes: u64,
     /// Pipeline build timestamp (Unix ms)
     pub built_at: u64,
+    /// When the component was stored into the runtime (Unix ms).
+    /// `loaded_at - built_at` is the build-to-load drift.
+    #[serde(default)]
+    pub loaded_at: u64,
+    /// Environment variable names the component's policy allows it to read.
+    /// Values are supplied per call from `[tools.env.<tool_name>]`.
+    #[serde(default)]
+    pub allowed_env: Vec<String>,
+    /// Hosts the component's policy allows it to reach (`host`, `host:port`,
+    /// or `*.domain`), each optionally limited to some methods and path
+    /// prefixes. Enforced when the egress proxy is enabled.
+    #[serde(default)]
+    pub allowed_hosts: Vec<NetworkGrant>,
+    /// The policy's cap on each HTTP response body. Enforced when the
+    /// egress proxy is enabled.
+    #[serde(default)]
+    pub max_response_bytes: Option<u64>,
+    /// The policy's time limit for one call, in milliseconds. Outgoing
+    /// HTTP requests are cut short to finish within it.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Calls have no side effects, so the proxy may retry them on
+    /// transient errors. Set by the pipeline for read-only tools.
+    #[serde(default)]
+    pub idempotent: bool,
+    /// Set by `girt tools deprecate`. The tool keeps working, with a
+    /// warning, until the grace period after `deprecated_at` runs out.
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Tool that agents should call instead of this one.
+    #[serde(default)]
+    pub replaced_by: Option<String>,
+    /// When the tool was deprecated (Unix ms).
+    #[serde(default)]
+    pub deprecated_at: Option<u64>,
+    /// Where the component was pushed (`registry/name:tag`), if it was.
+    #[serde(default)]
+    pub oci_reference: Option<String>,
+    /// Where the tool's source was mirrored by tool sync, if it was.
+    #[serde(default)]
+    pub source_repo_path: Option<String>,
+    /// When the tool's test vectors were last re-run against it by
+    /// `girt tools validate` (Unix ms).
+    #[serde(default)]
+    pub last_validated_at: Option<u64>,
+    /// The last validation got output that no longer matches the tool's
+    /// test vectors, typically because an upstream API changed shape.
+    #[serde(default)]
+    pub drift_detected: bool,
+    /// Why the tool is quarantined: the policy rule that denied it when it
+    /// was last restored. A quarantined tool stays stored but is neither
+    /// listed to agents nor callable.
+    #[serde(default)]
+    pub quarantined: Option<String>,
+    /// Released from quarantine by `girt tools release`. Restores no
+    /// longer re-check the tool against the policy rules.
+    #[serde(default)]
+    pub quarantine_released: bool,
 }
 
 /// Disk-backed component cache.
@@ -52,11 +116,8 @@ impl ComponentStorage {
         Self { base_dir }
     }
 
-    pub fn default_path() -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".girt")
-            .join("components")
+    pub fn default_path() -> Result<PathBuf, PathError> {
+        Ok(paths::data_dir()?.join("components"))
     }
 
     pub fn init(&self) -> Result<()> {
@@ -77,8 +138,57 @@ impl ComponentStorage {
     }
 
     /// Copy a WASM binary into storage and write its metadata.
-    pub fn store(&self, wasm_src: &Path, meta: &ComponentMeta) -> Result<(), RuntimeError> {
+    ///
+    /// The hash, size, and load time are computed from the stored copy and
+    /// written back into `meta` before it is persisted. Returns the hash.
+    pub fn store(
+        &self,
+        wasm_src: &Path,
+        meta: &mut ComponentMeta,
+    ) -> Result<String, RuntimeError> {
         std::fs::copy(wasm_src, self.wasm_path(&meta.component_id))?;
+        self.stored(meta)
+    }
+
+    /// Like [`Self::store`], for a component held in memory (a
+    /// [bundled](crate::bundled) one).
+    pub fn store_bytes(
+        &self,
+        wasm: &[u8],
+        meta: &mut ComponentMeta,
+    ) -> Result<String, RuntimeError> {
+        std::fs::write(self.wasm_path(&meta.component_id), wasm)?;
+        self.stored(meta)
+    }
+
+    /// Fill in `meta` from the .wasm just stored for it and persist it.
+    fn stored(&self, meta: &mut ComponentMeta) -> Result<String, RuntimeError> {
+        // A precompiled copy of whatever was stored under this id before
+        // must not be loaded in place of the new wasm.
+        match std::fs::remove_file(self.cwasm_path(&meta.component_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let wasm_dest = self.wasm_path(&meta.component_id);
+        let hash = hash_wasm(&wasm_dest)?;
+        if !meta.wasm_hash.is_empty() && meta.wasm_hash != hash {
+            tracing::warn!(
+                component_id = %meta.component_id,
+                expected = %meta.wasm_hash,
+                actual = %hash,
+                "Stored wasm hash differs from caller-supplied hash"
+            );
+        }
+        meta.wasm_hash = hash.clone();
+        meta.wasm_size_bytes = std::fs::metadata(&wasm_dest)?.len();
+        meta.loaded_at = now_ms();
+
+        self.save_meta(meta)?;
+        Ok(hash)
+    }
+
+    /// Overwrite a component's metadata, leaving its .wasm untouched.
+    pub fn save_meta(&self, meta: &ComponentMeta) -> Result<(), RuntimeError> {
         let meta_json = serde_json::to_string_pretty(meta)?;
         std::fs::write(self.meta_path(&meta.component_id), meta_json)?;
         Ok(())
@@ -152,9 +262,161 @@ impl ComponentStorage {
     }
 }
 
+pub(crate) fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 /// Hash the bytes of a WASM file for cache validation.
 pub fn hash_wasm(path: &Path) -> Result<String, RuntimeError> {
     let bytes = std::fs::read(path)?;
     let hash = Sha256::digest(&bytes);
     Ok(hex::encode(hash))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn meta(component_id: &str) -> ComponentMeta {
+        ComponentMeta {
+            component_id: component_id.into(),
+            tool_name: "hash_check".into(),
+            description: "test".into(),
+            input_schema: serde_json::Value::Null,
+            wasm_hash: String::new(),
+            wasm_size_bytes: 0,
+            built_at: 1_000,
+            loaded_at: 0,
+            allowed_env: vec![],
+            allowed_hosts: vec![],
+            max_response_bytes: None,
+            timeout_ms: None,
+            idempotent: false,
+            deprecated: false,
+            replaced_by: None,
+            deprecated_at: None,
+            oci_reference: None,
+            source_repo_path: None,
+            last_validated_at: None,
+            drift_detected: false,
+            quarantined: None,
+            quarantine_released: false,
+        }
+    }
+
+    #[test]
+    fn store_fills_hash_size_and_load_time() {
+        let tmp = TempDir::new().unwrap();
+        let storage = ComponentStorage::new(tmp.path().join("components"));
+        storage.init().unwrap();
+
+        let src = tmp.path().join("tool.wasm");
+        std::fs::write(&src, b"\0asm fake component bytes").unwrap();
+
+        let mut meta = meta("hash_check@0.1.0");
+        let hash = storage.store(&src, &mut meta).unwrap();
+
+        assert!(!hash.is_empty());
+        assert_eq!(meta.wasm_hash, hash);
+        assert_eq!(hash, hash_wasm(&storage.wasm_path("hash_check@0.1.0")).unwrap());
+        assert_eq!(meta.wasm_size_bytes, std::fs::metadata(&src).unwrap().len());
+        assert!(meta.loaded_at >= meta.built_at);
+
+        let persisted = storage.load_meta("hash_check@0.1.0").unwrap();
+        assert_eq!(persisted.wasm_hash, hash);
+        assert_eq!(persisted.wasm_size_bytes, meta.wasm_size_bytes);
+    }
+
+    #[test]
+    fn store_bytes_replaces_the_wasm_and_its_precompiled_copy() {
+        let tmp = TempDir::new().unwrap();
+        let storage = ComponentStorage::new(tmp.path().join("components"));
+        storage.init().unwrap();
+
+        // Precompiled from whatever was stored before.
+        let cwasm_path = storage.cwasm_path("hash_check@0.1.0");
+        std::fs::write(&cwasm_path, b"stale").unwrap();
+
+        let mut meta = meta("hash_check@0.1.0");
+        let hash = storage
+            .store_bytes(b"\0asm fake component bytes", &mut meta)
+            .unwrap();
+        assert!(!cwasm_path.exists());
+
+        let wasm_path = storage.wasm_path("hash_check@0.1.0");
+        let stored = std::fs::read(&wasm_path).unwrap();
+        assert_eq!(stored, b"\0asm fake component bytes");
+        assert_eq!(hash, hash_wasm(&wasm_path).unwrap());
+        assert_eq!(meta.wasm_size_bytes, 25);
+        let persisted = storage.load_meta("hash_check@0.1.0").unwrap();
+        assert_eq!(persisted.wasm_hash, hash);
+    }
+
+    #[test]
+    fn metadata_without_size_fields_still_loads() {
+        let tmp = TempDir::new().unwrap();
+        let storage = ComponentStorage::new(tmp.path().to_path_buf());
+        storage.init().unwrap();
+
+        let legacy = serde_json::json!({
+            "component_id": "old@0.1.0",
+            "tool_name": "old",
+            "description": "pre-size metadata",
+            "input_schema": null,
+            "wasm_hash": "abc",
+            "built_at": 5
+        });
+        std::fs::write(storage.meta_path("old@0.1.0"), legacy.to_string()).unwrap();
+
+        let meta = storage.load_meta("old@0.1.0").unwrap();
+        assert_eq!(meta.wasm_size_bytes, 0);
+        assert_eq!(meta.loaded_at, 0);
+        assert!(!meta.deprecated);
+        assert_eq!(meta.replaced_by, None);
+        assert_eq!(meta.deprecated_at, None);
+        assert_eq!(meta.oci_reference, None);
+        assert_eq!(meta.source_repo_path, None);
+    }
+
+    #[test]
+    fn deprecation_fields_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let storage = ComponentStorage::new(tmp.path().to_path_buf());
+        storage.init().unwrap();
+
+        let meta = ComponentMeta {
+            deprecated: true,
+            replaced_by: Some("hash_check_v2".into()),
+            deprecated_at: Some(2_000),
+            ..meta("hash_check@0.1.0")
+        };
+        storage.save_meta(&meta).unwrap();
+
+        let persisted = storage.load_meta("hash_check@0.1.0").unwrap();
+        assert!(persisted.deprecated);
+        assert_eq!(persisted.replaced_by.as_deref(), Some("hash_check_v2"));
+        assert_eq!(persisted.deprecated_at, Some(2_000));
+    }
+
+    #[test]
+    fn artifact_locations_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let storage = ComponentStorage::new(tmp.path().to_path_buf());
+        storage.init().unwrap();
+
+        let meta = ComponentMeta {
+            oci_reference: Some("ghcr.io/acme/girt-tools/hash_check:v1".into()),
+            source_repo_path: Some("git@github.com:acme/tools.git (main:tools)".into()),
+            ..meta("hash_check@0.1.0")
+        };
+        storage.save_meta(&meta).unwrap();
+
+        let persisted = storage.load_meta("hash_check@0.1.0").unwrap();
+        assert_eq!(persisted.oci_reference, meta.oci_reference);
+        assert_eq!(persisted.source_repo_path, meta.source_repo_path);
+    }
+}
@@ -1,9 +1,23 @@
 // Ported from microsoft/wassette (MIT License)
 // Copyright (c) Microsoft Corporation.
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
 use wasmtime::component::ResourceTable;
-use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
-use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
+use wasmtime::{ResourceLimiter, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::random::Deterministic;
+use wasmtime_wasi::{
+    HostMonotonicClock, HostWallClock, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView,
+};
+use wasmtime_wasi_http::body::HyperOutgoingBody;
+use wasmtime_wasi_http::types::{
+    HostFutureIncomingResponse, OutgoingRequestConfig, default_send_request,
+};
+use wasmtime_wasi_http::{HttpResult, WasiHttpCtx, WasiHttpView};
+
+use crate::egress::EgressProxy;
+use crate::host_utils::HostUtils;
 
 /// Per-invocation WASM state.
 ///
@@ -12,13 +26,25 @@ use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
 ///
 /// Security posture (deny-default):
 /// - No filesystem preopens
-/// - No host environment variables
+/// - No host environment variables; only values configured for the tool
+///   and allowed by its policy are injected (see [`WasiState::with_env`])
 /// - stdout/stderr forwarded to tracing (captured by WasiCtxBuilder)
-/// - Network access via WASI HTTP only (policy enforced at the gate layer)
+/// - Network access via WASI HTTP only (policy enforced at the gate layer,
+///   and per request when an [`EgressProxy`] is attached: in proxy mode,
+///   or for a tool with scoped network grants)
+/// - Outgoing HTTP timeouts cut short to the call's deadline, if it has one
+///   (see [`within_deadline`])
+/// - Real clocks and host entropy, unless [`CallOptions::deterministic`] is set
+/// - `girt:host/utils` answers from the same clock and seed (see
+///   [`crate::host_utils`])
 pub struct WasiState {
     ctx: WasiCtx,
     table: ResourceTable,
     http: WasiHttpCtx,
+    egress: Option<EgressProxy>,
+    limits: UsageLimiter,
+    host_utils: HostUtils,
+    deadline: Option<Instant>,
 }
 
 impl WasiView for WasiState {
@@ -37,22 +63,187 @@ impl WasiHttpView for WasiState {
     fn table(&mut self) -> &mut ResourceTable {
         &mut self.table
     }
+
+    fn send_request(
+        &mut self,
+        request: http::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        let config = match self.deadline {
+            Some(deadline) => within_deadline(config, deadline),
+            None => config,
+        };
+        match &self.egress {
+            Some(egress) => egress.send_request(request, config),
+            None => Ok(default_send_request(request, config)),
+        }
+    }
 }
 
 impl WasiState {
     /// Build a minimal WASI sandbox for tool execution.
     pub fn new() -> anyhow::Result<Self> {
-        let ctx = WasiCtxBuilder::new()
-            // No filesystem preopens — deny-default
-            // No env vars — components are isolated from host environment
-            .build();
+        Self::with_env(&[])
+    }
+
+    /// Build a WASI sandbox exposing exactly `env` to the component.
+    ///
+    /// The host environment is never inherited; callers are responsible
+    /// for filtering `env` against the component's policy.
+    pub fn with_env(env: &[(String, String)]) -> anyhow::Result<Self> {
+        Self::with_options(env, &CallOptions::default())
+    }
+
+    /// Build a WASI sandbox exposing `env`, with clocks and random sources
+    /// chosen by `options`.
+    pub fn with_options(env: &[(String, String)], options: &CallOptions) -> anyhow::Result<Self> {
+        let mut builder = WasiCtxBuilder::new();
+        // No filesystem preopens — deny-default
+        builder.envs(env);
+
+        if options.deterministic {
+            let time_ms = options.fixed_time_ms.unwrap_or(0);
+            let seed = options.rng_seed.unwrap_or(0);
+            builder
+                .wall_clock(FixedWallClock(Duration::from_millis(time_ms)))
+                .monotonic_clock(SteppingMonotonicClock::default())
+                .secure_random(Deterministic::new(seeded_bytes(seed)))
+                .insecure_random(Deterministic::new(seeded_bytes(seed ^ INSECURE_STREAM)))
+                .insecure_random_seed(u128::from(seed));
+        }
+        let ctx = builder.build();
 
         Ok(Self {
             ctx,
             table: ResourceTable::new(),
             http: WasiHttpCtx::new(),
+            egress: None,
+            limits: UsageLimiter::default(),
+            host_utils: HostUtils::new(options),
+            deadline: None,
         })
     }
+
+    /// Tag the component's `girt:host/utils` log lines with `component_id`.
+    pub fn with_component_id(mut self, component_id: &str) -> Self {
+        self.host_utils = std::mem::take(&mut self.host_utils).with_component_id(component_id);
+        self
+    }
+
+    /// Route the component's outgoing HTTP through `egress`.
+    pub fn with_egress(mut self, egress: EgressProxy) -> Self {
+        self.egress = Some(egress);
+        self
+    }
+
+    /// End the call at `deadline`: outgoing HTTP gives up in time for the
+    /// component to see the error, and `girt:host/utils` reports what is
+    /// left of the budget.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self.host_utils = std::mem::take(&mut self.host_utils).with_deadline(deadline);
+        self
+    }
+
+    /// Stop any of the component's memories growing past `bytes`.
+    pub fn with_max_memory_bytes(mut self, bytes: usize) -> Self {
+        self.limits.limits = StoreLimitsBuilder::new().memory_size(bytes).build();
+        self
+    }
+
+    /// The limits to install with [`wasmtime::Store::limiter`].
+    pub(crate) fn limits(&mut self) -> &mut UsageLimiter {
+        &mut self.limits
+    }
+
+    /// Size the largest of the component's memories has grown to, in bytes.
+    pub fn peak_memory_bytes(&self) -> usize {
+        self.limits.peak_memory_bytes
+    }
+
+    /// Log lines the component sent through `girt:host/utils` past the
+    /// per-call limit.
+    pub fn logs_dropped(&self) -> u32 {
+        self.host_utils.logs_dropped()
+    }
+
+    pub(crate) fn host_utils(&self) -> &HostUtils {
+        &self.host_utils
+    }
+
+    pub(crate) fn host_utils_mut(&mut self) -> &mut HostUtils {
+        &mut self.host_utils
+    }
+}
+
+/// Time an outgoing HTTP request leaves before the call's deadline, so the
+/// component can still handle its timeout error and return.
+pub const DEADLINE_MARGIN: Duration = Duration::from_millis(250);
+
+/// `config` with each timeout cut to what is left before `deadline`, less
+/// [`DEADLINE_MARGIN`]. Timeouts that are already shorter are kept.
+pub fn within_deadline(config: OutgoingRequestConfig, deadline: Instant) -> OutgoingRequestConfig {
+    let budget = deadline
+        .saturating_duration_since(Instant::now())
+        .saturating_sub(DEADLINE_MARGIN);
+    OutgoingRequestConfig {
+        connect_timeout: config.connect_timeout.min(budget),
+        first_byte_timeout: config.first_byte_timeout.min(budget),
+        between_bytes_timeout: config.between_bytes_timeout.min(budget),
+        ..config
+    }
+}
+
+/// [`StoreLimits`] that also record the memory high-water mark.
+#[derive(Default)]
+pub(crate) struct UsageLimiter {
+    limits: StoreLimits,
+    peak_memory_bytes: usize,
+}
+
+impl ResourceLimiter for UsageLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        // Also asked with `current` 0 when a memory is first created.
+        let allowed = self.limits.memory_growing(current, desired, maximum)?;
+        if allowed && maximum.is_none_or(|max| desired <= max) {
+            self.peak_memory_bytes = self.peak_memory_bytes.max(desired);
+        }
+        Ok(allowed)
+    }
+
+    fn memory_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        self.limits.memory_grow_failed(error)
+    }
+
+    fn table_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn table_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        self.limits.table_grow_failed(error)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
 }
 
 impl Default for WasiState {
@@ -60,3 +251,146 @@ impl Default for WasiState {
         Self::new().expect("WasiState::new should not fail")
     }
 }
+
+// ── Deterministic mode ───────────────────────────────────────────────────────
+
+/// Per-invocation options for [`crate::LifecycleManager::call_tool_with_options`].
+///
+/// The default is a normal call: real clocks and host entropy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallOptions {
+    /// Replace the WASI clocks and random sources with fixed/seeded ones so
+    /// repeated calls with the same input produce the same output.
+    pub deterministic: bool,
+    /// Wall-clock time reported in deterministic mode (Unix ms, default 0).
+    pub fixed_time_ms: Option<u64>,
+    /// Seed for the random sources in deterministic mode (default 0).
+    pub rng_seed: Option<u64>,
+}
+
+impl CallOptions {
+    /// Deterministic call with the default fixed time and seed.
+    pub fn deterministic() -> Self {
+        Self {
+            deterministic: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_fixed_time_ms(mut self, time_ms: u64) -> Self {
+        self.fixed_time_ms = Some(time_ms);
+        self
+    }
+
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+}
+
+/// Length of the byte cycle backing the seeded random sources.
+const SEEDED_BYTES_LEN: usize = 4096;
+
+/// Seed perturbation so `insecure-random` doesn't mirror `random`.
+const INSECURE_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Expand `seed` into a fixed byte sequence (SplitMix64).
+pub(crate) fn seeded_bytes(seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    let mut bytes = Vec::with_capacity(SEEDED_BYTES_LEN);
+    while bytes.len() < SEEDED_BYTES_LEN {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        bytes.extend_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+    bytes
+}
+
+/// Wall clock stopped at a fixed instant.
+struct FixedWallClock(Duration);
+
+impl HostWallClock for FixedWallClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_millis(1)
+    }
+
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
+/// Monotonic clock that starts at zero and advances 1ms per read, so
+/// elapsed-time loops still terminate while staying reproducible.
+#[derive(Default)]
+struct SteppingMonotonicClock(AtomicU64);
+
+const MONOTONIC_STEP_NS: u64 = 1_000_000;
+
+impl HostMonotonicClock for SteppingMonotonicClock {
+    fn resolution(&self) -> u64 {
+        MONOTONIC_STEP_NS
+    }
+
+    fn now(&self) -> u64 {
+        self.0.fetch_add(MONOTONIC_STEP_NS, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_bytes_depend_only_on_seed() {
+        assert_eq!(seeded_bytes(7), seeded_bytes(7));
+        assert_ne!(seeded_bytes(7), seeded_bytes(8));
+        assert_eq!(seeded_bytes(0).len(), SEEDED_BYTES_LEN);
+    }
+
+    #[test]
+    fn limiter_records_only_allowed_growth() {
+        let mut limiter = UsageLimiter {
+            limits: StoreLimitsBuilder::new().memory_size(4 << 16).build(),
+            peak_memory_bytes: 0,
+        };
+        assert!(limiter.memory_growing(0, 1 << 16, None).unwrap());
+        assert!(limiter.memory_growing(1 << 16, 3 << 16, None).unwrap());
+        assert!(!limiter.memory_growing(3 << 16, 8 << 16, None).unwrap());
+        assert!(limiter.memory_growing(0, 2 << 16, Some(4 << 16)).unwrap());
+        assert_eq!(limiter.peak_memory_bytes, 3 << 16);
+    }
+
+    #[test]
+    fn http_timeouts_are_cut_to_the_deadline() {
+        let config = OutgoingRequestConfig {
+            use_tls: true,
+            connect_timeout: Duration::from_secs(600),
+            first_byte_timeout: Duration::from_millis(100),
+            between_bytes_timeout: Duration::from_secs(600),
+        };
+        let clamped = within_deadline(config, Instant::now() + Duration::from_secs(2));
+        assert!(clamped.use_tls);
+        assert!(clamped.connect_timeout <= Duration::from_secs(2) - DEADLINE_MARGIN);
+        assert!(clamped.connect_timeout > Duration::from_secs(1));
+        assert_eq!(clamped.first_byte_timeout, Duration::from_millis(100));
+        assert_eq!(clamped.between_bytes_timeout, clamped.connect_timeout);
+
+        let config = OutgoingRequestConfig {
+            use_tls: false,
+            connect_timeout: Duration::from_secs(600),
+            first_byte_timeout: Duration::from_secs(600),
+            between_bytes_timeout: Duration::from_secs(600),
+        };
+        let late = within_deadline(config, Instant::now());
+        assert_eq!(late.connect_timeout, Duration::ZERO);
+    }
+
+    #[test]
+    fn monotonic_clock_steps_per_read() {
+        let clock = SteppingMonotonicClock::default();
+        assert_eq!(clock.now(), 0);
+        assert_eq!(clock.now(), MONOTONIC_STEP_NS);
+    }
+}
@@ -0,0 +1,134 @@
+//! Versions of the `girt:tool` world a component can implement.
+//!
+//! Both versions export `run: func(input: string) -> result<string, E>` and
+//! differ only in the error type `E`:
+//!
+//! - `0.1.0` (`wit/world.wit`): `E` is a human-readable string.
+//! - `0.2.0` (`wit/0.2.0/world.wit`): `E` is the `tool-error` record
+//!   `{ kind: string, message: string, retryable: bool }`.
+//!
+//! The version is read off the component's type when it is loaded, so
+//! tools built against either world can be served side by side.
+
+use std::fmt;
+
+use wasmtime::Engine;
+use wasmtime::component::types::{self, ComponentItem};
+use wasmtime::component::{Component, Type, Val};
+
+use crate::error::RuntimeError;
+
+/// A `girt:tool` world version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WorldVersion {
+    /// `run` errors are plain strings.
+    V0_1,
+    /// `run` errors are `tool-error` records.
+    V0_2,
+}
+
+impl fmt::Display for WorldVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V0_1 => f.write_str("0.1.0"),
+            Self::V0_2 => f.write_str("0.2.0"),
+        }
+    }
+}
+
+impl WorldVersion {
+    /// The world `component` implements, from the error type of its `run`
+    /// export.
+    pub fn detect(component: &Component, engine: &Engine) -> Result<Self, RuntimeError> {
+        let unsupported = |detail: &str| RuntimeError::UnsupportedWorld(detail.to_string());
+
+        let run = match component.component_type().get_export(engine, "run") {
+            Some(ComponentItem::ComponentFunc(run)) => run,
+            Some(_) => return Err(unsupported("'run' export is not a function")),
+            None => return Err(unsupported("no 'run' export")),
+        };
+        let mut results = run.results();
+        let (Some(Type::Result(result)), None) = (results.next(), results.next()) else {
+            return Err(unsupported("'run' does not return a single result"));
+        };
+        match result.err() {
+            Some(Type::String) => Ok(Self::V0_1),
+            Some(Type::Record(record)) if is_tool_error(&record) => Ok(Self::V0_2),
+            _ => Err(unsupported("'run' error type is not string or tool-error")),
+        }
+    }
+
+    /// Map the payload of a `run` error onto a [`RuntimeError`].
+    pub(crate) fn decode_error(self, payload: Option<Val>) -> RuntimeError {
+        match (self, payload) {
+            (_, None) => RuntimeError::ToolError("(no error detail)".into()),
+            (Self::V0_1, Some(Val::String(message))) => RuntimeError::ToolError(message),
+            (Self::V0_2, Some(Val::Record(fields))) => {
+                let mut kind = String::new();
+                let mut message = String::new();
+                let mut retryable = false;
+                for (name, value) in fields {
+                    match (name.as_str(), value) {
+                        ("kind", Val::String(s)) => kind = s,
+                        ("message", Val::String(s)) => message = s,
+                        ("retryable", Val::Bool(b)) => retryable = b,
+                        _ => {}
+                    }
+                }
+                RuntimeError::ToolFailure {
+                    kind,
+                    message,
+                    retryable,
+                }
+            }
+            (_, Some(other)) => RuntimeError::ToolError(format!("{other:?}")),
+        }
+    }
+}
+
+/// Whether `record` has the `tool-error` fields, in order.
+fn is_tool_error(record: &types::Record) -> bool {
+    let fields: Vec<(&str, Type)> = record.fields().map(|f| (f.name, f.ty)).collect();
+    matches!(
+        fields.as_slice(),
+        [
+            ("kind", Type::String),
+            ("message", Type::String),
+            ("retryable", Type::Bool),
+        ]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structured_errors_decode_by_field_name() {
+        let payload = Val::Record(vec![
+            ("kind".into(), Val::String("timeout".into())),
+            ("message".into(), Val::String("no response in 30s".into())),
+            ("retryable".into(), Val::Bool(true)),
+        ]);
+        match WorldVersion::V0_2.decode_error(Some(payload)) {
+            RuntimeError::ToolFailure {
+                kind,
+                message,
+                retryable,
+            } => {
+                assert_eq!(kind, "timeout");
+                assert_eq!(message, "no response in 30s");
+                assert!(retryable);
+            }
+            other => panic!("expected ToolFailure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn string_errors_stay_plain() {
+        let err = WorldVersion::V0_1.decode_error(Some(Val::String("bad input".into())));
+        assert!(matches!(err, RuntimeError::ToolError(m) if m == "bad input"));
+        let err = WorldVersion::V0_2.decode_error(None);
+        assert!(matches!(err, RuntimeError::ToolError(_)));
+    }
+}
@@ -0,0 +1,90 @@
+//! GIRT's own bundled components (`girt_runtime::bundled`), loaded under
+//! their reserved names.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use girt_runtime::bundled::{self, ECHO};
+use girt_runtime::storage::ComponentStorage;
+use girt_runtime::{CallOptions, LifecycleManager, RuntimeError};
+use serde_json::json;
+
+#[tokio::test]
+async fn echo_returns_its_input_and_what_it_sees() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    manager.load_bundled(ECHO.meta()).await.unwrap();
+    assert!(manager.is_bundled("girt_echo").await);
+
+    let args = json!({ "message": "hello", "n": [1, 2, 3] });
+    let options = CallOptions::deterministic().with_fixed_time_ms(1_700_000_000_000);
+    let output = manager
+        .call_tool_with_options("girt_echo", &args, &options)
+        .await
+        .unwrap();
+
+    assert_eq!(output["input"], args);
+    assert_eq!(output["input_bytes"], args.to_string().len());
+    assert_eq!(output["now_ms"], 1_700_000_000_000u64);
+    assert_eq!(output["env"], json!({}));
+}
+
+#[tokio::test]
+async fn echo_reports_env_names_but_not_values() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_tool_env(HashMap::from([(
+            "girt_echo".to_string(),
+            HashMap::from([
+                ("API_TOKEN".to_string(), "s3cret-value".to_string()),
+                ("REGION".to_string(), "eu".to_string()),
+            ]),
+        )]));
+    let mut meta = ECHO.meta();
+    meta.allowed_env = vec!["API_TOKEN".into()];
+    manager.load_bundled(meta).await.unwrap();
+
+    let output = manager.call_tool("girt_echo", &json!({})).await.unwrap();
+    assert_eq!(output["env"], json!({ "API_TOKEN": true }));
+    assert!(!output.to_string().contains("s3cret-value"), "{output}");
+}
+
+#[tokio::test]
+async fn only_bundled_tool_names_load_as_bundled() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+
+    let mut meta = ECHO.meta();
+    meta.tool_name = "girt_shell".into();
+    let err = manager.load_bundled(meta).await.unwrap_err();
+    assert!(matches!(err, RuntimeError::InvalidMetadata(_)), "{err}");
+    assert!(!manager.has_tool("girt_shell").await);
+}
+
+#[tokio::test]
+async fn restored_echo_is_bundled_only_with_its_exact_wasm() {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = ComponentStorage::new(tmp.path().to_path_buf());
+    storage.init().unwrap();
+    storage.store_bytes(ECHO.wasm, &mut ECHO.meta()).unwrap();
+
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    manager.load_persisted().await;
+    assert!(manager.is_bundled("girt_echo").await);
+
+    // The same name over other bytes is just a squatter on a reserved name.
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = ComponentStorage::new(tmp.path().to_path_buf());
+    storage.init().unwrap();
+    let other =
+        std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/echo.wasm"))
+            .unwrap();
+    assert!(bundled::identify("girt_echo", &other).is_none());
+    storage.store_bytes(&other, &mut ECHO.meta()).unwrap();
+
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    manager.load_persisted().await;
+    assert!(manager.has_tool("girt_echo").await);
+    assert!(!manager.is_bundled("girt_echo").await);
+}
@@ -0,0 +1,251 @@
+//! The host egress proxy against a local HTTP server.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use girt_core::spec::NetworkGrant;
+use girt_runtime::egress::EgressProxy;
+use girt_runtime::wasistate::within_deadline;
+use http_body_util::{BodyExt, Empty};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::body::HyperOutgoingBody;
+use wasmtime_wasi_http::types::OutgoingRequestConfig;
+
+/// Size of the `/big` response body.
+const BIG_BODY: usize = 10_000;
+
+/// Answers `/echo` with the request head it received, and anything else
+/// with `BIG_BODY` bytes.
+struct TestServer {
+    port: u16,
+    connections: Arc<AtomicUsize>,
+}
+
+impl TestServer {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let body = if head.starts_with(b"GET /echo ") {
+                        head
+                    } else {
+                        vec![b'x'; BIG_BODY]
+                    };
+                    let status = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(status.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                });
+            }
+        });
+        Self { port, connections }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{path}", self.port)
+    }
+}
+
+fn get(url: &str) -> http::Request<HyperOutgoingBody> {
+    let body = Empty::<Bytes>::new()
+        .map_err(|never| -> ErrorCode { match never {} })
+        .boxed();
+    http::Request::get(url).body(body).unwrap()
+}
+
+fn config() -> OutgoingRequestConfig {
+    OutgoingRequestConfig {
+        use_tls: false,
+        connect_timeout: Duration::from_secs(5),
+        first_byte_timeout: Duration::from_secs(5),
+        between_bytes_timeout: Duration::from_secs(5),
+    }
+}
+
+#[tokio::test]
+async fn allowed_host_is_forwarded_and_logged() {
+    let server = TestServer::start().await;
+    let allowed = vec![format!("127.0.0.1:{}", server.port).into()];
+    let proxy = EgressProxy::new("fetch_url", allowed, None);
+
+    let Ok(response) = proxy.send(get(&server.url("/echo")), config()).await else {
+        panic!("allowed request failed");
+    };
+    assert_eq!(response.resp.status(), 200);
+    let body = response
+        .resp
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes();
+
+    // The server saw the standard headers.
+    let head = String::from_utf8_lossy(&body).to_ascii_lowercase();
+    assert!(head.contains("x-girt-tool: fetch_url"), "{head}");
+    assert!(head.contains("user-agent: girt/"), "{head}");
+    assert!(
+        head.contains(&format!("host: 127.0.0.1:{}", server.port)),
+        "{head}"
+    );
+
+    let records = proxy.take_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].method, "GET");
+    assert_eq!(records[0].host, "127.0.0.1");
+    assert_eq!(records[0].status, Some(200));
+    assert_eq!(records[0].bytes, body.len() as u64);
+    assert_eq!(records[0].error, None);
+}
+
+#[tokio::test]
+async fn host_off_the_allowlist_is_blocked() {
+    let server = TestServer::start().await;
+    let proxy = EgressProxy::new("fetch_url", vec!["api.example.com".into()], None);
+
+    let result = proxy.send(get(&server.url("/echo")), config()).await;
+    assert!(matches!(result, Err(ErrorCode::HttpRequestDenied)));
+    assert_eq!(server.connections.load(Ordering::SeqCst), 0);
+
+    let records = proxy.take_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].status, None);
+    let error = records[0].error.as_deref().unwrap();
+    assert!(error.contains("not on the network allowlist"), "{error}");
+}
+
+#[tokio::test]
+async fn requests_outside_a_scoped_grant_are_blocked() {
+    let server = TestServer::start().await;
+    let grant = NetworkGrant::scoped(
+        format!("127.0.0.1:{}", server.port),
+        vec!["GET".into()],
+        vec!["/echo".into()],
+    );
+    let proxy = EgressProxy::new("fetch_url", vec![grant], None);
+
+    assert!(
+        proxy
+            .send(get(&server.url("/echo")), config())
+            .await
+            .is_ok()
+    );
+    let mut post = get(&server.url("/echo"));
+    *post.method_mut() = http::Method::POST;
+    for request in [
+        get(&server.url("/big")),
+        get(&server.url("/echo/../big")),
+        post,
+    ] {
+        let result = proxy.send(request, config()).await;
+        assert!(matches!(result, Err(ErrorCode::HttpRequestDenied)));
+    }
+    assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+
+    let records = proxy.take_records();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0].error, None);
+    let error = records[1].error.as_deref().unwrap();
+    assert_eq!(error, "GET /big is outside the scope granted for 127.0.0.1");
+    let error = records[3].error.as_deref().unwrap();
+    assert!(
+        error.starts_with("POST /echo is outside the scope"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn oversized_body_is_truncated_with_an_error() {
+    let server = TestServer::start().await;
+    let allowed = vec![format!("127.0.0.1:{}", server.port).into()];
+    let proxy = EgressProxy::new("fetch_url", allowed, Some(1024));
+
+    let Ok(response) = proxy.send(get(&server.url("/big")), config()).await else {
+        panic!("allowed request failed");
+    };
+    let mut body = response.resp.into_body();
+    let mut received = 0;
+    let error = loop {
+        match body.frame().await {
+            Some(Ok(frame)) => received += frame.into_data().map(|d| d.len()).unwrap_or(0),
+            Some(Err(code)) => break code,
+            None => panic!("body ended without an error after {received} bytes"),
+        }
+    };
+    assert_eq!(received, 1024);
+    assert!(matches!(error, ErrorCode::HttpResponseBodySize(Some(n)) if n > 1024));
+    drop(body);
+
+    let records = proxy.take_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].status, Some(200));
+    assert_eq!(records[0].bytes, 1024);
+    let error = records[0].error.as_deref().unwrap();
+    assert!(error.contains("max_response_bytes"), "{error}");
+}
+
+/// Accepts connections and never answers them.
+async fn silent_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn requests_time_out_before_the_call_deadline() {
+    let port = silent_server().await;
+    let proxy = EgressProxy::new("slow_api", vec![format!("127.0.0.1:{port}").into()], None);
+    // What a guest gets without setting any timeouts of its own.
+    let upstream_default = OutgoingRequestConfig {
+        use_tls: false,
+        connect_timeout: Duration::from_secs(600),
+        first_byte_timeout: Duration::from_secs(600),
+        between_bytes_timeout: Duration::from_secs(600),
+    };
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(2);
+    let request = proxy.send(
+        get(&format!("http://127.0.0.1:{port}/forecast")),
+        within_deadline(upstream_default, deadline),
+    );
+    let Ok(result) = tokio::time::timeout_at(deadline.into(), request).await else {
+        panic!("the call deadline passed before the request timed out");
+    };
+    assert!(
+        matches!(result, Err(ErrorCode::ConnectionReadTimeout)),
+        "{:?}",
+        result.err()
+    );
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    let records = proxy.take_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].status, None);
+    assert!(records[0].error.is_some());
+}
@@ -0,0 +1,190 @@
+//! `[runtime]` engine settings take effect on the engine that runs tools.
+//!
+//! Uses `tests/fixtures/grow_memory.wasm`, a prebuilt component whose `run`
+//! grows its memory by 2 MiB and fails with "memory.grow failed" when it
+//! cannot (source: `grow_memory.wat`).
+
+use std::path::{Path, PathBuf};
+
+use girt_runtime::runtime_context::RuntimeContext;
+use girt_runtime::wasistate::WasiState;
+use girt_runtime::{CallOptions, ComponentMeta, EngineConfig, LifecycleManager, RuntimeError};
+use serde_json::json;
+use wasmtime::Store;
+use wasmtime::component::Component;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{name}.wasm"))
+}
+
+async fn load_fixture(manager: &LifecycleManager, name: &str) {
+    let meta = ComponentMeta {
+        component_id: format!("{name}@0.1.0"),
+        tool_name: name.into(),
+        description: "Engine config fixture".into(),
+        input_schema: json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    };
+    manager.load_component(&fixture(name), meta).await.unwrap();
+}
+
+fn manager(tmp: &tempfile::TempDir, config: &EngineConfig) -> LifecycleManager {
+    LifecycleManager::with_engine_config(Some(tmp.path().to_path_buf()), config).unwrap()
+}
+
+#[tokio::test]
+async fn default_engine_lets_tools_grow_memory() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = manager(&tmp, &EngineConfig::default());
+    load_fixture(&manager, "grow_memory").await;
+
+    let result = manager.call_tool("grow_memory", &json!({})).await.unwrap();
+    assert_eq!(result, json!({}));
+}
+
+#[tokio::test]
+async fn memory_cannot_grow_past_max_memory_mb() {
+    for pooling_allocator in [false, true] {
+        let tmp = tempfile::tempdir().unwrap();
+        let manager = manager(
+            &tmp,
+            &EngineConfig {
+                pooling_allocator,
+                max_memory_mb: 1,
+                ..EngineConfig::default()
+            },
+        );
+        load_fixture(&manager, "grow_memory").await;
+
+        let err = manager
+            .call_tool("grow_memory", &json!({}))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, RuntimeError::ToolError(m) if m.contains("memory.grow failed")),
+            "pooling {pooling_allocator}: {err:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn epoch_ticks_do_not_interrupt_calls() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = manager(
+        &tmp,
+        &EngineConfig {
+            epoch_tick_ms: 1,
+            ..EngineConfig::default()
+        },
+    );
+    load_fixture(&manager, "grow_memory").await;
+
+    for _ in 0..3 {
+        let result = manager.call_tool("grow_memory", &json!({})).await.unwrap();
+        assert_eq!(result, json!({}));
+    }
+}
+
+#[tokio::test]
+async fn calls_report_their_memory_high_water_mark() {
+    for pooling_allocator in [false, true] {
+        let tmp = tempfile::tempdir().unwrap();
+        let manager = manager(
+            &tmp,
+            &EngineConfig {
+                pooling_allocator,
+                max_memory_mb: 4,
+                ..EngineConfig::default()
+            },
+        );
+        load_fixture(&manager, "grow_memory").await;
+
+        let (result, trace) = manager
+            .call_tool_traced("grow_memory", &json!({}), &CallOptions::default())
+            .await;
+        assert_eq!(result.unwrap(), json!({}));
+        // The initial page plus the 32 the call grows by.
+        assert_eq!(trace.resources.peak_memory_bytes, 33 * 64 * 1024);
+        assert_eq!(trace.resources.memory_limit_bytes, 4 * 1024 * 1024);
+        assert_eq!(trace.resources.fuel_consumed, None);
+    }
+}
+
+#[tokio::test]
+async fn fuel_is_reported_with_metering_on() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = manager(
+        &tmp,
+        &EngineConfig {
+            fuel_metering: true,
+            ..EngineConfig::default()
+        },
+    );
+    load_fixture(&manager, "grow_memory").await;
+
+    let (result, trace) = manager
+        .call_tool_traced("grow_memory", &json!({}), &CallOptions::default())
+        .await;
+    assert_eq!(result.unwrap(), json!({}));
+    assert!(
+        trace.resources.fuel_consumed.is_some_and(|fuel| fuel > 0),
+        "{:?}",
+        trace.resources
+    );
+}
+
+#[tokio::test]
+async fn pooling_allocator_caps_live_instances() {
+    let runtime = RuntimeContext::with_config(&EngineConfig {
+        pooling_allocator: true,
+        max_instances: 1,
+        ..EngineConfig::default()
+    })
+    .unwrap();
+    let component = Component::from_file(&runtime.engine, fixture("grow_memory")).unwrap();
+    let pre = runtime.linker.instantiate_pre(&component).unwrap();
+
+    let mut first = Store::new(&runtime.engine, WasiState::new().unwrap());
+    pre.instantiate_async(&mut first).await.unwrap();
+    let mut second = Store::new(&runtime.engine, WasiState::new().unwrap());
+    assert!(pre.instantiate_async(&mut second).await.is_err());
+
+    drop(first);
+    let mut third = Store::new(&runtime.engine, WasiState::new().unwrap());
+    pre.instantiate_async(&mut third).await.unwrap();
+}
+
+#[test]
+fn unusable_settings_are_rejected() {
+    let zero_memory = EngineConfig {
+        max_memory_mb: 0,
+        ..EngineConfig::default()
+    };
+    assert!(RuntimeContext::with_config(&zero_memory).is_err());
+
+    let no_instances = EngineConfig {
+        pooling_allocator: true,
+        max_instances: 0,
+        ..EngineConfig::default()
+    };
+    assert!(RuntimeContext::with_config(&no_instances).is_err());
+}
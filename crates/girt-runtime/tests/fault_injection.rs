@@ -0,0 +1,140 @@
+//! Fault injection on tool calls (`[runtime.fault_injection]`).
+//!
+//! Uses `tests/fixtures/clock_random.wasm`, which returns
+//! `{"now_ms": <wall clock>, "random": <u64>}`.
+
+use std::path::Path;
+use std::time::Duration;
+
+use girt_core::faults::{FaultInjector, FaultKind};
+use girt_runtime::{ComponentMeta, LifecycleManager, RuntimeError};
+use serde_json::json;
+
+async fn load_clock_random(manager: &LifecycleManager) {
+    load_clock_random_with(manager, None).await;
+}
+
+async fn load_clock_random_with(manager: &LifecycleManager, timeout_ms: Option<u64>) {
+    let meta = ComponentMeta {
+        component_id: "clock_random@0.1.0".into(),
+        tool_name: "clock_random".into(),
+        description: "Report the current time and a random number".into(),
+        input_schema: json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    };
+    let wasm = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/clock_random.wasm");
+    manager.load_component(&wasm, meta).await.unwrap();
+}
+
+#[tokio::test]
+async fn injected_faults_replace_call_results() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_fault_injector(FaultInjector::new(1.0, vec![FaultKind::Garbage], 1));
+    load_clock_random(&manager).await;
+
+    // Numbers come back as strings.
+    let garbled = manager.call_tool("clock_random", &json!({})).await.unwrap();
+    assert!(garbled["now_ms"].is_string(), "{garbled}");
+    assert!(garbled["random"].is_string(), "{garbled}");
+
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_fault_injector(FaultInjector::new(1.0, vec![FaultKind::Error], 1));
+    load_clock_random(&manager).await;
+    let err = manager
+        .call_tool("clock_random", &json!({}))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, RuntimeError::ToolError(msg) if msg.contains("injected fault")),
+        "{err}"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn injected_timeout_takes_the_whole_time_limit() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_fault_injector(FaultInjector::new(1.0, vec![FaultKind::Timeout], 1));
+    load_clock_random_with(&manager, Some(30_000)).await;
+
+    let started = tokio::time::Instant::now();
+    let err = manager
+        .call_tool("clock_random", &json!({}))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            RuntimeError::Timeout {
+                timeout_ms: 30_000,
+                ..
+            }
+        ),
+        "{err}"
+    );
+    assert!(started.elapsed() >= Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn garbage_injected_into_a_failed_call_is_an_error() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_fault_injector(FaultInjector::new(1.0, vec![FaultKind::Garbage], 1));
+    load_clock_random(&manager).await;
+
+    // The tool is not loaded, so the call fails before any response exists.
+    let err = manager.call_tool("missing", &json!({})).await.unwrap_err();
+    assert!(
+        matches!(&err, RuntimeError::ToolError(msg) if msg.contains("garbled response")),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn seeded_faults_repeat_across_runs() {
+    let outcomes = |seed| async move {
+        let tmp = tempfile::tempdir().unwrap();
+        let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+            .unwrap()
+            .with_fault_injector(FaultInjector::new(0.5, FaultKind::ALL.to_vec(), seed));
+        // Injected timeouts take the whole time limit.
+        load_clock_random_with(&manager, Some(200)).await;
+        let mut outcomes = Vec::new();
+        for _ in 0..20 {
+            let outcome = match manager.call_tool("clock_random", &json!({})).await {
+                Ok(value) if value["now_ms"].is_number() => "ok",
+                Ok(_) => "garbage",
+                Err(_) => "error",
+            };
+            outcomes.push(outcome);
+        }
+        outcomes
+    };
+
+    let first = outcomes(3).await;
+    assert_eq!(first, outcomes(3).await);
+    assert!(first.contains(&"ok") && first.iter().any(|o| *o != "ok"));
+}
@@ -0,0 +1,95 @@
+;; girt-tool component that reports the wall clock and a random number:
+;; `run(_) -> ok({"now_ms": <wall-clock ms>, "random": <u64>})`.
+;;
+;; Source for clock_random.wasm, used by the deterministic-call tests.
+;; Regenerate with: wasm-tools parse clock_random.wat -o clock_random.wasm
+(component
+  (import "wasi:clocks/wall-clock@0.2.0" (instance $wall
+    (type $datetime' (record (field "seconds" u64) (field "nanoseconds" u32)))
+    (export "datetime" (type $datetime (eq $datetime')))
+    (export "now" (func (result $datetime)))
+  ))
+  (import "wasi:random/random@0.2.0" (instance $random
+    (export "get-random-u64" (func (result u64)))
+  ))
+
+  ;; Memory and allocator live in their own instance so the imports can be
+  ;; lowered against them before the main module is instantiated.
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (alias export $wall "now" (func $now))
+  (alias export $random "get-random-u64" (func $get_random_u64))
+  (core func $now_lowered (canon lower (func $now) (memory $libc "memory")))
+  (core func $random_lowered (canon lower (func $get_random_u64)))
+  (core instance $host
+    (export "now" (func $now_lowered))
+    (export "random" (func $random_lowered)))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "host" "now" (func $now (param i32)))
+    (import "host" "random" (func $random (result i64)))
+
+    (data (i32.const 512) "{\"now_ms\":")
+    (data (i32.const 528) ",\"random\":")
+    (data (i32.const 544) "}")
+
+    ;; Write `v` in decimal at `p`, returning the end pointer.
+    (func $write_u64 (param $v i64) (param $p i32) (result i32)
+      (local $t i32)
+      (local $n i32)
+      (local.set $t (i32.const 960))
+      (loop $digits
+        (local.set $t (i32.sub (local.get $t) (i32.const 1)))
+        (i32.store8 (local.get $t)
+          (i32.add (i32.const 48)
+            (i32.wrap_i64 (i64.rem_u (local.get $v) (i64.const 10)))))
+        (local.set $v (i64.div_u (local.get $v) (i64.const 10)))
+        (br_if $digits (i64.ne (local.get $v) (i64.const 0))))
+      (local.set $n (i32.sub (i32.const 960) (local.get $t)))
+      (memory.copy (local.get $p) (local.get $t) (local.get $n))
+      (i32.add (local.get $p) (local.get $n)))
+
+    (func (export "run") (param i32 i32) (result i32)
+      (local $p i32)
+      (local $now_ms i64)
+      ;; datetime { seconds: u64 @64, nanoseconds: u32 @72 }
+      (call $now (i32.const 64))
+      (local.set $now_ms
+        (i64.add
+          (i64.mul (i64.load (i32.const 64)) (i64.const 1000))
+          (i64.extend_i32_u
+            (i32.div_u (i32.load (i32.const 72)) (i32.const 1000000)))))
+
+      (memory.copy (i32.const 2048) (i32.const 512) (i32.const 10))
+      (local.set $p (call $write_u64 (local.get $now_ms) (i32.const 2058)))
+      (memory.copy (local.get $p) (i32.const 528) (i32.const 10))
+      (local.set $p (call $write_u64 (call $random) (i32.add (local.get $p) (i32.const 10))))
+      (memory.copy (local.get $p) (i32.const 544) (i32.const 1))
+      (local.set $p (i32.add (local.get $p) (i32.const 1)))
+
+      ;; result<string, string>: { tag @16, ptr @20, len @24 }
+      (i32.store8 (i32.const 16) (i32.const 0))
+      (i32.store (i32.const 20) (i32.const 2048))
+      (i32.store (i32.const 24) (i32.sub (local.get $p) (i32.const 2048)))
+      (i32.const 16))
+  )
+  (core instance $i (instantiate $m
+    (with "libc" (instance $libc))
+    (with "host" (instance $host))))
+
+  (func (export "run") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $i "run") (memory $libc "memory") (realloc (func $libc "realloc"))))
+)
@@ -0,0 +1,42 @@
+;; girt:tool 0.2.0 component that always fails with a structured error:
+;; `run(input) -> err({kind: "upstream", message: input, retryable: true})`.
+;;
+;; Source for fail_v0_2.wasm, used by world_versions.rs.
+;; Regenerate with: wasm-tools parse fail_v0_2.wat -o fail_v0_2.wasm
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (data (i32.const 64) "upstream")
+
+    ;; Bump allocator; the component is instantiated fresh for every call.
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+
+    ;; result<string, tool-error> is returned via a pointer to
+    ;; { tag: u8 @0, kind: (i32, i32) @4, message: (i32, i32) @12,
+    ;;   retryable: u8 @20 }; tag 1 is `err`.
+    (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+      (i32.store8 (i32.const 16) (i32.const 1))
+      (i32.store (i32.const 20) (i32.const 64))
+      (i32.store (i32.const 24) (i32.const 8))
+      (i32.store (i32.const 28) (local.get $ptr))
+      (i32.store (i32.const 32) (local.get $len))
+      (i32.store8 (i32.const 36) (i32.const 1))
+      (i32.const 16))
+  )
+  (core instance $i (instantiate $m))
+  (type $tool-error' (record
+    (field "kind" string)
+    (field "message" string)
+    (field "retryable" bool)))
+  (export $tool-error "tool-error" (type $tool-error'))
+  (func (export "run") (param "input" string) (result (result string (error $tool-error)))
+    (canon lift (core func $i "run") (memory $i "memory") (realloc (func $i "realloc"))))
+)
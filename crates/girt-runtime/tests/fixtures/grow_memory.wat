@@ -0,0 +1,40 @@
+;; girt:tool 0.1.0 component that grows its memory by 2 MiB on each call:
+;; `run(_) -> ok("{}")`, or `err("memory.grow failed")` when it cannot.
+;;
+;; Source for grow_memory.wasm, used by engine_config.rs.
+;; Regenerate with: wasm-tools parse grow_memory.wat -o grow_memory.wasm
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (data (i32.const 64) "{}")
+    (data (i32.const 80) "memory.grow failed")
+
+    ;; Bump allocator; the component is instantiated fresh for every call.
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+
+    ;; result<string, string> is returned via a pointer to
+    ;; { tag: u8 @0, ptr: i32 @4, len: i32 @8 }; tag 1 is `err`.
+    (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+      (if (i32.eq (memory.grow (i32.const 32)) (i32.const -1))
+        (then
+          (i32.store8 (i32.const 16) (i32.const 1))
+          (i32.store (i32.const 20) (i32.const 80))
+          (i32.store (i32.const 24) (i32.const 18)))
+        (else
+          (i32.store8 (i32.const 16) (i32.const 0))
+          (i32.store (i32.const 20) (i32.const 64))
+          (i32.store (i32.const 24) (i32.const 2))))
+      (i32.const 16))
+  )
+  (core instance $i (instantiate $m))
+  (func (export "run") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $i "run") (memory $i "memory") (realloc (func $i "realloc"))))
+)
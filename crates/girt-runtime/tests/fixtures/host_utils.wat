@@ -0,0 +1,104 @@
+;; girt-tool component that uses every girt:host/utils import: it logs one
+;; "info" line per byte of its input, then returns
+;; `ok({"now_ms": <now-ms>, "uuid": "<uuid-v4>"})`.
+;;
+;; Source for host_utils.wasm, used by the host utils tests.
+;; Regenerate with: wasm-tools parse host_utils.wat -o host_utils.wasm
+(component
+  (import "girt:host/utils@0.1.0" (instance $utils
+    (export "now-ms" (func (result u64)))
+    (export "uuid-v4" (func (result string)))
+    (export "log" (func (param "level" string) (param "message" string)))
+  ))
+
+  ;; Memory and allocator live in their own instance so the imports can be
+  ;; lowered against them before the main module is instantiated.
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (alias export $utils "now-ms" (func $now_ms))
+  (alias export $utils "uuid-v4" (func $uuid_v4))
+  (alias export $utils "log" (func $log))
+  (core func $now_ms_lowered (canon lower (func $now_ms)))
+  (core func $uuid_v4_lowered
+    (canon lower (func $uuid_v4) (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (core func $log_lowered (canon lower (func $log) (memory $libc "memory")))
+  (core instance $host
+    (export "now-ms" (func $now_ms_lowered))
+    (export "uuid-v4" (func $uuid_v4_lowered))
+    (export "log" (func $log_lowered)))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "host" "now-ms" (func $now_ms (result i64)))
+    (import "host" "uuid-v4" (func $uuid_v4 (param i32)))
+    (import "host" "log" (func $log (param i32 i32 i32 i32)))
+
+    (data (i32.const 512) "{\"now_ms\":")
+    (data (i32.const 528) ",\"uuid\":\"")
+    (data (i32.const 544) "\"}")
+    (data (i32.const 560) "info")
+    (data (i32.const 568) "line")
+
+    ;; Write `v` in decimal at `p`, returning the end pointer.
+    (func $write_u64 (param $v i64) (param $p i32) (result i32)
+      (local $t i32)
+      (local $n i32)
+      (local.set $t (i32.const 960))
+      (loop $digits
+        (local.set $t (i32.sub (local.get $t) (i32.const 1)))
+        (i32.store8 (local.get $t)
+          (i32.add (i32.const 48)
+            (i32.wrap_i64 (i64.rem_u (local.get $v) (i64.const 10)))))
+        (local.set $v (i64.div_u (local.get $v) (i64.const 10)))
+        (br_if $digits (i64.ne (local.get $v) (i64.const 0))))
+      (local.set $n (i32.sub (i32.const 960) (local.get $t)))
+      (memory.copy (local.get $p) (local.get $t) (local.get $n))
+      (i32.add (local.get $p) (local.get $n)))
+
+    (func (export "run") (param i32 i32) (result i32)
+      (local $p i32)
+      (local $i i32)
+      (block $logged
+        (loop $lines
+          (br_if $logged (i32.ge_u (local.get $i) (local.get 1)))
+          (call $log (i32.const 560) (i32.const 4) (i32.const 568) (i32.const 4))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $lines)))
+
+      ;; string { ptr @80, len @84 }
+      (call $uuid_v4 (i32.const 80))
+
+      (memory.copy (i32.const 2048) (i32.const 512) (i32.const 10))
+      (local.set $p (call $write_u64 (call $now_ms) (i32.const 2058)))
+      (memory.copy (local.get $p) (i32.const 528) (i32.const 9))
+      (local.set $p (i32.add (local.get $p) (i32.const 9)))
+      (memory.copy (local.get $p) (i32.load (i32.const 80)) (i32.load (i32.const 84)))
+      (local.set $p (i32.add (local.get $p) (i32.load (i32.const 84))))
+      (memory.copy (local.get $p) (i32.const 544) (i32.const 2))
+      (local.set $p (i32.add (local.get $p) (i32.const 2)))
+
+      ;; result<string, string>: { tag @16, ptr @20, len @24 }
+      (i32.store8 (i32.const 16) (i32.const 0))
+      (i32.store (i32.const 20) (i32.const 2048))
+      (i32.store (i32.const 24) (i32.sub (local.get $p) (i32.const 2048)))
+      (i32.const 16))
+  )
+  (core instance $i (instantiate $m
+    (with "libc" (instance $libc))
+    (with "host" (instance $host))))
+
+  (func (export "run") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $i "run") (memory $libc "memory") (realloc (func $libc "realloc"))))
+)
@@ -0,0 +1,111 @@
+//! The `girt:host/utils` imports: clock, UUIDs, and rate-limited logging.
+//!
+//! Uses `tests/fixtures/host_utils.wasm`, a prebuilt component that logs one
+//! line per byte of its input and returns `{"now_ms": <now-ms>, "uuid":
+//! <uuid-v4>}` (source: `host_utils.wat`).
+
+use std::path::Path;
+
+use girt_runtime::host_utils::MAX_LOG_LINES_PER_CALL;
+use girt_runtime::{CallOptions, ComponentMeta, LifecycleManager};
+use serde_json::{Value, json};
+
+async fn load_host_utils(tmp: &tempfile::TempDir) -> LifecycleManager {
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    let meta = ComponentMeta {
+        component_id: "host_utils@0.1.0".into(),
+        tool_name: "host_utils".into(),
+        description: "Report the time and a fresh id, logging along the way".into(),
+        input_schema: json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    };
+    let wasm = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/host_utils.wasm");
+    manager.load_component(&wasm, meta).await.unwrap();
+    manager
+}
+
+fn is_uuid_v4(value: &Value) -> bool {
+    let text = value.as_str().unwrap_or_default();
+    let groups: Vec<usize> = text.split('-').map(str::len).collect();
+    groups == [8, 4, 4, 4, 12] && text.as_bytes()[14] == b'4'
+}
+
+#[tokio::test]
+async fn imports_report_the_clock_and_fresh_uuids() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = load_host_utils(&tmp).await;
+
+    let first = manager.call_tool("host_utils", &json!({})).await.unwrap();
+    let second = manager.call_tool("host_utils", &json!({})).await.unwrap();
+
+    // Any real clock is well past 2020-01-01.
+    assert!(first["now_ms"].as_u64().unwrap() > 1_577_836_800_000);
+    assert!(is_uuid_v4(&first["uuid"]), "{first}");
+    assert_ne!(first["uuid"], second["uuid"]);
+}
+
+#[tokio::test]
+async fn deterministic_calls_fix_the_clock_and_uuid() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = load_host_utils(&tmp).await;
+    let options = CallOptions::deterministic()
+        .with_fixed_time_ms(1_700_000_000_000)
+        .with_rng_seed(42);
+
+    let args = json!({});
+    let call = || manager.call_tool_with_options("host_utils", &args, &options);
+    let first = call().await.unwrap();
+    let second = call().await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(first["now_ms"], 1_700_000_000_000u64);
+    assert!(is_uuid_v4(&first["uuid"]), "{first}");
+}
+
+#[tokio::test]
+async fn log_lines_past_the_per_call_limit_are_dropped() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = load_host_utils(&tmp).await;
+
+    // `{}` is two bytes of input, so two lines.
+    let (result, trace) = manager
+        .call_tool_traced("host_utils", &json!({}), &CallOptions::default())
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(trace.logs_dropped, 0);
+    let json = serde_json::to_value(&trace).unwrap();
+    assert!(json.get("logs_dropped").is_none(), "{json}");
+
+    // A JSON string input is its characters plus two quotes.
+    let chatty = json!("x".repeat(MAX_LOG_LINES_PER_CALL as usize + 8));
+    let (result, trace) = manager
+        .call_tool_traced("host_utils", &chatty, &CallOptions::default())
+        .await;
+    assert!(is_uuid_v4(&result.unwrap()["uuid"]));
+    assert_eq!(trace.logs_dropped, 10);
+}
+
+#[test]
+fn builds_scaffold_the_interface_the_runtime_provides() {
+    assert_eq!(
+        girt_pipeline::compiler::HOST_UTILS_WIT,
+        include_str!("../wit/host/utils.wit")
+    );
+}
@@ -1,14 +1,15 @@
-/// End-to-end integration tests for girt-runtime.
-///
-/// These tests compile real WASM components with cargo-component and execute
-/// them through the LifecycleManager. They require:
-///   - `cargo-component` installed (`cargo install cargo-component`)
-///   - `wasm32-wasip1` target installed (`rustup target add wasm32-wasip1`)
-///
-/// Run with: `cargo test -p girt-runtime --test integration_test -- --include-ignored`
+//! End-to-end integration tests for girt-runtime.
+//!
+//! These tests compile real WASM components with cargo-component and execute
+//! them through the LifecycleManager. They require:
+//!   - `cargo-component` installed (`cargo install cargo-component`)
+//!   - `wasm32-wasip1` target installed (`rustup target add wasm32-wasip1`)
+//!
+//! Run with: `cargo test -p girt-runtime --test integration_test -- --include-ignored`
 
 use girt_runtime::{ComponentMeta, LifecycleManager};
 use girt_pipeline::compiler::{CompileInput, WasmCompiler};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Celsius-to-Fahrenheit converter — the canonical GIRT smoke test tool.
@@ -48,6 +49,7 @@ async fn smoke_test_celsius_to_fahrenheit() {
         wit_definition: String::new(), // uses default girt-tool world
         tool_name: "celsius_to_fahrenheit".into(),
         tool_version: "0.1.0".into(),
+        files: vec![],
     };
 
     let compiled = compiler.compile(&input).await
@@ -73,8 +75,24 @@ async fn smoke_test_celsius_to_fahrenheit() {
             "required": ["celsius"]
         }),
         wasm_hash: String::new(),
+        wasm_size_bytes: 0,
         built_at: SystemTime::now()
             .duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
     };
 
     manager.load_component(&compiled.wasm_path, meta).await
@@ -86,6 +104,12 @@ async fn smoke_test_celsius_to_fahrenheit() {
     assert_eq!(tools[0].tool_name, "celsius_to_fahrenheit");
     println!("list_tools: {} tool(s)", tools.len());
 
+    // 3b. Verify integrity metadata was back-filled from the stored file
+    let stored_wasm = tmp.path().join("celsius_to_fahrenheit@0.1.0.wasm");
+    assert!(!tools[0].wasm_hash.is_empty(), "wasm_hash should be filled on load");
+    assert_eq!(tools[0].wasm_hash, girt_runtime::storage::hash_wasm(&stored_wasm).unwrap());
+    assert_eq!(tools[0].wasm_size_bytes, std::fs::metadata(&stored_wasm).unwrap().len());
+
     // 4. Call the tool
     let args = serde_json::json!({ "celsius": 100.0 });
     let result = manager.call_tool("celsius_to_fahrenheit", &args).await
@@ -124,6 +148,87 @@ async fn smoke_test_celsius_to_fahrenheit() {
     println!("All smoke test assertions passed.");
 }
 
+/// Echoes the component's environment back as a JSON object.
+const ECHO_ENV_SRC: &str = r#"
+#[allow(warnings)]
+mod bindings;
+
+use bindings::Guest;
+
+struct Component;
+
+impl Guest for Component {
+    fn run(_input: String) -> Result<String, String> {
+        let env: serde_json::Map<String, serde_json::Value> = std::env::vars()
+            .map(|(k, v)| (k, serde_json::Value::String(v)))
+            .collect();
+        Ok(serde_json::Value::Object(env).to_string())
+    }
+}
+
+bindings::export!(Component with_types_in bindings);
+"#;
+
+#[tokio::test]
+#[ignore = "requires cargo-component and wasm32-wasip1 target"]
+async fn tool_env_injects_only_policy_allowed_keys() {
+    let compiler = WasmCompiler::new();
+    let compiled = compiler
+        .compile(&CompileInput {
+            source_code: ECHO_ENV_SRC.into(),
+            wit_definition: String::new(),
+            tool_name: "echo_env".into(),
+            tool_version: "0.1.0".into(),
+            files: vec![],
+        })
+        .await
+        .expect("WasmCompiler::compile failed — is cargo-component installed?");
+
+    let tool_env = HashMap::from([(
+        "echo_env".to_string(),
+        HashMap::from([
+            ("GITLAB_BASE_URL".to_string(), "https://gitlab.example.com".to_string()),
+            ("NOT_WHITELISTED".to_string(), "leak".to_string()),
+        ]),
+    )]);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_tool_env(tool_env);
+
+    let meta = ComponentMeta {
+        component_id: "echo_env@0.1.0".into(),
+        tool_name: "echo_env".into(),
+        description: "Echo the component environment".into(),
+        input_schema: serde_json::json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec!["GITLAB_BASE_URL".into()],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    };
+    manager.load_component(&compiled.wasm_path, meta).await.unwrap();
+
+    let env = manager.call_tool("echo_env", &serde_json::json!({})).await.unwrap();
+    assert_eq!(env["GITLAB_BASE_URL"], "https://gitlab.example.com");
+    assert!(env.get("NOT_WHITELISTED").is_none(), "non-whitelisted key leaked: {env}");
+    assert!(env.get("PATH").is_none(), "host environment leaked: {env}");
+}
+
 #[tokio::test]
 #[ignore = "requires cargo-component and wasm32-wasip1 target"]
 async fn smoke_test_tool_not_found_returns_error() {
@@ -0,0 +1,78 @@
+//! Tools named after GIRT built-ins (`girt_core::reserved`).
+//!
+//! Uses `tests/fixtures/clock_random.wasm`; only its name matters here.
+
+use std::path::{Path, PathBuf};
+
+use girt_runtime::storage::ComponentStorage;
+use girt_runtime::{ComponentMeta, LifecycleManager, RuntimeError};
+use serde_json::json;
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/clock_random.wasm")
+}
+
+fn meta(tool_name: &str) -> ComponentMeta {
+    ComponentMeta {
+        component_id: format!("{tool_name}@0.1.0"),
+        tool_name: tool_name.into(),
+        description: "Report the current time and a random number".into(),
+        input_schema: json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    }
+}
+
+#[tokio::test]
+async fn reserved_tool_names_are_refused_at_load() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+
+    for name in ["request_capability", "girt_build_status"] {
+        let err = manager
+            .load_component(&fixture(), meta(name))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, RuntimeError::InvalidMetadata(msg) if msg.contains(name)),
+            "{err}"
+        );
+        assert!(!manager.has_tool(name).await);
+    }
+    // Nothing was stored either.
+    assert!(
+        ComponentStorage::new(tmp.path().to_path_buf())
+            .list_component_ids()
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn persisted_tools_with_reserved_names_are_still_restored() {
+    // Stored before the name was reserved: restored so it can be removed.
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = ComponentStorage::new(tmp.path().to_path_buf());
+    storage.init().unwrap();
+    storage.store(&fixture(), &mut meta("girt_info")).unwrap();
+
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    manager.load_persisted().await;
+    assert!(manager.has_tool("girt_info").await);
+}
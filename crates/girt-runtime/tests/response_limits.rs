@@ -0,0 +1,142 @@
+//! Response size limits: a tool's `max_response_bytes`, the runtime-wide
+//! ceiling, and `oversize_response`.
+//!
+//! Uses `tests/fixtures/echo.wasm`, a prebuilt component whose `run` returns
+//! its input unchanged (source: `echo.wat`), so each test sizes the response
+//! through the arguments.
+
+use std::path::Path;
+
+use girt_core::limits::{OversizeResponse, Truncation};
+use girt_runtime::{CallOptions, ComponentMeta, LifecycleManager, RuntimeError};
+use serde_json::{Value, json};
+
+fn echo_meta(max_response_bytes: Option<u64>) -> ComponentMeta {
+    ComponentMeta {
+        component_id: "echo@0.1.0".into(),
+        tool_name: "echo".into(),
+        description: "Returns its input unchanged".into(),
+        input_schema: json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    }
+}
+
+async fn load_echo(manager: &LifecycleManager, max_response_bytes: Option<u64>) {
+    let wasm = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/echo.wasm");
+    manager
+        .load_component(&wasm, echo_meta(max_response_bytes))
+        .await
+        .unwrap();
+}
+
+/// Arguments that serialize to exactly `bytes` bytes.
+fn args_of_size(bytes: usize) -> Value {
+    // {"pad":"<pad>"} is 10 bytes of framing.
+    json!({ "pad": "x".repeat(bytes - 10) })
+}
+
+#[tokio::test]
+async fn responses_within_the_limit_are_untouched() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    load_echo(&manager, Some(100)).await;
+
+    let args = args_of_size(100);
+    let (result, trace) = manager
+        .call_tool_traced("echo", &args, &CallOptions::default())
+        .await;
+    assert_eq!(result.unwrap(), args);
+    assert_eq!(trace.truncated, None);
+}
+
+#[tokio::test]
+async fn oversized_responses_are_truncated_with_their_original_size() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_oversize_response(OversizeResponse::Truncate);
+    load_echo(&manager, Some(100)).await;
+
+    let (result, trace) = manager
+        .call_tool_traced("echo", &args_of_size(5000), &CallOptions::default())
+        .await;
+    let Value::String(text) = result.unwrap() else {
+        panic!("a truncated response is no longer JSON");
+    };
+    assert!(text.len() <= 100, "{text}");
+    assert!(text.starts_with("{\"pad\":\"xxx"), "{text}");
+    assert!(
+        text.ends_with("[truncated: 5000 bytes, limit 100]"),
+        "{text}"
+    );
+    assert_eq!(
+        trace.truncated,
+        Some(Truncation {
+            original_bytes: 5000,
+            limit_bytes: 100,
+        })
+    );
+}
+
+#[tokio::test]
+async fn oversized_responses_fail_in_error_mode() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_oversize_response(OversizeResponse::Error);
+    load_echo(&manager, Some(100)).await;
+
+    let (result, trace) = manager
+        .call_tool_traced("echo", &args_of_size(101), &CallOptions::default())
+        .await;
+    match result {
+        Err(RuntimeError::ResponseTooLarge { tool, source }) => {
+            assert_eq!(tool, "echo");
+            assert_eq!(source.measured, 101);
+            assert_eq!(source.limit, 100);
+        }
+        other => panic!("expected ResponseTooLarge, got {other:?}"),
+    }
+    assert_eq!(trace.truncated, None);
+}
+
+/// The limit a 1000-byte response was truncated to, with a 200-byte
+/// runtime ceiling and the tool's own `max_response_bytes`.
+async fn limit_under_ceiling(tool_limit: Option<u64>) -> Option<usize> {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf()))
+        .unwrap()
+        .with_max_response_bytes(200);
+    load_echo(&manager, tool_limit).await;
+
+    let (result, trace) = manager
+        .call_tool_traced("echo", &args_of_size(1000), &CallOptions::default())
+        .await;
+    assert!(result.unwrap().is_string());
+    trace.truncated.map(|t| t.limit_bytes)
+}
+
+#[tokio::test]
+async fn the_runtime_ceiling_caps_tools_with_larger_or_no_limits() {
+    assert_eq!(limit_under_ceiling(None).await, Some(200));
+    assert_eq!(limit_under_ceiling(Some(5000)).await, Some(200));
+    // The tool's own, smaller limit still wins.
+    assert_eq!(limit_under_ceiling(Some(50)).await, Some(50));
+}
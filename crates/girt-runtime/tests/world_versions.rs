@@ -0,0 +1,103 @@
+//! Components built against either `girt:tool` world load and report
+//! errors in that world's shape.
+//!
+//! Uses `tests/fixtures/fail_v0_1.wasm` and `fail_v0_2.wasm`, prebuilt
+//! components whose `run` always fails with the input as the message
+//! (sources: the matching `.wat` files).
+
+use std::path::Path;
+
+use girt_runtime::{ComponentMeta, LifecycleManager, RuntimeError};
+use serde_json::json;
+
+async fn load_fixture(manager: &LifecycleManager, fixture: &str) {
+    let meta = ComponentMeta {
+        component_id: format!("{fixture}@0.1.0"),
+        tool_name: fixture.into(),
+        description: "Always fails".into(),
+        input_schema: json!({ "type": "object" }),
+        wasm_hash: String::new(),
+        wasm_size_bytes: 0,
+        built_at: 0,
+        loaded_at: 0,
+        allowed_env: vec![],
+        allowed_hosts: vec![],
+        max_response_bytes: None,
+        timeout_ms: None,
+        idempotent: false,
+        deprecated: false,
+        replaced_by: None,
+        deprecated_at: None,
+        oci_reference: None,
+        source_repo_path: None,
+        last_validated_at: None,
+        drift_detected: false,
+        quarantined: None,
+        quarantine_released: false,
+    };
+    let wasm = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{fixture}.wasm"));
+    manager.load_component(&wasm, meta).await.unwrap();
+}
+
+#[tokio::test]
+async fn v0_1_errors_are_plain_strings() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    load_fixture(&manager, "fail_v0_1").await;
+
+    let err = manager
+        .call_tool("fail_v0_1", &json!("bad input"))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, RuntimeError::ToolError(m) if m == "\"bad input\""),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn v0_2_errors_are_structured() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    load_fixture(&manager, "fail_v0_2").await;
+
+    let err = manager
+        .call_tool("fail_v0_2", &json!("503 from api.example.com"))
+        .await
+        .unwrap_err();
+    match err {
+        RuntimeError::ToolFailure {
+            kind,
+            message,
+            retryable,
+        } => {
+            assert_eq!(kind, "upstream");
+            assert_eq!(message, "\"503 from api.example.com\"");
+            assert!(retryable);
+        }
+        other => panic!("expected ToolFailure, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn both_worlds_are_restored_after_a_restart() {
+    let tmp = tempfile::tempdir().unwrap();
+    {
+        let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+        load_fixture(&manager, "fail_v0_1").await;
+        load_fixture(&manager, "fail_v0_2").await;
+    }
+
+    let manager = LifecycleManager::new(Some(tmp.path().to_path_buf())).unwrap();
+    manager.load_persisted().await;
+    assert!(matches!(
+        manager.call_tool("fail_v0_1", &json!({})).await,
+        Err(RuntimeError::ToolError(_))
+    ));
+    assert!(matches!(
+        manager.call_tool("fail_v0_2", &json!({})).await,
+        Err(RuntimeError::ToolFailure { .. })
+    ));
+}
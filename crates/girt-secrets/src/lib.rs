@@ -2,4 +2,7 @@ pub mod error;
 pub mod oauth;
 pub mod store;
 
-pub use oauth::{AnthropicOAuthStore, OAuthFlow, OAuthMode, OAuthStoreError, TokenStatus};
+pub use oauth::{
+    AnthropicOAuthStore, AnthropicProvider, GitHubDeviceCode, GitHubOAuthStore, GitHubProvider,
+    OAuthFlow, OAuthMode, OAuthProviderSpec, OAuthStoreError, OAuthTokenStore, TokenStatus,
+};
@@ -1,9 +1,14 @@
-//! Anthropic OAuth token store for GIRT.
+//! OAuth token stores for GIRT.
 //!
-//! Wraps the [`anthropic_auth`] crate to provide:
-//! - A `girt auth login` flow (PKCE, Max or Console mode)
-//! - File-backed token persistence (`~/.config/girt/auth.json`)
-//! - Automatic token refresh on expiry
+//! Provides a generic, file-backed [`OAuthTokenStore`] parameterised by an
+//! [`OAuthProviderSpec`], plus two providers:
+//! - [`AnthropicProvider`] — wraps the [`anthropic_auth`] crate for the
+//!   `girt auth login` PKCE flow (Max or Console mode)
+//! - [`GitHubProvider`] — GitHub OAuth device-code flow
+//!
+//! Each provider persists its tokens at `~/.config/girt/auth/{provider}.json`
+//! and refreshes them automatically on expiry. The pre-multi-provider
+//! Anthropic file (`~/.config/girt/auth.json`) is migrated on first load.
 //!
 //! ## Credential resolution order (in `girt-proxy`)
 //!
@@ -12,13 +17,18 @@
 //! 3. OpenClaw `auth-profiles.json`
 //! 4. `api_key` in `girt.toml`
 
-use std::path::PathBuf;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use serde::Deserialize;
 use thiserror::Error;
 
 // Re-export key types so callers only need to import from `girt_secrets`.
-pub use anthropic_auth::{OAuthFlow, OAuthMode};
-use anthropic_auth::{AsyncOAuthClient, OAuthConfig, TokenSet};
+use anthropic_auth::{AsyncOAuthClient, OAuthConfig};
+pub use anthropic_auth::{OAuthFlow, OAuthMode, TokenSet};
 
 // ── Errors ────────────────────────────────────────────────────────────────────
 
@@ -35,6 +45,9 @@ pub enum OAuthStoreError {
 
     #[error("Authentication error: {0}")]
     Auth(String),
+
+    #[error(transparent)]
+    Paths(#[from] girt_core::paths::PathError),
 }
 
 // ── Status ────────────────────────────────────────────────────────────────────
@@ -45,6 +58,7 @@ pub struct TokenStatus {
     /// First 16 characters of the access token for display purposes.
     pub access_token_prefix: String,
     /// Unix timestamp (seconds) when the access token expires.
+    /// `0` means the provider issued a non-expiring token.
     pub expires_at_unix: u64,
     /// Whether the token is expired (or expiring within 5 minutes).
     pub is_expired: bool,
@@ -52,66 +66,266 @@ pub struct TokenStatus {
     pub has_refresh_token: bool,
 }
 
+// ── Providers ─────────────────────────────────────────────────────────────────
+
+/// Future returned by [`OAuthProviderSpec::refresh`].
+pub type RefreshFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<TokenSet, OAuthStoreError>> + Send + 'a>>;
+
+/// Describes one OAuth provider: where its tokens live and how to refresh them.
+///
+/// Tokens are stored in the provider-agnostic [`TokenSet`] format regardless
+/// of provider.
+pub trait OAuthProviderSpec: Send + Sync + 'static {
+    /// Stable provider identifier. Used as the credential file stem
+    /// (`~/.config/girt/auth/{NAME}.json`) and on the CLI (`--provider`).
+    const NAME: &'static str;
+
+    /// Pre-multi-provider file name under `~/.config/girt/` to migrate from
+    /// on first load, if this provider had one.
+    const LEGACY_FILE: Option<&'static str> = None;
+
+    /// Whether the stored token needs refreshing before use.
+    fn is_expired(tokens: &TokenSet) -> bool;
+
+    /// Exchange the stored refresh token for a new token set.
+    fn refresh(tokens: &TokenSet) -> RefreshFuture<'_>;
+}
+
+/// Anthropic OAuth (PKCE) via the [`anthropic_auth`] crate.
+pub struct AnthropicProvider;
+
+impl OAuthProviderSpec for AnthropicProvider {
+    const NAME: &'static str = "anthropic";
+    const LEGACY_FILE: Option<&'static str> = Some("auth.json");
+
+    fn is_expired(tokens: &TokenSet) -> bool {
+        tokens.is_expired()
+    }
+
+    fn refresh(tokens: &TokenSet) -> RefreshFuture<'_> {
+        Box::pin(async move {
+            let client = AsyncOAuthClient::new(OAuthConfig::default())
+                .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
+            client
+                .refresh_token(&tokens.refresh_token)
+                .await
+                .map_err(|e| OAuthStoreError::Auth(e.to_string()))
+        })
+    }
+}
+
+/// GitHub OAuth via the device-code flow.
+///
+/// The OAuth App client id is read from `GIRT_GITHUB_CLIENT_ID`. Classic
+/// OAuth App tokens do not expire and are stored with `expires_at = 0` and
+/// no refresh token; GitHub App user tokens carry both and are refreshed.
+pub struct GitHubProvider;
+
+impl GitHubProvider {
+    pub const CLIENT_ID_ENV: &'static str = "GIRT_GITHUB_CLIENT_ID";
+
+    const DEVICE_CODE_URL: &'static str = "https://github.com/login/device/code";
+    const TOKEN_URL: &'static str = "https://github.com/login/oauth/access_token";
+    const DEVICE_GRANT_TYPE: &'static str = "urn:ietf:params:oauth:grant-type:device_code";
+
+    /// Resolve the OAuth App client id from the environment.
+    pub fn client_id() -> Result<String, OAuthStoreError> {
+        std::env::var(Self::CLIENT_ID_ENV).map_err(|_| {
+            OAuthStoreError::Auth(format!(
+                "{} is not set (GitHub OAuth App client id)",
+                Self::CLIENT_ID_ENV
+            ))
+        })
+    }
+}
+
+impl OAuthProviderSpec for GitHubProvider {
+    const NAME: &'static str = "github";
+
+    fn is_expired(tokens: &TokenSet) -> bool {
+        if tokens.expires_at == 0 {
+            return false;
+        }
+        unix_now() + EXPIRY_SKEW_SECS >= tokens.expires_at
+    }
+
+    fn refresh(tokens: &TokenSet) -> RefreshFuture<'_> {
+        Box::pin(async move {
+            if tokens.refresh_token.is_empty() {
+                return Err(OAuthStoreError::Auth(
+                    "GitHub token expired and no refresh token is stored. \
+                     Run `girt auth login --provider github`."
+                        .into(),
+                ));
+            }
+            let client_id = Self::client_id()?;
+            let params = [
+                ("client_id", client_id.as_str()),
+                ("grant_type", "refresh_token"),
+                ("refresh_token", tokens.refresh_token.as_str()),
+            ];
+            match github_token_request(&params).await? {
+                GitHubTokenResponse::Token(t) => Ok(t.into_token_set()),
+                GitHubTokenResponse::Error(e) => Err(OAuthStoreError::Auth(e.describe())),
+            }
+        })
+    }
+}
+
+/// Refresh tokens this many seconds before they actually expire.
+const EXPIRY_SKEW_SECS: u64 = 300;
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// ── GitHub device flow ────────────────────────────────────────────────────────
+
+/// Response to a device-code request: what the user must do to authorize.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubDeviceCode {
+    pub device_code: String,
+    /// Short code the user enters at `verification_uri`.
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until `device_code` expires.
+    pub expires_in: u64,
+    /// Minimum polling interval in seconds.
+    pub interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubAccessToken {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+impl GitHubAccessToken {
+    fn into_token_set(self) -> TokenSet {
+        TokenSet {
+            access_token: self.access_token,
+            refresh_token: self.refresh_token.unwrap_or_default(),
+            expires_at: self.expires_in.map(|s| unix_now() + s).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubOAuthError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+impl GitHubOAuthError {
+    fn describe(&self) -> String {
+        match &self.error_description {
+            Some(desc) => format!("{}: {desc}", self.error),
+            None => self.error.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum GitHubTokenResponse {
+    Token(GitHubAccessToken),
+    Error(GitHubOAuthError),
+}
+
+async fn github_form_post<T: for<'de> Deserialize<'de>>(
+    url: &str,
+    params: &[(&str, &str)],
+) -> Result<T, OAuthStoreError> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("Accept", "application/json")
+        .form(params)
+        .send()
+        .await
+        .map_err(|e| OAuthStoreError::Auth(format!("GitHub request failed: {e}")))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| OAuthStoreError::Auth(format!("GitHub response unreadable: {e}")))?;
+    if !status.is_success() {
+        return Err(OAuthStoreError::Auth(format!(
+            "GitHub returned {status}: {body}"
+        )));
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+async fn github_token_request(
+    params: &[(&str, &str)],
+) -> Result<GitHubTokenResponse, OAuthStoreError> {
+    github_form_post(GitHubProvider::TOKEN_URL, params).await
+}
+
 // ── Store ─────────────────────────────────────────────────────────────────────
 
-/// File-backed Anthropic OAuth token store.
+/// File-backed OAuth token store for provider `P`.
 ///
-/// Tokens are persisted as JSON at `~/.config/girt/auth.json` (or a custom path
-/// for testing). The stored format is [`anthropic_auth::TokenSet`], which is
+/// Tokens are persisted as JSON at `~/.config/girt/auth/{provider}.json` (or a
+/// custom path for testing). The stored format is [`TokenSet`], which is
 /// already `Serialize + Deserialize`.
-pub struct AnthropicOAuthStore {
+pub struct OAuthTokenStore<P: OAuthProviderSpec> {
     token_path: PathBuf,
+    /// Pre-multi-provider credential file, moved to `token_path` on first load.
+    legacy_path: Option<PathBuf>,
+    _provider: PhantomData<P>,
 }
 
-impl AnthropicOAuthStore {
-    /// Create a store using the default path: `~/.config/girt/auth.json`.
-    pub fn new() -> Self {
-        let path = dirs::home_dir()
-            .expect("could not resolve home directory")
-            .join(".config")
-            .join("girt")
-            .join("auth.json");
-        Self { token_path: path }
+/// Anthropic OAuth token store (`~/.config/girt/auth/anthropic.json`).
+pub type AnthropicOAuthStore = OAuthTokenStore<AnthropicProvider>;
+
+/// GitHub OAuth token store (`~/.config/girt/auth/github.json`).
+pub type GitHubOAuthStore = OAuthTokenStore<GitHubProvider>;
+
+impl<P: OAuthProviderSpec> OAuthTokenStore<P> {
+    /// Create a store using the default path: `auth/{provider}.json` in
+    /// girt's config directory (`~/.config/girt` unless `GIRT_HOME` or
+    /// `XDG_CONFIG_HOME` is set).
+    pub fn new() -> Result<Self, OAuthStoreError> {
+        let config_dir = girt_core::paths::config_dir()?;
+        Ok(Self {
+            token_path: config_dir.join("auth").join(format!("{}.json", P::NAME)),
+            legacy_path: P::LEGACY_FILE.map(|f| config_dir.join(f)),
+            _provider: PhantomData,
+        })
     }
 
     /// Create a store with a custom token path. Useful for tests.
     pub fn with_path(path: PathBuf) -> Self {
-        Self { token_path: path }
+        Self {
+            token_path: path,
+            legacy_path: None,
+            _provider: PhantomData,
+        }
     }
 
-    // ── Login flow ────────────────────────────────────────────────────────────
+    /// Set the legacy credential file to migrate from on first load.
+    pub fn with_legacy_path(mut self, path: PathBuf) -> Self {
+        self.legacy_path = Some(path);
+        self
+    }
 
-    /// Start a new OAuth PKCE login flow.
-    ///
-    /// Returns an [`OAuthFlow`] whose `authorization_url` the user must visit.
-    /// After authorizing, they receive a `code#state` string which should be
-    /// passed to [`complete_login`].
-    ///
-    /// This method is synchronous — no I/O is performed.
-    pub fn start_login_flow(mode: OAuthMode) -> Result<OAuthFlow, OAuthStoreError> {
-        let client = AsyncOAuthClient::new(OAuthConfig::default())
-            .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
-        client
-            .start_flow(mode)
-            .map_err(|e| OAuthStoreError::Auth(e.to_string()))
+    /// Provider identifier (e.g. `"anthropic"`, `"github"`).
+    pub fn provider_name(&self) -> &'static str {
+        P::NAME
     }
 
-    /// Exchange the authorization response for tokens and persist them.
-    ///
-    /// `response` is the `code#state` string the user pastes after authorizing
-    /// (Anthropic returns authorization responses in `code#state` format).
-    pub async fn complete_login(
-        &self,
-        response: &str,
-        flow: &OAuthFlow,
-    ) -> Result<(), OAuthStoreError> {
-        let client = AsyncOAuthClient::new(OAuthConfig::default())
-            .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
-        let tokens = client
-            .exchange_code(response, &flow.state, &flow.verifier)
-            .await
-            .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
-        self.save_tokens(&tokens).await
+    /// Where tokens for this provider are stored.
+    pub fn token_path(&self) -> &Path {
+        &self.token_path
     }
 
     // ── Token access ──────────────────────────────────────────────────────────
@@ -126,14 +340,12 @@ impl AnthropicOAuthStore {
             Err(e) => return Err(e),
         };
 
-        if tokens.is_expired() {
-            tracing::debug!("OAuth token expired or expiring soon — refreshing");
-            let client = AsyncOAuthClient::new(OAuthConfig::default())
-                .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
-            let refreshed = client
-                .refresh_token(&tokens.refresh_token)
-                .await
-                .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
+        if P::is_expired(&tokens) {
+            tracing::debug!(
+                provider = P::NAME,
+                "OAuth token expired or expiring soon — refreshing"
+            );
+            let refreshed = P::refresh(&tokens).await?;
             let access_token = refreshed.access_token.clone();
             self.save_tokens(&refreshed).await?;
             Ok(Some(access_token))
@@ -150,7 +362,7 @@ impl AnthropicOAuthStore {
             Ok(tokens) => Ok(Some(TokenStatus {
                 access_token_prefix: tokens.access_token.chars().take(16).collect(),
                 expires_at_unix: tokens.expires_at,
-                is_expired: tokens.is_expired(),
+                is_expired: P::is_expired(&tokens),
                 has_refresh_token: !tokens.refresh_token.is_empty(),
             })),
             Err(OAuthStoreError::NoTokenStored) => Ok(None),
@@ -160,11 +372,14 @@ impl AnthropicOAuthStore {
 
     // ── Logout ────────────────────────────────────────────────────────────────
 
-    /// Delete stored credentials.
+    /// Delete stored credentials (including an unmigrated legacy file).
     pub fn logout(&self) -> Result<(), OAuthStoreError> {
-        if self.token_path.exists() {
-            std::fs::remove_file(&self.token_path).map_err(OAuthStoreError::Io)?;
-            tracing::info!(path = %self.token_path.display(), "OAuth credentials removed");
+        let paths = std::iter::once(&self.token_path).chain(self.legacy_path.as_ref());
+        for path in paths {
+            if path.exists() {
+                std::fs::remove_file(path).map_err(OAuthStoreError::Io)?;
+                tracing::info!(path = %path.display(), "OAuth credentials removed");
+            }
         }
         Ok(())
     }
@@ -172,6 +387,7 @@ impl AnthropicOAuthStore {
     // ── Private helpers ───────────────────────────────────────────────────────
 
     async fn load_tokens(&self) -> Result<TokenSet, OAuthStoreError> {
+        self.migrate_legacy().await?;
         if !self.token_path.exists() {
             return Err(OAuthStoreError::NoTokenStored);
         }
@@ -187,17 +403,129 @@ impl AnthropicOAuthStore {
         let content = serde_json::to_string_pretty(tokens)?;
         tokio::fs::write(&self.token_path, &content).await?;
         tracing::debug!(
+            provider = P::NAME,
             path = %self.token_path.display(),
             expires_at = tokens.expires_at,
             "OAuth credentials saved"
         );
         Ok(())
     }
+
+    /// Move the legacy credential file into the per-provider location if the
+    /// new file does not exist yet. A no-op once migrated.
+    async fn migrate_legacy(&self) -> Result<(), OAuthStoreError> {
+        let Some(legacy) = &self.legacy_path else {
+            return Ok(());
+        };
+        if self.token_path.exists() || !legacy.exists() {
+            return Ok(());
+        }
+
+        // Only move files that actually parse as tokens.
+        let content = tokio::fs::read_to_string(legacy).await?;
+        serde_json::from_str::<TokenSet>(&content)?;
+
+        if let Some(parent) = self.token_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if tokio::fs::rename(legacy, &self.token_path).await.is_err() {
+            // Cross-device: copy then remove.
+            tokio::fs::write(&self.token_path, &content).await?;
+            tokio::fs::remove_file(legacy).await?;
+        }
+        tracing::info!(
+            provider = P::NAME,
+            from = %legacy.display(),
+            to = %self.token_path.display(),
+            "Migrated OAuth credentials to per-provider location"
+        );
+        Ok(())
+    }
 }
 
-impl Default for AnthropicOAuthStore {
-    fn default() -> Self {
-        Self::new()
+// ── Anthropic login flow ──────────────────────────────────────────────────────
+
+impl OAuthTokenStore<AnthropicProvider> {
+    /// Start a new OAuth PKCE login flow.
+    ///
+    /// Returns an [`OAuthFlow`] whose `authorization_url` the user must visit.
+    /// After authorizing, they receive a `code#state` string which should be
+    /// passed to [`complete_login`](Self::complete_login).
+    ///
+    /// This method is synchronous — no I/O is performed.
+    pub fn start_login_flow(mode: OAuthMode) -> Result<OAuthFlow, OAuthStoreError> {
+        let client = AsyncOAuthClient::new(OAuthConfig::default())
+            .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
+        client
+            .start_flow(mode)
+            .map_err(|e| OAuthStoreError::Auth(e.to_string()))
+    }
+
+    /// Exchange the authorization response for tokens and persist them.
+    ///
+    /// `response` is the `code#state` string the user pastes after authorizing
+    /// (Anthropic returns authorization responses in `code#state` format).
+    pub async fn complete_login(
+        &self,
+        response: &str,
+        flow: &OAuthFlow,
+    ) -> Result<(), OAuthStoreError> {
+        let client = AsyncOAuthClient::new(OAuthConfig::default())
+            .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
+        let tokens = client
+            .exchange_code(response, &flow.state, &flow.verifier)
+            .await
+            .map_err(|e| OAuthStoreError::Auth(e.to_string()))?;
+        self.save_tokens(&tokens).await
+    }
+}
+
+// ── GitHub login flow ─────────────────────────────────────────────────────────
+
+impl OAuthTokenStore<GitHubProvider> {
+    /// Request a device code. The user visits `verification_uri` and enters
+    /// `user_code`; meanwhile call [`complete_device_login`](Self::complete_device_login).
+    pub async fn start_device_flow(scopes: &str) -> Result<GitHubDeviceCode, OAuthStoreError> {
+        let client_id = GitHubProvider::client_id()?;
+        github_form_post(
+            GitHubProvider::DEVICE_CODE_URL,
+            &[("client_id", client_id.as_str()), ("scope", scopes)],
+        )
+        .await
+    }
+
+    /// Poll GitHub until the user authorizes the device code (or it expires),
+    /// then persist the issued tokens.
+    pub async fn complete_device_login(
+        &self,
+        device: &GitHubDeviceCode,
+    ) -> Result<(), OAuthStoreError> {
+        let client_id = GitHubProvider::client_id()?;
+        let params = [
+            ("client_id", client_id.as_str()),
+            ("device_code", device.device_code.as_str()),
+            ("grant_type", GitHubProvider::DEVICE_GRANT_TYPE),
+        ];
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(device.expires_in);
+        let mut interval = Duration::from_secs(device.interval.max(1));
+
+        loop {
+            tokio::time::sleep(interval).await;
+            if tokio::time::Instant::now() >= deadline {
+                return Err(OAuthStoreError::Auth("device code expired".into()));
+            }
+            match github_token_request(&params).await? {
+                GitHubTokenResponse::Token(t) => {
+                    return self.save_tokens(&t.into_token_set()).await;
+                }
+                GitHubTokenResponse::Error(e) => match e.error.as_str() {
+                    "authorization_pending" => {}
+                    // GitHub asks clients to back off by 5 seconds.
+                    "slow_down" => interval += Duration::from_secs(5),
+                    _ => return Err(OAuthStoreError::Auth(e.describe())),
+                },
+            }
+        }
     }
 }
 
@@ -315,4 +643,147 @@ mod tests {
             flow.authorization_url
         );
     }
+
+    // ── Legacy migration ──────────────────────────────────────────────────────
+
+    fn sample_tokens() -> TokenSet {
+        TokenSet {
+            access_token: "sk-ant-oat-legacy".into(),
+            refresh_token: "legacy-refresh".into(),
+            expires_at: unix_now() + 3600,
+        }
+    }
+
+    fn migrating_store(dir: &TempDir) -> AnthropicOAuthStore {
+        AnthropicOAuthStore::with_path(dir.path().join("auth").join("anthropic.json"))
+            .with_legacy_path(dir.path().join("auth.json"))
+    }
+
+    #[tokio::test]
+    async fn legacy_file_is_migrated_on_first_load() {
+        let dir = TempDir::new().unwrap();
+        let legacy = dir.path().join("auth.json");
+        std::fs::write(&legacy, serde_json::to_string(&sample_tokens()).unwrap()).unwrap();
+
+        let store = migrating_store(&dir);
+        let token = store.get_valid_token().await.unwrap();
+
+        assert_eq!(token, Some("sk-ant-oat-legacy".into()));
+        assert!(!legacy.exists(), "legacy file should be moved");
+        assert!(store.token_path.exists());
+    }
+
+    #[tokio::test]
+    async fn migration_does_not_overwrite_existing_provider_file() {
+        let dir = TempDir::new().unwrap();
+        let legacy = dir.path().join("auth.json");
+        std::fs::write(&legacy, serde_json::to_string(&sample_tokens()).unwrap()).unwrap();
+
+        let store = migrating_store(&dir);
+        let current = TokenSet {
+            access_token: "sk-ant-oat-current".into(),
+            ..sample_tokens()
+        };
+        store.save_tokens(&current).await.unwrap();
+
+        let token = store.get_valid_token().await.unwrap();
+        assert_eq!(token, Some("sk-ant-oat-current".into()));
+        assert!(legacy.exists(), "legacy file is left alone once migrated");
+    }
+
+    #[tokio::test]
+    async fn migration_skips_unparseable_legacy_file() {
+        let dir = TempDir::new().unwrap();
+        let legacy = dir.path().join("auth.json");
+        std::fs::write(&legacy, "not json").unwrap();
+
+        let store = migrating_store(&dir);
+        assert!(store.status().await.is_err());
+        assert!(legacy.exists());
+        assert!(!store.token_path.exists());
+    }
+
+    #[tokio::test]
+    async fn logout_removes_unmigrated_legacy_file() {
+        let dir = TempDir::new().unwrap();
+        let legacy = dir.path().join("auth.json");
+        std::fs::write(&legacy, serde_json::to_string(&sample_tokens()).unwrap()).unwrap();
+
+        let store = migrating_store(&dir);
+        store.logout().unwrap();
+        assert!(!legacy.exists());
+        assert!(store.status().await.unwrap().is_none());
+    }
+
+    // ── Providers ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn default_paths_are_per_provider() {
+        let anthropic = AnthropicOAuthStore::new().unwrap();
+        let github = GitHubOAuthStore::new().unwrap();
+        assert!(anthropic.token_path.ends_with("auth/anthropic.json"));
+        assert!(github.token_path.ends_with("auth/github.json"));
+        assert!(
+            anthropic
+                .legacy_path
+                .as_ref()
+                .unwrap()
+                .ends_with("girt/auth.json")
+        );
+        assert!(github.legacy_path.is_none());
+    }
+
+    #[tokio::test]
+    async fn github_non_expiring_token_is_never_expired() {
+        let dir = TempDir::new().unwrap();
+        let store = GitHubOAuthStore::with_path(dir.path().join("github.json"));
+        let tokens = GitHubAccessToken {
+            access_token: "gho_abcdef".into(),
+            refresh_token: None,
+            expires_in: None,
+        }
+        .into_token_set();
+        store.save_tokens(&tokens).await.unwrap();
+
+        let status = store.status().await.unwrap().unwrap();
+        assert!(!status.is_expired);
+        assert!(!status.has_refresh_token);
+        assert_eq!(status.expires_at_unix, 0);
+        assert_eq!(
+            store.get_valid_token().await.unwrap(),
+            Some("gho_abcdef".into())
+        );
+    }
+
+    #[test]
+    fn github_expiring_token_respects_skew() {
+        let soon = TokenSet {
+            access_token: "ghu_x".into(),
+            refresh_token: "ghr_x".into(),
+            expires_at: unix_now() + 60,
+        };
+        assert!(GitHubProvider::is_expired(&soon));
+
+        let later = TokenSet {
+            expires_at: unix_now() + 3600,
+            ..soon
+        };
+        assert!(!GitHubProvider::is_expired(&later));
+    }
+
+    #[test]
+    fn github_token_response_parses_both_shapes() {
+        let ok: GitHubTokenResponse = serde_json::from_str(
+            r#"{"access_token":"gho_1","token_type":"bearer","scope":"repo"}"#,
+        )
+        .unwrap();
+        assert!(matches!(ok, GitHubTokenResponse::Token(_)));
+
+        let pending: GitHubTokenResponse =
+            serde_json::from_str(r#"{"error":"authorization_pending"}"#).unwrap();
+        match pending {
+            GitHubTokenResponse::Error(e) => assert_eq!(e.error, "authorization_pending"),
+            GitHubTokenResponse::Token(_) => panic!("expected error response"),
+        }
+    }
 }
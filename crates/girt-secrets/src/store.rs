@@ -52,6 +52,20 @@ pub trait SecretStore: Send + Sync {
 
     /// Backend name for logging and configuration.
     fn backend_name(&self) -> &str;
+
+    /// The service whose credential a tool granted `env_var` can use, by
+    /// the `<SERVICE>_TOKEN` / `<SERVICE>_API_KEY` convention.
+    fn service_for_env_var(&self, env_var: &str) -> Option<String> {
+        conventional_service(env_var)
+    }
+}
+
+/// `GITHUB_TOKEN` -> `github`, `OPENAI_API_KEY` -> `openai`.
+pub fn conventional_service(env_var: &str) -> Option<String> {
+    let service = env_var
+        .strip_suffix("_TOKEN")
+        .or_else(|| env_var.strip_suffix("_API_KEY"))?;
+    (!service.is_empty()).then(|| service.to_lowercase())
 }
 
 /// Environment variable backend. Reads secrets from environment variables.
@@ -154,6 +168,16 @@ impl SecretStore for EnvSecretStore {
     fn backend_name(&self) -> &str {
         "env"
     }
+
+    fn service_for_env_var(&self, env_var: &str) -> Option<String> {
+        let mapped = self
+            .mappings
+            .iter()
+            .filter(|(_, var)| *var == env_var)
+            .map(|(service, _)| service.clone())
+            .min();
+        mapped.or_else(|| conventional_service(env_var))
+    }
 }
 
 /// In-memory secret store for testing. Pre-loaded with known credentials.
@@ -293,6 +317,33 @@ mod tests {
         assert!(!debug.is_empty());
     }
 
+    #[test]
+    fn env_vars_map_back_to_their_services() {
+        let mut mappings = HashMap::new();
+        mappings.insert("custom_service".into(), "MY_CUSTOM_SECRET".into());
+        let store = EnvSecretStore::with_mappings(mappings);
+        assert_eq!(
+            store.service_for_env_var("MY_CUSTOM_SECRET").as_deref(),
+            Some("custom_service")
+        );
+        assert_eq!(
+            store.service_for_env_var("GITHUB_TOKEN").as_deref(),
+            Some("github")
+        );
+        assert_eq!(
+            store.service_for_env_var("NEWSERVICE_API_KEY").as_deref(),
+            Some("newservice")
+        );
+        assert_eq!(store.service_for_env_var("HOME"), None);
+
+        let memory = MemorySecretStore::new(HashMap::new());
+        assert_eq!(
+            memory.service_for_env_var("GITLAB_TOKEN").as_deref(),
+            Some("gitlab")
+        );
+        assert_eq!(memory.service_for_env_var("_TOKEN"), None);
+    }
+
     #[test]
     fn backend_names() {
         let env_store = EnvSecretStore::new();
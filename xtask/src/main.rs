@@ -0,0 +1,80 @@
+//! Workspace build tasks, run as `cargo xtask <task>`.
+//!
+//! - `components [--check]`: assemble the first-party components under
+//!   `crates/girt-runtime/components/` from their `.wat` source into the
+//!   `.wasm` files girt-runtime embeds. With `--check`, assemble in memory
+//!   and fail if a checked-in `.wasm` is missing or out of date.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+
+const USAGE: &str = "usage: cargo xtask components [--check]";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["components"] => components(false),
+        ["components", "--check"] => components(true),
+        _ => bail!("{USAGE}"),
+    }
+}
+
+/// Directory holding the bundled component sources.
+fn components_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives in the workspace root")
+        .join("crates/girt-runtime/components")
+}
+
+fn components(check: bool) -> anyhow::Result<()> {
+    let mut sources = wat_sources(&components_dir())?;
+    sources.sort();
+    if sources.is_empty() {
+        bail!("no .wat sources under {}", components_dir().display());
+    }
+
+    let mut stale = Vec::new();
+    for source in &sources {
+        let wasm = wat::parse_file(source)
+            .with_context(|| format!("cannot assemble {}", source.display()))?;
+        let target = source.with_extension("wasm");
+        if check {
+            if std::fs::read(&target).ok().as_deref() != Some(wasm.as_slice()) {
+                stale.push(target);
+            }
+            continue;
+        }
+        std::fs::write(&target, &wasm)
+            .with_context(|| format!("cannot write {}", target.display()))?;
+        println!("{} ({} bytes)", target.display(), wasm.len());
+    }
+
+    if !stale.is_empty() {
+        for target in &stale {
+            eprintln!("out of date: {}", target.display());
+        }
+        bail!("run `cargo xtask components` and commit the result");
+    }
+    Ok(())
+}
+
+/// Every `.wat` file one level below `dir` (`<component>/<name>.wat`).
+fn wat_sources(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("cannot read {}", dir.display()))? {
+        let component = entry?.path();
+        if !component.is_dir() {
+            continue;
+        }
+        for file in std::fs::read_dir(&component)? {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "wat") {
+                sources.push(path);
+            }
+        }
+    }
+    Ok(sources)
+}